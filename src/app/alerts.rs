@@ -0,0 +1,183 @@
+//! Bell and flash alerts for things that need attention
+//!
+//! A long task is easy to leave running and look away from, and then a
+//! session error or a permission prompt waits unnoticed. Each class of
+//! event can ring the terminal bell, flash the status bar for a moment, or
+//! both, as set in the `[alerts]` table of the config file:
+//!
+//! ```toml
+//! [alerts]
+//! quiet = false
+//! permission_request = "both" # or "none", "bell", "flash"
+//! session_error = "bell"
+//! response_complete = "flash"
+//! ```
+//!
+//! Nothing alerts by default, `quiet` silences every class at once, and a
+//! class alerts at most once a second, so a burst of errors rings once.
+
+use crate::app::{
+    event_msg::Cmd,
+    tea_model::{Model, TimeoutType},
+};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
+
+/// How long the status bar stays inverted
+pub const ALERT_FLASH_MS: u64 = 300;
+/// Alerts of one class closer together than this are dropped
+const MIN_ALERT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// What an alert is for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AlertClass {
+    PermissionRequest,
+    SessionError,
+    ResponseComplete,
+}
+
+/// How an alert gets noticed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AlertStyle {
+    #[default]
+    None,
+    Bell,
+    Flash,
+    Both,
+}
+
+impl AlertStyle {
+    fn rings(self) -> bool {
+        matches!(self, AlertStyle::Bell | AlertStyle::Both)
+    }
+
+    fn flashes(self) -> bool {
+        matches!(self, AlertStyle::Flash | AlertStyle::Both)
+    }
+}
+
+/// The style of each class of alert, and whether they're all silenced
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AlertPolicy {
+    pub quiet: bool,
+    pub permission_request: AlertStyle,
+    pub session_error: AlertStyle,
+    pub response_complete: AlertStyle,
+}
+
+impl AlertPolicy {
+    pub fn style(&self, class: AlertClass) -> AlertStyle {
+        if self.quiet {
+            return AlertStyle::None;
+        }
+        match class {
+            AlertClass::PermissionRequest => self.permission_request,
+            AlertClass::SessionError => self.session_error,
+            AlertClass::ResponseComplete => self.response_complete,
+        }
+    }
+}
+
+/// When each class last alerted
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AlertLimiter {
+    last_alerted: HashMap<AlertClass, SystemTime>,
+}
+
+impl AlertLimiter {
+    /// Whether `class` may alert at `now`, noting it as alerted if so
+    pub fn allow(&mut self, class: AlertClass, now: SystemTime) -> bool {
+        if let Some(last) = self.last_alerted.get(&class) {
+            // A clock set back counts as long enough ago
+            if now
+                .duration_since(*last)
+                .is_ok_and(|since| since < MIN_ALERT_INTERVAL)
+            {
+                return false;
+            }
+        }
+        self.last_alerted.insert(class, now);
+        true
+    }
+}
+
+/// Alert for `class` as the config asks: start the flash on the model, and
+/// return the bell to ring, if any
+pub fn alert(model: &mut Model, class: AlertClass) -> Option<Cmd> {
+    let style = model.config.alerts.style(class);
+    if style == AlertStyle::None {
+        return None;
+    }
+    let now = model.clock.now();
+    if !model.alert_limiter.allow(class, now) {
+        return None;
+    }
+    if style.flashes() {
+        model.set_timeout(TimeoutType::AlertFlash, ALERT_FLASH_MS);
+        model.dirty.status = true;
+    }
+    style.rings().then_some(Cmd::TerminalBell)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::tea_model::Clock;
+    use std::time::UNIX_EPOCH;
+
+    fn at(ms: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn test_limiter_allows_one_alert_per_second_per_class() {
+        let mut limiter = AlertLimiter::default();
+        assert!(limiter.allow(AlertClass::SessionError, at(10_000)));
+        assert!(!limiter.allow(AlertClass::SessionError, at(10_200)));
+        assert!(!limiter.allow(AlertClass::SessionError, at(10_999)));
+        // Other classes have their own second
+        assert!(limiter.allow(AlertClass::ResponseComplete, at(10_500)));
+        assert!(limiter.allow(AlertClass::SessionError, at(11_000)));
+        assert!(!limiter.allow(AlertClass::ResponseComplete, at(11_000)));
+
+        // Dropped alerts don't push the next one back
+        assert!(limiter.allow(AlertClass::ResponseComplete, at(11_500)));
+        // Nor does a clock set back hold alerts off
+        assert!(limiter.allow(AlertClass::SessionError, at(5_000)));
+    }
+
+    #[test]
+    fn test_alert_rings_and_flashes_as_configured() {
+        let mut model = Model::new();
+        model.clock = Clock::Manual(at(10_000));
+        model.config.alerts = AlertPolicy {
+            session_error: AlertStyle::Both,
+            response_complete: AlertStyle::Flash,
+            ..AlertPolicy::default()
+        };
+
+        assert_eq!(alert(&mut model, AlertClass::PermissionRequest), None);
+        assert!(!model.is_timeout_active(&TimeoutType::AlertFlash));
+
+        assert_eq!(
+            alert(&mut model, AlertClass::SessionError),
+            Some(Cmd::TerminalBell)
+        );
+        assert!(model.is_timeout_active(&TimeoutType::AlertFlash));
+        // Rate limited within the second
+        assert_eq!(alert(&mut model, AlertClass::SessionError), None);
+
+        model.clear_timeout(&TimeoutType::AlertFlash);
+        assert_eq!(alert(&mut model, AlertClass::ResponseComplete), None);
+        assert!(model.is_timeout_active(&TimeoutType::AlertFlash));
+
+        // Quiet silences every class
+        model.config.alerts.quiet = true;
+        model.clock = Clock::Manual(at(20_000));
+        model.clear_timeout(&TimeoutType::AlertFlash);
+        assert_eq!(alert(&mut model, AlertClass::SessionError), None);
+        assert!(!model.is_timeout_active(&TimeoutType::AlertFlash));
+    }
+}
@@ -15,73 +15,254 @@
 
 use crate::{
     app::{
+        bugreport::bugreport_directory,
+        changelog::{default_seen_version_path, load_seen_version, save_seen_version},
+        cli::CliArgs,
+        config_file::{default_config_path, save_session_sort, ConfigFile},
+        draft_store::DraftStore,
         error::Result,
-        event_async_task_manager::AsyncTaskManager,
+        event_async_task_manager::{
+            AsyncTaskManager, ChainId, ChainProgress, TaskContext, TaskId, TaskKind,
+        },
         event_msg::{Cmd, CmdOrBatch, Msg},
         event_sync_subscriptions,
-        tea_model::{AppModalState, ConnectionStatus, Model, ModelInit},
+        file_opener::{open_file_part, open_with_platform_opener},
+        input_history::InputHistory,
+        job_control::ResumeFlag,
+        log_control::{LogControl, LogLevels},
+        logger,
+        message_state::MESSAGE_PAGE_SIZE,
+        msg_recorder::MsgRecorder,
+        one_shot::{InterruptFlag, OneShotOutput, Output},
+        paste::resolve_pasted_paths,
+        profile::{variant_name, PROFILE_TARGET},
+        project_config::{self, ResolvedSettings},
+        strings,
+        tea_model::{
+            AppModalState, ConnectionStatus, DirtyRegions, Model, ModelInit, TimeoutType,
+            HINT_ROTATION_MS,
+        },
         tea_update::update,
-        tea_view::{render_manual_inline_history, view, view_clear},
-        terminal::{init_terminal, restore_terminal},
+        terminal::{terminal_rows, CrosstermTerminal, TerminalBackend},
         ui_components::{
-            banner::{create_welcome_text, welcome_text_height},
-            text_input::TEXT_INPUT_HEIGHT,
+            glyphs::GlyphMode, layout_class::fit_config_to_terminal,
+            modal_save_output::save_output, text_input::TEXT_INPUT_HEIGHT,
+        },
+    },
+    sdk::{
+        discovery::{
+            discover_opencode_servers, discover_or_list_servers, DiscoveryConfig, DiscoveryOutcome,
         },
+        OpenCodeClient, OpenCodeError,
     },
-    sdk::{extensions::events::EventStream, OpenCodeClient},
 };
 use crossterm::event;
-use eyre::WrapErr;
-use ratatui::prelude::Widget;
-use ratatui::{backend::CrosstermBackend, crossterm, widgets::Paragraph, Terminal};
-use std::io::{self};
-use std::time::Duration;
+use std::io::{IsTerminal, Write};
+use std::path::Path;
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
 use tokio::time::interval;
 
+/// How long quitting waits on the server to abort a running response
+const QUIT_ABORT_TIMEOUT: Duration = Duration::from_secs(2);
+
 pub struct Program {
     model: Model,
-    terminal: Option<Terminal<CrosstermBackend<io::Stdout>>>,
+    terminal: Option<Box<dyn TerminalBackend>>,
     task_manager: AsyncTaskManager,
+    draft_store: DraftStore,
+    draft_save_task: Option<TaskId>,
+    abort_task: Option<TaskId>,
+    // Redraw for changes on the terminal's side, which the model's dirty
+    // regions don't know about
     needs_render: bool,
+    resumed: ResumeFlag,
+    recorder: Option<MsgRecorder>,
+    scripted: Option<scripted::ScriptedState>,
+    // Printed with the first draw rather than before the first keystroke
+    welcome_banner: bool,
+    // Whether to look up what's new since the version last run
+    whats_new: bool,
+    // When starting up began, until input is first read
+    started_at: Option<Instant>,
+    // Printing the reply to one prompt instead of drawing, see `one_shot`
+    one_shot: Option<OneShotOutput>,
+    interrupted: InterruptFlag,
+    // The log file's filter, changed by `/loglevel`
+    log_control: Option<LogControl>,
+}
+
+/// Apply the config file, then the project's settings, then the arguments
+fn load_config(model: &mut Model, args: &CliArgs) -> Result<()> {
+    ConfigFile::load(&default_config_path())?.apply(&mut model.config)?;
+    if let Ok(dir) = std::env::current_dir() {
+        project_config::apply(&mut model.config.settings, &dir)?;
+    }
+    args.apply(&mut model.config);
+    tracing::info!("Settings: {}", model.config.settings.describe().join(", "));
+    model.apply_settings();
+    Ok(())
 }
 
 impl Program {
-    pub fn new() -> Result<Self> {
-        let model = Model::new();
+    pub fn new(args: &CliArgs) -> Result<Self> {
+        let started_at = Instant::now();
+        let mut model = Model::new();
+        tracing::debug_span!("load_config").in_scope(|| load_config(&mut model, args))?;
+        model.launch_directory = std::env::current_dir().ok();
+        model
+            .message_log
+            .set_tool_render(model.config.tool_render.clone());
+        model.message_log.set_tab_width(model.config.ui_tab_width);
+        model
+            .message_log
+            .set_prompt_collapse_lines(model.config.ui_prompt_collapse_lines);
+        model.input_history = InputHistory::new(model.config.input_history_size);
+        model
+            .message_state
+            .set_retained_messages(model.config.retained_messages);
+        if let Some(rows) = terminal_rows() {
+            fit_config_to_terminal(&mut model.config, rows);
+        }
+        model.inline_height.base = model.config.height;
+        if let Some(rows) = terminal_rows() {
+            model.fit_inline_height_to_terminal(rows);
+            model.config.height = model.inline_height_for_input();
+        }
+        if model.config.ui_glyphs == GlyphMode::Auto {
+            model.config.ui_glyphs = GlyphMode::detect();
+        }
+        model
+            .message_log
+            .set_glyphs(model.config.ui_glyphs.glyphs());
+        // The inline viewport asks the terminal where its cursor is, which
+        // output to a pipe or file never answers
+        if !std::io::stdout().is_terminal() {
+            model.init = ModelInit::new(false);
+        }
+
+        let terminal = tracing::debug_span!("init_terminal")
+            .in_scope(|| CrosstermTerminal::new(&model.init, model.config.height))?;
+        let welcome_banner = model.config.ui_show_banner;
+
+        let mut program = Self::with_terminal(model, Box::new(terminal));
+        program.recorder = MsgRecorder::from_env()?;
+        program.resumed = ResumeFlag::install();
+        program.welcome_banner = welcome_banner;
+        program.whats_new = true;
+        program.started_at = Some(started_at);
+        Ok(program)
+    }
 
-        let welcome_text = create_welcome_text();
-        let mut terminal = init_terminal(&model.init, model.config.height)?;
-        terminal.insert_before(welcome_text_height().saturating_add(1), |buf| {
-            Paragraph::new(welcome_text).render(buf.area, buf)
-        });
+    /// A program that sends `prompt` to a new session and prints the reply,
+    /// without a terminal or any input of its own
+    pub fn one_shot(args: &CliArgs, prompt: &str) -> Result<Self> {
+        let mut model = Model::new();
+        load_config(&mut model, args)?;
+        model.config.session_resume = false;
+        model.config.ui_show_banner = false;
+        model.config.notify_on_complete = false;
+        // Sent as soon as the server is found and the modes are loaded
+        model.text_input_area.set_content(prompt);
 
-        // Create async task manager
-        let task_manager = AsyncTaskManager::new();
+        let mut program = Self::from_parts(model, None);
+        program.recorder = MsgRecorder::from_env()?;
+        program.one_shot = Some(OneShotOutput::new());
+        program.interrupted = InterruptFlag::install();
+        Ok(program)
+    }
+
+    pub fn with_terminal(model: Model, terminal: Box<dyn TerminalBackend>) -> Self {
+        Self::from_parts(model, Some(terminal))
+    }
 
-        Ok(Program {
+    fn from_parts(model: Model, terminal: Option<Box<dyn TerminalBackend>>) -> Self {
+        Program {
             model,
-            terminal: Some(terminal),
-            task_manager,
+            terminal,
+            // Create async task manager
+            task_manager: AsyncTaskManager::new(),
+            draft_store: DraftStore::new(),
+            draft_save_task: None,
+            abort_task: None,
             needs_render: true, // Initial render needed
-        })
+            resumed: ResumeFlag::default(),
+            recorder: None,
+            scripted: None,
+            welcome_banner: false,
+            whats_new: false,
+            started_at: None,
+            one_shot: None,
+            interrupted: InterruptFlag::default(),
+            log_control: None,
+        }
     }
 
-    pub fn run(self) -> Result<()> {
+    /// Keep the handle to the log file's filter, and set the configured
+    /// levels with it
+    pub fn with_log_control(mut self, log_control: LogControl) -> Self {
+        self.log_control = Some(log_control);
+        if !self.model.config.log_levels.is_empty() {
+            let levels = self.model.config.log_levels.clone();
+            self.set_log_levels(&levels);
+        }
+        self
+    }
+
+    fn set_log_levels(&self, levels: &LogLevels) {
+        if let Some(log_control) = &self.log_control {
+            if let Err(e) = log_control.apply(levels) {
+                tracing::warn!("Failed to set the log levels: {:?}", e);
+            }
+        }
+    }
+
+    /// Run until quit, with the exit code of a one-shot run
+    pub fn run(self) -> Result<ExitCode> {
         // Create a Tokio runtime for this blocking function
         let runtime = tokio::runtime::Runtime::new()?;
         runtime.block_on(self.run_async())
     }
 
-    async fn run_async(mut self) -> Result<()> {
+    async fn run_async(mut self) -> Result<ExitCode> {
         // Create tick interval for periodic updates (60 FPS) - must be inside tokio runtime
         let mut tick_interval = interval(Duration::from_millis(4));
 
         // Auto-trigger client discovery at startup
         self.spawn_command(Cmd::AsyncSpawnClientDiscovery).await?;
+        if self.whats_new {
+            self.spawn_command(Cmd::AsyncLoadSeenVersion).await?;
+        }
+        if self.one_shot.is_some() {
+            self.handle_msg(Msg::SubmitTextInput).await?;
+        } else {
+            self.model
+                .set_timeout(TimeoutType::RotateHints, HINT_ROTATION_MS);
+        }
 
         loop {
+            // Back from ctrl+z, or from being stopped some other way
+            self.resume_if_continued()?;
+            self.end_one_shot_if_done().await?;
+
             // Check for quit state
             if matches!(self.model.state, AppModalState::Quit) {
+                // Let a requested abort and the final draft save land before
+                // tasks are aborted and the terminal is restored
+                if let Some(task_id) = self.abort_task.take() {
+                    if !self
+                        .task_manager
+                        .join_task(task_id, QUIT_ABORT_TIMEOUT)
+                        .await
+                    {
+                        tracing::warn!("Session abort did not finish before quitting");
+                    }
+                }
+                if let Some(task_id) = self.draft_save_task.take() {
+                    self.task_manager
+                        .join_task(task_id, Duration::from_millis(500))
+                        .await;
+                }
                 break;
             }
 
@@ -93,24 +274,29 @@ impl Program {
             if !async_messages.is_empty() {
                 had_events = true;
                 for msg in async_messages {
-                    let cmd = update(&mut self.model, msg);
-                    self.needs_render = true;
-                    self.spawn_commands(cmd).await?;
+                    self.handle_msg(msg).await?;
                 }
+                self.advance_chains().await?;
             }
 
             // Check for input events (non-blocking)
-            if let Some(msg) = self.poll_input_events().await? {
-                had_events = true;
-                let cmd = update(&mut self.model, msg);
-                self.needs_render = true;
-                self.spawn_commands(cmd).await?;
+            if let Some(started_at) = self.started_at.take() {
+                tracing::debug!("ready for input in {}ms", started_at.elapsed().as_millis());
+            }
+            if self.one_shot.is_none() {
+                if let Some(msg) = self.poll_input_events().await? {
+                    had_events = true;
+                    self.handle_msg(msg).await?;
+                }
             }
 
             // Check for SSE events (non-blocking)
             if self.poll_sse_events().await? {
                 had_events = true;
             }
+            if self.poll_parked_project_events().await? {
+                had_events = true;
+            }
 
             // If we had events, continue loop immediately to process more
             if had_events {
@@ -125,50 +311,181 @@ impl Program {
                     self.task_manager.cleanup_completed_tasks();
 
                     // Check for expired timeouts and process them
-                    let expired_timeouts = self.model.get_expired_timeouts();
-                    for timeout_type in expired_timeouts {
-                        let cmd = update(&mut self.model, Msg::TimeoutExpired(timeout_type));
-                        self.needs_render = true;
-                        self.spawn_commands(cmd).await?;
-                    }
-
-                    // Only render if needed
-                    if self.needs_render {
-                        self.render_view().await?;
+                    self.process_expired_timeouts().await?;
 
-                        self.needs_render = false;
-                    }
+                    self.render_if_needed().await?;
                 },
             }
         }
+
+        let Some(mut one_shot) = self.one_shot.take() else {
+            return Ok(ExitCode::SUCCESS);
+        };
+        if let Some(output) = one_shot.finish() {
+            write_output(output);
+        }
+        Ok(ExitCode::from(one_shot.exit_code().unwrap_or_default()))
+    }
+
+    /// Quit a one-shot run once it has ended, aborting the response first
+    /// if it was interrupted
+    async fn end_one_shot_if_done(&mut self) -> Result<()> {
+        let Some(one_shot) = self.one_shot.as_mut() else {
+            return Ok(());
+        };
+        if self.interrupted.take() {
+            one_shot.interrupt();
+            return self.handle_msg(Msg::QuitAndAbort).await;
+        }
+        if one_shot.exit_code().is_some() && self.model.state != AppModalState::Quit {
+            return self.handle_msg(Msg::QuitLeaveRunning).await;
+        }
+        Ok(())
+    }
+
+    /// Set the terminal up again if the process was continued. The window
+    /// may have been resized while we were stopped, so the inline height is
+    /// fitted to it first, and the whole screen is redrawn.
+    fn resume_if_continued(&mut self) -> Result<()> {
+        if !self.resumed.take() {
+            return Ok(());
+        }
+        let Some(terminal) = self.terminal.take() else {
+            return Ok(());
+        };
+        if let Some(rows) = terminal.window_rows() {
+            // Starting over from the configured height, which the input
+            // grows the viewport from again
+            self.model.config.height = self.model.inline_height.base;
+            fit_config_to_terminal(&mut self.model.config, rows);
+            self.model.inline_height.base = self.model.config.height;
+            self.model.fit_inline_height_to_terminal(rows);
+            self.model.config.height = self.model.inline_height_for_input();
+        }
+        self.terminal = Some(terminal.resume(&self.model.init, self.model.config.height)?);
+        self.needs_render = true;
+        Ok(())
+    }
+
+    /// Apply a message to the model, recording it first if recording is on
+    fn update_model(&mut self, msg: Msg) -> CmdOrBatch<Cmd> {
+        if let Some(recorder) = self.recorder.as_mut() {
+            if let Err(e) = recorder.record(&msg, self.model.clock.now()) {
+                tracing::warn!("Failed to record message, recording stopped: {}", e);
+                self.recorder = None;
+            }
+        }
+        if let Some(one_shot) = self.one_shot.as_mut() {
+            for output in one_shot.observe(&msg, &self.model) {
+                write_output(output);
+            }
+        }
+        let span =
+            tracing::debug_span!(target: PROFILE_TARGET, "update", msg = tracing::field::Empty);
+        if !span.is_disabled() {
+            span.record("msg", variant_name(&msg));
+        }
+        let _entered = span.enter();
+        update(&mut self.model, msg)
+    }
+
+    async fn handle_msg(&mut self, msg: Msg) -> Result<()> {
+        let cmd = self.update_model(msg);
+        self.spawn_commands(cmd).await
+    }
+
+    /// Start the next step of each sequence whose last step's responses have
+    /// been handled, or report the ones that failed
+    async fn advance_chains(&mut self) -> Result<()> {
+        for progress in self.task_manager.take_chain_progress() {
+            match progress {
+                ChainProgress::Continue(chain_id) => self.run_chain(chain_id).await?,
+                ChainProgress::Aborted { error, skipped } => {
+                    self.handle_msg(Msg::SequenceAborted(error, skipped))
+                        .await?
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Run the steps of a sequence until one is waiting on a task
+    async fn run_chain(&mut self, chain_id: ChainId) -> Result<()> {
+        while let Some(step) = self.task_manager.next_chain_step(chain_id) {
+            let started = Box::pin(self.spawn_command(step)).await;
+            let waiting = self.task_manager.end_chain_step();
+            started?;
+            if waiting {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    async fn process_expired_timeouts(&mut self) -> Result<()> {
+        let expired_timeouts = self.model.get_expired_timeouts();
+        for timeout_type in expired_timeouts {
+            self.handle_msg(Msg::TimeoutExpired(timeout_type)).await?;
+        }
+        Ok(())
+    }
+
+    /// Draw if anything changed since the last draw
+    async fn render_if_needed(&mut self) -> Result<()> {
+        if !self.needs_render && !self.model.dirty.any() {
+            return Ok(());
+        }
+        self.render_view().await?;
+
+        // Keep drawing until streamed text is fully revealed
+        self.needs_render = self.model.message_log.is_revealing();
         Ok(())
     }
 
     async fn render_view(&mut self) -> Result<()> {
-        let cmd = update(
-            &mut self.model,
-            Msg::RecordActiveTaskCount(self.task_manager.active_task_count()),
-        );
+        let cmd = self.update_model(Msg::RecordActiveTasks(
+            self.task_manager.active_task_count(),
+            self.task_manager.active_kinds(),
+        ));
+        self.spawn_commands(cmd).await?;
+
+        if self.model.config.ui_stream_reveal {
+            let cmd = self.update_model(Msg::AdvanceStreamReveal);
+            self.spawn_commands(cmd).await?;
+        }
+
+        self.draw_view()?;
+        let cmd = self.update_model(Msg::MarkMessagesViewed);
         self.spawn_commands(cmd).await?;
 
+        Ok(())
+    }
+
+    fn draw_view(&mut self) -> Result<()> {
+        let _frame = tracing::debug_span!(target: PROFILE_TARGET, "frame").entered();
+        if std::mem::take(&mut self.welcome_banner) {
+            if let Some(terminal) = self.terminal.as_mut() {
+                let _span = tracing::debug_span!("welcome_banner").entered();
+                terminal.insert_welcome_banner(self.model.config.ui_glyphs.glyphs())?;
+            }
+        }
+
         // View: Manual rendering outside the TUI viewport
         if self.model.needs_manual_output() {
             if let Some(terminal) = self.terminal.as_mut() {
-                // // Clear the TUI
-                // terminal.draw(|f| view_clear(f))?;
-
                 // Manually execute with crossterm
-                render_manual_inline_history(&self.model, terminal)?;
+                let _span =
+                    tracing::debug_span!(target: PROFILE_TARGET, "insert_history").entered();
+                terminal.insert_history(&self.model)?;
             }
         }
 
         // View: Pure rendering, within the TUI
         if let Some(terminal) = self.terminal.as_mut() {
-            terminal.draw(|f| view(&self.model, f))?;
+            let _span = tracing::debug_span!(target: PROFILE_TARGET, "draw").entered();
+            terminal.draw(&self.model)?;
         }
-        let cmd = update(&mut self.model, Msg::MarkMessagesViewed);
-        self.spawn_commands(cmd).await?;
-
+        self.model.dirty = DirtyRegions::NONE;
         Ok(())
     }
 
@@ -205,24 +522,63 @@ impl Program {
         if let EventStreamState::Connected(event_stream) = &mut self.model.event_stream_state {
             // Loop to drain all pending events from the stream's buffer
             while let Some(event) = event_stream.try_next_event() {
-                events.push(event);
+                events.push(match event {
+                    Ok(event) => Msg::EventReceived(event),
+                    Err(OpenCodeError::EventStreamLagged { missed }) => {
+                        Msg::EventStreamLagged(missed)
+                    }
+                    Err(error) => Msg::EventStreamError(error.to_string()),
+                });
+            }
+            let unknown = event_stream.unknown_event_count();
+            if unknown > 0 && !self.model.unknown_events_reported {
+                events.push(Msg::EventStreamUnknownEvents(unknown));
             }
         }
 
         if !events.is_empty() {
+            tracing::debug!(name: "event_batch", target: PROFILE_TARGET, events = events.len());
             let mut processed_event = false;
-            for event in events {
-                let cmd = update(&mut self.model, Msg::EventReceived(event));
-                self.needs_render = true; // Signal that a re-render is needed
+            for msg in events {
+                let cmd = self.update_model(msg);
                 self.spawn_commands(cmd).await?;
                 processed_event = true;
             }
+            // Apply scrolling once for the whole batch rather than per event
+            let cmd = self.update_model(Msg::EventBatchDrained);
+            self.spawn_commands(cmd).await?;
             Ok(processed_event)
         } else {
             Ok(false)
         }
     }
 
+    /// Drain the event streams of the projects in the background, which
+    /// only keep their activity current
+    async fn poll_parked_project_events(&mut self) -> Result<bool> {
+        use crate::app::tea_model::EventStreamState;
+
+        let mut events = Vec::new();
+        for (root, project) in self.model.projects.iter_mut() {
+            if let EventStreamState::Connected(event_stream) = &mut project.event_stream_state {
+                while let Some(event) = event_stream.try_next_event() {
+                    match event {
+                        Ok(event) => events.push((root.clone(), event)),
+                        // Parked projects reload their session when switched to
+                        Err(error) => tracing::warn!("Parked project {}: {}", root, error),
+                    }
+                }
+            }
+        }
+
+        let processed_event = !events.is_empty();
+        for (root, event) in events {
+            let cmd = self.update_model(Msg::ProjectEventReceived(root, event));
+            self.spawn_commands(cmd).await?;
+        }
+        Ok(processed_event)
+    }
+
     async fn spawn_commands(&mut self, cmds: CmdOrBatch<Cmd>) -> Result<()> {
         match cmds {
             CmdOrBatch::Single(cmd) => {
@@ -234,24 +590,64 @@ impl Program {
                 for cmd in commands {
                     match cmd {
                         Cmd::AsyncSpawnClientDiscovery
+                        | Cmd::AsyncConnectServer(_)
+                        | Cmd::AsyncListServers
+                        | Cmd::AsyncListServerCandidates
                         | Cmd::AsyncSpawnSessionInit(_)
-                        | Cmd::AsyncCreateSessionWithMessage(_, _)
+                        | Cmd::AsyncResumeLastSession(_)
+                        | Cmd::AsyncCreateSessionWithMessage(_, _, _)
                         | Cmd::AsyncLoadSessions(_)
+                        | Cmd::AsyncLoadSessionDirectories(_)
                         | Cmd::AsyncLoadModes(_)
+                        | Cmd::AsyncLoadProviders(_)
                         | Cmd::AsyncLoadSessionMessages(_, _)
+                        | Cmd::AsyncLoadEarlierMessages(_, _, _)
+                        | Cmd::AsyncPollMessages(_, _)
                         | Cmd::AsyncLoadFileStatus(_)
+                        | Cmd::AsyncLoadGitInfo(_)
                         | Cmd::AsyncLoadFindFiles(_, _)
+                        | Cmd::AsyncFindDirectories(_, _)
+                        | Cmd::AsyncFindSymbols(_, _)
+                        | Cmd::AsyncCheckSessionDirectory(_)
                         | Cmd::AsyncSendUserMessage(_, _, _, _, _, _, _)
                         | Cmd::AsyncSendUserMessageWithAttachments(_, _, _, _, _, _, _, _)
+                        | Cmd::AsyncLoadDraft(_)
+                        | Cmd::AsyncSaveDraft(_, _)
+                        | Cmd::AsyncDeleteDraft(_)
+                        | Cmd::AsyncSaveSessionSort(_)
+                        | Cmd::AsyncLoadSeenVersion
+                        | Cmd::AsyncSaveSeenVersion(_)
                         | Cmd::AsyncCancelTask(_)
+                        | Cmd::AsyncCancelKeyed(_)
+                        | Cmd::AsyncCancelContext(_)
                         | Cmd::AsyncSessionAbort
+                        | Cmd::AsyncAbortSession(_, _)
+                        | Cmd::AsyncOpenFilePart(_, _)
+                        | Cmd::AsyncLoadAppInfo(_)
+                        | Cmd::AsyncLoadServerVersion(_)
+                        | Cmd::AsyncCheckMentions(_, _, _, _)
+                        | Cmd::AsyncScanAttachments(_, _, _, _)
+                        | Cmd::AsyncMeasureAttachments(_, _)
+                        | Cmd::AsyncCheckLinkPaths(_, _, _)
+                        | Cmd::AsyncReadPinnedFile(_, _)
+                        | Cmd::AsyncSaveToolOutput(_)
+                        | Cmd::AsyncWriteBugReport(_)
+                        | Cmd::AsyncOpenUrl(_)
+                        | Cmd::AsyncResolvePastedPaths(_, _, _)
                         | Cmd::AsyncStartEventStream(_)
                         | Cmd::AsyncStopEventStream
+                        | Cmd::Sequence(_)
                         | Cmd::AsyncReconnectEventStream
                         | Cmd::TerminalRebootWithInline(_)
                         | Cmd::TerminalResizeInlineViewport(_)
+                        | Cmd::TerminalInsertBannerInfo(_)
                         | Cmd::TerminalScrollPastHeight
-                        | Cmd::TerminalAutoResize => {
+                        | Cmd::TerminalNotify(_)
+                        | Cmd::TerminalBell
+                        | Cmd::TerminalCopyToClipboard(_)
+                        | Cmd::TerminalSuspend
+                        | Cmd::TerminalAutoResize
+                        | Cmd::LogSetLevels(_) => {
                             Box::pin(self.spawn_command(cmd)).await?;
                         }
                         Cmd::None => {}
@@ -263,32 +659,46 @@ impl Program {
     }
 
     async fn spawn_command(&mut self, cmd: Cmd) -> Result<()> {
+        if let Some(scripted) = self.scripted.as_mut() {
+            if scripted.record(&cmd) {
+                return Ok(());
+            }
+        }
+        // A one-shot prompt isn't typed, so it leaves the TUI's drafts alone
+        if self.one_shot.is_some()
+            && matches!(
+                cmd,
+                Cmd::AsyncLoadDraft(_) | Cmd::AsyncSaveDraft(_, _) | Cmd::AsyncDeleteDraft(_)
+            )
+        {
+            return Ok(());
+        }
+
         match cmd {
             Cmd::TerminalRebootWithInline(new_inline_mode) => {
                 // Deconstruct the old terminal by taking ownership from the Option
-                let mut old_terminal = self.terminal.take();
-
-                if !new_inline_mode {
-                    if let Some(terminal) = old_terminal.as_mut() {
-                        // Clear the TUI when leaving inline mode, so it doesn't
-                        // leave artifacts in the history
-                        tracing::debug!("Clearing to switch from inline to altscreen");
-                        terminal.draw(|f| view_clear(f))?;
-                        // Move the cursor back to the top left of the TUI,
-                        // so if we switch back and forth we don't offset
-                        terminal
-                            .draw(|f| f.set_cursor_position((f.area().left(), f.area().top())))?;
+                let new_init = ModelInit::new(new_inline_mode);
+
+                // Flush anything still pending into the inline scrollback before
+                // leaving it, then seed the printed state for the new mode
+                if self.model.needs_manual_output() {
+                    if let Some(terminal) = self.terminal.as_mut() {
+                        terminal.insert_history(&self.model)?;
                     }
                 }
+                self.model.prepare_inline_change(new_inline_mode);
 
-                // Restore the old terminal state before creating new one
-                if let Some(mut terminal) = old_terminal.take() {
-                    restore_terminal(&self.model.init, self.model.config.height)
-                        .wrap_err("Failed to restore terminal")?;
+                if let Some(old_terminal) = self.terminal.take() {
+                    let terminal = old_terminal.reboot(
+                        &self.model.init,
+                        &new_init,
+                        self.model.config.height,
+                    )?;
+                    self.terminal = Some(terminal);
+                } else {
+                    let terminal = CrosstermTerminal::new(&new_init, self.model.config.height)?;
+                    self.terminal = Some(Box::new(terminal));
                 }
-                let new_init = ModelInit::new(new_inline_mode);
-                let mut terminal = init_terminal(&new_init, self.model.config.height)?;
-                self.terminal = Some(terminal);
                 self.model.init = new_init;
             }
 
@@ -297,12 +707,7 @@ impl Program {
                     if self.model.init.inline_mode() {
                         // Update model state first
                         self.model.config.height = new_height;
-
-                        // Use ratatui's resize method with new inline viewport
-                        let terminal_size = terminal.size()?;
-                        let new_viewport_area =
-                            ratatui::layout::Rect::new(0, 0, terminal_size.width, new_height);
-                        terminal.resize(new_viewport_area)?;
+                        terminal.resize_inline_viewport(new_height)?;
 
                         // Force re-render
                         self.needs_render = true;
@@ -317,129 +722,471 @@ impl Program {
                 }
             }
 
+            Cmd::TerminalInsertBannerInfo(info) => {
+                if let Some(terminal) = self.terminal.as_mut() {
+                    terminal.insert_banner_info(&info)?;
+                    self.needs_render = true;
+                }
+            }
+
+            Cmd::TerminalNotify(text) => {
+                if let Some(terminal) = self.terminal.as_mut() {
+                    terminal.notify(&text)?;
+                }
+            }
+
+            Cmd::TerminalBell => {
+                if let Some(terminal) = self.terminal.as_mut() {
+                    terminal.bell()?;
+                }
+            }
+
+            Cmd::TerminalCopyToClipboard(text) => {
+                if let Some(terminal) = self.terminal.as_mut() {
+                    terminal.copy_to_clipboard(&text)?;
+                }
+            }
+
+            Cmd::TerminalSuspend => {
+                if let Some(terminal) = self.terminal.as_mut() {
+                    terminal.suspend(&self.model.init, self.model.config.height)?;
+                }
+            }
+
+            Cmd::LogSetLevels(levels) => self.set_log_levels(&levels),
+
+            Cmd::AsyncLoadAppInfo(client) => {
+                self.task_manager.spawn_task(async move {
+                    Msg::ResponseAppInfoLoad(client.get_app_info().await)
+                });
+            }
+
+            Cmd::AsyncLoadServerVersion(client) => {
+                self.task_manager.spawn_task(async move {
+                    Msg::ResponseServerVersionLoad(client.get_server_version().await)
+                });
+            }
+
+            Cmd::AsyncCheckMentions(client, root, text, paths) => {
+                self.task_manager.spawn_task(async move {
+                    let mut missing = Vec::new();
+                    for path in paths {
+                        // A failed lookup shouldn't hold the message back
+                        if let Ok(false) = client.file_exists(&path, root.as_deref()).await {
+                            missing.push(path);
+                        }
+                    }
+                    Msg::ResponseMentionsCheck(text, missing)
+                });
+            }
+
+            Cmd::AsyncMeasureAttachments(root, paths) => {
+                self.task_manager.spawn_task(async move {
+                    let mut sizes = Vec::new();
+                    for path in paths {
+                        // One that can't be found counts for nothing
+                        let size = tokio::fs::metadata(Path::new(&root).join(&path))
+                            .await
+                            .map(|metadata| metadata.len() as usize)
+                            .unwrap_or(0);
+                        sizes.push((path, size));
+                    }
+                    Msg::ResponseAttachmentSizes(sizes)
+                });
+            }
+
+            Cmd::AsyncScanAttachments(client, redactor, text, paths) => {
+                self.task_manager.spawn_task(async move {
+                    let mut found = Vec::new();
+                    for path in paths {
+                        // A file that can't be read is sent or fails as usual
+                        if let Ok(file) = client.read_file(&path).await {
+                            let patterns = redactor.matching_patterns(&file.content);
+                            if !patterns.is_empty() {
+                                found.push((path, patterns));
+                            }
+                        }
+                    }
+                    Msg::ResponseAttachmentScan(text, found)
+                });
+            }
+
+            Cmd::AsyncResolvePastedPaths(text, paths, root) => {
+                self.task_manager.spawn_task(async move {
+                    let home = dirs::home_dir();
+                    let files =
+                        resolve_pasted_paths(&paths, home.as_deref(), root.as_deref()).await;
+                    Msg::ResponsePastedPaths(text, files)
+                });
+            }
+
             Cmd::TerminalScrollPastHeight => {
                 // Inline mode text input will have some stdout messages in
                 // viewport, so switching screens we have to push that up
 
                 if let Some(terminal) = self.terminal.as_mut() {
-                    // Clear the TUI
-                    terminal.draw(|f| view_clear(f))?;
-
                     // Rows of message output that need to be moved up,
                     // before more TUI can be rendered
                     let scroll_line_count = self.model.config.height - TEXT_INPUT_HEIGHT;
-                    crossterm::execute!(
-                        io::stdout(),
-                        crossterm::terminal::ScrollUp(scroll_line_count)
-                    )?;
+                    terminal.scroll_past_height(scroll_line_count)?;
                     self.needs_render = true;
                 }
             }
 
             Cmd::AsyncSpawnClientDiscovery => {
-                // Spawn async client discovery task
+                // Spawn async client discovery task, trying the configured
+                // server first and preferring one started in this directory
+                // when several are running
+                let server_url = ResolvedSettings::value(&self.model.config.settings.server_url)
+                    .map(str::to_string);
                 self.task_manager.spawn_task(async move {
-                    match OpenCodeClient::discover().await {
-                        Ok(client) => Msg::ResponseClientConnect(Ok(client)),
+                    let config = DiscoveryConfig {
+                        preferred_project_path: std::env::current_dir().ok(),
+                        server_url,
+                        ..DiscoveryConfig::default()
+                    };
+                    match discover_or_list_servers(&config).await {
+                        Ok(DiscoveryOutcome::Server(url)) => {
+                            Msg::ResponseClientConnect(Ok(OpenCodeClient::new(&url)))
+                        }
+                        Ok(DiscoveryOutcome::Ambiguous(candidates)) => {
+                            Msg::ResponseServerCandidates(candidates)
+                        }
                         Err(error) => Msg::ResponseClientConnect(Err(error)),
                     }
                 });
             }
 
+            Cmd::AsyncConnectServer(url) => {
+                // The chosen server may have stopped since it was listed
+                self.task_manager.spawn_task(async move {
+                    let client = OpenCodeClient::new(&url);
+                    match client.test_connection().await {
+                        Ok(()) => Msg::ResponseClientConnect(Ok(client)),
+                        Err(error) => Msg::ResponseClientConnect(Err(error)),
+                    }
+                });
+            }
+
+            Cmd::AsyncListServers => {
+                self.task_manager.spawn_task(async move {
+                    let servers = discover_opencode_servers(&DiscoveryConfig::default()).await;
+                    Msg::ResponseProjectCandidates(servers)
+                });
+            }
+
+            Cmd::AsyncListServerCandidates => {
+                self.task_manager.spawn_task(async move {
+                    let servers = discover_opencode_servers(&DiscoveryConfig::default()).await;
+                    Msg::ResponseServerCandidates(servers)
+                });
+            }
+
             Cmd::AsyncSpawnSessionInit(client) => {
                 // Check if there's a selected session from the session selector
                 let selected_session_id = self.model.current_session_id();
 
                 // Spawn async session initialization task
-                self.task_manager.spawn_task(async move {
-                    // If we have a selected session ID, save it as the last session first
-                    if let Some(session_id) = selected_session_id {
-                        if let Err(e) = client.switch_to_session(&session_id).await {
-                            tracing::error!("Save session ID {} failed: {}", session_id, e);
+                self.task_manager
+                    .spawn_keyed(TaskKind::InitSession, async move {
+                        // If we have a selected session ID, save it as the last session first
+                        if let Some(session_id) = selected_session_id {
+                            if let Err(e) = client.switch_to_session(&session_id).await {
+                                tracing::error!("Save session ID {} failed: {}", session_id, e);
+                            }
+                        } else {
+                            if let Err(e) = client.clear_current_session().await {
+                                tracing::error!("Clear session failed: {}", e);
+                            }
                         }
-                    } else {
-                        if let Err(e) = client.clear_current_session().await {
-                            tracing::error!("Clear session failed: {}", e);
+
+                        // Get or create session (will use saved session if available)
+                        match client.get_or_create_session().await {
+                            Ok(session) => Msg::ResponseSessionInit(Ok(session)),
+                            Err(error) => Msg::ResponseSessionInit(Err(error)),
                         }
-                    }
+                    });
+            }
 
-                    // Get or create session (will use saved session if available)
-                    match client.get_or_create_session().await {
-                        Ok(session) => Msg::ResponseSessionInit(Ok(session)),
-                        Err(error) => Msg::ResponseSessionInit(Err(error)),
+            Cmd::AsyncResumeLastSession(client) => {
+                // Look up the remembered session; a deleted one resolves to None
+                self.task_manager.spawn_task(async move {
+                    match client.get_current_session().await {
+                        Ok(session) => Msg::ResponseSessionResume(Ok(session)),
+                        Err(error) => Msg::ResponseSessionResume(Err(error)),
                     }
                 });
             }
 
-            Cmd::AsyncCreateSessionWithMessage(client, first_message) => {
+            Cmd::AsyncCreateSessionWithMessage(client, first_message, directory) => {
                 // Spawn async session creation task with first message
-                self.task_manager.spawn_task(async move {
-                    // Clear any existing session first
-                    if let Err(error) = client.clear_current_session().await {
-                        tracing::error!("Clear session failed: {}", error);
-                        Msg::ResponseSessionCreateWithMessage(Err(error))
-                    } else {
-                        // Create new session
-                        match client.create_new_session().await {
-                            Ok(session) => {
-                                Msg::ResponseSessionCreateWithMessage(Ok((session, first_message)))
-                            }
-                            Err(error) => {
-                                tracing::error!("Create session failed: {}", error);
-                                Msg::ResponseSessionCreateWithMessage(Err(error))
+                self.task_manager
+                    .spawn_in(TaskContext::SessionCreation, async move {
+                        // Clear any existing session first
+                        if let Err(error) = client.clear_current_session().await {
+                            tracing::error!("Clear session failed: {}", error);
+                            Msg::ResponseSessionCreateWithMessage(Err(error))
+                        } else {
+                            // Create new session
+                            let created = match &directory {
+                                Some(directory) => client.create_new_session_in(directory).await,
+                                None => client.create_new_session().await,
+                            };
+                            match created {
+                                Ok(session) => Msg::ResponseSessionCreateWithMessage(Ok((
+                                    session,
+                                    first_message,
+                                ))),
+                                Err(error) => {
+                                    tracing::error!("Create session failed: {}", error);
+                                    Msg::ResponseSessionCreateWithMessage(Err(error))
+                                }
                             }
                         }
-                    }
-                });
+                    });
             }
 
             Cmd::AsyncLoadSessions(client) => {
                 // Spawn async session loading task
+                self.task_manager.spawn_keyed_in(
+                    TaskContext::SessionSelector,
+                    TaskKind::LoadSessions,
+                    async move {
+                        match client.list_sessions().await {
+                            Ok(sessions) => Msg::ResponseSessionsLoad(Ok(sessions)),
+                            Err(error) => Msg::ResponseSessionsLoad(Err(error)),
+                        }
+                    },
+                );
+            }
+
+            Cmd::AsyncLoadSessionDirectories(client) => {
+                // Directories come separately, as the generated model drops them
+                self.task_manager
+                    .spawn_in(TaskContext::SessionSelector, async move {
+                        Msg::ResponseSessionDirectoriesLoad(client.list_session_directories().await)
+                    });
+            }
+
+            Cmd::AsyncLoadFileStatus(client) => {
+                // Spawn async file status loading task
+                self.task_manager.spawn_keyed_in(
+                    TaskContext::project(&client),
+                    TaskKind::LoadFileStatus,
+                    async move {
+                        match client.get_file_status().await {
+                            Ok(file_status) => Msg::ResponseFileStatusesLoad(Ok(file_status)),
+                            Err(error) => Msg::ResponseFileStatusesLoad(Err(error)),
+                        }
+                    },
+                );
+            }
+
+            Cmd::AsyncLoadGitInfo(client) => {
+                // Spawn async git info loading task
+                self.task_manager.spawn_keyed_in(
+                    TaskContext::project(&client),
+                    TaskKind::LoadGitInfo,
+                    async move {
+                        match client.get_git_info().await {
+                            Ok(git_info) => Msg::ResponseGitInfoLoad(Ok(git_info)),
+                            Err(error) => Msg::ResponseGitInfoLoad(Err(error)),
+                        }
+                    },
+                );
+            }
+
+            Cmd::AsyncLoadFindFiles(client, query) => {
+                // Spawn async find files task
+                self.task_manager.spawn_keyed(
+                    TaskKind::FindFiles {
+                        query: query.clone(),
+                    },
+                    async move {
+                        let result = client.find_files(&query).await;
+                        Msg::ResponseFindFiles(query, result)
+                    },
+                );
+            }
+
+            Cmd::AsyncFindSymbols(client, query) => {
+                self.task_manager.spawn_keyed(
+                    TaskKind::FindSymbols {
+                        query: query.clone(),
+                    },
+                    async move {
+                        let result = client.find_symbols(&query).await;
+                        Msg::ResponseFindSymbols(query, result)
+                    },
+                );
+            }
+
+            Cmd::AsyncFindDirectories(client, query) => {
+                self.task_manager.spawn_keyed(
+                    TaskKind::FindDirectories {
+                        query: query.clone(),
+                    },
+                    async move {
+                        let result = client.find_files(&query).await;
+                        Msg::ResponseFindDirectories(query, result)
+                    },
+                );
+            }
+
+            Cmd::AsyncCheckSessionDirectory(directory) => {
                 self.task_manager.spawn_task(async move {
-                    match client.list_sessions().await {
-                        Ok(sessions) => Msg::ResponseSessionsLoad(Ok(sessions)),
-                        Err(error) => Msg::ResponseSessionsLoad(Err(error)),
+                    let result = match tokio::fs::metadata(&directory).await {
+                        Ok(metadata) if metadata.is_dir() => Ok(()),
+                        Ok(_) => Err(strings::NOT_A_DIRECTORY.to_string()),
+                        Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+                            Err(strings::NO_SUCH_DIRECTORY.to_string())
+                        }
+                        Err(error) => Err(error.to_string()),
+                    };
+                    Msg::ResponseSessionDirectoryCheck(directory, result)
+                });
+            }
+
+            Cmd::AsyncLoadModes(client) => {
+                // Spawn async modes loading task
+                self.task_manager.spawn_keyed_in(
+                    TaskContext::project(&client),
+                    TaskKind::LoadModes,
+                    async move {
+                        match client.get_agent_configs().await {
+                            Ok(agent_configs) => Msg::ResponseModesLoad(Ok(agent_configs)),
+                            Err(error) => Msg::ResponseModesLoad(Err(error)),
+                        }
+                    },
+                );
+            }
+
+            Cmd::AsyncLoadProviders(client) => {
+                self.task_manager.spawn_keyed_in(
+                    TaskContext::project(&client),
+                    TaskKind::LoadProviders,
+                    async move { Msg::ResponseProvidersLoad(client.get_providers().await) },
+                );
+            }
+
+            Cmd::AsyncLoadDraft(key) => {
+                let draft_store = self.draft_store.clone();
+                self.task_manager.spawn_keyed(
+                    TaskKind::LoadDraft { key: key.clone() },
+                    async move {
+                        let draft = draft_store.load(&key).await.unwrap_or_else(|e| {
+                            tracing::error!("Load draft for {} failed: {}", key, e);
+                            None
+                        });
+                        Msg::ResponseDraftLoad(key, draft)
+                    },
+                );
+            }
+
+            Cmd::AsyncSaveDraft(key, draft) => {
+                let draft_store = self.draft_store.clone();
+                let task_id = self.task_manager.spawn_task(async move {
+                    let saved = draft_store.save(&key, &draft).await;
+                    if let Err(e) = &saved {
+                        tracing::error!("Save draft for {} failed: {}", key, e);
                     }
+                    Msg::ResponseDraftSave(key, saved.is_ok())
                 });
+                self.draft_save_task = Some(task_id);
             }
 
-            Cmd::AsyncLoadFileStatus(client) => {
-                // Spawn async file status loading task
+            Cmd::AsyncDeleteDraft(key) => {
+                let draft_store = self.draft_store.clone();
                 self.task_manager.spawn_task(async move {
-                    match client.get_file_status().await {
-                        Ok(file_status) => Msg::ResponseFileStatusesLoad(Ok(file_status)),
-                        Err(error) => Msg::ResponseFileStatusesLoad(Err(error)),
+                    let removed = draft_store.remove(&key).await;
+                    if let Err(e) = &removed {
+                        tracing::error!("Delete draft for {} failed: {}", key, e);
                     }
+                    Msg::ResponseDraftSave(key, removed.is_ok())
                 });
             }
 
-            Cmd::AsyncLoadFindFiles(client, query) => {
-                // Spawn async find files task
+            Cmd::AsyncLoadSeenVersion => {
+                self.task_manager.spawn_task(async move {
+                    let last_seen = load_seen_version(&default_seen_version_path())
+                        .await
+                        .unwrap_or_else(|e| {
+                            tracing::error!("Load the version last run failed: {}", e);
+                            None
+                        });
+                    Msg::ResponseSeenVersionLoad(last_seen)
+                });
+            }
+
+            Cmd::AsyncSaveSeenVersion(version) => {
                 self.task_manager.spawn_task(async move {
-                    match client.find_files(&query).await {
-                        Ok(file_paths) => Msg::ResponseFindFiles(Ok(file_paths)),
-                        Err(error) => Msg::ResponseFindFiles(Err(error)),
+                    let saved = save_seen_version(&default_seen_version_path(), &version).await;
+                    if let Err(e) = &saved {
+                        tracing::error!("Save the version last run failed: {}", e);
                     }
+                    Msg::ResponseSeenVersionSave(saved.is_ok())
                 });
             }
 
-            Cmd::AsyncLoadModes(client) => {
-                // Spawn async modes loading task
+            Cmd::AsyncSaveSessionSort(sort) => {
                 self.task_manager.spawn_task(async move {
-                    match client.get_agent_configs().await {
-                        Ok(agent_configs) => Msg::ResponseModesLoad(Ok(agent_configs)),
-                        Err(error) => Msg::ResponseModesLoad(Err(error)),
+                    let saved = save_session_sort(&default_config_path(), sort).await;
+                    if let Err(e) = &saved {
+                        tracing::error!("Save session sort failed: {}", e);
                     }
+                    Msg::ResponseSessionSortSave(saved.is_ok())
                 });
             }
 
             Cmd::AsyncLoadSessionMessages(client, session_id) => {
                 // Spawn async session messages loading task
-                self.task_manager.spawn_task(async move {
-                    match client.get_messages(&session_id).await {
-                        Ok(messages) => Msg::ResponseSessionMessagesLoad(Ok(messages)),
-                        Err(error) => Msg::ResponseSessionMessagesLoad(Err(error)),
-                    }
+                self.task_manager.spawn_keyed_in(
+                    TaskContext::Session {
+                        session_id: session_id.clone(),
+                    },
+                    TaskKind::LoadMessages {
+                        session_id: session_id.clone(),
+                    },
+                    async move {
+                        let page = client
+                            .get_messages_page(&session_id, None, MESSAGE_PAGE_SIZE)
+                            .await;
+                        Msg::ResponseSessionMessagesLoad(session_id, page)
+                    },
+                );
+            }
+
+            Cmd::AsyncLoadEarlierMessages(client, session_id, before_message_id) => {
+                self.task_manager.spawn_keyed_in(
+                    TaskContext::Session {
+                        session_id: session_id.clone(),
+                    },
+                    TaskKind::LoadMessages {
+                        session_id: session_id.clone(),
+                    },
+                    async move {
+                        let page = client
+                            .get_messages_page(
+                                &session_id,
+                                Some(&before_message_id),
+                                MESSAGE_PAGE_SIZE,
+                            )
+                            .await;
+                        Msg::ResponseEarlierMessagesLoad(session_id, page)
+                    },
+                );
+            }
+
+            Cmd::AsyncPollMessages(client, session_id) => {
+                // Not keyed, so a poll doesn't supersede a page being loaded
+                let context = TaskContext::Session {
+                    session_id: session_id.clone(),
+                };
+                self.task_manager.spawn_in(context, async move {
+                    let page = client
+                        .get_messages_page(&session_id, None, MESSAGE_PAGE_SIZE)
+                        .await;
+                    Msg::ResponseMessagesPoll(session_id, page)
                 });
             }
 
@@ -450,7 +1197,7 @@ impl Program {
                 text,
                 provider_id,
                 model_id,
-                mode,
+                options,
             ) => {
                 // Spawn async user message sending task
                 self.task_manager.spawn_task(async move {
@@ -462,12 +1209,12 @@ impl Program {
                             &text,
                             &provider_id,
                             &model_id,
-                            mode.as_deref(),
+                            &options,
                         )
                         .await
                     {
-                        Ok(_) => Msg::ResponseUserMessageSend(Ok(text)),
-                        Err(error) => Msg::ResponseUserMessageSend(Err(error)),
+                        Ok(_) => Msg::ResponseUserMessageSend(message_id, Ok(text)),
+                        Err(error) => Msg::ResponseUserMessageSend(message_id, Err(error)),
                     }
                 });
             }
@@ -480,7 +1227,7 @@ impl Program {
                 attached_files,
                 provider_id,
                 model_id,
-                mode,
+                options,
             ) => {
                 // Spawn async user message with attachments sending task
                 self.task_manager.spawn_task(async move {
@@ -492,12 +1239,12 @@ impl Program {
                             &attached_files,
                             &provider_id,
                             &model_id,
-                            mode.as_deref(),
+                            &options,
                         )
                         .await
                     {
-                        Ok(_) => Msg::ResponseUserMessageSend(Ok(text)),
-                        Err(error) => Msg::ResponseUserMessageSend(Err(error)),
+                        Ok(_) => Msg::ResponseUserMessageSend(message_id, Ok(text)),
+                        Err(error) => Msg::ResponseUserMessageSend(message_id, Err(error)),
                     }
                 });
             }
@@ -509,18 +1256,93 @@ impl Program {
                 });
             }
 
+            Cmd::AsyncAbortSession(client, session_id) => {
+                let task_id = self.task_manager.spawn_task(async move {
+                    Msg::ResponseSessionAbort(client.abort_session(&session_id).await)
+                });
+                self.abort_task = Some(task_id);
+            }
+
+            Cmd::AsyncOpenFilePart(client, file_part) => {
+                self.task_manager.spawn_task(async move {
+                    let name = file_part
+                        .filename
+                        .clone()
+                        .unwrap_or_else(|| "attachment".to_string());
+                    match open_file_part(&client, &file_part).await {
+                        Ok(path) => {
+                            tracing::info!("Opened {} from {}", name, path.display());
+                            Msg::ResponseFileOpen(name, None)
+                        }
+                        Err(e) => {
+                            tracing::error!("Open {} failed: {}", name, e);
+                            Msg::ResponseFileOpen(name, Some(e.to_string()))
+                        }
+                    }
+                });
+            }
+
+            Cmd::AsyncCheckLinkPaths(client, root, paths) => {
+                self.task_manager.spawn_task(async move {
+                    let mut results = Vec::new();
+                    for path in paths {
+                        // A failed lookup leaves the path to be tried again
+                        let exists = client.file_exists(&path, root.as_deref()).await.ok();
+                        results.push((path, exists));
+                    }
+                    Msg::ResponseLinkPaths(results)
+                });
+            }
+
+            Cmd::AsyncReadPinnedFile(client, path) => {
+                self.task_manager.spawn_task(async move {
+                    let file = client.read_file(&path).await;
+                    Msg::ResponsePinFile(path, file)
+                });
+            }
+
+            Cmd::AsyncSaveToolOutput(request) => {
+                self.task_manager.spawn_task(async move {
+                    let path = request.path.clone();
+                    Msg::ResponseToolOutputSave(path, save_output(request).await)
+                });
+            }
+
+            Cmd::AsyncWriteBugReport(report) => {
+                self.task_manager.spawn_task(async move {
+                    let written = report
+                        .write(&bugreport_directory(), &logger::log_directory())
+                        .await
+                        .map(|dir| dir.display().to_string())
+                        .map_err(|error| error.to_string());
+                    Msg::ResponseBugReport(written)
+                });
+            }
+
+            Cmd::AsyncOpenUrl(url) => {
+                self.task_manager.spawn_task(async move {
+                    let error = open_with_platform_opener(&url).err().map(|e| e.to_string());
+                    Msg::ResponseFileOpen(url, error)
+                });
+            }
+
             Cmd::AsyncCancelTask(task_id) => {
                 self.task_manager.cancel_task(task_id);
             }
 
+            Cmd::AsyncCancelKeyed(kind) => {
+                self.task_manager.cancel_keyed(&kind);
+            }
+
+            Cmd::AsyncCancelContext(context) => {
+                self.task_manager.cancel_context(&context);
+            }
+
             Cmd::AsyncStartEventStream(client) => {
                 // Spawn async event stream initialization task
                 self.task_manager.spawn_task(async move {
-                    match EventStream::new(client.configuration().clone()).await {
-                        Ok(event_stream) => {
-                            let handle = event_stream.handle();
-                            Msg::EventStreamConnected(handle)
-                        }
+                    match client.subscribe_to_events().await {
+                        Ok(handle) => Msg::EventStreamConnected(handle),
                         Err(error) => Msg::EventStreamError(format!(
                             "Failed to start event stream: {}",
                             error
@@ -529,6 +1351,11 @@ impl Program {
                 });
             }
 
+            Cmd::Sequence(steps) => {
+                let chain_id = self.task_manager.start_chain(steps);
+                self.run_chain(chain_id).await?;
+            }
+
             Cmd::AsyncStopEventStream => {
                 // Event stream will be dropped when the handle is removed from the model
                 // No explicit action needed as the EventStream handles cleanup internally
@@ -549,10 +1376,27 @@ impl Program {
     }
 }
 
+/// Print a one-shot run's output right away, so a pipe sees the reply as it
+/// streams. A reader that went away, like `head`, isn't worth failing over.
+fn write_output(output: Output) {
+    let written = match output {
+        Output::Stdout(text) => {
+            let mut stdout = std::io::stdout().lock();
+            stdout
+                .write_all(text.as_bytes())
+                .and_then(|()| stdout.flush())
+        }
+        Output::Stderr(line) => writeln!(std::io::stderr(), "{}", line),
+    };
+    if let Err(e) = written {
+        tracing::debug!("Failed to write one-shot output: {}", e);
+    }
+}
+
 impl Drop for Program {
     fn drop(&mut self) {
-        if let Some(_) = self.terminal.take() {
-            if let Err(e) = restore_terminal(&self.model.init, self.model.config.height) {
+        if let Some(mut terminal) = self.terminal.take() {
+            if let Err(e) = terminal.restore(&self.model.init, self.model.config.height) {
                 tracing::error!("Failed to restore terminal during program cleanup: {}", e);
                 eprintln!(
                     "Failed to restore terminal. Run `reset` or restart your terminal to recover: {}",
@@ -562,3 +1406,771 @@ impl Drop for Program {
         }
     }
 }
+
+/// Headless driver for the program loop, used by tests and recording replays
+/// to run update/view against an in-memory terminal with a manual clock
+pub mod scripted {
+    use super::*;
+    use crate::app::{
+        event_msg::Sub,
+        msg_recorder::RecordedMsg,
+        tea_model::Clock,
+        terminal::{HeadlessFrames, HeadlessHistory, HeadlessTerminal},
+    };
+    use ratatui::buffer::Buffer;
+    use std::panic::{self, AssertUnwindSafe};
+    use std::time::SystemTime;
+
+    /// One step of a scripted run
+    #[derive(Debug, Clone)]
+    #[allow(clippy::large_enum_variant)]
+    pub enum ScriptedEvent {
+        /// Terminal input, mapped through the keyboard subscription
+        Input(crossterm::event::Event),
+        /// A message delivered directly, e.g. a faked async response
+        Msg(Msg),
+        /// Advance the manual clock, firing any expired timeouts
+        Advance(Duration),
+    }
+
+    pub struct ScriptedState {
+        commands: Vec<Cmd>,
+        frames: HeadlessFrames,
+        history: HeadlessHistory,
+    }
+
+    impl ScriptedState {
+        /// Record a command, returning true if it should not be executed.
+        /// Async commands are only recorded since there is no server to talk to.
+        pub(super) fn record(&mut self, cmd: &Cmd) -> bool {
+            if !matches!(cmd, Cmd::None) {
+                self.commands.push(cmd.clone());
+            }
+            !matches!(
+                cmd,
+                Cmd::TerminalRebootWithInline(_)
+                    | Cmd::TerminalResizeInlineViewport(_)
+                    | Cmd::TerminalAutoResize
+                    | Cmd::TerminalInsertBannerInfo(_)
+                    | Cmd::TerminalScrollPastHeight
+                    | Cmd::TerminalNotify(_)
+                    | Cmd::TerminalBell
+                    | Cmd::TerminalCopyToClipboard(_)
+                    | Cmd::TerminalSuspend
+            )
+        }
+    }
+
+    /// Result of a scripted run
+    pub struct ScriptedRun {
+        pub model: Model,
+        pub frames: Vec<Buffer>,
+        pub commands: Vec<Cmd>,
+        /// IDs of the messages printed into the inline scrollback, in order
+        pub history: Vec<String>,
+    }
+
+    impl ScriptedRun {
+        pub fn last_frame_text(&self) -> String {
+            self.frames.last().map(buffer_to_string).unwrap_or_default()
+        }
+    }
+
+    /// A panic raised while replaying a recording
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct ReplayPanic {
+        /// Index of the message being replayed
+        pub index: usize,
+        pub message: String,
+    }
+
+    /// Result of replaying a recording
+    pub struct ReplayReport {
+        pub model: Model,
+        /// The last rendered frame
+        pub screen: String,
+        /// Messages applied before the replay ended
+        pub replayed: usize,
+        pub panic: Option<ReplayPanic>,
+    }
+
+    fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+        payload
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string())
+    }
+
+    pub fn buffer_to_string(buffer: &Buffer) -> String {
+        let area = buffer.area;
+        (area.top()..area.bottom())
+            .map(|y| {
+                (area.left()..area.right())
+                    .map(|x| buffer[(x, y)].symbol())
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    impl Program {
+        /// Create a program on a headless terminal, with the model on a manual clock
+        pub fn headless(mut model: Model, width: u16, height: u16) -> Result<Self> {
+            model.clock = Clock::Manual(SystemTime::UNIX_EPOCH);
+            let terminal = HeadlessTerminal::new(&model.init, width, height, model.config.height)?;
+            let frames = terminal.frames();
+            let history = terminal.history();
+            let mut program = Program::with_terminal(model, Box::new(terminal));
+            program.scripted = Some(ScriptedState {
+                commands: Vec::new(),
+                frames,
+                history,
+            });
+            Ok(program)
+        }
+
+        /// Run the script step by step, rendering after each one, until the
+        /// script ends or `until` holds for the model
+        pub async fn run_scripted<I, F>(mut self, script: I, until: F) -> Result<ScriptedRun>
+        where
+            I: IntoIterator<Item = ScriptedEvent>,
+            F: Fn(&Model) -> bool,
+        {
+            self.render_view().await?;
+
+            for step in script {
+                match step {
+                    ScriptedEvent::Input(event) => {
+                        let subs = event_sync_subscriptions::subscriptions(&self.model);
+                        if subs.contains(&Sub::KeyboardInput) {
+                            if let Some(msg) =
+                                event_sync_subscriptions::crossterm_to_msg(event, &self.model)
+                            {
+                                self.handle_msg(msg).await?;
+                            }
+                        }
+                    }
+                    ScriptedEvent::Msg(msg) => self.handle_msg(msg).await?,
+                    ScriptedEvent::Advance(duration) => {
+                        self.model.clock.advance(duration);
+                        self.process_expired_timeouts().await?;
+                    }
+                }
+
+                for msg in self.task_manager.poll_messages() {
+                    self.handle_msg(msg).await?;
+                }
+                self.advance_chains().await?;
+                self.render_view().await?;
+
+                if until(&self.model) {
+                    break;
+                }
+            }
+
+            let scripted = self
+                .scripted
+                .take()
+                .expect("program was not created headless");
+            let frames = scripted.frames.borrow().clone();
+            let history = scripted.history.borrow().clone();
+            Ok(ScriptedRun {
+                model: std::mem::replace(&mut self.model, Model::new()),
+                frames,
+                commands: scripted.commands,
+                history,
+            })
+        }
+
+        /// Feed recorded messages through update, rendering after each one,
+        /// with the clock following the recorded timestamps. The replay stops
+        /// at the first panic in update or view.
+        pub fn replay(mut self, recording: Vec<RecordedMsg>) -> Result<ReplayReport> {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()?;
+            let frames = self
+                .scripted
+                .as_ref()
+                .expect("program was not created headless")
+                .frames
+                .clone();
+
+            let mut replayed = 0;
+            let mut replay_panic = None;
+            for (index, recorded) in recording.into_iter().enumerate() {
+                self.model.clock = Clock::Manual(SystemTime::UNIX_EPOCH + recorded.elapsed());
+                let step = panic::catch_unwind(AssertUnwindSafe(|| {
+                    runtime.block_on(self.handle_msg(recorded.msg))?;
+                    self.draw_view()
+                }));
+                match step {
+                    Ok(result) => result?,
+                    Err(payload) => {
+                        replay_panic = Some(ReplayPanic {
+                            index,
+                            message: panic_message(payload.as_ref()),
+                        });
+                        break;
+                    }
+                }
+                replayed += 1;
+
+                // Only the latest frame is reported, so don't hold on to the rest
+                let mut frames = frames.borrow_mut();
+                let stale = frames.len().saturating_sub(1);
+                frames.drain(..stale);
+                if let Some(scripted) = self.scripted.as_mut() {
+                    scripted.commands.clear();
+                }
+            }
+
+            let screen = frames
+                .borrow()
+                .last()
+                .map(buffer_to_string)
+                .unwrap_or_default();
+            Ok(ReplayReport {
+                model: std::mem::replace(&mut self.model, Model::new()),
+                screen,
+                replayed,
+                panic: replay_panic,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::scripted::{ScriptedEvent, ScriptedRun};
+    use super::*;
+    use crate::app::tea_model::SessionState;
+    use crate::app::ui_components::{banner::BannerInfo, glyphs::Glyphs};
+    use crate::sdk::MessagePage;
+    use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+    use opencode_sdk::models::{
+        event_period_message_period_part_period_updated, event_period_message_period_updated,
+        text_part, user_message, ConfigAgent, Event as SdkEvent, EventMessagePartUpdatedProperties,
+        EventMessageUpdatedProperties, EventPeriodMessagePeriodPartPeriodUpdated,
+        EventPeriodMessagePeriodUpdated, Message, Part, Session, SessionMessages200ResponseInner,
+        SessionTime, TextPart, UserMessage, UserMessageTime,
+    };
+
+    fn key(code: KeyCode) -> ScriptedEvent {
+        ScriptedEvent::Input(Event::Key(KeyEvent::new(code, KeyModifiers::NONE)))
+    }
+
+    fn ctrl(c: char) -> ScriptedEvent {
+        ScriptedEvent::Input(Event::Key(KeyEvent::new(
+            KeyCode::Char(c),
+            KeyModifiers::CONTROL,
+        )))
+    }
+
+    fn type_text(text: &str) -> Vec<ScriptedEvent> {
+        text.chars().map(|c| key(KeyCode::Char(c))).collect()
+    }
+
+    fn test_client() -> OpenCodeClient {
+        OpenCodeClient::new("http://127.0.0.1:0")
+    }
+
+    fn test_session(id: &str, title: &str) -> Session {
+        Session::new(
+            id.to_string(),
+            title.to_string(),
+            "0.0.0".to_string(),
+            SessionTime::new(0.0, 0.0),
+        )
+    }
+
+    async fn run(script: Vec<ScriptedEvent>) -> ScriptedRun {
+        Program::headless(Model::new(), 80, 24)
+            .unwrap()
+            .run_scripted(script, |_| false)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_submit_with_pending_session_creates_session() {
+        let client = test_client();
+        let mut script = vec![ScriptedEvent::Msg(Msg::ResponseClientConnect(Ok(
+            client.clone()
+        )))];
+        script.extend(type_text("hello"));
+        script.push(key(KeyCode::Enter));
+        // The send waits on the modes, which are only loaded now
+        script.push(ScriptedEvent::Msg(Msg::ResponseModesLoad(Ok(
+            ConfigAgent::default(),
+        ))));
+
+        let result = run(script).await;
+
+        assert!(matches!(
+            result.model.session_state,
+            SessionState::Creating(_)
+        ));
+        assert_eq!(
+            result.model.pending_first_message,
+            Some("hello".to_string())
+        );
+        assert!(result.model.text_input_area.is_empty());
+        assert!(result
+            .commands
+            .contains(&Cmd::AsyncLoadModes(client.clone())));
+        assert_eq!(
+            result.commands.last(),
+            Some(&Cmd::AsyncCreateSessionWithMessage(
+                client,
+                "hello".to_string(),
+                None
+            ))
+        );
+
+        // The draft was on screen before it was submitted
+        let drafted = &result.frames[result.frames.len() - 2];
+        assert!(scripted::buffer_to_string(drafted).contains("hello"));
+    }
+
+    #[tokio::test]
+    async fn test_input_taken_while_the_server_is_still_found() {
+        // Nothing connected yet, as when discovery has only just started
+        let mut script = type_text("hi");
+        script.push(key(KeyCode::Enter));
+        let result = Program::headless(Model::new(), 80, 24)
+            .unwrap()
+            .run_scripted(script.clone(), |_| false)
+            .await
+            .unwrap();
+        assert_eq!(result.model.text_input_area.content(), "hi");
+        assert!(result.model.send_queued);
+        assert!(result.last_frame_text().contains("hi"));
+        assert!(result.commands.is_empty());
+
+        // Sent once the server is found and the modes are in
+        let client = test_client();
+        script.extend([
+            ScriptedEvent::Msg(Msg::ResponseClientConnect(Ok(client.clone()))),
+            ScriptedEvent::Msg(Msg::ResponseModesLoad(Ok(ConfigAgent::default()))),
+        ]);
+        let result = run(script).await;
+        assert!(!result.model.send_queued);
+        assert!(result
+            .commands
+            .contains(&Cmd::AsyncLoadModes(client.clone())));
+        assert_eq!(
+            result.commands.last(),
+            Some(&Cmd::AsyncCreateSessionWithMessage(
+                client,
+                "hi".to_string(),
+                None
+            ))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_session_select_flow() {
+        let client = test_client();
+        let session = test_session("ses_test", "Scripted session");
+        let script = vec![
+            ScriptedEvent::Msg(Msg::ResponseClientConnect(Ok(client.clone()))),
+            ctrl('x'),
+            key(KeyCode::Char('l')),
+            ScriptedEvent::Msg(Msg::ResponseSessionsLoad(Ok(vec![session.clone()]))),
+            key(KeyCode::Down),
+            key(KeyCode::Enter),
+            ScriptedEvent::Msg(Msg::ResponseSessionInit(Ok(session.clone()))),
+        ];
+
+        let result = run(script).await;
+
+        assert!(result
+            .commands
+            .contains(&Cmd::AsyncLoadSessions(client.clone())));
+        assert!(result
+            .commands
+            .contains(&Cmd::AsyncSpawnSessionInit(client.clone())));
+        assert!(result
+            .commands
+            .contains(&Cmd::AsyncLoadSessionMessages(client, session.id.clone())));
+        assert_eq!(result.model.session(), Some(&session));
+        assert_eq!(result.model.state, AppModalState::None);
+
+        // The selector was drawn while it was open, and closed afterwards
+        assert!(result
+            .frames
+            .iter()
+            .any(|frame| scripted::buffer_to_string(frame).contains("Switch Session")));
+        assert!(!result.last_frame_text().contains("Switch Session"));
+    }
+
+    #[tokio::test]
+    async fn test_repeat_shortcut_expires_with_clock() {
+        let script = vec![
+            ScriptedEvent::Msg(Msg::ResponseClientConnect(Ok(test_client()))),
+            ctrl('x'),
+            ScriptedEvent::Advance(Duration::from_secs(2)),
+            key(KeyCode::Char('l')),
+        ];
+
+        let result = run(script).await;
+
+        // The leader timed out, so 'l' was typed instead of opening sessions
+        assert_eq!(result.model.state, AppModalState::None);
+        assert_eq!(result.model.text_input_area.content(), "l");
+    }
+
+    fn user_message(id: &str, text: &str) -> (Message, Part) {
+        let info = Message::User(Box::new(UserMessage::new(
+            id.to_string(),
+            "ses_test".to_string(),
+            user_message::Role::User,
+            UserMessageTime::new(0.0),
+        )));
+        let part = Part::Text(Box::new(TextPart::new(
+            format!("prt_{}", id),
+            "ses_test".to_string(),
+            id.to_string(),
+            text_part::Type::Text,
+            text.to_string(),
+        )));
+        (info, part)
+    }
+
+    /// Deliver a message through the event stream, as it would arrive live
+    fn message_events(id: &str, text: &str) -> Vec<ScriptedEvent> {
+        let (info, part) = user_message(id, text);
+        vec![
+            ScriptedEvent::Msg(Msg::EventReceived(SdkEvent::MessagePeriodUpdated(Box::new(
+                EventPeriodMessagePeriodUpdated::new(
+                    event_period_message_period_updated::Type::MessagePeriodUpdated,
+                    EventMessageUpdatedProperties::new(info),
+                ),
+            )))),
+            ScriptedEvent::Msg(Msg::EventReceived(SdkEvent::MessagePeriodPartPeriodUpdated(
+                Box::new(EventPeriodMessagePeriodPartPeriodUpdated::new(
+                    event_period_message_period_part_period_updated::Type::MessagePeriodPartPeriodUpdated,
+                    EventMessagePartUpdatedProperties::new(part),
+                )),
+            ))),
+        ]
+    }
+
+    #[tokio::test]
+    async fn test_inline_toggle_round_trips_keep_scrollback_in_sync() {
+        let (info, part) = user_message("msg_1", "printed inline");
+        let mut script = vec![
+            ScriptedEvent::Msg(Msg::ResponseClientConnect(Ok(test_client()))),
+            ScriptedEvent::Msg(Msg::ResponseSessionInit(Ok(test_session(
+                "ses_test", "Test",
+            )))),
+            ScriptedEvent::Msg(Msg::ResponseSessionMessagesLoad(
+                "ses_test".to_string(),
+                Ok(MessagePage {
+                    messages: vec![SessionMessages200ResponseInner::new(info, vec![part])],
+                    has_earlier: false,
+                }),
+            )),
+        ];
+        // First round trip: a message arrives while fullscreen
+        script.push(ScriptedEvent::Msg(Msg::LeaderChangeInline));
+        script.extend(message_events("msg_2", "arrived fullscreen"));
+        script.push(ScriptedEvent::Msg(Msg::LeaderChangeInline));
+        // Second round trip: another one
+        script.push(ScriptedEvent::Msg(Msg::LeaderChangeInline));
+        script.extend(message_events("msg_3", "arrived fullscreen again"));
+        script.push(ScriptedEvent::Msg(Msg::LeaderChangeInline));
+
+        let result = run(script).await;
+
+        assert!(result.model.init.inline_mode());
+        assert_eq!(
+            result
+                .commands
+                .iter()
+                .filter(|cmd| matches!(cmd, Cmd::TerminalRebootWithInline(_)))
+                .count(),
+            4
+        );
+        // Each message reached the scrollback exactly once, in order
+        assert_eq!(result.history, vec!["msg_1", "msg_2", "msg_3"]);
+        assert!(!result
+            .model
+            .message_state
+            .has_messages_needing_stdout_print());
+    }
+
+    #[tokio::test]
+    async fn test_entering_fullscreen_marks_everything_printed() {
+        let (info, part) = user_message("msg_1", "printed inline");
+        let mut script = vec![
+            ScriptedEvent::Msg(Msg::ResponseClientConnect(Ok(test_client()))),
+            ScriptedEvent::Msg(Msg::ResponseSessionInit(Ok(test_session(
+                "ses_test", "Test",
+            )))),
+            ScriptedEvent::Msg(Msg::ResponseSessionMessagesLoad(
+                "ses_test".to_string(),
+                Ok(MessagePage {
+                    messages: vec![SessionMessages200ResponseInner::new(info, vec![part])],
+                    has_earlier: false,
+                }),
+            )),
+            ScriptedEvent::Msg(Msg::LeaderChangeInline),
+        ];
+        script.extend(message_events("msg_2", "arrived fullscreen"));
+
+        let result = run(script).await;
+
+        // Fullscreen doesn't print, so only the new message is left to flush
+        assert!(!result.model.init.inline_mode());
+        assert_eq!(result.history, vec!["msg_1"]);
+        assert_eq!(
+            result.model.messages_needing_stdout_print(),
+            vec!["arrived fullscreen".to_string()]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_scripted_stops_when_until_holds() {
+        let script = vec![
+            ScriptedEvent::Msg(Msg::ResponseClientConnect(Ok(test_client()))),
+            ScriptedEvent::Msg(Msg::Quit),
+            ScriptedEvent::Msg(Msg::InitializeClient),
+        ];
+
+        let result = Program::headless(Model::new(), 80, 24)
+            .unwrap()
+            .run_scripted(script, |model| model.state == AppModalState::Quit)
+            .await
+            .unwrap();
+
+        assert_eq!(result.model.state, AppModalState::Quit);
+        assert!(!result.commands.contains(&Cmd::AsyncSpawnClientDiscovery));
+        assert!(!result.last_frame_text().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_profile_reports_a_scripted_run() {
+        use crate::app::profile::Profile;
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let profile = Profile::default();
+        let _subscriber =
+            tracing::subscriber::set_default(tracing_subscriber::registry().with(profile.layer()));
+        let mut script = vec![ScriptedEvent::Msg(Msg::ResponseClientConnect(Ok(
+            test_client(),
+        )))];
+        script.extend(type_text("hello"));
+        run(script).await;
+
+        let report = profile.report();
+        let update_row = |msg: &str| {
+            report
+                .lines()
+                .find(|line| line.starts_with(&format!("{} ", msg)))
+                .map(|line| line.split_whitespace().nth(1).unwrap().to_string())
+        };
+        // One update per key, and one recording the tasks before every frame
+        assert_eq!(update_row("TextArea").as_deref(), Some("5"), "{}", report);
+        assert!(update_row("RecordActiveTasks").is_some(), "{}", report);
+        // The first frame, then one after each step of the script
+        assert!(report.contains("(7 draws)"), "{}", report);
+    }
+
+    #[test]
+    fn test_recorded_session_replays_to_same_state() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("session.jsonl");
+        let session = test_session("ses_test", "Recorded session");
+        let mut script = vec![
+            ScriptedEvent::Msg(Msg::ResponseClientConnect(Ok(test_client()))),
+            ScriptedEvent::Msg(Msg::ResponseSessionInit(Ok(session.clone()))),
+        ];
+        script.extend(message_events("msg_recorded", "recorded message"));
+        script.extend(type_text("draft"));
+        script.push(ctrl('x'));
+        script.push(ScriptedEvent::Advance(Duration::from_secs(2)));
+        script.push(key(KeyCode::Char('!')));
+
+        let mut program = Program::headless(Model::new(), 80, 24).unwrap();
+        program.recorder = Some(MsgRecorder::create(&path).unwrap());
+        let recorded = tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(program.run_scripted(script, |_| false))
+            .unwrap();
+
+        let recording = crate::app::msg_recorder::read_recording(&path).unwrap();
+        assert!(recording
+            .iter()
+            .any(|line| matches!(line.msg, Msg::TimeoutExpired(_))));
+        assert_eq!(recording.last().unwrap().elapsed_ms, 2_000);
+
+        let replay = Program::headless(Model::new(), 80, 24)
+            .unwrap()
+            .replay(recording.clone())
+            .unwrap();
+
+        assert_eq!(replay.panic, None);
+        assert_eq!(replay.replayed, recording.len());
+        assert_eq!(replay.model.session(), Some(&session));
+        assert_eq!(replay.model.state, recorded.model.state);
+        assert_eq!(replay.model.text_input_area.content(), "draft!");
+        assert_eq!(
+            replay.model.message_containers_for_rendering().len(),
+            recorded.model.message_containers_for_rendering().len()
+        );
+        assert_eq!(replay.screen, recorded.last_frame_text());
+    }
+
+    /// Terminal that logs the draws, suspends and resumes it is asked for,
+    /// in a window of a fixed number of rows
+    struct LoggingTerminal {
+        calls: std::rc::Rc<std::cell::RefCell<Vec<String>>>,
+        rows: u16,
+    }
+
+    impl TerminalBackend for LoggingTerminal {
+        fn draw(&mut self, _model: &Model) -> Result<()> {
+            self.calls.borrow_mut().push("draw".to_string());
+            Ok(())
+        }
+
+        fn insert_history(&mut self, _model: &Model) -> Result<()> {
+            self.calls.borrow_mut().push("insert history".to_string());
+            Ok(())
+        }
+
+        fn insert_welcome_banner(&mut self, _glyphs: &Glyphs) -> Result<()> {
+            Ok(())
+        }
+
+        fn insert_banner_info(&mut self, _info: &BannerInfo) -> Result<()> {
+            Ok(())
+        }
+
+        fn reboot(
+            self: Box<Self>,
+            _old_init: &ModelInit,
+            _new_init: &ModelInit,
+            _height: u16,
+        ) -> Result<Box<dyn TerminalBackend>> {
+            Ok(self)
+        }
+
+        fn resize_inline_viewport(&mut self, _height: u16) -> Result<()> {
+            Ok(())
+        }
+
+        fn autoresize(&mut self) -> Result<()> {
+            Ok(())
+        }
+
+        fn scroll_past_height(&mut self, _line_count: u16) -> Result<()> {
+            Ok(())
+        }
+
+        fn notify(&mut self, _text: &str) -> Result<()> {
+            Ok(())
+        }
+
+        fn bell(&mut self) -> Result<()> {
+            Ok(())
+        }
+
+        fn copy_to_clipboard(&mut self, _text: &str) -> Result<()> {
+            Ok(())
+        }
+
+        fn restore(&mut self, _init: &ModelInit, _height: u16) -> std::io::Result<()> {
+            Ok(())
+        }
+
+        fn suspend(&mut self, _init: &ModelInit, height: u16) -> Result<()> {
+            self.calls.borrow_mut().push(format!("suspend {}", height));
+            Ok(())
+        }
+
+        fn resume(
+            self: Box<Self>,
+            _init: &ModelInit,
+            height: u16,
+        ) -> Result<Box<dyn TerminalBackend>> {
+            self.calls.borrow_mut().push(format!("resume {}", height));
+            Ok(self)
+        }
+
+        fn window_rows(&self) -> Option<u16> {
+            Some(self.rows)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ctrl_z_suspends() {
+        let result = run(vec![ctrl('z')]).await;
+        assert!(result.commands.contains(&Cmd::TerminalSuspend));
+    }
+
+    #[tokio::test]
+    async fn test_continuing_reinitializes_the_terminal() {
+        let calls = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+        let terminal = LoggingTerminal {
+            calls: calls.clone(),
+            // The window shrank while we were stopped
+            rows: 20,
+        };
+        let mut model = Model::new();
+        model.config.height = 16;
+        let mut program = Program::with_terminal(model, Box::new(terminal));
+
+        program.spawn_command(Cmd::TerminalSuspend).await.unwrap();
+        assert_eq!(*calls.borrow(), vec!["suspend 16"]);
+
+        // Nothing happens until the process is continued
+        program.needs_render = false;
+        program.resume_if_continued().unwrap();
+        assert_eq!(calls.borrow().len(), 1);
+        assert!(!program.needs_render);
+
+        program.resumed.raise();
+        program.resume_if_continued().unwrap();
+        assert_eq!(*calls.borrow(), vec!["suspend 16", "resume 8"]);
+        assert_eq!(program.model.config.height, 8);
+        assert!(program.needs_render);
+        assert!(program.terminal.is_some());
+
+        // The flag was cleared by handling it
+        program.resume_if_continued().unwrap();
+        assert_eq!(calls.borrow().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_unchanged_task_count_does_not_redraw() {
+        let calls = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+        let terminal = LoggingTerminal {
+            calls: calls.clone(),
+            rows: 40,
+        };
+        let mut program = Program::with_terminal(Model::new(), Box::new(terminal));
+        program.render_if_needed().await.unwrap();
+        assert_eq!(*calls.borrow(), vec!["draw"]);
+
+        for _ in 0..100 {
+            program
+                .handle_msg(Msg::RecordActiveTasks(0, Vec::new()))
+                .await
+                .unwrap();
+            program.render_if_needed().await.unwrap();
+        }
+        assert_eq!(*calls.borrow(), vec!["draw"]);
+
+        // A changed count is drawn
+        program
+            .handle_msg(Msg::RecordActiveTasks(1, Vec::new()))
+            .await
+            .unwrap();
+        program.render_if_needed().await.unwrap();
+        assert_eq!(*calls.borrow(), vec!["draw", "draw"]);
+    }
+}
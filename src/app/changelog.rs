@@ -0,0 +1,243 @@
+//! What changed between releases, shown after an upgrade
+//!
+//! `CHANGELOG.md` is built into the binary. The version last run is kept in
+//! the state directory, and the first launch of a newer one shows the
+//! entries added since, in a what's-new modal that `/changelog` reopens
+//! with every release.
+
+use crate::app::{draft_store::state_dir, error::Result, server_capabilities::ServerVersion};
+use eyre::WrapErr;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+/// The changelog, as built into the binary
+const CHANGELOG: &str = include_str!("../../CHANGELOG.md");
+
+/// The running version
+pub const CURRENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// `/changelog` shows every release instead of sending
+pub const CHANGELOG_COMMAND: &str = "/changelog";
+
+/// One release's entries
+#[derive(Debug, Clone, PartialEq)]
+pub struct Release {
+    pub version: String,
+    pub entries: Vec<String>,
+}
+
+/// The releases in `markdown`, in the order they're listed. A `## [1.2.0]`
+/// or `## 1.2.0` heading starts a release, `- ` or `* ` starts an entry,
+/// and indented lines continue the entry above.
+pub fn parse(markdown: &str) -> Vec<Release> {
+    let mut releases: Vec<Release> = Vec::new();
+    for line in markdown.lines() {
+        if let Some(heading) = line.strip_prefix("## ") {
+            let heading = heading.trim();
+            let version = match heading.strip_prefix('[') {
+                Some(rest) => rest.split(']').next().unwrap_or_default(),
+                None => heading.split_whitespace().next().unwrap_or_default(),
+            };
+            releases.push(Release {
+                version: version.to_string(),
+                entries: Vec::new(),
+            });
+            continue;
+        }
+        let Some(release) = releases.last_mut() else {
+            continue;
+        };
+        let trimmed = line.trim();
+        if let Some(entry) = trimmed
+            .strip_prefix("- ")
+            .or_else(|| trimmed.strip_prefix("* "))
+            .filter(|_| !line.starts_with(char::is_whitespace))
+        {
+            release.entries.push(entry.trim().to_string());
+        } else if line.starts_with(char::is_whitespace) && !trimmed.is_empty() {
+            if let Some(entry) = release.entries.last_mut() {
+                entry.push(' ');
+                entry.push_str(trimmed);
+            }
+        }
+    }
+    releases
+}
+
+/// Every release built into the binary
+pub fn releases() -> Vec<Release> {
+    parse(CHANGELOG)
+}
+
+/// The releases to show once `current` first runs, after `last_seen` last
+/// did: those after it, or only the latest when nothing was recorded. The
+/// ones after `current`, like an unreleased section, are left out.
+pub fn whats_new(releases: &[Release], last_seen: Option<&str>, current: &str) -> Vec<Release> {
+    let Some(current) = ServerVersion::parse(current) else {
+        return Vec::new();
+    };
+    let mut released: Vec<(ServerVersion, &Release)> = releases
+        .iter()
+        .filter_map(|release| Some((ServerVersion::parse(&release.version)?, release)))
+        .filter(|(version, _)| *version <= current)
+        .collect();
+    released.sort_by(|(a, _), (b, _)| b.cmp(a));
+    match last_seen.and_then(ServerVersion::parse) {
+        Some(last_seen) => released
+            .into_iter()
+            .take_while(|(version, _)| *version > last_seen)
+            .map(|(_, release)| release.clone())
+            .collect(),
+        None => released
+            .into_iter()
+            .take(1)
+            .map(|(_, release)| release.clone())
+            .collect(),
+    }
+}
+
+/// The lines of the modal for `releases`
+pub fn changelog_lines(releases: &[Release]) -> Vec<String> {
+    let mut lines = Vec::new();
+    for release in releases {
+        if !lines.is_empty() {
+            lines.push(String::new());
+        }
+        lines.push(release.version.clone());
+        lines.extend(release.entries.iter().map(|entry| format!("  - {}", entry)));
+    }
+    lines
+}
+
+/// Whether `input` is the `/changelog` command
+pub fn is_changelog_command(input: &str) -> bool {
+    input.trim() == CHANGELOG_COMMAND
+}
+
+/// Where the version last run is kept,
+/// `~/.local/state/opencoders/last_seen_version`
+pub fn default_seen_version_path() -> PathBuf {
+    state_dir().join("opencoders").join("last_seen_version")
+}
+
+/// The version last run, None before the first
+pub async fn load_seen_version(path: &Path) -> Result<Option<String>> {
+    match fs::read_to_string(path).await {
+        Ok(version) => Ok(Some(version.trim().to_string()).filter(|v| !v.is_empty())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e).wrap_err_with(|| format!("Failed to read {}", path.display())),
+    }
+}
+
+/// Record `version` as the one last run
+pub async fn save_seen_version(path: &Path, version: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .await
+            .wrap_err("Failed to create the state directory")?;
+    }
+    fs::write(path, format!("{}\n", version))
+        .await
+        .wrap_err_with(|| format!("Failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHANGELOG_FIXTURE: &str = "# Changelog
+
+Some words about the file.
+
+## [Unreleased]
+
+- Not out yet
+
+## [0.3.0] - 2026-09-01
+
+- Third
+- A longer entry
+  that wraps
+
+## 0.2.1
+
+* Fix
+## [0.2.0]
+- Second
+## [0.1.0]
+- First
+";
+
+    fn versions(releases: &[Release]) -> Vec<&str> {
+        releases
+            .iter()
+            .map(|release| release.version.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_releases_and_entries() {
+        let releases = parse(CHANGELOG_FIXTURE);
+        assert_eq!(
+            versions(&releases),
+            vec!["Unreleased", "0.3.0", "0.2.1", "0.2.0", "0.1.0"]
+        );
+        assert_eq!(
+            releases[1].entries,
+            vec!["Third", "A longer entry that wraps"]
+        );
+        assert_eq!(releases[2].entries, vec!["Fix"]);
+    }
+
+    #[test]
+    fn test_whats_new_since_the_last_seen_version() {
+        let releases = parse(CHANGELOG_FIXTURE);
+        let since = |last_seen| whats_new(&releases, Some(last_seen), "0.3.0");
+        assert_eq!(versions(&since("0.2.0")), vec!["0.3.0", "0.2.1"]);
+        assert_eq!(versions(&since("0.1.0")), vec!["0.3.0", "0.2.1", "0.2.0"]);
+        // Nothing new, or an older version run after a newer one
+        assert!(since("0.3.0").is_empty());
+        assert!(since("0.4.0").is_empty());
+        // Releases after the running one aren't out for it
+        assert_eq!(
+            versions(&whats_new(&releases, Some("0.1.0"), "0.2.0")),
+            vec!["0.2.0"]
+        );
+    }
+
+    #[test]
+    fn test_first_run_shows_only_the_latest_release() {
+        let releases = parse(CHANGELOG_FIXTURE);
+        assert_eq!(
+            versions(&whats_new(&releases, None, "0.3.0")),
+            vec!["0.3.0"]
+        );
+        // A version that can't be read counts as none
+        assert_eq!(
+            versions(&whats_new(&releases, Some("dev"), "0.2.1")),
+            vec!["0.2.1"]
+        );
+    }
+
+    #[test]
+    fn test_built_in_changelog_has_the_running_version() {
+        let releases = releases();
+        assert_eq!(
+            versions(&whats_new(&releases, None, CURRENT_VERSION)),
+            vec![CURRENT_VERSION]
+        );
+        assert!(releases.iter().all(|release| !release.entries.is_empty()));
+    }
+
+    #[tokio::test]
+    async fn test_seen_version_round_trip() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("opencoders").join("last_seen_version");
+        assert_eq!(load_seen_version(&path).await.unwrap(), None);
+        save_seen_version(&path, "0.2.0").await.unwrap();
+        assert_eq!(
+            load_seen_version(&path).await.unwrap().as_deref(),
+            Some("0.2.0")
+        );
+    }
+}
@@ -0,0 +1,154 @@
+//! Command line arguments for the OpenCode TUI
+//!
+//! Arguments are parsed by hand to keep the dependency footprint small;
+//! each flag maps onto a field of the user config before the program starts.
+
+use crate::app::{error::Result, tea_model::UserConfig};
+use std::path::PathBuf;
+
+/// Parsed command line arguments
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CliArgs {
+    /// Always start on a fresh session instead of resuming the last one
+    pub new_session: bool,
+    /// Skip the startup banner and server info
+    pub no_banner: bool,
+    /// Replay a message recording headlessly instead of starting the TUI
+    pub replay: Option<PathBuf>,
+    /// Send this as the first message of a new session and print the reply
+    /// instead of starting the TUI
+    pub prompt: Option<String>,
+    /// Time the update and render pipeline and print a report on exit
+    pub profile: bool,
+    /// Print diagnostics instead of starting the TUI
+    pub doctor: Option<DoctorArgs>,
+}
+
+/// Arguments of `doctor`
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DoctorArgs {
+    /// Also write a bug report bundle, see `bugreport`
+    pub bundle: bool,
+    /// Keep message content in the bundle
+    pub include_content: bool,
+}
+
+impl CliArgs {
+    /// Parse arguments from the current process
+    pub fn from_env() -> Result<Self> {
+        Self::parse(std::env::args().skip(1))
+    }
+
+    /// Parse arguments, excluding the program name
+    pub fn parse<I, S>(args: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut cli = CliArgs::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_ref() {
+                "--new" | "-n" => cli.new_session = true,
+                "--no-banner" => cli.no_banner = true,
+                "--profile" => cli.profile = true,
+                "--replay" => match args.next() {
+                    Some(path) => cli.replay = Some(PathBuf::from(path.as_ref())),
+                    None => eyre::bail!("--replay needs a recording file"),
+                },
+                "--prompt" | "-p" => match args.next() {
+                    Some(prompt) => cli.prompt = Some(prompt.as_ref().to_string()),
+                    None => eyre::bail!("--prompt needs the text to send"),
+                },
+                "doctor" => cli.doctor = Some(DoctorArgs::default()),
+                "--bundle" => match cli.doctor.as_mut() {
+                    Some(doctor) => doctor.bundle = true,
+                    None => eyre::bail!("--bundle is an option of doctor"),
+                },
+                "--include-content" => match cli.doctor.as_mut() {
+                    Some(doctor) => doctor.include_content = true,
+                    None => eyre::bail!("--include-content is an option of doctor"),
+                },
+                other => eyre::bail!("Unknown argument: {}", other),
+            }
+        }
+        Ok(cli)
+    }
+
+    /// Apply the arguments on top of the user config
+    pub fn apply(&self, config: &mut UserConfig) {
+        if self.new_session {
+            config.session_resume = false;
+        }
+        if self.no_banner {
+            config.ui_show_banner = false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::tea_model::Model;
+
+    #[test]
+    fn test_parse_new_flag() {
+        assert!(!CliArgs::parse(Vec::<String>::new()).unwrap().new_session);
+        assert!(CliArgs::parse(["--new"]).unwrap().new_session);
+        assert!(CliArgs::parse(["-n"]).unwrap().new_session);
+    }
+
+    #[test]
+    fn test_no_banner_flag_disables_banner() {
+        let cli = CliArgs::parse(["--no-banner"]).unwrap();
+        let mut config = Model::new().config;
+        cli.apply(&mut config);
+        assert!(!config.ui_show_banner);
+        assert!(config.session_resume);
+    }
+
+    #[test]
+    fn test_parse_replay_path() {
+        let cli = CliArgs::parse(["--replay", "session.jsonl"]).unwrap();
+        assert_eq!(cli.replay, Some(PathBuf::from("session.jsonl")));
+        assert!(CliArgs::parse(["--replay"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_prompt_text() {
+        let cli = CliArgs::parse(["--no-banner", "-p", "explain main.rs"]).unwrap();
+        assert_eq!(cli.prompt.as_deref(), Some("explain main.rs"));
+        assert!(cli.no_banner);
+        assert!(CliArgs::parse(["--prompt"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_profile_flag() {
+        assert!(!CliArgs::parse(["--new"]).unwrap().profile);
+        assert!(CliArgs::parse(["--new", "--profile"]).unwrap().profile);
+    }
+
+    #[test]
+    fn test_parse_doctor_options() {
+        assert_eq!(CliArgs::parse(["--new"]).unwrap().doctor, None);
+        assert_eq!(
+            CliArgs::parse(["doctor"]).unwrap().doctor,
+            Some(DoctorArgs::default())
+        );
+        assert_eq!(
+            CliArgs::parse(["doctor", "--bundle", "--include-content"])
+                .unwrap()
+                .doctor,
+            Some(DoctorArgs {
+                bundle: true,
+                include_content: true,
+            })
+        );
+        assert!(CliArgs::parse(["--bundle"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_unknown_argument() {
+        assert!(CliArgs::parse(["--bogus"]).is_err());
+    }
+}
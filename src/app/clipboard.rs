@@ -0,0 +1,47 @@
+//! Copying to the system clipboard through the terminal
+//!
+//! OSC 52 asks the terminal itself to set the clipboard, which works over
+//! SSH and inside tmux (with `set-clipboard on`) where no clipboard tool on
+//! this machine could reach the user's. Terminals that don't support it
+//! ignore the sequence.
+
+/// Escape sequence that puts `text` on the clipboard
+pub fn clipboard_sequence(text: &str) -> String {
+    format!("\x1b]52;c;{}\x07", encode_base64(text.as_bytes()))
+}
+
+/// Standard base64 encoding, with padding
+fn encode_base64(input: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut output = String::with_capacity(input.len().div_ceil(3) * 4);
+    for chunk in input.chunks(3) {
+        let buffer = chunk.iter().enumerate().fold(0u32, |buffer, (i, byte)| {
+            buffer | (*byte as u32) << (16 - 8 * i)
+        });
+        for i in 0..4 {
+            if i <= chunk.len() {
+                output.push(ALPHABET[(buffer >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                output.push('=');
+            }
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clipboard_sequence_encodes_the_text() {
+        assert_eq!(encode_base64(b""), "");
+        assert_eq!(encode_base64(b"f"), "Zg==");
+        assert_eq!(encode_base64(b"fo"), "Zm8=");
+        assert_eq!(encode_base64(b"foo"), "Zm9v");
+        assert_eq!(encode_base64("héllo\n".as_bytes()), "aMOpbGxvCg==");
+        // Control characters are encoded, so they can't end the sequence
+        assert_eq!(clipboard_sequence("a\x07b"), "\x1b]52;c;YQdi\x07");
+    }
+}
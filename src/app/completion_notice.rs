@@ -0,0 +1,59 @@
+//! Desktop notification when a long response completes
+//!
+//! A response can take minutes, long enough to switch to another window and
+//! miss it finishing. When the current session goes idle after a response
+//! that ran past the threshold, and the terminal has lost focus, the terminal
+//! is asked to show a desktop notification with OSC 9 (iTerm2, WezTerm, kitty
+//! and others show one) followed by a bell, which most other terminals turn
+//! into an urgency hint.
+
+use std::time::Duration;
+
+/// Responses shorter than this finish while the user is still looking
+pub const DEFAULT_NOTIFY_AFTER_SECS: u64 = 20;
+
+/// Whether a response that ran for `elapsed` is worth a notification
+pub fn should_notify(enabled: bool, threshold: Duration, focused: bool, elapsed: Duration) -> bool {
+    enabled && !focused && elapsed >= threshold
+}
+
+/// Notification text for a response finishing in the given session
+pub fn notice_text(session_title: &str) -> String {
+    format!("OpenCode: response ready in {}", session_title)
+}
+
+/// Escape sequence that shows `text` as a desktop notification, then rings
+/// the bell. Control characters are dropped so the text can't end the
+/// sequence early.
+pub fn notification_sequence(text: &str) -> String {
+    let text: String = text.chars().filter(|c| !c.is_control()).collect();
+    format!("\x1b]9;{}\x07\x07", text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_should_notify() {
+        let threshold = Duration::from_secs(DEFAULT_NOTIFY_AFTER_SECS);
+        let slow = Duration::from_secs(45);
+        let fast = Duration::from_secs(5);
+
+        assert!(should_notify(true, threshold, false, slow));
+        assert!(should_notify(true, threshold, false, threshold));
+        // Still looking at the terminal
+        assert!(!should_notify(true, threshold, true, slow));
+        // Quick enough not to have looked away
+        assert!(!should_notify(true, threshold, false, fast));
+        assert!(!should_notify(false, threshold, false, slow));
+    }
+
+    #[test]
+    fn test_notification_sequence_strips_control_characters() {
+        assert_eq!(
+            notification_sequence(&notice_text("Fix\x07 the\x1b]9;build")),
+            "\x1b]9;OpenCode: response ready in Fix the]9;build\x07\x07"
+        );
+    }
+}
@@ -0,0 +1,223 @@
+//! Per-session draft persistence
+//!
+//! Unsent prompt text and attachments are kept in a small JSON state file,
+//! keyed by session id, so quitting or crashing doesn't lose them. All file IO
+//! here is async and only ever runs from Cmds, so it never blocks the loop.
+
+use crate::{app::error::Result, sdk::AttachedFile};
+use eyre::WrapErr;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use tokio::sync::Mutex;
+
+/// Key for the draft of a session that hasn't been created yet
+pub const NEW_SESSION_DRAFT_KEY: &str = "new";
+
+/// How long input has to settle before the draft is written
+pub const DRAFT_SAVE_DEBOUNCE_MS: u64 = 2000;
+
+/// Serializes read-modify-write cycles on the drafts file
+static WRITE_LOCK: Mutex<()> = Mutex::const_new(());
+
+/// An unsent prompt for one session
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Draft {
+    pub text: String,
+    #[serde(default)]
+    pub attached_files: Vec<AttachedFile>,
+}
+
+impl Draft {
+    pub fn is_empty(&self) -> bool {
+        self.text.trim().is_empty() && self.attached_files.is_empty()
+    }
+}
+
+/// Drafts file on disk, as a map of session id to draft
+#[derive(Debug, Clone, PartialEq)]
+pub struct DraftStore {
+    path: PathBuf,
+}
+
+impl DraftStore {
+    /// Store at the default location, `~/.local/state/opencoders/drafts.json`
+    pub fn new() -> Self {
+        Self::with_path(default_drafts_path())
+    }
+
+    pub fn with_path(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Load the draft for a session, if one was saved
+    pub async fn load(&self, key: &str) -> Result<Option<Draft>> {
+        let mut drafts = self.read_drafts().await?;
+        Ok(drafts.remove(key))
+    }
+
+    /// Save the draft for a session, removing it instead if it's empty
+    pub async fn save(&self, key: &str, draft: &Draft) -> Result<()> {
+        let _guard = WRITE_LOCK.lock().await;
+        let mut drafts = self.read_drafts().await?;
+        if draft.is_empty() {
+            if drafts.remove(key).is_none() {
+                return Ok(());
+            }
+        } else {
+            drafts.insert(key.to_string(), draft.clone());
+        }
+        self.write_drafts(&drafts).await
+    }
+
+    /// Forget the draft for a session
+    pub async fn remove(&self, key: &str) -> Result<()> {
+        self.save(key, &Draft::default()).await
+    }
+
+    /// Read every draft. A missing file is empty; a corrupt one is moved
+    /// aside so the next save starts clean without destroying it.
+    async fn read_drafts(&self) -> Result<HashMap<String, Draft>> {
+        let content = match fs::read_to_string(&self.path).await {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(HashMap::new()),
+            Err(e) => return Err(e).wrap_err("Failed to read drafts file"),
+        };
+
+        match serde_json::from_str(&content) {
+            Ok(drafts) => Ok(drafts),
+            Err(e) => {
+                let corrupt_path = self.path.with_extension("json.corrupt");
+                tracing::warn!(
+                    "Drafts file {} is corrupt ({}), moving it to {}",
+                    self.path.display(),
+                    e,
+                    corrupt_path.display()
+                );
+                fs::rename(&self.path, &corrupt_path)
+                    .await
+                    .wrap_err("Failed to move corrupt drafts file aside")?;
+                Ok(HashMap::new())
+            }
+        }
+    }
+
+    /// Write every draft, via a temp file so a crash mid-write can't corrupt it
+    async fn write_drafts(&self, drafts: &HashMap<String, Draft>) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)
+                .await
+                .wrap_err("Failed to create drafts directory")?;
+        }
+        let content = serde_json::to_string_pretty(drafts)?;
+        let tmp_path = self.path.with_extension("json.tmp");
+        fs::write(&tmp_path, content)
+            .await
+            .wrap_err("Failed to write drafts file")?;
+        fs::rename(&tmp_path, &self.path)
+            .await
+            .wrap_err("Failed to replace drafts file")?;
+        Ok(())
+    }
+}
+
+impl Default for DraftStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// `$XDG_STATE_HOME`, or `~/.local/state` without it
+pub fn state_dir() -> PathBuf {
+    if let Ok(dir) = std::env::var("XDG_STATE_HOME") {
+        PathBuf::from(dir)
+    } else if let Some(home) = dirs::home_dir() {
+        home.join(".local").join("state")
+    } else {
+        PathBuf::from(".")
+    }
+}
+
+fn default_drafts_path() -> PathBuf {
+    state_dir().join("opencoders").join("drafts.json")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opencode_sdk::models::File;
+    use tempfile::TempDir;
+
+    fn test_store() -> (DraftStore, TempDir) {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let store = DraftStore::with_path(temp_dir.path().join("state").join("drafts.json"));
+        (store, temp_dir)
+    }
+
+    fn draft(text: &str) -> Draft {
+        Draft {
+            text: text.to_string(),
+            attached_files: vec![AttachedFile {
+                file: File {
+                    path: "src/main.rs".to_string(),
+                    ..Default::default()
+                },
+                part_id: "prt_test".to_string(),
+                display_name: "main.rs".to_string(),
+            }],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_missing_file_has_no_drafts() {
+        let (store, _temp_dir) = test_store();
+        assert_eq!(store.load("ses_a").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_save_and_restore_round_trip() {
+        let (store, _temp_dir) = test_store();
+
+        store.save("ses_a", &draft("first")).await.unwrap();
+        store.save("ses_b", &draft("second")).await.unwrap();
+        store.save("ses_a", &draft("first, edited")).await.unwrap();
+
+        assert_eq!(
+            store.load("ses_a").await.unwrap(),
+            Some(draft("first, edited"))
+        );
+        assert_eq!(store.load("ses_b").await.unwrap(), Some(draft("second")));
+    }
+
+    #[tokio::test]
+    async fn test_empty_draft_removes_entry() {
+        let (store, _temp_dir) = test_store();
+
+        store.save("ses_a", &draft("sent soon")).await.unwrap();
+        store.remove("ses_a").await.unwrap();
+        assert_eq!(store.load("ses_a").await.unwrap(), None);
+
+        // Saving an empty draft is the same as removing it
+        store.save("ses_b", &draft("typed")).await.unwrap();
+        store.save("ses_b", &Draft::default()).await.unwrap();
+        assert_eq!(store.load("ses_b").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_corrupt_file_is_moved_aside() {
+        let (store, _temp_dir) = test_store();
+        std::fs::create_dir_all(store.path().parent().unwrap()).unwrap();
+        std::fs::write(store.path(), "{ not json").unwrap();
+
+        assert_eq!(store.load("ses_a").await.unwrap(), None);
+        assert!(store.path().with_extension("json.corrupt").exists());
+
+        store.save("ses_a", &draft("fresh")).await.unwrap();
+        assert_eq!(store.load("ses_a").await.unwrap(), Some(draft("fresh")));
+    }
+}
@@ -5,7 +5,7 @@
 
 use crate::sdk::OpenCodeError;
 use color_eyre::{Section, SectionExt};
-use eyre::{Report, WrapErr};
+use eyre::Report;
 
 /// Result type alias for application operations
 pub type Result<T> = eyre::Result<T>;
@@ -1,16 +1,165 @@
-use crate::app::event_msg::Msg;
-use std::collections::HashMap;
+use crate::{
+    app::{
+        event_msg::{Cmd, Msg},
+        log_throttle::debug_throttled,
+        profile::PROFILE_TARGET,
+    },
+    sdk::OpenCodeClient,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
 use std::future::Future;
+use std::mem::Discriminant;
+use std::time::Duration;
 use tokio::sync::mpsc;
 use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
 
 pub type TaskId = u64;
+pub type Generation = u64;
+pub type ChainId = u64;
+
+/// What a keyed task is doing. Only one task of each kind runs at a time:
+/// spawning another one supersedes the previous, whatever its payload.
+/// Messages are the exception, loading in one slot per session, so going
+/// back to a session while its load runs doesn't cancel it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum TaskKind {
+    InitSession,
+    LoadSessions,
+    LoadModes,
+    LoadProviders,
+    LoadMessages { session_id: String },
+    LoadFileStatus,
+    FindFiles { query: String },
+    FindDirectories { query: String },
+    FindSymbols { query: String },
+    LoadGitInfo,
+    LoadDraft { key: String },
+}
+
+impl TaskKind {
+    /// Short description for the status bar
+    pub fn label(&self) -> &'static str {
+        match self {
+            TaskKind::InitSession => "Opening session...",
+            TaskKind::LoadSessions => "Loading sessions...",
+            TaskKind::LoadModes => "Loading modes...",
+            TaskKind::LoadProviders => "Loading providers...",
+            TaskKind::LoadMessages { .. } => "Loading messages...",
+            TaskKind::LoadFileStatus => "Loading file status...",
+            TaskKind::FindFiles { .. } => "Finding files...",
+            TaskKind::FindDirectories { .. } => "Finding directories...",
+            TaskKind::FindSymbols { .. } => "Finding symbols...",
+            TaskKind::LoadGitInfo => "Loading git info...",
+            TaskKind::LoadDraft { .. } => "Loading draft...",
+        }
+    }
+
+    fn slot(&self) -> TaskSlot {
+        match self {
+            TaskKind::LoadMessages { session_id } => TaskSlot::Session(session_id.clone()),
+            kind => TaskSlot::Kind(std::mem::discriminant(kind)),
+        }
+    }
+}
+
+/// Keyed tasks in the same slot supersede each other
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum TaskSlot {
+    Kind(Discriminant<TaskKind>),
+    Session(String),
+}
+
+/// Identifies one spawn of a keyed task. Generations only ever increase, so
+/// a result with an older generation than the latest of its kind is stale.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TaskKey {
+    pub kind: TaskKind,
+    pub generation: Generation,
+}
+
+/// Latest generation seen per task kind, for discarding stale results
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TaskGenerations {
+    latest: HashMap<TaskSlot, Generation>,
+}
+
+impl TaskGenerations {
+    /// Record a result's key, returning false if a newer one was already seen
+    pub fn accept(&mut self, key: &TaskKey) -> bool {
+        let latest = self.latest.entry(key.kind.slot()).or_insert(0);
+        if key.generation < *latest {
+            return false;
+        }
+        *latest = key.generation;
+        true
+    }
+}
+
+/// The part of the UI a task was started for. Closing it cancels the
+/// task, and a result that arrives anyway is never applied.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum TaskContext {
+    /// The session selector, while it's open
+    SessionSelector,
+    /// A session, while it's the current one
+    Session { session_id: String },
+    /// A new session being created, until it's given up on
+    SessionCreation,
+    /// The project of the server at `base_url`, while it's the active one
+    Project { base_url: String },
+}
+
+impl TaskContext {
+    /// The project `client` is connected to
+    pub fn project(client: &OpenCodeClient) -> Self {
+        Self::Project {
+            base_url: client.base_url().to_string(),
+        }
+    }
+}
+
+struct KeyedTask {
+    task_id: TaskId,
+    key: TaskKey,
+}
+
+/// A `Cmd::Sequence` part way through: the steps still to run, and how many
+/// tasks of the current step haven't reported back yet
+struct Chain {
+    rest: VecDeque<Cmd>,
+    pending: usize,
+    error: Option<String>,
+}
+
+/// What happens to a sequence once the responses of its current step have
+/// been handled
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChainProgress {
+    /// The step succeeded, so the next one can run
+    Continue(ChainId),
+    /// The step failed, and the steps after it were dropped
+    Aborted { error: String, skipped: usize },
+}
 
 pub struct AsyncTaskManager {
     handles: HashMap<TaskId, JoinHandle<()>>,
-    receiver: mpsc::UnboundedReceiver<Msg>,
-    sender: mpsc::UnboundedSender<Msg>,
+    keyed: HashMap<TaskSlot, KeyedTask>,
+    receiver: mpsc::UnboundedReceiver<(TaskId, Msg)>,
+    sender: mpsc::UnboundedSender<(TaskId, Msg)>,
     next_id: TaskId,
+    next_generation: Generation,
+    chains: HashMap<ChainId, Chain>,
+    chained_tasks: HashMap<TaskId, ChainId>,
+    // Chain whose step is being started, which new tasks belong to
+    current_chain: Option<ChainId>,
+    settled_chains: Vec<ChainId>,
+    next_chain: ChainId,
+    // Tasks started for a context, with the token that stops them
+    contexts: HashMap<TaskId, (TaskContext, CancellationToken)>,
+    // Tasks whose context closed, whose results are replaced when polled
+    cancelled: HashMap<TaskId, TaskContext>,
 }
 
 impl AsyncTaskManager {
@@ -19,12 +168,116 @@ impl AsyncTaskManager {
 
         Self {
             handles: HashMap::new(),
+            keyed: HashMap::new(),
             receiver,
             sender,
             next_id: 1,
+            next_generation: 1,
+            chains: HashMap::new(),
+            chained_tasks: HashMap::new(),
+            current_chain: None,
+            settled_chains: Vec::new(),
+            next_chain: 1,
+            contexts: HashMap::new(),
+            cancelled: HashMap::new(),
         }
     }
 
+    /// Spawn a task that supersedes any running task of the same kind. Its
+    /// result is delivered as `Msg::TaskResult` tagged with the task's key.
+    pub fn spawn_keyed<F>(&mut self, kind: TaskKind, future: F) -> TaskId
+    where
+        F: Future<Output = Msg> + Send + 'static,
+    {
+        if let Some(previous) = self.keyed.remove(&kind.slot()) {
+            tracing::debug!("Superseding task {:?}", previous.key);
+            if let Some(handle) = self.handles.remove(&previous.task_id) {
+                handle.abort();
+            }
+            self.contexts.remove(&previous.task_id);
+            self.drop_chain_of(previous.task_id);
+        }
+
+        let key = TaskKey {
+            kind,
+            generation: self.next_generation,
+        };
+        self.next_generation += 1;
+
+        let result_key = key.clone();
+        let task_id =
+            self.spawn_task(async move { Msg::TaskResult(result_key, Box::new(future.await)) });
+        self.keyed
+            .insert(key.kind.slot(), KeyedTask { task_id, key });
+        task_id
+    }
+
+    /// Spawn a keyed task that is cancelled when `context` closes
+    pub fn spawn_keyed_in<F>(&mut self, context: TaskContext, kind: TaskKind, future: F) -> TaskId
+    where
+        F: Future<Output = Msg> + Send + 'static,
+    {
+        let token = CancellationToken::new();
+        let future = cancellable(context.clone(), token.clone(), future);
+        let task_id = self.spawn_keyed(kind, future);
+        self.contexts.insert(task_id, (context, token));
+        task_id
+    }
+
+    /// Spawn a task that is cancelled when `context` closes
+    pub fn spawn_in<F>(&mut self, context: TaskContext, future: F) -> TaskId
+    where
+        F: Future<Output = Msg> + Send + 'static,
+    {
+        let token = CancellationToken::new();
+        let future = cancellable(context.clone(), token.clone(), future);
+        let task_id = self.spawn_task(future);
+        self.contexts.insert(task_id, (context, token));
+        task_id
+    }
+
+    /// Cancel every task started for `context`, returning how many there
+    /// were. Each reports back with `Msg::TaskCancelled` instead of its
+    /// result, even one that had already finished.
+    pub fn cancel_context(&mut self, context: &TaskContext) -> usize {
+        let task_ids: Vec<TaskId> = self
+            .contexts
+            .iter()
+            .filter(|(_, (task_context, _))| task_context == context)
+            .map(|(task_id, _)| *task_id)
+            .collect();
+        for task_id in &task_ids {
+            if let Some((context, token)) = self.contexts.remove(task_id) {
+                tracing::debug!("Cancelling task {} with its context {:?}", task_id, context);
+                token.cancel();
+                self.keyed.retain(|_, task| task.task_id != *task_id);
+                self.drop_chain_of(*task_id);
+                self.cancelled.insert(*task_id, context);
+            }
+        }
+        task_ids.len()
+    }
+
+    /// Kinds of the keyed tasks still running
+    pub fn active_kinds(&self) -> Vec<TaskKind> {
+        self.keyed
+            .values()
+            .filter(|task| {
+                self.handles
+                    .get(&task.task_id)
+                    .is_some_and(|handle| !handle.is_finished())
+            })
+            .map(|task| task.key.kind.clone())
+            .collect()
+    }
+
+    /// Whether a keyed result is from the latest spawn of its kind
+    fn is_current(&self, key: &TaskKey) -> bool {
+        self.keyed
+            .get(&key.kind.slot())
+            .is_none_or(|task| task.key.generation <= key.generation)
+    }
+
     pub fn spawn_task<F>(&mut self, future: F) -> TaskId
     where
         F: Future<Output = Msg> + Send + 'static,
@@ -32,17 +285,23 @@ impl AsyncTaskManager {
         let task_id = self.next_id;
         self.next_id += 1;
 
-        tracing::debug!("Spawning async task with ID: {}", task_id);
+        debug_throttled!("Spawning async task with ID: {}", task_id);
 
         let sender = self.sender.clone();
         let handle = tokio::spawn(async move {
             let result = future.await;
-            let _ = sender.send(result);
+            let _ = sender.send((task_id, result));
         });
 
         self.handles.insert(task_id, handle);
+        if let Some(chain_id) = self.current_chain {
+            if let Some(chain) = self.chains.get_mut(&chain_id) {
+                chain.pending += 1;
+                self.chained_tasks.insert(task_id, chain_id);
+            }
+        }
         #[cfg(debug_assertions)]
-        tracing::debug!("Active tasks: {}", self.handles.len());
+        debug_throttled!("Active tasks: {}", self.handles.len());
         task_id
     }
 
@@ -50,6 +309,8 @@ impl AsyncTaskManager {
         if let Some(handle) = self.handles.remove(&task_id) {
             tracing::debug!("Cancelling task with ID: {}", task_id);
             handle.abort();
+            self.contexts.remove(&task_id);
+            self.drop_chain_of(task_id);
             true
         } else {
             tracing::warn!("Attempted to cancel non-existent task: {}", task_id);
@@ -57,21 +318,146 @@ impl AsyncTaskManager {
         }
     }
 
+    /// Cancel the running task of `kind`'s slot, whatever its payload
+    pub fn cancel_keyed(&mut self, kind: &TaskKind) -> bool {
+        match self.keyed.remove(&kind.slot()) {
+            Some(task) => self.cancel_task(task.task_id),
+            None => false,
+        }
+    }
+
+    /// Wait for a task to finish, giving up after `timeout`. Returns true if it finished.
+    pub async fn join_task(&mut self, task_id: TaskId, timeout: Duration) -> bool {
+        match self.handles.remove(&task_id) {
+            Some(handle) => tokio::time::timeout(timeout, handle).await.is_ok(),
+            None => true,
+        }
+    }
+
     pub fn poll_messages(&mut self) -> Vec<Msg> {
         let mut messages = Vec::new();
-        while let Ok(msg) = self.receiver.try_recv() {
+        while let Ok((task_id, msg)) = self.receiver.try_recv() {
+            // The context closed while the result was on its way
+            if let Some(context) = self.cancelled.remove(&task_id) {
+                messages.push(Msg::TaskCancelled(context));
+                continue;
+            }
+            self.contexts.remove(&task_id);
+            // A superseded task can finish before it's aborted, so its result
+            // may already be queued
+            if let Msg::TaskResult(key, _) = &msg {
+                if !self.is_current(key) {
+                    tracing::debug!("Dropping stale result for {:?}", key);
+                    self.drop_chain_of(task_id);
+                    continue;
+                }
+                self.keyed.remove(&key.kind.slot());
+            }
+            self.settle_chained_task(task_id, &msg);
             messages.push(msg);
         }
+        tracing::debug!(name: "task_poll", target: PROFILE_TARGET, messages = messages.len());
         messages
     }
 
+    /// Register the steps of a `Cmd::Sequence`, to be taken one at a time
+    /// with `next_chain_step`
+    pub fn start_chain(&mut self, steps: Vec<Cmd>) -> ChainId {
+        let chain_id = self.next_chain;
+        self.next_chain += 1;
+        self.chains.insert(
+            chain_id,
+            Chain {
+                rest: steps.into(),
+                pending: 0,
+                error: None,
+            },
+        );
+        chain_id
+    }
+
+    /// Take the next step of a sequence to run. Tasks spawned until
+    /// `end_chain_step` belong to it, and the step after waits for them.
+    pub fn next_chain_step(&mut self, chain_id: ChainId) -> Option<Cmd> {
+        let chain = self.chains.get_mut(&chain_id)?;
+        match chain.rest.pop_front() {
+            Some(step) => {
+                self.current_chain = Some(chain_id);
+                Some(step)
+            }
+            None => {
+                self.chains.remove(&chain_id);
+                None
+            }
+        }
+    }
+
+    /// Finish starting a step, returning whether it is waiting on tasks.
+    /// A step that spawned none, like a terminal command, is already done.
+    pub fn end_chain_step(&mut self) -> bool {
+        self.current_chain
+            .take()
+            .and_then(|chain_id| self.chains.get(&chain_id))
+            .is_some_and(|chain| chain.pending > 0)
+    }
+
+    /// Sequences whose current step has reported back since the last call.
+    /// Call this once the polled messages have been handled, so each step
+    /// only starts after the previous step's response was applied.
+    pub fn take_chain_progress(&mut self) -> Vec<ChainProgress> {
+        let settled = std::mem::take(&mut self.settled_chains);
+        settled
+            .into_iter()
+            .filter_map(|chain_id| {
+                let chain = self.chains.get_mut(&chain_id)?;
+                match chain.error.take() {
+                    Some(error) => {
+                        let skipped = chain.rest.len();
+                        self.chains.remove(&chain_id);
+                        Some(ChainProgress::Aborted { error, skipped })
+                    }
+                    None => Some(ChainProgress::Continue(chain_id)),
+                }
+            })
+            .collect()
+    }
+
+    fn settle_chained_task(&mut self, task_id: TaskId, msg: &Msg) {
+        let Some(chain_id) = self.chained_tasks.remove(&task_id) else {
+            return;
+        };
+        let Some(chain) = self.chains.get_mut(&chain_id) else {
+            return;
+        };
+        chain.pending = chain.pending.saturating_sub(1);
+        if chain.error.is_none() {
+            chain.error = msg.error();
+        }
+        if chain.pending == 0 {
+            self.settled_chains.push(chain_id);
+        }
+    }
+
+    /// Forget the sequence a cancelled or superseded task belonged to. Newer
+    /// work replaced it, so the rest of the steps are dropped quietly.
+    fn drop_chain_of(&mut self, task_id: TaskId) {
+        if let Some(chain_id) = self.chained_tasks.remove(&task_id) {
+            tracing::debug!("Dropping sequence {} with its cancelled task", chain_id);
+            self.chains.remove(&chain_id);
+        }
+    }
+
     pub fn cleanup_completed_tasks(&mut self) {
         let initial_count = self.handles.len();
         self.handles.retain(|_id, handle| !handle.is_finished());
         let cleaned_count = initial_count - self.handles.len();
         if cleaned_count > 0 {
             #[cfg(debug_assertions)]
-            tracing::debug!("Cleaned up {} completed tasks, {} remaining", cleaned_count, self.handles.len());
+            debug_throttled!(
+                "Cleaned up {} completed tasks, {} remaining",
+                cleaned_count,
+                self.handles.len()
+            );
         }
     }
 
@@ -80,6 +466,17 @@ impl AsyncTaskManager {
     }
 }
 
+/// Stop `future` once `token` is cancelled, reporting the cancellation instead
+async fn cancellable<F>(context: TaskContext, token: CancellationToken, future: F) -> Msg
+where
+    F: Future<Output = Msg>,
+{
+    tokio::select! {
+        msg = future => msg,
+        _ = token.cancelled() => Msg::TaskCancelled(context),
+    }
+}
+
 impl Drop for AsyncTaskManager {
     fn drop(&mut self) {
         let task_count = self.handles.len();
@@ -91,3 +488,313 @@ impl Drop for AsyncTaskManager {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sdk::OpenCodeError;
+
+    fn find_files(query: &str) -> TaskKind {
+        TaskKind::FindFiles {
+            query: query.to_string(),
+        }
+    }
+
+    fn found(path: &str) -> Msg {
+        Msg::ResponseFindFiles(String::new(), Ok(vec![path.to_string()]))
+    }
+
+    async fn settle(manager: &mut AsyncTaskManager) -> Vec<Msg> {
+        for _ in 0..10 {
+            tokio::task::yield_now().await;
+        }
+        manager.poll_messages()
+    }
+
+    #[tokio::test]
+    async fn test_spawn_keyed_cancels_running_task_of_same_kind() {
+        let mut manager = AsyncTaskManager::new();
+        let (_never_sent, never) = tokio::sync::oneshot::channel::<()>();
+
+        manager.spawn_keyed(find_files("ma"), async move {
+            let _ = never.await;
+            found("src/app/mod.rs")
+        });
+        assert_eq!(manager.active_kinds(), vec![find_files("ma")]);
+
+        manager.spawn_keyed(find_files("main"), async { found("src/main.rs") });
+
+        let messages = settle(&mut manager).await;
+        assert_eq!(messages.len(), 1);
+        assert!(matches!(
+            &messages[0],
+            Msg::TaskResult(key, msg)
+                if key.kind == find_files("main") && **msg == found("src/main.rs")
+        ));
+        assert!(manager.active_kinds().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_queued_result_of_superseded_task_is_dropped() {
+        let mut manager = AsyncTaskManager::new();
+
+        // The first search finishes, but nothing polls before the next one
+        manager.spawn_keyed(find_files("ma"), async { found("src/app/mod.rs") });
+        for _ in 0..10 {
+            tokio::task::yield_now().await;
+        }
+        manager.spawn_keyed(find_files("main"), async { found("src/main.rs") });
+
+        let messages = settle(&mut manager).await;
+        assert_eq!(messages.len(), 1);
+        assert!(matches!(
+            &messages[0],
+            Msg::TaskResult(_, msg) if **msg == found("src/main.rs")
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_cancel_keyed_aborts_the_running_task() {
+        let mut manager = AsyncTaskManager::new();
+        let (_never_sent, never) = tokio::sync::oneshot::channel::<()>();
+
+        manager.spawn_keyed(find_files("ma"), async move {
+            let _ = never.await;
+            found("src/app/mod.rs")
+        });
+        // Any query cancels the search, the slot is per kind
+        assert!(manager.cancel_keyed(&find_files("")));
+        assert!(!manager.cancel_keyed(&find_files("")));
+
+        assert!(settle(&mut manager).await.is_empty());
+        assert!(manager.active_kinds().is_empty());
+        assert_eq!(manager.active_task_count(), 0);
+    }
+
+    fn sessions_loaded() -> Msg {
+        Msg::ResponseSessionsLoad(Ok(vec![]))
+    }
+
+    #[tokio::test]
+    async fn test_closing_a_context_cancels_its_tasks() {
+        let mut manager = AsyncTaskManager::new();
+        let (_never_sent, never) = tokio::sync::oneshot::channel::<()>();
+        let session = TaskContext::Session {
+            session_id: "ses_1".to_string(),
+        };
+
+        manager.spawn_keyed_in(
+            TaskContext::SessionSelector,
+            TaskKind::LoadSessions,
+            async move {
+                let _ = never.await;
+                sessions_loaded()
+            },
+        );
+        manager.spawn_in(session.clone(), async { found("src/main.rs") });
+
+        assert_eq!(manager.cancel_context(&TaskContext::SessionSelector), 1);
+        assert_eq!(manager.cancel_context(&TaskContext::SessionSelector), 0);
+
+        // Tasks of other contexts carry on
+        let messages = settle(&mut manager).await;
+        assert_eq!(messages.len(), 2);
+        assert!(messages.contains(&Msg::TaskCancelled(TaskContext::SessionSelector)));
+        assert!(messages.contains(&found("src/main.rs")));
+        assert!(manager.active_kinds().is_empty());
+        assert_eq!(manager.cancel_context(&session), 0);
+    }
+
+    #[tokio::test]
+    async fn test_late_result_of_a_closed_context_is_not_delivered() {
+        let mut manager = AsyncTaskManager::new();
+
+        // The load finishes, but nothing polls before the selector closes
+        manager.spawn_keyed_in(
+            TaskContext::SessionSelector,
+            TaskKind::LoadSessions,
+            async { sessions_loaded() },
+        );
+        for _ in 0..10 {
+            tokio::task::yield_now().await;
+        }
+        manager.cancel_context(&TaskContext::SessionSelector);
+
+        assert_eq!(
+            settle(&mut manager).await,
+            vec![Msg::TaskCancelled(TaskContext::SessionSelector)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_messages_of_different_sessions_run_side_by_side() {
+        let mut manager = AsyncTaskManager::new();
+        let load = |session_id: &str| TaskKind::LoadMessages {
+            session_id: session_id.to_string(),
+        };
+        let (_never_sent, never) = tokio::sync::oneshot::channel::<()>();
+        manager.spawn_keyed(load("ses_a"), async move {
+            let _ = never.await;
+            found("a")
+        });
+        manager.spawn_keyed(load("ses_b"), async { found("b") });
+        assert_eq!(settle(&mut manager).await.len(), 1);
+        assert_eq!(manager.active_kinds(), vec![load("ses_a")]);
+
+        // The same session's loads still supersede each other
+        manager.spawn_keyed(load("ses_a"), async { found("a again") });
+        let messages = settle(&mut manager).await;
+        assert!(matches!(
+            &messages[..],
+            [Msg::TaskResult(_, msg)] if **msg == found("a again")
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_different_kinds_run_side_by_side() {
+        let mut manager = AsyncTaskManager::new();
+        manager.spawn_keyed(TaskKind::LoadSessions, async {
+            Msg::ResponseSessionsLoad(Err(OpenCodeError::ServerNotFound))
+        });
+        manager.spawn_keyed(find_files("main"), async { found("src/main.rs") });
+
+        assert_eq!(settle(&mut manager).await.len(), 2);
+    }
+
+    fn step(name: &str) -> Cmd {
+        Cmd::AsyncLoadDraft(name.to_string())
+    }
+
+    fn loaded(name: &str) -> Msg {
+        Msg::ResponseDraftLoad(name.to_string(), None)
+    }
+
+    /// Start the next step of a chain the way the program does, with `run`
+    /// spawning its tasks, returning the step and whether it is waiting
+    fn start_step(
+        manager: &mut AsyncTaskManager,
+        chain_id: ChainId,
+        run: impl FnOnce(&mut AsyncTaskManager, Cmd),
+    ) -> Option<(Cmd, bool)> {
+        let step = manager.next_chain_step(chain_id)?;
+        run(manager, step.clone());
+        Some((step, manager.end_chain_step()))
+    }
+
+    #[tokio::test]
+    async fn test_chain_step_waits_for_the_previous_response() {
+        let mut manager = AsyncTaskManager::new();
+        let chain_id = manager.start_chain(vec![step("first"), step("second")]);
+        let (release, released) = tokio::sync::oneshot::channel::<()>();
+
+        let started = start_step(&mut manager, chain_id, |manager, _| {
+            manager.spawn_task(async move {
+                let _ = released.await;
+                loaded("first")
+            });
+        });
+        assert_eq!(started, Some((step("first"), true)));
+
+        // Nothing moves on while the slow first step is running
+        assert!(settle(&mut manager).await.is_empty());
+        assert!(manager.take_chain_progress().is_empty());
+
+        // Unrelated tasks finishing don't advance the chain either
+        manager.spawn_task(async { found("src/main.rs") });
+        assert_eq!(settle(&mut manager).await, vec![found("src/main.rs")]);
+        assert!(manager.take_chain_progress().is_empty());
+
+        let _ = release.send(());
+        assert_eq!(settle(&mut manager).await, vec![loaded("first")]);
+        assert_eq!(
+            manager.take_chain_progress(),
+            vec![ChainProgress::Continue(chain_id)]
+        );
+
+        let started = start_step(&mut manager, chain_id, |manager, _| {
+            manager.spawn_task(async { loaded("second") });
+        });
+        assert_eq!(started, Some((step("second"), true)));
+        settle(&mut manager).await;
+        assert_eq!(
+            manager.take_chain_progress(),
+            vec![ChainProgress::Continue(chain_id)]
+        );
+        assert_eq!(manager.next_chain_step(chain_id), None);
+    }
+
+    #[tokio::test]
+    async fn test_failed_chain_step_drops_the_rest() {
+        let mut manager = AsyncTaskManager::new();
+        let chain_id = manager.start_chain(vec![step("first"), step("second"), step("third")]);
+
+        start_step(&mut manager, chain_id, |manager, _| {
+            manager.spawn_keyed(TaskKind::LoadSessions, async {
+                Msg::ResponseSessionsLoad(Err(OpenCodeError::ServerNotFound))
+            });
+        });
+        settle(&mut manager).await;
+
+        assert_eq!(
+            manager.take_chain_progress(),
+            vec![ChainProgress::Aborted {
+                error: OpenCodeError::ServerNotFound.to_string(),
+                skipped: 2,
+            }]
+        );
+        assert_eq!(manager.next_chain_step(chain_id), None);
+    }
+
+    #[tokio::test]
+    async fn test_chain_step_without_tasks_does_not_wait() {
+        let mut manager = AsyncTaskManager::new();
+        let chain_id = manager.start_chain(vec![step("first"), step("second")]);
+
+        assert_eq!(
+            start_step(&mut manager, chain_id, |_, _| {}),
+            Some((step("first"), false))
+        );
+        assert_eq!(
+            start_step(&mut manager, chain_id, |_, _| {}),
+            Some((step("second"), false))
+        );
+        assert_eq!(start_step(&mut manager, chain_id, |_, _| {}), None);
+    }
+
+    #[tokio::test]
+    async fn test_superseded_chain_step_drops_the_chain() {
+        let mut manager = AsyncTaskManager::new();
+        let chain_id = manager.start_chain(vec![step("first"), step("second")]);
+        let (_never_sent, never) = tokio::sync::oneshot::channel::<()>();
+
+        start_step(&mut manager, chain_id, |manager, _| {
+            manager.spawn_keyed(find_files("ma"), async move {
+                let _ = never.await;
+                found("src/app/mod.rs")
+            });
+        });
+        manager.spawn_keyed(find_files("main"), async { found("src/main.rs") });
+        settle(&mut manager).await;
+
+        assert!(manager.take_chain_progress().is_empty());
+        assert_eq!(manager.next_chain_step(chain_id), None);
+    }
+
+    #[test]
+    fn test_generations_reject_older_results() {
+        let mut generations = TaskGenerations::default();
+        let key = |generation| TaskKey {
+            kind: find_files("main"),
+            generation,
+        };
+
+        assert!(generations.accept(&key(2)));
+        assert!(!generations.accept(&key(1)));
+        assert!(generations.accept(&key(3)));
+        // Other kinds are tracked separately
+        assert!(generations.accept(&TaskKey {
+            kind: TaskKind::LoadSessions,
+            generation: 1,
+        }));
+    }
+}
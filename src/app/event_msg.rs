@@ -1,74 +1,218 @@
 use crate::{
     app::{
-        event_async_task_manager::TaskId,
+        bugreport::BugReport,
+        draft_store::Draft,
+        event_async_task_manager::{TaskContext, TaskId, TaskKey, TaskKind},
+        log_control::LogLevels,
+        msg_recorder::{recorded_client_response, recorded_response, recorded_stream},
+        redaction::{AttachmentSecrets, Redactor},
         tea_model::{AppModalState, RepeatShortcutKey},
-        ui_components::{MsgModalFileSelector, MsgModalSessionSelector, MsgTextArea},
+        ui_components::{
+            banner::BannerInfo,
+            modal_save_output::{SaveRefusal, SaveRequest},
+            modal_session_selector::SessionSort,
+            MsgLogFind, MsgLogSelection, MsgModalActivityLog, MsgModalFileSelector,
+            MsgModalMetrics, MsgModalProjectSelector, MsgModalServerSelector,
+            MsgModalSessionSelector, MsgModalSnippetSelector, MsgModalSymbolSelector,
+            MsgNewSessionPrompt, MsgSaveOutputPrompt, MsgTextArea,
+        },
     },
-    sdk::{extensions::events::EventStreamHandle, OpenCodeClient, OpenCodeError},
+    sdk::{
+        discovery::ServerCandidate,
+        extensions::{events::EventStreamHandle, git::GitInfo},
+        ChatOptions, MessagePage, OpenCodeClient, OpenCodeError,
+    },
+};
+use opencode_sdk::models::{
+    App, ConfigAgent, ConfigProviders200Response, Event, FilePart, FileRead200Response, Session,
+    Symbol,
 };
-use opencode_sdk::models::{ConfigAgent, Event, Model, Session, SessionMessages200ResponseInner};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 type OpenCodeResponse<T> = Result<T, OpenCodeError>;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Msg {
     // State changes
     InitializeClient,
+    RecheckProviders, // load the providers again, from the onboarding modal
     SessionAbort,
     ChangeState(AppModalState),
+    CloseModal, // back to the state the modal was opened over
     Quit,
+    QuitAndAbort,     // abort the running response, then quit
+    QuitLeaveRunning, // quit and let the server finish the response
+    QuitCancel,
+    SessionDirectoryConfirm, // open the session from another project anyway
+    SessionDirectoryCancel,
+    WorkspaceMismatchAccept, // go on with a server working on another project
+    WorkspaceMismatchPickServer, // drop it for one of the other running servers
 
     // Major input events
     ScrollMessageLog(i16),
-    ScrollMessageLogHorizontal(i16),
-    ValidateScrollPosition(u16, u16), // viewport_height, viewport_width
+    ScrollCodeBlock(i16), // columns, of the code block under the cursor
+    ScrollMessageLogToBottom,
+    ValidateScrollPosition(u16), // viewport_height
     SubmitTextInput,
+    Paste(String),
     CycleModeState,
     ToggleVerbosity,
     LeaderShowHelp,
     LeaderShowSessionSelector,
+    LeaderToggleSession,
     LeaderChangeInline,
+    LeaderOpenLatestImage,
+    LeaderShowMetrics,
+    LeaderShowActivityLog,
+    LeaderShowSnippetSelector,
+    LeaderShowSymbolSearch,
+    LeaderShowProjectSelector,
+    LeaderRetryLastMessage,
+    LeaderSelectLog,
+    LeaderFindInLog,
+    LeaderShowLinks,
+    LeaderPinToolOutput,
+    LeaderUnpin,
+    LeaderSaveToolOutput,
+    ScrollPinnedPane(i16), // rows, independent of the message log
+    ScrollChangelog(i16),  // rows
+    CloseChangelog,
+    LeaderLoadEarlierMessages,
+    FollowLink(char), // digit of the link in the links picker
+    RetryFailedSend,
+    EditFailedSend,
+    SendRedacted, // choices in the redaction confirmation
+    SendUnredacted,
+    CancelRedaction,
     MarkMessagesViewed,
+    AdvanceStreamReveal,
 
     // Unified repeat shortcut timeout events
     RepeatShortcutPressed(RepeatShortcutKey),
     ClearTimeout,
-    
+
     // General timeout expiration
     TimeoutExpired(crate::app::tea_model::TimeoutType),
 
+    // Notifications
+    DismissNotifications,
+
     // Client initialization messages
-    ResponseClientConnect(OpenCodeResponse<OpenCodeClient>),
-    ResponseSessionInit(OpenCodeResponse<Session>),
-    ResponseSessionCreateWithMessage(OpenCodeResponse<(Session, String)>),
-    ResponseSessionsLoad(OpenCodeResponse<Vec<Session>>),
-    ResponseModesLoad(OpenCodeResponse<ConfigAgent>),
-    ResponseSessionMessagesLoad(OpenCodeResponse<Vec<SessionMessages200ResponseInner>>),
-    ResponseUserMessageSend(OpenCodeResponse<String>),
-    ResponseFileStatusesLoad(OpenCodeResponse<Vec<opencode_sdk::models::File>>),
-    ResponseFindFiles(OpenCodeResponse<Vec<String>>),
+    ResponseClientConnect(
+        #[serde(with = "recorded_client_response")] OpenCodeResponse<OpenCodeClient>,
+    ),
+    ResponseServerCandidates(Vec<ServerCandidate>),
+    ResponseProjectCandidates(Vec<ServerCandidate>), // running servers, for the project picker
+    ResponseSessionInit(#[serde(with = "recorded_response")] OpenCodeResponse<Session>),
+    ResponseSessionResume(#[serde(with = "recorded_response")] OpenCodeResponse<Option<Session>>),
+    ResponseSessionCreateWithMessage(
+        #[serde(with = "recorded_response")] OpenCodeResponse<(Session, String)>,
+    ),
+    ResponseSessionsLoad(#[serde(with = "recorded_response")] OpenCodeResponse<Vec<Session>>),
+    ResponseSessionDirectoriesLoad(
+        #[serde(with = "recorded_response")] OpenCodeResponse<HashMap<String, String>>,
+    ), // by session id
+    ResponseModesLoad(#[serde(with = "recorded_response")] OpenCodeResponse<ConfigAgent>),
+    ResponseProvidersLoad(
+        #[serde(with = "recorded_response")] OpenCodeResponse<ConfigProviders200Response>,
+    ),
+    ResponseSessionMessagesLoad(
+        String,
+        #[serde(with = "recorded_response")] OpenCodeResponse<MessagePage>,
+    ), // session id, latest page
+    ResponseEarlierMessagesLoad(
+        String,
+        #[serde(with = "recorded_response")] OpenCodeResponse<MessagePage>,
+    ), // session id, page
+    ResponseMessagesPoll(
+        String,
+        #[serde(with = "recorded_response")] OpenCodeResponse<MessagePage>,
+    ), // session id, latest page
+    ResponseUserMessageSend(
+        String,
+        #[serde(with = "recorded_response")] OpenCodeResponse<String>,
+    ), // message id, sent text
+    ResponseFileStatusesLoad(
+        #[serde(with = "recorded_response")] OpenCodeResponse<Vec<opencode_sdk::models::File>>,
+    ),
+    ResponseGitInfoLoad(#[serde(with = "recorded_response")] OpenCodeResponse<GitInfo>),
+    ResponseFindFiles(
+        String,
+        #[serde(with = "recorded_response")] OpenCodeResponse<Vec<String>>,
+    ), // query, file paths
+    ResponseFindDirectories(
+        String,
+        #[serde(with = "recorded_response")] OpenCodeResponse<Vec<String>>,
+    ), // query, file paths
+    ResponseFindSymbols(
+        String,
+        #[serde(with = "recorded_response")] OpenCodeResponse<Vec<Symbol>>,
+    ), // query, symbols
+    ResponseSessionDirectoryCheck(String, Result<(), String>), // directory, why it can't be used
+    ResponseDraftLoad(String, Option<Draft>),                  // draft key, saved draft
+    ResponseDraftSave(String, bool),                           // draft key, whether it was written
+    ResponseSessionSortSave(bool),                             // whether it was written
+    ResponseSessionAbort(#[serde(with = "recorded_response")] OpenCodeResponse<bool>),
+    ResponseAppInfoLoad(#[serde(with = "recorded_response")] OpenCodeResponse<App>),
+    ResponseServerVersionLoad(
+        #[serde(with = "recorded_response")] OpenCodeResponse<Option<String>>,
+    ),
+    ResponseFileOpen(String, Option<String>), // file name, error if it couldn't be opened
+    ResponseMentionsCheck(String, Vec<String>), // checked input, mentioned paths that are missing
+    ResponseAttachmentSizes(Vec<(String, usize)>), // path, size in bytes
+    ResponseAttachmentScan(String, AttachmentSecrets), // scanned input, files with secrets
+    ResponsePastedPaths(String, Option<Vec<String>>), // pasted text, its files if they all exist
+    ResponseLinkPaths(Vec<(String, Option<bool>)>), // path in a reply, whether it names a file
+    ResponsePinFile(
+        String,
+        #[serde(with = "recorded_response")] OpenCodeResponse<FileRead200Response>,
+    ), // path, its content
+    ResponseToolOutputSave(String, Result<usize, SaveRefusal>), // path, bytes of output written
+    ResponseBugReport(Result<String, String>), // bundle directory, or why it wasn't written
+    ResponseSeenVersionLoad(Option<String>),  // version last run, None before the first
+    ResponseSeenVersionSave(bool),            // whether it was written
 
     // Event stream messages
     EventReceived(Event),
-    EventStreamConnected(EventStreamHandle),
+    ProjectEventReceived(String, Event), // root of the parked project it's from
+    EventBatchDrained,                   // all pending events from one poll have been applied
+    EventStreamConnected(#[serde(with = "recorded_stream")] EventStreamHandle),
     EventStreamDisconnected,
     EventStreamError(String),
-    EventStreamReconnecting(u32), // attempt number
+    EventStreamReconnecting(u32),    // attempt number
+    EventStreamLagged(u64),          // events missed by falling behind the stream
+    EventStreamUnknownEvents(usize), // events skipped for being of an unknown type
 
     // Task lifecycle messages
     TaskStarted(TaskId, String),
     TaskCompleted(TaskId),
     TaskFailed(TaskId, String),
-    RecordActiveTaskCount(usize),
+    RecordActiveTasks(usize, Vec<TaskKind>), // task count, kinds of keyed tasks
+    TaskResult(TaskKey, Box<Msg>),           // result of a keyed task
+    TaskCancelled(TaskContext),              // a task whose view closed before it finished
+    SequenceAborted(String, usize),          // error of the failed step, steps skipped
 
     // Terminal events
-    TerminalResize(u16, u16), // width, height
-    ChangeInlineHeight(u16),  // new height for inline mode
+    TerminalResize(u16, u16),   // width, height
+    TerminalFocusChanged(bool), // whether the window has focus now
+    TerminalSuspend,            // ctrl+z, stop until the shell continues us
+    ChangeInlineHeight(u16),    // new height for inline mode
 
     // Component messages
     TextArea(MsgTextArea),
     ModalSessionSelector(MsgModalSessionSelector),
+    NewSessionPrompt(MsgNewSessionPrompt),
+    SaveOutputPrompt(MsgSaveOutputPrompt),
     ModalFileSelector(MsgModalFileSelector),
+    ModalMetrics(MsgModalMetrics),
+    ModalActivityLog(MsgModalActivityLog),
+    ModalSnippetSelector(MsgModalSnippetSelector),
+    ModalSymbolSelector(MsgModalSymbolSelector),
+    ModalServerSelector(MsgModalServerSelector),
+    ModalProjectSelector(MsgModalProjectSelector),
+    LogSelection(MsgLogSelection),
+    LogFind(MsgLogFind),
 }
 #[derive(Debug, Clone, PartialEq)]
 pub enum Cmd {
@@ -78,17 +222,36 @@ pub enum Cmd {
     TerminalAutoResize,             // trigger autoresize for any viewport changes
     TerminalRebootWithInline(bool), // reinitialize for new viewport
     TerminalResizeInlineViewport(u16), // new height for inline mode
-    TerminalScrollPastHeight,       // scroll past any manual stdio output
+    TerminalInsertBannerInfo(BannerInfo),
+    TerminalScrollPastHeight, // scroll past any manual stdio output
+    TerminalNotify(String),   // desktop notification with this text
+    TerminalBell,             // ring the bell for an alert
+    TerminalCopyToClipboard(String),
+    TerminalSuspend, // hand the terminal back to the shell and stop
+
+    // Swap the log file's filter for the base one with these levels on top
+    LogSetLevels(LogLevels),
 
     // Async commands that don't block
     AsyncSpawnClientDiscovery,
+    AsyncConnectServer(String),
+    AsyncListServers,          // every running server, for the project picker
+    AsyncListServerCandidates, // every running server, for the server picker
     AsyncSpawnSessionInit(OpenCodeClient),
-    AsyncCreateSessionWithMessage(OpenCodeClient, String),
+    AsyncResumeLastSession(OpenCodeClient),
+    AsyncCreateSessionWithMessage(OpenCodeClient, String, Option<String>), // client, text, directory
     AsyncLoadSessions(OpenCodeClient),
+    AsyncLoadSessionDirectories(OpenCodeClient),
     AsyncLoadModes(OpenCodeClient),
+    AsyncLoadProviders(OpenCodeClient),
     AsyncLoadSessionMessages(OpenCodeClient, String),
+    AsyncLoadEarlierMessages(OpenCodeClient, String, String), // session id, before message id
     AsyncLoadFileStatus(OpenCodeClient),
+    AsyncLoadGitInfo(OpenCodeClient),
     AsyncLoadFindFiles(OpenCodeClient, String),
+    AsyncFindDirectories(OpenCodeClient, String), // client, workspace-relative query
+    AsyncFindSymbols(OpenCodeClient, String),
+    AsyncCheckSessionDirectory(String),
     AsyncSendUserMessage(
         OpenCodeClient,
         String,
@@ -96,25 +259,81 @@ pub enum Cmd {
         String,
         String,
         String,
-        Option<String>,
-    ), // client, session_id, message_id, text, provider_id, model_id, mode
+        ChatOptions,
+    ), // client, session_id, message_id, text, provider_id, model_id, options
     AsyncSendUserMessageWithAttachments(
         OpenCodeClient,
         String,
         String,
         String,
-        Vec<crate::app::tea_model::AttachedFile>,
+        Vec<crate::sdk::AttachedFile>,
         String,
         String,
-        Option<String>,
-    ), // client, session_id, message_id, text, attached_files, provider_id, model_id, mode
+        ChatOptions,
+    ), // client, session_id, message_id, text, attached_files, provider_id, model_id, options
+    AsyncLoadDraft(String),        // draft key
+    AsyncSaveDraft(String, Draft), // draft key, draft (empty removes it)
+    AsyncDeleteDraft(String),      // draft key
+    AsyncSaveSessionSort(SessionSort),
+    AsyncLoadSeenVersion,
+    AsyncSaveSeenVersion(String),
+    AsyncCheckLinkPaths(OpenCodeClient, Option<String>, Vec<String>), // client, workspace root, paths
+    AsyncReadPinnedFile(OpenCodeClient, String),                      // client, path
+    AsyncSaveToolOutput(SaveRequest),
+    AsyncWriteBugReport(BugReport),
+    AsyncOpenUrl(String),
     AsyncCancelTask(TaskId),
+    AsyncCancelKeyed(TaskKind),
+    AsyncCancelContext(TaskContext),
     AsyncSessionAbort,
+    AsyncAbortSession(OpenCodeClient, String), // client, session_id
+    AsyncOpenFilePart(OpenCodeClient, FilePart),
+    AsyncLoadAppInfo(OpenCodeClient),
+    AsyncLoadServerVersion(OpenCodeClient),
+    AsyncCheckMentions(OpenCodeClient, Option<String>, String, Vec<String>), // client, root, input, paths
+    AsyncMeasureAttachments(String, Vec<String>), // workspace root, attached paths
+    AsyncScanAttachments(OpenCodeClient, Redactor, String, Vec<String>), // client, patterns, input, attached paths
+    AsyncResolvePastedPaths(String, Vec<String>, Option<String>), // pasted text, paths, workspace root
 
     // Event stream commands
     AsyncStartEventStream(OpenCodeClient),
     AsyncStopEventStream,
+    AsyncPollMessages(OpenCodeClient, String), // client, session_id
     AsyncReconnectEventStream,
+
+    // Run each command once the previous one's response has been handled,
+    // stopping at the first that fails
+    Sequence(Vec<Cmd>),
+}
+
+impl Msg {
+    /// The error a response carries, which stops a `Cmd::Sequence`
+    pub fn error(&self) -> Option<String> {
+        match self {
+            Msg::ResponseClientConnect(Err(error))
+            | Msg::ResponseSessionInit(Err(error))
+            | Msg::ResponseSessionResume(Err(error))
+            | Msg::ResponseSessionCreateWithMessage(Err(error))
+            | Msg::ResponseSessionsLoad(Err(error))
+            | Msg::ResponseSessionDirectoriesLoad(Err(error))
+            | Msg::ResponseModesLoad(Err(error))
+            | Msg::ResponseProvidersLoad(Err(error))
+            | Msg::ResponseSessionMessagesLoad(_, Err(error))
+            | Msg::ResponseEarlierMessagesLoad(_, Err(error))
+            | Msg::ResponseUserMessageSend(_, Err(error))
+            | Msg::ResponseFileStatusesLoad(Err(error))
+            | Msg::ResponseGitInfoLoad(Err(error))
+            | Msg::ResponseFindFiles(_, Err(error))
+            | Msg::ResponseFindDirectories(_, Err(error))
+            | Msg::ResponseFindSymbols(_, Err(error))
+            | Msg::ResponseSessionAbort(Err(error))
+            | Msg::ResponseAppInfoLoad(Err(error))
+            | Msg::ResponseServerVersionLoad(Err(error)) => Some(error.to_string()),
+            Msg::EventStreamError(error) | Msg::TaskFailed(_, error) => Some(error.clone()),
+            Msg::TaskResult(_, msg) => msg.error(),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -2,11 +2,14 @@ use crate::app::{
     event_msg::{Msg, Sub},
     tea_model::{AppModalState, ConnectionStatus, EventStreamState, Model, RepeatShortcutKey},
     ui_components::{
-        modal_file_selector::FileData, ModalSelector, ModalSelectorEvent, MsgModalFileSelector,
-        MsgModalSessionSelector, MsgTextArea,
+        modal_file_selector::FileData, ModalSelector, ModalSelectorEvent, MsgLogFind,
+        MsgLogSelection, MsgModalActivityLog, MsgModalFileSelector, MsgModalMetrics,
+        MsgModalProjectSelector, MsgModalServerSelector, MsgModalSessionSelector,
+        MsgModalSnippetSelector, MsgModalSymbolSelector, MsgNewSessionPrompt, MsgSaveOutputPrompt,
+        MsgTextArea, BLOCK_SCROLL_COLUMNS,
     },
 };
-use crossterm::event::{self, Event, KeyCode, KeyModifiers, MouseEventKind};
+use crossterm::event::{Event, KeyCode, KeyModifiers, MouseEventKind};
 
 pub fn subscriptions(model: &Model) -> Vec<Sub> {
     let mut subs = match model.state {
@@ -51,6 +54,7 @@ pub fn crossterm_to_msg(event: Event, model: &Model) -> Option<Msg> {
                 (_, KeyCode::Char('x'), KeyModifiers::CONTROL, _) => {
                     Some(Msg::RepeatShortcutPressed(RepeatShortcutKey::Leader))
                 }
+                (_, KeyCode::Char('z'), KeyModifiers::CONTROL, _) => Some(Msg::TerminalSuspend),
 
                 // Leader shortcuts:
                 // /new                      new session               ctrl+x n
@@ -66,15 +70,46 @@ pub fn crossterm_to_msg(event: Event, model: &Model) -> Option<Msg> {
                 // /themes                   list themes               ctrl+x t
                 // /details                  toggle tool details       ctrl+x d
                 // TODO the others, once those messages are supported
-                (_, KeyCode::Char('h'), _, true) => Some(Msg::LeaderShowHelp),
+                (_, KeyCode::Char('h' | '?'), _, true) => Some(Msg::LeaderShowHelp),
                 (_, KeyCode::Char('l'), _, true) => Some(Msg::LeaderShowSessionSelector),
+                (_, KeyCode::Char('`'), _, true) => Some(Msg::LeaderToggleSession),
                 (_, KeyCode::Char('n'), _, true) => Some(Msg::SessionAbort),
                 (_, KeyCode::Tab, _, true) => Some(Msg::LeaderChangeInline),
+                (_, KeyCode::Char('o'), _, true) => Some(Msg::LeaderOpenLatestImage),
+                (_, KeyCode::Char('M'), _, true) => Some(Msg::LeaderShowMetrics),
+                (_, KeyCode::Char('L'), _, true) => Some(Msg::LeaderLoadEarlierMessages),
+                (_, KeyCode::Char('t'), _, true) => Some(Msg::LeaderShowSnippetSelector),
+                (_, KeyCode::Char('O'), _, true) => Some(Msg::LeaderShowSymbolSearch),
+                (_, KeyCode::Char('p'), _, true) => Some(Msg::LeaderShowProjectSelector),
+                (_, KeyCode::Char('r'), _, true) => Some(Msg::LeaderRetryLastMessage),
+                (_, KeyCode::Char('v'), _, true) => Some(Msg::LeaderSelectLog),
+                (_, KeyCode::Char('/'), _, true) => Some(Msg::LeaderFindInLog),
+                (_, KeyCode::Char('f'), _, true) => Some(Msg::LeaderShowLinks),
+                (_, KeyCode::Char('P'), _, true) => Some(Msg::LeaderPinToolOutput),
+                (_, KeyCode::Char('U'), _, true) => Some(Msg::LeaderUnpin),
+                (_, KeyCode::Char('S'), _, true) => Some(Msg::LeaderSaveToolOutput),
+                (_, KeyCode::Char('a'), _, true) => Some(Msg::LeaderShowActivityLog),
                 (_, KeyCode::Char('q'), _, true) => Some(Msg::Quit),
 
-                // Works both without session (pending creation) and with explicit session
+                // A failed send can be retried or put back into the input
+                (AppModalState::None, KeyCode::Enter, KeyModifiers::CONTROL, _)
+                    if model.failed_send.is_some() =>
+                {
+                    Some(Msg::RetryFailedSend)
+                }
+                (AppModalState::None, KeyCode::Char('e'), KeyModifiers::CONTROL, _)
+                    if model.failed_send.is_some() =>
+                {
+                    Some(Msg::EditFailedSend)
+                }
+
+                // Works both without session (pending creation) and with explicit session.
+                // Input is taken while still connecting, and a send waits for the server
                 (
-                    AppModalState::None | AppModalState::Connecting(ConnectionStatus::Connected),
+                    AppModalState::None
+                    | AppModalState::Connecting(
+                        ConnectionStatus::Connecting | ConnectionStatus::Connected,
+                    ),
                     KeyCode::Enter,
                     modifiers,
                     _,
@@ -86,28 +121,54 @@ pub fn crossterm_to_msg(event: Event, model: &Model) -> Option<Msg> {
                     }
                 }
                 (
-                    AppModalState::None | AppModalState::Connecting(ConnectionStatus::Connected),
+                    AppModalState::None
+                    | AppModalState::Connecting(
+                        ConnectionStatus::Connecting | ConnectionStatus::Connected,
+                    ),
                     KeyCode::Tab,
                     _,
                     _,
                 ) => Some(Msg::CycleModeState),
+
+                // Undo/redo of the input, since ctrl+z suspends. Terminals that
+                // can't report ctrl+shift+z can use ctrl+_, which arrives as ctrl+7
                 (
-                    AppModalState::None | AppModalState::Connecting(ConnectionStatus::Connected),
-                    KeyCode::Char('c'),
+                    AppModalState::None
+                    | AppModalState::Connecting(
+                        ConnectionStatus::Connecting | ConnectionStatus::Connected,
+                    ),
+                    KeyCode::Char('u'),
                     KeyModifiers::CONTROL,
                     _,
-                ) => {
-                    if model.is_repeat_shortcut_timeout_active(RepeatShortcutKey::CtrlC) {
-                        Some(Msg::Quit)
-                    } else {
-                        Some(Msg::TextArea(MsgTextArea::Clear))
-                    }
+                ) => Some(Msg::TextArea(MsgTextArea::Undo)),
+                (
+                    AppModalState::None
+                    | AppModalState::Connecting(
+                        ConnectionStatus::Connecting | ConnectionStatus::Connected,
+                    ),
+                    KeyCode::Char('z' | 'Z'),
+                    modifiers,
+                    _,
+                ) if modifiers.contains(KeyModifiers::CONTROL | KeyModifiers::SHIFT) => {
+                    Some(Msg::TextArea(MsgTextArea::Redo))
                 }
+                (
+                    AppModalState::None
+                    | AppModalState::Connecting(
+                        ConnectionStatus::Connecting | ConnectionStatus::Connected,
+                    ),
+                    KeyCode::Char('_' | '7'),
+                    KeyModifiers::CONTROL,
+                    _,
+                ) => Some(Msg::TextArea(MsgTextArea::Redo)),
 
                 // Requires session connected
                 (AppModalState::None, KeyCode::Esc, _, _) => {
                     // Leave session for main screen
-                    if model.is_repeat_shortcut_timeout_active(RepeatShortcutKey::Esc) {
+                    if !model.notifications.is_empty() {
+                        // First Esc dismisses any visible notifications
+                        Some(Msg::DismissNotifications)
+                    } else if model.is_repeat_shortcut_timeout_active(RepeatShortcutKey::Esc) {
                         // Some(Msg::SessionAbort)
                         // TODO: interrupt execution
                         None
@@ -118,12 +179,48 @@ pub fn crossterm_to_msg(event: Event, model: &Model) -> Option<Msg> {
                 (AppModalState::None, KeyCode::Char('r'), KeyModifiers::CONTROL, _) => {
                     Some(Msg::ToggleVerbosity)
                 }
+                // The pinned pane scrolls on its own, leaving the arrows to the input
+                (AppModalState::None, KeyCode::Up | KeyCode::Down, KeyModifiers::ALT, _)
+                    if model.pinned_pane.is_some() =>
+                {
+                    let rows = if key.code == KeyCode::Up { -1 } else { 1 };
+                    Some(Msg::ScrollPinnedPane(rows))
+                }
+                (
+                    AppModalState::None,
+                    KeyCode::PageUp | KeyCode::PageDown,
+                    KeyModifiers::ALT,
+                    _,
+                ) if model.pinned_pane.is_some() => {
+                    let rows = if key.code == KeyCode::PageUp { -10 } else { 10 };
+                    Some(Msg::ScrollPinnedPane(rows))
+                }
+                // Code blocks scroll sideways, unless the input has text to
+                // select with shift
+                (AppModalState::None, KeyCode::Left | KeyCode::Right, KeyModifiers::SHIFT, _)
+                    if !model.init.inline_mode() && model.text_input_area.is_empty() =>
+                {
+                    let columns = match key.code {
+                        KeyCode::Left => -BLOCK_SCROLL_COLUMNS,
+                        _ => BLOCK_SCROLL_COLUMNS,
+                    };
+                    Some(Msg::ScrollCodeBlock(columns))
+                }
                 // Message log scrolling (keeping Page Up/Down for fullscreen message history)
                 (AppModalState::None, KeyCode::PageUp, _, _) => Some(Msg::ScrollMessageLog(-5)),
                 (AppModalState::None, KeyCode::PageDown, _, _) => Some(Msg::ScrollMessageLog(5)),
+                // Jumps past messages that arrived while reading further up
+                (AppModalState::None, KeyCode::End, _, _)
+                    if !model.init.inline_mode() && model.message_log.unseen_messages() > 0 =>
+                {
+                    Some(Msg::ScrollMessageLogToBottom)
+                }
                 // Fall through for all other input
                 (
-                    AppModalState::None | AppModalState::Connecting(ConnectionStatus::Connected),
+                    AppModalState::None
+                    | AppModalState::Connecting(
+                        ConnectionStatus::Connecting | ConnectionStatus::Connected,
+                    ),
                     _,
                     _,
                     _,
@@ -136,14 +233,277 @@ pub fn crossterm_to_msg(event: Event, model: &Model) -> Option<Msg> {
                     KeyCode::Esc,
                     _,
                     _,
+                ) => Some(Msg::CloseModal),
+                (AppModalState::ModalHelp, _, _, _) => None,
+
+                // Quit confirmation while a response is running
+                (AppModalState::ModalQuitConfirm, KeyCode::Char('a'), _, _) => {
+                    Some(Msg::QuitAndAbort)
+                }
+                (AppModalState::ModalQuitConfirm, KeyCode::Char('l'), _, _) => {
+                    Some(Msg::QuitLeaveRunning)
+                }
+                (AppModalState::ModalQuitConfirm, KeyCode::Esc | KeyCode::Char('c'), _, _) => {
+                    Some(Msg::QuitCancel)
+                }
+                (AppModalState::ModalQuitConfirm, _, _, _) => None,
+
+                // Secrets found in a message about to be sent
+                (AppModalState::ModalRedactConfirm, KeyCode::Char('r'), _, _) => {
+                    Some(Msg::SendRedacted)
+                }
+                (AppModalState::ModalRedactConfirm, KeyCode::Char('s'), _, _) => {
+                    Some(Msg::SendUnredacted)
+                }
+                (AppModalState::ModalRedactConfirm, KeyCode::Esc | KeyCode::Char('c'), _, _) => {
+                    Some(Msg::CancelRedaction)
+                }
+                (AppModalState::ModalRedactConfirm, _, _, _) => None,
+
+                // Opening a session from another project
+                (
+                    AppModalState::ModalSessionDirectoryConfirm,
+                    KeyCode::Char('y') | KeyCode::Enter,
+                    _,
+                    _,
+                ) => Some(Msg::SessionDirectoryConfirm),
+                (
+                    AppModalState::ModalSessionDirectoryConfirm,
+                    KeyCode::Char('n') | KeyCode::Esc,
+                    _,
+                    _,
+                ) => Some(Msg::SessionDirectoryCancel),
+                (AppModalState::ModalSessionDirectoryConfirm, _, _, _) => None,
+
+                // Connected to a server working on another project
+                (AppModalState::ModalWorkspaceMismatch, KeyCode::Char('c'), _, _) => {
+                    Some(Msg::WorkspaceMismatchAccept)
+                }
+                (AppModalState::ModalWorkspaceMismatch, KeyCode::Char('p'), _, _) => {
+                    Some(Msg::WorkspaceMismatchPickServer)
+                }
+                (AppModalState::ModalWorkspaceMismatch, KeyCode::Char('q'), _, _) => {
+                    Some(Msg::Quit)
+                }
+                (AppModalState::ModalWorkspaceMismatch, _, _, _) => None,
+
+                // What's new, or the whole changelog
+                (AppModalState::ModalChangelog, KeyCode::Up | KeyCode::Char('k'), _, _) => {
+                    Some(Msg::ScrollChangelog(-1))
+                }
+                (AppModalState::ModalChangelog, KeyCode::Down | KeyCode::Char('j'), _, _) => {
+                    Some(Msg::ScrollChangelog(1))
+                }
+                (AppModalState::ModalChangelog, KeyCode::PageUp | KeyCode::PageDown, _, _) => {
+                    let page = model
+                        .changelog
+                        .as_ref()
+                        .map_or(1, |changelog| changelog.page());
+                    Some(Msg::ScrollChangelog(match key.code {
+                        KeyCode::PageUp => -page,
+                        _ => page,
+                    }))
+                }
+                (
+                    AppModalState::ModalChangelog,
+                    KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q'),
+                    _,
+                    _,
+                ) => Some(Msg::CloseChangelog),
+                (AppModalState::ModalChangelog, _, _, _) => None,
+
+                // Selecting text in the message log
+                (
+                    AppModalState::LogSelect,
+                    KeyCode::Left | KeyCode::Right,
+                    KeyModifiers::SHIFT,
+                    _,
                 ) => {
-                    // Close modals
-                    // TODO move to modal specific msg's
-                    Some(Msg::ChangeState(AppModalState::None))
+                    let columns = match key.code {
+                        KeyCode::Left => -BLOCK_SCROLL_COLUMNS,
+                        _ => BLOCK_SCROLL_COLUMNS,
+                    };
+                    Some(Msg::ScrollCodeBlock(columns))
+                }
+                (AppModalState::LogSelect, KeyCode::Up | KeyCode::Char('k'), _, _) => {
+                    Some(Msg::LogSelection(MsgLogSelection::Move(-1, 0)))
+                }
+                (AppModalState::LogSelect, KeyCode::Down | KeyCode::Char('j'), _, _) => {
+                    Some(Msg::LogSelection(MsgLogSelection::Move(1, 0)))
+                }
+                (AppModalState::LogSelect, KeyCode::Left | KeyCode::Char('h'), _, _) => {
+                    Some(Msg::LogSelection(MsgLogSelection::Move(0, -1)))
+                }
+                (AppModalState::LogSelect, KeyCode::Right | KeyCode::Char('l'), _, _) => {
+                    Some(Msg::LogSelection(MsgLogSelection::Move(0, 1)))
+                }
+                (AppModalState::LogSelect, KeyCode::Char('v'), _, _) => {
+                    Some(Msg::LogSelection(MsgLogSelection::Mark))
+                }
+                (AppModalState::LogSelect, KeyCode::Char('y'), _, _) => {
+                    Some(Msg::LogSelection(MsgLogSelection::Copy))
+                }
+                (AppModalState::LogSelect, KeyCode::Esc | KeyCode::Char('q'), _, _) => {
+                    Some(Msg::LogSelection(MsgLogSelection::Exit))
+                }
+                (AppModalState::LogSelect, KeyCode::Char('/'), _, _) => Some(Msg::LeaderFindInLog),
+                (AppModalState::LogSelect, _, _, _) => None,
+
+                // Finding text in the message log, typing the query first
+                (AppModalState::LogFind, KeyCode::Esc, _, _) => {
+                    Some(Msg::LogFind(MsgLogFind::Exit))
+                }
+                (AppModalState::LogFind, KeyCode::Enter, _, _)
+                    if model.message_log.is_typing_find() =>
+                {
+                    Some(Msg::LogFind(MsgLogFind::Submit))
+                }
+                (AppModalState::LogFind, KeyCode::Backspace, _, _)
+                    if model.message_log.is_typing_find() =>
+                {
+                    Some(Msg::LogFind(MsgLogFind::Backspace))
+                }
+                (AppModalState::LogFind, KeyCode::Char(ch), modifiers, _)
+                    if model.message_log.is_typing_find()
+                        && !modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) =>
+                {
+                    Some(Msg::LogFind(MsgLogFind::Type(ch)))
+                }
+                (AppModalState::LogFind, _, _, _) if model.message_log.is_typing_find() => None,
+                (AppModalState::LogFind, KeyCode::Char('n'), _, _) => {
+                    Some(Msg::LogFind(MsgLogFind::Next))
+                }
+                (AppModalState::LogFind, KeyCode::Char('N'), _, _) => {
+                    Some(Msg::LogFind(MsgLogFind::Previous))
+                }
+                (AppModalState::LogFind, KeyCode::Char('/'), _, _) => {
+                    Some(Msg::LogFind(MsgLogFind::Edit))
+                }
+                (AppModalState::LogFind, KeyCode::Char('q'), _, _) => {
+                    Some(Msg::LogFind(MsgLogFind::Exit))
+                }
+                (AppModalState::LogFind, _, _, _) => None,
+
+                // Links picker, a digit per link
+                (AppModalState::ModalLinks, KeyCode::Char(hint @ '1'..='9'), _, _) => {
+                    Some(Msg::FollowLink(hint))
+                }
+                (AppModalState::ModalLinks, KeyCode::Esc, _, _) => Some(Msg::CloseModal),
+                (AppModalState::ModalLinks, _, _, _) => None,
+
+                // No provider configured yet
+                (AppModalState::ModalOnboarding, KeyCode::Char('r'), _, _) => {
+                    Some(Msg::RecheckProviders)
+                }
+                (AppModalState::ModalOnboarding, KeyCode::Esc, _, _) => Some(Msg::CloseModal),
+                (AppModalState::ModalOnboarding, _, _, _) => None,
+
+                // Server older than the client supports
+                (AppModalState::ModalServerOutdated, KeyCode::Enter | KeyCode::Esc, _, _) => {
+                    Some(Msg::CloseModal)
+                }
+                (AppModalState::ModalServerOutdated, _, _, _) => None,
+
+                // Where to root a new session
+                (AppModalState::ModalNewSession, KeyCode::Tab, _, _) => {
+                    Some(Msg::NewSessionPrompt(MsgNewSessionPrompt::Complete))
+                }
+                (AppModalState::ModalNewSession, KeyCode::Enter, _, _) => {
+                    Some(Msg::NewSessionPrompt(MsgNewSessionPrompt::Submit))
+                }
+                (AppModalState::ModalNewSession, KeyCode::Esc, _, _) => {
+                    Some(Msg::NewSessionPrompt(MsgNewSessionPrompt::Cancel))
+                }
+                (
+                    AppModalState::ModalNewSession,
+                    KeyCode::Char(_) | KeyCode::Backspace,
+                    modifiers,
+                    _,
+                ) if !modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) => {
+                    Some(Msg::NewSessionPrompt(MsgNewSessionPrompt::KeyInput(key)))
+                }
+                (AppModalState::ModalNewSession, _, _, _) => None,
+
+                // Where to save a tool's output
+                (AppModalState::ModalSaveOutput, KeyCode::Tab, _, _) => {
+                    Some(Msg::SaveOutputPrompt(MsgSaveOutputPrompt::ToggleMetadata))
+                }
+                (AppModalState::ModalSaveOutput, KeyCode::Enter, _, _) => {
+                    Some(Msg::SaveOutputPrompt(MsgSaveOutputPrompt::Submit))
+                }
+                (AppModalState::ModalSaveOutput, KeyCode::Esc, _, _) => {
+                    Some(Msg::SaveOutputPrompt(MsgSaveOutputPrompt::Cancel))
+                }
+                (
+                    AppModalState::ModalSaveOutput,
+                    KeyCode::Char(_) | KeyCode::Backspace,
+                    modifiers,
+                    _,
+                ) if !modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) => {
+                    Some(Msg::SaveOutputPrompt(MsgSaveOutputPrompt::KeyInput(key)))
+                }
+                (AppModalState::ModalSaveOutput, _, _, _) => None,
+
+                // Request stats view events
+                (AppModalState::ModalMetrics, key_code, key_modifiers, _) => {
+                    let key_event = crossterm::event::KeyEvent::new(key_code, key_modifiers);
+                    Some(Msg::ModalMetrics(MsgModalMetrics::Event(
+                        ModalSelectorEvent::KeyInput(key_event),
+                    )))
+                }
+
+                // Activity log events
+                (AppModalState::ModalActivityLog, key_code, key_modifiers, _) => {
+                    let key_event = crossterm::event::KeyEvent::new(key_code, key_modifiers);
+                    Some(Msg::ModalActivityLog(MsgModalActivityLog::Event(
+                        ModalSelectorEvent::KeyInput(key_event),
+                    )))
+                }
+
+                // Server picker events
+                (AppModalState::ModalServerSelect, key_code, key_modifiers, _) => {
+                    let key_event = crossterm::event::KeyEvent::new(key_code, key_modifiers);
+                    Some(Msg::ModalServerSelector(MsgModalServerSelector::Event(
+                        ModalSelectorEvent::KeyInput(key_event),
+                    )))
+                }
+
+                // Project picker events
+                (AppModalState::ModalProjectSelect, key_code, key_modifiers, _) => {
+                    let key_event = crossterm::event::KeyEvent::new(key_code, key_modifiers);
+                    Some(Msg::ModalProjectSelector(MsgModalProjectSelector::Event(
+                        ModalSelectorEvent::KeyInput(key_event),
+                    )))
+                }
+
+                // Snippet picker events
+                (AppModalState::ModalSnippetSelect, key_code, key_modifiers, _) => {
+                    let key_event = crossterm::event::KeyEvent::new(key_code, key_modifiers);
+                    Some(Msg::ModalSnippetSelector(MsgModalSnippetSelector::Event(
+                        ModalSelectorEvent::KeyInput(key_event),
+                    )))
+                }
+
+                // Symbol search events, typing goes to the query
+                (AppModalState::ModalSymbolSelect, key_code, key_modifiers, _) => {
+                    let key_event = crossterm::event::KeyEvent::new(key_code, key_modifiers);
+                    if ModalSelector::<FileData>::is_modal_selector_input(key_code) {
+                        Some(Msg::ModalSymbolSelector(MsgModalSymbolSelector::Event(
+                            ModalSelectorEvent::KeyInput(key_event),
+                        )))
+                    } else {
+                        Some(Msg::ModalSymbolSelector(MsgModalSymbolSelector::KeyInput(
+                            key_event,
+                        )))
+                    }
                 }
-                (AppModalState::ModalHelp, _, _, _) => None,
 
                 // Session selector events
+                (AppModalState::ModalSessionSelect, KeyCode::Char('s'), KeyModifiers::NONE, _) => {
+                    Some(Msg::ModalSessionSelector(
+                        MsgModalSessionSelector::CycleSort,
+                    ))
+                }
                 (AppModalState::ModalSessionSelect, key_code, key_modifiers, _) => {
                     if true {
                         let key_event = crossterm::event::KeyEvent::new(key_code, key_modifiers);
@@ -205,7 +565,15 @@ pub fn crossterm_to_msg(event: Event, model: &Model) -> Option<Msg> {
             (AppModalState::None, MouseEventKind::ScrollDown) => Some(Msg::ScrollMessageLog(1)),
             _ => None,
         },
+        Event::Paste(text) => match &model.state {
+            AppModalState::None
+            | AppModalState::Connecting(
+                ConnectionStatus::Connecting | ConnectionStatus::Connected,
+            ) => Some(Msg::Paste(text)),
+            _ => None,
+        },
         Event::Resize(width, height) => Some(Msg::TerminalResize(width, height)),
-        _ => None,
+        Event::FocusGained => Some(Msg::TerminalFocusChanged(true)),
+        Event::FocusLost => Some(Msg::TerminalFocusChanged(false)),
     }
 }
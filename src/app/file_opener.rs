@@ -0,0 +1,207 @@
+//! Open file attachments with the platform's default application
+//!
+//! A file part's URL is turned into a local path first: `file://` URLs are
+//! resolved against the server's working directory, while inline `data:` and
+//! remote `http(s)://` URLs are written to a temp file. The opener
+//! (`xdg-open`, `open` or `start`) is spawned detached so the TUI keeps running.
+
+use crate::app::error::Result;
+use crate::sdk::OpenCodeClient;
+use eyre::{eyre, WrapErr};
+use opencode_sdk::models::FilePart;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use tokio::process::Command;
+
+/// Make a file part available locally and open it
+pub async fn open_file_part(client: &OpenCodeClient, file_part: &FilePart) -> Result<PathBuf> {
+    let download_dir = std::env::temp_dir().join("opencoders");
+    let path = local_path_for(client, file_part, &download_dir).await?;
+    open_with_platform_opener(&path)?;
+    Ok(path)
+}
+
+/// Local path holding the file part's content, downloading it if needed
+async fn local_path_for(
+    client: &OpenCodeClient,
+    file_part: &FilePart,
+    download_dir: &Path,
+) -> Result<PathBuf> {
+    let url = file_part.url.as_str();
+    if let Some(path) = url.strip_prefix("file://") {
+        let path = Path::new(path);
+        if path.is_absolute() {
+            return Ok(path.to_path_buf());
+        }
+        let app = client.get_app_info().await?;
+        return Ok(Path::new(&app.path.cwd).join(path));
+    }
+
+    let bytes = if url.starts_with("data:") {
+        decode_data_url(url).ok_or_else(|| eyre!("Attachment has a malformed data URL"))?
+    } else if url.starts_with("http://") || url.starts_with("https://") {
+        reqwest::get(url)
+            .await
+            .and_then(|response| response.error_for_status())
+            .wrap_err("Failed to download attachment")?
+            .bytes()
+            .await
+            .wrap_err("Failed to download attachment")?
+            .to_vec()
+    } else {
+        return Err(eyre!("Don't know how to open {}", url));
+    };
+
+    fs::create_dir_all(download_dir)
+        .await
+        .wrap_err("Failed to create download directory")?;
+    let path = download_dir.join(download_file_name(file_part));
+    fs::write(&path, bytes)
+        .await
+        .wrap_err("Failed to write attachment")?;
+    Ok(path)
+}
+
+/// Name for a downloaded copy, prefixed with the part id so attachments that
+/// share a filename don't overwrite each other
+fn download_file_name(file_part: &FilePart) -> String {
+    let name = file_part
+        .filename
+        .as_deref()
+        .and_then(|name| Path::new(name).file_name())
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| match file_part.mime.split_once('/') {
+            Some((_, subtype)) => format!("attachment.{}", subtype),
+            None => "attachment".to_string(),
+        });
+    format!("{}-{}", file_part.id, name)
+}
+
+/// Payload of a `data:` URL, base64 decoded when marked as such
+pub fn decode_data_url(url: &str) -> Option<Vec<u8>> {
+    let (header, payload) = url.strip_prefix("data:")?.split_once(',')?;
+    if header.ends_with(";base64") {
+        decode_base64(payload)
+    } else {
+        Some(payload.as_bytes().to_vec())
+    }
+}
+
+/// Standard base64 decoding, ignoring whitespace and padding
+fn decode_base64(input: &str) -> Option<Vec<u8>> {
+    fn value(c: u8) -> Option<u32> {
+        match c {
+            b'A'..=b'Z' => Some((c - b'A') as u32),
+            b'a'..=b'z' => Some((c - b'a' + 26) as u32),
+            b'0'..=b'9' => Some((c - b'0' + 52) as u32),
+            b'+' => Some(62),
+            b'/' => Some(63),
+            _ => None,
+        }
+    }
+
+    let mut output = Vec::with_capacity(input.len() / 4 * 3);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for c in input.bytes() {
+        if c.is_ascii_whitespace() || c == b'=' {
+            continue;
+        }
+        buffer = (buffer << 6) | value(c)?;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            output.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    Some(output)
+}
+
+/// Open a file or URL with whatever the platform has registered for it
+pub fn open_with_platform_opener(target: impl AsRef<OsStr>) -> Result<()> {
+    let mut command = if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else if cfg!(target_os = "windows") {
+        let mut command = Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    } else {
+        Command::new("xdg-open")
+    };
+    command
+        .arg(target)
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .wrap_err("Failed to launch the system file opener")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file_part(filename: Option<&str>, mime: &str, url: &str) -> FilePart {
+        FilePart {
+            id: "prt_1".to_string(),
+            session_id: "ses_1".to_string(),
+            message_id: "msg_1".to_string(),
+            mime: mime.to_string(),
+            filename: filename.map(str::to_string),
+            url: url.to_string(),
+            source: None,
+        }
+    }
+
+    #[test]
+    fn test_decode_data_url() {
+        assert_eq!(
+            decode_data_url("data:image/png;base64,aGVsbG8="),
+            Some(b"hello".to_vec())
+        );
+        assert_eq!(
+            decode_data_url("data:image/png;base64,aGVs\nbG8h"),
+            Some(b"hello!".to_vec())
+        );
+        assert_eq!(decode_data_url("data:text/plain,hi"), Some(b"hi".to_vec()));
+        assert_eq!(decode_data_url("data:image/png;base64,a*b="), None);
+        assert_eq!(decode_data_url("file:///tmp/a.png"), None);
+    }
+
+    #[test]
+    fn test_download_file_name() {
+        let named = file_part(Some("../shots/screenshot.png"), "image/png", "");
+        assert_eq!(download_file_name(&named), "prt_1-screenshot.png");
+        let unnamed = file_part(None, "image/jpeg", "");
+        assert_eq!(download_file_name(&unnamed), "prt_1-attachment.jpeg");
+    }
+
+    #[tokio::test]
+    async fn test_data_url_is_written_to_download_dir() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let client = OpenCodeClient::new("http://localhost:0");
+        let part = file_part(
+            Some("shot.png"),
+            "image/png",
+            "data:image/png;base64,aGVsbG8=",
+        );
+
+        let path = local_path_for(&client, &part, temp_dir.path())
+            .await
+            .unwrap();
+        assert_eq!(path, temp_dir.path().join("prt_1-shot.png"));
+        assert_eq!(std::fs::read(&path).unwrap(), b"hello");
+
+        // Absolute file URLs are opened in place
+        let local = file_part(None, "image/png", "file:///tmp/shot.png");
+        assert_eq!(
+            local_path_for(&client, &local, temp_dir.path())
+                .await
+                .unwrap(),
+            PathBuf::from("/tmp/shot.png")
+        );
+    }
+}
@@ -0,0 +1,96 @@
+//! Inputs submitted this run
+//!
+//! A TUI left running for days against a busy session would otherwise keep
+//! every message ever typed, so the history holds only the latest entries,
+//! set with `input_history` in the `[retention]` table of the config file.
+
+use std::collections::VecDeque;
+
+/// Entries kept when the config file doesn't say
+pub const DEFAULT_INPUT_HISTORY_SIZE: usize = 500;
+
+/// The latest submitted inputs, oldest first, dropping the oldest once full
+#[derive(Debug, Clone, PartialEq)]
+pub struct InputHistory {
+    entries: VecDeque<String>,
+    capacity: usize,
+}
+
+impl InputHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::with_capacity(capacity.min(DEFAULT_INPUT_HISTORY_SIZE)),
+            capacity,
+        }
+    }
+
+    pub fn push(&mut self, input: String) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(input);
+    }
+
+    pub fn latest(&self) -> Option<&str> {
+        self.entries.back().map(String::as_str)
+    }
+
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &str> {
+        self.entries.iter().map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+impl Default for InputHistory {
+    fn default() -> Self {
+        Self::new(DEFAULT_INPUT_HISTORY_SIZE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_full_history_drops_the_oldest() {
+        let mut history = InputHistory::new(3);
+        for input in ["one", "two", "three"] {
+            history.push(input.to_string());
+        }
+        assert_eq!(history.iter().collect::<Vec<_>>(), ["one", "two", "three"]);
+
+        history.push("four".to_string());
+        history.push("five".to_string());
+        assert_eq!(history.len(), 3);
+        assert_eq!(
+            history.iter().collect::<Vec<_>>(),
+            ["three", "four", "five"]
+        );
+        assert_eq!(history.latest(), Some("five"));
+
+        history.clear();
+        assert!(history.is_empty());
+        assert_eq!(history.latest(), None);
+    }
+
+    #[test]
+    fn test_zero_capacity_keeps_nothing() {
+        let mut history = InputHistory::new(0);
+        history.push("hello".to_string());
+        assert!(history.is_empty());
+    }
+}
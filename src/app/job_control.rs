@@ -0,0 +1,61 @@
+//! Suspending to the shell with ctrl+z
+//!
+//! Raw mode swallows ctrl+z as a key, so the terminal never sends SIGTSTP
+//! itself. The key handler asks the terminal to restore the shell's screen
+//! and then stops the process by hand. Once the shell continues it with
+//! `fg`, a SIGCONT handler raises a flag that the program loop polls, to set
+//! the terminal back up and redraw. Job control only exists on unix, so
+//! elsewhere suspending does nothing.
+
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+/// Raised when the process is continued after being stopped
+#[derive(Debug, Clone, Default)]
+pub struct ResumeFlag(Arc<AtomicBool>);
+
+impl ResumeFlag {
+    /// A flag raised by SIGCONT
+    pub fn install() -> Self {
+        let flag = Self::default();
+        #[cfg(unix)]
+        if let Err(e) = signal_hook::flag::register(signal_hook::consts::SIGCONT, flag.0.clone()) {
+            tracing::warn!("Failed to install the SIGCONT handler: {}", e);
+        }
+        flag
+    }
+
+    pub fn raise(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    /// Whether the process was continued since the last call
+    pub fn take(&self) -> bool {
+        self.0.swap(false, Ordering::SeqCst)
+    }
+}
+
+/// Stop the process like the terminal's ctrl+z would. Returns once the
+/// shell continues it, or right away where there is no job control.
+pub fn suspend_process() -> std::io::Result<()> {
+    #[cfg(unix)]
+    signal_hook::low_level::raise(signal_hook::consts::SIGTSTP)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_take_clears_the_flag() {
+        let flag = ResumeFlag::default();
+        assert!(!flag.take());
+
+        flag.clone().raise();
+        assert!(flag.take());
+        assert!(!flag.take());
+    }
+}
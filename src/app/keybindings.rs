@@ -0,0 +1,271 @@
+//! The keys the app responds to, as they're shown to the user
+//!
+//! Both the help overlay and the shortcut hints on the status line are drawn
+//! from `KEYBINDINGS`, so a key that moves is changed here once, next to its
+//! handling in `event_sync_subscriptions`. Keys that only matter in some
+//! situations (a picker is open, tools are on screen) aren't in the help
+//! overlay, but are hinted while they apply.
+
+use crate::app::strings::HELP_COMMANDS;
+use crate::app::tea_model::{AppModalState, Model};
+
+/// Hints shown at once; past that the last slot cycles through the rest
+pub const MAX_HINTS: usize = 4;
+
+/// A situation in which some keys are worth hinting
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HintContext {
+    /// The main screen, ready for a message
+    Main,
+    /// A reply is running
+    Streaming,
+    /// Tool calls are in the log
+    ToolsVisible,
+    /// Finding text in the log
+    Find,
+    /// Selecting text in the log
+    Select,
+    /// Any of the pickers
+    Picker,
+    FilePicker,
+    SymbolSearch,
+    SessionPicker,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keybinding {
+    pub keys: &'static str,
+    pub action: &'static str,
+    /// Whether the help overlay lists it
+    pub in_help: bool,
+    /// The context it's hinted in, and its rank there, lowest first
+    pub hint: Option<(HintContext, u8)>,
+}
+
+impl Keybinding {
+    /// Listed in the help overlay
+    const fn help(keys: &'static str, action: &'static str) -> Self {
+        Self {
+            keys,
+            action,
+            in_help: true,
+            hint: None,
+        }
+    }
+
+    /// Only hinted, in `context`
+    const fn context(
+        keys: &'static str,
+        action: &'static str,
+        context: HintContext,
+        rank: u8,
+    ) -> Self {
+        Self {
+            keys,
+            action,
+            in_help: false,
+            hint: Some((context, rank)),
+        }
+    }
+
+    const fn hinted(mut self, context: HintContext, rank: u8) -> Self {
+        self.hint = Some((context, rank));
+        self
+    }
+
+    /// "^x l select session"
+    pub fn hint_text(&self) -> String {
+        format!("{} {}", self.keys, self.action)
+    }
+}
+
+pub const KEYBINDINGS: &[Keybinding] = &[
+    Keybinding::help("^x h/?", "help").hinted(HintContext::Main, 0),
+    Keybinding::help("^x l", "select session").hinted(HintContext::Main, 3),
+    Keybinding::help("^x `", "previous session"),
+    Keybinding::help("^x n", "new session"),
+    Keybinding::help("^x tab", "toggle view"),
+    Keybinding::help("^x o", "open latest image"),
+    Keybinding::help("^x t", "insert snippet").hinted(HintContext::Main, 5),
+    Keybinding::help("^x O", "jump to a symbol").hinted(HintContext::Main, 6),
+    Keybinding::help("^x p", "switch project"),
+    Keybinding::help("^x M", "request stats"),
+    Keybinding::help("^x r", "retry last message"),
+    Keybinding::help("^x v", "select text in the log"),
+    Keybinding::help("shift ←→", "scroll a code block sideways").hinted(HintContext::Select, 3),
+    Keybinding::help("^x /", "find in the log").hinted(HintContext::Main, 4),
+    Keybinding::help("^x f", "links in the last reply"),
+    Keybinding::help("^x P", "pin the latest tool output").hinted(HintContext::ToolsVisible, 1),
+    Keybinding::help("^x U", "unpin (alt+↑↓ scrolls it)"),
+    Keybinding::help("^x S", "save the latest tool output").hinted(HintContext::ToolsVisible, 2),
+    Keybinding::help("^x a", "tool activity").hinted(HintContext::Streaming, 0),
+    Keybinding::help("^x q", "quit"),
+    Keybinding::help("^z", "suspend"),
+    Keybinding::help("^u", "undo (^Z or ^_ redo)"),
+    Keybinding::context("@", "attach a file", HintContext::Main, 1),
+    Keybinding::context("tab", "cycle mode", HintContext::Main, 2),
+    Keybinding::context("^r", "tool details", HintContext::ToolsVisible, 0),
+    Keybinding::context("n/N", "next/previous match", HintContext::Find, 0),
+    Keybinding::context("/", "new search", HintContext::Find, 1),
+    Keybinding::context("esc", "close", HintContext::Find, 2),
+    Keybinding::context("v", "mark", HintContext::Select, 0),
+    Keybinding::context("y", "copy", HintContext::Select, 1),
+    Keybinding::context("esc", "close", HintContext::Select, 2),
+    Keybinding::context("space", "check", HintContext::FilePicker, 0),
+    Keybinding::context("^y", "copy location", HintContext::SymbolSearch, 0),
+    Keybinding::context("s", "sort", HintContext::SessionPicker, 0),
+    Keybinding::context("enter", "choose", HintContext::Picker, 0),
+    Keybinding::context("↑↓", "move", HintContext::Picker, 1),
+    Keybinding::context("esc", "close", HintContext::Picker, 2),
+];
+
+/// The help overlay's text: the listed keys, then the slash commands
+pub fn help_text() -> String {
+    let keys: String = KEYBINDINGS
+        .iter()
+        .filter(|binding| binding.in_help)
+        .map(|binding| format!("    {:<8} {}\n", binding.keys, binding.action))
+        .collect();
+    format!("\n{}{}", keys, HELP_COMMANDS)
+}
+
+/// The contexts that apply to the model, the most pressing first
+pub fn hint_contexts(model: &Model) -> Vec<HintContext> {
+    match &model.state {
+        AppModalState::None => {
+            let mut contexts = Vec::new();
+            if !model.session_is_idle {
+                contexts.push(HintContext::Streaming);
+            }
+            if model.message_state.tool_activity().next().is_some() {
+                contexts.push(HintContext::ToolsVisible);
+            }
+            contexts.push(HintContext::Main);
+            contexts
+        }
+        AppModalState::LogFind => vec![HintContext::Find],
+        AppModalState::LogSelect => vec![HintContext::Select],
+        AppModalState::ModalFileSelect => vec![HintContext::FilePicker, HintContext::Picker],
+        AppModalState::ModalSymbolSelect => vec![HintContext::SymbolSearch, HintContext::Picker],
+        AppModalState::ModalSessionSelect => vec![HintContext::SessionPicker, HintContext::Picker],
+        AppModalState::ModalSnippetSelect
+        | AppModalState::ModalProjectSelect
+        | AppModalState::ModalServerSelect => vec![HintContext::Picker],
+        // The other overlays spell out their keys, and connecting takes none
+        _ => Vec::new(),
+    }
+}
+
+/// Every binding hinted in `contexts`, in order of context and then rank
+pub fn hints_for(contexts: &[HintContext]) -> Vec<&'static Keybinding> {
+    contexts
+        .iter()
+        .flat_map(|context| {
+            let mut hinted: Vec<(u8, &'static Keybinding)> = KEYBINDINGS
+                .iter()
+                .filter_map(|binding| match binding.hint {
+                    Some((hint_context, rank)) if hint_context == *context => Some((rank, binding)),
+                    _ => None,
+                })
+                .collect();
+            hinted.sort_by_key(|(rank, _)| *rank);
+            hinted.into_iter().map(|(_, binding)| binding)
+        })
+        .collect()
+}
+
+/// Up to `MAX_HINTS` of `hints`: the first ones always, and the last slot
+/// taking its turn through the rest as `rotation` goes up
+pub fn rotate_hints(hints: &[&'static Keybinding], rotation: usize) -> Vec<&'static Keybinding> {
+    if hints.len() <= MAX_HINTS {
+        return hints.to_vec();
+    }
+    let (fixed, rest) = hints.split_at(MAX_HINTS - 1);
+    let mut shown = fixed.to_vec();
+    shown.push(rest[rotation % rest.len()]);
+    shown
+}
+
+/// The hints for the status line
+pub fn shortcut_hints(model: &Model) -> Vec<&'static Keybinding> {
+    rotate_hints(&hints_for(&hint_contexts(model)), model.hint_rotation)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::tea_model::ConnectionStatus;
+
+    fn keys(hints: &[&Keybinding]) -> Vec<&'static str> {
+        hints.iter().map(|binding| binding.keys).collect()
+    }
+
+    fn idle() -> Model {
+        let mut model = Model::new();
+        model.state = AppModalState::None;
+        model.connection_status = ConnectionStatus::Connected;
+        model.session_is_idle = true;
+        model
+    }
+
+    #[test]
+    fn test_hints_follow_the_state() {
+        let mut streaming = idle();
+        streaming.session_is_idle = false;
+        let mut finding = idle();
+        finding.state = AppModalState::LogFind;
+        let mut picking_files = idle();
+        picking_files.state = AppModalState::ModalFileSelect;
+        let mut searching_symbols = idle();
+        searching_symbols.state = AppModalState::ModalSymbolSelect;
+        let mut picking_snippets = idle();
+        picking_snippets.state = AppModalState::ModalSnippetSelect;
+        let mut in_help = idle();
+        in_help.state = AppModalState::ModalHelp;
+        let mut connecting = idle();
+        connecting.state = AppModalState::Connecting(ConnectionStatus::Connecting);
+
+        let cases = [
+            (idle(), vec!["^x h/?", "@", "tab", "^x l"]),
+            (streaming, vec!["^x a", "^x h/?", "@", "tab"]),
+            (finding, vec!["n/N", "/", "esc"]),
+            (picking_files, vec!["space", "enter", "↑↓", "esc"]),
+            (searching_symbols, vec!["^y", "enter", "↑↓", "esc"]),
+            (picking_snippets, vec!["enter", "↑↓", "esc"]),
+            (in_help, vec![]),
+            (connecting, vec![]),
+        ];
+        for (model, expected) in cases {
+            assert_eq!(keys(&shortcut_hints(&model)), expected, "{:?}", model.state);
+        }
+    }
+
+    #[test]
+    fn test_tool_hints_once_tools_are_in_the_log() {
+        let contexts = [HintContext::ToolsVisible, HintContext::Main];
+        assert_eq!(
+            keys(&rotate_hints(&hints_for(&contexts), 0)),
+            vec!["^r", "^x P", "^x S", "^x h/?"]
+        );
+    }
+
+    #[test]
+    fn test_last_slot_rotates_through_the_rest() {
+        let hints = hints_for(&[HintContext::Main]);
+        let last = |rotation| *keys(&rotate_hints(&hints, rotation)).last().unwrap();
+        assert_eq!(
+            (0..5).map(last).collect::<Vec<_>>(),
+            vec!["^x l", "^x /", "^x t", "^x O", "^x l"]
+        );
+        // The highest ranked stay put
+        assert_eq!(keys(&rotate_hints(&hints, 3))[..3], ["^x h/?", "@", "tab"]);
+    }
+
+    #[test]
+    fn test_help_lists_the_help_keys() {
+        let help = help_text();
+        assert!(help.starts_with("\n    ^x h/?   help\n    ^x l     select session\n"));
+        assert!(help.contains("    ^u       undo (^Z or ^_ redo)\n\n    /snippet <name>"));
+        assert!(!help.contains("tool details"));
+    }
+}
@@ -0,0 +1,362 @@
+//! File paths and URLs in assistant replies
+//!
+//! Replies name files ("see src/app/tea_update.rs:142") and link to pages,
+//! and both can be acted on from the links picker: a file is mentioned in
+//! the input, a URL is opened or copied. URLs are taken as they are, while
+//! anything that looks like a path only counts once the server has found a
+//! file by that name, since words like "e.g." or "and/or" look like paths
+//! too. Inside code blocks, paths in string literals are left alone.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::ops::Range;
+use std::sync::LazyLock;
+
+/// Links offered for one message, one per digit key
+pub const MAX_LINK_HINTS: usize = 9;
+/// Looked up paths remembered, dropping the least recently seen beyond
+const MAX_KNOWN_PATHS: usize = 1000;
+
+/// URLs, then paths with an extension or a directory, each optionally
+/// followed by `:line` and `:column`
+static LINK_PATTERN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r#"(?x)
+        (?P<url>https?://[^\s<>"'`()\[\]{}]+)
+        | (?P<path>
+            (?:\.{1,2}/)* (?:[\w.-]+/)* [\w-][\w.-]*\.[A-Za-z]\w*
+            | (?:\.{1,2}/)* (?:[\w.-]+/)+ [\w.-]*\w
+          )
+          (?::(?P<line>\d+))? (?::\d+)?
+        "#,
+    )
+    .expect("link pattern is a valid regex")
+});
+
+/// Punctuation that ends a sentence rather than a URL
+const URL_TRAILING_PUNCTUATION: &[char] = &['.', ',', ';', ':', '!', '?', '\''];
+
+/// What a link points at
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum LinkTarget {
+    File { path: String, line: Option<u32> },
+    Url(String),
+}
+
+impl LinkTarget {
+    /// How the link reads in the picker
+    pub fn label(&self) -> String {
+        match self {
+            LinkTarget::File {
+                path,
+                line: Some(line),
+            } => format!("{}:{}", path, line),
+            LinkTarget::File { path, line: None } => path.clone(),
+            LinkTarget::Url(url) => url.clone(),
+        }
+    }
+}
+
+/// A link in a line of text, by its byte range
+#[derive(Debug, Clone, PartialEq)]
+pub struct FoundLink {
+    pub range: Range<usize>,
+    pub target: LinkTarget,
+}
+
+/// What opening a URL link does
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UrlAction {
+    #[default]
+    Open,
+    Copy,
+}
+
+/// Links in one line of text. Paths aren't checked here, see `LinkPaths`.
+pub fn find_links_in_line(line: &str, in_code_block: bool) -> Vec<FoundLink> {
+    LINK_PATTERN
+        .captures_iter(line)
+        .filter_map(|captures| {
+            if let Some(url) = captures.name("url") {
+                let trimmed = url.as_str().trim_end_matches(URL_TRAILING_PUNCTUATION);
+                return Some(FoundLink {
+                    range: url.start()..url.start() + trimmed.len(),
+                    target: LinkTarget::Url(trimmed.to_string()),
+                });
+            }
+            let whole = captures.get(0)?;
+            let path = captures.name("path")?;
+            if in_code_block && in_string_literal(&line[..whole.start()]) {
+                return None;
+            }
+            Some(FoundLink {
+                range: whole.range(),
+                target: LinkTarget::File {
+                    path: path.as_str().trim_start_matches("./").to_string(),
+                    line: captures
+                        .name("line")
+                        .and_then(|line| line.as_str().parse().ok()),
+                },
+            })
+        })
+        .collect()
+}
+
+/// Links in `text`, line by line, noting which lines are in code blocks
+pub fn find_links(text: &str) -> Vec<LinkTarget> {
+    let mut in_code_block = false;
+    let mut links = Vec::new();
+    for line in text.lines() {
+        if line.trim_start().starts_with("```") {
+            in_code_block = !in_code_block;
+            continue;
+        }
+        links.extend(
+            find_links_in_line(line, in_code_block)
+                .into_iter()
+                .map(|link| link.target),
+        );
+    }
+    links
+}
+
+/// Whether a line that starts with `before` is inside a quoted string there
+fn in_string_literal(before: &str) -> bool {
+    ['"', '\'', '`']
+        .iter()
+        .any(|quote| before.matches(*quote).count() % 2 == 1)
+}
+
+/// Which of the paths found in replies name a file, as far as the server
+/// has answered, for the paths seen most recently
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LinkPaths {
+    exists: HashMap<String, bool>,
+    checking: HashSet<String>,
+    /// Paths in `exists`, least recently seen in a reply first
+    recent: VecDeque<String>,
+}
+
+impl LinkPaths {
+    /// Whether `path` was found on the server
+    pub fn exists(&self, path: &str) -> bool {
+        self.exists.get(path).copied().unwrap_or(false)
+    }
+
+    pub fn is_checking(&self) -> bool {
+        !self.checking.is_empty()
+    }
+
+    /// Paths of `links` that haven't been looked up, now marked as being
+    /// looked up
+    pub fn start_checking(&mut self, links: &[LinkTarget]) -> Vec<String> {
+        let mut unchecked = Vec::new();
+        for link in links {
+            if let LinkTarget::File { path, .. } = link {
+                if self.exists.contains_key(path) {
+                    self.touch(path);
+                } else if self.checking.insert(path.clone()) {
+                    unchecked.push(path.clone());
+                }
+            }
+        }
+        unchecked
+    }
+
+    /// Take in lookups, `None` for those that failed and can be tried again
+    pub fn record(&mut self, results: Vec<(String, Option<bool>)>) {
+        for (path, exists) in results {
+            self.checking.remove(&path);
+            if let Some(exists) = exists {
+                if self.exists.insert(path.clone(), exists).is_some() {
+                    self.touch(&path);
+                } else {
+                    self.recent.push_back(path);
+                }
+            }
+        }
+        while self.recent.len() > MAX_KNOWN_PATHS {
+            if let Some(oldest) = self.recent.pop_front() {
+                self.exists.remove(&oldest);
+            }
+        }
+    }
+
+    /// Mark `path` as the most recently seen
+    fn touch(&mut self, path: &str) {
+        if let Some(index) = self.recent.iter().position(|known| known == path) {
+            if let Some(known) = self.recent.remove(index) {
+                self.recent.push_back(known);
+            }
+        }
+    }
+}
+
+/// The links of one message that can be acted on, numbered from 1 in the
+/// order they appear
+#[derive(Debug, Clone, PartialEq)]
+pub struct MessageLinks {
+    pub message_id: String,
+    links: Vec<LinkTarget>,
+}
+
+impl MessageLinks {
+    /// Every URL in `texts` and every path known to name a file, without
+    /// repeats
+    pub fn new<'a>(
+        message_id: &str,
+        texts: impl IntoIterator<Item = &'a str>,
+        paths: &LinkPaths,
+    ) -> Self {
+        let mut links: Vec<LinkTarget> = Vec::new();
+        for link in texts.into_iter().flat_map(find_links) {
+            let usable = match &link {
+                LinkTarget::File { path, .. } => paths.exists(path),
+                LinkTarget::Url(_) => true,
+            };
+            if usable && !links.contains(&link) && links.len() < MAX_LINK_HINTS {
+                links.push(link);
+            }
+        }
+        Self {
+            message_id: message_id.to_string(),
+            links,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.links.is_empty()
+    }
+
+    /// Links with the digit that picks each
+    pub fn hints(&self) -> impl Iterator<Item = (char, &LinkTarget)> {
+        ('1'..='9').zip(&self.links)
+    }
+
+    /// The link picked by digit `hint`
+    pub fn get(&self, hint: char) -> Option<&LinkTarget> {
+        let index = hint.to_digit(10)?.checked_sub(1)?;
+        self.links.get(index as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(path: &str, line: Option<u32>) -> LinkTarget {
+        LinkTarget::File {
+            path: path.to_string(),
+            line,
+        }
+    }
+
+    fn url(url: &str) -> LinkTarget {
+        LinkTarget::Url(url.to_string())
+    }
+
+    #[test]
+    fn test_find_links_on_tricky_text() {
+        assert_eq!(
+            find_links("see src/app/tea_update.rs:142, then ./Cargo.toml."),
+            [
+                file("src/app/tea_update.rs", Some(142)),
+                file("Cargo.toml", None)
+            ]
+        );
+        // Sentence punctuation and markdown brackets aren't part of a URL
+        assert_eq!(
+            find_links(
+                "Docs: https://docs.rs/ratatui/latest/. See [the guide](https://ratatui.rs/faq)!"
+            ),
+            [
+                url("https://docs.rs/ratatui/latest/"),
+                url("https://ratatui.rs/faq")
+            ]
+        );
+        // Columns are dropped, versions and plain words aren't paths
+        assert_eq!(
+            find_links("at lib.rs:10:5 in v1.2.3 with no extension"),
+            [file("lib.rs", Some(10))]
+        );
+        assert_eq!(find_links("nothing to see here"), []);
+
+        // Paths in string literals of code are left alone, others kept
+        let text = "```rust\nlet path = \"config/app.toml\";\nmod src/main.rs\n```\n\"quoted.md\"";
+        assert_eq!(
+            find_links(text),
+            [file("src/main.rs", None), file("quoted.md", None)]
+        );
+    }
+
+    #[test]
+    fn test_link_ranges_cover_the_line_suffix() {
+        let line = "open ../README.md:3 or http://localhost:8080/x";
+        let links = find_links_in_line(line, false);
+        let texts: Vec<&str> = links.iter().map(|link| &line[link.range.clone()]).collect();
+        assert_eq!(texts, ["../README.md:3", "http://localhost:8080/x"]);
+        assert_eq!(links[0].target, file("../README.md", Some(3)));
+    }
+
+    #[test]
+    fn test_message_links_are_numbered_in_order() {
+        let mut paths = LinkPaths::default();
+        let text = "Edit src/lib.rs and src/gone.rs, per https://example.com and e.g. src/lib.rs";
+        let found = find_links(text);
+        assert_eq!(
+            paths.start_checking(&found),
+            ["src/lib.rs", "src/gone.rs", "e.g"]
+        );
+        // Already being looked up
+        assert!(paths.start_checking(&found).is_empty());
+        assert!(paths.is_checking());
+
+        paths.record(vec![
+            ("src/lib.rs".to_string(), Some(true)),
+            ("src/gone.rs".to_string(), Some(false)),
+            ("e.g".to_string(), None),
+        ]);
+        assert!(!paths.is_checking());
+        // A failed lookup is tried again
+        assert_eq!(paths.start_checking(&found), ["e.g"]);
+
+        let links = MessageLinks::new("msg_1", [text], &paths);
+        let hints: Vec<(char, &LinkTarget)> = links.hints().collect();
+        assert_eq!(
+            hints,
+            [
+                ('1', &file("src/lib.rs", None)),
+                ('2', &url("https://example.com"))
+            ]
+        );
+        assert_eq!(links.get('2'), Some(&url("https://example.com")));
+        assert_eq!(links.get('3'), None);
+        assert_eq!(links.get('0'), None);
+        assert_eq!(links.get('x'), None);
+    }
+
+    #[test]
+    fn test_least_recently_seen_paths_are_forgotten() {
+        let mut paths = LinkPaths::default();
+        let path = |i: usize| format!("src/file_{}.rs", i);
+        let seen = |i: usize| LinkTarget::File {
+            path: path(i),
+            line: None,
+        };
+        paths.record(
+            (0..MAX_KNOWN_PATHS)
+                .map(|i| (path(i), Some(true)))
+                .collect(),
+        );
+        // Seeing the first path again keeps it over the second
+        assert!(paths.start_checking(&[seen(0)]).is_empty());
+
+        paths.record(vec![(path(MAX_KNOWN_PATHS), Some(true))]);
+        assert!(paths.exists(&path(0)));
+        assert!(!paths.exists(&path(1)));
+        assert!(paths.exists(&path(MAX_KNOWN_PATHS)));
+        assert_eq!(paths.start_checking(&[seen(1)]), [path(1)]);
+    }
+}
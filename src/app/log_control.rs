@@ -0,0 +1,326 @@
+//! Log levels per module, changed while running
+//!
+//! The file log's filter is built from `RUST_LOG`, or the defaults in
+//! `logger`, and kept behind a reload handle. The `[log]` section of the
+//! config file and `/loglevel` set a level for a group of modules on top of
+//! it, so a noisy part can be quietened, or a quiet one opened up, without
+//! restarting:
+//!
+//! ```text
+//! /loglevel sdk warn
+//! /loglevel app::render off
+//! /loglevel app::events default
+//! /loglevel reset
+//! ```
+
+use crate::app::error::Result;
+use eyre::WrapErr;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+use tracing_subscriber::{reload, EnvFilter, Registry};
+
+/// `/loglevel <module> <level>` changes a module's level instead of sending
+pub const LOGLEVEL_COMMAND: &str = "/loglevel";
+
+/// A group of modules whose level is set together
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogModule {
+    /// The server client, ours and the generated one
+    Sdk,
+    /// The event loop: updates, subscriptions and async tasks
+    Events,
+    /// Drawing the view and its components
+    Render,
+}
+
+impl LogModule {
+    pub const ALL: [LogModule; 3] = [LogModule::Sdk, LogModule::Events, LogModule::Render];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LogModule::Sdk => "sdk",
+            LogModule::Events => "app::events",
+            LogModule::Render => "app::render",
+        }
+    }
+
+    /// The tracing targets the group covers, each with those below it
+    pub fn targets(self) -> &'static [&'static str] {
+        match self {
+            LogModule::Sdk => &["opencoders::sdk", "opencode_sdk"],
+            LogModule::Events => &[
+                "opencoders::app::app_program",
+                "opencoders::app::tea_update",
+                "opencoders::app::event_sync_subscriptions",
+                "opencoders::app::event_async_task_manager",
+                "opencoders::app::message_polling",
+            ],
+            LogModule::Render => &[
+                "opencoders::app::tea_view",
+                "opencoders::app::ui_components",
+                "opencoders::app::terminal",
+            ],
+        }
+    }
+}
+
+impl FromStr for LogModule {
+    type Err = String;
+
+    fn from_str(module: &str) -> std::result::Result<Self, Self::Err> {
+        match module {
+            "sdk" => Ok(LogModule::Sdk),
+            "events" | "app::events" => Ok(LogModule::Events),
+            "render" | "app::render" => Ok(LogModule::Render),
+            _ => Err(format!(
+                "unknown log module {:?}, expected sdk, app::events or app::render",
+                module
+            )),
+        }
+    }
+}
+
+/// The most detailed lines a module writes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Off,
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl LogLevel {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LogLevel::Off => "off",
+            LogLevel::Error => "error",
+            LogLevel::Warn => "warn",
+            LogLevel::Info => "info",
+            LogLevel::Debug => "debug",
+            LogLevel::Trace => "trace",
+        }
+    }
+}
+
+impl FromStr for LogLevel {
+    type Err = String;
+
+    fn from_str(level: &str) -> std::result::Result<Self, Self::Err> {
+        match level {
+            "off" => Ok(LogLevel::Off),
+            "error" => Ok(LogLevel::Error),
+            "warn" => Ok(LogLevel::Warn),
+            "info" => Ok(LogLevel::Info),
+            "debug" => Ok(LogLevel::Debug),
+            "trace" => Ok(LogLevel::Trace),
+            _ => Err(format!(
+                "unknown log level {:?}, expected off, error, warn, info, debug, trace or default",
+                level
+            )),
+        }
+    }
+}
+
+/// The levels set per module, on top of the base filter
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LogLevels {
+    levels: BTreeMap<LogModule, LogLevel>,
+}
+
+impl LogLevels {
+    /// Set `module`'s level, or leave it to the base filter with None
+    pub fn set(&mut self, module: LogModule, level: Option<LogLevel>) {
+        match level {
+            Some(level) => self.levels.insert(module, level),
+            None => self.levels.remove(&module),
+        };
+    }
+
+    pub fn get(&self, module: LogModule) -> Option<LogLevel> {
+        self.levels.get(&module).copied()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.levels.is_empty()
+    }
+
+    /// The filter directives: `base` without the directives for the
+    /// targets set here, then those targets at their module's level
+    pub fn directives(&self, base: &str) -> String {
+        let overridden: Vec<(&str, LogLevel)> = self
+            .levels
+            .iter()
+            .flat_map(|(module, level)| module.targets().iter().map(|target| (*target, *level)))
+            .collect();
+        base.split(',')
+            .map(str::trim)
+            .filter(|directive| !directive.is_empty())
+            .filter(|directive| {
+                let target = directive.split('=').next().unwrap_or_default();
+                !overridden
+                    .iter()
+                    .any(|(overridden, _)| *overridden == target)
+            })
+            .map(str::to_string)
+            .chain(
+                overridden
+                    .iter()
+                    .map(|(target, level)| format!("{}={}", target, level.as_str())),
+            )
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
+impl fmt::Display for LogLevels {
+    /// "sdk warn, app::events default, app::render off"
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let levels: Vec<String> = LogModule::ALL
+            .iter()
+            .map(|module| {
+                let level = self.get(*module).map_or("default", LogLevel::as_str);
+                format!("{} {}", module.as_str(), level)
+            })
+            .collect();
+        write!(f, "{}", levels.join(", "))
+    }
+}
+
+/// What a `/loglevel` command asks for
+#[derive(Debug, Clone, PartialEq)]
+pub enum LogLevelCommand {
+    /// Show the levels, for a bare `/loglevel`
+    Show,
+    /// Leave every module to the base filter
+    Reset,
+    /// Set a module's level, or with None leave it to the base filter
+    Set(LogModule, Option<LogLevel>),
+}
+
+/// The arguments of a `/loglevel` command, None for any other input
+pub fn parse_loglevel_command(input: &str) -> Option<&str> {
+    let rest = input.trim().strip_prefix(LOGLEVEL_COMMAND)?;
+    if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+    Some(rest.trim())
+}
+
+impl FromStr for LogLevelCommand {
+    type Err = String;
+
+    fn from_str(args: &str) -> std::result::Result<Self, Self::Err> {
+        let words: Vec<&str> = args.split_whitespace().collect();
+        match words.as_slice() {
+            [] => Ok(LogLevelCommand::Show),
+            ["reset"] => Ok(LogLevelCommand::Reset),
+            [module, "default"] => Ok(LogLevelCommand::Set(module.parse()?, None)),
+            [module, level] => Ok(LogLevelCommand::Set(module.parse()?, Some(level.parse()?))),
+            _ => Err(format!(
+                "Usage: {} <module> <level>, or {} reset",
+                LOGLEVEL_COMMAND, LOGLEVEL_COMMAND
+            )),
+        }
+    }
+}
+
+/// The file log's filter, kept from `logger::init` to change while running
+#[derive(Clone)]
+pub struct LogControl {
+    handle: reload::Handle<EnvFilter, Registry>,
+    /// The directives the filter started with
+    base: String,
+}
+
+impl fmt::Debug for LogControl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LogControl")
+            .field("base", &self.base)
+            .finish_non_exhaustive()
+    }
+}
+
+impl LogControl {
+    pub fn new(handle: reload::Handle<EnvFilter, Registry>, base: String) -> Self {
+        Self { handle, base }
+    }
+
+    /// Swap the filter for the base one with `levels` on top
+    pub fn apply(&self, levels: &LogLevels) -> Result<()> {
+        let directives = levels.directives(&self.base);
+        let filter = EnvFilter::try_new(&directives)
+            .wrap_err_with(|| format!("Invalid log filter {:?}", directives))?;
+        self.handle
+            .reload(filter)
+            .wrap_err("Failed to change the log filter")?;
+        tracing::info!("Log filter changed to {}", directives);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE: &str = "opencoders=debug,opencoders::profile=info,opencode_sdk=debug";
+
+    #[test]
+    fn test_directives_set_each_target_of_a_module() {
+        let mut levels = LogLevels::default();
+        assert_eq!(levels.directives(BASE), BASE);
+
+        levels.set(LogModule::Sdk, Some(LogLevel::Warn));
+        levels.set(LogModule::Render, Some(LogLevel::Off));
+        assert_eq!(
+            levels.directives(BASE),
+            "opencoders=debug,opencoders::profile=info,\
+             opencoders::sdk=warn,opencode_sdk=warn,\
+             opencoders::app::tea_view=off,opencoders::app::ui_components=off,\
+             opencoders::app::terminal=off"
+        );
+        assert!(EnvFilter::try_new(levels.directives(BASE)).is_ok());
+
+        // Back to the base filter's own
+        levels.set(LogModule::Sdk, None);
+        levels.set(LogModule::Render, None);
+        assert_eq!(levels.directives(BASE), BASE);
+    }
+
+    #[test]
+    fn test_parse_loglevel_command() {
+        let parse = |input| parse_loglevel_command(input).map(|args| args.parse());
+        assert_eq!(parse("/loglevel"), Some(Ok(LogLevelCommand::Show)));
+        assert_eq!(parse("/loglevel reset"), Some(Ok(LogLevelCommand::Reset)));
+        assert_eq!(
+            parse("/loglevel app::events trace"),
+            Some(Ok(LogLevelCommand::Set(
+                LogModule::Events,
+                Some(LogLevel::Trace)
+            )))
+        );
+        assert_eq!(
+            parse(" /loglevel render default "),
+            Some(Ok(LogLevelCommand::Set(LogModule::Render, None)))
+        );
+        assert!(matches!(parse("/loglevel ui debug"), Some(Err(_))));
+        assert!(matches!(parse("/loglevel sdk loud"), Some(Err(_))));
+        assert!(matches!(parse("/loglevel sdk"), Some(Err(_))));
+        assert_eq!(parse("/loglevels"), None);
+        assert_eq!(parse("set /loglevel sdk warn"), None);
+    }
+
+    #[test]
+    fn test_display_names_every_module() {
+        let mut levels = LogLevels::default();
+        levels.set(LogModule::Events, Some(LogLevel::Info));
+        assert_eq!(
+            levels.to_string(),
+            "sdk default, app::events info, app::render default"
+        );
+    }
+}
@@ -0,0 +1,113 @@
+//! Rate limiting for log lines on hot paths
+//!
+//! Streaming a reply runs the same handlers hundreds of times a second, and
+//! a debug line in each of them buried everything else in the log file.
+//! `debug_throttled!` writes its line at most once a second per call site,
+//! and the next one that goes out says how many were held back since.
+
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+/// Least time between two lines from the same call site
+pub const LOG_INTERVAL: Duration = Duration::from_secs(1);
+
+/// When a call site last logged, and how often it was held back since
+pub struct Throttle {
+    interval: Duration,
+    state: Mutex<ThrottleState>,
+}
+
+struct ThrottleState {
+    last: Option<Instant>,
+    suppressed: u64,
+}
+
+impl Throttle {
+    pub const fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            state: Mutex::new(ThrottleState {
+                last: None,
+                suppressed: 0,
+            }),
+        }
+    }
+
+    /// Whether a line at `now` goes out, with the number held back since
+    /// the last one that did, or None to hold this one back too
+    pub fn check(&self, now: Instant) -> Option<u64> {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        match state.last {
+            Some(last) if now.saturating_duration_since(last) < self.interval => {
+                state.suppressed += 1;
+                None
+            }
+            _ => {
+                state.last = Some(now);
+                Some(std::mem::take(&mut state.suppressed))
+            }
+        }
+    }
+}
+
+/// " (12 suppressed)" after a line that others were held back before
+pub fn suppressed_suffix(suppressed: u64) -> String {
+    if suppressed == 0 {
+        String::new()
+    } else {
+        format!(" ({} suppressed)", suppressed)
+    }
+}
+
+/// `tracing::debug!`, at most once per `LOG_INTERVAL` from each call site
+macro_rules! debug_throttled {
+    ($($arg:tt)+) => {{
+        if tracing::enabled!(tracing::Level::DEBUG) {
+            static THROTTLE: $crate::app::log_throttle::Throttle =
+                $crate::app::log_throttle::Throttle::new($crate::app::log_throttle::LOG_INTERVAL);
+            if let Some(suppressed) = THROTTLE.check(std::time::Instant::now()) {
+                tracing::debug!(
+                    "{}{}",
+                    format_args!($($arg)+),
+                    $crate::app::log_throttle::suppressed_suffix(suppressed)
+                );
+            }
+        }
+    }};
+}
+pub(crate) use debug_throttled;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_once_per_interval() {
+        let throttle = Throttle::new(LOG_INTERVAL);
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+        assert_eq!(throttle.check(at(0)), Some(0));
+        assert_eq!(throttle.check(at(1)), None);
+        assert_eq!(throttle.check(at(999)), None);
+        // The interval runs from the last line that went out
+        assert_eq!(throttle.check(at(1000)), Some(2));
+        assert_eq!(throttle.check(at(1500)), None);
+        assert_eq!(throttle.check(at(2000)), Some(1));
+        // Nothing held back after a quiet spell
+        assert_eq!(throttle.check(at(5000)), Some(0));
+    }
+
+    #[test]
+    fn test_suppressed_count_is_reported() {
+        let throttle = Throttle::new(LOG_INTERVAL);
+        let start = Instant::now();
+        throttle.check(start);
+        for ms in 1..=250 {
+            assert_eq!(throttle.check(start + Duration::from_millis(ms)), None);
+        }
+        let suppressed = throttle.check(start + LOG_INTERVAL).unwrap();
+        assert_eq!(suppressed, 250);
+        assert_eq!(suppressed_suffix(suppressed), " (250 suppressed)");
+        assert_eq!(suppressed_suffix(0), "");
+    }
+}
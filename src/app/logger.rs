@@ -22,12 +22,23 @@
 //!
 //! - `OPENCODE_LOG_DIR`: Override log directory (default: `~/.opencode/logs`)
 //! - `RUST_LOG`: Override log levels (e.g., `RUST_LOG=opencoders=trace`)
+//!
+//! The filter can be changed while running, per group of modules, with the
+//! [`LogControl`] returned by [`init`]; see `log_control`.
+//!
+//! ## Profiling
+//!
+//! The spans timing each update and frame are kept out of the log file unless
+//! `RUST_LOG` asks for `opencoders::profile=debug`. Started with `--profile`,
+//! they're collected for the report printed on exit; see [`Profile`].
 
-use crate::app::error::Result;
+use crate::app::{error::Result, log_control::LogControl, profile::Profile};
 use eyre::WrapErr;
 use std::path::PathBuf;
 use tracing_appender::rolling;
-use tracing_subscriber::{self, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer};
+use tracing_subscriber::{self, layer::SubscriberExt, reload, util::SubscriberInitExt, EnvFilter, Layer};
+#[cfg(debug_assertions)]
+use tracing_subscriber::fmt::format::FmtSpan;
 
 /// Logger guard that ensures proper cleanup of logging resources
 pub struct LoggerGuard {
@@ -40,20 +51,34 @@ impl LoggerGuard {
     }
 }
 
-pub fn init() -> Result<LoggerGuard> {
-    let log_dir = get_log_directory();
+/// Start logging to a file, feeding `profile` too when one is given, with
+/// the handle to change the file's filter by
+pub fn init(profile: Option<&Profile>) -> Result<(LoggerGuard, LogControl)> {
+    let log_dir = log_directory();
     
     #[cfg(debug_assertions)]
     {
-        init_debug_tracing(&log_dir)
+        init_debug_tracing(&log_dir, profile)
     }
     #[cfg(not(debug_assertions))]
     {
-        init_release_tracing(&log_dir)
+        init_release_tracing(&log_dir, profile)
     }
 }
 
-fn get_log_directory() -> PathBuf {
+/// The filter `RUST_LOG` asks for, or `default` when it's unset or invalid,
+/// behind a handle to reload it with
+fn reloadable_filter(default: &str) -> (reload::Layer<EnvFilter, tracing_subscriber::Registry>, LogControl) {
+    let base = std::env::var(EnvFilter::DEFAULT_ENV)
+        .ok()
+        .filter(|directives| EnvFilter::try_new(directives).is_ok())
+        .unwrap_or_else(|| default.to_string());
+    let (filter, handle) = reload::Layer::new(EnvFilter::new(&base));
+    (filter, LogControl::new(handle, base))
+}
+
+/// Directory the log files are written to
+pub fn log_directory() -> PathBuf {
     if let Ok(dir) = std::env::var("OPENCODE_LOG_DIR") {
         PathBuf::from(dir)
     } else if let Some(home) = dirs::home_dir() {
@@ -64,10 +89,11 @@ fn get_log_directory() -> PathBuf {
 }
 
 #[cfg(debug_assertions)]
-fn init_debug_tracing(log_dir: &PathBuf) -> Result<LoggerGuard> {
+fn init_debug_tracing(log_dir: &PathBuf, profile: Option<&Profile>) -> Result<(LoggerGuard, LogControl)> {
     std::fs::create_dir_all(log_dir).wrap_err("Failed to create log directory")?;
     
     let log_file = rolling::daily(log_dir, "opencode-debug.log");
+    let (filter, control) = reloadable_filter("opencoders=debug,opencoders::profile=info,opencode_sdk=debug");
     let (non_blocking_log_file, guard) = tracing_appender::non_blocking(log_file);
     
     let file_layer = tracing_subscriber::fmt::layer()
@@ -78,26 +104,27 @@ fn init_debug_tracing(log_dir: &PathBuf) -> Result<LoggerGuard> {
         .with_file(true)
         .with_line_number(true)
         .with_target(true)
-        .with_filter(
-            EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| EnvFilter::new("opencoders=debug,opencode_sdk=debug"))
-        );
+        // Closing a span logs how long it took, e.g. the steps of starting up
+        .with_span_events(FmtSpan::CLOSE)
+        .with_filter(filter);
 
     tracing_subscriber::registry()
         .with(file_layer)
+        .with(profile.map(Profile::layer))
         .try_init()
         .wrap_err("Failed to initialize tracing subscriber")?;
     
     tracing::info!("Debug tracing initialized with detailed logging to: {}", log_dir.display());
-    Ok(LoggerGuard::new(guard))
+    Ok((LoggerGuard::new(guard), control))
 }
 
 #[cfg(not(debug_assertions))]
-fn init_release_tracing(log_dir: &PathBuf) -> Result<LoggerGuard> {
+fn init_release_tracing(log_dir: &PathBuf, profile: Option<&Profile>) -> Result<(LoggerGuard, LogControl)> {
     std::fs::create_dir_all(log_dir)
         .wrap_err("Failed to create log directory")?;
     
     let log_file = rolling::daily(log_dir, "opencode.log");
+    let (filter, control) = reloadable_filter("opencoders=info,opencode_sdk=warn");
     let (non_blocking_log_file, guard) = tracing_appender::non_blocking(log_file);
     
     let file_layer = tracing_subscriber::fmt::layer()
@@ -109,16 +136,14 @@ fn init_release_tracing(log_dir: &PathBuf) -> Result<LoggerGuard> {
         .with_line_number(false)
         .with_target(false)
         .compact()
-        .with_filter(
-            EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| EnvFilter::new("opencoders=info,opencode_sdk=warn"))
-        );
+        .with_filter(filter);
 
     tracing_subscriber::registry()
         .with(file_layer)
+        .with(profile.map(Profile::layer))
         .try_init()
         .wrap_err("Failed to initialize tracing subscriber")?;
     
     tracing::info!("Release tracing initialized with optimized logging to: {}", log_dir.display());
-    Ok(LoggerGuard::new(guard))
+    Ok((LoggerGuard::new(guard), control))
 }
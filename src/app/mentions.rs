@@ -0,0 +1,114 @@
+//! `@` file mentions in the input
+//!
+//! Files picked with the `@` picker are inserted as `@path`, relative to the
+//! workspace root so they match what the server's tools expect. Before a
+//! message with mentions is sent, the paths are checked against the server in
+//! case a file was deleted since the file list was loaded. A mention can
+//! point at a line, as in `@src/main.rs#42`, which the check ignores.
+
+pub use crate::sdk::extensions::files::workspace_relative_path;
+
+/// Marks a file mention in the input
+pub const MENTION_PREFIX: char = '@';
+
+/// Separates the line from the path in a mention
+pub const LINE_SEPARATOR: char = '#';
+
+/// Matches mentions, for highlighting them in the input
+pub const MENTION_PATTERN: &str = r"@[^\s@]+";
+
+/// Punctuation that ends a sentence rather than a path
+const TRAILING_PUNCTUATION: &[char] = &[',', '.', ';', ':', '!', '?', ')', '"', '\''];
+
+/// Paths mentioned in `text`, in order and without duplicates
+pub fn mentioned_paths(text: &str) -> Vec<String> {
+    let mut paths: Vec<String> = Vec::new();
+    for word in text.split_whitespace() {
+        let Some(path) = word.strip_prefix(MENTION_PREFIX) else {
+            continue;
+        };
+        let path = without_line(path.trim_end_matches(TRAILING_PUNCTUATION));
+        if !path.is_empty() && !paths.iter().any(|existing| existing == path) {
+            paths.push(path.to_string());
+        }
+    }
+    paths
+}
+
+/// `path` without a `#42` line after it
+fn without_line(path: &str) -> &str {
+    match path.rsplit_once(LINE_SEPARATOR) {
+        Some((file, line)) if !line.is_empty() && line.chars().all(|c| c.is_ascii_digit()) => file,
+        _ => path,
+    }
+}
+
+/// Notification asking whether to send a message whose mentions are missing
+pub fn missing_mentions_notice(missing: &[String]) -> String {
+    let verb = if missing.len() == 1 {
+        "exists"
+    } else {
+        "exist"
+    };
+    format!(
+        "{} no longer {} — send anyway? Enter to send, edit to cancel",
+        missing.join(", "),
+        verb
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_workspace_relative_path() {
+        let root = Some("/home/me/project");
+        assert_eq!(
+            workspace_relative_path("/home/me/project/src/app/mod.rs", root),
+            "src/app/mod.rs"
+        );
+        assert_eq!(
+            workspace_relative_path("/home/me/project/docs/report.md", Some("/home/me/project/")),
+            "docs/report.md"
+        );
+        assert_eq!(
+            workspace_relative_path("./src/main.rs", root),
+            "src/main.rs"
+        );
+        assert_eq!(workspace_relative_path("src/main.rs", root), "src/main.rs");
+        // Only whole directory names match the root
+        assert_eq!(
+            workspace_relative_path("/home/me/project-old/notes.md", root),
+            "/home/me/project-old/notes.md"
+        );
+        assert_eq!(workspace_relative_path("/etc/hosts", root), "/etc/hosts");
+        assert_eq!(workspace_relative_path("src/lib.rs", None), "src/lib.rs");
+    }
+
+    #[test]
+    fn test_mentioned_paths() {
+        assert_eq!(
+            mentioned_paths("Compare @src/a.rs and @docs/report.md, then @src/a.rs."),
+            vec!["src/a.rs", "docs/report.md"]
+        );
+        assert!(mentioned_paths("mail me@example.com about it @").is_empty());
+        // Lines aren't part of the path
+        assert_eq!(
+            mentioned_paths("see @src/a.rs#42 and @src/a.rs, not @notes#draft"),
+            vec!["src/a.rs", "notes#draft"]
+        );
+    }
+
+    #[test]
+    fn test_missing_mentions_notice() {
+        assert_eq!(
+            missing_mentions_notice(&["report.md".to_string()]),
+            "report.md no longer exists — send anyway? Enter to send, edit to cancel"
+        );
+        assert!(
+            missing_mentions_notice(&["a.md".to_string(), "b.md".to_string()])
+                .starts_with("a.md, b.md no longer exist")
+        );
+    }
+}
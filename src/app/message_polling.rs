@@ -0,0 +1,62 @@
+//! Polling for messages while the event stream is down
+//!
+//! Some proxies break the event stream entirely, which would leave the
+//! transcript frozen even though sends go through. Once the stream has
+//! failed for good, the current session's latest page of messages is fetched
+//! on a timer and merged into the message state. Polls come quickly while a
+//! response is running or messages are changing, and back off while nothing
+//! happens. A stream that connects again ends the polling.
+
+use crate::app::message_state::MessageState;
+use opencode_sdk::models::Message;
+
+/// Delay between polls while something is happening
+pub const POLL_ACTIVE_MS: u64 = 2_000;
+/// Longest delay between polls, reached by doubling while nothing changes
+pub const POLL_IDLE_MS: u64 = 10_000;
+
+/// Delay before the next poll, after one that came `previous_ms` after the
+/// one before it
+pub fn next_poll_ms(previous_ms: u64, changed: bool, busy: bool) -> u64 {
+    if changed || busy {
+        POLL_ACTIVE_MS
+    } else {
+        previous_ms
+            .saturating_mul(2)
+            .clamp(POLL_ACTIVE_MS, POLL_IDLE_MS)
+    }
+}
+
+/// Whether the latest message is a completed reply, which without events is
+/// how the session is seen to go idle. A message just sent is shown right
+/// away, so a poll from before the server had it doesn't count the previous
+/// reply.
+pub fn reply_completed(state: &MessageState) -> bool {
+    state
+        .get_all_message_containers()
+        .last()
+        .is_some_and(|container| {
+            matches!(
+                &container.info,
+                Message::Assistant(reply) if reply.time.completed.is_some()
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_polls_back_off_while_nothing_happens() {
+        let mut delay = POLL_ACTIVE_MS;
+        let mut delays = vec![];
+        for _ in 0..4 {
+            delay = next_poll_ms(delay, false, false);
+            delays.push(delay);
+        }
+        assert_eq!(delays, vec![4_000, 8_000, 10_000, 10_000]);
+        assert_eq!(next_poll_ms(POLL_IDLE_MS, true, false), POLL_ACTIVE_MS);
+        assert_eq!(next_poll_ms(POLL_IDLE_MS, false, true), POLL_ACTIVE_MS);
+    }
+}
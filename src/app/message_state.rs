@@ -1,7 +1,19 @@
-use opencode_sdk::models::{SessionMessages200ResponseInner, Message, Part};
-use std::collections::{HashMap, HashSet};
+use crate::app::ui_components::message_part::{is_image_mime, MessageRenderer};
+use crate::sdk::id::{generate_id, IdPrefix};
+use opencode_sdk::models::{
+    AssistantMessageTokens, FilePart, SessionMessages200ResponseInner, Message, Part, TextPart,
+    ToolPart, ToolState, ToolStateCompleted, UserMessage, UserMessageTime,
+};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::time::SystemTime;
 
+/// Messages fetched per page, so long sessions open without loading all
+/// of their history
+pub const MESSAGE_PAGE_SIZE: usize = 50;
+
+/// Provider and model of a reply whose message info hasn't arrived yet
+pub const UNKNOWN_MODEL: &str = "unknown";
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct MessageState {
     // Indexed storage for efficient updates
@@ -13,6 +25,121 @@ pub struct MessageState {
     
     // Streaming state tracking
     streaming_messages: HashSet<String>, // message IDs currently streaming
+    
+    // Messages kept before the oldest printed ones are dropped, if limited
+    retained_messages: Option<usize>,
+    // Messages dropped from the start of the session and not loaded again
+    dropped: usize,
+    // Model and tokens of each reply this session, kept when the reply is
+    // dropped by retention
+    reply_models: HashMap<String, ReplyModel>, // message_id -> ReplyModel
+    // Every tool call in the loaded messages, kept up to date part by part so
+    // the activity log doesn't walk all the parts to list them
+    tool_activity: BTreeMap<(String, String), ToolActivity>, // (message_id, part_id) -> ToolActivity
+    // What events changed while the session's messages were loading, which
+    // the loaded copy mustn't overwrite
+    load_window: Option<LoadWindow>,
+}
+
+/// Messages and parts changed by events since a load was requested
+#[derive(Debug, Clone, Default, PartialEq)]
+struct LoadWindow {
+    infos: HashSet<String>,           // message ids
+    parts: HashSet<(String, String)>, // (message_id, part_id)
+    removed: HashSet<String>,         // message ids
+}
+
+impl LoadWindow {
+    fn touches(&self, message_id: &str) -> bool {
+        self.infos.contains(message_id) || self.parts.iter().any(|(id, _)| id == message_id)
+    }
+}
+
+/// The provider and model a reply came from, and the tokens it used
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplyModel {
+    pub provider_id: String,
+    pub model_id: String,
+    pub tokens: f64,
+}
+
+/// Replies and tokens of one model over the session, see `usage_by_model`
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ModelUsage {
+    pub provider_id: String,
+    pub model_id: String,
+    pub replies: usize,
+    pub tokens: f64,
+}
+
+/// One tool call, as listed in the activity log
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ToolActivity {
+    pub message_id: String,
+    pub part_id: String,
+    pub tool: String,
+    /// The main argument on one line, as in the tool's header in the log
+    pub args: String,
+    pub status: ToolStatus,
+    /// When the tool started running, in ms since the epoch
+    pub started_ms: Option<f64>,
+    /// How long it ran, once it finished
+    pub duration_ms: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum ToolStatus {
+    Pending,
+    Running,
+    Completed,
+    Failed,
+}
+
+impl ToolActivity {
+    pub fn from_part(tool_part: &ToolPart) -> Self {
+        let (status, started_ms, duration_ms) = match &*tool_part.state {
+            ToolState::Pending(_) => (ToolStatus::Pending, None, None),
+            ToolState::Running(running) => (ToolStatus::Running, Some(running.time.start), None),
+            ToolState::Completed(completed) => (
+                ToolStatus::Completed,
+                Some(completed.time.start),
+                Some(completed.time.end - completed.time.start),
+            ),
+            ToolState::Error(error) => (
+                ToolStatus::Failed,
+                Some(error.time.start),
+                Some(error.time.end - error.time.start),
+            ),
+        };
+        Self {
+            message_id: tool_part.message_id.clone(),
+            part_id: tool_part.id.clone(),
+            tool: tool_part.tool.clone(),
+            args: MessageRenderer::format_tool_args(tool_part),
+            status,
+            started_ms,
+            duration_ms: duration_ms.map(|duration: f64| duration.max(0.0)),
+        }
+    }
+}
+
+/// Outcome of applying a message event to the state
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpdateOutcome {
+    Applied,
+    /// Nothing matched, e.g. removing a message that isn't loaded
+    Unchanged,
+    /// The event belongs to a different session
+    OtherSession,
+    /// No session is set yet, so the event can't be attributed; the caller
+    /// may hold on to it until one is
+    NoSession,
+}
+
+impl UpdateOutcome {
+    pub fn is_applied(self) -> bool {
+        self == UpdateOutcome::Applied
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -23,6 +150,10 @@ pub struct MessageContainer {
     pub is_streaming: bool,
     pub last_updated: SystemTime,
     pub printed_to_stdout: bool, // Track if this message has been printed to stdout
+    /// Shown locally from the typed text until the server echoes the message
+    pub provisional: bool,
+    /// The send request for a provisional message failed
+    pub send_failed: bool,
 }
 
 impl MessageContainer {
@@ -51,65 +182,293 @@ impl MessageState {
             message_order: Vec::new(),
             current_session_id: None,
             streaming_messages: HashSet::new(),
+            retained_messages: None,
+            dropped: 0,
+            reply_models: HashMap::new(),
+            tool_activity: BTreeMap::new(),
+            load_window: None,
         }
     }
 
+    pub fn set_retained_messages(&mut self, retained_messages: Option<usize>) {
+        self.retained_messages = retained_messages;
+    }
+
     pub fn set_session_id(&mut self, session_id: Option<String>) {
         if self.current_session_id != session_id {
             // Clear messages when switching sessions
             self.clear();
+            self.reply_models.clear();
+            self.load_window = None;
             self.current_session_id = session_id;
         }
     }
 
+    pub fn session_id(&self) -> Option<&str> {
+        self.current_session_id.as_deref()
+    }
+
+    /// Whether an event for `session_id` may be applied
+    fn check_session(&self, session_id: &str) -> Option<UpdateOutcome> {
+        match &self.current_session_id {
+            None => Some(UpdateOutcome::NoSession),
+            Some(current_session) if current_session != session_id => {
+                Some(UpdateOutcome::OtherSession)
+            }
+            Some(_) => None,
+        }
+    }
+
     pub fn clear(&mut self) {
         self.messages.clear();
         self.message_order.clear();
         self.streaming_messages.clear();
+        self.dropped = 0;
+        self.tool_activity.clear();
     }
 
     pub fn is_empty(&self) -> bool {
         self.messages.is_empty()
     }
 
+    pub fn message_count(&self) -> usize {
+        self.message_order.len()
+    }
+
+    /// Note that the session's messages are being fetched, so the events
+    /// arriving until `load_messages` are kept over the fetched copy, which
+    /// may predate them
+    pub fn begin_load(&mut self) {
+        self.load_window = Some(LoadWindow::default());
+    }
+
+    /// The fetch failed, so what's shown stays as the events left it
+    pub fn cancel_load(&mut self) {
+        self.load_window = None;
+    }
+
+    /// Replace the messages with the fetched ones, except for what events
+    /// changed since `begin_load`
     pub fn load_messages(&mut self, messages: Vec<SessionMessages200ResponseInner>) {
+        let window = self.load_window.take().unwrap_or_default();
+        let live: Vec<MessageContainer> = self
+            .message_order
+            .iter()
+            .filter(|message_id| window.touches(message_id))
+            .filter_map(|message_id| self.messages.get(message_id).cloned())
+            .collect();
         self.clear();
         
         for msg_container in messages {
             let message_id = self.extract_message_id(&msg_container.info);
-            
-            let mut parts_map = HashMap::new();
-            let mut part_order = Vec::new();
-            
-            for part in msg_container.parts {
-                let part_id = self.extract_part_id(&part);
-                part_order.push(part_id.clone());
-                parts_map.insert(part_id, part);
+            if window.removed.contains(&message_id) {
+                continue;
             }
-            
-            let container = MessageContainer {
-                info: *msg_container.info,
-                parts: parts_map,
-                part_order,
-                is_streaming: false,
-                last_updated: SystemTime::now(),
-                printed_to_stdout: false, // Loaded messages should be printed in inline mode
-            };
+            // Loaded messages should be printed in inline mode
+            let container = self.loaded_container(msg_container, false);
+            self.record_reply_model(&container.info);
+            self.index_tool_activity(&container);
             
             self.messages.insert(message_id.clone(), container);
             self.message_order.push(message_id);
         }
+
+        for live in live {
+            let message_id = self.extract_message_id(&live.info);
+            match self.messages.get_mut(&message_id) {
+                Some(container) => {
+                    if window.infos.contains(&message_id) {
+                        container.info = live.info;
+                    }
+                    for (part_id, part) in live.parts {
+                        if !window.parts.contains(&(message_id.clone(), part_id.clone())) {
+                            continue;
+                        }
+                        if !container.parts.contains_key(&part_id) {
+                            Self::insert_part_in_order(&mut container.part_order, part_id.clone());
+                        }
+                        container.parts.insert(part_id, part);
+                    }
+                    container.is_streaming = live.is_streaming;
+                    container.last_updated = live.last_updated;
+                }
+                None => {
+                    self.messages.insert(message_id.clone(), live);
+                    self.insert_message_in_order(message_id.clone());
+                }
+            }
+            if let Some(container) = self.messages.get(&message_id).cloned() {
+                if container.is_streaming {
+                    self.streaming_messages.insert(message_id.clone());
+                }
+                self.record_reply_model(&container.info);
+                self.unindex_tool_activity(&message_id);
+                self.index_tool_activity(&container);
+            }
+        }
+    }
+
+    /// Add a page of earlier history. Messages already loaded are kept as they
+    /// are, since events may have updated them since the page was fetched.
+    /// Returns the IDs of the messages added.
+    pub fn prepend_messages(&mut self, messages: Vec<SessionMessages200ResponseInner>) -> Vec<String> {
+        let mut added = Vec::new();
+        for msg_container in messages {
+            let message_id = self.extract_message_id(&msg_container.info);
+            if self.messages.contains_key(&message_id) {
+                continue;
+            }
+            // History from before the scrollback isn't printed out of order
+            let container = self.loaded_container(msg_container, true);
+            self.record_reply_model(&container.info);
+            self.index_tool_activity(&container);
+            
+            self.messages.insert(message_id.clone(), container);
+            self.insert_message_in_order(message_id.clone());
+            added.push(message_id);
+        }
+        self.dropped = self.dropped.saturating_sub(added.len());
+        added
+    }
+
+    /// Merge a freshly fetched page of the latest messages, for when there
+    /// are no events to follow. New messages are added and changed ones
+    /// replaced, keeping whether they were printed; unchanged ones are left
+    /// as they are. A reply is streaming until it has a completed time.
+    /// Returns the IDs added or changed, in order.
+    pub fn merge_messages(&mut self, messages: Vec<SessionMessages200ResponseInner>) -> Vec<String> {
+        let mut changed = Vec::new();
+        for msg_container in messages {
+            let message_id = self.extract_message_id(&msg_container.info);
+            let fetched = self.loaded_container(msg_container, false);
+            let streaming = matches!(
+                &fetched.info,
+                Message::Assistant(reply) if reply.time.completed.is_none()
+            );
+            match self.messages.get_mut(&message_id) {
+                Some(existing)
+                    if !existing.provisional
+                        && existing.info == fetched.info
+                        && existing.part_order == fetched.part_order
+                        && existing.parts == fetched.parts =>
+                {
+                    continue;
+                }
+                Some(existing) => {
+                    existing.info = fetched.info;
+                    existing.parts = fetched.parts;
+                    existing.part_order = fetched.part_order;
+                    existing.last_updated = SystemTime::now();
+                    existing.is_streaming = streaming;
+                    existing.provisional = false;
+                    existing.send_failed = false;
+                }
+                None => {
+                    self.messages.insert(
+                        message_id.clone(),
+                        MessageContainer {
+                            is_streaming: streaming,
+                            ..fetched
+                        },
+                    );
+                    self.insert_message_in_order(message_id.clone());
+                }
+            }
+            if streaming {
+                self.streaming_messages.insert(message_id.clone());
+            } else {
+                self.streaming_messages.remove(&message_id);
+            }
+            if let Some(container) = self.messages.get(&message_id).cloned() {
+                self.record_reply_model(&container.info);
+                self.unindex_tool_activity(&message_id);
+                self.index_tool_activity(&container);
+            }
+            changed.push(message_id);
+        }
+        changed
+    }
+
+    /// Drop the oldest messages beyond the retention limit, as long as they
+    /// have been printed and are done streaming. Only a run from the start
+    /// is dropped, so they load again a page at a time before the first
+    /// message kept. Returns the IDs dropped.
+    pub fn drop_beyond_retention(&mut self) -> Vec<String> {
+        let Some(retained) = self.retained_messages else {
+            return Vec::new();
+        };
+        let excess = self.message_order.len().saturating_sub(retained);
+        let dropped: Vec<String> = self
+            .message_order
+            .iter()
+            .take(excess)
+            .take_while(|message_id| {
+                !self.streaming_messages.contains(*message_id)
+                    && self.messages.get(*message_id).is_some_and(|container| {
+                        container.printed_to_stdout
+                            && !container.is_streaming
+                            && !container.provisional
+                    })
+            })
+            .cloned()
+            .collect();
+        for message_id in &dropped {
+            self.messages.remove(message_id);
+            self.unindex_tool_activity(message_id);
+        }
+        self.message_order.drain(..dropped.len());
+        self.dropped += dropped.len();
+        dropped
+    }
+
+    /// Messages dropped by the retention limit that haven't been loaded again
+    pub fn dropped_count(&self) -> usize {
+        self.dropped
+    }
+
+    /// ID of the earliest loaded message
+    pub fn first_message_id(&self) -> Option<&str> {
+        self.message_order.first().map(String::as_str)
+    }
+
+    fn loaded_container(
+        &self,
+        msg_container: SessionMessages200ResponseInner,
+        printed_to_stdout: bool,
+    ) -> MessageContainer {
+        let mut parts_map = HashMap::new();
+        let mut part_order = Vec::new();
+        
+        for part in msg_container.parts {
+            let part_id = self.extract_part_id(&part);
+            part_order.push(part_id.clone());
+            parts_map.insert(part_id, part);
+        }
+        
+        MessageContainer {
+            info: *msg_container.info,
+            parts: parts_map,
+            part_order,
+            is_streaming: false,
+            last_updated: SystemTime::now(),
+            printed_to_stdout,
+            provisional: false,
+            send_failed: false,
+        }
     }
 
-    pub fn update_message(&mut self, message_info: Message) -> bool {
+    pub fn update_message(&mut self, message_info: Message) -> UpdateOutcome {
         let message_id = self.extract_message_id(&message_info);
         
         // Only process messages for current session
-        if let Some(current_session) = &self.current_session_id {
-            let message_session_id = self.extract_session_id_from_message(&message_info);
-            if &message_session_id != current_session {
-                return false;
-            }
+        let message_session_id = self.extract_session_id_from_message(&message_info);
+        if let Some(rejected) = self.check_session(&message_session_id) {
+            return rejected;
+        }
+        self.record_reply_model(&message_info);
+        
+        if let Some(window) = &mut self.load_window {
+            window.infos.insert(message_id.clone());
         }
         
         match self.messages.get_mut(&message_id) {
@@ -117,7 +476,7 @@ impl MessageState {
                 // Update existing message
                 container.info = message_info;
                 container.last_updated = SystemTime::now();
-                true
+                UpdateOutcome::Applied
             }
             None => {
                 // Create new message
@@ -128,26 +487,26 @@ impl MessageState {
                     is_streaming: true, // New messages start as streaming
                     last_updated: SystemTime::now(),
                     printed_to_stdout: false, // New messages haven't been printed yet
+                    provisional: false,
+                    send_failed: false,
                 };
                 
                 self.messages.insert(message_id.clone(), container);
                 self.insert_message_in_order(message_id.clone());
                 self.streaming_messages.insert(message_id);
-                true
+                UpdateOutcome::Applied
             }
         }
     }
 
-    pub fn update_message_part(&mut self, part: Part) -> bool {
+    pub fn update_message_part(&mut self, part: Part) -> UpdateOutcome {
         let part_id = self.extract_part_id(&part);
         let message_id = self.extract_message_id_from_part(&part);
         
         // Only process parts for current session
-        if let Some(current_session) = &self.current_session_id {
-            let part_session_id = self.extract_session_id_from_part(&part);
-            if &part_session_id != current_session {
-                return false;
-            }
+        let part_session_id = self.extract_session_id_from_part(&part);
+        if let Some(rejected) = self.check_session(&part_session_id) {
+            return rejected;
         }
         
         // Get or create the message container
@@ -162,12 +521,17 @@ impl MessageState {
                 is_streaming: true,
                 last_updated: SystemTime::now(),
                 printed_to_stdout: false,
+                provisional: false,
+                send_failed: false,
             };
             
             self.messages.insert(message_id.clone(), placeholder_container);
             self.insert_message_in_order(message_id.clone());
         }
         
+        // The server's parts replace the locally typed text
+        self.reconcile_provisional(&message_id);
+        
         // Now we know the container exists
         if let Some(container) = self.messages.get_mut(&message_id) {
             let is_new_part = !container.parts.contains_key(&part_id);
@@ -176,6 +540,18 @@ impl MessageState {
                 Self::insert_part_in_order(&mut container.part_order, part_id.clone());
             }
             
+            let key = (message_id.clone(), part_id.clone());
+            if let Some(window) = &mut self.load_window {
+                window.parts.insert(key.clone());
+            }
+            match &part {
+                Part::Tool(tool_part) => {
+                    self.tool_activity.insert(key, ToolActivity::from_part(tool_part));
+                }
+                _ => {
+                    self.tool_activity.remove(&key);
+                }
+            }
             container.parts.insert(part_id, part);
             container.last_updated = SystemTime::now();
             
@@ -183,26 +559,119 @@ impl MessageState {
             container.is_streaming = true;
             self.streaming_messages.insert(message_id);
             
-            true
+            UpdateOutcome::Applied
         } else {
-            false
+            UpdateOutcome::Unchanged
         }
     }
 
-    pub fn remove_message(&mut self, session_id: &str, message_id: &str) -> bool {
-        // Only process removals for current session
-        if let Some(current_session) = &self.current_session_id {
-            if session_id != current_session {
-                return false;
+    /// Show a message the user just sent before the server echoes it. The
+    /// message keeps `message_id`, so the server's events for it land on
+    /// the same container.
+    pub fn add_provisional(&mut self, message_id: &str, text: &str) -> UpdateOutcome {
+        let Some(session_id) = self.current_session_id.clone() else {
+            return UpdateOutcome::NoSession;
+        };
+        if self.messages.contains_key(message_id) {
+            return UpdateOutcome::Unchanged;
+        }
+        
+        let created = SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as f64;
+        let info = Message::User(Box::new(UserMessage {
+            id: message_id.to_string(),
+            session_id: session_id.clone(),
+            time: Box::new(UserMessageTime::new(created)),
+        }));
+        let part_id = generate_id(IdPrefix::Part);
+        let part = Part::Text(Box::new(TextPart {
+            id: part_id.clone(),
+            session_id,
+            message_id: message_id.to_string(),
+            text: text.to_string(),
+            ..Default::default()
+        }));
+        
+        let container = MessageContainer {
+            info,
+            parts: HashMap::from([(part_id.clone(), part)]),
+            part_order: vec![part_id],
+            is_streaming: false,
+            last_updated: SystemTime::now(),
+            printed_to_stdout: false,
+            provisional: true,
+            send_failed: false,
+        };
+        self.messages.insert(message_id.to_string(), container);
+        self.insert_message_in_order(message_id.to_string());
+        UpdateOutcome::Applied
+    }
+
+    /// Drop the locally typed parts of a provisional message, now that the
+    /// server is sending its own. Returns whether the message was provisional.
+    pub fn reconcile_provisional(&mut self, message_id: &str) -> bool {
+        match self.messages.get_mut(message_id) {
+            Some(container) if container.provisional => {
+                container.parts.clear();
+                container.part_order.clear();
+                container.provisional = false;
+                container.send_failed = false;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Mark a provisional message whose send request failed
+    pub fn mark_send_failed(&mut self, message_id: &str) -> bool {
+        match self.messages.get_mut(message_id) {
+            Some(container) if container.provisional => {
+                container.send_failed = true;
+                container.last_updated = SystemTime::now();
+                true
             }
+            _ => false,
+        }
+    }
+
+    /// Remove the provisional messages that failed to send, before they're
+    /// retried. Returns the IDs removed.
+    pub fn remove_failed_provisional(&mut self) -> Vec<String> {
+        let failed: Vec<String> = self
+            .message_order
+            .iter()
+            .filter(|message_id| {
+                self.messages
+                    .get(*message_id)
+                    .is_some_and(|container| container.provisional && container.send_failed)
+            })
+            .cloned()
+            .collect();
+        for message_id in &failed {
+            self.messages.remove(message_id);
+        }
+        self.message_order.retain(|message_id| !failed.contains(message_id));
+        failed
+    }
+
+    pub fn remove_message(&mut self, session_id: &str, message_id: &str) -> UpdateOutcome {
+        // Only process removals for current session
+        if let Some(rejected) = self.check_session(session_id) {
+            return rejected;
         }
         
+        if let Some(window) = &mut self.load_window {
+            window.removed.insert(message_id.to_string());
+        }
         if self.messages.remove(message_id).is_some() {
             self.message_order.retain(|id| id != message_id);
             self.streaming_messages.remove(message_id);
-            true
+            self.unindex_tool_activity(message_id);
+            UpdateOutcome::Applied
         } else {
-            false
+            UpdateOutcome::Unchanged
         }
     }
 
@@ -213,6 +682,22 @@ impl MessageState {
         }
     }
 
+    /// Mark every streaming message complete, once the session is idle.
+    /// Returns the IDs of the messages that were streaming.
+    pub fn finish_streaming(&mut self) -> Vec<String> {
+        let finished: Vec<String> = self.streaming_messages.drain().collect();
+        for message_id in &finished {
+            if let Some(container) = self.messages.get_mut(message_id) {
+                container.is_streaming = false;
+            }
+        }
+        finished
+    }
+
+    pub fn get_message_container(&self, message_id: &str) -> Option<&MessageContainer> {
+        self.messages.get(message_id)
+    }
+
     pub fn get_all_message_containers(&self) -> Vec<&MessageContainer> {
         self.message_order
             .iter()
@@ -220,6 +705,205 @@ impl MessageState {
             .collect()
     }
 
+    /// Every tool call in the loaded messages, oldest first
+    pub fn tool_activity(&self) -> impl Iterator<Item = &ToolActivity> {
+        self.tool_activity.values()
+    }
+
+    /// Parts of the newest message's step that hasn't finished, in order,
+    /// without the step start. None outside a step, and once the step's
+    /// finish has arrived.
+    pub fn active_step_parts(&self) -> Option<Vec<&Part>> {
+        let container = self
+            .message_order
+            .last()
+            .and_then(|message_id| self.messages.get(message_id))?;
+        let mut active = None;
+        for part in container
+            .part_order
+            .iter()
+            .filter_map(|part_id| container.parts.get(part_id))
+        {
+            match part {
+                Part::StepStart(_) => active = Some(Vec::new()),
+                Part::StepFinish(_) => active = None,
+                part => {
+                    if let Some(parts) = active.as_mut() {
+                        parts.push(part);
+                    }
+                }
+            }
+        }
+        active
+    }
+
+    /// Tool calls of the step still running, in the order they were made
+    pub fn active_step_tools(&self) -> Vec<ToolActivity> {
+        self.active_step_parts()
+            .into_iter()
+            .flatten()
+            .filter_map(|part| match part {
+                Part::Tool(tool_part) => Some(ToolActivity::from_part(tool_part)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn index_tool_activity(&mut self, container: &MessageContainer) {
+        for part in container.parts.values() {
+            if let Part::Tool(tool_part) = part {
+                let key = (tool_part.message_id.clone(), tool_part.id.clone());
+                self.tool_activity.insert(key, ToolActivity::from_part(tool_part));
+            }
+        }
+    }
+
+    fn unindex_tool_activity(&mut self, message_id: &str) {
+        self.tool_activity
+            .retain(|(indexed_message_id, _), _| indexed_message_id != message_id);
+    }
+
+    /// Most recent image attachment across all messages
+    pub fn latest_image_part(&self) -> Option<&FilePart> {
+        self.message_order.iter().rev().find_map(|message_id| {
+            let container = self.messages.get(message_id)?;
+            container
+                .part_order
+                .iter()
+                .rev()
+                .find_map(|part_id| match container.parts.get(part_id) {
+                    Some(Part::File(file_part)) if is_image_mime(&file_part.mime) => {
+                        Some(file_part.as_ref())
+                    }
+                    _ => None,
+                })
+        })
+    }
+
+    /// Title and whole output of the most recent tool call to finish, or its
+    /// error if it failed
+    pub fn latest_tool_output(&self) -> Option<(String, String)> {
+        self.message_order.iter().rev().find_map(|message_id| {
+            let container = self.messages.get(message_id)?;
+            container
+                .part_order
+                .iter()
+                .rev()
+                .find_map(|part_id| match container.parts.get(part_id) {
+                    Some(Part::Tool(tool_part)) => match &*tool_part.state {
+                        ToolState::Completed(completed) if completed.title.is_empty() => {
+                            Some((tool_part.tool.clone(), completed.output.clone()))
+                        }
+                        ToolState::Completed(completed) => Some((
+                            format!("{} · {}", tool_part.tool, completed.title),
+                            completed.output.clone(),
+                        )),
+                        ToolState::Error(error) => {
+                            Some((tool_part.tool.clone(), error.error.clone()))
+                        }
+                        _ => None,
+                    },
+                    _ => None,
+                })
+        })
+    }
+
+    /// Tool name and completed state of the most recent tool call to finish
+    /// successfully
+    pub fn latest_completed_tool(&self) -> Option<(&str, &ToolStateCompleted)> {
+        self.message_order.iter().rev().find_map(|message_id| {
+            let container = self.messages.get(message_id)?;
+            container
+                .part_order
+                .iter()
+                .rev()
+                .find_map(|part_id| match container.parts.get(part_id) {
+                    Some(Part::Tool(tool_part)) => match &*tool_part.state {
+                        ToolState::Completed(completed) => {
+                            Some((tool_part.tool.as_str(), completed.as_ref()))
+                        }
+                        _ => None,
+                    },
+                    _ => None,
+                })
+        })
+    }
+
+    /// ID and text of the most recent reply with any text, a string per part
+    pub fn latest_reply_text(&self) -> Option<(&str, Vec<&str>)> {
+        self.message_order.iter().rev().find_map(|message_id| {
+            let container = self.messages.get(message_id)?;
+            if !matches!(container.info, Message::Assistant(_)) {
+                return None;
+            }
+            let texts: Vec<&str> = container
+                .part_order
+                .iter()
+                .filter_map(|part_id| match container.parts.get(part_id) {
+                    Some(Part::Text(text_part)) if !text_part.synthetic.unwrap_or(false) => {
+                        Some(text_part.text.as_str())
+                    }
+                    _ => None,
+                })
+                .collect();
+            (!texts.is_empty()).then_some((message_id.as_str(), texts))
+        })
+    }
+
+    /// Tokens in the context of the most recent response that reported usage
+    pub fn latest_token_count(&self) -> Option<f64> {
+        self.message_order.iter().rev().find_map(|message_id| {
+            match &self.messages.get(message_id)?.info {
+                Message::Assistant(message) => {
+                    let total = total_tokens(&message.tokens);
+                    (total > 0.0).then_some(total)
+                }
+                Message::User(_) => None,
+            }
+        })
+    }
+
+    /// Remember which model wrote `info`, if it's a reply
+    fn record_reply_model(&mut self, info: &Message) {
+        let Message::Assistant(message) = info else {
+            return;
+        };
+        if message.model_id == UNKNOWN_MODEL {
+            return;
+        }
+        self.reply_models.insert(
+            message.id.clone(),
+            ReplyModel {
+                provider_id: message.provider_id.clone(),
+                model_id: message.model_id.clone(),
+                tokens: total_tokens(&message.tokens),
+            },
+        );
+    }
+
+    /// The model that wrote the reply `message_id`, if it's known
+    pub fn reply_model(&self, message_id: &str) -> Option<&ReplyModel> {
+        self.reply_models.get(message_id)
+    }
+
+    /// Replies and tokens this session per model, ordered by provider and model
+    pub fn usage_by_model(&self) -> Vec<ModelUsage> {
+        let mut usage: BTreeMap<(&str, &str), ModelUsage> = BTreeMap::new();
+        for reply in self.reply_models.values() {
+            let entry = usage
+                .entry((&reply.provider_id, &reply.model_id))
+                .or_insert_with(|| ModelUsage {
+                    provider_id: reply.provider_id.clone(),
+                    model_id: reply.model_id.clone(),
+                    replies: 0,
+                    tokens: 0.0,
+                });
+            entry.replies += 1;
+            entry.tokens += reply.tokens;
+        }
+        usage.into_values().collect()
+    }
+
     pub fn is_message_streaming(&self, message_id: &str) -> bool {
         self.streaming_messages.contains(message_id)
     }
@@ -233,7 +917,7 @@ impl MessageState {
         
         for message_id in &self.message_order {
             if let Some(container) = self.messages.get(message_id) {
-                if !container.printed_to_stdout {
+                if !container.printed_to_stdout && !container.provisional {
                     // Extract text content from message parts for printing
                     let mut text_content = String::new();
                     
@@ -270,7 +954,7 @@ impl MessageState {
             }
             
             if let Some(container) = self.messages.get_mut(message_id) {
-                if !container.printed_to_stdout {
+                if !container.printed_to_stdout && !container.provisional {
                     container.printed_to_stdout = true;
                     marked += 1;
                 }
@@ -278,10 +962,16 @@ impl MessageState {
         }
     }
 
+    pub fn mark_all_messages_printed_to_stdout(&mut self) {
+        for container in self.messages.values_mut().filter(|c| !c.provisional) {
+            container.printed_to_stdout = true;
+        }
+    }
+
     pub fn has_messages_needing_stdout_print(&self) -> bool {
         self.message_order.iter().any(|message_id| {
             self.messages.get(message_id)
-                .map(|container| !container.printed_to_stdout)
+                .map(|container| !container.printed_to_stdout && !container.provisional)
                 .unwrap_or(false)
         })
     }
@@ -296,7 +986,7 @@ impl MessageState {
     }
 
     // Helper methods to extract IDs from different message types
-    fn extract_message_id(&self, message: &Message) -> String {
+    pub fn extract_message_id(&self, message: &Message) -> String {
         match message {
             Message::User(user_msg) => user_msg.id.clone(),
             Message::Assistant(assistant_msg) => assistant_msg.id.clone(),
@@ -324,7 +1014,7 @@ impl MessageState {
         }
     }
 
-    fn extract_message_id_from_part(&self, part: &Part) -> String {
+    pub fn extract_message_id_from_part(&self, part: &Part) -> String {
         match part {
             Part::Text(text_part) => text_part.message_id.clone(),
             Part::Tool(tool_part) => tool_part.message_id.clone(),
@@ -406,8 +1096,8 @@ impl MessageState {
             time: Box::new(time),
             error: None,
             system: vec![],
-            model_id: "unknown".to_string(),
-            provider_id: "unknown".to_string(),
+            model_id: UNKNOWN_MODEL.to_string(),
+            provider_id: UNKNOWN_MODEL.to_string(),
             mode: "unknown".to_string(),
             path: Box::new(path),
             summary: None,
@@ -417,8 +1107,519 @@ impl MessageState {
     }
 }
 
+/// Every token a reply used, cached ones included
+fn total_tokens(tokens: &AssistantMessageTokens) -> f64 {
+    tokens.input + tokens.output + tokens.reasoning + tokens.cache.read + tokens.cache.write
+}
+
 impl Default for MessageState {
     fn default() -> Self {
         Self::new()
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opencode_sdk::models::{text_part, user_message, ToolStateCompletedTime};
+
+    fn loaded(message_id: &str) -> SessionMessages200ResponseInner {
+        let info = Message::User(Box::new(UserMessage::new(
+            message_id.to_string(),
+            "ses_test".to_string(),
+            user_message::Role::User,
+            UserMessageTime::new(0.0),
+        )));
+        let part = Part::Text(Box::new(TextPart::new(
+            format!("prt_{}", message_id),
+            "ses_test".to_string(),
+            message_id.to_string(),
+            text_part::Type::Text,
+            message_id.to_string(),
+        )));
+        SessionMessages200ResponseInner::new(info, vec![part])
+    }
+
+    fn loaded_ids(state: &MessageState) -> Vec<String> {
+        state
+            .get_all_message_containers()
+            .iter()
+            .map(|container| state.extract_message_id(&container.info))
+            .collect()
+    }
+
+    fn state_with(message_ids: &[&str]) -> MessageState {
+        let mut state = MessageState::new();
+        state.set_session_id(Some("ses_test".to_string()));
+        state.load_messages(message_ids.iter().map(|id| loaded(id)).collect());
+        state
+    }
+
+    #[test]
+    fn test_pages_are_ingested_in_order() {
+        let mut state = state_with(&["msg_05", "msg_06"]);
+        assert_eq!(state.first_message_id(), Some("msg_05"));
+
+        assert_eq!(state.prepend_messages(vec![loaded("msg_03"), loaded("msg_04")]).len(), 2);
+        assert_eq!(state.prepend_messages(vec![loaded("msg_01"), loaded("msg_02")]).len(), 2);
+        assert_eq!(
+            loaded_ids(&state),
+            vec!["msg_01", "msg_02", "msg_03", "msg_04", "msg_05", "msg_06"]
+        );
+        assert_eq!(state.first_message_id(), Some("msg_01"));
+    }
+
+    #[test]
+    fn test_prepend_keeps_newer_updates() {
+        let mut state = state_with(&["msg_02", "msg_03"]);
+        // A message that streamed in while the page was loading
+        state.update_message_part(loaded("msg_04").parts.remove(0));
+        state.update_message_part(Part::Text(Box::new(TextPart::new(
+            "prt_msg_02".to_string(),
+            "ses_test".to_string(),
+            "msg_02".to_string(),
+            text_part::Type::Text,
+            "edited".to_string(),
+        ))));
+
+        // The page overlaps what's loaded, which stays as it was
+        let added = state.prepend_messages(vec![loaded("msg_01"), loaded("msg_02")]);
+        assert_eq!(added, vec!["msg_01"]);
+        assert_eq!(loaded_ids(&state), vec!["msg_01", "msg_02", "msg_03", "msg_04"]);
+        let kept = state.get_message_container("msg_02").unwrap();
+        assert!(matches!(
+            kept.parts.get("prt_msg_02"),
+            Some(Part::Text(text)) if text.text == "edited"
+        ));
+
+        // Earlier history isn't printed to the scrollback after newer messages
+        let prepended = state.get_message_container("msg_01").unwrap();
+        assert!(prepended.printed_to_stdout);
+        assert!(!state.get_message_container("msg_03").unwrap().printed_to_stdout);
+    }
+
+    #[test]
+    fn test_load_keeps_events_from_while_it_ran() {
+        let mut state = state_with(&["msg_01", "msg_02"]);
+        state.begin_load();
+        // Events that land before the fetched messages do
+        state.update_message_part(loaded("msg_04").parts.remove(0));
+        state.update_message_part(Part::Text(Box::new(TextPart::new(
+            "prt_msg_02".to_string(),
+            "ses_test".to_string(),
+            "msg_02".to_string(),
+            text_part::Type::Text,
+            "edited".to_string(),
+        ))));
+        state.remove_message("ses_test", "msg_01");
+
+        // The fetched copy predates all of them
+        state.load_messages(vec![loaded("msg_01"), loaded("msg_02"), loaded("msg_03")]);
+        assert_eq!(loaded_ids(&state), vec!["msg_02", "msg_03", "msg_04"]);
+        let edited = |state: &MessageState| {
+            matches!(
+                state.get_message_container("msg_02").unwrap().parts.get("prt_msg_02"),
+                Some(Part::Text(text)) if text.text == "edited"
+            )
+        };
+        assert!(edited(&state));
+
+        // Without a load in flight the fetched copy is taken whole
+        state.load_messages(vec![loaded("msg_02")]);
+        assert_eq!(loaded_ids(&state), vec!["msg_02"]);
+        assert!(!edited(&state));
+    }
+
+    #[test]
+    fn test_provisional_message_replaced_by_the_echo() {
+        let mut state = state_with(&["msg_01"]);
+        assert!(state.add_provisional("msg_02", "hello").is_applied());
+        assert_eq!(loaded_ids(&state), vec!["msg_01", "msg_02"]);
+        let provisional = state.get_message_container("msg_02").unwrap();
+        assert!(provisional.provisional);
+        // Nothing is printed to the scrollback until the server has it
+        assert_eq!(state.get_messages_needing_stdout_print(), vec!["msg_01"]);
+
+        // The server's info alone keeps the typed text on screen
+        let mut echo = loaded("msg_02");
+        assert!(state.update_message(*echo.info).is_applied());
+        assert_eq!(state.get_message_container("msg_02").unwrap().parts.len(), 1);
+        assert!(state.get_message_container("msg_02").unwrap().provisional);
+
+        // Its first part replaces the typed one
+        state.update_message_part(echo.parts.remove(0));
+        let echoed = state.get_message_container("msg_02").unwrap();
+        assert!(!echoed.provisional);
+        assert_eq!(echoed.part_order, vec!["prt_msg_02"]);
+        assert_eq!(loaded_ids(&state), vec!["msg_01", "msg_02"]);
+    }
+
+    #[test]
+    fn test_failed_send_marked_then_removed_on_retry() {
+        let mut state = state_with(&["msg_01"]);
+        state.add_provisional("msg_02", "hello");
+        assert!(!state.mark_send_failed("msg_01"));
+        assert!(state.mark_send_failed("msg_02"));
+        assert!(state.get_message_container("msg_02").unwrap().send_failed);
+
+        assert_eq!(state.remove_failed_provisional(), vec!["msg_02"]);
+        assert_eq!(loaded_ids(&state), vec!["msg_01"]);
+        assert!(state.remove_failed_provisional().is_empty());
+    }
+
+    #[test]
+    fn test_provisional_needs_a_session() {
+        let mut state = MessageState::new();
+        assert_eq!(state.add_provisional("msg_01", "hello"), UpdateOutcome::NoSession);
+        assert!(state.is_empty());
+    }
+
+    #[test]
+    fn test_retention_drops_the_oldest_printed_messages() {
+        let mut state = state_with(&["msg_01", "msg_02", "msg_03", "msg_04", "msg_05"]);
+        state.mark_all_messages_printed_to_stdout();
+        // Unlimited by default
+        assert!(state.drop_beyond_retention().is_empty());
+
+        state.set_retained_messages(Some(3));
+        assert_eq!(state.drop_beyond_retention(), vec!["msg_01", "msg_02"]);
+        assert_eq!(loaded_ids(&state), vec!["msg_03", "msg_04", "msg_05"]);
+        assert_eq!(state.dropped_count(), 2);
+        assert!(state.drop_beyond_retention().is_empty());
+
+        // Loading them again counts them back
+        state.prepend_messages(vec![loaded("msg_02")]);
+        assert_eq!(state.dropped_count(), 1);
+        assert_eq!(state.first_message_id(), Some("msg_02"));
+
+        state.set_session_id(Some("ses_other".to_string()));
+        assert_eq!(state.dropped_count(), 0);
+    }
+
+    #[test]
+    fn test_retention_keeps_streaming_and_unprinted_messages() {
+        let mut state = state_with(&["msg_01", "msg_02", "msg_03"]);
+        state.set_retained_messages(Some(1));
+        // Nothing has reached the scrollback yet
+        assert!(state.drop_beyond_retention().is_empty());
+
+        state.mark_messages_printed_to_stdout(1);
+        assert_eq!(state.drop_beyond_retention(), vec!["msg_01"]);
+
+        // A message still streaming holds back the ones after it too, so
+        // what's dropped stays a run from the start
+        state.mark_all_messages_printed_to_stdout();
+        state.update_message_part(loaded("msg_02").parts.remove(0));
+        assert!(state.is_message_streaming("msg_02"));
+        assert!(state.drop_beyond_retention().is_empty());
+        assert_eq!(loaded_ids(&state), vec!["msg_02", "msg_03"]);
+
+        // Provisional messages are never printed, so never dropped
+        state.add_provisional("msg_04", "hello");
+        assert_eq!(state.finish_streaming(), vec!["msg_02"]);
+        assert!(!state.get_message_container("msg_02").unwrap().is_streaming);
+        state.set_retained_messages(Some(0));
+        assert_eq!(state.drop_beyond_retention(), vec!["msg_02", "msg_03"]);
+        assert_eq!(loaded_ids(&state), vec!["msg_04"]);
+        assert_eq!(state.dropped_count(), 3);
+    }
+
+    fn reply(message_id: &str, model_id: &str, output: f64) -> Message {
+        use opencode_sdk::models::{AssistantMessage, AssistantMessageTokens};
+        Message::Assistant(Box::new(AssistantMessage {
+            id: message_id.to_string(),
+            session_id: "ses_test".to_string(),
+            provider_id: "anthropic".to_string(),
+            model_id: model_id.to_string(),
+            tokens: Box::new(AssistantMessageTokens {
+                input: 100.0,
+                output,
+                ..Default::default()
+            }),
+            ..Default::default()
+        }))
+    }
+
+    #[test]
+    fn test_usage_broken_down_per_model() {
+        let mut state = state_with(&["msg_01"]);
+        state.set_retained_messages(Some(1));
+        state.update_message(reply("msg_02", "claude-sonnet-4", 50.0));
+        state.update_message(reply("msg_03", "claude-opus-4", 20.0));
+        // The final info replaces what streamed in
+        state.update_message(reply("msg_04", "claude-sonnet-4", 10.0));
+        state.update_message(reply("msg_04", "claude-sonnet-4", 30.0));
+        // The model is kept once the message is dropped
+        state.mark_all_messages_printed_to_stdout();
+        state.finish_streaming();
+        state.drop_beyond_retention();
+        assert!(state.get_message_container("msg_02").is_none());
+        assert_eq!(state.reply_model("msg_02").unwrap().model_id, "claude-sonnet-4");
+
+        let usage = state.usage_by_model();
+        assert_eq!(
+            usage,
+            vec![
+                ModelUsage {
+                    provider_id: "anthropic".to_string(),
+                    model_id: "claude-opus-4".to_string(),
+                    replies: 1,
+                    tokens: 120.0,
+                },
+                ModelUsage {
+                    provider_id: "anthropic".to_string(),
+                    model_id: "claude-sonnet-4".to_string(),
+                    replies: 2,
+                    tokens: 280.0,
+                },
+            ]
+        );
+
+        // Another session starts over
+        state.set_session_id(Some("ses_other".to_string()));
+        assert!(state.usage_by_model().is_empty());
+    }
+
+    fn tool(message_id: &str, part_id: &str, state: ToolState) -> Part {
+        Part::Tool(Box::new(ToolPart {
+            id: part_id.to_string(),
+            session_id: "ses_test".to_string(),
+            message_id: message_id.to_string(),
+            call_id: format!("call_{}", part_id),
+            tool: "bash".to_string(),
+            state: Box::new(state),
+        }))
+    }
+
+    fn bash_input() -> HashMap<String, serde_json::Value> {
+        HashMap::from([("command".to_string(), serde_json::json!("cargo test"))])
+    }
+
+    fn completed(start: f64, end: f64) -> ToolState {
+        ToolState::Completed(Box::new(ToolStateCompleted {
+            input: bash_input(),
+            output: "ok".to_string(),
+            title: "cargo test".to_string(),
+            metadata: HashMap::new(),
+            time: Box::new(ToolStateCompletedTime { start, end }),
+        }))
+    }
+
+    fn activity_ids(state: &MessageState) -> Vec<(String, String)> {
+        state
+            .tool_activity()
+            .map(|activity| (activity.message_id.clone(), activity.part_id.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn test_tool_activity_follows_part_states() {
+        use opencode_sdk::models::{ToolStateError, ToolStatePending, ToolStateRunning, ToolStateRunningTime};
+
+        let mut state = state_with(&["msg_01"]);
+        assert_eq!(state.tool_activity().count(), 0);
+
+        state.update_message_part(tool("msg_02", "prt_02", ToolState::Pending(Box::new(ToolStatePending {}))));
+        let pending = state.tool_activity().next().unwrap().clone();
+        assert_eq!((pending.status, pending.started_ms, pending.args.as_str()), (ToolStatus::Pending, None, ""));
+
+        let running = ToolStateRunning {
+            input: Some(Some(serde_json::json!({ "command": "cargo test" }))),
+            time: Box::new(ToolStateRunningTime::new(1_000.0)),
+            ..Default::default()
+        };
+        state.update_message_part(tool("msg_02", "prt_02", ToolState::Running(Box::new(running))));
+        let activity = state.tool_activity().next().unwrap();
+        assert_eq!(activity.status, ToolStatus::Running);
+        assert_eq!(activity.started_ms, Some(1_000.0));
+        assert_eq!(activity.duration_ms, None);
+        assert_eq!(activity.args, "cargo test");
+
+        state.update_message_part(tool("msg_02", "prt_02", completed(1_000.0, 3_500.0)));
+        let activity = state.tool_activity().next().unwrap();
+        assert_eq!(activity.status, ToolStatus::Completed);
+        assert_eq!(activity.duration_ms, Some(2_500.0));
+        assert_eq!(state.tool_activity().count(), 1);
+
+        let error = ToolStateError::new(
+            opencode_sdk::models::tool_state_error::Status::Error,
+            bash_input(),
+            "exit 1".to_string(),
+            ToolStateCompletedTime { start: 4_000.0, end: 4_200.0 },
+        );
+        state.update_message_part(tool("msg_02", "prt_03", ToolState::Error(Box::new(error))));
+        // An earlier message's call goes before, whenever it arrives
+        state.update_message_part(tool("msg_015", "prt_01", completed(500.0, 600.0)));
+        assert_eq!(
+            activity_ids(&state),
+            vec![
+                ("msg_015".to_string(), "prt_01".to_string()),
+                ("msg_02".to_string(), "prt_02".to_string()),
+                ("msg_02".to_string(), "prt_03".to_string()),
+            ]
+        );
+        assert_eq!(state.tool_activity().last().unwrap().status, ToolStatus::Failed);
+
+        // Other sessions' parts aren't listed, and removed messages leave
+        let Part::Tool(mut elsewhere) = tool("msg_03", "prt_04", completed(0.0, 1.0)) else {
+            unreachable!();
+        };
+        elsewhere.session_id = "ses_other".to_string();
+        state.update_message_part(Part::Tool(elsewhere));
+        state.remove_message("ses_test", "msg_02");
+        assert_eq!(activity_ids(&state), vec![("msg_015".to_string(), "prt_01".to_string())]);
+    }
+
+    fn step_start(message_id: &str, part_id: &str) -> Part {
+        Part::StepStart(Box::new(opencode_sdk::models::StepStartPart::new(
+            part_id.to_string(),
+            "ses_test".to_string(),
+            message_id.to_string(),
+            opencode_sdk::models::step_start_part::Type::StepStart,
+        )))
+    }
+
+    fn step_finish(message_id: &str, part_id: &str) -> Part {
+        Part::StepFinish(Box::new(opencode_sdk::models::StepFinishPart::new(
+            part_id.to_string(),
+            "ses_test".to_string(),
+            message_id.to_string(),
+            opencode_sdk::models::step_finish_part::Type::StepFinish,
+            0.0,
+            AssistantMessageTokens::default(),
+        )))
+    }
+
+    fn active_tool_ids(state: &MessageState) -> Vec<String> {
+        state.active_step_tools().into_iter().map(|activity| activity.part_id).collect()
+    }
+
+    #[test]
+    fn test_active_step_is_the_newest_unfinished_one() {
+        use opencode_sdk::models::ToolStatePending;
+        let pending = || ToolState::Pending(Box::new(ToolStatePending {}));
+
+        let mut state = state_with(&["msg_01"]);
+        // Outside a step there is nothing running
+        assert_eq!(state.active_step_parts(), None);
+        state.update_message_part(tool("msg_02", "prt_01", pending()));
+        assert_eq!(state.active_step_parts(), None);
+
+        state.update_message_part(step_start("msg_02", "prt_02"));
+        assert_eq!(state.active_step_parts(), Some(vec![]));
+        state.update_message_part(tool("msg_02", "prt_03", pending()));
+        state.update_message_part(tool("msg_02", "prt_04", completed(0.0, 1.0)));
+        assert_eq!(active_tool_ids(&state), vec!["prt_03", "prt_04"]);
+        assert_eq!(
+            state.active_step_tools().iter().map(|activity| activity.status).collect::<Vec<_>>(),
+            vec![ToolStatus::Pending, ToolStatus::Completed]
+        );
+
+        // Its finish hands the tools back to the transcript
+        state.update_message_part(step_finish("msg_02", "prt_05"));
+        assert_eq!(state.active_step_parts(), None);
+        assert!(active_tool_ids(&state).is_empty());
+
+        // The next step starts out empty
+        state.update_message_part(step_start("msg_02", "prt_06"));
+        state.update_message_part(tool("msg_02", "prt_07", pending()));
+        assert_eq!(active_tool_ids(&state), vec!["prt_07"]);
+
+        // Only the newest message counts
+        state.update_message_part(loaded("msg_03").parts.remove(0));
+        assert_eq!(state.active_step_parts(), None);
+    }
+
+    #[test]
+    fn test_tool_activity_follows_loaded_pages() {
+        let with_tool = |message_id: &str| {
+            let mut page = loaded(message_id);
+            page.parts.push(tool(message_id, &format!("prt_tool_{}", message_id), completed(0.0, 1.0)));
+            page
+        };
+        let mut state = MessageState::new();
+        state.set_session_id(Some("ses_test".to_string()));
+        state.load_messages(vec![with_tool("msg_05"), loaded("msg_06")]);
+        assert_eq!(state.tool_activity().count(), 1);
+
+        state.prepend_messages(vec![with_tool("msg_03"), loaded("msg_04")]);
+        let listed: Vec<&str> = state.tool_activity().map(|activity| activity.message_id.as_str()).collect();
+        assert_eq!(listed, vec!["msg_03", "msg_05"]);
+
+        // Dropped messages can't be jumped to, so their calls go with them
+        state.set_retained_messages(Some(2));
+        state.mark_all_messages_printed_to_stdout();
+        state.drop_beyond_retention();
+        let listed: Vec<&str> = state.tool_activity().map(|activity| activity.message_id.as_str()).collect();
+        assert_eq!(listed, vec!["msg_05"]);
+
+        // Loading anew starts over
+        state.load_messages(vec![with_tool("msg_07")]);
+        assert_eq!(state.tool_activity().count(), 1);
+        state.set_session_id(Some("ses_other".to_string()));
+        assert_eq!(state.tool_activity().count(), 0);
+    }
+
+    #[test]
+    fn test_merge_adds_new_messages() {
+        let mut state = state_with(&["msg_01", "msg_02"]);
+        state.mark_all_messages_printed_to_stdout();
+
+        let changed = state.merge_messages(vec![loaded("msg_01"), loaded("msg_02"), loaded("msg_03")]);
+        assert_eq!(changed, vec!["msg_03"]);
+        assert_eq!(loaded_ids(&state), vec!["msg_01", "msg_02", "msg_03"]);
+        // New messages are printed in turn, like ones from events
+        assert!(!state.get_message_container("msg_03").unwrap().printed_to_stdout);
+
+        // A reply without a completed time is still running
+        let mut running = loaded("msg_04");
+        running.info = Box::new(reply("msg_04", "claude-sonnet-4", 0.0));
+        assert_eq!(state.merge_messages(vec![running]), vec!["msg_04"]);
+        assert!(state.is_message_streaming("msg_04"));
+        assert_eq!(state.reply_model("msg_04").unwrap().model_id, "claude-sonnet-4");
+    }
+
+    #[test]
+    fn test_merge_replaces_updated_parts() {
+        let mut state = state_with(&["msg_01", "msg_02"]);
+        state.mark_all_messages_printed_to_stdout();
+
+        let mut updated = loaded("msg_02");
+        updated.parts.push(tool("msg_02", "prt_tool", completed(0.0, 1.0)));
+        assert_eq!(state.merge_messages(vec![loaded("msg_01"), updated]), vec!["msg_02"]);
+        let merged = state.get_message_container("msg_02").unwrap();
+        assert_eq!(merged.part_order, vec!["prt_msg_02", "prt_tool"]);
+        assert!(merged.printed_to_stdout);
+        assert_eq!(activity_ids(&state), vec![("msg_02".to_string(), "prt_tool".to_string())]);
+
+        // The reply completing ends its streaming
+        let mut reply_page = loaded("msg_03");
+        reply_page.info = Box::new(reply("msg_03", "claude-sonnet-4", 10.0));
+        state.merge_messages(vec![reply_page.clone()]);
+        assert!(state.is_message_streaming("msg_03"));
+        if let Message::Assistant(info) = &mut *reply_page.info {
+            info.time.completed = Some(2.0);
+        }
+        assert_eq!(state.merge_messages(vec![reply_page]), vec!["msg_03"]);
+        assert!(!state.is_message_streaming("msg_03"));
+        assert!(!state.get_message_container("msg_03").unwrap().is_streaming);
+
+        // The server's copy replaces the typed text of a provisional message
+        state.add_provisional("msg_04", "hello");
+        assert_eq!(state.merge_messages(vec![loaded("msg_04")]), vec!["msg_04"]);
+        assert!(!state.get_message_container("msg_04").unwrap().provisional);
+    }
+
+    #[test]
+    fn test_merge_leaves_unchanged_messages_alone() {
+        let mut state = state_with(&["msg_01", "msg_02"]);
+        state.mark_all_messages_printed_to_stdout();
+        // Streaming from before the stream was lost
+        state.update_message_part(loaded("msg_02").parts.remove(0));
+        let before = state.clone();
+
+        assert!(state.merge_messages(vec![loaded("msg_01"), loaded("msg_02")]).is_empty());
+        assert_eq!(state, before);
+        assert!(state.is_message_streaming("msg_02"));
+    }
+}
@@ -1,23 +1,69 @@
-#![allow(unused)]
-
+pub mod alerts;
 mod app_program;
+pub mod bugreport;
+pub mod changelog;
+pub mod cli;
+pub mod clipboard;
+pub mod completion_notice;
+pub mod config_file;
+pub mod draft_store;
 pub mod error;
 pub mod event_async_task_manager;
 pub mod event_msg;
 pub mod event_sync_subscriptions;
+pub mod file_opener;
+pub mod input_history;
+pub mod job_control;
+pub mod keybindings;
+pub mod links;
+pub mod log_control;
+pub mod log_throttle;
 pub mod logger;
+pub mod mentions;
+pub mod message_polling;
 pub mod message_state;
+pub mod msg_recorder;
+pub mod one_shot;
+pub mod paste;
+pub mod profile;
+pub mod project_config;
+pub mod projects;
+pub mod provider_models;
+pub mod redaction;
+pub mod server_capabilities;
+pub mod session_directory;
+pub mod session_options;
+pub mod session_templates;
+pub mod snippets;
+pub mod strings;
 pub mod tea_model;
 pub mod tea_update;
 pub mod tea_view;
 pub mod terminal;
+pub mod token_estimate;
 pub mod ui_components;
-pub mod view_model_context;
+pub mod working_time;
+pub mod workspace_guard;
 
 pub use app_program::Program;
 pub use error::Result;
+use log_control::LogControl;
+use std::process::ExitCode;
 
-pub fn run() -> Result<()> {
-    let program = Program::new()?;
+pub fn run(args: cli::CliArgs, log_control: LogControl) -> Result<ExitCode> {
+    if let Some(doctor) = &args.doctor {
+        bugreport::run_doctor(doctor)?;
+        return Ok(ExitCode::SUCCESS);
+    }
+    if let Some(path) = &args.replay {
+        msg_recorder::replay_file(path)?;
+        return Ok(ExitCode::SUCCESS);
+    }
+    if let Some(prompt) = &args.prompt {
+        return Program::one_shot(&args, prompt)?
+            .with_log_control(log_control)
+            .run();
+    }
+    let program = Program::new(&args)?.with_log_control(log_control);
     program.run()
 }
@@ -0,0 +1,392 @@
+//! Recording of the message stream, for replaying a session headlessly
+//!
+//! With `OPENCODE_RECORD=<file>` set, every message processed by `update` is
+//! appended to the file as a line of JSON, along with the time since the
+//! recording started. `opencoders --replay <file>` feeds the messages back
+//! through `update` against a fresh model on an in-memory terminal and prints
+//! the final screen, so a UI bug can be reproduced without a server.
+//!
+//! Commands aren't run during a replay, since their results were recorded as
+//! messages of their own. Client handles are recorded as their server URL and
+//! event streams as a marker that replays as an ended stream.
+
+use crate::{
+    app::{error::Result, event_msg::Msg, tea_model::Model, Program},
+    sdk::{extensions::events::EventStreamHandle, OpenCodeClient, OpenCodeError},
+};
+use eyre::WrapErr;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, LineWriter, Write},
+    path::Path,
+    time::{Duration, SystemTime},
+};
+
+/// Environment variable naming the file to record messages to
+pub const RECORD_ENV_VAR: &str = "OPENCODE_RECORD";
+
+/// Terminal size for replays, since the recording doesn't capture one
+pub const REPLAY_WIDTH: u16 = 120;
+pub const REPLAY_HEIGHT: u16 = 40;
+
+/// One line of a recording
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedMsg {
+    /// Milliseconds since the recording started
+    pub elapsed_ms: u64,
+    pub msg: Msg,
+}
+
+impl RecordedMsg {
+    pub fn elapsed(&self) -> Duration {
+        Duration::from_millis(self.elapsed_ms)
+    }
+}
+
+#[derive(Serialize)]
+struct RecordedMsgRef<'a> {
+    elapsed_ms: u64,
+    msg: &'a Msg,
+}
+
+/// Appends processed messages to a recording file
+#[derive(Debug)]
+pub struct MsgRecorder {
+    writer: LineWriter<File>,
+    /// Time of the first recorded message
+    started: Option<SystemTime>,
+}
+
+impl MsgRecorder {
+    /// Start recording to the file named by `OPENCODE_RECORD`, if it is set
+    pub fn from_env() -> Result<Option<Self>> {
+        match std::env::var_os(RECORD_ENV_VAR) {
+            Some(path) if !path.is_empty() => Ok(Some(Self::create(Path::new(&path))?)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Start recording to `path`, replacing any previous recording
+    pub fn create(path: &Path) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(path)
+            .wrap_err_with(|| format!("Failed to create recording {}", path.display()))?;
+        tracing::info!("Recording messages to {}", path.display());
+        Ok(Self {
+            writer: LineWriter::new(file),
+            started: None,
+        })
+    }
+
+    /// Append a message processed at `now`, as read from the model's clock.
+    /// Each line is flushed as it is written, so the recording survives a panic.
+    pub fn record(&mut self, msg: &Msg, now: SystemTime) -> io::Result<()> {
+        let started = *self.started.get_or_insert(now);
+        let line = RecordedMsgRef {
+            elapsed_ms: now.duration_since(started).unwrap_or_default().as_millis() as u64,
+            msg,
+        };
+        serde_json::to_writer(&mut self.writer, &line)?;
+        self.writer.write_all(b"\n")
+    }
+}
+
+/// Read every message of a recording
+pub fn read_recording(path: &Path) -> Result<Vec<RecordedMsg>> {
+    let contents = std::fs::read_to_string(path)
+        .wrap_err_with(|| format!("Failed to read recording {}", path.display()))?;
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            serde_json::from_str(line).wrap_err_with(|| {
+                format!(
+                    "Invalid message on line {} of {}",
+                    index + 1,
+                    path.display()
+                )
+            })
+        })
+        .collect()
+}
+
+/// Replay a recording against a fresh model and print the final screen. A
+/// panic during the replay is reported and returned as an error.
+pub fn replay_file(path: &Path) -> Result<()> {
+    let recording = read_recording(path)?;
+    let total = recording.len();
+    let program = Program::headless(Model::new(), REPLAY_WIDTH, REPLAY_HEIGHT)?;
+    let report = program.replay(recording)?;
+
+    println!("{}", report.screen);
+    eprintln!("Replayed {} of {} messages", report.replayed, total);
+    if let Some(panic) = report.panic {
+        eyre::bail!(
+            "Replay panicked at message {}: {}",
+            panic.index + 1,
+            panic.message
+        );
+    }
+    Ok(())
+}
+
+/// Recorded form of an error. HTTP and JSON errors can't be rebuilt, so they
+/// are kept as `Unexpected` messages.
+#[derive(Serialize, Deserialize)]
+enum RecordedError {
+    Api {
+        status: u16,
+        message: String,
+    },
+    Auth(String),
+    SessionNotFound {
+        session_id: String,
+    },
+    MessageNotFound {
+        session_id: String,
+        message_id: String,
+    },
+    EventStream(String),
+    EventStreamLagged {
+        missed: u64,
+    },
+    Configuration(String),
+    InvalidRequest(String),
+    Timeout(String),
+    ServerNotFound,
+    ConnectionTimeout,
+    ProcessDetectionFailed,
+    SessionPersistence(String),
+    ServerStartFailed(String),
+    TextPartTooLarge {
+        line_bytes: usize,
+        limit: usize,
+    },
+    Unexpected(String),
+}
+
+impl From<&OpenCodeError> for RecordedError {
+    fn from(error: &OpenCodeError) -> Self {
+        match error {
+            OpenCodeError::Http(_) | OpenCodeError::Serialization(_) => {
+                Self::Unexpected(error.to_string())
+            }
+            OpenCodeError::Api { status, message } => Self::Api {
+                status: *status,
+                message: message.clone(),
+            },
+            OpenCodeError::Auth(message) => Self::Auth(message.clone()),
+            OpenCodeError::SessionNotFound { session_id } => Self::SessionNotFound {
+                session_id: session_id.clone(),
+            },
+            OpenCodeError::MessageNotFound {
+                session_id,
+                message_id,
+            } => Self::MessageNotFound {
+                session_id: session_id.clone(),
+                message_id: message_id.clone(),
+            },
+            OpenCodeError::EventStream(message) => Self::EventStream(message.clone()),
+            OpenCodeError::EventStreamLagged { missed } => {
+                Self::EventStreamLagged { missed: *missed }
+            }
+            OpenCodeError::Configuration(message) => Self::Configuration(message.clone()),
+            OpenCodeError::InvalidRequest(message) => Self::InvalidRequest(message.clone()),
+            OpenCodeError::Timeout(message) => Self::Timeout(message.clone()),
+            OpenCodeError::ServerNotFound => Self::ServerNotFound,
+            OpenCodeError::ConnectionTimeout => Self::ConnectionTimeout,
+            OpenCodeError::ProcessDetectionFailed => Self::ProcessDetectionFailed,
+            OpenCodeError::SessionPersistence(message) => Self::SessionPersistence(message.clone()),
+            OpenCodeError::ServerStartFailed(message) => Self::ServerStartFailed(message.clone()),
+            OpenCodeError::TextPartTooLarge { line_bytes, limit } => Self::TextPartTooLarge {
+                line_bytes: *line_bytes,
+                limit: *limit,
+            },
+            OpenCodeError::Unexpected(message) => Self::Unexpected(message.clone()),
+        }
+    }
+}
+
+impl From<RecordedError> for OpenCodeError {
+    fn from(error: RecordedError) -> Self {
+        match error {
+            RecordedError::Api { status, message } => Self::Api { status, message },
+            RecordedError::Auth(message) => Self::Auth(message),
+            RecordedError::SessionNotFound { session_id } => Self::SessionNotFound { session_id },
+            RecordedError::MessageNotFound {
+                session_id,
+                message_id,
+            } => Self::MessageNotFound {
+                session_id,
+                message_id,
+            },
+            RecordedError::EventStream(message) => Self::EventStream(message),
+            RecordedError::EventStreamLagged { missed } => Self::EventStreamLagged { missed },
+            RecordedError::Configuration(message) => Self::Configuration(message),
+            RecordedError::InvalidRequest(message) => Self::InvalidRequest(message),
+            RecordedError::Timeout(message) => Self::Timeout(message),
+            RecordedError::ServerNotFound => Self::ServerNotFound,
+            RecordedError::ConnectionTimeout => Self::ConnectionTimeout,
+            RecordedError::ProcessDetectionFailed => Self::ProcessDetectionFailed,
+            RecordedError::SessionPersistence(message) => Self::SessionPersistence(message),
+            RecordedError::ServerStartFailed(message) => Self::ServerStartFailed(message),
+            RecordedError::TextPartTooLarge { line_bytes, limit } => {
+                Self::TextPartTooLarge { line_bytes, limit }
+            }
+            RecordedError::Unexpected(message) => Self::Unexpected(message),
+        }
+    }
+}
+
+/// Serde `with` module for the message fields holding an SDK response
+pub mod recorded_response {
+    use super::*;
+
+    pub fn serialize<T: Serialize, S: Serializer>(
+        response: &std::result::Result<T, OpenCodeError>,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        response
+            .as_ref()
+            .map_err(RecordedError::from)
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, T: Deserialize<'de>, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<std::result::Result<T, OpenCodeError>, D::Error> {
+        let response = std::result::Result::<T, RecordedError>::deserialize(deserializer)?;
+        Ok(response.map_err(OpenCodeError::from))
+    }
+}
+
+/// Serde `with` module for a connected client, recorded as its server URL so
+/// the replay gets a client that points at the same server
+pub mod recorded_client_response {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        response: &std::result::Result<OpenCodeClient, OpenCodeError>,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        response
+            .as_ref()
+            .map(OpenCodeClient::base_url)
+            .map_err(RecordedError::from)
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<std::result::Result<OpenCodeClient, OpenCodeError>, D::Error> {
+        let response = std::result::Result::<String, RecordedError>::deserialize(deserializer)?;
+        Ok(response
+            .map(|base_url| OpenCodeClient::new(&base_url))
+            .map_err(OpenCodeError::from))
+    }
+}
+
+/// Serde `with` module for an event stream handle, recorded as a marker that
+/// replays as an ended stream
+pub mod recorded_stream {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        _handle: &EventStreamHandle,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_unit_struct("EventStreamHandle")
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<EventStreamHandle, D::Error> {
+        <()>::deserialize(deserializer)?;
+        Ok(EventStreamHandle::ended())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(msg: &Msg) -> Msg {
+        let json = serde_json::to_string(msg).unwrap();
+        serde_json::from_str(&json).unwrap()
+    }
+
+    #[test]
+    fn test_handles_are_recorded_as_markers() {
+        let client = OpenCodeClient::new("http://localhost:4096");
+        match round_trip(&Msg::ResponseClientConnect(Ok(client))) {
+            Msg::ResponseClientConnect(Ok(client)) => {
+                assert_eq!(client.base_url(), "http://localhost:4096")
+            }
+            other => panic!("unexpected message {:?}", other),
+        }
+
+        let json =
+            serde_json::to_string(&Msg::EventStreamConnected(EventStreamHandle::ended())).unwrap();
+        assert_eq!(json, r#"{"EventStreamConnected":null}"#);
+    }
+
+    #[test]
+    fn test_errors_round_trip() {
+        let error = OpenCodeError::api_error(500, "boom");
+        assert_eq!(
+            round_trip(&Msg::ResponseSessionInit(Err(error.clone()))),
+            Msg::ResponseSessionInit(Err(error))
+        );
+
+        // Errors from the HTTP client keep only their message
+        let json_error = serde_json::from_str::<u8>("x").unwrap_err();
+        let message = json_error.to_string();
+        assert_eq!(
+            round_trip(&Msg::ResponseSessionsLoad(Err(json_error.into()))),
+            Msg::ResponseSessionsLoad(Err(OpenCodeError::Unexpected(format!(
+                "Serialization error: {}",
+                message
+            ))))
+        );
+    }
+
+    #[test]
+    fn test_read_recording_reports_bad_lines() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("session.jsonl");
+        let mut recorder = MsgRecorder::create(&path).unwrap();
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000);
+        recorder.record(&Msg::ToggleVerbosity, start).unwrap();
+        recorder
+            .record(
+                &Msg::ScrollMessageLog(-3),
+                start + Duration::from_millis(250),
+            )
+            .unwrap();
+        drop(recorder);
+
+        let recording = read_recording(&path).unwrap();
+        assert_eq!(
+            recording,
+            vec![
+                RecordedMsg {
+                    elapsed_ms: 0,
+                    msg: Msg::ToggleVerbosity
+                },
+                RecordedMsg {
+                    elapsed_ms: 250,
+                    msg: Msg::ScrollMessageLog(-3)
+                },
+            ]
+        );
+
+        std::fs::write(&path, "{\"elapsed_ms\":0,\"msg\":\"Quit\"}\nnot json\n").unwrap();
+        let error = read_recording(&path).unwrap_err().to_string();
+        assert!(error.contains("line 2"), "{}", error);
+    }
+}
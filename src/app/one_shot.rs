@@ -0,0 +1,412 @@
+//! Answering one prompt without the TUI
+//!
+//! `--prompt <text>` sends the text as the first message of a new session
+//! and prints the reply as it streams in, then exits. The reply's text goes
+//! to stdout as it arrives, with nothing around it, so it can be piped into
+//! another program; tool activity and errors go to stderr. The exit code
+//! says how it ended: 0 once the session goes idle, 2 when the session
+//! reports an error, 1 when the prompt couldn't be sent at all, and 130
+//! when interrupted with ctrl+c, after the response is aborted.
+//!
+//! The program runs the same messages through the same update as the TUI,
+//! without a terminal; `OneShotOutput` watches those messages to decide what
+//! to print and when to stop.
+
+use crate::app::{event_msg::Msg, tea_model::Model, ui_components::message_log::SessionErrorBlock};
+use opencode_sdk::models::{Event, Message, Part, ToolState};
+use std::collections::{HashMap, HashSet};
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+/// The session went idle with the reply finished
+pub const EXIT_OK: u8 = 0;
+/// The prompt was never answered: no server, or the send failed
+pub const EXIT_FAILED: u8 = 1;
+/// The session reported an error while answering
+pub const EXIT_SESSION_ERROR: u8 = 2;
+/// Interrupted with ctrl+c, as shells report SIGINT
+pub const EXIT_INTERRUPTED: u8 = 130;
+
+/// Text for one of the output streams
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Output {
+    Stdout(String),
+    Stderr(String),
+}
+
+/// What has been printed of the reply so far, and how the run ended
+#[derive(Debug, Clone, Default)]
+pub struct OneShotOutput {
+    assistant_messages: HashSet<String>,
+    // Bytes of each text part already printed, by part id
+    printed: HashMap<String, usize>,
+    // Tool parts whose end was already reported
+    reported_tools: HashSet<String>,
+    stdout_ends_with_newline: bool,
+    stdout_written: bool,
+    exit_code: Option<u8>,
+}
+
+impl OneShotOutput {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The exit code, once the run has ended
+    pub fn exit_code(&self) -> Option<u8> {
+        self.exit_code
+    }
+
+    /// Ctrl+c ends the run, whatever else happened
+    pub fn interrupt(&mut self) {
+        self.exit_code = Some(EXIT_INTERRUPTED);
+    }
+
+    /// What to print for `msg`, given the model before it's applied
+    pub fn observe(&mut self, msg: &Msg, model: &Model) -> Vec<Output> {
+        match msg {
+            Msg::TaskResult(_, msg) => self.observe(msg, model),
+            Msg::EventReceived(event) => self.observe_event(event, model),
+            Msg::ResponseClientConnect(Err(error)) => self.fail(format!(
+                "Failed to connect to the OpenCode server: {}",
+                error
+            )),
+            Msg::ResponseServerCandidates(candidates) => {
+                let mut lines = vec![
+                    "Several OpenCode servers are running; set OPENCODE_SERVER_URL to pick one:"
+                        .to_string(),
+                ];
+                lines.extend(
+                    candidates.iter().map(|candidate| {
+                        format!("  {}  {}", candidate.url, candidate.project_path)
+                    }),
+                );
+                self.fail(lines.join("\n"))
+            }
+            Msg::ResponseSessionCreateWithMessage(Err(error)) => {
+                self.fail(format!("Failed to create a session: {}", error))
+            }
+            Msg::ResponseUserMessageSend(_, Err(error)) => {
+                self.fail(format!("Failed to send the prompt: {}", error))
+            }
+            Msg::SequenceAborted(error, _) => self.fail(error.clone()),
+            _ => Vec::new(),
+        }
+    }
+
+    /// The newline the reply is missing, if any, to print before exiting
+    pub fn finish(&mut self) -> Option<Output> {
+        if self.stdout_written && !self.stdout_ends_with_newline {
+            self.stdout_ends_with_newline = true;
+            return Some(Output::Stdout("\n".to_string()));
+        }
+        None
+    }
+
+    fn observe_event(&mut self, event: &Event, model: &Model) -> Vec<Output> {
+        let Some(session_id) = model.session().map(|session| session.id.as_str()) else {
+            return Vec::new();
+        };
+        match event {
+            Event::MessagePeriodUpdated(event) => {
+                if let Message::Assistant(message) = &*event.properties.info {
+                    if message.session_id == session_id {
+                        self.assistant_messages.insert(message.id.clone());
+                    }
+                }
+                Vec::new()
+            }
+            Event::MessagePeriodPartPeriodUpdated(event) => match &*event.properties.part {
+                Part::Text(part)
+                    if part.session_id == session_id
+                        && self.assistant_messages.contains(&part.message_id)
+                        && !part.synthetic.unwrap_or(false) =>
+                {
+                    self.print_text(&part.id, &part.text)
+                }
+                Part::Tool(part) if part.session_id == session_id => {
+                    let line = match &*part.state {
+                        ToolState::Completed(state) => format!("{}: {}", part.tool, state.title),
+                        ToolState::Error(state) => format!("{} failed: {}", part.tool, state.error),
+                        ToolState::Pending(_) | ToolState::Running(_) => return Vec::new(),
+                    };
+                    if !self.reported_tools.insert(part.id.clone()) {
+                        return Vec::new();
+                    }
+                    vec![Output::Stderr(line)]
+                }
+                _ => Vec::new(),
+            },
+            // Only once a reply has started, so an idle session left over
+            // from before the prompt doesn't end the run
+            Event::SessionPeriodIdle(event)
+                if event.properties.session_id == session_id
+                    && !self.assistant_messages.is_empty() =>
+            {
+                self.exit_code.get_or_insert(EXIT_OK);
+                Vec::new()
+            }
+            Event::SessionPeriodError(event)
+                if event
+                    .properties
+                    .session_id
+                    .as_deref()
+                    .is_none_or(|id| id == session_id) =>
+            {
+                let block = SessionErrorBlock::new(None, event.properties.error.as_deref());
+                self.exit_code.get_or_insert(EXIT_SESSION_ERROR);
+                vec![Output::Stderr(format!("{}: {}", block.name, block.message))]
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// The part of `text` not yet printed for part `part_id`
+    fn print_text(&mut self, part_id: &str, text: &str) -> Vec<Output> {
+        let printed = self.printed.get(part_id).copied();
+        let from = printed.unwrap_or(0);
+        // Text is only ever appended to while streaming; anything else
+        // already printed can't be taken back
+        if from >= text.len() || !text.is_char_boundary(from) {
+            return Vec::new();
+        }
+        let mut output = String::new();
+        // Parts of the reply are set apart by a blank line
+        if printed.is_none() && self.stdout_written {
+            output.push_str(if self.stdout_ends_with_newline {
+                "\n"
+            } else {
+                "\n\n"
+            });
+        }
+        output.push_str(&text[from..]);
+        self.printed.insert(part_id.to_string(), text.len());
+        self.stdout_written = true;
+        self.stdout_ends_with_newline = output.ends_with('\n');
+        vec![Output::Stdout(output)]
+    }
+
+    fn fail(&mut self, message: String) -> Vec<Output> {
+        self.exit_code.get_or_insert(EXIT_FAILED);
+        vec![Output::Stderr(message)]
+    }
+}
+
+/// Raised when ctrl+c is pressed, once the handler is installed
+#[derive(Debug, Clone, Default)]
+pub struct InterruptFlag(Arc<AtomicBool>);
+
+impl InterruptFlag {
+    /// A flag raised by SIGINT instead of the process being killed. Only
+    /// unix has the handler; elsewhere ctrl+c ends the process as usual.
+    pub fn install() -> Self {
+        let flag = Self::default();
+        #[cfg(unix)]
+        if let Err(e) = signal_hook::flag::register(signal_hook::consts::SIGINT, flag.0.clone()) {
+            tracing::warn!("Failed to install the SIGINT handler: {}", e);
+        }
+        flag
+    }
+
+    /// Whether ctrl+c was pressed since the last call
+    pub fn take(&self) -> bool {
+        self.0.swap(false, Ordering::SeqCst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{app::tea_model::SessionState, sdk::OpenCodeError};
+    use opencode_sdk::models::{
+        event_period_message_period_part_period_updated, event_period_message_period_updated,
+        event_period_session_period_error, event_period_session_period_idle, AssistantMessage,
+        AssistantMessageError, EventMessagePartUpdatedProperties, EventMessageUpdatedProperties,
+        EventPeriodMessagePeriodPartPeriodUpdated, EventPeriodMessagePeriodUpdated,
+        EventPeriodSessionPeriodError, EventPeriodSessionPeriodIdle, EventSessionErrorProperties,
+        EventSessionIdleProperties, Session, SessionTime, TextPart, ToolPart, ToolStateCompleted,
+        UnknownError, UnknownErrorData,
+    };
+
+    fn ready_model(session_id: &str) -> Model {
+        let mut model = Model::new();
+        model.session_state = SessionState::Ready(Session::new(
+            session_id.to_string(),
+            "Prompt".to_string(),
+            "0.3.1".to_string(),
+            SessionTime::new(0.0, 0.0),
+        ));
+        model
+    }
+
+    fn assistant_started(session_id: &str, message_id: &str) -> Msg {
+        let message = AssistantMessage {
+            id: message_id.to_string(),
+            session_id: session_id.to_string(),
+            ..Default::default()
+        };
+        Msg::EventReceived(Event::MessagePeriodUpdated(Box::new(
+            EventPeriodMessagePeriodUpdated::new(
+                event_period_message_period_updated::Type::MessagePeriodUpdated,
+                EventMessageUpdatedProperties::new(Message::Assistant(Box::new(message))),
+            ),
+        )))
+    }
+
+    fn part_updated(part: Part) -> Msg {
+        Msg::EventReceived(Event::MessagePeriodPartPeriodUpdated(Box::new(
+            EventPeriodMessagePeriodPartPeriodUpdated::new(
+                event_period_message_period_part_period_updated::Type::MessagePeriodPartPeriodUpdated,
+                EventMessagePartUpdatedProperties::new(part),
+            ),
+        )))
+    }
+
+    fn text(session_id: &str, message_id: &str, part_id: &str, text: &str) -> Msg {
+        part_updated(Part::Text(Box::new(TextPart {
+            id: part_id.to_string(),
+            session_id: session_id.to_string(),
+            message_id: message_id.to_string(),
+            text: text.to_string(),
+            ..Default::default()
+        })))
+    }
+
+    fn idle(session_id: &str) -> Msg {
+        Msg::EventReceived(Event::SessionPeriodIdle(Box::new(
+            EventPeriodSessionPeriodIdle::new(
+                event_period_session_period_idle::Type::SessionPeriodIdle,
+                EventSessionIdleProperties::new(session_id.to_string()),
+            ),
+        )))
+    }
+
+    fn observe_all(output: &mut OneShotOutput, model: &Model, msgs: &[Msg]) -> Vec<Output> {
+        msgs.iter()
+            .flat_map(|msg| output.observe(msg, model))
+            .collect()
+    }
+
+    fn stdout(text: &str) -> Output {
+        Output::Stdout(text.to_string())
+    }
+
+    #[test]
+    fn test_reply_text_printed_once_as_it_streams() {
+        let model = ready_model("ses_1");
+        let mut output = OneShotOutput::new();
+        let printed = observe_all(
+            &mut output,
+            &model,
+            &[
+                // The user's own message isn't echoed
+                text("ses_1", "msg_user", "prt_user", "What is 2+2?"),
+                assistant_started("ses_1", "msg_1"),
+                text("ses_1", "msg_1", "prt_1", "It is"),
+                text("ses_1", "msg_1", "prt_1", "It is"),
+                text("ses_1", "msg_1", "prt_1", "It is 4."),
+                text("ses_1", "msg_1", "prt_2", "Done.\n"),
+                // Another session's reply isn't ours
+                assistant_started("ses_2", "msg_2"),
+                text("ses_2", "msg_2", "prt_3", "Elsewhere"),
+                idle("ses_2"),
+            ],
+        );
+        assert_eq!(
+            printed,
+            [stdout("It is"), stdout(" 4."), stdout("\n\nDone.\n")]
+        );
+        assert_eq!(output.exit_code(), None);
+        assert_eq!(output.finish(), None);
+
+        output.observe(&idle("ses_1"), &model);
+        assert_eq!(output.exit_code(), Some(EXIT_OK));
+    }
+
+    #[test]
+    fn test_tools_and_errors_go_to_stderr() {
+        let model = ready_model("ses_1");
+        let mut output = OneShotOutput::new();
+        let tool = part_updated(Part::Tool(Box::new(ToolPart {
+            id: "prt_tool".to_string(),
+            session_id: "ses_1".to_string(),
+            message_id: "msg_1".to_string(),
+            tool: "read".to_string(),
+            state: Box::new(ToolState::Completed(Box::new(ToolStateCompleted {
+                title: "src/main.rs".to_string(),
+                ..Default::default()
+            }))),
+            ..Default::default()
+        })));
+        let error = AssistantMessageError::UnknownError(Box::new(UnknownError {
+            data: Box::new(UnknownErrorData {
+                message: "Rate limit exceeded".to_string(),
+            }),
+        }));
+        let session_error = Msg::EventReceived(Event::SessionPeriodError(Box::new(
+            EventPeriodSessionPeriodError::new(
+                event_period_session_period_error::Type::SessionPeriodError,
+                EventSessionErrorProperties {
+                    session_id: Some("ses_1".to_string()),
+                    error: Some(Box::new(error.clone())),
+                },
+            ),
+        )));
+
+        let printed = observe_all(
+            &mut output,
+            &model,
+            &[
+                assistant_started("ses_1", "msg_1"),
+                text("ses_1", "msg_1", "prt_1", "Reading"),
+                tool.clone(),
+                tool,
+                session_error,
+            ],
+        );
+        let block = SessionErrorBlock::new(None, Some(&error));
+        assert_eq!(
+            printed,
+            [
+                stdout("Reading"),
+                Output::Stderr("read: src/main.rs".to_string()),
+                Output::Stderr(format!("{}: Rate limit exceeded", block.name)),
+            ]
+        );
+        assert_eq!(output.exit_code(), Some(EXIT_SESSION_ERROR));
+
+        // An idle after the error doesn't make it a success
+        output.observe(&idle("ses_1"), &model);
+        assert_eq!(output.exit_code(), Some(EXIT_SESSION_ERROR));
+        assert_eq!(output.finish(), Some(stdout("\n")));
+        assert_eq!(output.finish(), None);
+    }
+
+    #[test]
+    fn test_idle_before_the_reply_and_failures() {
+        let model = ready_model("ses_1");
+        let mut output = OneShotOutput::new();
+        output.observe(&idle("ses_1"), &model);
+        assert_eq!(output.exit_code(), None);
+
+        let printed = output.observe(
+            &Msg::ResponseUserMessageSend(
+                "msg_1".to_string(),
+                Err(OpenCodeError::Unexpected("timed out".to_string())),
+            ),
+            &model,
+        );
+        assert_eq!(
+            printed,
+            [Output::Stderr(
+                "Failed to send the prompt: Unexpected error: timed out".to_string()
+            )]
+        );
+        assert_eq!(output.exit_code(), Some(EXIT_FAILED));
+
+        output.interrupt();
+        assert_eq!(output.exit_code(), Some(EXIT_INTERRUPTED));
+    }
+}
@@ -0,0 +1,269 @@
+//! Pasted text and dropped files
+//!
+//! Terminals deliver a file dropped onto the window as a paste of its path,
+//! often quoted and space-separated when there are several. A paste that
+//! looks like nothing but paths is checked against the filesystem and, when
+//! every file exists, attached instead of inserted as text.
+
+use crate::app::mentions::workspace_relative_path;
+use std::path::{Path, PathBuf};
+
+/// Normalize the line endings of pasted text, since some terminals send `\r`,
+/// and drop escape sequences and other control characters, as in colored
+/// output copied from a terminal, which would otherwise reach the server and
+/// the transcript. Newlines and tabs are kept.
+pub fn normalize_paste(text: &str) -> String {
+    strip_controls(&text.replace("\r\n", "\n").replace('\r', "\n"))
+}
+
+/// `text` without ANSI escape sequences or control characters other than
+/// newlines and tabs
+fn strip_controls(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\n' | '\t' => stripped.push(c),
+            // Escape sequences, like colors and titles, go with their parameters
+            '\u{1b}' => match chars.next() {
+                Some('[') => skip_control_sequence(&mut chars),
+                Some(']' | 'P' | '_' | '^' | 'X') => skip_control_string(&mut chars),
+                Some(' '..='/') => {
+                    while chars.next_if(|c| (' '..='/').contains(c)).is_some() {}
+                    chars.next();
+                }
+                _ => {}
+            },
+            '\u{9b}' => skip_control_sequence(&mut chars),
+            '\u{90}' | '\u{98}' | '\u{9d}' | '\u{9e}' | '\u{9f}' => skip_control_string(&mut chars),
+            c if c.is_control() => {}
+            c => stripped.push(c),
+        }
+    }
+    stripped
+}
+
+/// Skip a CSI sequence's parameters up to its final byte
+fn skip_control_sequence(chars: &mut impl Iterator<Item = char>) {
+    for c in chars.by_ref() {
+        if ('@'..='~').contains(&c) {
+            break;
+        }
+    }
+}
+
+/// Skip an OSC or similar string up to its BEL or string terminator
+fn skip_control_string(chars: &mut std::iter::Peekable<impl Iterator<Item = char>>) {
+    while let Some(c) = chars.next() {
+        match c {
+            '\u{7}' | '\u{9c}' => break,
+            '\u{1b}' => {
+                chars.next_if_eq(&'\\');
+                break;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Split a paste into paths, if every word of it looks like one. Quotes and
+/// backslash-escaped spaces keep a path with spaces together.
+pub fn split_pasted_paths(text: &str) -> Option<Vec<String>> {
+    let words = split_shell_words(text.trim())?;
+    if words.is_empty() || !words.iter().all(|word| looks_like_path(word)) {
+        return None;
+    }
+    Some(words)
+}
+
+/// Resolve pasted paths to files, or None unless every one of them exists.
+/// `~` expands to `home` and relative paths are taken from the workspace
+/// `root`. The result is relative to the root where possible.
+pub async fn resolve_pasted_paths(
+    paths: &[String],
+    home: Option<&Path>,
+    root: Option<&str>,
+) -> Option<Vec<String>> {
+    let mut resolved = Vec::new();
+    for path in paths {
+        let full_path = expand_path(path, home, root);
+        match tokio::fs::metadata(&full_path).await {
+            Ok(metadata) if metadata.is_file() => {}
+            _ => return None,
+        }
+        resolved.push(workspace_relative_path(&full_path.to_string_lossy(), root));
+    }
+    Some(resolved)
+}
+
+fn expand_path(path: &str, home: Option<&Path>, root: Option<&str>) -> PathBuf {
+    if let (Some(rest), Some(home)) = (path.strip_prefix("~/"), home) {
+        return home.join(rest);
+    }
+    let path = Path::new(path);
+    match root {
+        Some(root) if path.is_relative() => Path::new(root).join(path),
+        _ => path.to_path_buf(),
+    }
+}
+
+fn looks_like_path(word: &str) -> bool {
+    // URLs are pasted links, not dropped files
+    if word.contains("://") {
+        return false;
+    }
+    word.starts_with('/')
+        || word.starts_with("~/")
+        || word.starts_with("./")
+        || word.starts_with("../")
+        || word.contains('/')
+}
+
+/// Split on whitespace like a shell, honoring quotes and backslash escapes.
+/// None if a quote is left open.
+fn split_shell_words(text: &str) -> Option<Vec<String>> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut quote: Option<char> = None;
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(open), c) if c == open => quote = None,
+            (Some(_), c) => word.push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (None, '\\') => {
+                word.extend(chars.next());
+                in_word = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            (None, c) => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if quote.is_some() {
+        return None;
+    }
+    if in_word {
+        words.push(word);
+    }
+    Some(words)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_pasted_paths() {
+        assert_eq!(
+            split_pasted_paths("/tmp/a.png '/home/me/My Shots/b.png' ~/c.txt\n"),
+            Some(vec![
+                "/tmp/a.png".to_string(),
+                "/home/me/My Shots/b.png".to_string(),
+                "~/c.txt".to_string(),
+            ])
+        );
+        assert_eq!(
+            split_pasted_paths(r#""./notes dir/x.md" /var/log/with\ space.log"#),
+            Some(vec![
+                "./notes dir/x.md".to_string(),
+                "/var/log/with space.log".to_string(),
+            ])
+        );
+        assert_eq!(
+            split_pasted_paths("src/main.rs"),
+            Some(vec!["src/main.rs".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_prose_and_urls_are_not_paths() {
+        assert_eq!(split_pasted_paths("fix the bug in main"), None);
+        assert_eq!(split_pasted_paths("https://example.com/a.png"), None);
+        assert_eq!(split_pasted_paths("/tmp/a.png see https://x.io/b"), None);
+        assert_eq!(split_pasted_paths("'/tmp/unterminated"), None);
+        assert_eq!(split_pasted_paths("   "), None);
+    }
+
+    #[test]
+    fn test_normalize_paste() {
+        assert_eq!(normalize_paste("a\r\nb\rc\n"), "a\nb\nc\n");
+    }
+
+    #[test]
+    fn test_paste_drops_escapes_and_controls() {
+        // Colored `cargo test` output copied from a terminal
+        assert_eq!(
+            normalize_paste("test a ... \u{1b}[32mok\u{1b}[0m\r\n\u{1b}[1;31mFAILED\u{1b}[m\n"),
+            "test a ... ok\nFAILED\n"
+        );
+        // Titles and hyperlinks, ended by BEL or ST
+        assert_eq!(
+            normalize_paste(
+                "\u{1b}]0;title\u{7}see \u{1b}]8;;https://x.io\u{1b}\\docs\u{1b}]8;;\u{1b}\\"
+            ),
+            "see docs"
+        );
+        // Charset switches, C1 controls, and stray bells and backspaces
+        assert_eq!(
+            normalize_paste("\u{1b}(Bplain\u{9b}1mtext\u{7}\u{8}\u{0}\u{7f}"),
+            "plaintext"
+        );
+        // Tabs, newlines and multi-byte text are kept as they are
+        assert_eq!(
+            normalize_paste("fn 日本() {\n\t\"🦀\"\n}"),
+            "fn 日本() {\n\t\"🦀\"\n}"
+        );
+        // A bare CR is a line ending, not a carriage return over the line
+        assert_eq!(normalize_paste("50%\r100%"), "50%\n100%");
+        assert_eq!(normalize_paste("cut off \u{1b}["), "cut off ");
+    }
+
+    #[tokio::test]
+    async fn test_resolve_pasted_paths() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path().join("project");
+        std::fs::create_dir_all(root.join("docs")).unwrap();
+        std::fs::write(root.join("docs/report.md"), "report").unwrap();
+        std::fs::write(temp_dir.path().join("home file.txt"), "home").unwrap();
+        let root = root.to_string_lossy().to_string();
+
+        let resolved = resolve_pasted_paths(
+            &["docs/report.md".to_string(), "~/home file.txt".to_string()],
+            Some(temp_dir.path()),
+            Some(&root),
+        )
+        .await
+        .unwrap();
+        assert_eq!(resolved[0], "docs/report.md");
+        assert_eq!(
+            resolved[1],
+            temp_dir.path().join("home file.txt").to_string_lossy()
+        );
+
+        // One missing file means the paste is text after all
+        let missing = resolve_pasted_paths(
+            &["docs/report.md".to_string(), "docs/gone.md".to_string()],
+            None,
+            Some(&root),
+        )
+        .await;
+        assert_eq!(missing, None);
+
+        // Directories aren't attachments
+        let directory = resolve_pasted_paths(&["docs/".to_string()], None, Some(&root)).await;
+        assert_eq!(directory, None);
+    }
+}
@@ -0,0 +1,398 @@
+//! Where the time goes in the update, render and event pipeline
+//!
+//! The hot path is instrumented with debug spans and events under the
+//! [`PROFILE_TARGET`] target: each update, tagged with its message's variant,
+//! each frame with the history printed above the inline viewport and the
+//! draw within it, the batches drained from the event stream, and the polls
+//! of the task manager. They are left out of the log file unless `RUST_LOG`
+//! asks for them, and with nothing listening they cost next to nothing.
+//!
+//! Started with `--profile`, a [`Profile`] collects them for a report printed
+//! on exit: the slowest updates by message, the average frame time, and how
+//! many draws and server events there were a second.
+
+use std::collections::HashMap;
+use std::fmt::{self, Write};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{
+    field::{Field, Visit},
+    span, Event, Subscriber,
+};
+use tracing_subscriber::{
+    filter::{filter_fn, Filtered},
+    layer::Context,
+    registry::LookupSpan,
+    Layer,
+};
+
+/// Target of the spans and events of the pipeline
+pub const PROFILE_TARGET: &str = "opencoders::profile";
+
+/// Update variants listed in the report, slowest first
+const SLOWEST_UPDATE_COUNT: usize = 10;
+
+/// How many times something took time, and how much
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Timings {
+    count: u64,
+    total: Duration,
+    max: Duration,
+}
+
+impl Timings {
+    fn add(&mut self, elapsed: Duration) {
+        self.count += 1;
+        self.total += elapsed;
+        self.max = self.max.max(elapsed);
+    }
+
+    fn mean(&self) -> Duration {
+        match self.count {
+            0 => Duration::ZERO,
+            count => self.total / count as u32,
+        }
+    }
+}
+
+/// What the spans and events added up to so far
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProfileStats {
+    updates: HashMap<String, Timings>,
+    frames: Timings,
+    history_prints: Timings,
+    draws: Timings,
+    event_batches: u64,
+    events: u64,
+    task_polls: u64,
+    task_messages: u64,
+}
+
+impl ProfileStats {
+    /// Add a closed span of the pipeline, `msg` naming an update's message
+    fn add_span(&mut self, name: &str, msg: Option<&str>, elapsed: Duration) {
+        match name {
+            "update" => self
+                .updates
+                .entry(msg.unwrap_or("unknown").to_string())
+                .or_default()
+                .add(elapsed),
+            "frame" => self.frames.add(elapsed),
+            "insert_history" => self.history_prints.add(elapsed),
+            "draw" => self.draws.add(elapsed),
+            _ => {}
+        }
+    }
+
+    /// Add an event of the pipeline, `count` being what it counted
+    fn add_event(&mut self, name: &str, count: u64) {
+        match name {
+            "event_batch" => {
+                self.event_batches += 1;
+                self.events += count;
+            }
+            "task_poll" => {
+                self.task_polls += 1;
+                self.task_messages += count;
+            }
+            _ => {}
+        }
+    }
+
+    /// The report over a run of `elapsed`
+    pub fn report(&self, elapsed: Duration) -> String {
+        let per_second = |count: u64| match elapsed.as_secs_f64() {
+            secs if secs > 0.0 => count as f64 / secs,
+            _ => 0.0,
+        };
+        let ms = |duration: Duration| duration.as_secs_f64() * 1000.0;
+
+        let mut report = String::new();
+        let _ = writeln!(report, "Profile over {:.1}s", elapsed.as_secs_f64());
+        let _ = writeln!(
+            report,
+            "\n{:<32} {:>8} {:>10} {:>10}",
+            "Slowest updates", "count", "mean ms", "max ms"
+        );
+        let mut updates: Vec<(&String, &Timings)> = self.updates.iter().collect();
+        updates.sort_by(|a, b| b.1.mean().cmp(&a.1.mean()).then(a.0.cmp(b.0)));
+        for (msg, timings) in updates.into_iter().take(SLOWEST_UPDATE_COUNT) {
+            let _ = writeln!(
+                report,
+                "{:<32} {:>8} {:>10.3} {:>10.3}",
+                msg,
+                timings.count,
+                ms(timings.mean()),
+                ms(timings.max)
+            );
+        }
+        let _ = writeln!(report);
+        let _ = writeln!(
+            report,
+            "{:<32} {:>10.3} ms ({:.3} history, {:.3} draw)",
+            "Average frame",
+            ms(self.frames.mean()),
+            ms(self.history_prints.mean()),
+            ms(self.draws.mean())
+        );
+        let _ = writeln!(
+            report,
+            "{:<32} {:>10.1} /s ({} draws)",
+            "Draws",
+            per_second(self.draws.count),
+            self.draws.count
+        );
+        let _ = writeln!(
+            report,
+            "{:<32} {:>10.1} /s ({} in {} batches)",
+            "Server events",
+            per_second(self.events),
+            self.events,
+            self.event_batches
+        );
+        let _ = writeln!(
+            report,
+            "{:<32} {:>10.1} /s ({} messages)",
+            "Task polls",
+            per_second(self.task_polls),
+            self.task_messages
+        );
+        report
+    }
+}
+
+/// Collects the pipeline's spans for the report, from when it was created
+#[derive(Debug, Clone)]
+pub struct Profile {
+    stats: Arc<Mutex<ProfileStats>>,
+    started: Instant,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Self {
+            stats: Arc::default(),
+            started: Instant::now(),
+        }
+    }
+}
+
+impl Profile {
+    /// A layer feeding this profile, seeing only the pipeline's spans
+    pub fn layer<S>(&self) -> Filtered<ProfileLayer, impl tracing_subscriber::layer::Filter<S>, S>
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        ProfileLayer {
+            stats: self.stats.clone(),
+        }
+        .with_filter(filter_fn(|metadata| metadata.target() == PROFILE_TARGET))
+    }
+
+    pub fn stats(&self) -> ProfileStats {
+        self.stats
+            .lock()
+            .map(|stats| stats.clone())
+            .unwrap_or_default()
+    }
+
+    /// The report over the time since the profile was created
+    pub fn report(&self) -> String {
+        self.stats().report(self.started.elapsed())
+    }
+}
+
+/// Name of `msg`'s variant, read off the start of its `Debug` output so its
+/// fields are never formatted
+pub fn variant_name(msg: &impl fmt::Debug) -> String {
+    /// Keeps the first write, which is the name, and stops the rest
+    struct FirstWrite(String);
+
+    impl Write for FirstWrite {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            if !self.0.is_empty() {
+                return Err(fmt::Error);
+            }
+            self.0.push_str(s);
+            Ok(())
+        }
+    }
+
+    let mut name = FirstWrite(String::new());
+    let _ = write!(name, "{:?}", msg);
+    name.0
+}
+
+/// Start of a span and the message it's updating with, if any
+struct SpanTiming {
+    started: Instant,
+    msg: Option<String>,
+}
+
+/// Reads the `msg` field of an update span
+struct MsgField<'a>(&'a mut Option<String>);
+
+impl Visit for MsgField<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "msg" {
+            *self.0 = Some(value.to_string());
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "msg" {
+            *self.0 = Some(format!("{:?}", value));
+        }
+    }
+}
+
+/// Reads the count an event of the pipeline carries
+#[derive(Default)]
+struct CountField(u64);
+
+impl Visit for CountField {
+    fn record_u64(&mut self, _field: &Field, value: u64) {
+        self.0 = value;
+    }
+
+    fn record_i64(&mut self, _field: &Field, value: i64) {
+        self.0 = value.max(0) as u64;
+    }
+
+    fn record_debug(&mut self, _field: &Field, _value: &dyn fmt::Debug) {}
+}
+
+pub struct ProfileLayer {
+    stats: Arc<Mutex<ProfileStats>>,
+}
+
+impl<S> Layer<S> for ProfileLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        let mut msg = None;
+        attrs.record(&mut MsgField(&mut msg));
+        if let Some(span) = ctx.span(id) {
+            span.extensions_mut().insert(SpanTiming {
+                started: Instant::now(),
+                msg,
+            });
+        }
+    }
+
+    fn on_record(&self, id: &span::Id, values: &span::Record<'_>, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            if let Some(timing) = span.extensions_mut().get_mut::<SpanTiming>() {
+                values.record(&mut MsgField(&mut timing.msg));
+            }
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut count = CountField::default();
+        event.record(&mut count);
+        if let Ok(mut stats) = self.stats.lock() {
+            stats.add_event(event.metadata().name(), count.0);
+        }
+    }
+
+    fn on_close(&self, id: span::Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(&id) else {
+            return;
+        };
+        let extensions = span.extensions();
+        let Some(timing) = extensions.get::<SpanTiming>() else {
+            return;
+        };
+        if let Ok(mut stats) = self.stats.lock() {
+            stats.add_span(span.name(), timing.msg.as_deref(), timing.started.elapsed());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::event_msg::Msg;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[test]
+    fn test_variant_name_skips_the_fields() {
+        assert_eq!(variant_name(&Msg::Quit), "Quit");
+        assert_eq!(variant_name(&Msg::ScrollMessageLog(-5)), "ScrollMessageLog");
+        assert_eq!(
+            variant_name(&Msg::ResponseLinkPaths(vec![("a".to_string(), None)])),
+            "ResponseLinkPaths"
+        );
+    }
+
+    #[test]
+    fn test_spans_add_up_by_message_and_stage() {
+        let profile = Profile::default();
+        let subscriber = tracing_subscriber::registry().with(profile.layer());
+        tracing::subscriber::with_default(subscriber, || {
+            for msg in [Msg::Quit, Msg::Quit, Msg::ScrollMessageLog(1)] {
+                let span = tracing::debug_span!(target: PROFILE_TARGET, "update", msg = tracing::field::Empty);
+                span.record("msg", variant_name(&msg));
+                let _entered = span.enter();
+                if msg == Msg::ScrollMessageLog(1) {
+                    std::thread::sleep(Duration::from_millis(5));
+                }
+            }
+            tracing::debug_span!(target: PROFILE_TARGET, "frame").in_scope(|| {
+                tracing::debug_span!(target: PROFILE_TARGET, "insert_history").in_scope(|| {});
+                tracing::debug_span!(target: PROFILE_TARGET, "draw").in_scope(|| {});
+            });
+            tracing::debug!(name: "event_batch", target: PROFILE_TARGET, events = 3);
+            tracing::debug!(name: "event_batch", target: PROFILE_TARGET, events = 2);
+            tracing::debug!(name: "task_poll", target: PROFILE_TARGET, messages = 0);
+            // Anything else is left to the log
+            tracing::debug_span!("update", msg = "Elsewhere").in_scope(|| {});
+            tracing::debug!(name: "event_batch", events = 10);
+        });
+
+        let stats = profile.stats();
+        assert_eq!(stats.updates.len(), 2);
+        assert_eq!(stats.updates["Quit"].count, 2);
+        let scroll = stats.updates["ScrollMessageLog"];
+        assert_eq!(scroll.count, 1);
+        assert!(scroll.max >= Duration::from_millis(5));
+        assert_eq!(stats.frames.count, 1);
+        assert_eq!(stats.history_prints.count, 1);
+        assert_eq!(stats.draws.count, 1);
+        assert_eq!((stats.event_batches, stats.events), (2, 5));
+        assert_eq!((stats.task_polls, stats.task_messages), (1, 0));
+    }
+
+    #[test]
+    fn test_report_lists_the_slowest_updates_first() {
+        let mut stats = ProfileStats::default();
+        for n in 0..12_u64 {
+            stats.add_span(
+                "update",
+                Some(&format!("Msg{}", n)),
+                Duration::from_millis(n),
+            );
+        }
+        stats.add_span("draw", None, Duration::from_millis(4));
+        stats.add_span("frame", None, Duration::from_millis(6));
+        stats.add_event("event_batch", 30);
+
+        let report = stats.report(Duration::from_secs(2));
+        let rows: Vec<&str> = report
+            .lines()
+            .skip_while(|line| !line.starts_with("Slowest updates"))
+            .skip(1)
+            .take_while(|line| !line.is_empty())
+            .collect();
+        assert_eq!(rows.len(), SLOWEST_UPDATE_COUNT);
+        assert!(rows[0].starts_with("Msg11 "), "{}", report);
+        assert!(rows[9].starts_with("Msg2 "), "{}", report);
+        assert!(report.contains("Average frame"));
+        assert!(report.contains("6.000 ms"), "{}", report);
+        // A draw and 30 events over 2 seconds
+        assert!(report.contains("0.5 /s (1 draws)"), "{}", report);
+        assert!(report.contains("15.0 /s (30 in 1 batches)"), "{}", report);
+    }
+}
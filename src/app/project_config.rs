@@ -0,0 +1,254 @@
+//! Per-project settings
+//!
+//! A team can keep a `.opencoders.toml` at the root of a repository, checked
+//! in or ignored, to point everyone at the same server or model. It's found
+//! by walking up from the working directory, stopping at the repository
+//! root or the filesystem root. Since the file comes with the code, only a
+//! few keys are honored, and any other key is an error rather than ignored.
+//!
+//! ```toml
+//! server_url = "http://127.0.0.1:4096"
+//! provider = "anthropic"
+//! model = "claude-sonnet-4-20250514"
+//! agent = "plan"
+//! ```
+//!
+//! The same keys can go under `[defaults]` in the user config. Each setting
+//! is taken from the first of: `$OPENCODE_SERVER_URL` for the server, the
+//! project file, the user config, and the built-in default.
+
+use crate::app::error::Result;
+use crate::sdk::discovery::SERVER_URL_ENV;
+use eyre::WrapErr;
+use serde::Deserialize;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+pub const PROJECT_CONFIG_FILE: &str = ".opencoders.toml";
+
+/// The settings a project can override, the only keys its file may set
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProjectSettings {
+    pub server_url: Option<String>,
+    pub provider: Option<String>,
+    pub model: Option<String>,
+    /// Mode selected at startup, like "build" or "plan"
+    pub agent: Option<String>,
+}
+
+impl ProjectSettings {
+    pub fn parse(content: &str) -> Result<Self> {
+        Ok(toml::from_str(content)?)
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .wrap_err_with(|| format!("Failed to read {}", path.display()))?;
+        Self::parse(&content).wrap_err_with(|| format!("Invalid project config {}", path.display()))
+    }
+}
+
+/// Where a setting was taken from
+#[derive(Debug, Clone, PartialEq)]
+pub enum SettingSource {
+    Environment,
+    ProjectFile(PathBuf),
+    UserConfig,
+}
+
+impl fmt::Display for SettingSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SettingSource::Environment => write!(f, "${}", SERVER_URL_ENV),
+            SettingSource::ProjectFile(path) => write!(f, "{}", path.display()),
+            SettingSource::UserConfig => write!(f, "config file"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Setting {
+    pub value: String,
+    pub source: SettingSource,
+}
+
+/// The project settings after every layer, None where the built-in default
+/// is used
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ResolvedSettings {
+    pub server_url: Option<Setting>,
+    pub provider: Option<Setting>,
+    pub model: Option<Setting>,
+    pub agent: Option<Setting>,
+}
+
+impl ResolvedSettings {
+    /// Take the settings `layer` sets over those of earlier layers
+    pub fn overlay(&mut self, layer: &ProjectSettings, source: SettingSource) {
+        let fields = [
+            (&mut self.server_url, &layer.server_url),
+            (&mut self.provider, &layer.provider),
+            (&mut self.model, &layer.model),
+            (&mut self.agent, &layer.agent),
+        ];
+        for (setting, value) in fields {
+            if let Some(value) = value {
+                *setting = Some(Setting {
+                    value: value.clone(),
+                    source: source.clone(),
+                });
+            }
+        }
+    }
+
+    pub fn value(setting: &Option<Setting>) -> Option<&str> {
+        setting.as_ref().map(|setting| setting.value.as_str())
+    }
+
+    /// Each setting and where it came from, for the log and doctor
+    pub fn describe(&self) -> Vec<String> {
+        [
+            ("server_url", &self.server_url),
+            ("provider", &self.provider),
+            ("model", &self.model),
+            ("agent", &self.agent),
+        ]
+        .into_iter()
+        .map(|(name, setting)| match setting {
+            Some(setting) => format!("{} {} ({})", name, setting.value, setting.source),
+            None => format!("{} default", name),
+        })
+        .collect()
+    }
+}
+
+/// The nearest project file in `dir` or above it, up to the repository root
+pub fn find_project_file(dir: &Path) -> Option<PathBuf> {
+    for dir in dir.ancestors() {
+        let path = dir.join(PROJECT_CONFIG_FILE);
+        if path.is_file() {
+            return Some(path);
+        }
+        // Files above the repository belong to something else
+        if dir.join(".git").exists() {
+            return None;
+        }
+    }
+    None
+}
+
+/// Apply the project file found from `dir`, then the environment, on top of
+/// the settings from the user config
+pub fn apply(settings: &mut ResolvedSettings, dir: &Path) -> Result<()> {
+    if let Some(path) = find_project_file(dir) {
+        let project = ProjectSettings::load(&path)?;
+        settings.overlay(&project, SettingSource::ProjectFile(path));
+    }
+    let environment = ProjectSettings {
+        server_url: std::env::var(SERVER_URL_ENV).ok(),
+        ..ProjectSettings::default()
+    };
+    settings.overlay(&environment, SettingSource::Environment);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_later_layers_take_precedence() {
+        let mut settings = ResolvedSettings::default();
+        settings.overlay(
+            &ProjectSettings {
+                server_url: Some("http://user:4096".to_string()),
+                model: Some("user-model".to_string()),
+                agent: Some("plan".to_string()),
+                ..ProjectSettings::default()
+            },
+            SettingSource::UserConfig,
+        );
+        let project_path = PathBuf::from("/repo/.opencoders.toml");
+        settings.overlay(
+            &ProjectSettings {
+                server_url: Some("http://project:4096".to_string()),
+                model: Some("project-model".to_string()),
+                ..ProjectSettings::default()
+            },
+            SettingSource::ProjectFile(project_path.clone()),
+        );
+        settings.overlay(
+            &ProjectSettings {
+                server_url: Some("http://env:4096".to_string()),
+                ..ProjectSettings::default()
+            },
+            SettingSource::Environment,
+        );
+
+        assert_eq!(
+            settings.server_url,
+            Some(Setting {
+                value: "http://env:4096".to_string(),
+                source: SettingSource::Environment,
+            })
+        );
+        assert_eq!(
+            settings.model.as_ref().map(|setting| &setting.source),
+            Some(&SettingSource::ProjectFile(project_path))
+        );
+        assert_eq!(ResolvedSettings::value(&settings.agent), Some("plan"));
+        assert_eq!(settings.provider, None);
+        assert_eq!(
+            settings.describe(),
+            vec![
+                "server_url http://env:4096 ($OPENCODE_SERVER_URL)",
+                "provider default",
+                "model project-model (/repo/.opencoders.toml)",
+                "agent plan (config file)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_project_file_found_up_to_the_repository_root() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let outside = temp_dir.path();
+        let repo = outside.join("repo");
+        let nested = repo.join("src").join("app");
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::write(outside.join(PROJECT_CONFIG_FILE), "model = \"outside\"").unwrap();
+
+        // Without a repository the walk goes on up
+        assert_eq!(
+            find_project_file(&nested),
+            Some(outside.join(PROJECT_CONFIG_FILE))
+        );
+
+        // It stops at the repository root
+        std::fs::create_dir(repo.join(".git")).unwrap();
+        assert_eq!(find_project_file(&nested), None);
+        std::fs::write(repo.join(PROJECT_CONFIG_FILE), "model = \"repo\"").unwrap();
+        assert_eq!(
+            find_project_file(&nested),
+            Some(repo.join(PROJECT_CONFIG_FILE))
+        );
+
+        // The filesystem root ends it without one
+        assert_eq!(find_project_file(Path::new("/")), None);
+    }
+
+    #[test]
+    fn test_only_whitelisted_keys_are_accepted() {
+        let settings = ProjectSettings::parse(
+            "server_url = \"http://127.0.0.1:4096\"\nprovider = \"openai\"\nmodel = \"gpt-4.1\"\nagent = \"plan\"",
+        )
+        .unwrap();
+        assert_eq!(settings.agent.as_deref(), Some("plan"));
+
+        let error = ProjectSettings::parse("model = \"gpt-4.1\"\n[redaction]\nenabled = false")
+            .unwrap_err();
+        assert!(format!("{:#}", error).contains("unknown field `redaction`"));
+        assert!(ProjectSettings::parse("shell = \"rm -rf /\"").is_err());
+    }
+}
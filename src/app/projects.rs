@@ -0,0 +1,358 @@
+//! Several projects in one TUI
+//!
+//! Each project is served by its own OpenCode server. The active project's
+//! state lives in the model as usual; switching to another project parks it
+//! here, keyed by its project root, with its client and event stream still
+//! connected. Events from a parked project's stream keep its activity
+//! current, so the project picker shows which ones are working or have
+//! finished a response since they were left.
+
+use crate::app::{
+    event_msg::{Cmd, CmdOrBatch},
+    message_state::MessageState,
+    provider_models::ProviderModels,
+    strings,
+    tea_model::{
+        AppModalState, ConnectionStatus, EventStreamState, Model, ModesLoad, NotificationLevel,
+        SessionState, TimeoutType,
+    },
+    ui_components::SessionSelector,
+};
+use crate::sdk::{extensions::git::GitInfo, OpenCodeClient};
+use opencode_sdk::models::{ConfigAgent, Event, File, Message, Session};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::time::SystemTime;
+
+/// Slash command that connects to a server by URL, as another project
+pub const CONNECT_COMMAND: &str = "/connect";
+
+/// The URL of a `/connect <url>` command, empty if none was given
+pub fn parse_connect_command(input: &str) -> Option<&str> {
+    let rest = input.trim().strip_prefix(CONNECT_COMMAND)?;
+    if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+    Some(rest.trim())
+}
+
+/// What a parked project's current session is doing
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ProjectActivity {
+    Idle,
+    Working,
+    /// A response finished since the project was left
+    Finished,
+}
+
+impl ProjectActivity {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ProjectActivity::Idle => "",
+            ProjectActivity::Working => "working",
+            ProjectActivity::Finished => "done",
+        }
+    }
+}
+
+/// The project-specific parts of the model, while another project is active
+#[derive(Debug, Clone)]
+pub struct ProjectState {
+    pub client: OpenCodeClient,
+    pub connection_status: ConnectionStatus,
+    pub session_state: SessionState,
+    pub sessions: Vec<Session>,
+    pub session_directories: HashMap<String, String>,
+    pub session_selector: SessionSelector,
+    pub modes: Option<ConfigAgent>,
+    pub modes_load: ModesLoad,
+    pub provider_models: Option<ProviderModels>,
+    pub mode_state: Option<u16>,
+    pub message_state: MessageState,
+    pub event_stream_state: EventStreamState,
+    pub session_is_idle: bool,
+    pub response_started_at: Option<SystemTime>,
+    /// When the agent started working, see `WorkingTime`
+    pub working_since: Option<SystemTime>,
+    pub server_version: Option<String>,
+    pub file_status: Vec<File>,
+    pub git_info: Option<GitInfo>,
+    pub workspace_root: Option<String>,
+    pub previous_session_id: Option<String>,
+    /// Whether a response finished while the project was parked
+    pub finished_while_parked: bool,
+}
+
+impl ProjectState {
+    pub fn activity(&self) -> ProjectActivity {
+        if !self.session_is_idle {
+            ProjectActivity::Working
+        } else if self.finished_while_parked {
+            ProjectActivity::Finished
+        } else {
+            ProjectActivity::Idle
+        }
+    }
+
+    pub fn session_id(&self) -> Option<&str> {
+        match &self.session_state {
+            SessionState::Ready(session) => Some(&session.id),
+            _ => None,
+        }
+    }
+
+    /// Keep the activity and session list current from one of the
+    /// project's events, returning whether its activity changed
+    pub fn apply_event(&mut self, event: &Event) -> bool {
+        let before = self.activity();
+        match event {
+            Event::MessagePeriodUpdated(message_event) => {
+                if let Message::Assistant(message) = &*message_event.properties.info {
+                    if self.session_id() == Some(message.session_id.as_str())
+                        && message.time.completed.is_none()
+                    {
+                        self.session_is_idle = false;
+                    }
+                }
+            }
+            Event::SessionPeriodIdle(idle_event)
+                if self.session_id() == Some(idle_event.properties.session_id.as_str())
+                    && !self.session_is_idle =>
+            {
+                self.session_is_idle = true;
+                self.response_started_at = None;
+                self.finished_while_parked = true;
+            }
+            Event::SessionPeriodUpdated(session_event) => {
+                let updated = &*session_event.properties.info;
+                if let Some(session) = self.sessions.iter_mut().find(|s| s.id == updated.id) {
+                    *session = updated.clone();
+                }
+                if self.session_id() == Some(updated.id.as_str()) {
+                    self.session_state = SessionState::Ready(updated.clone());
+                }
+            }
+            _ => {}
+        }
+        self.activity() != before
+    }
+}
+
+/// The parked projects, by project root
+#[derive(Debug, Clone, Default)]
+pub struct Projects {
+    parked: BTreeMap<String, ProjectState>,
+}
+
+impl Projects {
+    pub fn park(&mut self, root: String, project: ProjectState) {
+        self.parked.insert(root, project);
+    }
+
+    pub fn take(&mut self, root: &str) -> Option<ProjectState> {
+        self.parked.remove(root)
+    }
+
+    pub fn get_mut(&mut self, root: &str) -> Option<&mut ProjectState> {
+        self.parked.get_mut(root)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &ProjectState)> {
+        self.parked.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&String, &mut ProjectState)> {
+        self.parked.iter_mut()
+    }
+
+    /// Root of the parked project served from `url`
+    pub fn root_for_url(&self, url: &str) -> Option<String> {
+        self.parked
+            .iter()
+            .find(|(_, project)| project.client.base_url() == url)
+            .map(|(root, _)| root.clone())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.parked.is_empty()
+    }
+}
+
+/// A new session's first message is still being sent, and its response
+/// would land in whichever project is active by then
+fn is_switch_blocked(model: &mut Model) -> bool {
+    let blocked = matches!(model.session_state, SessionState::Creating(_));
+    if blocked {
+        model.push_notification(
+            NotificationLevel::Warning,
+            strings::WAIT_FOR_NEW_SESSION.to_string(),
+            false,
+        );
+    }
+    blocked
+}
+
+/// Park the active project with its draft saved, returning the save
+fn park_active_project(model: &mut Model) -> Vec<Cmd> {
+    let mut cmds = Vec::new();
+    let draft = model.current_draft();
+    if !draft.is_empty() || model.is_timeout_active(&TimeoutType::DebounceSaveDraft) {
+        cmds.push(Cmd::AsyncSaveDraft(model.draft_key(), draft));
+    }
+    model.clear_timeout(&TimeoutType::DebounceSaveDraft);
+    model.text_input_area.clear();
+    model.attached_files.clear();
+    if let Some((root, project)) = model.park_project() {
+        model.projects.park(root, project);
+    }
+    cmds
+}
+
+/// Switch to the server at `url`, connecting to it unless it already is
+pub fn connect_project(model: &mut Model, url: &str) -> CmdOrBatch<Cmd> {
+    let url = url.trim_end_matches('/');
+    if model
+        .client()
+        .is_some_and(|client| client.base_url() == url)
+    {
+        model.push_notification(
+            NotificationLevel::Info,
+            format!("Already connected to {}", url),
+            false,
+        );
+        return CmdOrBatch::Single(Cmd::None);
+    }
+    if let Some(root) = model.projects.root_for_url(url) {
+        return resume_project(model, &root);
+    }
+    if is_switch_blocked(model) {
+        return CmdOrBatch::Single(Cmd::None);
+    }
+    let mut cmds = park_active_project(model);
+    model.set_state(AppModalState::Connecting(ConnectionStatus::Connecting));
+    model.connection_status = ConnectionStatus::Connecting;
+    cmds.push(Cmd::AsyncConnectServer(url.to_string()));
+    CmdOrBatch::Batch(cmds)
+}
+
+/// Switch to a parked project. Its messages are fetched again, since only
+/// its activity was kept current while it was parked.
+pub fn resume_project(model: &mut Model, root: &str) -> CmdOrBatch<Cmd> {
+    if is_switch_blocked(model) {
+        return CmdOrBatch::Single(Cmd::None);
+    }
+    let Some(project) = model.projects.take(root) else {
+        return CmdOrBatch::Single(Cmd::None);
+    };
+    let mut cmds = park_active_project(model);
+    model.restore_project(project);
+    model.set_state(AppModalState::None);
+    cmds.push(Cmd::AsyncLoadDraft(model.draft_key()));
+    if let (Some(client), Some(session)) = (model.client.clone(), model.session()) {
+        let session_id = session.id.clone();
+        model.message_state.begin_load();
+        cmds.push(Cmd::AsyncLoadSessionMessages(client, session_id));
+    }
+    CmdOrBatch::Batch(cmds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opencode_sdk::models::{
+        event_period_message_period_updated, event_period_session_period_idle, AssistantMessage,
+        AssistantMessageTime, EventMessageUpdatedProperties, EventPeriodMessagePeriodUpdated,
+        EventPeriodSessionPeriodIdle, EventSessionIdleProperties, SessionTime,
+    };
+
+    fn parked_project(session_id: &str) -> ProjectState {
+        let session = Session::new(
+            session_id.to_string(),
+            "title".to_string(),
+            "0.0.0".to_string(),
+            SessionTime::new(0.0, 0.0),
+        );
+        ProjectState {
+            client: OpenCodeClient::new("http://127.0.0.1:4096"),
+            connection_status: ConnectionStatus::SessionReady,
+            session_state: SessionState::Ready(session.clone()),
+            sessions: vec![session],
+            session_directories: HashMap::new(),
+            session_selector: SessionSelector::new(),
+            modes: None,
+            modes_load: ModesLoad::NotStarted,
+            provider_models: None,
+            mode_state: None,
+            message_state: MessageState::new(),
+            event_stream_state: EventStreamState::Disconnected,
+            session_is_idle: true,
+            response_started_at: None,
+            working_since: None,
+            server_version: None,
+            file_status: Vec::new(),
+            git_info: None,
+            workspace_root: Some("/work/a".to_string()),
+            previous_session_id: None,
+            finished_while_parked: false,
+        }
+    }
+
+    fn idle_event(session_id: &str) -> Event {
+        Event::SessionPeriodIdle(Box::new(EventPeriodSessionPeriodIdle::new(
+            event_period_session_period_idle::Type::SessionPeriodIdle,
+            EventSessionIdleProperties::new(session_id.to_string()),
+        )))
+    }
+
+    fn assistant_event(session_id: &str) -> Event {
+        let message = AssistantMessage {
+            id: "msg_1".to_string(),
+            session_id: session_id.to_string(),
+            time: Box::new(AssistantMessageTime::new(0.0)),
+            ..Default::default()
+        };
+        Event::MessagePeriodUpdated(Box::new(EventPeriodMessagePeriodUpdated::new(
+            event_period_message_period_updated::Type::MessagePeriodUpdated,
+            EventMessageUpdatedProperties::new(Message::Assistant(Box::new(message))),
+        )))
+    }
+
+    #[test]
+    fn test_events_track_the_parked_session() {
+        let mut project = parked_project("ses_a");
+        assert_eq!(project.activity(), ProjectActivity::Idle);
+
+        // Other sessions of the project don't count
+        assert!(!project.apply_event(&assistant_event("ses_other")));
+        assert!(!project.apply_event(&idle_event("ses_other")));
+
+        assert!(project.apply_event(&assistant_event("ses_a")));
+        assert_eq!(project.activity(), ProjectActivity::Working);
+        assert!(!project.apply_event(&assistant_event("ses_a")));
+
+        assert!(project.apply_event(&idle_event("ses_a")));
+        assert_eq!(project.activity(), ProjectActivity::Finished);
+    }
+
+    #[test]
+    fn test_parse_connect_command() {
+        assert_eq!(
+            parse_connect_command("/connect http://localhost:4096 "),
+            Some("http://localhost:4096")
+        );
+        assert_eq!(parse_connect_command("/connect"), Some(""));
+        assert_eq!(parse_connect_command("/connection"), None);
+        assert_eq!(parse_connect_command("connect"), None);
+    }
+
+    #[test]
+    fn test_root_for_url() {
+        let mut projects = Projects::default();
+        projects.park("/work/a".to_string(), parked_project("ses_a"));
+        assert_eq!(
+            projects.root_for_url("http://127.0.0.1:4096"),
+            Some("/work/a".to_string())
+        );
+        assert_eq!(projects.root_for_url("http://127.0.0.1:4097"), None);
+    }
+}
@@ -0,0 +1,191 @@
+//! Check that the selected model is one its provider serves
+//!
+//! The provider and model are picked separately: the provider is a default,
+//! while the model comes from the active mode's agent config or a default of
+//! its own. Nothing stops the pair from disagreeing, and the server answers
+//! such a send with an error that doesn't say why. Once the providers are
+//! loaded, the status bar warns about a mismatch and a failed send names the
+//! models the provider does offer. With none configured at all, as on a fresh
+//! install, nothing can be sent and the onboarding modal says how to add one.
+//!
+//! The models' context limits are kept too, for `token_estimate`.
+
+use opencode_sdk::models::ConfigProviders200Response;
+use std::collections::{BTreeSet, HashMap};
+
+/// Most model ids listed in a hint before the rest are cut
+const HINT_MODEL_COUNT: usize = 3;
+
+/// Model ids served by each provider, by provider id
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProviderModels {
+    models: HashMap<String, BTreeSet<String>>,
+    /// Context window in tokens, by provider and model id, where declared
+    context_limits: HashMap<(String, String), usize>,
+}
+
+impl ProviderModels {
+    pub fn from_response(response: &ConfigProviders200Response) -> Self {
+        let models = response
+            .providers
+            .iter()
+            .map(|provider| {
+                (
+                    provider.id.clone(),
+                    provider.models.keys().cloned().collect(),
+                )
+            })
+            .collect();
+        let context_limits = response
+            .providers
+            .iter()
+            .flat_map(|provider| {
+                provider.models.iter().filter_map(|(model_id, model)| {
+                    // An undeclared limit comes through as zero
+                    let context = model.limit.context;
+                    (context > 0.0)
+                        .then(|| ((provider.id.clone(), model_id.clone()), context as usize))
+                })
+            })
+            .collect();
+        Self {
+            models,
+            context_limits,
+        }
+    }
+
+    /// Tokens `model_id` of `provider_id` can take in, if declared
+    pub fn context_limit(&self, provider_id: &str, model_id: &str) -> Option<usize> {
+        self.context_limits
+            .get(&(provider_id.to_string(), model_id.to_string()))
+            .copied()
+    }
+
+    /// Whether no provider is configured, so no model can be used
+    pub fn is_empty(&self) -> bool {
+        self.models.is_empty()
+    }
+
+    /// Whether `provider_id` serves `model_id`
+    pub fn is_offered(&self, provider_id: &str, model_id: &str) -> bool {
+        self.models
+            .get(provider_id)
+            .is_some_and(|models| models.contains(model_id))
+    }
+
+    /// What to do about a pair the provider doesn't serve, `None` if it does
+    pub fn mismatch_hint(&self, provider_id: &str, model_id: &str) -> Option<String> {
+        if self.is_offered(provider_id, model_id) {
+            return None;
+        }
+        let Some(models) = self.models.get(provider_id) else {
+            return Some(format!("provider {} is not configured", provider_id));
+        };
+        let mut listed: Vec<&str> = models
+            .iter()
+            .take(HINT_MODEL_COUNT)
+            .map(String::as_str)
+            .collect();
+        if models.len() > HINT_MODEL_COUNT {
+            listed.push("…");
+        }
+        Some(format!(
+            "{} doesn't offer {}, try one of: {}",
+            provider_id,
+            model_id,
+            listed.join(", ")
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opencode_sdk::models::{Model, ModelLimit, Provider};
+
+    fn provider(id: &str, model_ids: &[&str]) -> Provider {
+        let models = model_ids
+            .iter()
+            .map(|model_id| (model_id.to_string(), Model::default()))
+            .collect();
+        Provider::new(id.to_string(), Vec::new(), id.to_string(), models)
+    }
+
+    fn fixture() -> ProviderModels {
+        ProviderModels::from_response(&ConfigProviders200Response::new(
+            vec![
+                provider("anthropic", &["claude-sonnet-4-20250514", "claude-opus-4"]),
+                provider(
+                    "openai",
+                    &["gpt-4.1", "gpt-4.1-mini", "gpt-4o", "o3", "o4-mini"],
+                ),
+            ],
+            HashMap::new(),
+        ))
+    }
+
+    #[test]
+    fn test_model_offered_only_by_its_own_provider() {
+        let models = fixture();
+        assert!(models.is_offered("anthropic", "claude-opus-4"));
+        assert!(models.is_offered("openai", "gpt-4o"));
+        assert!(!models.is_offered("anthropic", "gpt-4o"));
+        assert!(!models.is_offered("openai", "claude-opus-4"));
+        assert!(!models.is_offered("google", "gemini-2.5-pro"));
+    }
+
+    #[test]
+    fn test_mismatch_hint_lists_what_the_provider_offers() {
+        let models = fixture();
+        assert_eq!(models.mismatch_hint("anthropic", "claude-opus-4"), None);
+        assert_eq!(
+            models.mismatch_hint("anthropic", "gpt-4o").as_deref(),
+            Some("anthropic doesn't offer gpt-4o, try one of: claude-opus-4, claude-sonnet-4-20250514")
+        );
+        assert_eq!(
+            models.mismatch_hint("openai", "claude-opus-4").as_deref(),
+            Some(
+                "openai doesn't offer claude-opus-4, try one of: gpt-4.1, gpt-4.1-mini, gpt-4o, …"
+            )
+        );
+        assert_eq!(
+            models.mismatch_hint("google", "gemini-2.5-pro").as_deref(),
+            Some("provider google is not configured")
+        );
+    }
+
+    #[test]
+    fn test_context_limits_where_declared() {
+        let with_limit = |context: f64| Model {
+            limit: Box::new(ModelLimit::new(context, 8_192.0)),
+            ..Model::default()
+        };
+        let anthropic = Provider::new(
+            "anthropic".to_string(),
+            Vec::new(),
+            "anthropic".to_string(),
+            HashMap::from([
+                (
+                    "claude-sonnet-4-20250514".to_string(),
+                    with_limit(200_000.0),
+                ),
+                // Not declared, as for some local models
+                ("claude-custom".to_string(), Model::default()),
+            ]),
+        );
+        let models = ProviderModels::from_response(&ConfigProviders200Response::new(
+            vec![anthropic],
+            HashMap::new(),
+        ));
+        assert_eq!(
+            models.context_limit("anthropic", "claude-sonnet-4-20250514"),
+            Some(200_000)
+        );
+        assert_eq!(models.context_limit("anthropic", "claude-custom"), None);
+        assert_eq!(
+            models.context_limit("openai", "claude-sonnet-4-20250514"),
+            None
+        );
+        assert_eq!(fixture().context_limit("openai", "gpt-4o"), None);
+    }
+}
@@ -0,0 +1,300 @@
+//! What the connected server supports
+//!
+//! Endpoints come and go between server releases, and calling one the server
+//! doesn't have fails deep inside a flow with a bare 404. The server's version
+//! is checked once it's known against the range this client was built and
+//! tested against, and optional features are gated on the release that added
+//! them, so their entry points can say what they need instead of failing.
+
+use crate::app::strings::{
+    FEATURE_REVERT, FEATURE_SHARE, FEATURE_SUMMARIZE, FEATURE_SYMBOL_SEARCH, REQUIRES_SERVER,
+};
+use std::cmp::Ordering;
+use std::fmt;
+
+/// Oldest server release the client is known to work with
+pub const MIN_SUPPORTED_VERSION: ServerVersion = ServerVersion::new(0, 3, 0);
+
+/// Newest server release the client was tested against
+pub const MAX_TESTED_VERSION: ServerVersion = ServerVersion::new(0, 4, 2);
+
+/// A semver-ish release number: `v` prefixes and `+build` metadata are
+/// ignored, a missing minor or patch counts as 0, and a `-pre` suffix sorts
+/// before the release it precedes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerVersion {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+    pub pre: Option<String>,
+}
+
+impl ServerVersion {
+    pub const fn new(major: u64, minor: u64, patch: u64) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+            pre: None,
+        }
+    }
+
+    /// Parse a version, or None for one without a numeric core, like "dev"
+    pub fn parse(version: &str) -> Option<Self> {
+        let version = version.trim();
+        let version = version.strip_prefix(['v', 'V']).unwrap_or(version);
+        let version = version.split('+').next().unwrap_or_default();
+        let (core, pre) = match version.split_once('-') {
+            Some((core, pre)) => (core, Some(pre.to_string()).filter(|pre| !pre.is_empty())),
+            None => (version, None),
+        };
+        let mut numbers = core.split('.').map(|number| number.parse::<u64>().ok());
+        let major = numbers.next()??;
+        let minor = numbers.next().unwrap_or(Some(0))?;
+        let patch = numbers.next().unwrap_or(Some(0))?;
+        if numbers.next().is_some() {
+            return None;
+        }
+        Some(Self {
+            major,
+            minor,
+            patch,
+            pre,
+        })
+    }
+}
+
+impl Ord for ServerVersion {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.major, self.minor, self.patch)
+            .cmp(&(other.major, other.minor, other.patch))
+            .then_with(|| match (&self.pre, &other.pre) {
+                (None, None) => Ordering::Equal,
+                (None, Some(_)) => Ordering::Greater,
+                (Some(_), None) => Ordering::Less,
+                (Some(ours), Some(theirs)) => compare_pre_release(ours, theirs),
+            })
+    }
+}
+
+impl PartialOrd for ServerVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for ServerVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)?;
+        match &self.pre {
+            Some(pre) => write!(f, "-{}", pre),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Compare pre-release suffixes by their dot-separated parts, numerically
+/// where both parts are numbers, so "beta.10" comes after "beta.2"
+fn compare_pre_release(ours: &str, theirs: &str) -> Ordering {
+    let mut ours = ours.split('.');
+    let mut theirs = theirs.split('.');
+    loop {
+        let ordering = match (ours.next(), theirs.next()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(a), Some(b)) => match (a.parse::<u64>(), b.parse::<u64>()) {
+                (Ok(a), Ok(b)) => a.cmp(&b),
+                // Numeric parts sort before named ones
+                (Ok(_), Err(_)) => Ordering::Less,
+                (Err(_), Ok(_)) => Ordering::Greater,
+                (Err(_), Err(_)) => a.cmp(b),
+            },
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+}
+
+/// Features only some servers have
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServerFeature {
+    Summarize,
+    Share,
+    Revert,
+    SymbolSearch,
+}
+
+impl ServerFeature {
+    pub const ALL: [ServerFeature; 4] = [
+        ServerFeature::Summarize,
+        ServerFeature::Share,
+        ServerFeature::Revert,
+        ServerFeature::SymbolSearch,
+    ];
+
+    /// First server release with the feature
+    pub fn since(self) -> ServerVersion {
+        match self {
+            ServerFeature::Share => ServerVersion::new(0, 3, 0),
+            ServerFeature::Summarize => ServerVersion::new(0, 3, 30),
+            ServerFeature::Revert => ServerVersion::new(0, 3, 100),
+            ServerFeature::SymbolSearch => ServerVersion::new(0, 4, 0),
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ServerFeature::Summarize => FEATURE_SUMMARIZE,
+            ServerFeature::Share => FEATURE_SHARE,
+            ServerFeature::Revert => FEATURE_REVERT,
+            ServerFeature::SymbolSearch => FEATURE_SYMBOL_SEARCH,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ServerCapabilities {
+    /// The server's version, None until it's known or when it can't be
+    /// parsed, as with development builds, which are given the benefit of the
+    /// doubt
+    pub version: Option<ServerVersion>,
+}
+
+impl ServerCapabilities {
+    pub fn from_version(version: &str) -> Self {
+        Self {
+            // Development builds report 0.0.0 with a suffix
+            version: ServerVersion::parse(version)
+                .filter(|version| *version >= ServerVersion::new(0, 0, 1)),
+        }
+    }
+
+    pub fn supports(&self, feature: ServerFeature) -> bool {
+        match &self.version {
+            Some(version) => *version >= feature.since(),
+            None => true,
+        }
+    }
+
+    /// What `feature` needs, e.g. "sharing requires server ≥ 0.3.0", when the
+    /// server doesn't have it
+    pub fn unsupported_notice(&self, feature: ServerFeature) -> Option<String> {
+        (!self.supports(feature)).then(|| {
+            REQUIRES_SERVER
+                .replace("{feature}", feature.label())
+                .replace("{version}", &feature.since().to_string())
+        })
+    }
+
+    /// Whether the server is older than any release the client supports
+    pub fn is_below_minimum(&self) -> bool {
+        self.version
+            .as_ref()
+            .is_some_and(|version| *version < MIN_SUPPORTED_VERSION)
+    }
+
+    /// Whether the server is newer than the client was tested against
+    pub fn is_untested(&self) -> bool {
+        self.version
+            .as_ref()
+            .is_some_and(|version| *version > MAX_TESTED_VERSION)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(text: &str) -> ServerVersion {
+        ServerVersion::parse(text).unwrap_or_else(|| panic!("{text} parses"))
+    }
+
+    #[test]
+    fn test_parse_versions_with_suffixes() {
+        assert_eq!(version("0.3.1"), ServerVersion::new(0, 3, 1));
+        assert_eq!(version("v0.3.1"), ServerVersion::new(0, 3, 1));
+        assert_eq!(version(" 0.3.1+linux.x64 "), ServerVersion::new(0, 3, 1));
+        assert_eq!(version("0.4"), ServerVersion::new(0, 4, 0));
+        assert_eq!(version("1"), ServerVersion::new(1, 0, 0));
+
+        let beta = version("0.4.0-beta.2+sha.5114f85");
+        assert_eq!(beta.pre.as_deref(), Some("beta.2"));
+        assert_eq!(beta.to_string(), "0.4.0-beta.2");
+
+        assert_eq!(ServerVersion::parse("dev"), None);
+        assert_eq!(ServerVersion::parse(""), None);
+        assert_eq!(ServerVersion::parse("0.x.1"), None);
+        assert_eq!(ServerVersion::parse("0.3.1.2"), None);
+    }
+
+    #[test]
+    fn test_version_ordering() {
+        assert!(version("0.3.9") < version("0.3.10"));
+        assert!(version("0.10.0") > version("0.9.9"));
+        assert_eq!(
+            version("v0.4").cmp(&version("0.4.0+build")),
+            Ordering::Equal
+        );
+        // Pre-releases come before their release, in numeric order
+        assert!(version("0.4.0-beta.2") < version("0.4.0"));
+        assert!(version("0.4.0-beta.2") > version("0.3.9"));
+        assert!(version("0.4.0-beta.2") < version("0.4.0-beta.10"));
+        assert!(version("0.4.0-alpha") < version("0.4.0-beta"));
+        assert!(version("0.4.0-beta") < version("0.4.0-beta.1"));
+        assert!(version("0.4.0-1") < version("0.4.0-rc"));
+    }
+
+    #[test]
+    fn test_gating_matrix() {
+        use ServerFeature::*;
+        let matrix = [
+            ("0.2.9", [false, false, false, false]),
+            ("0.3.0", [false, true, false, false]),
+            ("0.3.30-beta", [false, true, false, false]),
+            ("0.3.30", [true, true, false, false]),
+            ("0.3.100", [true, true, true, false]),
+            ("0.4.0-rc.1", [true, true, true, false]),
+            ("0.4.0", [true, true, true, true]),
+            ("1.0.0", [true, true, true, true]),
+            // Unparseable versions are assumed to have everything
+            ("dev", [true, true, true, true]),
+            ("0.0.0-dev-202508011200", [true, true, true, true]),
+        ];
+        for (server, expected) in matrix {
+            let capabilities = ServerCapabilities::from_version(server);
+            let supported = ServerFeature::ALL.map(|feature| capabilities.supports(feature));
+            assert_eq!(supported, expected, "server {server}");
+        }
+        assert_eq!(ServerFeature::ALL, [Summarize, Share, Revert, SymbolSearch]);
+
+        let old = ServerCapabilities::from_version("0.3.12");
+        assert_eq!(
+            old.unsupported_notice(Summarize).as_deref(),
+            Some("summarizing requires server ≥ 0.3.30")
+        );
+        assert_eq!(old.unsupported_notice(Share), None);
+        assert!(ServerCapabilities::default()
+            .unsupported_notice(SymbolSearch)
+            .is_none());
+    }
+
+    #[test]
+    fn test_supported_range() {
+        let range = |server| {
+            let capabilities = ServerCapabilities::from_version(server);
+            (capabilities.is_below_minimum(), capabilities.is_untested())
+        };
+        assert_eq!(range("0.2.9"), (true, false));
+        assert_eq!(range("0.3.0-rc.1"), (true, false));
+        assert_eq!(range("0.3.0"), (false, false));
+        assert_eq!(range("0.4.2"), (false, false));
+        assert_eq!(range("0.4.2+linux.x64"), (false, false));
+        assert_eq!(range("0.4.3-beta"), (false, true));
+        assert_eq!(range("0.4.3"), (false, true));
+        assert_eq!(range("dev"), (false, false));
+        assert_eq!(range("0.0.0-dev-202508011200"), (false, false));
+        assert!(!ServerCapabilities::default().is_below_minimum());
+    }
+}
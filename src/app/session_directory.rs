@@ -0,0 +1,116 @@
+//! Guard against opening a session from another project
+//!
+//! A session remembers the directory it was created in, and the assistant's
+//! tools run against the server's workspace. Opening a session from another
+//! project would silently point its history at the wrong files, so the
+//! session picker asks first, and the status bar names the session's
+//! directory while it differs from the workspace.
+
+use std::path::Path;
+
+/// Whether a session created in `session_dir` belongs to another project
+/// than the workspace at `workspace_root`. Sessions in a subdirectory of the
+/// workspace count as the same project, and an unknown side is never foreign.
+pub fn is_foreign_directory(session_dir: Option<&str>, workspace_root: Option<&str>) -> bool {
+    match (session_dir, workspace_root) {
+        (Some(session_dir), Some(workspace_root)) => {
+            !Path::new(session_dir).starts_with(Path::new(workspace_root))
+        }
+        _ => false,
+    }
+}
+
+/// Last component of a directory, for the status bar
+pub fn directory_name(directory: &str) -> String {
+    Path::new(directory)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| directory.to_string())
+}
+
+/// A directory with the home directory shortened to `~`
+pub fn display_directory(directory: &str, home: Option<&Path>) -> String {
+    match home.and_then(|home| Path::new(directory).strip_prefix(home).ok()) {
+        Some(rest) if rest.as_os_str().is_empty() => "~".to_string(),
+        Some(rest) => format!("~/{}", rest.display()),
+        None => directory.to_string(),
+    }
+}
+
+/// How a session's directory is shown beside it in the session list: not at
+/// all for the workspace root, relative below it, and from `~` elsewhere
+pub fn list_label(
+    directory: &str,
+    workspace_root: Option<&str>,
+    home: Option<&Path>,
+) -> Option<String> {
+    match Path::new(directory).strip_prefix(workspace_root?) {
+        Ok(rest) if rest.as_os_str().is_empty() => None,
+        Ok(rest) => Some(format!("{}/", rest.display())),
+        Err(_) => Some(display_directory(directory, home)),
+    }
+}
+
+/// Body of the confirmation modal for opening a session from `directory`
+pub fn confirm_text(directory: &str, home: Option<&Path>) -> String {
+    format!(
+        "\n    This session belongs to {} — open anyway?\n\n    y/enter  open it\n    n/esc    cancel\n",
+        display_directory(directory, home)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_foreign_directory() {
+        let root = Some("/home/me/project");
+        assert!(!is_foreign_directory(Some("/home/me/project"), root));
+        assert!(!is_foreign_directory(Some("/home/me/project/"), root));
+        assert!(!is_foreign_directory(Some("/home/me/project/src"), root));
+        assert!(is_foreign_directory(Some("/home/me/other"), root));
+        // A shared prefix isn't a subdirectory
+        assert!(is_foreign_directory(Some("/home/me/project-old"), root));
+        // Nothing to compare against
+        assert!(!is_foreign_directory(None, root));
+        assert!(!is_foreign_directory(Some("/home/me/other"), None));
+    }
+
+    #[test]
+    fn test_list_label() {
+        let home = Some(Path::new("/home/me"));
+        let root = Some("/home/me/project");
+        assert_eq!(list_label("/home/me/project", root, home), None);
+        assert_eq!(list_label("/home/me/project/", root, home), None);
+        assert_eq!(
+            list_label("/home/me/project/services/api", root, home).as_deref(),
+            Some("services/api/")
+        );
+        assert_eq!(
+            list_label("/home/me/other", root, home).as_deref(),
+            Some("~/other")
+        );
+        assert_eq!(list_label("/home/me/other", None, home), None);
+    }
+
+    #[test]
+    fn test_display_directory() {
+        let home = Some(Path::new("/home/me"));
+        assert_eq!(
+            display_directory("/home/me/other/project", home),
+            "~/other/project"
+        );
+        assert_eq!(display_directory("/home/me", home), "~");
+        assert_eq!(display_directory("/srv/project", home), "/srv/project");
+        assert_eq!(
+            display_directory("/home/meg/project", home),
+            "/home/meg/project"
+        );
+        assert_eq!(
+            display_directory("/home/me/project", None),
+            "/home/me/project"
+        );
+        assert_eq!(directory_name("/home/me/other/project"), "project");
+    }
+}
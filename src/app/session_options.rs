@@ -0,0 +1,133 @@
+//! Per-session system prompt and tool restrictions
+//!
+//! `/system <prompt>` sets a system prompt that is sent with every following
+//! message of the session, and a bare `/system` clears it. `/tools <name>`
+//! toggles a tool off and back on, such as `/tools bash` for a cautious
+//! session, and a bare `/tools` enables them all again. Options are kept per
+//! session, so switching sessions switches them too. A session started from
+//! a template also keeps the template's provider and model, see
+//! `session_templates`.
+
+use crate::sdk::ChatOptions;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// Slash command that sets or clears the system prompt
+pub const SYSTEM_COMMAND: &str = "/system";
+
+/// Slash command that toggles a tool
+pub const TOOLS_COMMAND: &str = "/tools";
+
+/// System prompt, disabled tools and model of one session
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SessionOptions {
+    pub system: Option<String>,
+    pub disabled_tools: BTreeSet<String>,
+    /// Provider and model sent with instead of the mode's
+    pub model: Option<(String, String)>,
+}
+
+impl SessionOptions {
+    /// Toggle a tool, returning whether it is enabled now
+    pub fn toggle_tool(&mut self, name: &str) -> bool {
+        if self.disabled_tools.remove(name) {
+            true
+        } else {
+            self.disabled_tools.insert(name.to_string());
+            false
+        }
+    }
+
+    /// Settings to send a message in `mode` with
+    pub fn chat_options(&self, mode: Option<String>) -> ChatOptions {
+        ChatOptions {
+            mode,
+            system: self.system.clone(),
+            tools: (!self.disabled_tools.is_empty()).then(|| {
+                self.disabled_tools
+                    .iter()
+                    .map(|name| (name.clone(), false))
+                    .collect()
+            }),
+        }
+    }
+
+    /// Status bar badges, such as "system -bash"
+    pub fn badges(&self) -> Vec<String> {
+        self.system
+            .as_ref()
+            .map(|_| "system".to_string())
+            .into_iter()
+            .chain(self.disabled_tools.iter().map(|name| format!("-{}", name)))
+            .collect()
+    }
+}
+
+/// A parsed `/system` or `/tools` command, with its argument
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OptionsCommand<'a> {
+    System(&'a str),
+    Tools(&'a str),
+}
+
+/// The options command in the input, if it is one
+pub fn parse_options_command(input: &str) -> Option<OptionsCommand<'_>> {
+    let input = input.trim();
+    if let Some(prompt) = command_argument(input, SYSTEM_COMMAND) {
+        return Some(OptionsCommand::System(prompt));
+    }
+    command_argument(input, TOOLS_COMMAND).map(OptionsCommand::Tools)
+}
+
+fn command_argument<'a>(input: &'a str, command: &str) -> Option<&'a str> {
+    let rest = input.strip_prefix(command)?;
+    if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+    Some(rest.trim())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_parse_options_command() {
+        assert_eq!(
+            parse_options_command("/system Answer in French\nBe brief"),
+            Some(OptionsCommand::System("Answer in French\nBe brief"))
+        );
+        assert_eq!(
+            parse_options_command("/system"),
+            Some(OptionsCommand::System(""))
+        );
+        assert_eq!(
+            parse_options_command(" /tools bash "),
+            Some(OptionsCommand::Tools("bash"))
+        );
+        assert_eq!(parse_options_command("/systemic"), None);
+        assert_eq!(parse_options_command("run /tools bash"), None);
+    }
+
+    #[test]
+    fn test_toggle_tool_and_chat_options() {
+        let mut options = SessionOptions::default();
+        assert_eq!(options.chat_options(None), ChatOptions::default());
+
+        assert!(!options.toggle_tool("bash"));
+        options.system = Some("Be careful".to_string());
+        assert_eq!(
+            options.chat_options(Some("plan".to_string())),
+            ChatOptions {
+                mode: Some("plan".to_string()),
+                system: Some("Be careful".to_string()),
+                tools: Some(HashMap::from([("bash".to_string(), false)])),
+            }
+        );
+        assert_eq!(options.badges(), vec!["system", "-bash"]);
+
+        assert!(options.toggle_tool("bash"));
+        assert_eq!(options.chat_options(None).tools, None);
+    }
+}
@@ -0,0 +1,158 @@
+//! Sessions started set up for a recurring workflow
+//!
+//! Templates come from the `[session_templates]` section of the config file.
+//! Each names the agent to work in, and optionally a model other than the
+//! agent's, a system prompt, and a first message to send as soon as the
+//! session is created. "Create New Session" in the session selector lists
+//! them under it, with "blank" for a session set up as before, and
+//! `/new <template>` starts one from the input.
+
+use crate::app::{
+    config_file::SessionTemplateConfig,
+    error::Result,
+    event_msg::{Cmd, CmdOrBatch},
+    strings,
+    tea_model::{AppModalState, Model, NotificationLevel, MODE_NAMES},
+    ui_components::modal_new_session::{create_pending_session, start_new_session},
+};
+use eyre::bail;
+
+/// Slash command that starts a new session, from a template when one is named
+pub const NEW_COMMAND: &str = "/new";
+
+/// The entry for a session without a template, so no template takes its name
+pub const BLANK_TEMPLATE: &str = "blank";
+
+/// A named way to start a session
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionTemplate {
+    pub name: String,
+    /// The mode to select, one of `MODE_NAMES`
+    pub agent: String,
+    /// Provider and model, over the ones the agent would use
+    pub model: Option<(String, String)>,
+    pub system: Option<String>,
+    /// Sent as soon as the session is created
+    pub message: Option<String>,
+}
+
+impl SessionTemplate {
+    pub fn new(name: &str, config: &SessionTemplateConfig) -> Result<Self> {
+        if name.is_empty() || name.chars().any(char::is_whitespace) {
+            bail!("Session template name {:?} must be a single word", name);
+        }
+        if name == BLANK_TEMPLATE {
+            bail!("Session template name {:?} is reserved", name);
+        }
+        if !MODE_NAMES.contains(&config.agent.as_str()) {
+            bail!(
+                "Session template {:?} has unknown agent {:?}, expected one of {}",
+                name,
+                config.agent,
+                MODE_NAMES.join(", ")
+            );
+        }
+        let model = match &config.model {
+            Some(model) => match model.split_once('/') {
+                Some((provider, model)) if !provider.is_empty() && !model.is_empty() => {
+                    Some((provider.to_string(), model.to_string()))
+                }
+                _ => bail!(
+                    "Session template {:?} has model {:?}, expected provider/model",
+                    name,
+                    model
+                ),
+            },
+            None => None,
+        };
+        let non_empty = |text: &Option<String>| text.clone().filter(|text| !text.trim().is_empty());
+        Ok(Self {
+            name: name.to_string(),
+            agent: config.agent.clone(),
+            model,
+            system: non_empty(&config.system),
+            message: non_empty(&config.message),
+        })
+    }
+}
+
+/// The template name of a `/new` command, empty if none was given
+pub fn parse_new_command(input: &str) -> Option<&str> {
+    let rest = input.trim().strip_prefix(NEW_COMMAND)?;
+    if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+    Some(rest.trim())
+}
+
+/// Start a new session set up by `template`, creating it with the template's
+/// first message when it has one
+pub fn start_template_session(model: &mut Model, template: &SessionTemplate) -> CmdOrBatch<Cmd> {
+    let Some(client) = model.client.clone() else {
+        model.push_notification(
+            NotificationLevel::Warning,
+            strings::NO_CLIENT_CONNECTION.to_string(),
+            false,
+        );
+        return CmdOrBatch::Single(Cmd::None);
+    };
+    start_new_session(model, None);
+    model.apply_session_template(template);
+    let Some(message) = template.message.clone() else {
+        return CmdOrBatch::Single(Cmd::None);
+    };
+    // Left pending, for the first message to be sent once a provider is set up
+    if model.needs_provider_setup() {
+        model.push_modal(AppModalState::ModalOnboarding);
+        model.push_notification(
+            NotificationLevel::Warning,
+            strings::NO_PROVIDER.to_string(),
+            false,
+        );
+        return CmdOrBatch::Single(Cmd::None);
+    }
+    CmdOrBatch::Single(create_pending_session(model, client, message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(agent: &str, model: Option<&str>) -> SessionTemplateConfig {
+        SessionTemplateConfig {
+            agent: agent.to_string(),
+            model: model.map(str::to_string),
+            system: Some("You review code".to_string()),
+            message: Some("  ".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_new_validates_the_template() {
+        let template =
+            SessionTemplate::new("review", &config("plan", Some("anthropic/claude-sonnet-4")))
+                .unwrap();
+        assert_eq!(template.agent, "plan");
+        assert_eq!(
+            template.model,
+            Some(("anthropic".to_string(), "claude-sonnet-4".to_string()))
+        );
+        assert_eq!(template.system.as_deref(), Some("You review code"));
+        // Nothing to send
+        assert_eq!(template.message, None);
+
+        assert!(SessionTemplate::new("review", &config("reviewer", None)).is_err());
+        assert!(SessionTemplate::new("review", &config("plan", Some("claude"))).is_err());
+        assert!(SessionTemplate::new("review", &config("plan", Some("anthropic/"))).is_err());
+        assert!(SessionTemplate::new("code review", &config("plan", None)).is_err());
+        assert!(SessionTemplate::new(BLANK_TEMPLATE, &config("plan", None)).is_err());
+    }
+
+    #[test]
+    fn test_parse_new_command() {
+        assert_eq!(parse_new_command("/new review"), Some("review"));
+        assert_eq!(parse_new_command(" /new "), Some(""));
+        assert_eq!(parse_new_command("/newer"), None);
+        assert_eq!(parse_new_command("make a /new one"), None);
+    }
+}
@@ -0,0 +1,146 @@
+//! Saved prompt templates
+//!
+//! Snippets come from the `[snippets]` section of the config file and are
+//! inserted into the input at the cursor. A `{}` in the body marks where the
+//! cursor lands, and `{selection}` expands to the attached files.
+
+use crate::{app::error::Result, sdk::AttachedFile};
+use eyre::bail;
+use serde::{Deserialize, Serialize};
+
+/// Where the cursor is placed after inserting a snippet
+pub const CURSOR_PLACEHOLDER: &str = "{}";
+
+/// Expands to the paths of the attached files
+pub const SELECTION_PLACEHOLDER: &str = "{selection}";
+
+/// Slash command that inserts a snippet by name
+pub const SNIPPET_COMMAND: &str = "/snippet";
+
+/// A named prompt template
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Snippet {
+    pub name: String,
+    pub body: String,
+}
+
+impl Snippet {
+    pub fn new(name: &str, body: &str) -> Result<Self> {
+        if name.is_empty() || name.chars().any(char::is_whitespace) {
+            bail!("Snippet name {:?} must be a single word", name);
+        }
+        if body.trim().is_empty() {
+            bail!("Snippet {:?} has an empty template", name);
+        }
+        if body.matches(CURSOR_PLACEHOLDER).count() > 1 {
+            bail!(
+                "Snippet {:?} has more than one {} cursor placeholder",
+                name,
+                CURSOR_PLACEHOLDER
+            );
+        }
+        Ok(Self {
+            name: name.to_string(),
+            body: body.to_string(),
+        })
+    }
+
+    /// The text before and after the cursor once the snippet is expanded.
+    /// Without a cursor placeholder the cursor goes at the end.
+    pub fn expand(&self, attached_files: &[AttachedFile]) -> (String, String) {
+        let selection = attached_files
+            .iter()
+            .map(|attached| attached.file.path.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+        // Split first so a `{}` inside a file path can't move the cursor
+        let (before, after) = self
+            .body
+            .split_once(CURSOR_PLACEHOLDER)
+            .unwrap_or((&self.body, ""));
+        (
+            before.replace(SELECTION_PLACEHOLDER, &selection),
+            after.replace(SELECTION_PLACEHOLDER, &selection),
+        )
+    }
+
+    /// First line of the template, for the picker
+    pub fn preview(&self) -> &str {
+        self.body.lines().next().unwrap_or_default()
+    }
+}
+
+/// Name given to `/snippet <name>`, if the input is that command
+pub fn parse_snippet_command(input: &str) -> Option<&str> {
+    let rest = input.trim().strip_prefix(SNIPPET_COMMAND)?;
+    if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+    Some(rest.trim())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opencode_sdk::models::File;
+
+    fn attached(path: &str) -> AttachedFile {
+        AttachedFile {
+            file: File {
+                path: path.to_string(),
+                ..Default::default()
+            },
+            part_id: "prt_test".to_string(),
+            display_name: path.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_snippet_validation() {
+        assert!(Snippet::new("review", "Review this diff: {}").is_ok());
+        assert!(Snippet::new("", "body").is_err());
+        assert!(Snippet::new("two words", "body").is_err());
+        assert!(Snippet::new("empty", "  \n").is_err());
+        assert!(Snippet::new("cursors", "{} and {}").is_err());
+    }
+
+    #[test]
+    fn test_expand_splits_at_cursor() {
+        let snippet = Snippet::new("review", "Review this diff and {}\nThanks").unwrap();
+        assert_eq!(
+            snippet.expand(&[]),
+            ("Review this diff and ".to_string(), "\nThanks".to_string())
+        );
+
+        let no_cursor = Snippet::new("eli5", "Explain like I'm new: ").unwrap();
+        assert_eq!(
+            no_cursor.expand(&[]),
+            ("Explain like I'm new: ".to_string(), String::new())
+        );
+    }
+
+    #[test]
+    fn test_expand_selection() {
+        let snippet = Snippet::new("files", "Look at {selection}: {}").unwrap();
+        let files = [attached("src/main.rs"), attached("weird/{}.rs")];
+        assert_eq!(
+            snippet.expand(&files),
+            (
+                "Look at src/main.rs, weird/{}.rs: ".to_string(),
+                String::new()
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_snippet_command() {
+        assert_eq!(parse_snippet_command("/snippet review"), Some("review"));
+        assert_eq!(
+            parse_snippet_command("  /snippet   review "),
+            Some("review")
+        );
+        assert_eq!(parse_snippet_command("/snippet"), Some(""));
+        assert_eq!(parse_snippet_command("/snippets"), None);
+        assert_eq!(parse_snippet_command("please /snippet review"), None);
+    }
+}
@@ -0,0 +1,171 @@
+//! User-facing text of the TUI
+//!
+//! Kept in one place so it can be translated later. Layout that depends on
+//! this text measures it with `text_width` instead of assuming a size, so a
+//! longer or wider translation still fits.
+
+// Overlays
+
+/// Listed in the help overlay after the keys, see `keybindings`
+pub const HELP_COMMANDS: &str = "
+    /snippet <name>  insert snippet
+    /system <prompt> set system prompt
+    /tools <name>    toggle a tool
+    /new <template>  new session from a template
+    /connect <url>   switch to the server at url
+    /pin <path>      pin a file beside the log
+    /bugreport       write a bug report bundle
+    /changelog       release notes
+    /loglevel        log levels per module
+    ";
+pub const HELP_TITLE: &str = "Help";
+pub const WHATS_NEW_TITLE: &str = "What's new in opencoders {version}";
+pub const CHANGELOG_TITLE: &str = "Changelog";
+pub const CHANGELOG_HINT: &str = "↑↓ scroll · esc close";
+pub const QUIT_CONFIRM_TEXT: &str = "
+    A response is still running.
+
+    a        quit and abort it
+    l        quit and leave it running
+    c/esc    cancel
+    ";
+pub const QUIT_CONFIRM_TITLE: &str = "Quit?";
+pub const REDACT_CONFIRM_TITLE: &str = "Send secrets?";
+pub const SESSION_DIRECTORY_CONFIRM_TITLE: &str = "Open session?";
+pub const WORKSPACE_MISMATCH_TEXT: &str = "
+    The server is working on
+        {root}
+    but this terminal is in
+        {directory}
+
+    c        connect anyway
+    p        pick another server
+    q        quit
+    ";
+pub const WORKSPACE_MISMATCH_TITLE: &str = "Another project";
+pub const NEW_SESSION_TITLE: &str = "New session in";
+pub const NEW_SESSION_HINT: &str = "tab complete · enter create · esc back";
+pub const NEW_SESSION_CHECKING: &str = "checking…";
+pub const NO_MATCHING_DIRECTORY: &str = "No directory matches";
+pub const NO_SUCH_DIRECTORY: &str = "no such directory";
+pub const NOT_A_DIRECTORY: &str = "not a directory";
+pub const DIRECTORY_LOOKUP_FAILED: &str = "Couldn't look up directories";
+pub const SAVE_OUTPUT_TITLE: &str = "Save tool output to";
+pub const SAVE_OUTPUT_HINT: &str = "tab metadata · enter save · esc back";
+pub const SAVE_OUTPUT_METADATA: &str = "with its metadata as JSON beside it";
+pub const SAVE_OUTPUT_SAVING: &str = "saving…";
+pub const SAVE_OUTPUT_NO_PATH: &str = "Enter a file to save to";
+pub const SAVE_OUTPUT_OVERWRITE: &str = "{path} exists · enter to overwrite it";
+pub const SAVE_OUTPUT_CREATE_DIRECTORY: &str = "{path} doesn't exist · enter to create it";
+pub const LINKS_TITLE: &str = "Links";
+pub const LINKS_CHECKING: &str = "looking up files…";
+pub const NO_LINKS: &str = "No links in the last reply";
+pub const ONBOARDING_TEXT: &str = "
+    No AI provider is configured yet, so
+    there is nothing to send messages to.
+
+    Log in to one in another terminal with
+        opencode auth login
+
+    Anthropic, OpenAI, GitHub Copilot,
+    Google, OpenRouter, Amazon Bedrock,
+    Groq and more are supported.
+
+    r        check again
+    esc      close
+    ";
+pub const ONBOARDING_TITLE: &str = "Welcome";
+pub const SERVER_OUTDATED_TEXT: &str = "
+    The server is opencode {running}, older
+    than {minimum}, the oldest release this
+    client supports, so some things may not
+    work.
+
+    Upgrade it in another terminal with
+        opencode upgrade
+    then restart the server.
+
+    enter    continue anyway
+    ";
+pub const SERVER_OUTDATED_TITLE: &str = "Server out of date";
+pub const SERVER_UNTESTED: &str = "Server {running} is newer than this client was tested with";
+pub const REQUIRES_SERVER: &str = "{feature} requires server ≥ {version}";
+pub const FEATURE_SUMMARIZE: &str = "summarizing";
+pub const FEATURE_SHARE: &str = "sharing";
+pub const FEATURE_REVERT: &str = "reverting";
+pub const FEATURE_SYMBOL_SEARCH: &str = "symbol search";
+pub const SYMBOL_SEARCH_TITLE: &str = "Jump to Symbol";
+pub const SYMBOL_SEARCH_FOOTER: &str = "Enter insert reference, ^y copy location, Esc cancel";
+pub const SYMBOL_SEARCH_PROMPT: &str = "Type to search the project's symbols";
+pub const NO_SYMBOLS_FOUND: &str =
+    "No symbols found, the server's language servers may not have indexed the project yet";
+
+// Message log
+
+pub const SESSION_ERROR_HINT: &str = "^x r retry last message";
+pub const SENDING_MARKER: &str = "  sending…";
+pub const NOT_SENT_MARKER: &str = "  not sent · ";
+pub const UNSEEN_MESSAGE: &str = "new message";
+pub const UNSEEN_MESSAGES: &str = "new messages";
+pub const JUMP_TO_BOTTOM_KEY: &str = "End";
+pub const LOG_TITLE: &str = "Message Log";
+pub const LIVE_STEP_TITLE: &str = "Current step";
+pub const LOG_SELECTING_TITLE: &str = "Message Log · v mark · y copy · esc cancel";
+pub const LOG_FIND_TYPING_HINT: &str = "enter jump · esc clear";
+pub const LOG_FIND_HINT: &str = "n next · N previous · / edit · esc clear";
+pub const LOG_FIND_MATCH: &str = "match";
+pub const LOG_FIND_MATCHES: &str = "matches";
+pub const LOG_FIND_NO_MATCHES: &str = "no matches";
+pub const PIN_HINT: &str = "alt+↑↓ scroll · ^x U unpin";
+
+// Diff review
+
+pub const NEW_FILE_MARKER: &str = "  new file";
+pub const BINARY_FILE_NO_DIFF: &str = "Binary file, no diff to show";
+pub const DIFF_TRUNCATED: &str = "more lines, view the full diff in a pager";
+
+// Input and status lines
+
+pub const INPUT_PLACEHOLDER: &str = "Type your message...";
+pub const FILES_CHECKED_HINT: &str = "checked · space toggle · enter attach";
+pub const UNTITLED_SESSION: &str = "new session";
+pub const MODE_UNKNOWN: &str = "UNKNOWN";
+pub const UPDATE_MARKER: &str = " ↑ update available";
+pub const MODEL_NOT_OFFERED: &str = " model not offered by provider";
+
+// Notifications
+
+pub const NO_IMAGE_TO_OPEN: &str = "No image to open";
+pub const NO_TOOL_OUTPUT: &str = "No tool output to pin";
+pub const NO_TOOL_ACTIVITY: &str = "No tool calls in this session yet";
+pub const NOTHING_PINNED: &str = "Nothing pinned";
+pub const NO_OUTPUT_TO_SAVE: &str = "No tool output to save";
+pub const OUTPUT_SAVED: &str = "Saved {bytes} bytes to {path}";
+pub const PIN_HIDDEN: &str = "Pinned, shown beside the log in fullscreen on a wide terminal";
+pub const WAIT_FOR_RESPONSE: &str = "Wait for the current response to finish";
+pub const NO_MESSAGE_TO_RETRY: &str = "No message to retry";
+pub const FAILED_SEND_HINT: &str = "press ctrl+enter to retry / ctrl+e to edit";
+pub const CLEAR_INPUT_TO_EDIT: &str = "Clear the input to edit the message that failed";
+pub const SESSION_CREATION_TIMED_OUT: &str =
+    "The server didn't create the session in time, press enter to try again";
+pub const SEND_QUEUED: &str = "Sending once connected to the server";
+pub const NO_CLIENT_CONNECTION: &str = "No client connection";
+pub const NO_PREVIOUS_SESSION: &str = "No previous session to go back to";
+pub const PREVIOUS_SESSION_DELETED: &str = "The previous session was deleted";
+pub const SESSION_TOGGLE_HINT: &str = "^x ` back to the previous session";
+pub const NO_SNIPPETS: &str = "No snippets yet, add them under [snippets] in the config file";
+pub const CONNECTION_LOST: &str = "Connection lost";
+pub const POLLING_FOR_MESSAGES: &str = "Live updates unavailable — polling";
+pub const EVENTS_MISSED: &str = "Fell behind the server's updates, reloading the session";
+pub const NO_PROVIDER: &str = "Add an AI provider before sending";
+pub const STILL_NO_PROVIDER: &str = "Still no provider configured";
+pub const PROVIDER_CONFIGURED: &str = "Provider configured, ready to send";
+pub const UNKNOWN_EVENTS: &str =
+    "The server sent updates this version doesn't know, try upgrading opencoders";
+pub const SESSION_SORT_NOT_SAVED: &str = "Failed to save the session sort to the config file";
+pub const DRAFT_NOT_SAVED: &str = "Failed to save draft";
+pub const SYSTEM_PROMPT_CLEARED: &str = "System prompt cleared";
+pub const SYSTEM_PROMPT_SET: &str = "System prompt set for this session";
+pub const ALL_TOOLS_ENABLED: &str = "All tools enabled";
+pub const WAIT_FOR_NEW_SESSION: &str =
+    "Wait for the new session to start before switching projects";
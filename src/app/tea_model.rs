@@ -1,20 +1,57 @@
 use crate::{
     app::{
+        alerts::{AlertLimiter, AlertPolicy},
+        bugreport::EventTap,
+        completion_notice::{notice_text, should_notify, DEFAULT_NOTIFY_AFTER_SECS},
+        draft_store::{Draft, NEW_SESSION_DRAFT_KEY},
+        event_async_task_manager::{TaskContext, TaskGenerations, TaskKind},
+        input_history::{InputHistory, DEFAULT_INPUT_HISTORY_SIZE},
+        links::{MessageLinks, UrlAction},
+        log_control::LogLevels,
+        message_polling::POLL_ACTIVE_MS,
         message_state::MessageState,
+        project_config::ResolvedSettings,
+        projects::{ProjectState, Projects},
+        provider_models::ProviderModels,
+        redaction::{AttachmentSecrets, RedactionPrompt, Redactor},
+        server_capabilities::ServerCapabilities,
+        session_directory::{is_foreign_directory, list_label},
+        session_options::SessionOptions,
+        session_templates::SessionTemplate,
+        snippets::Snippet,
+        token_estimate::{transcript_tokens, TokenEstimate, TokenEstimator},
         ui_components::{
-            message_part::VerbosityLevel, FileSelector, MessageLog, SessionSelector, TextInputArea,
+            glyphs::GlyphMode,
+            inline_height::InlineHeight,
+            message_part::{VerbosityLevel, DEFAULT_PROMPT_COLLAPSE_LINES},
+            modal_new_session::NewSessionPrompt,
+            modal_project_selector::project_rows,
+            modal_session_selector::SessionSort,
+            text_width::DEFAULT_TAB_WIDTH,
+            tool_render::ToolRenderPolicies,
+            ActivityLog, ChangelogView, FileSelector, MessageLog, MetricsView, PinnedPane,
+            ProjectSelector, SaveOutputPrompt, ScrollPosition, SelectableData, ServerSelector,
+            SessionSelector, SnippetSelector, SymbolSelector, TextInputArea,
         },
+        working_time::WorkingTime,
+        workspace_guard::WorkspaceMismatch,
     },
     sdk::{
-        client::{generate_id, IdPrefix},
-        extensions::events::EventStreamHandle,
-        OpenCodeClient,
+        client::DEFAULT_MAX_TEXT_PART_BYTES,
+        extensions::{events::EventStreamHandle, git::GitInfo},
+        id::{generate_id, IdPrefix},
+        AttachedFile, ChatOptions, OpenCodeClient,
     },
 };
-use opencode_sdk::models::{AgentConfig, ConfigAgent, File, Session};
-use std::{fmt::Display, time::SystemTime};
+use opencode_sdk::models::{AgentConfig, ConfigAgent, Event, File, Session};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fmt::Display,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, SystemTimeError, UNIX_EPOCH},
+};
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 pub enum RepeatShortcutKey {
     CtrlC,
     CtrlD,
@@ -22,10 +59,54 @@ pub enum RepeatShortcutKey {
     Leader,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 pub enum TimeoutType {
     RepeatShortcut(RepeatShortcutKey),
-    DebounceFindFiles(String), // query string
+    DebounceFindFiles(String),   // query string
+    DebounceFindSymbols(String), // query string
+    DebounceSaveDraft,
+    DebounceRefreshFileStatus,
+    ExpireNotifications,
+    AlertFlash,
+    DebounceEstimateTokens,
+    PollMessages, // next fetch of the session's messages while the event stream is down
+    SessionToggleHint, // how long the status bar shows the way back after a switch
+    RotateHints,  // next turn of the status bar's shortcut hints
+    SessionCreation, // gives up on a new session the server hasn't created
+}
+
+/// Source of time for timeouts, so tests can step time deterministically
+#[derive(Debug, Clone, PartialEq)]
+pub enum Clock {
+    System,
+    Manual(SystemTime),
+}
+
+impl Clock {
+    pub fn now(&self) -> SystemTime {
+        match self {
+            Clock::System => SystemTime::now(),
+            Clock::Manual(now) => *now,
+        }
+    }
+
+    /// Milliseconds since the epoch, like the server's timestamps
+    pub fn now_ms(&self) -> f64 {
+        self.now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as f64)
+            .unwrap_or(0.0)
+    }
+
+    pub fn elapsed_since(&self, earlier: SystemTime) -> Result<Duration, SystemTimeError> {
+        self.now().duration_since(earlier)
+    }
+
+    pub fn advance(&mut self, duration: Duration) {
+        if let Clock::Manual(now) = self {
+            *now += duration;
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -41,19 +122,56 @@ pub struct RepeatShortcutTimeout {
     pub started_at: SystemTime,
 }
 
+/// How a session was being viewed when switching away from it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SessionView {
+    pub scroll: ScrollPosition,
+    pub verbosity: VerbosityLevel,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct PendingSessionInfo {
     pub temp_id: String,
     pub created_at: SystemTime,
+    /// Directory to create the session in, rather than the workspace
+    pub directory: Option<String>,
+}
+
+/// A message as it was sent, kept until the server accepts it so a failed
+/// send can be retried or put back into the input
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutgoingMessage {
+    pub message_id: String,
+    pub session_id: String,
+    pub text: String,
+    pub attached_files: Vec<AttachedFile>,
+    pub provider_id: String,
+    pub model_id: String,
+    pub options: ChatOptions,
 }
 
 #[derive(Debug, Clone, PartialEq)]
-pub struct AttachedFile {
-    pub file: File,           // From opencode_sdk::models::File
-    pub part_id: String,      // Generated ID for the file part
-    pub display_name: String, // For UI display (filename only)
+pub enum NotificationLevel {
+    Info,
+    Warning,
+    Error,
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub struct Notification {
+    pub level: NotificationLevel,
+    pub text: String,
+    // Persistent notifications stay until dismissed, others expire on a timeout
+    pub persistent: bool,
+    pub created_at: SystemTime,
+}
+
+pub const NOTIFICATION_TIMEOUT_MS: u64 = 5000;
+// Quiet period after file events before git status is refreshed
+pub const FILE_STATUS_REFRESH_DEBOUNCE_MS: u64 = 500;
+// Message events held while no session is set, replayed once one is
+pub const EARLY_EVENT_BUFFER_LIMIT: usize = 64;
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum SessionState {
     None,
@@ -62,6 +180,17 @@ pub enum SessionState {
     Ready(Session),
 }
 
+/// Loading the modes, which waits until they're first needed, by a send or
+/// by cycling modes, so that starting up doesn't wait on them
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum ModesLoad {
+    #[default]
+    NotStarted,
+    Loading,
+    /// Loaded, or failed and sends fall back to the default model
+    Finished,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum EventStreamState {
     Disconnected,
@@ -71,15 +200,54 @@ pub enum EventStreamState {
     Failed(String),
 }
 
+/// Areas of the screen changed since the last draw. The draw itself is
+/// always the full frame, but nothing is drawn while no area is dirty.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DirtyRegions {
+    pub message_log: bool,
+    pub input: bool,
+    pub status: bool,
+    pub modal: bool,
+}
+
+impl DirtyRegions {
+    pub const NONE: Self = Self {
+        message_log: false,
+        input: false,
+        status: false,
+        modal: false,
+    };
+    pub const ALL: Self = Self {
+        message_log: true,
+        input: true,
+        status: true,
+        modal: true,
+    };
+
+    pub fn any(&self) -> bool {
+        self.message_log || self.input || self.status || self.modal
+    }
+
+    /// Add the areas dirty in `other`
+    pub fn mark(&mut self, other: DirtyRegions) {
+        self.message_log |= other.message_log;
+        self.input |= other.input;
+        self.status |= other.status;
+        self.modal |= other.modal;
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Model {
     pub init: ModelInit,
     pub config: UserConfig,
+    pub clock: Clock,
     // App state
     pub state: AppModalState,
-    pub input_history: Vec<String>,
+    /// States the open modals were opened over, innermost last
+    pub modal_stack: Vec<AppModalState>,
+    pub input_history: InputHistory,
     pub last_input: Option<String>,
-    pub printed_to_stdout_count: usize,
     pub sdk_mode: String,
     pub sdk_provider: String,
     pub sdk_model: String,
@@ -88,30 +256,125 @@ pub struct Model {
     // Stateful components:
     pub message_log: MessageLog,
     pub text_input_area: TextInputArea, // New tui-textarea based input
+    // How far the inline viewport grows with the input, see `inline_height`
+    pub inline_height: InlineHeight,
     pub modal_session_selector: SessionSelector,
+    pub new_session_prompt: NewSessionPrompt,
+    pub save_output_prompt: SaveOutputPrompt,
     pub modal_file_selector: FileSelector,
-    // Client and session state
+    pub modal_metrics: MetricsView,
+    pub modal_activity_log: ActivityLog,
+    pub modal_snippet_selector: SnippetSelector,
+    pub modal_symbol_selector: SymbolSelector,
+    pub modal_server_selector: ServerSelector,
+    pub modal_project_selector: ProjectSelector,
+    // Links of the reply the links picker shows
+    pub modal_links: Option<MessageLinks>,
+    // Tool output or file kept beside the log in fullscreen
+    pub pinned_pane: Option<PinnedPane>,
+    // Client and session state, of the active project
     pub client: Option<OpenCodeClient>,
     pub session_state: SessionState,
     pub sessions: Vec<Session>,
     pub modes: Option<ConfigAgent>,
+    pub modes_load: ModesLoad,
+    // Models each provider serves, once loaded, to check the selected pair
+    pub provider_models: Option<ProviderModels>,
     pub mode_state: Option<u16>,
     pub connection_status: ConnectionStatus,
     pub pending_first_message: Option<String>,
+    /// Whether the input was submitted before the server and the modes were
+    /// ready, to be sent once they are
+    pub send_queued: bool,
+    /// The send waiting on the server's reply
+    pub outgoing: Option<OutgoingMessage>,
+    /// The last send the server rejected, until it's retried, edited or a new
+    /// message is submitted
+    pub failed_send: Option<OutgoingMessage>,
     // Message state and event streaming
     pub message_state: MessageState,
+    pub early_events: VecDeque<Event>,
+    // The latest events, for bug reports
+    pub event_tap: EventTap,
     pub event_stream_state: EventStreamState,
+    // Delay of the message poll standing in for the event stream, while it's down
+    pub message_poll_ms: Option<u64>,
     pub active_task_count: usize,
+    pub active_task_kinds: Vec<TaskKind>,
+    pub task_generations: TaskGenerations,
     // Session state for UI indicators
     pub session_is_idle: bool,
-    // File picker state
+    // When the running response was sent, for the completion notification
+    pub response_started_at: Option<SystemTime>,
+    // How long the agent worked in each session, see `working_time`
+    pub working_time: WorkingTime,
+    // Whether the terminal window has focus, as last reported by the terminal
+    pub terminal_focused: bool,
+    // Whether the terminal was resized below the smallest layout, so only the
+    // placeholder is drawn and printing into the scrollback waits for it to
+    // grow back
+    pub terminal_too_small: bool,
+    // Notifications and server version tracking
+    pub notifications: Vec<Notification>,
+    pub server_version: Option<String>,
+    // Optional features the server's version has, kept with the version
+    pub server_capabilities: ServerCapabilities,
+    pub installed_version: Option<String>,
+    // Whether skipped events of an unknown type have been pointed out yet
+    pub unknown_events_reported: bool,
+    // File picker state, also the source of the dirty file count
     pub file_status: Vec<File>,
+    pub git_info: Option<GitInfo>,
     // File attachment state
     pub attached_files: Vec<AttachedFile>,
+    // Workspace root reported by the server, for relative `@` paths
+    pub workspace_root: Option<String>,
+    // Directory each listed session was created in, by session id
+    pub session_directories: HashMap<String, String>,
+    // Session picked from another project, waiting for the open confirmation
+    pub pending_session_switch: Option<usize>,
+    // Directory the TUI was started in, checked against the server's
+    // workspace when known
+    pub launch_directory: Option<PathBuf>,
+    // The server's workspace doesn't hold the launch directory, waiting for
+    // the user to go on or not
+    pub workspace_mismatch: Option<WorkspaceMismatch>,
+    // Workspaces connected to anyway, which aren't asked about again
+    pub accepted_workspace_roots: HashSet<String>,
+    // Session open before the current one, which ctrl+x ` goes back to
+    pub previous_session_id: Option<String>,
+    // Turns taken by the last of the status bar's shortcut hints
+    pub hint_rotation: usize,
+    // Input whose `@` mentions were checked, or sent anyway despite missing files
+    pub mentions_checked: Option<String>,
+    // Input checked for secrets, or confirmed to be sent as it is
+    pub secrets_checked: Option<String>,
+    // Secrets found in the attached files, and the input they were scanned for
+    pub attachment_secrets: Option<(String, AttachmentSecrets)>,
+    // Message held back for its secrets, waiting on the redaction confirmation
+    pub redaction_prompt: Option<RedactionPrompt>,
+    // Changelog being shown, or what's new waiting to be
+    pub changelog: Option<ChangelogView>,
+    // Input warned about for its estimated tokens, so submitting it again sends it
+    pub token_limit_warned: Option<String>,
+    // Estimated tokens of the draft as sent, updated once typing pauses
+    pub token_estimate: Option<TokenEstimate>,
+    // Size in bytes of each attached file, by path, once measured for the estimate
+    pub attachment_sizes: HashMap<String, usize>,
+    // System prompt and disabled tools, by draft key so a new session has its own
+    pub session_options: HashMap<String, SessionOptions>,
+    // Scroll position and verbosity of sessions switched away from, by session id
+    pub session_views: HashMap<String, SessionView>,
     // Unified repeat shortcut timeout system
     pub repeat_shortcut_timeout: Option<RepeatShortcutTimeout>,
     // General timeout system for debouncing and other purposes
     pub active_timeouts: Vec<Timeout>,
+    // What needs drawing again, set by update and cleared by each draw
+    pub dirty: DirtyRegions,
+    // Connected projects other than the active one
+    pub projects: Projects,
+    // When each class of alert last rang or flashed
+    pub alert_limiter: AlertLimiter,
 }
 
 mod model_init {
@@ -140,19 +403,69 @@ pub struct UserConfig {
     pub ui_block_is_rounded: bool,
     pub ui_status_is_bottom: bool,
     pub ui_status_use_labels: bool,
+    pub ui_show_banner: bool,
     pub height: u16,
     pub keys_shortcut_timeout_ms: u16,
+    pub session_resume: bool,
+    pub session_resume_max_age_secs: u64,
+    pub session_sort: SessionSort,
+    pub snippets: Vec<Snippet>,
+    pub notify_on_complete: bool,
+    pub notify_after_secs: u64,
+    pub ui_stream_reveal: bool,
+    /// Resolved from `Auto` at startup, see `glyphs`
+    pub ui_glyphs: GlyphMode,
+    /// Rows the inline viewport grows to as the input does, half the
+    /// terminal when unset
+    pub ui_max_height: Option<u16>,
+    /// Columns between tab stops in messages
+    pub ui_tab_width: usize,
+    /// Prompts longer than this many lines are folded in the log
+    pub ui_prompt_collapse_lines: usize,
+    pub link_url_action: UrlAction,
+    pub tool_render: ToolRenderPolicies,
+    pub alerts: AlertPolicy,
+    pub input_history_size: usize,
+    /// Messages kept in memory before the oldest printed ones are dropped,
+    /// unlimited when unset
+    pub retained_messages: Option<usize>,
+    /// Patterns of secrets held back from sending, see `redaction`
+    pub redactor: Redactor,
+    /// Log levels per module on top of the base filter, see `log_control`
+    pub log_levels: LogLevels,
+    /// Named ways to start a session, see `session_templates`
+    pub session_templates: Vec<SessionTemplate>,
+    /// Server, provider, model and agent, and where each was set
+    pub settings: ResolvedSettings,
 }
 
 pub use model_init::ModelInit;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 pub enum AppModalState {
     None,
     Connecting(ConnectionStatus),
     ModalHelp,
     ModalFileSelect,
     ModalSessionSelect,
+    ModalQuitConfirm,
+    ModalSessionDirectoryConfirm,
+    ModalWorkspaceMismatch, // the server works on another project than the terminal is in
+    ModalRedactConfirm,     // secrets found in a message about to be sent
+    ModalNewSession,        // where to root a new session
+    ModalSaveOutput,        // where to save a tool's output
+    ModalMetrics,
+    ModalActivityLog, // every tool call of the session
+    ModalSnippetSelect,
+    ModalSymbolSelect, // searching the project's symbols
+    ModalServerSelect,
+    ModalProjectSelect,
+    LogSelect, // moving a cursor over the message log to copy from it
+    LogFind,   // searching the message log
+    ModalLinks,
+    ModalOnboarding,     // no provider configured, so nothing can be sent
+    ModalChangelog,      // what's new since the version last run, or every release
+    ModalServerOutdated, // server older than the oldest supported release
     // SelectModel,
     // SelectAgent,
     // SelectFile,
@@ -160,7 +473,7 @@ pub enum AppModalState {
     Quit,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 pub enum ConnectionStatus {
     Disconnected,
     Connecting,
@@ -183,12 +496,23 @@ impl Display for ConnectionStatus {
                 ConnectionStatus::SessionReady => "✓ Session ready!",
                 ConnectionStatus::Error(ref _error) => "Connection failed! Press 'r' to retry",
             }
-        );
-        Ok(())
+        )
     }
 }
 
 pub const INLINE_HEIGHT: u16 = 12;
+/// Modals open over each other past this depth forget the oldest state
+pub const MODAL_STACK_LIMIT: usize = 8;
+pub const SESSION_RESUME_MAX_AGE_SECS: u64 = 24 * 60 * 60;
+/// Modes by index, as cycled through with tab
+pub const MODE_NAMES: [&str; 3] = ["build", "plan", "general"];
+/// How long the way back to the previous session shows after a switch
+pub const SESSION_TOGGLE_HINT_MS: u64 = 4000;
+/// How long each of the lower ranked shortcut hints shows
+pub const HINT_ROTATION_MS: u64 = 4000;
+/// How long a new session may take to be created before its first message
+/// goes back into the input
+pub const SESSION_CREATION_TIMEOUT_MS: u64 = 15_000;
 
 impl Model {
     pub fn new() -> Self {
@@ -205,42 +529,118 @@ impl Model {
                 ui_block_is_rounded: true,
                 ui_status_is_bottom: true,
                 ui_status_use_labels: true,
+                ui_show_banner: true,
                 height: INLINE_HEIGHT,
                 keys_shortcut_timeout_ms: 1000,
+                session_resume: true,
+                session_resume_max_age_secs: SESSION_RESUME_MAX_AGE_SECS,
+                session_sort: SessionSort::default(),
+                snippets: Vec::new(),
+                notify_on_complete: true,
+                notify_after_secs: DEFAULT_NOTIFY_AFTER_SECS,
+                ui_stream_reveal: false,
+                ui_glyphs: GlyphMode::Auto,
+                ui_max_height: None,
+                ui_tab_width: DEFAULT_TAB_WIDTH,
+                ui_prompt_collapse_lines: DEFAULT_PROMPT_COLLAPSE_LINES,
+                link_url_action: UrlAction::default(),
+                tool_render: ToolRenderPolicies::default(),
+                alerts: AlertPolicy::default(),
+                input_history_size: DEFAULT_INPUT_HISTORY_SIZE,
+                retained_messages: None,
+                redactor: Redactor::defaults(),
+                log_levels: LogLevels::default(),
+                session_templates: Vec::new(),
+                settings: ResolvedSettings::default(),
             },
+            clock: Clock::System,
             state: AppModalState::Connecting(ConnectionStatus::Connecting),
-            input_history: Vec::new(),
+            modal_stack: Vec::new(),
+            input_history: InputHistory::default(),
             last_input: None,
-            printed_to_stdout_count: 0,
             sdk_mode: "chat".to_string(),
             sdk_provider: "anthropic".to_string(),
             sdk_model: "claude-sonnet-4-20250514".to_string(),
             verbosity_level: VerbosityLevel::Summary,
             message_log,
             text_input_area,
+            inline_height: InlineHeight::default(),
             modal_session_selector,
+            new_session_prompt: NewSessionPrompt::default(),
+            save_output_prompt: SaveOutputPrompt::default(),
             modal_file_selector,
+            modal_metrics: MetricsView::new(),
+            modal_activity_log: ActivityLog::new(),
+            modal_snippet_selector: SnippetSelector::new(),
+            modal_symbol_selector: SymbolSelector::new(),
+            modal_server_selector: ServerSelector::new(),
+            modal_project_selector: ProjectSelector::new(),
+            modal_links: None,
+            pinned_pane: None,
             client: None,
             session_state: SessionState::None,
             sessions: Vec::new(),
             modes: None,
+            modes_load: ModesLoad::NotStarted,
+            provider_models: None,
             mode_state: None,
             connection_status: ConnectionStatus::Connecting,
             pending_first_message: None,
+            send_queued: false,
+            outgoing: None,
+            failed_send: None,
             message_state: MessageState::new(),
+            early_events: VecDeque::new(),
+            event_tap: EventTap::default(),
             event_stream_state: EventStreamState::Disconnected,
+            message_poll_ms: None,
             active_task_count: 0,
+            active_task_kinds: Vec::new(),
+            task_generations: TaskGenerations::default(),
             session_is_idle: true,
+            response_started_at: None,
+            working_time: WorkingTime::default(),
+            terminal_focused: true,
+            terminal_too_small: false,
+            notifications: Vec::new(),
+            server_version: None,
+            server_capabilities: ServerCapabilities::default(),
+            installed_version: None,
+            unknown_events_reported: false,
             file_status: Vec::new(),
+            git_info: None,
             attached_files: Vec::new(),
+            workspace_root: None,
+            session_directories: HashMap::new(),
+            pending_session_switch: None,
+            launch_directory: None,
+            workspace_mismatch: None,
+            accepted_workspace_roots: HashSet::new(),
+            previous_session_id: None,
+            hint_rotation: 0,
+            mentions_checked: None,
+            secrets_checked: None,
+            attachment_secrets: None,
+            redaction_prompt: None,
+            changelog: None,
+            token_limit_warned: None,
+            token_estimate: None,
+            attachment_sizes: HashMap::new(),
+            session_options: HashMap::new(),
+            session_views: HashMap::new(),
             repeat_shortcut_timeout: None,
             active_timeouts: Vec::new(),
+            dirty: DirtyRegions::ALL,
+            projects: Projects::default(),
+            alert_limiter: AlertLimiter::default(),
         }
     }
 
     // Message outputs
     pub fn needs_manual_output(&self) -> bool {
-        return self.init.inline_mode() & self.message_state.has_messages_needing_stdout_print();
+        return self.init.inline_mode()
+            & !self.terminal_too_small
+            & self.message_state.has_messages_needing_stdout_print();
     }
 
     pub fn messages_needing_stdout_print(&self) -> Vec<String> {
@@ -256,8 +656,242 @@ impl Model {
 
     pub fn mark_messages_printed_to_stdout(&mut self, count: usize) {
         self.message_state.mark_messages_printed_to_stdout(count);
-        // Keep the old counter for backward compatibility with input_history
-        self.printed_to_stdout_count += count;
+        self.drop_old_messages();
+    }
+
+    /// Keep the stdout bookkeeping consistent across an inline/fullscreen switch.
+    /// Fullscreen never prints to stdout, so anything that arrives there stays
+    /// unprinted and is flushed into the scrollback once back inline. Entering
+    /// fullscreen treats everything as printed, since the inline scrollback
+    /// already holds it.
+    pub fn prepare_inline_change(&mut self, new_inline: bool) {
+        if self.init.inline_mode() && !new_inline {
+            self.message_state.mark_all_messages_printed_to_stdout();
+        }
+    }
+
+    /// Whether the server is still working on something the user sent, so
+    /// quitting should ask before leaving it running
+    pub fn has_work_in_flight(&self) -> bool {
+        !self.session_is_idle
+            || self.pending_first_message.is_some()
+            || self.message_state.get_streaming_message_count() > 0
+    }
+
+    /// The user sent something, so the session is busy until the server
+    /// reports it idle
+    pub fn start_response(&mut self) {
+        if self.session_is_idle || self.response_started_at.is_none() {
+            self.response_started_at = Some(self.clock.now());
+        }
+        self.working_time.start(self.clock.now());
+        self.session_is_idle = false;
+    }
+
+    /// Show a message the user sent in the log right away, until the server
+    /// echoes it
+    pub fn show_provisional_message(&mut self, message_id: &str, text: &str) {
+        if self
+            .message_state
+            .add_provisional(message_id, text)
+            .is_applied()
+        {
+            self.mirror_message(message_id);
+            // Sending shows the bottom even when reading further up
+            self.message_log.touch_scroll();
+        }
+    }
+
+    /// Turn a provisional message red after its send request failed
+    pub fn mark_message_send_failed(&mut self, message_id: &str) {
+        if self.message_state.mark_send_failed(message_id) {
+            self.mirror_message(message_id);
+        }
+    }
+
+    /// Remove the messages that failed to send, before one is retried
+    pub fn drop_failed_messages(&mut self) {
+        for message_id in self.message_state.remove_failed_provisional() {
+            self.message_log.remove_message(&message_id);
+        }
+    }
+
+    /// Drop the oldest messages past the retention limit, leaving a marker
+    /// to load them again. Nothing is dropped while the user reads further
+    /// up the fullscreen log.
+    pub fn drop_old_messages(&mut self) {
+        if !self.init.inline_mode() && !self.message_log.is_following() {
+            return;
+        }
+        let dropped = self.message_state.drop_beyond_retention();
+        if !dropped.is_empty() {
+            self.message_log
+                .drop_earliest(&dropped, self.message_state.dropped_count());
+        }
+    }
+
+    /// The session went idle, so none of its messages are streaming any more
+    pub fn finish_streaming_messages(&mut self) {
+        for message_id in self.message_state.finish_streaming() {
+            self.mirror_message(&message_id);
+        }
+        self.drop_old_messages();
+    }
+
+    /// Merge a polled page of the session's latest messages into the log,
+    /// returning whether anything changed
+    pub fn merge_polled_messages(
+        &mut self,
+        messages: Vec<opencode_sdk::models::SessionMessages200ResponseInner>,
+    ) -> bool {
+        let changed = self.message_state.merge_messages(messages);
+        for message_id in &changed {
+            self.mirror_message(message_id);
+        }
+        !changed.is_empty()
+    }
+
+    /// Poll for messages in place of the event stream, which has failed
+    pub fn start_message_polling(&mut self) {
+        self.message_poll_ms = Some(POLL_ACTIVE_MS);
+        self.set_timeout(TimeoutType::PollMessages, POLL_ACTIVE_MS);
+    }
+
+    /// Stop polling, as the event stream is back or the project is parked
+    pub fn stop_message_polling(&mut self) {
+        self.message_poll_ms = None;
+        self.clear_timeout(&TimeoutType::PollMessages);
+    }
+
+    fn mirror_message(&mut self, message_id: &str) {
+        if let Some(container) = self.message_state.get_message_container(message_id) {
+            self.message_log.apply_update(message_id, container);
+        }
+    }
+
+    /// Notification text for the response that just completed, if it ran long
+    /// enough and the terminal isn't focused
+    pub fn take_completion_notice(&mut self) -> Option<String> {
+        let started_at = self.response_started_at.take()?;
+        let elapsed = self.clock.elapsed_since(started_at).ok()?;
+        let threshold = Duration::from_secs(self.config.notify_after_secs);
+        if !should_notify(
+            self.config.notify_on_complete,
+            threshold,
+            self.terminal_focused,
+            elapsed,
+        ) {
+            return None;
+        }
+        let title = self.session().map(|session| session.title.as_str());
+        Some(notice_text(title.unwrap_or("new session")))
+    }
+
+    /// Time the agent worked in the current session, counting a running
+    /// span, or None before it has worked at all
+    pub fn session_working_time(&self) -> Option<Duration> {
+        let finished = self
+            .session()
+            .map(|session| self.working_time.total(&session.id))
+            .unwrap_or_default();
+        let total = finished + self.working_time.running(self.clock.now());
+        (!total.is_zero()).then_some(total)
+    }
+
+    /// Time the agent worked in each session it has, by id
+    pub fn session_working_times(&self) -> HashMap<String, Duration> {
+        let mut times = self.working_time.totals().clone();
+        if let Some(session) = self.session() {
+            *times.entry(session.id.clone()).or_default() +=
+                self.working_time.running(self.clock.now());
+        }
+        times
+    }
+
+    pub fn dirty_file_count(&self) -> usize {
+        self.file_status.len()
+    }
+
+    // Drafts
+    /// Key the current draft is stored under: the session id, or a shared
+    /// key while the session hasn't been created yet
+    pub fn draft_key(&self) -> String {
+        match self.session() {
+            Some(session) => session.id.clone(),
+            None => NEW_SESSION_DRAFT_KEY.to_string(),
+        }
+    }
+
+    /// Options of the current session, or of the new session being drafted
+    pub fn session_options(&self) -> SessionOptions {
+        self.session_options
+            .get(&self.draft_key())
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub fn session_options_mut(&mut self) -> &mut SessionOptions {
+        let key = self.draft_key();
+        self.session_options.entry(key).or_default()
+    }
+
+    /// Let the inline viewport grow as far as a terminal `rows` tall allows
+    pub fn fit_inline_height_to_terminal(&mut self, rows: u16) {
+        let base = self.inline_height.base;
+        self.inline_height = InlineHeight::new(base, self.config.ui_max_height, rows);
+        self.text_input_area
+            .set_max_height(self.inline_height.max_input_height());
+    }
+
+    /// Inline viewport rows the input needs at its current height
+    pub fn inline_height_for_input(&self) -> u16 {
+        self.inline_height
+            .for_input(self.text_input_area.current_height())
+    }
+
+    pub fn current_draft(&self) -> Draft {
+        Draft {
+            text: self.text_input_area.content(),
+            attached_files: self.attached_files.clone(),
+        }
+    }
+
+    /// Attach a file to the next message, unless it's already attached
+    pub fn attach_file(&mut self, file: File) {
+        if self
+            .attached_files
+            .iter()
+            .any(|attached| attached.file.path == file.path)
+        {
+            return;
+        }
+        self.attached_files.push(AttachedFile {
+            display_name: file
+                .path
+                .rsplit('/')
+                .next()
+                .unwrap_or(&file.path)
+                .to_string(),
+            part_id: generate_id(IdPrefix::Part),
+            file,
+        });
+    }
+
+    /// Insert a snippet at the cursor, expanding it against the attached files
+    pub fn insert_snippet(&mut self, snippet: &Snippet) {
+        let (before, after) = snippet.expand(&self.attached_files);
+        self.text_input_area.insert_around_cursor(&before, &after);
+    }
+
+    /// Restore a saved draft, unless the user has already started typing
+    pub fn restore_draft(&mut self, draft: Draft) -> bool {
+        if !self.current_draft().is_empty() {
+            return false;
+        }
+        self.text_input_area.set_content(&draft.text);
+        self.text_input_area.move_cursor_to_end();
+        self.attached_files = draft.attached_files;
+        true
     }
 
     // Input management
@@ -265,7 +899,6 @@ impl Model {
         self.text_input_area.clear();
         self.last_input = None;
         self.input_history.clear();
-        self.printed_to_stdout_count = 0;
     }
 
     // Convenience accessors
@@ -273,6 +906,19 @@ impl Model {
         self.client().map(|c| c.base_url()).unwrap_or("unknown")
     }
 
+    pub fn max_text_part_bytes(&self) -> usize {
+        self.client()
+            .map(|c| c.options().max_text_part_bytes)
+            .unwrap_or(DEFAULT_MAX_TEXT_PART_BYTES)
+    }
+
+    /// Draft size and part limit in bytes, once the draft crosses half the limit
+    pub fn draft_size_warning(&self) -> Option<(usize, usize)> {
+        let limit = self.max_text_part_bytes();
+        let draft_bytes = self.text_input_area.content().len();
+        (draft_bytes * 2 >= limit).then_some((draft_bytes, limit))
+    }
+
     pub fn is_client_ready(&self) -> bool {
         self.client.is_some()
             && matches!(
@@ -281,12 +927,64 @@ impl Model {
             )
     }
 
+    /// Whether the server has loaded its providers and there are none, which
+    /// leaves nothing to send to
+    pub fn needs_provider_setup(&self) -> bool {
+        self.provider_models
+            .as_ref()
+            .is_some_and(ProviderModels::is_empty)
+    }
+
+    /// Open `modal` over the current state, which closing it goes back to
+    pub fn push_modal(&mut self, modal: AppModalState) {
+        if self.state == modal {
+            return;
+        }
+        if self.modal_stack.len() >= MODAL_STACK_LIMIT {
+            self.modal_stack.remove(0);
+        }
+        let previous = std::mem::replace(&mut self.state, modal);
+        self.modal_stack.push(previous);
+    }
+
+    /// Close the current modal, going back to the state it was opened over
+    pub fn pop_modal(&mut self) {
+        self.state = self.modal_stack.pop().unwrap_or(AppModalState::None);
+    }
+
+    /// Move on to `state`, closing every open modal on the way, except a
+    /// workspace mismatch that's still to be answered
+    pub fn set_state(&mut self, state: AppModalState) {
+        self.modal_stack.clear();
+        self.state = state;
+        if self.workspace_mismatch.is_some() && self.state != AppModalState::Quit {
+            self.push_modal(AppModalState::ModalWorkspaceMismatch);
+        }
+    }
+
+    /// The parts of the UI open now that tasks can be started for
+    pub fn open_task_contexts(&self) -> Vec<TaskContext> {
+        let mut contexts = Vec::new();
+        let selector = AppModalState::ModalSessionSelect;
+        if self.state == selector || self.modal_stack.contains(&selector) {
+            contexts.push(TaskContext::SessionSelector);
+        }
+        if let Some(session_id) = self.message_state.session_id() {
+            contexts.push(TaskContext::Session {
+                session_id: session_id.to_string(),
+            });
+        }
+        if let Some(client) = &self.client {
+            contexts.push(TaskContext::project(client));
+        }
+        contexts
+    }
+
     pub fn is_connnection_modal_active(&self) -> bool {
         matches!(
             self.state,
             AppModalState::Connecting(ConnectionStatus::Disconnected)
                 | AppModalState::Connecting(ConnectionStatus::InitializingSession)
-                | AppModalState::Connecting(ConnectionStatus::Connecting)
                 | AppModalState::Connecting(ConnectionStatus::Error(_))
         )
     }
@@ -298,6 +996,22 @@ impl Model {
             AppModalState::ModalSessionSelect
                 | AppModalState::ModalHelp
                 | AppModalState::ModalFileSelect
+                | AppModalState::ModalQuitConfirm
+                | AppModalState::ModalSessionDirectoryConfirm
+                | AppModalState::ModalWorkspaceMismatch
+                | AppModalState::ModalRedactConfirm
+                | AppModalState::ModalNewSession
+                | AppModalState::ModalSaveOutput
+                | AppModalState::ModalMetrics
+                | AppModalState::ModalActivityLog
+                | AppModalState::ModalSnippetSelect
+                | AppModalState::ModalSymbolSelect
+                | AppModalState::ModalServerSelect
+                | AppModalState::ModalProjectSelect
+                | AppModalState::ModalLinks
+                | AppModalState::ModalOnboarding
+                | AppModalState::ModalChangelog
+                | AppModalState::ModalServerOutdated
         ) || self.is_connnection_modal_active()
     }
 
@@ -323,6 +1037,111 @@ impl Model {
         self.client.as_ref()
     }
 
+    /// Key of the active project: its root once the server has reported it,
+    /// otherwise the server's URL
+    pub fn project_key(&self) -> Option<String> {
+        self.workspace_root
+            .clone()
+            .or_else(|| self.client().map(|client| client.base_url().to_string()))
+    }
+
+    /// Move the active project's state out of the model, leaving it as if
+    /// no server was connected. None if there is no project to park.
+    pub fn park_project(&mut self) -> Option<(String, ProjectState)> {
+        let key = self.project_key()?;
+        let client = self.client.take()?;
+        let project = ProjectState {
+            client,
+            connection_status: std::mem::replace(
+                &mut self.connection_status,
+                ConnectionStatus::Connecting,
+            ),
+            session_state: std::mem::replace(&mut self.session_state, SessionState::None),
+            sessions: std::mem::take(&mut self.sessions),
+            session_directories: std::mem::take(&mut self.session_directories),
+            session_selector: std::mem::replace(
+                &mut self.modal_session_selector,
+                SessionSelector::new(),
+            ),
+            modes: self.modes.take(),
+            // Parking cancels the project's requests, so a load in flight
+            // is started again once it's needed
+            modes_load: match std::mem::take(&mut self.modes_load) {
+                ModesLoad::Loading => ModesLoad::NotStarted,
+                load => load,
+            },
+            provider_models: self.provider_models.take(),
+            mode_state: self.mode_state.take(),
+            message_state: std::mem::take(&mut self.message_state),
+            event_stream_state: std::mem::replace(
+                &mut self.event_stream_state,
+                EventStreamState::Disconnected,
+            ),
+            session_is_idle: std::mem::replace(&mut self.session_is_idle, true),
+            response_started_at: self.response_started_at.take(),
+            working_since: self.working_time.working_since.take(),
+            server_version: self.set_server_version(None),
+            file_status: std::mem::take(&mut self.file_status),
+            git_info: self.git_info.take(),
+            workspace_root: self.workspace_root.take(),
+            previous_session_id: self.previous_session_id.take(),
+            finished_while_parked: false,
+        };
+        self.stop_message_polling();
+        self.early_events.clear();
+        self.pending_session_switch = None;
+        self.message_log.set_message_containers(vec![]);
+        Some((key, project))
+    }
+
+    /// Make a parked project the active one again
+    pub fn restore_project(&mut self, project: ProjectState) {
+        self.client = Some(project.client);
+        self.connection_status = project.connection_status;
+        self.session_state = project.session_state;
+        self.sessions = project.sessions;
+        self.session_directories = project.session_directories;
+        self.modal_session_selector = project.session_selector;
+        self.modes = project.modes;
+        self.modes_load = project.modes_load;
+        self.provider_models = project.provider_models;
+        self.mode_state = project.mode_state;
+        self.sync_current_model();
+        self.message_state = project.message_state;
+        self.event_stream_state = project.event_stream_state;
+        self.session_is_idle = project.session_is_idle;
+        self.response_started_at = project.response_started_at;
+        self.working_time.working_since = project.working_since;
+        self.set_server_version(project.server_version);
+        self.file_status = project.file_status;
+        self.git_info = project.git_info;
+        self.set_workspace_root(project.workspace_root);
+        self.previous_session_id = project.previous_session_id;
+        if matches!(self.event_stream_state, EventStreamState::Failed(_)) {
+            self.start_message_polling();
+        }
+        self.early_events.clear();
+        self.message_log.set_message_containers(vec![]);
+    }
+
+    /// Rebuild the project picker's rows, keeping the selected project
+    pub fn refresh_project_selector(&mut self) {
+        let selected = self
+            .modal_project_selector
+            .modal
+            .selected_item()
+            .map(SelectableData::to_string);
+        let rows = project_rows(self, self.modal_project_selector.candidates());
+        let index = selected
+            .and_then(|selected| {
+                rows.iter()
+                    .position(|row| SelectableData::to_string(row) == selected)
+            })
+            .unwrap_or(0);
+        self.modal_project_selector.modal.set_items(rows);
+        self.modal_project_selector.modal.select(index);
+    }
+
     pub fn session(&self) -> Option<&Session> {
         match &self.session_state {
             SessionState::Ready(session) => Some(session),
@@ -331,9 +1150,58 @@ impl Model {
     }
 
     pub fn change_session_by_index(&mut self, index: Option<usize>) {
+        self.remember_previous_session(index);
+        self.save_session_view();
+        // Until the next session is set, message events can't be attributed
+        self.message_state.set_session_id(None);
+        self.early_events.clear();
         self.message_log.set_message_containers(vec![]);
         self.modal_session_selector.set_current_session_index(index);
-        self.state = AppModalState::None;
+        self.set_state(AppModalState::None);
+    }
+
+    /// Remember the session being left for the one at `index`, for ctrl+x `
+    /// to go back to. It's listed if it wasn't, so it can be opened by index.
+    fn remember_previous_session(&mut self, index: Option<usize>) {
+        let Some(current) = self.session().cloned() else {
+            return;
+        };
+        let next = index
+            .and_then(|index| index.checked_sub(1))
+            .and_then(|index| self.sessions.get(index));
+        if next.is_some_and(|next| next.id == current.id) {
+            return;
+        }
+        if !self.sessions.iter().any(|session| session.id == current.id) {
+            self.sessions.push(current.clone());
+        }
+        self.previous_session_id = Some(current.id);
+    }
+
+    /// Remember how the current session is viewed, for coming back to it
+    fn save_session_view(&mut self) {
+        if let Some(session_id) = self.message_state.session_id().map(str::to_string) {
+            let view = SessionView {
+                scroll: self.message_log.scroll_position(),
+                verbosity: self.verbosity_level,
+            };
+            self.session_views.insert(session_id, view);
+        }
+    }
+
+    /// Put a session back the way it was viewed, once its messages are loaded.
+    /// Sessions not seen before stay at the bottom.
+    pub fn restore_session_view(&mut self) {
+        let Some(view) = self
+            .message_state
+            .session_id()
+            .and_then(|session_id| self.session_views.get(session_id))
+            .copied()
+        else {
+            return;
+        };
+        self.verbosity_level = view.verbosity;
+        self.message_log.restore_scroll_position(view.scroll);
     }
 
     pub fn change_session(&mut self, index: Option<usize>) -> bool {
@@ -341,22 +1209,26 @@ impl Model {
             // Handle selection
             Some(0) => {
                 self.change_session_by_index(None);
-                self.state = AppModalState::None;
 
                 // Create pending session info
                 let pending_info = PendingSessionInfo {
                     temp_id: generate_id(IdPrefix::Session),
-                    created_at: SystemTime::now(),
+                    created_at: self.clock.now(),
+                    directory: None,
                 };
                 self.session_state = SessionState::Pending(pending_info);
             }
             Some(requested_session_index) => {
                 // Use existing session (requested_session_index - 1 in sessions list)
                 let session_index = requested_session_index - 1;
-                if session_index < self.sessions.len() {
-                    self.change_session_by_index(Some(requested_session_index));
-                    self.state = AppModalState::Connecting(ConnectionStatus::InitializingSession);
-
+                if let Some(session) = self.sessions.get(session_index) {
+                    if self.foreign_session_directory(session).is_some() {
+                        // Ask before pointing the assistant at another project
+                        self.pending_session_switch = Some(requested_session_index);
+                        self.push_modal(AppModalState::ModalSessionDirectoryConfirm);
+                        return false;
+                    }
+                    self.open_session(requested_session_index);
                     return true;
                 }
             }
@@ -365,6 +1237,74 @@ impl Model {
         false
     }
 
+    fn open_session(&mut self, requested_session_index: usize) {
+        self.change_session_by_index(Some(requested_session_index));
+        self.set_state(AppModalState::Connecting(
+            ConnectionStatus::InitializingSession,
+        ));
+    }
+
+    /// Open the session from another project that was waiting on confirmation
+    pub fn confirm_session_switch(&mut self) -> bool {
+        match self.pending_session_switch.take() {
+            Some(index) if index > 0 && index <= self.sessions.len() => {
+                self.open_session(index);
+                true
+            }
+            _ => {
+                self.pop_modal();
+                false
+            }
+        }
+    }
+
+    pub fn cancel_session_switch(&mut self) {
+        self.pending_session_switch = None;
+        self.pop_modal();
+    }
+
+    /// Directory the session was created in, when it's outside the workspace
+    pub fn foreign_session_directory(&self, session: &Session) -> Option<&str> {
+        let directory = self.session_directories.get(&session.id)?;
+        is_foreign_directory(Some(directory), self.workspace_root.as_deref())
+            .then_some(directory.as_str())
+    }
+
+    /// Directory the session was created in, when it isn't the workspace
+    /// root, be it below it or in another project
+    pub fn session_directory_off_root(&self, session: &Session) -> Option<&str> {
+        let directory = self.session_directories.get(&session.id)?;
+        let root = self.workspace_root.as_deref()?;
+        (Path::new(directory) != Path::new(root)).then_some(directory.as_str())
+    }
+
+    /// Labels for the directories of the listed sessions, by session id
+    pub fn session_directory_labels(&self) -> HashMap<String, String> {
+        let home = dirs::home_dir();
+        self.session_directories
+            .iter()
+            .filter_map(|(session_id, directory)| {
+                let label = list_label(directory, self.workspace_root.as_deref(), home.as_deref())?;
+                Some((session_id.clone(), label))
+            })
+            .collect()
+    }
+
+    /// Directory of the session waiting on confirmation
+    pub fn pending_session_directory(&self) -> Option<&str> {
+        let session = self.sessions.get(self.pending_session_switch? - 1)?;
+        self.foreign_session_directory(session)
+    }
+
+    /// Whether a remembered session is recent enough to start straight into
+    pub fn should_resume_session(&self, session: &Session) -> bool {
+        if !self.config.session_resume {
+            return false;
+        }
+        let age_ms = (self.clock.now_ms() - session.time.updated).max(0.0);
+        age_ms <= (self.config.session_resume_max_age_secs * 1000) as f64
+    }
+
     pub fn current_session_id(&self) -> Option<String> {
         match &self.modal_session_selector.current_session_index() {
             None => None,
@@ -391,7 +1331,7 @@ impl Model {
     pub fn set_repeat_shortcut_timeout(&mut self, key: RepeatShortcutKey) {
         self.repeat_shortcut_timeout = Some(RepeatShortcutTimeout {
             key,
-            started_at: SystemTime::now(),
+            started_at: self.clock.now(),
         });
     }
 
@@ -411,7 +1351,7 @@ impl Model {
     pub fn is_repeat_shortcut_timeout_active(&self, key: RepeatShortcutKey) -> bool {
         if let Some(timeout) = &self.repeat_shortcut_timeout {
             if timeout.key == key {
-                if let Ok(elapsed) = timeout.started_at.elapsed() {
+                if let Ok(elapsed) = self.clock.elapsed_since(timeout.started_at) {
                     return elapsed.as_secs() < 1;
                 }
             }
@@ -421,7 +1361,7 @@ impl Model {
 
     pub fn has_active_timeout(&self) -> bool {
         if let Some(timeout) = &self.repeat_shortcut_timeout {
-            if let Ok(elapsed) = timeout.started_at.elapsed() {
+            if let Ok(elapsed) = self.clock.elapsed_since(timeout.started_at) {
                 return elapsed.as_secs() < 1;
             }
         }
@@ -430,7 +1370,7 @@ impl Model {
 
     pub fn expire_timeout_if_needed(&mut self) -> bool {
         if let Some(timeout) = &self.repeat_shortcut_timeout {
-            if let Ok(elapsed) = timeout.started_at.elapsed() {
+            if let Ok(elapsed) = self.clock.elapsed_since(timeout.started_at) {
                 if elapsed.as_secs() >= 1 {
                     self.repeat_shortcut_timeout = None;
                     return true;
@@ -447,13 +1387,16 @@ impl Model {
             TimeoutType::DebounceFindFiles(_) => {
                 !matches!(t.timeout_type, TimeoutType::DebounceFindFiles(_))
             }
+            TimeoutType::DebounceFindSymbols(_) => {
+                !matches!(t.timeout_type, TimeoutType::DebounceFindSymbols(_))
+            }
             _ => t.timeout_type != timeout_type,
         });
 
         // Add new timeout
         self.active_timeouts.push(Timeout {
             timeout_type,
-            started_at: SystemTime::now(),
+            started_at: self.clock.now(),
             duration_ms,
         });
     }
@@ -466,19 +1409,19 @@ impl Model {
     pub fn is_timeout_active(&self, timeout_type: &TimeoutType) -> bool {
         self.active_timeouts.iter().any(|t| {
             &t.timeout_type == timeout_type
-                && t.started_at
-                    .elapsed()
+                && self
+                    .clock
+                    .elapsed_since(t.started_at)
                     .map(|elapsed| elapsed.as_millis() < t.duration_ms as u128)
                     .unwrap_or(false)
         })
     }
 
     pub fn get_expired_timeouts(&mut self) -> Vec<TimeoutType> {
-        let now = SystemTime::now();
         let mut expired = Vec::new();
 
         self.active_timeouts.retain(|timeout| {
-            if let Ok(elapsed) = timeout.started_at.elapsed() {
+            if let Ok(elapsed) = self.clock.elapsed_since(timeout.started_at) {
                 if elapsed.as_millis() >= timeout.duration_ms as u128 {
                     tracing::debug!(
                         "since {:?} has been {} >= {}",
@@ -499,9 +1442,113 @@ impl Model {
         expired
     }
 
+    // Notification management
+    pub fn push_notification(&mut self, level: NotificationLevel, text: String, persistent: bool) {
+        self.notifications.push(Notification {
+            level,
+            text,
+            persistent,
+            created_at: self.clock.now(),
+        });
+        if !persistent {
+            self.set_timeout(TimeoutType::ExpireNotifications, NOTIFICATION_TIMEOUT_MS);
+        }
+    }
+
+    pub fn expire_notifications(&mut self) {
+        self.notifications.retain(|n| n.persistent);
+    }
+
+    pub fn dismiss_notifications(&mut self) {
+        self.notifications.clear();
+        self.clear_timeout(&TimeoutType::ExpireNotifications);
+    }
+
+    pub fn latest_notification(&self) -> Option<&Notification> {
+        self.notifications.last()
+    }
+
+    /// Show the next of the lower ranked shortcut hints, and schedule the one
+    /// after
+    pub fn rotate_hints(&mut self) {
+        self.hint_rotation = self.hint_rotation.wrapping_add(1);
+        self.dirty.status = true;
+        self.set_timeout(TimeoutType::RotateHints, HINT_ROTATION_MS);
+    }
+
+    /// Record the root of the server's project, which the log shows where
+    /// commands ran relative to
+    pub fn set_workspace_root(&mut self, root: Option<String>) {
+        self.message_log.set_workspace_root(root.clone());
+        self.workspace_root = root;
+    }
+
+    /// Ask before going on with a server whose workspace at `root` doesn't
+    /// hold the launch directory, unless it was connected to anyway before
+    pub fn check_workspace(&mut self, root: &str) {
+        if self.accepted_workspace_roots.contains(root) {
+            return;
+        }
+        let Some(directory) = self.launch_directory.as_deref() else {
+            return;
+        };
+        if let Some(mismatch) = WorkspaceMismatch::check(directory, root) {
+            tracing::warn!(
+                "Server workspace {} doesn't hold {}",
+                mismatch.root,
+                mismatch.directory.display()
+            );
+            self.workspace_mismatch = Some(mismatch);
+            self.push_modal(AppModalState::ModalWorkspaceMismatch);
+        }
+    }
+
+    /// Show the changelog, unless the connection's state holds the screen
+    pub fn show_changelog(&mut self) {
+        if self.changelog.is_some() && !self.is_connnection_modal_active() {
+            self.push_modal(AppModalState::ModalChangelog);
+        }
+    }
+
+    pub fn close_changelog(&mut self) {
+        self.changelog = None;
+        if self.state == AppModalState::ModalChangelog {
+            self.pop_modal();
+        }
+    }
+
+    /// Go on with the mismatched workspace, for as long as the app runs
+    pub fn accept_workspace(&mut self) {
+        if let Some(mismatch) = self.workspace_mismatch.take() {
+            self.accepted_workspace_roots.insert(mismatch.root);
+        }
+        if self.state == AppModalState::ModalWorkspaceMismatch {
+            self.pop_modal();
+        }
+    }
+
+    /// Record the running server's version along with what it supports,
+    /// returning the version it replaces
+    pub fn set_server_version(&mut self, version: Option<String>) -> Option<String> {
+        self.server_capabilities = version
+            .as_deref()
+            .map(ServerCapabilities::from_version)
+            .unwrap_or_default();
+        std::mem::replace(&mut self.server_version, version)
+    }
+
+    /// True when the server has announced an installed version newer than the running one
+    pub fn is_update_available(&self) -> bool {
+        match (&self.server_version, &self.installed_version) {
+            (Some(running), Some(installed)) => version_lags(running, installed),
+            _ => false,
+        }
+    }
+
     // Mode management
     pub fn set_mode(&mut self, index: u16) {
         self.mode_state = Some(index);
+        self.sync_current_model();
     }
 
     pub fn get_current_mode(&self) -> Option<&AgentConfig> {
@@ -514,18 +1561,56 @@ impl Model {
     }
 
     pub fn get_current_mode_name(&self) -> Option<String> {
-        match self.mode_state {
-            Some(0u16) => Some("build"),
-            Some(1u16) => Some("plan"),
-            Some(2u16) => Some("general"),
-            _ => None,
-        }
-        .map(|m| m.to_string())
+        self.mode_state
+            .and_then(|index| MODE_NAMES.get(index as usize))
+            .map(|m| m.to_string())
     }
 
     pub fn set_modes(&mut self, modes: ConfigAgent) {
         self.modes = Some(modes);
-        self.mode_state = Some(0);
+        self.mode_state = Some(self.default_mode_index());
+        self.sync_current_model();
+    }
+
+    /// The mode the settings name as the agent, or else the first one
+    fn default_mode_index(&self) -> u16 {
+        let Some(agent) = ResolvedSettings::value(&self.config.settings.agent) else {
+            return 0;
+        };
+        match MODE_NAMES.iter().position(|name| *name == agent) {
+            Some(index) => index as u16,
+            None => {
+                tracing::warn!("Unknown agent {}, starting in {}", agent, MODE_NAMES[0]);
+                0
+            }
+        }
+    }
+
+    /// Select the template's agent, and keep its model and system prompt for
+    /// the session being drafted
+    pub fn apply_session_template(&mut self, template: &SessionTemplate) {
+        if let Some(index) = MODE_NAMES.iter().position(|name| *name == template.agent) {
+            self.mode_state = Some(index as u16);
+        }
+        *self.session_options_mut() = SessionOptions {
+            system: template.system.clone(),
+            model: template.model.clone(),
+            ..SessionOptions::default()
+        };
+        self.sync_current_model();
+    }
+
+    /// Send with the provider and model the settings name, instead of the
+    /// built-in ones
+    pub fn apply_settings(&mut self) {
+        let settings = &self.config.settings;
+        if let Some(provider) = ResolvedSettings::value(&settings.provider) {
+            self.sdk_provider = provider.to_string();
+        }
+        if let Some(model) = ResolvedSettings::value(&settings.model) {
+            self.sdk_model = model.to_string();
+        }
+        self.sync_current_model();
     }
 
     pub fn increment_mode_index(&mut self) {
@@ -548,9 +1633,13 @@ impl Model {
                 }
             }
         };
+        self.sync_current_model();
     }
 
     pub fn get_mode_and_model_settings(&self) -> (String, String, Option<String>) {
+        if let Some((provider, model_name)) = self.session_options().model {
+            return (provider, model_name, self.get_current_mode_name());
+        }
         if let Some(current_mode) = self.get_current_mode() {
             // TODO fix this to be dynamic
             let provider = &self.sdk_provider;
@@ -567,6 +1656,63 @@ impl Model {
         }
     }
 
+    /// `provider/model` the next message would be sent with
+    pub fn current_model_label(&self) -> String {
+        let (provider_id, model_id, _) = self.get_mode_and_model_settings();
+        format!("{}/{}", provider_id, model_id)
+    }
+
+    /// Let the log annotate replies from other models than the current one
+    fn sync_current_model(&mut self) {
+        let label = self.current_model_label();
+        self.message_log.set_current_model(label);
+    }
+
+    /// What's wrong with the provider and model the next message would be
+    /// sent with, if the provider doesn't serve that model
+    pub fn model_mismatch_hint(&self) -> Option<String> {
+        let (provider_id, model_id, _) = self.get_mode_and_model_settings();
+        self.provider_models
+            .as_ref()?
+            .mismatch_hint(&provider_id, &model_id)
+    }
+
+    /// Estimated tokens of sending the draft now, with the session so far, and
+    /// the model's context limit. None without a draft. Attachments count
+    /// once measured, see `attachment_sizes`.
+    pub fn estimate_prompt_tokens(&self) -> Option<TokenEstimate> {
+        let text = self.text_input_area.content();
+        if text.trim().is_empty() && self.attached_files.is_empty() {
+            return None;
+        }
+        let (provider_id, model_id, _) = self.get_mode_and_model_settings();
+        let estimator = TokenEstimator::for_model(&model_id);
+        let attachments: usize = self
+            .attached_files
+            .iter()
+            .filter_map(|attached| self.attachment_sizes.get(&attached.file.path))
+            .map(|bytes| estimator.estimate_chars(*bytes))
+            .sum();
+        Some(TokenEstimate {
+            tokens: estimator.estimate(&text)
+                + attachments
+                + transcript_tokens(&self.message_state, &estimator),
+            limit: self
+                .provider_models
+                .as_ref()
+                .and_then(|models| models.context_limit(&provider_id, &model_id)),
+        })
+    }
+
+    /// Attached files whose size isn't known yet
+    pub fn unmeasured_attachments(&self) -> Vec<String> {
+        self.attached_files
+            .iter()
+            .map(|attached| attached.file.path.clone())
+            .filter(|path| !self.attachment_sizes.contains_key(path))
+            .collect()
+    }
+
     // Verbosity management
     pub fn toggle_verbosity(&mut self) {
         self.verbosity_level = match self.verbosity_level {
@@ -575,3 +1721,18 @@ impl Model {
         };
     }
 }
+
+/// Compare dotted versions numerically, falling back to string inequality
+/// when either side can't be parsed (e.g. "dev" or "0.3.0-beta")
+pub fn version_lags(running: &str, installed: &str) -> bool {
+    let parse = |v: &str| {
+        v.trim_start_matches('v')
+            .split('.')
+            .map(|n| n.parse::<u64>())
+            .collect::<Result<Vec<_>, _>>()
+    };
+    match (parse(running), parse(installed)) {
+        (Ok(running), Ok(installed)) => running < installed,
+        _ => running != installed,
+    }
+}
@@ -1,37 +1,61 @@
 use crate::app::{
+    keybindings::help_text,
+    links::LinkTarget,
+    server_capabilities::MIN_SUPPORTED_VERSION,
+    session_directory::confirm_text,
+    strings::{
+        HELP_TITLE, LINKS_CHECKING, LINKS_TITLE, NEW_SESSION_CHECKING, NEW_SESSION_HINT,
+        NEW_SESSION_TITLE, ONBOARDING_TEXT, ONBOARDING_TITLE, QUIT_CONFIRM_TEXT,
+        QUIT_CONFIRM_TITLE, REDACT_CONFIRM_TITLE, SAVE_OUTPUT_HINT, SAVE_OUTPUT_METADATA,
+        SAVE_OUTPUT_SAVING, SAVE_OUTPUT_TITLE, SERVER_OUTDATED_TEXT, SERVER_OUTDATED_TITLE,
+        SESSION_DIRECTORY_CONFIRM_TITLE, WORKSPACE_MISMATCH_TITLE,
+    },
     tea_model::*,
     ui_components::{
-        banner::{create_welcome_text, welcome_text_height},
-        message_part::StepRenderingMode,
-        text_input::TEXT_INPUT_HEIGHT,
-        AttachmentDisplay, MessageContext, MessageLog, MessageRenderer, SessionSelector, StatusBar,
+        banner::{banner_height, create_server_info_text, create_welcome_text, BannerInfo},
+        glyphs::Glyphs,
+        layout_class::too_small_notice,
+        live_step::{split_for_live_step, LiveStep},
+        modal_new_session::MAX_COMPLETIONS,
+        pinned_pane::split_for_pin,
+        session_header::SESSION_HEADER_HEIGHT,
+        text_input::TEXT_INPUT_AREA_MIN_HEIGHT,
+        text_width::display_width,
+        AttachmentDisplay, MessageContext, MessageRenderer, RenderCtx, SessionHeader, StatusBar,
     },
-    view_model_context::ViewModelContext,
 };
-use eyre::WrapErr;
 use ratatui::{
-    backend::CrosstermBackend,
+    backend::Backend,
     buffer::Buffer,
-    crossterm,
     layout::{Constraint, Direction, Layout, Rect},
     prelude::Widget,
-    style::{Color, Style},
-    text::{Line, Text, ToText},
-    widgets::{Block, Borders, Paragraph, Wrap},
+    style::{Color, Modifier, Style},
+    text::{Line, Span, Text},
+    widgets::{Block, BorderType, Borders, Paragraph, Wrap},
     Frame, Terminal,
 };
-use std::io;
 
 pub const MAX_UI_WIDTH: u16 = 140;
-const HELP_TEXT: &str = "
-    ^x h     help
-    ^x l     select session
-    ^x n     new session
-    ^x tab   toggle view
-    ^x q     quit
-    ";
+/// Smallest width of the help overlay; wider text widens it, and it's as
+/// tall as its text
 const HELP_WIDTH: u16 = 50;
-const HELP_HEIGHT: u16 = 8;
+/// Smallest size of the quit confirmation; wider text widens it
+const QUIT_CONFIRM_WIDTH: u16 = 40;
+const QUIT_CONFIRM_HEIGHT: u16 = 9;
+/// Smallest size of the onboarding modal; wider text widens it
+const ONBOARDING_WIDTH: u16 = 46;
+const ONBOARDING_HEIGHT: u16 = 16;
+/// Smallest size of the outdated server warning; wider text widens it
+const SERVER_OUTDATED_WIDTH: u16 = 46;
+const SERVER_OUTDATED_HEIGHT: u16 = 14;
+/// Smallest width of the redaction confirmation; it's as tall as its text
+const REDACT_CONFIRM_WIDTH: u16 = 50;
+const SESSION_DIRECTORY_CONFIRM_MAX_WIDTH: u16 = 80;
+const SESSION_DIRECTORY_CONFIRM_HEIGHT: u16 = 8;
+/// Smallest size of the workspace mismatch warning; longer paths widen it
+const WORKSPACE_MISMATCH_WIDTH: u16 = 40;
+const WORKSPACE_MISMATCH_HEIGHT: u16 = 12;
+const NEW_SESSION_MIN_WIDTH: u16 = 50;
 
 // Config:
 // - inline_mode          := true
@@ -41,6 +65,7 @@ const HELP_HEIGHT: u16 = 8;
 //
 // Design:
 //
+// ● Refactor the session loader · 12 messages
 // ╭─────────────────────────────────────────────────────────────────────────────────────────────╮
 // │ >                                                                                           │
 // ╰─────────────────────────────────────────────────────────────────────────────────────────────╯
@@ -55,6 +80,17 @@ const HELP_HEIGHT: u16 = 8;
 // │ > /quit  │
 // ╰──────────╯
 
+/// Columns a bordered box needs to show every line of `text` unwrapped
+fn bordered_width(text: &str) -> u16 {
+    let text_width = text.lines().map(display_width).max().unwrap_or_default();
+    (text_width as u16).saturating_add(2)
+}
+
+/// Rows a bordered box needs to show every line of `text`
+fn bordered_height(text: &str) -> u16 {
+    (text.lines().count() as u16).saturating_add(2)
+}
+
 pub fn clear_area_for_rect(buf: &mut Buffer, area: Rect) {
     // Clear the area (overlay effect)
     for y in area.y..area.y + area.height {
@@ -66,17 +102,23 @@ pub fn clear_area_for_rect(buf: &mut Buffer, area: Rect) {
     }
 }
 
-pub fn render_manual_inline_history(
+pub fn render_manual_inline_history<B: Backend>(
     model: &Model,
-    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    terminal: &mut Terminal<B>,
 ) -> crate::app::error::Result<()> {
     let message_containers = model.message_containers_for_rendering();
-    let (window_cols, _window_rows) = crossterm::terminal::size()?;
+    let window_cols = terminal.size()?.width;
+    let current_model = model.current_model_label();
 
     for container in &message_containers {
         let renderer =
-            MessageRenderer::step_safe(container, MessageContext::Inline, model.verbosity_level);
-        let rendered_text = renderer.render();
+            MessageRenderer::step_safe(container, MessageContext::Inline, model.verbosity_level)
+                .with_tool_render(&model.config.tool_render)
+                .with_glyphs(model.config.ui_glyphs.glyphs())
+                .with_tab_width(model.config.ui_tab_width)
+                .with_workspace_root(model.workspace_root.as_deref())
+                .with_model_annotation(&container.info, &current_model);
+        let rendered_text = renderer.render_to_width(Some(window_cols as usize));
         let paragraph = Paragraph::new(rendered_text).wrap(Wrap { trim: false });
         let line_count = paragraph.clone().line_count(window_cols) as u16;
 
@@ -88,41 +130,393 @@ pub fn render_manual_inline_history(
     Ok(())
 }
 
+pub fn render_welcome_banner<B: Backend>(
+    glyphs: &Glyphs,
+    terminal: &mut Terminal<B>,
+) -> crate::app::error::Result<()> {
+    let text = create_welcome_text(glyphs);
+    terminal.insert_before(banner_height(true), |buf| {
+        Paragraph::new(text).render(buf.area, buf);
+    })?;
+    Ok(())
+}
+
+pub fn render_banner_info<B: Backend>(
+    info: &BannerInfo,
+    terminal: &mut Terminal<B>,
+) -> crate::app::error::Result<()> {
+    let text = create_server_info_text(info);
+    terminal.insert_before(text.lines.len() as u16, |buf| {
+        Paragraph::new(text).render(buf.area, buf);
+    })?;
+    Ok(())
+}
+
 pub fn view(model: &Model, frame: &mut Frame) {
-    ViewModelContext::with_model(model, || {
-        if model.is_connnection_modal_active() {
-            render_connecting_screen(frame, frame.area());
-        } else {
-            // First render the text entry
-            render_base_screen(frame);
-
-            // Then render the modals depending on state
-            match &model.state {
-                AppModalState::ModalSessionSelect => {
-                    // Then render the popover selector on top
-                    frame.render_widget(&model.modal_session_selector, frame.area());
+    let ctx = RenderCtx::from_model(model, frame.area());
+    if ctx.layout_class.too_small {
+        render_too_small(frame);
+    } else if model.is_connnection_modal_active() {
+        render_connecting_screen(frame, model, &ctx, frame.area());
+    } else {
+        // First render the text entry
+        render_base_screen(frame, model, &ctx);
+
+        // Then render the modals depending on state
+        match &model.state {
+            AppModalState::ModalSessionSelect => {
+                // Then render the popover selector on top
+                model
+                    .modal_session_selector
+                    .render_with(&ctx, frame.area(), frame.buffer_mut());
+            }
+            AppModalState::ModalServerSelect => {
+                model
+                    .modal_server_selector
+                    .render_with(&ctx, frame.area(), frame.buffer_mut());
+            }
+            AppModalState::ModalSnippetSelect => {
+                model
+                    .modal_snippet_selector
+                    .render_with(&ctx, frame.area(), frame.buffer_mut());
+            }
+            AppModalState::ModalSymbolSelect => {
+                model
+                    .modal_symbol_selector
+                    .render_with(&ctx, frame.area(), frame.buffer_mut());
+            }
+            AppModalState::ModalProjectSelect => {
+                model
+                    .modal_project_selector
+                    .render_with(&ctx, frame.area(), frame.buffer_mut());
+            }
+            AppModalState::ModalMetrics => {
+                model
+                    .modal_metrics
+                    .render_with(&ctx, frame.area(), frame.buffer_mut());
+            }
+            AppModalState::ModalActivityLog => {
+                model
+                    .modal_activity_log
+                    .render_with(&ctx, frame.area(), frame.buffer_mut());
+            }
+            AppModalState::ModalHelp => {
+                let frame_area = frame.area();
+                let help = help_text();
+                let width = HELP_WIDTH.max(bordered_width(&help)).min(frame_area.width);
+                let height = bordered_height(&help).min(frame_area.height);
+                let help_area = Rect {
+                    x: frame_area.x + (frame_area.width - width) / 2,
+                    y: frame_area.y + (frame_area.height - height) / 2,
+                    width,
+                    height,
+                };
+                clear_area_for_rect(frame.buffer_mut(), help_area);
+
+                frame.render_widget(
+                    Paragraph::new(help).block(
+                        Block::default()
+                            .borders(Borders::ALL)
+                            .border_set(ctx.glyphs.border_set(BorderType::Plain))
+                            .title(HELP_TITLE),
+                    ),
+                    help_area,
+                )
+            }
+            AppModalState::ModalQuitConfirm => {
+                let frame_area = frame.area();
+                let width = QUIT_CONFIRM_WIDTH
+                    .max(bordered_width(QUIT_CONFIRM_TEXT))
+                    .min(frame_area.width);
+                let height = QUIT_CONFIRM_HEIGHT.min(frame_area.height);
+                let confirm_area = Rect {
+                    x: frame_area.x + (frame_area.width - width) / 2,
+                    y: frame_area.y + (frame_area.height - height) / 2,
+                    width,
+                    height,
+                };
+                clear_area_for_rect(frame.buffer_mut(), confirm_area);
+
+                frame.render_widget(
+                    Paragraph::new(QUIT_CONFIRM_TEXT).block(
+                        Block::default()
+                            .borders(Borders::ALL)
+                            .border_set(ctx.glyphs.border_set(BorderType::Plain))
+                            .title(QUIT_CONFIRM_TITLE),
+                    ),
+                    confirm_area,
+                )
+            }
+            AppModalState::ModalRedactConfirm => {
+                if let Some(prompt) = &model.redaction_prompt {
+                    let text = prompt.confirm_text();
+                    let text = ctx.glyphs.text(&text);
+                    let height = text.lines().count() as u16 + 2;
+                    render_text_modal(
+                        frame,
+                        &ctx,
+                        &text,
+                        REDACT_CONFIRM_TITLE,
+                        (REDACT_CONFIRM_WIDTH, height),
+                    )
                 }
-                AppModalState::ModalHelp => {
-                    let frame_area = frame.area();
-                    let help_area = Rect {
-                        x: frame_area.x + (frame_area.width - HELP_WIDTH) / 2,
-                        y: frame_area.y + (frame_area.height - HELP_HEIGHT) / 2,
-                        width: HELP_WIDTH,
-                        height: HELP_HEIGHT,
-                    };
-                    clear_area_for_rect(frame.buffer_mut(), help_area);
-
-                    frame.render_widget(
-                        Paragraph::new(HELP_TEXT)
-                            .block(Block::default().borders(Borders::ALL).title("Help")),
-                        help_area,
+            }
+            AppModalState::ModalSessionDirectoryConfirm => {
+                let directory = model.pending_session_directory().unwrap_or_default();
+                let text = confirm_text(directory, dirs::home_dir().as_deref());
+                let text_width = text.lines().map(display_width).max();
+                let frame_area = frame.area();
+                // Long paths wrap once the modal reaches its widest
+                let width = (text_width.unwrap_or_default() as u16 + 6)
+                    .min(SESSION_DIRECTORY_CONFIRM_MAX_WIDTH)
+                    .min(frame_area.width);
+                let height = SESSION_DIRECTORY_CONFIRM_HEIGHT.min(frame_area.height);
+                let confirm_area = Rect {
+                    x: frame_area.x + (frame_area.width - width) / 2,
+                    y: frame_area.y + (frame_area.height - height) / 2,
+                    width,
+                    height,
+                };
+                clear_area_for_rect(frame.buffer_mut(), confirm_area);
+
+                frame.render_widget(
+                    Paragraph::new(text).wrap(Wrap { trim: false }).block(
+                        Block::default()
+                            .borders(Borders::ALL)
+                            .border_set(ctx.glyphs.border_set(BorderType::Plain))
+                            .title(SESSION_DIRECTORY_CONFIRM_TITLE),
+                    ),
+                    confirm_area,
+                )
+            }
+            AppModalState::ModalWorkspaceMismatch => {
+                if let Some(mismatch) = &model.workspace_mismatch {
+                    let text = mismatch.confirm_text(dirs::home_dir().as_deref());
+                    render_text_modal(
+                        frame,
+                        &ctx,
+                        &text,
+                        WORKSPACE_MISMATCH_TITLE,
+                        (WORKSPACE_MISMATCH_WIDTH, WORKSPACE_MISMATCH_HEIGHT),
                     )
                 }
-                // No modals/overlays/notifications needed
-                _ => {}
+            }
+            AppModalState::ModalChangelog => {
+                if let Some(changelog) = &model.changelog {
+                    let area = changelog.area(frame.area());
+                    clear_area_for_rect(frame.buffer_mut(), area);
+                    changelog.render_with(&ctx, area, frame.buffer_mut());
+                }
+            }
+            AppModalState::ModalOnboarding => render_text_modal(
+                frame,
+                &ctx,
+                ONBOARDING_TEXT,
+                ONBOARDING_TITLE,
+                (ONBOARDING_WIDTH, ONBOARDING_HEIGHT),
+            ),
+            AppModalState::ModalServerOutdated => {
+                let text = SERVER_OUTDATED_TEXT
+                    .replace(
+                        "{running}",
+                        model.server_version.as_deref().unwrap_or_default(),
+                    )
+                    .replace("{minimum}", &MIN_SUPPORTED_VERSION.to_string());
+                render_text_modal(
+                    frame,
+                    &ctx,
+                    &text,
+                    SERVER_OUTDATED_TITLE,
+                    (SERVER_OUTDATED_WIDTH, SERVER_OUTDATED_HEIGHT),
+                )
+            }
+            AppModalState::ModalLinks => render_links_modal(frame, model, &ctx),
+            AppModalState::ModalNewSession => render_new_session_modal(frame, model, &ctx),
+            AppModalState::ModalSaveOutput => render_save_output_modal(frame, model, &ctx),
+            // No modals/overlays/notifications needed
+            _ => {}
+        };
+    }
+}
+
+/// Fixed text in a box centered on the frame, at least `min_size` unless the
+/// frame is smaller, and widened to fit the text
+fn render_text_modal(
+    frame: &mut Frame,
+    ctx: &RenderCtx,
+    text: &str,
+    title: &str,
+    min_size: (u16, u16),
+) {
+    let frame_area = frame.area();
+    let width = min_size.0.max(bordered_width(text)).min(frame_area.width);
+    let height = min_size.1.min(frame_area.height);
+    let area = Rect {
+        x: frame_area.x + (frame_area.width - width) / 2,
+        y: frame_area.y + (frame_area.height - height) / 2,
+        width,
+        height,
+    };
+    clear_area_for_rect(frame.buffer_mut(), area);
+
+    frame.render_widget(
+        Paragraph::new(text).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_set(ctx.glyphs.border_set(BorderType::Plain))
+                .title(title),
+        ),
+        area,
+    )
+}
+
+/// The links of the last reply, each after the digit that picks it
+fn render_links_modal(frame: &mut Frame, model: &Model, ctx: &RenderCtx) {
+    let mut lines = Vec::new();
+    if let Some(links) = &model.modal_links {
+        for (hint, link) in links.hints() {
+            let color = match link {
+                LinkTarget::File { .. } => Color::Cyan,
+                LinkTarget::Url(_) => Color::Blue,
             };
+            lines.push(Line::from(vec![
+                Span::styled(format!(" {} ", hint), Style::default().fg(Color::Yellow)),
+                Span::styled(link.label(), Style::default().fg(color)),
+            ]));
         }
-    })
+    }
+    if model.message_log.link_paths().is_checking() {
+        lines.push(Line::from(Span::styled(
+            format!(" {}", ctx.glyphs.text(LINKS_CHECKING)),
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+
+    let frame_area = frame.area();
+    let text_width = lines.iter().map(Line::width).max().unwrap_or_default();
+    let width = (text_width.max(display_width(LINKS_TITLE)) as u16 + 3).min(frame_area.width);
+    let height = (lines.len() as u16 + 2).min(frame_area.height);
+    let links_area = Rect {
+        x: frame_area.x + (frame_area.width - width) / 2,
+        y: frame_area.y + (frame_area.height - height) / 2,
+        width,
+        height,
+    };
+    clear_area_for_rect(frame.buffer_mut(), links_area);
+    frame.render_widget(
+        Paragraph::new(lines).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_set(ctx.glyphs.border_set(BorderType::Plain))
+                .title(LINKS_TITLE),
+        ),
+        links_area,
+    );
+}
+
+/// The directory being typed for a new session, with the completions left
+/// to pick from and why the last one was refused
+fn render_new_session_modal(frame: &mut Frame, model: &Model, ctx: &RenderCtx) {
+    let prompt = &model.new_session_prompt;
+    let dim = Style::default().fg(Color::DarkGray);
+    let mut lines = vec![
+        Line::default(),
+        Line::from(vec![
+            Span::raw(" > "),
+            Span::raw(prompt.input.as_str()),
+            // A block cursor at the end, where typing goes
+            Span::styled(" ", Style::default().add_modifier(Modifier::REVERSED)),
+        ]),
+    ];
+    for completion in prompt.completions.iter().take(MAX_COMPLETIONS) {
+        lines.push(Line::from(Span::styled(format!("   {}/", completion), dim)));
+    }
+    if prompt.completions.len() > MAX_COMPLETIONS {
+        let more = prompt.completions.len() - MAX_COMPLETIONS;
+        lines.push(Line::from(Span::styled(format!("   +{} more", more), dim)));
+    }
+    lines.push(Line::default());
+    if prompt.checking.is_some() {
+        lines.push(Line::from(Span::styled(
+            format!(" {}", ctx.glyphs.text(NEW_SESSION_CHECKING)),
+            dim,
+        )));
+    } else if let Some(error) = &prompt.error {
+        lines.push(Line::from(Span::styled(
+            format!(" {}", error),
+            Style::default().fg(Color::Red),
+        )));
+    }
+    lines.push(Line::from(Span::styled(
+        format!(" {}", ctx.glyphs.text(NEW_SESSION_HINT)),
+        dim,
+    )));
+    render_prompt_box(frame, ctx, lines, NEW_SESSION_TITLE);
+}
+
+/// The path to save the latest tool output to, and whether its metadata goes
+/// with it
+fn render_save_output_modal(frame: &mut Frame, model: &Model, ctx: &RenderCtx) {
+    let prompt = &model.save_output_prompt;
+    let dim = Style::default().fg(Color::DarkGray);
+    let check = if prompt.with_metadata { "[x]" } else { "[ ]" };
+    let mut lines = vec![
+        Line::default(),
+        Line::from(vec![
+            Span::raw(" > "),
+            Span::raw(prompt.input.as_str()),
+            Span::styled(" ", Style::default().add_modifier(Modifier::REVERSED)),
+        ]),
+        Line::from(Span::styled(
+            format!("   {} {}", check, SAVE_OUTPUT_METADATA),
+            dim,
+        )),
+        Line::default(),
+    ];
+    if prompt.saving.is_some() {
+        lines.push(Line::from(Span::styled(
+            format!(" {}", ctx.glyphs.text(SAVE_OUTPUT_SAVING)),
+            dim,
+        )));
+    } else if let Some(error) = &prompt.error {
+        lines.push(Line::from(Span::styled(
+            format!(" {}", ctx.glyphs.text(error)),
+            Style::default().fg(Color::Red),
+        )));
+    }
+    lines.push(Line::from(Span::styled(
+        format!(" {}", ctx.glyphs.text(SAVE_OUTPUT_HINT)),
+        dim,
+    )));
+    render_prompt_box(frame, ctx, lines, SAVE_OUTPUT_TITLE);
+}
+
+/// A single line prompt's `lines` in a box centered on the frame, as wide as
+/// they are within limits
+fn render_prompt_box(frame: &mut Frame, ctx: &RenderCtx, lines: Vec<Line>, title: &str) {
+    let frame_area = frame.area();
+    let text_width = lines.iter().map(Line::width).max().unwrap_or_default();
+    // Long paths wrap once the modal reaches its widest
+    let width = (text_width as u16 + 3)
+        .clamp(NEW_SESSION_MIN_WIDTH, SESSION_DIRECTORY_CONFIRM_MAX_WIDTH)
+        .min(frame_area.width);
+    let height = (lines.len() as u16 + 2).min(frame_area.height);
+    let prompt_area = Rect {
+        x: frame_area.x + (frame_area.width - width) / 2,
+        y: frame_area.y + (frame_area.height - height) / 2,
+        width,
+        height,
+    };
+    clear_area_for_rect(frame.buffer_mut(), prompt_area);
+    frame.render_widget(
+        Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_set(ctx.glyphs.border_set(BorderType::Plain))
+                .title(title),
+        ),
+        prompt_area,
+    );
 }
 
 pub fn view_clear(frame: &mut Frame) {
@@ -130,10 +524,25 @@ pub fn view_clear(frame: &mut Frame) {
     frame.render_widget(Paragraph::new(""), frame.area());
 }
 
-fn render_base_screen(frame: &mut Frame) {
-    let model = ViewModelContext::current();
+/// Shown instead of the UI when the frame is below the minimum size
+fn render_too_small(frame: &mut Frame) {
+    let area = frame.area();
+    let notice_area = Rect {
+        y: area.y + area.height / 2,
+        height: area.height.min(1),
+        ..area
+    };
+    frame.render_widget(
+        Paragraph::new(too_small_notice())
+            .style(Style::default().fg(Color::Yellow))
+            .wrap(Wrap { trim: true }),
+        notice_area,
+    );
+}
+
+fn render_base_screen(frame: &mut Frame, model: &Model, ctx: &RenderCtx) {
     let terminal_width = frame.area().width;
-    let content_width = match model.init().inline_mode() {
+    let content_width = match ctx.inline_mode {
         true => terminal_width.max(MAX_UI_WIDTH),
         // Full screen is 1 character padding
         false => terminal_width.saturating_sub(2),
@@ -153,13 +562,21 @@ fn render_base_screen(frame: &mut Frame) {
 
     let content_area = horizontal_chunks[1];
 
-    // Use dynamic height from TextInputArea and add space for StatusBar
-    let text_input_height = model.get().text_input_area.current_height();
+    // Use dynamic height from TextInputArea and add space for StatusBar. A
+    // short frame keeps the input at its minimum to leave room for the rest.
+    let text_input_height = match ctx.layout_class.short {
+        true => TEXT_INPUT_AREA_MIN_HEIGHT,
+        false => model.text_input_area.current_height(),
+    };
     let status_bar_height = 1;
     let total_input_section_height = text_input_height + status_bar_height;
 
-    let spacer_height = match model.init().inline_mode() {
-        true => &model.get().config.height - total_input_section_height,
+    let header_height = inline_header_height(model, ctx, total_input_section_height);
+    let spacer_height = match ctx.inline_mode {
+        true => model
+            .config
+            .height
+            .saturating_sub(total_input_section_height + header_height),
         false => 0,
     };
 
@@ -168,13 +585,15 @@ fn render_base_screen(frame: &mut Frame) {
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Min(0),                             // (optional) Message log
+            Constraint::Length(header_height),              // (optional) Session header
             Constraint::Length(spacer_height),              // (optional) Buffer space
             Constraint::Length(total_input_section_height), // Input textarea + status bar
         ])
         .split(content_area);
     let fullscreen_chunk = vertical_chunks[0];
-    let spacer_chunk = vertical_chunks[1];
-    let input_chunk = vertical_chunks[2];
+    let header_chunk = vertical_chunks[1];
+    let spacer_chunk = vertical_chunks[2];
+    let input_chunk = vertical_chunks[3];
 
     // Split the input section into textarea and status bar
     let input_section_chunks = Layout::default()
@@ -187,37 +606,23 @@ fn render_base_screen(frame: &mut Frame) {
     let input_textarea = input_section_chunks[0];
     let input_status = input_section_chunks[1];
 
-    if model.init().inline_mode() {
+    if header_height > 0 {
+        frame.render_widget(&SessionHeader::from_model(model), header_chunk);
+    }
+
+    if ctx.inline_mode {
         // Render file selector on top of spacer_chunk
-        if matches!(model.state(), AppModalState::ModalFileSelect) {
-            frame.render_widget(&model.get().modal_file_selector, spacer_chunk);
-        } else {
-            render_main_body(frame, spacer_chunk);
-        }
-        frame.render_widget(&model.get().text_input_area, input_textarea);
-        
-        // Render attachment indicator and status bar side by side
-        if !model.get().attached_files.is_empty() {
-            let status_chunks = Layout::default()
-                .direction(Direction::Horizontal)
-                .constraints([
-                    Constraint::Length(20), // Attachment display
-                    Constraint::Min(0),     // Status bar
-                ])
-                .split(input_status);
-            
-            let attachment_display = AttachmentDisplay::new(model.get().attached_files.clone());
-            attachment_display.render_inline(status_chunks[0], frame.buffer_mut());
-            
-            let status_bar = StatusBar::new();
-            frame.render_widget(&status_bar, status_chunks[1]);
+        if matches!(&model.state, AppModalState::ModalFileSelect) {
+            model
+                .modal_file_selector
+                .render_with(ctx, spacer_chunk, frame.buffer_mut());
         } else {
-            let status_bar = StatusBar::new();
-            frame.render_widget(&status_bar, input_status);
+            render_main_body(frame, spacer_chunk, model, ctx);
+            render_notification(frame, spacer_chunk, model);
         }
     } else {
         // In fullscreen mode, we have more space - render file selector above the text input
-        if matches!(model.state(), AppModalState::ModalFileSelect) {
+        if matches!(&model.state, AppModalState::ModalFileSelect) {
             // Split fullscreen area to accommodate file selector
             let fullscreen_with_selector_chunks = Layout::default()
                 .direction(Direction::Vertical)
@@ -227,74 +632,141 @@ fn render_base_screen(frame: &mut Frame) {
                 ])
                 .split(fullscreen_chunk);
 
-            render_main_body(frame, fullscreen_with_selector_chunks[0]);
-            frame.render_widget(
-                &model.get().modal_file_selector,
+            render_main_body_beside_pin(frame, fullscreen_with_selector_chunks[0], model, ctx);
+            model.modal_file_selector.render_with(
+                ctx,
                 fullscreen_with_selector_chunks[1],
+                frame.buffer_mut(),
             );
         } else {
-            render_main_body(frame, fullscreen_chunk);
+            let log_area = render_main_body_beside_pin(frame, fullscreen_chunk, model, ctx);
+            render_notification(frame, log_area, model);
         }
+    }
 
-        frame.render_widget(&model.get().text_input_area, input_textarea);
-        
-        // Render attachment indicator and status bar side by side
-        if !model.get().attached_files.is_empty() {
-            let status_chunks = Layout::default()
-                .direction(Direction::Horizontal)
-                .constraints([
-                    Constraint::Length(20), // Attachment display
-                    Constraint::Min(0),     // Status bar
-                ])
-                .split(input_status);
-            
-            let attachment_display = AttachmentDisplay::new(model.get().attached_files.clone());
-            attachment_display.render_inline(status_chunks[0], frame.buffer_mut());
-            
-            let status_bar = StatusBar::new();
-            frame.render_widget(&status_bar, status_chunks[1]);
-        } else {
-            let status_bar = StatusBar::new();
-            frame.render_widget(&status_bar, input_status);
-        }
+    model
+        .text_input_area
+        .render_with(ctx, input_textarea, frame.buffer_mut());
+    render_status_line(frame, input_status, model, ctx);
+}
+
+/// Height of the session header over the inline input. A short viewport
+/// leaves it out first, so the input keeps its lines.
+fn inline_header_height(model: &Model, ctx: &RenderCtx, input_section_height: u16) -> u16 {
+    let room = model.config.height.saturating_sub(input_section_height);
+    let shown = ctx.inline_mode
+        && !ctx.layout_class.short
+        && model.is_session_ready()
+        && room >= SESSION_HEADER_HEIGHT;
+    match shown {
+        true => SESSION_HEADER_HEIGHT,
+        false => 0,
     }
 }
 
-fn render_main_body(frame: &mut Frame, buf: Rect) {
-    let model = ViewModelContext::current();
+/// The status bar under the input, with the attachments to its left
+fn render_status_line(frame: &mut Frame, area: Rect, model: &Model, ctx: &RenderCtx) {
+    let status_area = if model.attached_files.is_empty() {
+        area
+    } else {
+        let status_chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Length(20), // Attachment display
+                Constraint::Min(0),     // Status bar
+            ])
+            .split(area);
+
+        let attachment_display =
+            AttachmentDisplay::new(model.attached_files.clone()).with_glyphs(ctx.glyphs);
+        attachment_display.render_inline(status_chunks[0], frame.buffer_mut());
+        status_chunks[1]
+    };
+    frame.render_widget(&StatusBar::new(model, ctx), status_area);
+}
 
-    if model.get().is_session_ready() {
-        if !model.init().inline_mode() {
-            frame.render_widget(&model.get().message_log, buf);
+fn render_main_body(frame: &mut Frame, buf: Rect, model: &Model, ctx: &RenderCtx) {
+    if model.is_session_ready() {
+        if !ctx.inline_mode {
+            // The running step's tools go under the log while it has room
+            let live_step = LiveStep::from_model(model)
+                .and_then(|step| split_for_live_step(buf, &step).map(|split| (step, split)));
+            match live_step {
+                Some((step, (log_area, step_area))) => {
+                    model
+                        .message_log
+                        .render_with(ctx, log_area, frame.buffer_mut());
+                    step.render_with(ctx, step_area, frame.buffer_mut());
+                }
+                None => model.message_log.render_with(ctx, buf, frame.buffer_mut()),
+            }
         }
     } else {
-        let welcome_text = Text::from(format!("\n{}{}", model.connection_status(), HELP_TEXT));
-        let line_height = (welcome_text.to_text().lines.len().saturating_add(2) as u16)
-            .max(model.get().config.height);
+        let welcome_text = Text::from(format!("\n{}{}", model.connection_status, help_text()));
         let paragraph = Paragraph::new(welcome_text);
 
         frame.render_widget(paragraph, buf);
     }
 }
 
-fn render_connecting_screen(frame: &mut Frame, rect: Rect) {
-    let model = ViewModelContext::current();
+/// The main body with the pinned pane to its right when there's room for
+/// both, returning where the body went
+fn render_main_body_beside_pin(
+    frame: &mut Frame,
+    area: Rect,
+    model: &Model,
+    ctx: &RenderCtx,
+) -> Rect {
+    let split = model.pinned_pane.as_ref().zip(split_for_pin(area));
+    let Some((pane, (body_area, pane_area))) = split else {
+        render_main_body(frame, area, model, ctx);
+        return area;
+    };
+    render_main_body(frame, body_area, model, ctx);
+    pane.render_with(ctx, pane_area, frame.buffer_mut());
+    body_area
+}
+
+fn render_notification(frame: &mut Frame, area: Rect, model: &Model) {
+    let Some(notification) = model.latest_notification() else {
+        return;
+    };
+    if area.height == 0 {
+        return;
+    }
+
+    // Overlay the last line of the area, just above the input box
+    let notification_area = Rect {
+        y: area.y + area.height - 1,
+        height: 1,
+        ..area
+    };
+    let color = match notification.level {
+        NotificationLevel::Info => Color::Cyan,
+        NotificationLevel::Warning => Color::Yellow,
+        NotificationLevel::Error => Color::Red,
+    };
+    let hint = if notification.persistent {
+        " (esc to dismiss)"
+    } else {
+        ""
+    };
+    clear_area_for_rect(frame.buffer_mut(), notification_area);
+    frame.render_widget(
+        Paragraph::new(Line::from(vec![
+            Span::styled(notification.text.clone(), Style::default().fg(color)),
+            Span::styled(hint, Style::default().fg(Color::DarkGray)),
+        ])),
+        notification_area,
+    );
+}
+
+fn render_connecting_screen(frame: &mut Frame, model: &Model, ctx: &RenderCtx, rect: Rect) {
     let block = Block::default()
         .borders(Borders::ALL)
+        .border_set(ctx.glyphs.border_set(BorderType::Plain))
         .title("Connection Status");
-    let paragraph = match &model.get().state {
-        AppModalState::Connecting(ConnectionStatus::Connecting) => {
-            let text = Text::from(vec![
-                Line::from("Connecting to OpenCode server..."),
-                Line::from(""),
-                Line::from("Looking for running OpenCode processes..."),
-                Line::from(""),
-                Line::from("Press 'q' or 'Esc' to cancel"),
-            ]);
-            Paragraph::new(text)
-                .style(Style::default().fg(Color::Yellow))
-                .block(block)
-        }
+    let paragraph = match &model.state {
         AppModalState::Connecting(ConnectionStatus::InitializingSession) => {
             let client_url = model.client_base_url();
             let text = Text::from(vec![
@@ -312,8 +784,14 @@ fn render_connecting_screen(frame: &mut Frame, rect: Rect) {
             let text = Text::from(vec![
                 Line::from(format!("Connection Error! {}", error.to_string())),
                 Line::from("Suggestions:"),
-                Line::from("• Make sure OpenCode server is running"),
-                Line::from("• Check OPENCODE_SERVER_URL environment variable"),
+                Line::from(format!(
+                    "{} Make sure OpenCode server is running",
+                    ctx.glyphs.tool
+                )),
+                Line::from(format!(
+                    "{} Check OPENCODE_SERVER_URL environment variable",
+                    ctx.glyphs.tool
+                )),
                 Line::from("Press 'r' to retry, 'q' or 'Esc' to quit"),
             ]);
             Paragraph::new(text)
@@ -330,7 +808,103 @@ fn render_connecting_screen(frame: &mut Frame, rect: Rect) {
             Constraint::Length(5),
             Constraint::Min(0),
         ])
-        .split(frame.area());
+        .split(rect);
     frame.render_widget(paragraph, vertical_chunks[1]);
     // }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::strings::WORKSPACE_MISMATCH_TEXT;
+    use crate::app::ui_components::layout_class::SHORT_INLINE_HEIGHT;
+    use crate::sdk::OpenCodeClient;
+    use opencode_sdk::models::{Session, SessionTime};
+    use ratatui::backend::TestBackend;
+
+    fn inline_session_model(title: &str) -> Model {
+        let mut model = Model::new();
+        model.state = AppModalState::None;
+        model.connection_status = ConnectionStatus::SessionReady;
+        model.client = Some(OpenCodeClient::new("http://127.0.0.1:4096"));
+        model.session_state = SessionState::Ready(Session::new(
+            "ses_a".to_string(),
+            title.to_string(),
+            "0.3.1".to_string(),
+            SessionTime::new(0.0, 0.0),
+        ));
+        model
+    }
+
+    fn render_lines(model: &Model, width: u16) -> Vec<String> {
+        let height = model.config.height;
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        let frame = terminal.draw(|f| view(model, f)).unwrap();
+        (0..height)
+            .map(|y| {
+                (0..width)
+                    .map(|x| frame.buffer[(x, y)].symbol())
+                    .collect::<String>()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_inline_header_names_the_session() {
+        let model = inline_session_model("Refactor the session loader");
+        let lines = render_lines(&model, 80);
+        assert!(
+            lines[0].starts_with("● Refactor the session loader · 0 messages"),
+            "{:?}",
+            lines
+        );
+
+        let mut fullscreen = model.clone();
+        fullscreen.init = ModelInit::new(false);
+        let lines = render_lines(&fullscreen, 80);
+        assert!(!lines.iter().any(|line| line.contains("0 messages")));
+    }
+
+    #[test]
+    fn test_inline_header_dropped_on_a_short_viewport() {
+        let mut model = inline_session_model("Refactor");
+        model
+            .text_input_area
+            .set_content("one\ntwo\nthree\nfour\nfive\nsix\nseven\neight");
+        model.text_input_area.move_cursor_to_end();
+
+        // The header fits over a fully grown input
+        let lines = render_lines(&model, 80);
+        assert!(lines[0].contains("Refactor"), "{:?}", lines);
+        assert!(lines.iter().any(|line| line.contains("eight")));
+
+        // A short viewport leaves it out, and the input keeps its minimum
+        model.config.height = SHORT_INLINE_HEIGHT;
+        let lines = render_lines(&model, 80);
+        assert!(!lines.iter().any(|line| line.contains("Refactor")));
+        let input_top = lines.iter().position(|line| line.starts_with('╭'));
+        let input_bottom = lines.iter().position(|line| line.starts_with('╰'));
+        assert_eq!(
+            input_bottom
+                .zip(input_top)
+                .map(|(bottom, top)| bottom - top + 1),
+            Some(TEXT_INPUT_AREA_MIN_HEIGHT as usize),
+            "{:?}",
+            lines
+        );
+    }
+
+    #[test]
+    fn test_overlays_sized_by_display_width() {
+        // The built-in text fits the default sizes
+        assert!(bordered_width(&help_text()) <= HELP_WIDTH);
+        assert!(bordered_width(QUIT_CONFIRM_TEXT) <= QUIT_CONFIRM_WIDTH);
+        assert!(bordered_width(ONBOARDING_TEXT) <= ONBOARDING_WIDTH);
+        assert!(bordered_width(SERVER_OUTDATED_TEXT) <= SERVER_OUTDATED_WIDTH);
+        assert!(bordered_width(WORKSPACE_MISMATCH_TEXT) <= WORKSPACE_MISMATCH_WIDTH);
+        // Wide characters count two columns each
+        assert_eq!(bordered_width("\n    応答はまだ実行中です。\n"), 28);
+        // A leading blank line takes a row, a trailing newline doesn't
+        assert_eq!(bordered_height("\nkeys\ncommands\n"), 5);
+    }
+}
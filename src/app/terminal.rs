@@ -1,17 +1,28 @@
 use crate::app::{
+    clipboard::clipboard_sequence,
+    completion_notice::notification_sequence,
     error::Result,
+    job_control::suspend_process,
     tea_model::{Model, ModelInit},
+    tea_view::{
+        render_banner_info, render_manual_inline_history, render_welcome_banner, view, view_clear,
+    },
+    ui_components::{banner::BannerInfo, glyphs::Glyphs},
 };
 use crossterm::{
     event::{
-        DisableMouseCapture, EnableMouseCapture, KeyboardEnhancementFlags,
-        PushKeyboardEnhancementFlags,
+        DisableBracketedPaste, DisableFocusChange, DisableMouseCapture, EnableBracketedPaste,
+        EnableFocusChange, EnableMouseCapture,
     },
     execute,
+    style::Print,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use eyre::WrapErr;
-use ratatui::{backend::CrosstermBackend, Terminal, TerminalOptions, Viewport};
+use ratatui::{
+    backend::{Backend, CrosstermBackend},
+    Terminal, TerminalOptions, Viewport,
+};
 use std::io::{self, stdout, Write};
 
 pub fn align_crossterm_output_to_bottom(model: &Model) -> Result<()> {
@@ -27,6 +38,11 @@ pub fn align_crossterm_output_to_bottom(model: &Model) -> Result<()> {
     Ok(())
 }
 
+/// Rows of the terminal window, if it can be queried
+pub fn terminal_rows() -> Option<u16> {
+    crossterm::terminal::size().ok().map(|(_cols, rows)| rows)
+}
+
 /// Initialize the terminal with panic hook for automatic cleanup
 pub fn init_terminal(
     init: &ModelInit,
@@ -48,6 +64,10 @@ pub fn init_terminal(
 
     let mut stdout = stdout();
     execute!(stdout, EnableMouseCapture).wrap_err("Failed to enable mouse capture")?;
+    // Pastes arrive as one event, so pasted newlines don't submit the input
+    execute!(stdout, EnableBracketedPaste).wrap_err("Failed to enable bracketed paste")?;
+    // Focus reports decide whether a finished response needs a notification
+    execute!(stdout, EnableFocusChange).wrap_err("Failed to enable focus reporting")?;
 
     if !init.inline_mode() {
         tracing::debug!("Entering alternate screen mode");
@@ -102,6 +122,12 @@ pub fn restore_terminal(init: &ModelInit, height: u16) -> io::Result<()> {
     if let Err(e) = execute!(stdout, DisableMouseCapture) {
         tracing::error!("Failed to disable mouse capture during restore: {}", e);
     }
+    if let Err(e) = execute!(stdout, DisableBracketedPaste) {
+        tracing::error!("Failed to disable bracketed paste during restore: {}", e);
+    }
+    if let Err(e) = execute!(stdout, DisableFocusChange) {
+        tracing::error!("Failed to disable focus reporting during restore: {}", e);
+    }
 
     if !init.inline_mode() {
         // Handle screen mode restoration
@@ -112,7 +138,7 @@ pub fn restore_terminal(init: &ModelInit, height: u16) -> io::Result<()> {
         // to maintain position during inline toggle
         // TODO fix! Need to move to the bottom of the viewport, not the bottom of the terminal
         // probably need to pass in the viewport rect instead of the height?
-        if let Ok((cols, rows)) = crossterm::terminal::size() {
+        if let Ok((_, rows)) = crossterm::terminal::size() {
             // Clear from cursor position down to prevent overlap
             execute!(
                 stdout,
@@ -129,3 +155,395 @@ pub fn restore_terminal(init: &ModelInit, height: u16) -> io::Result<()> {
     tracing::info!("Terminal restore completed");
     Ok(())
 }
+
+/// Terminal IO used by the program loop, so the loop can run against a real
+/// terminal or a headless buffer
+pub trait TerminalBackend {
+    /// Render the TUI viewport from the model
+    fn draw(&mut self, model: &Model) -> Result<()>;
+
+    /// Print messages above the inline viewport into the scrollback history
+    fn insert_history(&mut self, model: &Model) -> Result<()>;
+
+    /// Print the welcome banner above the inline viewport
+    fn insert_welcome_banner(&mut self, glyphs: &Glyphs) -> Result<()>;
+
+    /// Print the connected server's details above the inline viewport
+    fn insert_banner_info(&mut self, info: &BannerInfo) -> Result<()>;
+
+    /// Tear down this terminal and set up a new one for the given viewport
+    fn reboot(
+        self: Box<Self>,
+        old_init: &ModelInit,
+        new_init: &ModelInit,
+        height: u16,
+    ) -> Result<Box<dyn TerminalBackend>>;
+
+    /// Resize the inline viewport to a new height
+    fn resize_inline_viewport(&mut self, height: u16) -> Result<()>;
+
+    /// Pick up any changes to the terminal window size
+    fn autoresize(&mut self) -> Result<()>;
+
+    /// Clear the viewport and scroll its rows up into the history
+    fn scroll_past_height(&mut self, line_count: u16) -> Result<()>;
+
+    /// Ask the terminal to show a desktop notification
+    fn notify(&mut self, text: &str) -> Result<()>;
+
+    /// Ring the terminal bell
+    fn bell(&mut self) -> Result<()>;
+
+    /// Ask the terminal to put `text` on the system clipboard
+    fn copy_to_clipboard(&mut self, text: &str) -> Result<()>;
+
+    /// Restore the terminal to its original state
+    fn restore(&mut self, init: &ModelInit, height: u16) -> io::Result<()>;
+
+    /// Hand the terminal back to the shell and stop the process, returning
+    /// once it is continued
+    fn suspend(&mut self, init: &ModelInit, height: u16) -> Result<()>;
+
+    /// Set the terminal up again after the process was continued
+    fn resume(self: Box<Self>, init: &ModelInit, height: u16) -> Result<Box<dyn TerminalBackend>>;
+
+    /// Rows of the terminal window, if they can be queried
+    fn window_rows(&self) -> Option<u16>;
+}
+
+fn clear_and_home_cursor<B: Backend>(terminal: &mut Terminal<B>) -> Result<()> {
+    // Clear the TUI when leaving inline mode, so it doesn't
+    // leave artifacts in the history
+    terminal.draw(view_clear)?;
+    // Move the cursor back to the top left of the TUI,
+    // so if we switch back and forth we don't offset
+    terminal.draw(|f| f.set_cursor_position((f.area().left(), f.area().top())))?;
+    Ok(())
+}
+
+/// Rebuild the inline viewport `height` rows tall from the row it starts on.
+/// Ratatui keeps an inline viewport at the height it was created with, so
+/// the terminal is replaced, writing through a `backend` made from the old.
+fn resize_inline<B: Backend>(
+    terminal: &mut Terminal<B>,
+    height: u16,
+    backend: impl FnOnce(&B) -> B,
+) -> Result<()> {
+    let top = terminal.get_frame().area().top();
+    let mut backend = backend(terminal.backend());
+    // Growing past the bottom of the window scrolls the history above the
+    // viewport up into the scrollback, rather than drawing over it
+    backend.set_cursor_position((0, top))?;
+    let mut resized = Terminal::with_options(
+        backend,
+        TerminalOptions {
+            viewport: Viewport::Inline(height),
+        },
+    )?;
+    // Shrinking leaves the old viewport's last rows under the new one
+    resized.clear()?;
+    *terminal = resized;
+    Ok(())
+}
+
+/// The real crossterm terminal on stdout
+pub struct CrosstermTerminal {
+    terminal: Terminal<CrosstermBackend<io::Stdout>>,
+}
+
+impl CrosstermTerminal {
+    pub fn new(init: &ModelInit, height: u16) -> Result<Self> {
+        Ok(Self {
+            terminal: init_terminal(init, height)?,
+        })
+    }
+
+    pub fn terminal_mut(&mut self) -> &mut Terminal<CrosstermBackend<io::Stdout>> {
+        &mut self.terminal
+    }
+}
+
+impl TerminalBackend for CrosstermTerminal {
+    fn draw(&mut self, model: &Model) -> Result<()> {
+        self.terminal.draw(|f| view(model, f))?;
+        Ok(())
+    }
+
+    fn insert_history(&mut self, model: &Model) -> Result<()> {
+        render_manual_inline_history(model, &mut self.terminal)
+    }
+
+    fn insert_welcome_banner(&mut self, glyphs: &Glyphs) -> Result<()> {
+        render_welcome_banner(glyphs, &mut self.terminal)
+    }
+
+    fn insert_banner_info(&mut self, info: &BannerInfo) -> Result<()> {
+        render_banner_info(info, &mut self.terminal)
+    }
+
+    fn reboot(
+        mut self: Box<Self>,
+        old_init: &ModelInit,
+        new_init: &ModelInit,
+        height: u16,
+    ) -> Result<Box<dyn TerminalBackend>> {
+        if !new_init.inline_mode() {
+            tracing::debug!("Clearing to switch from inline to altscreen");
+            clear_and_home_cursor(&mut self.terminal)?;
+        }
+
+        // Restore and drop the old terminal state before creating new one
+        restore_terminal(old_init, height).wrap_err("Failed to restore terminal")?;
+        drop(self);
+
+        Ok(Box::new(CrosstermTerminal::new(new_init, height)?))
+    }
+
+    fn resize_inline_viewport(&mut self, height: u16) -> Result<()> {
+        resize_inline(&mut self.terminal, height, |_| {
+            CrosstermBackend::new(stdout())
+        })
+    }
+
+    fn autoresize(&mut self) -> Result<()> {
+        self.terminal.autoresize()?;
+        Ok(())
+    }
+
+    fn scroll_past_height(&mut self, line_count: u16) -> Result<()> {
+        // Clear the TUI
+        self.terminal.draw(view_clear)?;
+        execute!(io::stdout(), crossterm::terminal::ScrollUp(line_count))?;
+        Ok(())
+    }
+
+    fn notify(&mut self, text: &str) -> Result<()> {
+        execute!(io::stdout(), Print(notification_sequence(text)))?;
+        Ok(())
+    }
+
+    fn bell(&mut self) -> Result<()> {
+        execute!(io::stdout(), Print("\x07"))?;
+        Ok(())
+    }
+
+    fn copy_to_clipboard(&mut self, text: &str) -> Result<()> {
+        execute!(io::stdout(), Print(clipboard_sequence(text)))?;
+        Ok(())
+    }
+
+    fn restore(&mut self, init: &ModelInit, height: u16) -> io::Result<()> {
+        restore_terminal(init, height)
+    }
+
+    fn suspend(&mut self, init: &ModelInit, height: u16) -> Result<()> {
+        restore_terminal(init, height).wrap_err("Failed to restore terminal")?;
+        suspend_process().wrap_err("Failed to suspend")?;
+        Ok(())
+    }
+
+    fn resume(self: Box<Self>, init: &ModelInit, height: u16) -> Result<Box<dyn TerminalBackend>> {
+        // The shell may have changed any of the modes while we were stopped,
+        // so set everything up from scratch
+        drop(self);
+        Ok(Box::new(CrosstermTerminal::new(init, height)?))
+    }
+
+    fn window_rows(&self) -> Option<u16> {
+        terminal_rows()
+    }
+}
+
+/// In-memory terminal for driving the program loop in tests and replays,
+/// which keeps a copy of every rendered frame
+pub struct HeadlessTerminal {
+    terminal: Terminal<ratatui::backend::TestBackend>,
+    frames: HeadlessFrames,
+    history: HeadlessHistory,
+}
+
+pub type HeadlessFrames = std::rc::Rc<std::cell::RefCell<Vec<ratatui::buffer::Buffer>>>;
+
+/// IDs of the messages inserted into the scrollback, in order
+pub type HeadlessHistory = std::rc::Rc<std::cell::RefCell<Vec<String>>>;
+
+impl HeadlessTerminal {
+    pub fn new(init: &ModelInit, width: u16, height: u16, inline_height: u16) -> Result<Self> {
+        let viewport = if init.inline_mode() {
+            Viewport::Inline(inline_height)
+        } else {
+            Viewport::Fullscreen
+        };
+        let terminal = Terminal::with_options(
+            ratatui::backend::TestBackend::new(width, height),
+            TerminalOptions { viewport },
+        )?;
+        Ok(Self {
+            terminal,
+            frames: HeadlessFrames::default(),
+            history: HeadlessHistory::default(),
+        })
+    }
+
+    /// Shared handle to the rendered frames, which stays valid across reboots
+    pub fn frames(&self) -> HeadlessFrames {
+        self.frames.clone()
+    }
+
+    /// Shared handle to the scrollback history, which stays valid across reboots
+    pub fn history(&self) -> HeadlessHistory {
+        self.history.clone()
+    }
+}
+
+impl TerminalBackend for HeadlessTerminal {
+    fn draw(&mut self, model: &Model) -> Result<()> {
+        let frame = self.terminal.draw(|f| view(model, f))?;
+        self.frames.borrow_mut().push(frame.buffer.clone());
+        Ok(())
+    }
+
+    fn insert_history(&mut self, model: &Model) -> Result<()> {
+        self.history
+            .borrow_mut()
+            .extend(
+                model
+                    .message_containers_for_rendering()
+                    .iter()
+                    .map(|container| match &container.info {
+                        opencode_sdk::models::Message::User(m) => m.id.clone(),
+                        opencode_sdk::models::Message::Assistant(m) => m.id.clone(),
+                    }),
+            );
+        render_manual_inline_history(model, &mut self.terminal)
+    }
+
+    fn insert_welcome_banner(&mut self, glyphs: &Glyphs) -> Result<()> {
+        render_welcome_banner(glyphs, &mut self.terminal)
+    }
+
+    fn insert_banner_info(&mut self, info: &BannerInfo) -> Result<()> {
+        render_banner_info(info, &mut self.terminal)
+    }
+
+    fn reboot(
+        self: Box<Self>,
+        _old_init: &ModelInit,
+        new_init: &ModelInit,
+        height: u16,
+    ) -> Result<Box<dyn TerminalBackend>> {
+        let size = self.terminal.backend().buffer().area;
+        let mut terminal = HeadlessTerminal::new(new_init, size.width, size.height, height)?;
+        terminal.frames = self.frames;
+        terminal.history = self.history;
+        Ok(Box::new(terminal))
+    }
+
+    fn resize_inline_viewport(&mut self, height: u16) -> Result<()> {
+        resize_inline(&mut self.terminal, height, |backend| backend.clone())
+    }
+
+    fn autoresize(&mut self) -> Result<()> {
+        self.terminal.autoresize()?;
+        Ok(())
+    }
+
+    fn scroll_past_height(&mut self, _line_count: u16) -> Result<()> {
+        self.terminal.draw(view_clear)?;
+        Ok(())
+    }
+
+    fn notify(&mut self, _text: &str) -> Result<()> {
+        Ok(())
+    }
+
+    fn bell(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn copy_to_clipboard(&mut self, _text: &str) -> Result<()> {
+        Ok(())
+    }
+
+    fn restore(&mut self, _init: &ModelInit, _height: u16) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn suspend(&mut self, _init: &ModelInit, _height: u16) -> Result<()> {
+        Ok(())
+    }
+
+    fn resume(self: Box<Self>, init: &ModelInit, height: u16) -> Result<Box<dyn TerminalBackend>> {
+        self.reboot(init, init, height)
+    }
+
+    fn window_rows(&self) -> Option<u16> {
+        Some(self.terminal.backend().buffer().area.height)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::{
+        backend::TestBackend, buffer::Buffer, layout::Rect, text::Line, widgets::Widget,
+    };
+
+    fn rows(buffer: &Buffer) -> Vec<String> {
+        let area = buffer.area;
+        (area.top()..area.bottom())
+            .map(|y| {
+                (area.left()..area.right())
+                    .map(|x| buffer[(x, y)].symbol())
+                    .collect::<String>()
+                    .trim_end()
+                    .to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_resizing_inline_keeps_the_history_above() {
+        let mut terminal = Terminal::with_options(
+            TestBackend::new(12, 10),
+            TerminalOptions {
+                viewport: Viewport::Inline(4),
+            },
+        )
+        .unwrap();
+        for i in 0..6 {
+            terminal
+                .insert_before(1, |buf| {
+                    Line::from(format!("history {}", i)).render(buf.area, buf)
+                })
+                .unwrap();
+        }
+        terminal
+            .draw(|f| Line::from("input").render(f.area(), f.buffer_mut()))
+            .unwrap();
+
+        // Growing scrolls the oldest history into the scrollback
+        resize_inline(&mut terminal, 7, |backend| backend.clone()).unwrap();
+        assert_eq!(terminal.get_frame().area(), Rect::new(0, 3, 12, 7));
+        let backend = terminal.backend();
+        assert_eq!(
+            rows(backend.scrollback()),
+            ["history 0", "history 1", "history 2"]
+        );
+        assert_eq!(
+            rows(backend.buffer())[..3],
+            ["history 3", "history 4", "history 5"]
+        );
+
+        // Shrinking leaves it in place and clears what was below
+        terminal
+            .draw(|f| Line::from("input").render(f.area(), f.buffer_mut()))
+            .unwrap();
+        resize_inline(&mut terminal, 4, |backend| backend.clone()).unwrap();
+        assert_eq!(terminal.get_frame().area(), Rect::new(0, 3, 12, 4));
+        let screen = rows(terminal.backend().buffer());
+        assert_eq!(screen[..3], ["history 3", "history 4", "history 5"]);
+        // The test backend's clear spares the cell under the cursor
+        assert!(screen[4..].iter().all(String::is_empty), "{:?}", screen);
+    }
+}
@@ -0,0 +1,167 @@
+//! Estimating a prompt's tokens before it's sent
+//!
+//! A huge paste plus a few attachments can overflow the model's context
+//! window, and the server only says so after the tokens are paid for. The
+//! draft, its attachments and the session so far are estimated locally
+//! instead, from their length: about four characters a token, adjusted for
+//! the model family since their tokenizers differ. It's rough, but it only
+//! has to tell a prompt that fits from one that's several times too big.
+//!
+//! Sending a prompt estimated over the model's context limit asks first.
+
+use crate::app::message_state::MessageState;
+use opencode_sdk::models::{Part, ToolState};
+
+/// Characters a token stands for, before the model's adjustment
+pub const CHARS_PER_TOKEN: f64 = 4.0;
+
+/// Tokens per character relative to `CHARS_PER_TOKEN`, by model id prefix.
+/// The first prefix that matches wins; other models aren't adjusted.
+const MODEL_FACTORS: &[(&str, f64)] = &[
+    ("claude", 1.15),
+    ("gemini", 0.9),
+    ("gpt", 1.0),
+    ("o1", 1.0),
+    ("o3", 1.0),
+    ("o4", 1.0),
+];
+
+/// Messages at the end of the session counted when no reply has reported
+/// its token usage yet
+pub const TRANSCRIPT_TAIL_MESSAGES: usize = 20;
+
+/// Typing pause after which the estimate is updated
+pub const ESTIMATE_DEBOUNCE_MS: u64 = 500;
+
+/// Estimates tokens from text length, for one model
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TokenEstimator {
+    factor: f64,
+}
+
+impl TokenEstimator {
+    pub fn for_model(model_id: &str) -> Self {
+        let factor = MODEL_FACTORS
+            .iter()
+            .find(|(prefix, _)| model_id.starts_with(prefix))
+            .map(|(_, factor)| *factor)
+            .unwrap_or(1.0);
+        Self { factor }
+    }
+
+    pub fn estimate(&self, text: &str) -> usize {
+        self.estimate_chars(text.chars().count())
+    }
+
+    /// Tokens of `chars` characters, also used for a file's size in bytes
+    pub fn estimate_chars(&self, chars: usize) -> usize {
+        (chars as f64 / CHARS_PER_TOKEN * self.factor).ceil() as usize
+    }
+}
+
+/// A prompt's estimated tokens, and the model's context limit if known
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TokenEstimate {
+    pub tokens: usize,
+    pub limit: Option<usize>,
+}
+
+impl TokenEstimate {
+    pub fn exceeds_limit(&self) -> bool {
+        self.limit.is_some_and(|limit| self.tokens > limit)
+    }
+
+    /// The warning shown before sending a prompt over the limit
+    pub fn over_limit_notice(&self) -> Option<String> {
+        let limit = self.limit.filter(|_| self.exceeds_limit())?;
+        Some(format!(
+            "Estimated {} tokens exceeds the {} limit — send anyway?",
+            format_tokens(self.tokens),
+            format_tokens(limit)
+        ))
+    }
+}
+
+/// "850", "12k" or "1.2M"
+pub fn format_tokens(tokens: usize) -> String {
+    match tokens {
+        tokens if tokens < 1_000 => tokens.to_string(),
+        tokens if tokens < 1_000_000 => format!("{}k", (tokens as f64 / 1_000.0).round()),
+        tokens => format!("{:.1}M", tokens as f64 / 1_000_000.0),
+    }
+}
+
+/// Tokens the session already takes up: what the latest reply reported, or
+/// else the text of its last few messages
+pub fn transcript_tokens(state: &MessageState, estimator: &TokenEstimator) -> usize {
+    if let Some(tokens) = state.latest_token_count() {
+        return tokens as usize;
+    }
+    let containers = state.get_all_message_containers();
+    let tail = containers.len().saturating_sub(TRANSCRIPT_TAIL_MESSAGES);
+    let chars: usize = containers[tail..]
+        .iter()
+        .flat_map(|container| container.parts.values())
+        .map(|part| match part {
+            Part::Text(text) => text.text.chars().count(),
+            Part::Tool(tool) => match &*tool.state {
+                ToolState::Completed(completed) => completed.output.chars().count(),
+                _ => 0,
+            },
+            _ => 0,
+        })
+        .sum();
+    estimator.estimate_chars(chars)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimates_of_known_strings() {
+        let plain = TokenEstimator::for_model("llama-3");
+        assert_eq!(plain.estimate(""), 0);
+        assert_eq!(plain.estimate("abcd"), 1);
+        assert_eq!(plain.estimate("hello world"), 3);
+        // Characters, not bytes
+        assert_eq!(plain.estimate("héllo wörld"), 3);
+        assert_eq!(plain.estimate(&"x".repeat(800_000)), 200_000);
+
+        let claude = TokenEstimator::for_model("claude-sonnet-4-20250514");
+        assert_eq!(claude.estimate(&"x".repeat(800_000)), 230_000);
+        let gemini = TokenEstimator::for_model("gemini-2.5-pro");
+        assert_eq!(gemini.estimate(&"x".repeat(800_000)), 180_000);
+        assert_eq!(TokenEstimator::for_model("gpt-4.1"), plain);
+    }
+
+    #[test]
+    fn test_over_limit_notice() {
+        let estimate = TokenEstimate {
+            tokens: 215_000,
+            limit: Some(200_000),
+        };
+        assert!(estimate.exceeds_limit());
+        assert_eq!(
+            estimate.over_limit_notice().as_deref(),
+            Some("Estimated 215k tokens exceeds the 200k limit — send anyway?")
+        );
+
+        let fits = TokenEstimate {
+            tokens: 1_200,
+            limit: Some(200_000),
+        };
+        assert!(!fits.exceeds_limit());
+        assert_eq!(fits.over_limit_notice(), None);
+        // Without a known limit there's nothing to exceed
+        let unknown = TokenEstimate {
+            tokens: 5_000_000,
+            limit: None,
+        };
+        assert!(!unknown.exceeds_limit());
+
+        assert_eq!(format_tokens(850), "850");
+        assert_eq!(format_tokens(1_499), "1k");
+        assert_eq!(format_tokens(1_048_576), "1.0M");
+    }
+}
@@ -1,30 +1,42 @@
-use crate::app::tea_model::AttachedFile;
+use crate::{
+    app::ui_components::glyphs::{Glyphs, UNICODE},
+    sdk::AttachedFile,
+};
 use ratatui::{
     buffer::Buffer,
-    layout::{Constraint, Rect},
+    layout::Rect,
     style::{Color, Modifier, Style},
-    symbols,
     text::{Line, Span},
-    widgets::{Block, Borders, List, ListItem, Widget},
+    widgets::{Block, BorderType, Borders, List, ListItem, Widget},
 };
 
 #[derive(Debug, Clone)]
 pub struct AttachmentDisplay {
     pub files: Vec<AttachedFile>,
+    glyphs: &'static Glyphs,
 }
 
 impl AttachmentDisplay {
     pub fn new(files: Vec<AttachedFile>) -> Self {
-        Self { files }
+        Self {
+            files,
+            glyphs: &UNICODE,
+        }
+    }
+
+    pub fn with_glyphs(mut self, glyphs: &'static Glyphs) -> Self {
+        self.glyphs = glyphs;
+        self
     }
 
     /// Render as a simple inline indicator (e.g., "📎 3 files")
     pub fn render_inline(&self, area: Rect, buf: &mut Buffer) {
+        let glyphs = self.glyphs;
         if !self.files.is_empty() {
             let attachment_text = if self.files.len() == 1 {
-                format!("📎 {} file", self.files.len())
+                format!("{} {} file", glyphs.attachment, self.files.len())
             } else {
-                format!("📎 {} files", self.files.len())
+                format!("{} {} files", glyphs.attachment, self.files.len())
             };
             
             let span = Span::styled(
@@ -39,6 +51,7 @@ impl AttachmentDisplay {
 
     /// Render detailed view showing all attached files
     pub fn render_detailed(&self, area: Rect, buf: &mut Buffer) {
+        let glyphs = self.glyphs;
         if self.files.is_empty() {
             return;
         }
@@ -48,7 +61,10 @@ impl AttachmentDisplay {
             .iter()
             .map(|file| {
                 let spans = vec![
-                    Span::styled("📎 ", Style::default().fg(Color::Cyan)),
+                    Span::styled(
+                        format!("{} ", glyphs.attachment),
+                        Style::default().fg(Color::Cyan),
+                    ),
                     Span::styled(&file.display_name, Style::default().fg(Color::White)),
                 ];
                 ListItem::new(Line::from(spans))
@@ -66,6 +82,7 @@ impl AttachmentDisplay {
                 Block::default()
                     .title(title)
                     .borders(Borders::ALL)
+                    .border_set(glyphs.border_set(BorderType::Plain))
                     .border_style(Style::default().fg(Color::Cyan)),
             )
             .style(Style::default().fg(Color::White));
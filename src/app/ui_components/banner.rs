@@ -1,13 +1,95 @@
+use crate::app::ui_components::glyphs::Glyphs;
+use opencode_sdk::models::App;
 use ratatui::{
     style::{Color, Style},
     text::{Line, Span, Text},
 };
 
+/// One-line tips, one of which is shown under the server info
+pub const TIPS: &[&str] = &[
+    "ctrl+x tab toggles fullscreen",
+    "ctrl+x l switches to another session",
+    "ctrl+x n starts a new session",
+    "@ attaches a file to your message",
+    "ctrl+r expands tool output in fullscreen",
+    "tab cycles through the agent modes",
+];
+
 pub fn welcome_text_height() -> u16 {
     4
 }
 
-pub fn create_welcome_text() -> Text<'static> {
+/// Rows reserved above the inline viewport at startup
+pub fn banner_height(show_banner: bool) -> u16 {
+    if show_banner {
+        welcome_text_height().saturating_add(1)
+    } else {
+        0
+    }
+}
+
+/// Details about the connected server, printed under the banner
+#[derive(Debug, Clone, PartialEq)]
+pub struct BannerInfo {
+    pub server_url: String,
+    pub server_version: Option<String>,
+    pub directory: String,
+    pub provider: String,
+    pub model: String,
+    pub tip: &'static str,
+}
+
+impl BannerInfo {
+    pub fn from_app(
+        app: &App,
+        server_url: &str,
+        server_version: Option<String>,
+        provider: &str,
+        model: &str,
+        tip_seed: usize,
+    ) -> Self {
+        Self {
+            server_url: server_url.to_string(),
+            server_version,
+            directory: app.path.cwd.clone(),
+            provider: provider.to_string(),
+            model: model.to_string(),
+            tip: TIPS[tip_seed % TIPS.len()],
+        }
+    }
+}
+
+pub fn create_server_info_text(info: &BannerInfo) -> Text<'static> {
+    let label = Style::default().fg(Color::DarkGray);
+    let value = Style::default().fg(Color::White);
+
+    let mut server = vec![
+        Span::styled("  server   ", label),
+        Span::styled(info.server_url.clone(), value),
+    ];
+    if let Some(version) = &info.server_version {
+        server.push(Span::styled(format!(" (v{})", version), label));
+    }
+
+    Text::from(vec![
+        Line::from(server),
+        Line::from(vec![
+            Span::styled("  project  ", label),
+            Span::styled(info.directory.clone(), value),
+        ]),
+        Line::from(vec![
+            Span::styled("  model    ", label),
+            Span::styled(format!("{}/{}", info.provider, info.model), value),
+        ]),
+        Line::from(vec![
+            Span::styled("  tip      ", label),
+            Span::styled(info.tip, Style::default().fg(Color::Gray)),
+        ]),
+        Line::from(""),
+    ])
+}
+
+pub fn create_welcome_text(glyphs: &Glyphs) -> Text<'static> {
     #[rustfmt::skip]
     let letters = vec![
         vec!["▄▀▀█",
@@ -64,7 +146,7 @@ pub fn create_welcome_text() -> Text<'static> {
             let color = colors.get(letter_idx).unwrap_or(&Color::White);
             let style = Style::default().fg(*color);
 
-            spans.push(Span::styled(letter[row], style));
+            spans.push(Span::styled(glyphs.blocks(letter[row]), style));
 
             if letter_idx < letters.len() - 1 {
                 spans.push(Span::raw(" "));
@@ -77,3 +159,86 @@ pub fn create_welcome_text() -> Text<'static> {
     lines.push(Line::from(""));
     Text::from(lines)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::ui_components::glyphs::{ASCII, UNICODE};
+    use opencode_sdk::models::{AppPath, AppTime};
+
+    fn app_fixture() -> App {
+        App::new(
+            "devbox".to_string(),
+            true,
+            AppPath::new(
+                "/home/dev/.config/opencode".to_string(),
+                "/home/dev/.local/share/opencode".to_string(),
+                "/home/dev/project".to_string(),
+                "/home/dev/project".to_string(),
+                "/home/dev/.local/state/opencode".to_string(),
+            ),
+            AppTime::new(),
+        )
+    }
+
+    #[test]
+    fn test_server_info_text() {
+        let info = BannerInfo::from_app(
+            &app_fixture(),
+            "http://127.0.0.1:4096",
+            Some("0.3.1".to_string()),
+            "anthropic",
+            "claude-sonnet-4-20250514",
+            TIPS.len() + 1,
+        );
+        assert_eq!(info.tip, TIPS[1]);
+
+        let lines: Vec<String> = create_server_info_text(&info)
+            .lines
+            .iter()
+            .map(|line| line.to_string())
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                "  server   http://127.0.0.1:4096 (v0.3.1)".to_string(),
+                "  project  /home/dev/project".to_string(),
+                "  model    anthropic/claude-sonnet-4-20250514".to_string(),
+                format!("  tip      {}", TIPS[1]),
+                String::new(),
+            ]
+        );
+
+        // An unknown version is left out rather than shown empty
+        let info = BannerInfo {
+            server_version: None,
+            ..info
+        };
+        assert_eq!(
+            create_server_info_text(&info).lines[0].to_string(),
+            "  server   http://127.0.0.1:4096"
+        );
+    }
+
+    #[test]
+    fn test_banner_height() {
+        assert_eq!(banner_height(true), welcome_text_height() + 1);
+        assert_eq!(
+            banner_height(true) as usize,
+            create_welcome_text(&UNICODE).lines.len()
+        );
+        assert_eq!(banner_height(false), 0);
+    }
+
+    #[test]
+    fn test_ascii_logo_keeps_its_shape() {
+        let unicode = create_welcome_text(&UNICODE);
+        let ascii = create_welcome_text(&ASCII);
+        assert_eq!(ascii.lines.len(), unicode.lines.len());
+        for (ascii, unicode) in ascii.lines.iter().zip(&unicode.lines) {
+            assert!(ascii.to_string().is_ascii());
+            assert_eq!(ascii.width(), unicode.width());
+        }
+        assert_eq!(ascii.lines[1].spans[0].content, "_\"\"#");
+    }
+}
@@ -0,0 +1,378 @@
+//! Review of a file change before approving the tool that makes it
+//!
+//! An edit or write tool only says what it wants the file to become. Given
+//! the file as it is now (from `read_file`), this builds the unified diff of
+//! that change and renders it colorized and scrollable, so a permission
+//! prompt for the tool can show what approving it would do.
+//!
+//! Diffs longer than `MAX_DIFF_LINES` are cut, with a note pointing at the
+//! full diff, which `FileChange::unified_diff` gives for a pager.
+
+use crate::app::strings;
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Paragraph, Widget},
+};
+use serde_json::{Map, Value};
+use similar::{ChangeTag, TextDiff};
+
+/// Most diff lines shown in the review, past that the pager takes over
+pub const MAX_DIFF_LINES: usize = 500;
+/// Unchanged lines kept around each hunk
+const CONTEXT_LINES: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffLineKind {
+    Hunk,
+    Context,
+    Added,
+    Removed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffLine {
+    pub kind: DiffLineKind,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileChange {
+    /// A text file, `is_new` when it doesn't exist yet
+    Diff {
+        path: String,
+        is_new: bool,
+        lines: Vec<DiffLine>,
+    },
+    /// The file or its new content isn't text, so there's nothing to show
+    Binary { path: String },
+}
+
+impl FileChange {
+    /// The change `tool` would make to the file it targets, given the file's
+    /// content now (`None` when it doesn't exist). `None` for tools that
+    /// don't modify a file or input missing what they'd write.
+    pub fn from_tool_input(
+        tool: &str,
+        input: &Map<String, Value>,
+        current: Option<&str>,
+    ) -> Option<FileChange> {
+        let text = |key: &str| input.get(key).and_then(Value::as_str);
+        let path = text("filePath")?.to_string();
+
+        let proposed = match (tool, current) {
+            ("write", _) => text("content")?.to_string(),
+            ("edit", None) => text("newString")?.to_string(),
+            ("edit", Some(current)) => {
+                let old = text("oldString")?;
+                let new = text("newString")?;
+                if input.get("replaceAll").and_then(Value::as_bool) == Some(true) {
+                    current.replace(old, new)
+                } else {
+                    current.replacen(old, new, 1)
+                }
+            }
+            _ => return None,
+        };
+
+        if current.is_some_and(is_binary) || is_binary(&proposed) {
+            return Some(FileChange::Binary { path });
+        }
+        Some(FileChange::Diff {
+            path,
+            is_new: current.is_none(),
+            lines: diff_lines(current.unwrap_or(""), &proposed),
+        })
+    }
+
+    pub fn path(&self) -> &str {
+        match self {
+            FileChange::Diff { path, .. } | FileChange::Binary { path } => path,
+        }
+    }
+
+    /// The whole diff as text, the way `diff -u` would print it
+    pub fn unified_diff(&self) -> String {
+        let FileChange::Diff {
+            path,
+            is_new,
+            lines,
+        } = self
+        else {
+            return String::new();
+        };
+        let old_path = if *is_new { "/dev/null" } else { path.as_str() };
+        let mut text = format!("--- {}\n+++ {}\n", old_path, path);
+        for line in lines {
+            let prefix = match line.kind {
+                DiffLineKind::Hunk => "",
+                DiffLineKind::Context => " ",
+                DiffLineKind::Added => "+",
+                DiffLineKind::Removed => "-",
+            };
+            text.push_str(prefix);
+            text.push_str(&line.text);
+            text.push('\n');
+        }
+        text
+    }
+}
+
+/// Text with a NUL in it is taken for binary, as git does
+fn is_binary(content: &str) -> bool {
+    content.contains('\0')
+}
+
+fn diff_lines(old: &str, new: &str) -> Vec<DiffLine> {
+    let diff = TextDiff::from_lines(old, new);
+    let mut lines = Vec::new();
+    for hunk in diff
+        .unified_diff()
+        .context_radius(CONTEXT_LINES)
+        .iter_hunks()
+    {
+        lines.push(DiffLine {
+            kind: DiffLineKind::Hunk,
+            text: hunk.header().to_string(),
+        });
+        for change in hunk.iter_changes() {
+            let kind = match change.tag() {
+                ChangeTag::Equal => DiffLineKind::Context,
+                ChangeTag::Insert => DiffLineKind::Added,
+                ChangeTag::Delete => DiffLineKind::Removed,
+            };
+            lines.push(DiffLine {
+                kind,
+                text: change.value().trim_end_matches(['\n', '\r']).to_string(),
+            });
+        }
+    }
+    lines
+}
+
+/// A file change shown for review, scrolled by line
+#[derive(Debug, Clone)]
+pub struct DiffReview {
+    change: FileChange,
+    scroll: usize,
+}
+
+impl DiffReview {
+    pub fn new(change: FileChange) -> Self {
+        Self { change, scroll: 0 }
+    }
+
+    pub fn change(&self) -> &FileChange {
+        &self.change
+    }
+
+    /// Whether the diff is longer than the review shows
+    pub fn is_truncated(&self) -> bool {
+        matches!(&self.change, FileChange::Diff { lines, .. } if lines.len() > MAX_DIFF_LINES)
+    }
+
+    /// Scroll by `delta` lines, keeping a page of `height` lines in view
+    pub fn scroll_by(&mut self, delta: isize, height: u16) {
+        let max_scroll = self.review_lines().len().saturating_sub(height as usize);
+        self.scroll = self.scroll.saturating_add_signed(delta).min(max_scroll);
+    }
+
+    fn review_lines(&self) -> Vec<Line<'static>> {
+        let (path, is_new, lines) = match &self.change {
+            FileChange::Binary { path } => {
+                return vec![
+                    Line::from(Span::styled(
+                        path.clone(),
+                        Style::default().add_modifier(Modifier::BOLD),
+                    )),
+                    Line::from(Span::styled(
+                        strings::BINARY_FILE_NO_DIFF,
+                        Style::default().fg(Color::Yellow),
+                    )),
+                ];
+            }
+            FileChange::Diff {
+                path,
+                is_new,
+                lines,
+            } => (path, *is_new, lines),
+        };
+
+        let mut title = vec![Span::styled(
+            path.clone(),
+            Style::default().add_modifier(Modifier::BOLD),
+        )];
+        if is_new {
+            title.push(Span::styled(
+                strings::NEW_FILE_MARKER,
+                Style::default().fg(Color::Green),
+            ));
+        }
+        let mut review = vec![Line::from(title)];
+        review.extend(lines.iter().take(MAX_DIFF_LINES).map(|line| {
+            let (prefix, style) = match line.kind {
+                DiffLineKind::Hunk => ("", Style::default().fg(Color::Cyan)),
+                DiffLineKind::Context => (" ", Style::default().fg(Color::Gray)),
+                DiffLineKind::Added => ("+", Style::default().fg(Color::Green)),
+                DiffLineKind::Removed => ("-", Style::default().fg(Color::Red)),
+            };
+            Line::from(Span::styled(format!("{}{}", prefix, line.text), style))
+        }));
+        if lines.len() > MAX_DIFF_LINES {
+            review.push(Line::from(Span::styled(
+                format!(
+                    "… {} {}",
+                    lines.len() - MAX_DIFF_LINES,
+                    strings::DIFF_TRUNCATED
+                ),
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::ITALIC),
+            )));
+        }
+        review
+    }
+}
+
+impl Widget for &DiffReview {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let lines: Vec<Line> = self
+            .review_lines()
+            .into_iter()
+            .skip(self.scroll)
+            .take(area.height as usize)
+            .collect();
+        Paragraph::new(lines).render(area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn input(value: Value) -> Map<String, Value> {
+        value.as_object().unwrap().clone()
+    }
+
+    fn diff_of(change: &FileChange) -> Vec<String> {
+        match change {
+            FileChange::Diff { lines, .. } => lines
+                .iter()
+                .map(|line| match line.kind {
+                    DiffLineKind::Hunk => line.text.clone(),
+                    DiffLineKind::Context => format!(" {}", line.text),
+                    DiffLineKind::Added => format!("+{}", line.text),
+                    DiffLineKind::Removed => format!("-{}", line.text),
+                })
+                .collect(),
+            FileChange::Binary { .. } => panic!("expected a diff"),
+        }
+    }
+
+    #[test]
+    fn test_edit_diffed_against_current_content() {
+        let edit = input(json!({
+            "filePath": "/src/main.rs",
+            "oldString": "println!(\"hi\");",
+            "newString": "println!(\"hello\");",
+        }));
+        let current = "fn main() {\n    println!(\"hi\");\n}\n";
+
+        let change = FileChange::from_tool_input("edit", &edit, Some(current)).unwrap();
+        assert_eq!(change.path(), "/src/main.rs");
+        assert_eq!(
+            diff_of(&change),
+            [
+                "@@ -1,3 +1,3 @@",
+                " fn main() {",
+                "-    println!(\"hi\");",
+                "+    println!(\"hello\");",
+                " }",
+            ]
+        );
+        assert!(change
+            .unified_diff()
+            .starts_with("--- /src/main.rs\n+++ /src/main.rs\n@@"));
+
+        // Only the first match, unless the tool replaces all
+        let twice = "a\nb\na\n";
+        let edit = input(json!({"filePath": "f", "oldString": "a", "newString": "c"}));
+        let change = FileChange::from_tool_input("edit", &edit, Some(twice)).unwrap();
+        assert_eq!(diff_of(&change)[1..], ["-a", "+c", " b", " a"]);
+        let edit = input(json!({
+            "filePath": "f", "oldString": "a", "newString": "c", "replaceAll": true,
+        }));
+        let change = FileChange::from_tool_input("edit", &edit, Some(twice)).unwrap();
+        assert_eq!(diff_of(&change)[1..], ["-a", "+c", " b", "-a", "+c"]);
+    }
+
+    #[test]
+    fn test_new_file_is_all_additions() {
+        let write = input(json!({"filePath": "notes.md", "content": "# Notes\n\nfirst\n"}));
+        let change = FileChange::from_tool_input("write", &write, None).unwrap();
+        assert!(matches!(change, FileChange::Diff { is_new: true, .. }));
+        assert_eq!(
+            diff_of(&change),
+            ["@@ -0,0 +1,3 @@", "+# Notes", "+", "+first"]
+        );
+        assert!(change
+            .unified_diff()
+            .starts_with("--- /dev/null\n+++ notes.md\n"));
+    }
+
+    #[test]
+    fn test_binary_file_skips_the_diff() {
+        let write = input(json!({"filePath": "logo.png", "content": "new"}));
+        let change = FileChange::from_tool_input("write", &write, Some("\u{89}PNG\0\0")).unwrap();
+        assert_eq!(
+            change,
+            FileChange::Binary {
+                path: "logo.png".to_string()
+            }
+        );
+
+        let review = DiffReview::new(change);
+        let mut buf = Buffer::empty(Rect::new(0, 0, 40, 3));
+        (&review).render(buf.area, &mut buf);
+        let row: String = (0..40).map(|x| buf[(x, 1)].symbol()).collect();
+        assert_eq!(row.trim_end(), strings::BINARY_FILE_NO_DIFF);
+    }
+
+    #[test]
+    fn test_tools_that_dont_write_files_have_no_change() {
+        let read = input(json!({"filePath": "src/main.rs"}));
+        assert_eq!(FileChange::from_tool_input("read", &read, Some("")), None);
+        let missing = input(json!({"filePath": "src/main.rs"}));
+        assert_eq!(FileChange::from_tool_input("write", &missing, None), None);
+    }
+
+    #[test]
+    fn test_long_diff_capped_and_scrollable() {
+        let content: String = (0..MAX_DIFF_LINES + 10)
+            .map(|n| format!("line {}\n", n))
+            .collect();
+        let write = input(json!({"filePath": "big.txt", "content": content}));
+        let mut review =
+            DiffReview::new(FileChange::from_tool_input("write", &write, None).unwrap());
+        assert!(review.is_truncated());
+
+        // Title, hunk header, the capped lines and the note
+        let shown = review.review_lines();
+        assert_eq!(shown.len(), MAX_DIFF_LINES + 2);
+        assert!(shown
+            .last()
+            .unwrap()
+            .to_string()
+            .contains(strings::DIFF_TRUNCATED));
+
+        review.scroll_by(-5, 20);
+        assert_eq!(review.scroll, 0);
+        review.scroll_by(30, 20);
+        assert_eq!(review.scroll, 30);
+        review.scroll_by(10_000, 20);
+        assert_eq!(review.scroll, MAX_DIFF_LINES + 2 - 20);
+    }
+}
@@ -0,0 +1,374 @@
+//! Special characters the interface draws with, and their ASCII stand-ins
+//!
+//! Bullets, tree corners, box drawing and the logo's block elements render
+//! as tofu with some fonts, and over SSH into a non-UTF-8 locale, which
+//! wrecks alignment. Every such character is looked up here instead of
+//! written inline. Each ASCII stand-in takes as many columns as the glyph it
+//! replaces, so layouts come out the same either way.
+//!
+//! The set is picked at startup from the locale and `TERM`, unless the
+//! `[ui]` table of the config file sets `glyphs = "unicode"` or `"ascii"`.
+
+use ratatui::{
+    symbols::{border, scrollbar},
+    widgets::BorderType,
+};
+use serde::Deserialize;
+use std::borrow::Cow;
+
+/// Which glyphs to draw with
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GlyphMode {
+    /// Decided from the terminal at startup
+    #[default]
+    Auto,
+    Unicode,
+    Ascii,
+}
+
+impl GlyphMode {
+    /// The mode the terminal can show, from its environment
+    pub fn detect() -> Self {
+        let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+            .iter()
+            .filter_map(|name| std::env::var(name).ok())
+            .find(|value| !value.is_empty());
+        let term = std::env::var("TERM").ok();
+        // Windows consoles are Unicode without setting a locale
+        let unicode = match locale {
+            None if cfg!(windows) => term.as_deref().is_none_or(|term| !is_limited_term(term)),
+            locale => supports_unicode(locale.as_deref(), term.as_deref()),
+        };
+        if unicode {
+            GlyphMode::Unicode
+        } else {
+            GlyphMode::Ascii
+        }
+    }
+
+    pub fn glyphs(self) -> &'static Glyphs {
+        match self {
+            GlyphMode::Ascii => &ASCII,
+            GlyphMode::Auto | GlyphMode::Unicode => &UNICODE,
+        }
+    }
+}
+
+/// Whether a terminal with `locale` and `term` shows more than ASCII: the
+/// locale has to be UTF-8, and the Linux console's font lacks most glyphs
+pub fn supports_unicode(locale: Option<&str>, term: Option<&str>) -> bool {
+    let utf8 = locale.is_some_and(|locale| {
+        let locale = locale.to_ascii_lowercase();
+        locale.contains("utf-8") || locale.contains("utf8")
+    });
+    utf8 && !term.is_some_and(is_limited_term)
+}
+
+fn is_limited_term(term: &str) -> bool {
+    matches!(term, "linux" | "dumb" | "vt100" | "vt102" | "vt220")
+}
+
+/// Every special character drawn, see `UNICODE` and `ASCII`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Glyphs {
+    /// Whether these are the ASCII stand-ins
+    pub ascii: bool,
+    // Tools, todos and agents in replies
+    pub tool: &'static str,
+    pub tool_result: &'static str,
+    pub todo_done: &'static str,
+    pub todo_in_progress: &'static str,
+    pub todo_pending: &'static str,
+    pub agent: &'static str,
+    pub waiting: &'static str,
+    pub image: &'static str,
+    pub attachment: &'static str,
+    pub streaming_cursor: &'static str,
+    // Punctuation
+    pub ellipsis: &'static str,
+    pub dash: &'static str,
+    pub separator: &'static str,
+    pub arrow_up: &'static str,
+    pub arrow_down: &'static str,
+    pub arrow_right: &'static str,
+    pub triangle_down: &'static str,
+    pub plus_minus: &'static str,
+    pub times: &'static str,
+    pub timer: &'static str,
+    // Boxes around tool input and output, errors and attachments
+    pub box_top_left: &'static str,
+    pub box_top_right: &'static str,
+    pub box_bottom_left: &'static str,
+    pub box_bottom_right: &'static str,
+    pub box_horizontal: &'static str,
+    pub box_vertical: &'static str,
+    /// The bar down the left of each message in the log
+    pub gutter: &'static str,
+    // Block elements of the logo
+    pub block_full: &'static str,
+    pub block_upper: &'static str,
+    pub block_lower: &'static str,
+    /// Borders of blocks, `None` to draw them as their `BorderType` says
+    pub border: Option<border::Set>,
+    pub scrollbar_log: scrollbar::Set,
+    pub scrollbar_modal: scrollbar::Set,
+}
+
+pub const UNICODE: Glyphs = Glyphs {
+    ascii: false,
+    tool: "●",
+    tool_result: "⎿",
+    todo_done: "☒",
+    todo_in_progress: "◐",
+    todo_pending: "☐",
+    agent: "⚒",
+    waiting: "⏳",
+    image: "🖼",
+    attachment: "📎",
+    streaming_cursor: "▌",
+    ellipsis: "…",
+    dash: "—",
+    separator: "·",
+    arrow_up: "↑",
+    arrow_down: "↓",
+    arrow_right: "→",
+    triangle_down: "▼",
+    plus_minus: "±",
+    times: "×",
+    timer: "⏱",
+    box_top_left: "┌",
+    box_top_right: "┐",
+    box_bottom_left: "└",
+    box_bottom_right: "┘",
+    box_horizontal: "─",
+    box_vertical: "│",
+    gutter: "┃",
+    block_full: "█",
+    block_upper: "▀",
+    block_lower: "▄",
+    border: None,
+    scrollbar_log: scrollbar::VERTICAL,
+    scrollbar_modal: scrollbar::DOUBLE_VERTICAL,
+};
+
+pub const ASCII: Glyphs = Glyphs {
+    ascii: true,
+    tool: "*",
+    tool_result: "L",
+    todo_done: "x",
+    todo_in_progress: "o",
+    todo_pending: "-",
+    agent: "&",
+    waiting: "..",
+    image: "#",
+    attachment: "@@",
+    streaming_cursor: "_",
+    ellipsis: "~",
+    dash: "-",
+    separator: "-",
+    arrow_up: "^",
+    arrow_down: "v",
+    arrow_right: ">",
+    triangle_down: "v",
+    plus_minus: "~",
+    times: "x",
+    timer: "t",
+    box_top_left: "+",
+    box_top_right: "+",
+    box_bottom_left: "+",
+    box_bottom_right: "+",
+    box_horizontal: "-",
+    box_vertical: "|",
+    gutter: "|",
+    block_full: "#",
+    block_upper: "\"",
+    block_lower: "_",
+    border: Some(border::Set {
+        top_left: "+",
+        top_right: "+",
+        bottom_left: "+",
+        bottom_right: "+",
+        vertical_left: "|",
+        vertical_right: "|",
+        horizontal_top: "-",
+        horizontal_bottom: "-",
+    }),
+    scrollbar_log: scrollbar::Set {
+        track: "|",
+        thumb: "#",
+        begin: "^",
+        end: "v",
+    },
+    scrollbar_modal: scrollbar::Set {
+        track: "|",
+        thumb: "#",
+        begin: "^",
+        end: "v",
+    },
+};
+
+impl Glyphs {
+    /// The characters to draw a block's borders with
+    pub fn border_set(&self, border_type: BorderType) -> border::Set {
+        self.border.unwrap_or(border_type.to_border_set())
+    }
+
+    /// `text` with the punctuation glyphs in it swapped for these, for
+    /// labels and prose written with them
+    pub fn text<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        if !self.ascii || text.is_ascii() {
+            return Cow::Borrowed(text);
+        }
+        let mut swapped = String::with_capacity(text.len());
+        for ch in text.chars() {
+            match ch {
+                '…' => swapped.push_str(self.ellipsis),
+                '—' => swapped.push_str(self.dash),
+                '–' => swapped.push('-'),
+                '·' => swapped.push_str(self.separator),
+                '↑' => swapped.push_str(self.arrow_up),
+                '↓' => swapped.push_str(self.arrow_down),
+                '→' => swapped.push_str(self.arrow_right),
+                '▼' => swapped.push_str(self.triangle_down),
+                '±' => swapped.push_str(self.plus_minus),
+                '×' => swapped.push_str(self.times),
+                '⏱' => swapped.push_str(self.timer),
+                '●' => swapped.push_str(self.tool),
+                _ => swapped.push(ch),
+            }
+        }
+        Cow::Owned(swapped)
+    }
+
+    /// A horizontal rule `width` columns long
+    pub fn rule(&self, width: usize) -> String {
+        self.box_horizontal.repeat(width)
+    }
+
+    /// `text` drawn with the logo's block elements written as `█`, `▀` and
+    /// `▄`, in these glyphs
+    pub fn blocks(&self, text: &str) -> String {
+        text.chars()
+            .map(|ch| match ch {
+                '█' => self.block_full,
+                '▀' => self.block_upper,
+                '▄' => self.block_lower,
+                ' ' => " ",
+                _ => unreachable!("the logo is drawn with block elements only"),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::ui_components::text_width::display_width;
+
+    /// Each glyph with its stand-in
+    fn pairs() -> Vec<(&'static str, &'static str)> {
+        let (u, a) = (UNICODE, ASCII);
+        let mut pairs = vec![
+            (u.tool, a.tool),
+            (u.tool_result, a.tool_result),
+            (u.todo_done, a.todo_done),
+            (u.todo_in_progress, a.todo_in_progress),
+            (u.todo_pending, a.todo_pending),
+            (u.agent, a.agent),
+            (u.waiting, a.waiting),
+            (u.image, a.image),
+            (u.attachment, a.attachment),
+            (u.streaming_cursor, a.streaming_cursor),
+            (u.ellipsis, a.ellipsis),
+            (u.dash, a.dash),
+            (u.separator, a.separator),
+            (u.arrow_up, a.arrow_up),
+            (u.arrow_down, a.arrow_down),
+            (u.arrow_right, a.arrow_right),
+            (u.triangle_down, a.triangle_down),
+            (u.plus_minus, a.plus_minus),
+            (u.times, a.times),
+            (u.box_top_left, a.box_top_left),
+            (u.box_top_right, a.box_top_right),
+            (u.box_bottom_left, a.box_bottom_left),
+            (u.box_bottom_right, a.box_bottom_right),
+            (u.box_horizontal, a.box_horizontal),
+            (u.box_vertical, a.box_vertical),
+            (u.gutter, a.gutter),
+            (u.block_full, a.block_full),
+            (u.block_upper, a.block_upper),
+            (u.block_lower, a.block_lower),
+            (u.timer, a.timer),
+        ];
+        let border = (
+            u.border_set(BorderType::Rounded),
+            a.border_set(BorderType::Rounded),
+        );
+        pairs.extend([
+            (border.0.top_left, border.1.top_left),
+            (border.0.top_right, border.1.top_right),
+            (border.0.bottom_left, border.1.bottom_left),
+            (border.0.bottom_right, border.1.bottom_right),
+            (border.0.vertical_left, border.1.vertical_left),
+            (border.0.vertical_right, border.1.vertical_right),
+            (border.0.horizontal_top, border.1.horizontal_top),
+            (border.0.horizontal_bottom, border.1.horizontal_bottom),
+        ]);
+        for (u, a) in [
+            (u.scrollbar_log, a.scrollbar_log),
+            (u.scrollbar_modal, a.scrollbar_modal),
+        ] {
+            pairs.extend([
+                (u.track, a.track),
+                (u.thumb, a.thumb),
+                (u.begin, a.begin),
+                (u.end, a.end),
+            ]);
+        }
+        pairs
+    }
+
+    #[test]
+    fn test_ascii_stand_ins_are_ascii_and_as_wide() {
+        for (unicode, ascii) in pairs() {
+            assert!(ascii.is_ascii(), "{:?} stands in with {:?}", unicode, ascii);
+            assert_eq!(
+                display_width(unicode),
+                display_width(ascii),
+                "{:?} and {:?} differ in width",
+                unicode,
+                ascii
+            );
+        }
+    }
+
+    #[test]
+    fn test_unicode_needs_a_utf8_locale_and_a_capable_term() {
+        assert!(supports_unicode(
+            Some("en_US.UTF-8"),
+            Some("xterm-256color")
+        ));
+        assert!(supports_unicode(Some("C.utf8"), None));
+        assert!(!supports_unicode(Some("C"), Some("xterm-256color")));
+        assert!(!supports_unicode(Some("POSIX"), None));
+        assert!(!supports_unicode(None, Some("xterm-256color")));
+        assert!(!supports_unicode(Some("en_US.UTF-8"), Some("linux")));
+        assert!(!supports_unicode(Some("en_US.UTF-8"), Some("dumb")));
+    }
+
+    #[test]
+    fn test_modes_pick_their_glyphs() {
+        assert_eq!(GlyphMode::Ascii.glyphs(), &ASCII);
+        assert_eq!(GlyphMode::Unicode.glyphs(), &UNICODE);
+        // Auto is resolved at startup, so only tests see it
+        assert_eq!(GlyphMode::Auto.glyphs(), &UNICODE);
+        assert_eq!(ASCII.blocks("▄▀▀█ "), "_\"\"# ");
+        assert_eq!(UNICODE.blocks("▄▀▀█ "), "▄▀▀█ ");
+        assert_eq!(
+            ASCII.text("↑↓ navigate · sending… ±4 — 80×24"),
+            "^v navigate - sending~ ~4 - 80x24"
+        );
+        assert_eq!(UNICODE.text("sending…"), "sending…");
+    }
+}
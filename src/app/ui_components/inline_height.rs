@@ -0,0 +1,96 @@
+//! Height of the inline viewport
+//!
+//! The viewport starts at the configured height and grows with the text input
+//! as a draft gets longer, up to `max_height` in the `[ui]` table of the config
+//! file or else half the terminal, then shrinks back as the draft does. It
+//! never takes the whole terminal, so a window shrunk below the configured
+//! height shrinks the viewport with it until the window grows back.
+
+use crate::app::tea_model::INLINE_HEIGHT;
+
+/// Rows of the viewport besides the input: the status bar and session header
+pub const INLINE_CHROME_HEIGHT: u16 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InlineHeight {
+    /// Rows with a short input, which the viewport shrinks back to
+    pub base: u16,
+    /// Rows the viewport grows to at most, below the base on a terminal too
+    /// short for it
+    pub max: u16,
+}
+
+impl InlineHeight {
+    /// Growing from `base` to `max_height`, or half of the terminal's rows
+    /// when unset, but always a row short of the terminal
+    pub fn new(base: u16, max_height: Option<u16>, terminal_rows: u16) -> Self {
+        let max = max_height
+            .unwrap_or(terminal_rows / 2)
+            .max(base)
+            .min(terminal_rows.saturating_sub(1).max(1));
+        Self { base, max }
+    }
+
+    /// Viewport rows fitting an input `input_height` rows tall
+    pub fn for_input(&self, input_height: u16) -> u16 {
+        input_height
+            .saturating_add(INLINE_CHROME_HEIGHT)
+            .max(self.base)
+            .min(self.max)
+    }
+
+    /// Tallest the input gets before it scrolls instead
+    pub fn max_input_height(&self) -> u16 {
+        self.max.saturating_sub(INLINE_CHROME_HEIGHT)
+    }
+}
+
+impl Default for InlineHeight {
+    /// Fixed at the default height until the terminal's size is known
+    fn default() -> Self {
+        Self {
+            base: INLINE_HEIGHT,
+            max: INLINE_HEIGHT,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grows_with_the_input_between_base_and_max() {
+        let height = InlineHeight::new(12, None, 50);
+        assert_eq!(height.max, 25);
+        assert_eq!(height.for_input(3), 12);
+        assert_eq!(height.for_input(10), 12);
+        assert_eq!(height.for_input(11), 13);
+        assert_eq!(height.for_input(30), 25);
+        assert_eq!(height.max_input_height(), 23);
+    }
+
+    #[test]
+    fn test_configured_max_stays_within_the_terminal() {
+        assert_eq!(InlineHeight::new(12, Some(20), 50).max, 20);
+        assert_eq!(InlineHeight::new(12, Some(80), 50).max, 49);
+        // Never below the base, so a short terminal just doesn't grow
+        assert_eq!(InlineHeight::new(12, Some(4), 50).max, 12);
+        assert_eq!(InlineHeight::new(8, None, 10).max, 8);
+        assert_eq!(InlineHeight::default().for_input(30), INLINE_HEIGHT);
+    }
+
+    #[test]
+    fn test_terminal_shorter_than_the_base_shrinks_it() {
+        let height = InlineHeight::new(12, None, 10);
+        assert_eq!(height.max, 9);
+        assert_eq!(height.for_input(3), 9);
+        assert_eq!(height.for_input(30), 9);
+        assert_eq!(height.max_input_height(), 7);
+        // Kept at a row however short the terminal gets
+        assert_eq!(InlineHeight::new(12, None, 1).for_input(3), 1);
+        assert_eq!(InlineHeight::new(12, None, 0).for_input(3), 1);
+        // The base is back once the terminal grows
+        assert_eq!(InlineHeight::new(12, None, 40).for_input(3), 12);
+    }
+}
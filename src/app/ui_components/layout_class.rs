@@ -0,0 +1,195 @@
+//! Layout rules for small terminals
+//!
+//! Each frame is classified by its size. Narrow frames drop optional selector
+//! columns and shorten the status bar, short frames keep the input at its
+//! minimum height and leave out the inline session header, and anything below
+//! the hard minimum only shows a notice.
+
+use crate::app::tea_model::UserConfig;
+use ratatui::layout::Rect;
+
+/// Smallest frame the UI draws into
+pub const MIN_WIDTH: u16 = 40;
+pub const MIN_HEIGHT: u16 = 8;
+
+/// Frames narrower than this drop optional columns and labels
+pub const NARROW_WIDTH: u16 = 100;
+
+/// Frames shorter than this keep the text input at its minimum height
+pub const SHORT_HEIGHT: u16 = 12;
+
+/// Terminals shorter than this start inline with a smaller viewport and
+/// without the banner, leaving more of the screen to the scrollback
+pub const SHORT_TERMINAL_HEIGHT: u16 = 30;
+pub const SHORT_INLINE_HEIGHT: u16 = 8;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LayoutClass {
+    pub too_small: bool,
+    pub narrow: bool,
+    pub short: bool,
+}
+
+impl LayoutClass {
+    pub fn from_area(area: Rect) -> Self {
+        Self {
+            too_small: is_too_small(area.width, area.height),
+            narrow: area.width < NARROW_WIDTH,
+            short: area.height < SHORT_HEIGHT,
+        }
+    }
+}
+
+/// Whether a frame this size only gets the placeholder
+pub fn is_too_small(width: u16, height: u16) -> bool {
+    width < MIN_WIDTH || height < MIN_HEIGHT
+}
+
+/// Placeholder drawn instead of the UI when the frame is too small
+pub fn too_small_notice() -> String {
+    format!("terminal too small (need {}×{})", MIN_WIDTH, MIN_HEIGHT)
+}
+
+/// Shrink the inline viewport and skip the banner on a short terminal
+pub fn fit_config_to_terminal(config: &mut UserConfig, terminal_height: u16) {
+    if terminal_height < SHORT_TERMINAL_HEIGHT {
+        config.height = config.height.min(SHORT_INLINE_HEIGHT);
+        config.ui_show_banner = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::{
+        event_msg::Msg,
+        snippets::Snippet,
+        tea_model::{AppModalState, ConnectionStatus, Model, ModelInit, RepeatShortcutKey},
+        tea_update::update,
+        tea_view::view,
+    };
+    use ratatui::{backend::TestBackend, Terminal};
+
+    fn render(model: &Model, width: u16, height: u16) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        let frame = terminal.draw(|f| view(model, f)).unwrap();
+        let area = frame.area;
+        (area.top()..area.bottom())
+            .map(|y| {
+                (area.left()..area.right())
+                    .map(|x| frame.buffer[(x, y)].symbol())
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn fullscreen_model() -> Model {
+        let mut model = Model::new();
+        model.init = ModelInit::new(false);
+        model.state = AppModalState::None;
+        model.connection_status = ConnectionStatus::Connected;
+        model
+    }
+
+    fn with_snippet_selector(mut model: Model) -> Model {
+        let snippet = Snippet::new("review", "Review this diff and {}").unwrap();
+        model.modal_snippet_selector.set_snippets(&[snippet]);
+        model.modal_snippet_selector.modal.show();
+        model.state = AppModalState::ModalSnippetSelect;
+        model
+    }
+
+    #[test]
+    fn test_layout_class_thresholds() {
+        let class = |width, height| LayoutClass::from_area(Rect::new(0, 0, width, height));
+        assert_eq!(class(140, 40), LayoutClass::default());
+        assert!(class(80, 24).narrow);
+        assert!(!class(80, 24).short);
+        assert!(class(80, SHORT_INLINE_HEIGHT).short);
+        assert!(!class(MIN_WIDTH, MIN_HEIGHT).too_small);
+        assert!(class(MIN_WIDTH - 1, 24).too_small);
+        assert!(class(80, MIN_HEIGHT - 1).too_small);
+    }
+
+    #[test]
+    fn test_fit_config_to_short_terminal() {
+        let mut config = Model::new().config;
+        fit_config_to_terminal(&mut config, 50);
+        assert_eq!(config.height, Model::new().config.height);
+        assert!(config.ui_show_banner);
+
+        fit_config_to_terminal(&mut config, 24);
+        assert_eq!(config.height, SHORT_INLINE_HEIGHT);
+        assert!(!config.ui_show_banner);
+    }
+
+    #[test]
+    fn test_too_small_placeholder() {
+        let model = with_snippet_selector(fullscreen_model());
+        let screen = render(&model, 39, 12);
+        assert!(screen.contains("terminal too small"), "{}", screen);
+        assert!(!screen.contains("Insert Snippet"));
+
+        let screen = render(&model, MIN_WIDTH, MIN_HEIGHT);
+        assert!(!screen.contains("terminal too small"), "{}", screen);
+    }
+
+    #[test]
+    fn test_inline_viewport_on_a_tiny_terminal() {
+        let mut model = fullscreen_model();
+        model.init = ModelInit::new(true);
+        model.fit_inline_height_to_terminal(40);
+        update(&mut model, Msg::TerminalResize(80, 4));
+        assert!(model.terminal_too_small);
+
+        let screen = render(&model, 80, model.config.height);
+        assert_eq!(model.config.height, 3);
+        assert!(screen.contains("terminal too small"), "{}", screen);
+    }
+
+    #[test]
+    fn test_narrow_selector_drops_optional_columns() {
+        let model = with_snippet_selector(fullscreen_model());
+
+        let wide = render(&model, 140, 40);
+        assert!(wide.contains("Template"), "{}", wide);
+        assert!(wide.contains("Review this diff"));
+
+        let narrow = render(&model, 80, 24);
+        assert!(narrow.contains("review"), "{}", narrow);
+        assert!(!narrow.contains("Template"));
+        assert!(!narrow.contains("Review this diff"));
+    }
+
+    #[test]
+    fn test_narrow_status_bar_shortens_hints() {
+        let mut model = fullscreen_model();
+        model.set_repeat_shortcut_timeout(RepeatShortcutKey::CtrlC);
+
+        let wide = render(&model, 140, 40);
+        assert!(wide.contains("Ctrl+C again to confirm"), "{}", wide);
+
+        let narrow = render(&model, 60, 24);
+        assert!(narrow.contains("^C again"), "{}", narrow);
+        assert!(!narrow.contains("Ctrl+C again to confirm"));
+    }
+
+    #[test]
+    fn test_short_frame_keeps_input_at_minimum() {
+        let mut model = fullscreen_model();
+        model
+            .text_input_area
+            .set_content("one\ntwo\nthree\nfour\nfive");
+        model.text_input_area.move_cursor_to_end();
+
+        // The input grows with its content when there's room
+        let tall = render(&model, 80, 40);
+        assert!(tall.contains("one") && tall.contains("five"), "{}", tall);
+
+        // A short frame shows a single line, following the cursor
+        let short = render(&model, 80, 10);
+        assert!(short.contains("five"), "{}", short);
+        assert!(!short.contains("one"));
+    }
+}
@@ -0,0 +1,239 @@
+//! The step still running, pinned under the message log
+//!
+//! A step often starts several tools at once, and the transcript only shows
+//! each one as its parts arrive, so it's hard to tell what's still going.
+//! While the session is busy, the tools of the newest unfinished step are
+//! listed under the log with their states. Once the step's finish arrives
+//! the section goes away, leaving the tools to the transcript.
+
+use crate::app::{
+    message_state::{ToolActivity, ToolStatus},
+    strings::LIVE_STEP_TITLE,
+    tea_model::Model,
+    ui_components::{glyphs::Glyphs, modal_activity_log::status_color, RenderCtx},
+};
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Borders, Widget},
+};
+use throbber_widgets_tui::Throbber;
+
+/// Tools listed at most, the latest ones, so the log keeps most of the room
+const MAX_ROWS: usize = 6;
+
+/// Rows the log keeps above the section, below which it isn't shown
+pub const LOG_MIN_HEIGHT: u16 = 8;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LiveStep {
+    tools: Vec<ToolActivity>,
+}
+
+impl LiveStep {
+    /// The running step's tools, None while the session is idle or the step
+    /// hasn't called any
+    pub fn from_model(model: &Model) -> Option<Self> {
+        if model.session_is_idle {
+            return None;
+        }
+        let tools = model.message_state.active_step_tools();
+        (!tools.is_empty()).then_some(Self { tools })
+    }
+
+    /// Rows taken with the border
+    pub fn height(&self) -> u16 {
+        self.tools.len().min(MAX_ROWS) as u16 + 2
+    }
+
+    fn title(&self, glyphs: &Glyphs) -> String {
+        format!(
+            " {} {} {} ",
+            LIVE_STEP_TITLE,
+            glyphs.separator,
+            tool_count(&self.tools)
+        )
+    }
+
+    /// The listed tools, the earliest dropped when there are too many
+    fn shown(&self) -> &[ToolActivity] {
+        &self.tools[self.tools.len().saturating_sub(MAX_ROWS)..]
+    }
+
+    /// One row per listed tool, with a blank where a running tool's spinner
+    /// goes
+    pub fn lines(&self, glyphs: &Glyphs) -> Vec<Line<'static>> {
+        self.shown()
+            .iter()
+            .map(|tool| {
+                let color = status_color(tool.status);
+                let marker = status_marker(tool.status, glyphs);
+                let mut spans = vec![
+                    Span::styled(format!("{} ", marker), Style::default().fg(color)),
+                    Span::styled(tool.tool.clone(), Style::default().fg(Color::White).bold()),
+                ];
+                if !tool.args.is_empty() {
+                    spans.push(Span::styled(
+                        format!(" {}", tool.args),
+                        Style::default().fg(Color::Gray),
+                    ));
+                }
+                Line::from(spans)
+            })
+            .collect()
+    }
+
+    pub fn render_with(&self, ctx: &RenderCtx, area: Rect, buf: &mut Buffer) {
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_set(ctx.border_set())
+            .border_style(Style::default().fg(Color::Blue))
+            .title(self.title(ctx.glyphs).bold());
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        for (row, (tool, line)) in self
+            .shown()
+            .iter()
+            .zip(self.lines(ctx.glyphs))
+            .enumerate()
+            .take(inner.height as usize)
+        {
+            let row_area = Rect {
+                y: inner.y + row as u16,
+                height: 1,
+                ..inner
+            };
+            line.render(row_area, buf);
+            if tool.status == ToolStatus::Running {
+                let throbber = Throbber::default()
+                    .throbber_style(Style::default().fg(status_color(tool.status)));
+                match ctx.glyphs.ascii {
+                    true => throbber.throbber_set(throbber_widgets_tui::ASCII),
+                    false => throbber,
+                }
+                .render(
+                    Rect {
+                        width: 1,
+                        ..row_area
+                    },
+                    buf,
+                );
+            }
+        }
+    }
+}
+
+/// Where the section goes, under the log, if there's room for both
+pub fn split_for_live_step(area: Rect, step: &LiveStep) -> Option<(Rect, Rect)> {
+    let height = step.height();
+    if area.height < LOG_MIN_HEIGHT + height {
+        return None;
+    }
+    let log = Rect {
+        height: area.height - height,
+        ..area
+    };
+    let section = Rect {
+        y: log.bottom(),
+        height,
+        ..area
+    };
+    Some((log, section))
+}
+
+/// Marker before a tool, blank while it runs since a spinner is drawn there
+fn status_marker(status: ToolStatus, glyphs: &Glyphs) -> &'static str {
+    match status {
+        ToolStatus::Pending => glyphs.todo_pending,
+        ToolStatus::Running => " ",
+        ToolStatus::Completed => glyphs.todo_done,
+        ToolStatus::Failed => glyphs.times,
+    }
+}
+
+/// "3 tools, 1 left", counting the ones still pending or running
+fn tool_count(tools: &[ToolActivity]) -> String {
+    let left = tools
+        .iter()
+        .filter(|tool| matches!(tool.status, ToolStatus::Pending | ToolStatus::Running))
+        .count();
+    let noun = match tools.len() {
+        1 => "tool",
+        _ => "tools",
+    };
+    format!("{} {}, {} left", tools.len(), noun, left)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::ui_components::glyphs::ASCII;
+
+    fn tool(part_id: &str, status: ToolStatus, args: &str) -> ToolActivity {
+        ToolActivity {
+            message_id: "msg_01".to_string(),
+            part_id: part_id.to_string(),
+            tool: "bash".to_string(),
+            args: args.to_string(),
+            status,
+            started_ms: None,
+            duration_ms: None,
+        }
+    }
+
+    fn rendered(step: &LiveStep, width: u16) -> Vec<String> {
+        let area = Rect::new(0, 0, width, step.height());
+        let mut buf = Buffer::empty(area);
+        let ctx = RenderCtx {
+            glyphs: &ASCII,
+            ..RenderCtx::default()
+        };
+        step.render_with(&ctx, area, &mut buf);
+        (0..area.height)
+            .map(|y| {
+                (0..area.width)
+                    .map(|x| buf[(x, y)].symbol())
+                    .collect::<String>()
+                    .trim_end()
+                    .to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_lists_the_latest_tools_with_their_states() {
+        let mut tools: Vec<ToolActivity> = (0..7)
+            .map(|n| tool(&format!("prt_{}", n), ToolStatus::Completed, "ls"))
+            .collect();
+        tools.push(tool("prt_7", ToolStatus::Pending, ""));
+        let step = LiveStep { tools };
+        assert_eq!(step.height(), 8);
+
+        let rows = rendered(&step, 40);
+        assert!(
+            rows[0].contains("Current step - 8 tools, 1 left"),
+            "{:?}",
+            rows
+        );
+        // The two earliest are left out
+        assert_eq!(rows.len(), 8);
+        assert!(rows[1].contains(&format!("{} bash ls", ASCII.todo_done)));
+        assert!(rows[6].contains(&format!("{} bash", ASCII.todo_pending)));
+        assert!(!rows[6].contains("ls"));
+    }
+
+    #[test]
+    fn test_section_needs_room_under_the_log() {
+        let step = LiveStep {
+            tools: vec![tool("prt_0", ToolStatus::Running, "cargo test")],
+        };
+        assert_eq!(split_for_live_step(Rect::new(0, 0, 80, 10), &step), None);
+        assert_eq!(
+            split_for_live_step(Rect::new(0, 2, 80, 20), &step),
+            Some((Rect::new(0, 2, 80, 17), Rect::new(0, 19, 80, 3)))
+        );
+    }
+}
@@ -1,48 +1,589 @@
 use crate::app::{
+    links::{LinkPaths, LinkTarget},
     message_state::MessageContainer,
-    ui_components::message_part::{MessageContext, MessageRenderer, VerbosityLevel},
-    view_model_context::ViewModelContext,
+    strings::{
+        JUMP_TO_BOTTOM_KEY, LOG_FIND_HINT, LOG_FIND_MATCH, LOG_FIND_MATCHES, LOG_FIND_NO_MATCHES,
+        LOG_FIND_TYPING_HINT, LOG_SELECTING_TITLE, LOG_TITLE, NOT_SENT_MARKER, SENDING_MARKER,
+        SESSION_ERROR_HINT, UNSEEN_MESSAGE, UNSEEN_MESSAGES,
+    },
+    ui_components::{
+        glyphs::{Glyphs, UNICODE},
+        message_part::{
+            MessageContext, MessageRenderer, VerbosityLevel, DEFAULT_PROMPT_COLLAPSE_LINES,
+        },
+        text_width::{display_width, wrap_line, DEFAULT_TAB_WIDTH},
+        tool_render::ToolRenderPolicies,
+        GutterColors, RenderCtx,
+    },
 };
-use opencode_sdk::models::{Message, Part};
+use opencode_sdk::models::{AssistantMessageError, Message, Part};
 use ratatui::{
     buffer::Buffer,
     layout::{Margin, Rect},
     style::{Color, Style, Stylize},
-    symbols::scrollbar,
     text::{Line, Span, Text},
     widgets::{
         Block, Borders, Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState, StatefulWidget,
         Widget, Wrap,
     },
 };
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use std::sync::Arc;
+use unicode_width::UnicodeWidthChar;
+
+/// Characters of streamed text revealed per frame while catching up
+pub const REVEAL_CHARS_PER_FRAME: usize = 80;
+/// Viewport height assumed when scrolling, since the log isn't told its size
+const MIN_VIEWPORT_HEIGHT: usize = 10;
+/// Columns a code block scrolls sideways per key press
+pub const BLOCK_SCROLL_COLUMNS: i16 = 8;
+/// Lines from the bottom that still count as being at the bottom
+const AT_BOTTOM_SLACK: usize = 2;
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct MessageLog {
-    message_containers: Vec<MessageContainer>,
+    // Shared so that updating one message leaves the others untouched
+    message_containers: Vec<Arc<MessageContainer>>,
     pub vertical_scroll_state: ScrollbarState,
-    pub horizontal_scroll_state: ScrollbarState,
     vertical_scroll: usize,
-    horizontal_scroll: usize,
+    // Columns each code block and expanded tool output is scrolled
+    // sideways, by block key. Prose wraps, so only these scroll.
+    block_scroll: HashMap<String, usize>,
     // Content caching to avoid recalculation
     cached_content_lines: Option<usize>,
-    cached_longest_line: Option<usize>,
     content_dirty: bool,
+    // Auto-scroll requested by updates, applied once per batch of events
+    auto_scroll_pending: bool,
+    // Whether updates scroll to the bottom, which stops while the user reads
+    // further up
+    following: bool,
+    // Messages added since the user scrolled away from the bottom
+    unseen_messages: usize,
+    // Latest error reported for the session, shown inline in the transcript
+    session_error: Option<SessionErrorBlock>,
+    // Whether older history can be loaded above the first message
+    earlier_messages: EarlierMessages,
+    // Messages dropped from memory above the first one, see `drop_earliest`
+    dropped_messages: usize,
+    // Characters shown so far of each streaming text part, by part ID, when
+    // the typewriter reveal is on
+    revealed: HashMap<String, usize>,
+    // Rows of each message as last drawn, so a frame lays out only what's in view
+    row_cache: RowCache,
+    // Text being selected with the keyboard, to copy
+    selection: Option<LogSelection>,
+    // Text being searched for, and where it was found
+    find: Option<LogFind>,
+    // Paths in replies looked up on the server, to show the ones that exist
+    // as links
+    link_paths: LinkPaths,
+    // How much of each tool's output shows in the summary view
+    tool_render: ToolRenderPolicies,
+    // Characters the gutter, tool boxes and markers are drawn with
+    glyphs: &'static Glyphs,
+    // Tool calls opened from the activity log, shown with all their input
+    // and output, by part ID, and long prompts unfolded, by message ID
+    expanded: HashSet<String>,
+    // Prompts longer than this many lines are folded in the summary view
+    prompt_collapse_lines: usize,
+    // Columns between tab stops in messages
+    tab_width: usize,
+    // `provider/model` replies are sent with now, those from another model
+    // are annotated with theirs
+    current_model: Option<String>,
+    // Project root the server reported, commands run elsewhere say where
+    workspace_root: Option<String>,
+}
+
+/// Rows each message took when a frame last laid it out, kept so the next
+/// frame can add them up to place the viewport and lay out only the
+/// messages in view
+#[derive(Debug, Clone, Default)]
+struct RowCache(RefCell<RowCounts>);
+
+/// Two logs showing the same messages are equal whatever they have cached
+impl PartialEq for RowCache {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct RowCounts {
+    /// Wrap width and verbosity the counts hold for
+    layout: Option<(usize, VerbosityLevel)>,
+    by_message: HashMap<String, CountedRows>,
+    /// Messages the latest frame laid out, to count their rows or draw them
+    laid_out: Vec<String>,
+    /// Rows the latest frame had room for
+    view_height: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
+struct CountedRows {
+    /// The container counted, a new one means the message changed
+    container: Arc<MessageContainer>,
+    has_error: bool,
+    rows: usize,
+}
+
+/// Older history of a long session, which loads a page at a time when
+/// scrolling past the top of the log
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EarlierMessages {
+    #[default]
+    None,
+    Available,
+    Loading,
+}
+
+/// Where the log was scrolled to, to come back to after switching sessions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScrollPosition {
+    pub vertical: usize,
+    /// Whether the log was at the bottom, following new messages
+    pub following: bool,
+}
+
+/// Keys of the log's selection mode
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum MsgLogSelection {
+    Move(i16, i16), // rows, columns
+    Mark,           // start the selected region at the cursor, or drop it
+    Copy,           // copy the selection and leave selection mode
+    Exit,
+}
+
+/// A row of the log as drawn and a column of it, in characters
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct LogCursor {
+    pub row: usize,
+    pub column: usize,
+}
+
+/// Text of a row as drawn, to move the cursor over and copy from
+#[derive(Debug, Clone, PartialEq)]
+struct RowText {
+    text: String,
+    /// Characters of gutter and wrap indent before the text of the line
+    start: usize,
+    /// Whether the row carries on the line above it, wrapped at the width
+    wrapped: bool,
+}
+
+/// A cursor over the rows of the log, and the region marked from it. The
+/// rows are taken when selecting starts, at the width and verbosity the log
+/// was last drawn with, so they line up with the rows on screen.
+#[derive(Debug, Clone, PartialEq)]
+struct LogSelection {
+    rows: Vec<RowText>,
+    layout: Option<(usize, VerbosityLevel)>,
+    view_height: usize,
+    cursor: LogCursor,
+    /// Other end of the region, once one is marked
+    anchor: Option<LogCursor>,
+}
+
+impl LogSelection {
+    /// Move the cursor, keeping it on a row and on a character of that row
+    fn move_cursor(&mut self, rows: i16, columns: i16) {
+        let row = self
+            .cursor
+            .row
+            .saturating_add_signed(rows as isize)
+            .min(self.rows.len().saturating_sub(1));
+        let last_column = self
+            .rows
+            .get(row)
+            .map_or(0, |row| row.text.chars().count().saturating_sub(1));
+        let column = self
+            .cursor
+            .column
+            .saturating_add_signed(columns as isize)
+            .min(last_column);
+        self.cursor = LogCursor { row, column };
+    }
+
+    /// First and last character of the region, the cursor alone if none is
+    /// marked
+    fn region(&self) -> (LogCursor, LogCursor) {
+        let anchor = self.anchor.unwrap_or(self.cursor);
+        (anchor.min(self.cursor), anchor.max(self.cursor))
+    }
+
+    /// Characters of `row` to highlight
+    fn highlighted(&self, row: usize) -> Option<Range<usize>> {
+        let (start, end) = self.region();
+        if row < start.row || row > end.row {
+            return None;
+        }
+        let from = if row == start.row { start.column } else { 0 };
+        let to = match row == end.row {
+            true => end.column + 1,
+            false => self.rows.get(row).map_or(0, |row| row.text.chars().count()),
+        };
+        // Even an empty row shows where the region passes
+        Some(from..to.max(from + 1))
+    }
+
+    /// The text of the region, or of the cursor's row if none is marked.
+    /// Rows wrapped from one line are joined back into it, and gutters and
+    /// wrap indents are left out.
+    fn text(&self) -> String {
+        let (start, end) = match self.anchor {
+            Some(_) => self.region(),
+            None => (
+                LogCursor {
+                    row: self.cursor.row,
+                    column: 0,
+                },
+                LogCursor {
+                    row: self.cursor.row,
+                    column: usize::MAX - 1,
+                },
+            ),
+        };
+        let mut text = String::new();
+        for (index, row) in self
+            .rows
+            .iter()
+            .enumerate()
+            .take(end.row + 1)
+            .skip(start.row)
+        {
+            if index > start.row && !row.wrapped {
+                text.push('\n');
+            }
+            let from = match index == start.row {
+                true => start.column.max(row.start),
+                false => row.start,
+            };
+            let to = match index == end.row {
+                true => end.column + 1,
+                false => usize::MAX,
+            };
+            text.extend(row.text.chars().take(to).skip(from));
+        }
+        text
+    }
+
+    /// Reverse the highlighted characters of the rows shown in `area`, from
+    /// row `top` of the log
+    fn render_highlight(&self, area: Rect, top: usize, buf: &mut Buffer) {
+        let style = Style::default().reversed();
+        for row in top..top + area.height as usize {
+            let (Some(text), Some(columns)) = (self.rows.get(row), self.highlighted(row)) else {
+                continue;
+            };
+            let from = column_width(&text.text, columns.start);
+            let to = column_width(&text.text, columns.end);
+            highlight_columns(area, top, row, from..to, style, buf);
+        }
+    }
+}
+
+/// Columns taken by the first `chars` characters of `text`, one each for
+/// any past its end
+fn column_width(text: &str, chars: usize) -> usize {
+    let prefix: String = text.chars().take(chars).collect();
+    display_width(&prefix) + chars.saturating_sub(text.chars().count())
+}
+
+/// Set `style` on `columns` of log row `row`, if it's in view in `area`
+/// from row `top` of the log
+fn highlight_columns(
+    area: Rect,
+    top: usize,
+    row: usize,
+    columns: Range<usize>,
+    style: Style,
+    buf: &mut Buffer,
+) {
+    let Some(y) = row.checked_sub(top).filter(|y| *y < area.height as usize) else {
+        return;
+    };
+    for x in columns.start..columns.end.min(area.width as usize) {
+        buf[(area.x + x as u16, area.y + y as u16)].set_style(style);
+    }
+}
+
+/// Keys of the log's find mode
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum MsgLogFind {
+    Type(char),
+    Backspace,
+    Submit, // jump to the first match, then step through them
+    Next,
+    Previous,
+    Edit, // back to typing the query
+    Exit,
+}
+
+/// Where the query was found, as the columns it covers of each row it runs
+/// across
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogMatch {
+    pub rows: Vec<(usize, Range<usize>)>,
+}
+
+impl LogMatch {
+    /// The row the match starts on
+    pub fn row(&self) -> usize {
+        self.rows.first().map_or(0, |(row, _)| *row)
+    }
+}
+
+/// Text searched for in the log. The query is looked for in each line of
+/// the log before it's wrapped, so a match running across rows is found
+/// too, and the matches are highlighted over the rows as drawn at the width
+/// and verbosity they were found at.
+#[derive(Debug, Clone, PartialEq)]
+struct LogFind {
+    query: String,
+    /// Whether the query is being typed, rather than stepped through
+    typing: bool,
+    matches: Vec<LogMatch>,
+    /// The match jumped to, once one is
+    current: Option<usize>,
+    layout: Option<(usize, VerbosityLevel)>,
+    view_height: usize,
+}
+
+impl LogFind {
+    /// "Message Log · /query · 2/5 · …", with the keys for what's next
+    fn title(&self) -> String {
+        let found = match (self.matches.len(), self.current) {
+            (0, _) => LOG_FIND_NO_MATCHES.to_string(),
+            (count, Some(current)) => format!("{}/{}", current + 1, count),
+            (1, None) => format!("1 {}", LOG_FIND_MATCH),
+            (count, None) => format!("{} {}", count, LOG_FIND_MATCHES),
+        };
+        let hint = match self.typing {
+            true => LOG_FIND_TYPING_HINT,
+            false => LOG_FIND_HINT,
+        };
+        match self.query.is_empty() {
+            true => format!("{} · / · {}", LOG_TITLE, hint),
+            false => format!("{} · /{} · {} · {}", LOG_TITLE, self.query, found, hint),
+        }
+    }
+
+    /// Highlight the matches shown in `area`, the current one apart
+    fn render_highlight(&self, area: Rect, top: usize, buf: &mut Buffer) {
+        let matched = Style::default().fg(Color::Black).bg(Color::Yellow);
+        let current = Style::default().fg(Color::Black).bg(Color::LightCyan);
+        let bottom = top + area.height as usize;
+        for (index, found) in self.matches.iter().enumerate() {
+            let style = match self.current == Some(index) {
+                true => current,
+                false => matched,
+            };
+            for (row, columns) in &found.rows {
+                if (top..bottom).contains(row) {
+                    highlight_columns(area, top, *row, columns.clone(), style, buf);
+                }
+            }
+        }
+    }
+}
+
+/// Matches of `query` in `rows`, ignoring case, with the rows numbered from
+/// `first_row`. Rows wrapped from one line are searched as that line.
+fn matches_in_rows(rows: &[RowText], first_row: usize, query: &str) -> Vec<LogMatch> {
+    let query: Vec<char> = query.chars().map(fold_case).collect();
+    let mut matches = Vec::new();
+    if query.is_empty() {
+        return matches;
+    }
+    // Each character of the line, with the row and columns it's drawn at
+    let mut line: Vec<(char, usize, Range<usize>)> = Vec::new();
+    for (index, row) in rows.iter().enumerate() {
+        if !row.wrapped {
+            find_in_line(&line, &query, &mut matches);
+            line.clear();
+        }
+        let mut column = column_width(&row.text, row.start);
+        for ch in row.text.chars().skip(row.start) {
+            let width = ch.width().unwrap_or(0);
+            line.push((fold_case(ch), first_row + index, column..column + width));
+            column += width;
+        }
+    }
+    find_in_line(&line, &query, &mut matches);
+    matches
+}
+
+fn find_in_line(line: &[(char, usize, Range<usize>)], query: &[char], matches: &mut Vec<LogMatch>) {
+    let mut start = 0;
+    while start + query.len() <= line.len() {
+        let found = &line[start..start + query.len()];
+        if !found.iter().map(|(ch, _, _)| ch).eq(query) {
+            start += 1;
+            continue;
+        }
+        let mut rows: Vec<(usize, Range<usize>)> = Vec::new();
+        for (_, row, columns) in found {
+            match rows.last_mut() {
+                Some((last, covered)) if last == row => covered.end = columns.end,
+                _ => rows.push((*row, columns.clone())),
+            }
+        }
+        matches.push(LogMatch { rows });
+        start += query.len();
+    }
+}
+
+fn fold_case(ch: char) -> char {
+    ch.to_lowercase().next().unwrap_or(ch)
+}
+
+/// An error the server reported for one message of the session, shown after
+/// the assistant message it interrupted
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionErrorBlock {
+    pub message_id: Option<String>,
+    pub name: String,
+    pub message: String,
+}
+
+impl SessionErrorBlock {
+    pub fn new(message_id: Option<String>, error: Option<&AssistantMessageError>) -> Self {
+        let (name, message) = match error {
+            Some(AssistantMessageError::ProviderAuthError(error)) => (
+                "Provider auth error",
+                format!("{}: {}", error.data.provider_id, error.data.message),
+            ),
+            Some(AssistantMessageError::UnknownError(error)) => {
+                ("Error", error.data.message.clone())
+            }
+            Some(AssistantMessageError::MessageOutputLengthError(_)) => (
+                "Output too long",
+                "The response hit the model's output limit".to_string(),
+            ),
+            Some(AssistantMessageError::MessageAbortedError(_)) => {
+                ("Aborted", "The response was aborted".to_string())
+            }
+            None => ("Error", "Unknown session error".to_string()),
+        };
+        Self {
+            message_id,
+            name: name.to_string(),
+            message,
+        }
+    }
+
+    /// Red bordered block with the error and how to recover
+    pub fn lines(&self, glyphs: &Glyphs) -> Vec<Line<'static>> {
+        let border_style = Style::default().fg(Color::Red);
+        let side = format!("  {} ", glyphs.box_vertical);
+        let mut lines = vec![Line::from(vec![
+            Span::styled(
+                format!("  {}{} ", glyphs.box_top_left, glyphs.box_horizontal),
+                border_style,
+            ),
+            Span::styled(self.name.clone(), border_style.bold()),
+        ])];
+        for line in self.message.lines() {
+            lines.push(Line::from(vec![
+                Span::styled(side.clone(), border_style),
+                Span::styled(line.to_string(), Style::default().fg(Color::White)),
+            ]));
+        }
+        lines.push(Line::from(vec![
+            Span::styled(side, border_style),
+            Span::styled(SESSION_ERROR_HINT, Style::default().fg(Color::DarkGray)),
+        ]));
+        lines.push(Line::from(Span::styled(
+            format!("  {}{}", glyphs.box_bottom_left, glyphs.box_horizontal),
+            border_style,
+        )));
+        lines
+    }
+}
+
+/// Drawn down the left of every line of a message, wrapped rows included
+fn gutter_for(glyphs: &Glyphs) -> String {
+    format!("{} ", glyphs.gutter)
+}
+
+/// A line of the log and the color of the gutter before it, if it's part of
+/// a message
+struct LogLine {
+    gutter: Option<Color>,
+    line: Line<'static>,
+}
+
+impl LogLine {
+    /// Separators, error blocks and the like, outside any message
+    fn plain(line: Line<'static>) -> Self {
+        Self { gutter: None, line }
+    }
+
+    fn in_gutter(color: Color, line: Line<'static>) -> Self {
+        Self {
+            gutter: Some(color),
+            line,
+        }
+    }
+}
+
+/// Line under a message the server hasn't echoed yet
+fn provisional_marker(send_failed: bool, glyphs: &Glyphs) -> Line<'static> {
+    match send_failed {
+        true => Line::from(vec![
+            Span::styled(
+                glyphs.text(NOT_SENT_MARKER).into_owned(),
+                Style::default().fg(Color::Red),
+            ),
+            Span::styled(SESSION_ERROR_HINT, Style::default().fg(Color::DarkGray)),
+        ]),
+        false => Line::from(Span::styled(
+            glyphs.text(SENDING_MARKER).into_owned(),
+            Style::default().fg(Color::DarkGray),
+        )),
+    }
 }
 
 // pub fn render_message_log(frame: &mut Frame, rect: Rect, model: &Model) {
 // }
 
+fn container_id(container: &MessageContainer) -> &str {
+    match &container.info {
+        Message::User(user_msg) => &user_msg.id,
+        Message::Assistant(assistant_msg) => &assistant_msg.id,
+    }
+}
+
 impl MessageLog {
     pub fn new() -> Self {
         Self {
             message_containers: Vec::new(),
             vertical_scroll_state: ScrollbarState::default(),
-            horizontal_scroll_state: ScrollbarState::default(),
             vertical_scroll: 0,
-            horizontal_scroll: 0,
+            block_scroll: HashMap::new(),
             cached_content_lines: None,
-            cached_longest_line: None,
             content_dirty: true,
+            auto_scroll_pending: false,
+            following: true,
+            unseen_messages: 0,
+            session_error: None,
+            earlier_messages: EarlierMessages::None,
+            dropped_messages: 0,
+            revealed: HashMap::new(),
+            row_cache: RowCache::default(),
+            selection: None,
+            find: None,
+            link_paths: LinkPaths::default(),
+            tool_render: ToolRenderPolicies::default(),
+            glyphs: &UNICODE,
+            expanded: HashSet::new(),
+            prompt_collapse_lines: DEFAULT_PROMPT_COLLAPSE_LINES,
+            tab_width: DEFAULT_TAB_WIDTH,
+            current_model: None,
+            workspace_root: None,
         }
     }
 
@@ -52,20 +593,19 @@ impl MessageLog {
 
     pub fn scroll_vertical(&mut self, direction: &i16) {
         let content_lines = self.get_total_line_count();
-        // Conservative estimate: assume minimum viewport of 10 lines
-        let min_viewport_height = 10;
-
-        let max_scroll = if content_lines > min_viewport_height {
-            content_lines - min_viewport_height
-        } else {
-            0
-        };
+        let max_scroll = self.max_vertical_scroll();
 
         let new_scroll = (self.vertical_scroll as i16 + direction)
             .max(0)
             .min(max_scroll as i16);
         self.vertical_scroll = new_scroll as usize;
 
+        // Scrolling back down to the bottom picks up following again
+        self.following = self.is_at_bottom();
+        if self.following {
+            self.unseen_messages = 0;
+        }
+
         // Update vertical scroll state with content length
         self.vertical_scroll_state = self
             .vertical_scroll_state
@@ -73,12 +613,10 @@ impl MessageLog {
             .position(self.vertical_scroll);
     }
 
-    pub fn validate_scroll_position(&mut self, viewport_height: u16, viewport_width: u16) {
+    pub fn validate_scroll_position(&mut self, viewport_height: u16) {
         let content_lines = self.get_total_line_count();
-        let longest_line_length = self.calculate_longest_line_length();
 
         let available_height = viewport_height.saturating_sub(2) as usize; // Account for borders
-        let available_width = viewport_width.saturating_sub(2) as usize; // Account for borders
 
         let max_vertical_scroll = if content_lines > available_height {
             content_lines - available_height
@@ -86,79 +624,46 @@ impl MessageLog {
             0
         };
 
-        let max_horizontal_scroll = if longest_line_length > available_width {
-            longest_line_length - available_width
-        } else {
-            0
-        };
-
-        // Constrain current scroll positions to viewport limits
+        // Constrain current scroll position to viewport limits
         self.vertical_scroll = self.vertical_scroll.min(max_vertical_scroll);
-        self.horizontal_scroll = self.horizontal_scroll.min(max_horizontal_scroll);
 
-        // Update scrollbar states with proper content lengths and positions
+        // Update scrollbar state with proper content length and position
         self.vertical_scroll_state = self
             .vertical_scroll_state
             .content_length(content_lines)
             .position(self.vertical_scroll);
-
-        self.horizontal_scroll_state = self
-            .horizontal_scroll_state
-            .content_length(longest_line_length)
-            .position(self.horizontal_scroll);
     }
 
     pub fn refresh_scrollbar_states(&mut self) {
         let content_lines = self.get_total_line_count();
-        let longest_line_length = self.calculate_longest_line_length();
 
-        // Update scrollbar states with current content dimensions
+        // Update scrollbar state with current content dimensions
         self.vertical_scroll_state = self
             .vertical_scroll_state
             .content_length(content_lines)
             .position(self.vertical_scroll);
-
-        self.horizontal_scroll_state = self
-            .horizontal_scroll_state
-            .content_length(longest_line_length)
-            .position(self.horizontal_scroll);
-    }
-
-    pub fn scroll_horizontal(&mut self, direction: i16) {
-        // Conservative estimate: assume minimum viewport of 50 characters
-        let min_viewport_width = 50; // Account for borders
-        let longest_line_length = self.calculate_longest_line_length();
-
-        let max_scroll = if longest_line_length > min_viewport_width {
-            longest_line_length - min_viewport_width
-        } else {
-            0
-        };
-
-        let new_scroll = (self.horizontal_scroll as i16 + direction)
-            .max(0)
-            .min(max_scroll as i16);
-        self.horizontal_scroll = new_scroll as usize;
-
-        // Update horizontal scroll state with content length
-        self.horizontal_scroll_state = self
-            .horizontal_scroll_state
-            .content_length(longest_line_length)
-            .position(self.horizontal_scroll);
     }
 
     pub fn touch_scroll(&mut self) {
         // Sync to bottom, then update the scroll state
         let content_lines = self.get_total_line_count();
         self.vertical_scroll = content_lines.saturating_sub(1).max(0);
-        self.horizontal_scroll = 0;
+        self.following = true;
+        self.unseen_messages = 0;
 
         // Refresh scrollbar states after changing position
         self.refresh_scrollbar_states();
     }
 
     pub fn set_message_containers(&mut self, containers: Vec<MessageContainer>) {
-        self.message_containers = containers;
+        self.message_containers = containers.into_iter().map(Arc::new).collect();
+        self.session_error = None;
+        self.earlier_messages = EarlierMessages::None;
+        self.dropped_messages = 0;
+        self.selection = None;
+        self.find = None;
+        self.expanded.clear();
+        self.block_scroll.clear();
         self.mark_content_dirty();
 
         // Auto-scroll to bottom when new message is added
@@ -166,215 +671,2107 @@ impl MessageLog {
     }
 
     pub fn add_message_container(&mut self, container: MessageContainer) {
-        self.message_containers.push(container);
+        self.message_containers.push(Arc::new(container));
         self.mark_content_dirty();
 
         // Auto-scroll to bottom when new message is added
         self.touch_scroll();
     }
 
-    fn render_message_content(&self, verbosity: VerbosityLevel) -> Text<'static> {
-        let mut lines = Vec::new();
+    pub fn message_containers(&self) -> &[Arc<MessageContainer>] {
+        &self.message_containers
+    }
 
-        for container in &self.message_containers {
-            let role = match &container.info {
-                Message::User(_) => "You",
-                Message::Assistant(_) => "Assistant",
-            };
+    /// Insert a page of earlier messages, keeping the view on the lines that
+    /// were showing before
+    pub fn prepend_message_containers(
+        &mut self,
+        containers: Vec<MessageContainer>,
+        earlier_messages: EarlierMessages,
+    ) {
+        let lines_before = self.get_total_line_count();
+        for container in containers {
+            let index = self
+                .message_containers
+                .binary_search_by(|existing| container_id(existing).cmp(container_id(&container)))
+                .unwrap_or_else(|index| index);
+            self.message_containers.insert(index, Arc::new(container));
+        }
+        self.earlier_messages = earlier_messages;
+        self.mark_content_dirty();
 
-            // Add role header for user messages (simple format)
-            if role == "You" {
-                lines.push(Line::from(vec![Span::styled(
-                    "> ",
-                    Style::default().fg(Color::Gray),
-                )]));
-
-                // Render user message content directly
-                for part_id in &container.part_order {
-                    if let Some(Part::Text(text_part)) = container.parts.get(part_id) {
-                        for line in text_part.text.lines() {
-                            lines.push(Line::from(vec![
-                                Span::styled("> ", Style::default().fg(Color::Gray)),
-                                Span::styled(line.to_string(), Style::default().fg(Color::White)),
-                            ]));
-                        }
-                    }
-                }
-            } else {
-                // Use MessageRenderer for assistant messages
-                let renderer = MessageRenderer::from_message_container(
-                    container,
-                    MessageContext::Fullscreen,
-                    verbosity,
-                );
-                let rendered_text = renderer.render();
-                lines.extend(rendered_text.lines);
-            }
+        let lines_after = self.get_total_line_count();
+        self.vertical_scroll = (self.vertical_scroll + lines_after).saturating_sub(lines_before);
+        self.refresh_scrollbar_states();
+    }
 
-            // Add empty line between messages
-            lines.push(Line::from(""));
-        }
+    /// Drop the oldest messages, which the retention limit took out of
+    /// memory, leaving a marker with how many can be loaded again
+    pub fn drop_earliest(&mut self, message_ids: &[String], dropped_messages: usize) {
+        self.message_containers
+            .retain(|existing| !message_ids.iter().any(|id| id == container_id(existing)));
+        self.earlier_messages = EarlierMessages::Available;
+        self.dropped_messages = dropped_messages;
+        self.mark_content_dirty();
+        self.request_auto_scroll();
+    }
 
-        Text::from(lines)
+    pub fn set_dropped_messages(&mut self, dropped_messages: usize) {
+        if self.dropped_messages != dropped_messages {
+            self.dropped_messages = dropped_messages;
+            self.mark_content_dirty();
+        }
     }
 
-    fn mark_content_dirty(&mut self) {
-        self.content_dirty = true;
-        self.cached_content_lines = None;
-        self.cached_longest_line = None;
+    pub fn earlier_messages(&self) -> EarlierMessages {
+        self.earlier_messages
     }
 
-    fn calculate_content_dimensions(&mut self) -> (usize, usize) {
-        if !self.content_dirty
-            && self.cached_content_lines.is_some()
-            && self.cached_longest_line.is_some()
-        {
-            return (
-                self.cached_content_lines.unwrap(),
-                self.cached_longest_line.unwrap(),
-            );
+    pub fn set_earlier_messages(&mut self, earlier_messages: EarlierMessages) {
+        if self.earlier_messages != earlier_messages {
+            self.earlier_messages = earlier_messages;
+            self.mark_content_dirty();
         }
+    }
 
-        let content = self.render_message_content(VerbosityLevel::Summary);
-        let line_count = content.lines.len();
-        let longest_line_length = content
-            .lines
-            .iter()
-            .map(|line| {
-                line.spans
-                    .iter()
-                    .map(|span| span.content.len())
-                    .sum::<usize>()
-            })
-            .max()
-            .unwrap_or(0);
+    /// Whether updates scroll to the bottom, rather than the user reading
+    /// further up
+    pub fn is_following(&self) -> bool {
+        self.following
+    }
 
-        // Cache the results
-        self.cached_content_lines = Some(line_count);
-        self.cached_longest_line = Some(longest_line_length);
-        self.content_dirty = false;
+    pub fn is_scrolled_to_top(&self) -> bool {
+        self.vertical_scroll == 0
+    }
 
-        (line_count, longest_line_length)
+    /// Whether the view is at, or within a couple of lines of, the bottom
+    pub fn is_at_bottom(&mut self) -> bool {
+        self.vertical_scroll + AT_BOTTOM_SLACK >= self.max_vertical_scroll()
     }
 
-    fn get_total_line_count(&mut self) -> usize {
-        let (line_count, _) = self.calculate_content_dimensions();
-        line_count
+    /// Messages that arrived below while the user was reading further up
+    pub fn unseen_messages(&self) -> usize {
+        self.unseen_messages
     }
 
-    fn calculate_longest_line_length(&mut self) -> usize {
-        let (_, longest_line_length) = self.calculate_content_dimensions();
-        longest_line_length
+    pub fn scroll_position(&mut self) -> ScrollPosition {
+        ScrollPosition {
+            vertical: self.vertical_scroll,
+            following: self.following && self.is_at_bottom(),
+        }
     }
-}
 
-impl Widget for &MessageLog {
-    fn render(self, area: Rect, buf: &mut Buffer) {
-        let model = ViewModelContext::current();
-        let content = self.render_message_content(model.get().verbosity_level);
+    /// Go back to a saved position, clamped to the content as it is now
+    /// since messages may have changed in the meantime
+    pub fn restore_scroll_position(&mut self, position: ScrollPosition) {
+        if position.following {
+            self.touch_scroll();
+            return;
+        }
+        self.vertical_scroll = position.vertical.min(self.max_vertical_scroll());
+        self.auto_scroll_pending = false;
+        self.following = self.is_at_bottom();
+        self.refresh_scrollbar_states();
+    }
 
-        // Always calculate dimensions from the actual content being rendered
-        // This ensures content and scroll state are perfectly synchronized
-        let content_lines = content.lines.len();
-        let longest_line_length = content
-            .lines
-            .iter()
-            .map(|line| {
-                line.spans
-                    .iter()
-                    .map(|span| span.content.len())
-                    .sum::<usize>()
-            })
-            .max()
-            .unwrap_or(0);
+    pub fn is_selecting(&self) -> bool {
+        self.selection.is_some()
+    }
 
-        let vertical_scrollbar_area = area.inner(Margin {
-            vertical: 1,
-            horizontal: 0,
-        });
-        let horizontal_scrollbar_area = area.inner(Margin {
-            vertical: 0,
-            horizontal: 1,
-        });
+    /// Start selecting text, with the cursor on the last row in view. New
+    /// messages don't scroll the log until selecting ends.
+    pub fn start_selection(&mut self) {
+        let (layout, view_height) = self.last_layout();
+        let (wrap_width, verbosity) = match layout {
+            Some((width, verbosity)) => (Some(width), verbosity),
+            None => (None, VerbosityLevel::Summary),
+        };
+        let rows = row_texts(
+            self.log_lines(verbosity, &GutterColors::default(), wrap_width),
+            wrap_width,
+            self.glyphs,
+        );
 
-        // Use current scroll positions directly from the model (no mutation)
-        let constrained_vertical_scroll = {
-            let available_height = area.height.saturating_sub(2) as usize;
-            let max_vertical_scroll = if content_lines > available_height {
-                content_lines - available_height
-            } else {
-                0
-            };
-            self.vertical_scroll.min(max_vertical_scroll)
+        // Drawing clamps the scroll to the content, so start from where it
+        // actually shows
+        self.vertical_scroll = self
+            .vertical_scroll
+            .min(rows.len().saturating_sub(view_height));
+        let cursor = LogCursor {
+            row: (self.vertical_scroll + view_height)
+                .min(rows.len())
+                .saturating_sub(1),
+            column: 0,
         };
+        self.following = false;
+        self.auto_scroll_pending = false;
+        self.selection = Some(LogSelection {
+            rows,
+            layout,
+            view_height,
+            cursor,
+            anchor: None,
+        });
+    }
 
-        let constrained_horizontal_scroll = {
-            let available_width = area.width.saturating_sub(2) as usize;
-            let max_horizontal_scroll = if longest_line_length > available_width {
-                longest_line_length - available_width
-            } else {
-                0
-            };
-            self.horizontal_scroll.min(max_horizontal_scroll)
+    /// Move the selection cursor, scrolling to keep it in view
+    pub fn move_selection(&mut self, rows: i16, columns: i16) {
+        let Some(selection) = self.selection.as_mut() else {
+            return;
         };
+        selection.move_cursor(rows, columns);
+        let row = selection.cursor.row;
+        if row < self.vertical_scroll {
+            self.vertical_scroll = row;
+        } else if row >= self.vertical_scroll + selection.view_height {
+            self.vertical_scroll = row + 1 - selection.view_height;
+        }
+        self.refresh_scrollbar_states();
+    }
 
-        // Create scrollbar states for rendering using fresh content dimensions
-        // This ensures scrollbar state matches the actual content being rendered
-        let mut vertical_scrollbar_state = self
-            .vertical_scroll_state
-            .content_length(content_lines)
-            .position(constrained_vertical_scroll);
+    /// Mark the start of the region at the cursor, or drop the marked one
+    pub fn toggle_selection_mark(&mut self) {
+        if let Some(selection) = self.selection.as_mut() {
+            selection.anchor = match selection.anchor {
+                Some(_) => None,
+                None => Some(selection.cursor),
+            };
+        }
+    }
 
-        let mut horizontal_scrollbar_state = self
-            .horizontal_scroll_state
-            .content_length(longest_line_length)
-            .position(constrained_horizontal_scroll);
+    /// Stop selecting, returning the selected text
+    pub fn end_selection(&mut self) -> Option<String> {
+        let selection = self.selection.take()?;
+        self.following = self.is_at_bottom();
+        Some(selection.text())
+    }
 
-        let paragraph = Paragraph::new(content)
-            .block(
-                Block::default()
-                    .borders(Borders::ALL)
-                    .border_type(model.border_type())
-                    .title("Message Log".bold())
-                    .gray(),
-            )
-            .wrap(Wrap { trim: false })
-            .scroll((
-                constrained_vertical_scroll as u16,
-                constrained_horizontal_scroll as u16,
-            ));
+    /// Wrap width and verbosity the log was last drawn with, and the rows it
+    /// had room for
+    fn last_layout(&self) -> (Option<(usize, VerbosityLevel)>, usize) {
+        let cache = self.row_cache.0.borrow();
+        let view_height = cache.view_height.unwrap_or(MIN_VIEWPORT_HEIGHT).max(1);
+        (cache.layout, view_height)
+    }
 
-        paragraph.render(area, buf);
+    pub fn is_finding(&self) -> bool {
+        self.find.is_some()
+    }
 
-        // Only render vertical scrollbar if content is taller than the available area
-        if content_lines > (area.height.saturating_sub(2)) as usize {
-            let vertical_scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
-                .symbols(scrollbar::VERTICAL)
-                .begin_symbol(None)
-                .end_symbol(None);
+    /// Whether the find query is being typed, rather than stepped through
+    pub fn is_typing_find(&self) -> bool {
+        self.find.as_ref().is_some_and(|find| find.typing)
+    }
 
-            vertical_scrollbar.render(vertical_scrollbar_area, buf, &mut vertical_scrollbar_state);
+    /// Start typing a find query, or go back to editing the last one. New
+    /// messages don't scroll the log until finding ends.
+    pub fn start_find(&mut self) {
+        let (layout, view_height) = self.last_layout();
+        self.following = false;
+        self.auto_scroll_pending = false;
+        match self.find.as_mut() {
+            Some(find) => {
+                find.typing = true;
+                find.current = None;
+            }
+            None => {
+                self.find = Some(LogFind {
+                    query: String::new(),
+                    typing: true,
+                    matches: Vec::new(),
+                    current: None,
+                    layout,
+                    view_height,
+                })
+            }
         }
+    }
 
-        // Only render horizontal scrollbar if content is wider than the available area
-        if longest_line_length > (area.width.saturating_sub(2)) as usize {
-            let horizontal_scrollbar = Scrollbar::new(ScrollbarOrientation::HorizontalBottom)
-                .symbols(scrollbar::HORIZONTAL)
-                .thumb_symbol("🬋")
-                .begin_symbol(None)
-                .end_symbol(None);
+    /// Change the query, highlighting what it matches as it's typed
+    pub fn edit_find_query(&mut self, edit: impl FnOnce(&mut String)) {
+        let Some(find) = self.find.as_mut() else {
+            return;
+        };
+        edit(&mut find.query);
+        let query = find.query.clone();
+        let (layout, view_height) = self.last_layout();
+        let (matches, rows) = self.find_all(layout, &query);
+        // Drawing clamps the scroll to the content, so jump from where it
+        // actually shows
+        self.vertical_scroll = self.vertical_scroll.min(rows.saturating_sub(view_height));
+        if let Some(find) = self.find.as_mut() {
+            find.matches = matches;
+            find.current = None;
+            find.layout = layout;
+            find.view_height = view_height;
+        }
+    }
 
-            horizontal_scrollbar.render(
-                horizontal_scrollbar_area,
-                buf,
-                &mut horizontal_scrollbar_state,
-            );
+    /// Matches of `query` in every block of the log at `layout`, and the rows
+    /// of the log. Each block is searched on its own, its rows placed after
+    /// those of the blocks above it: the earlier marker, each message, then
+    /// the trailing error.
+    fn find_all(
+        &self,
+        layout: Option<(usize, VerbosityLevel)>,
+        query: &str,
+    ) -> (Vec<LogMatch>, usize) {
+        let (wrap_width, verbosity) = match layout {
+            Some((width, verbosity)) => (Some(width), verbosity),
+            None => (None, VerbosityLevel::Summary),
+        };
+        let colors = GutterColors::default();
+        let blocks =
+            std::iter::once(self.earlier_lines())
+                .chain(self.message_containers.iter().map(|container| {
+                    self.container_lines(container, verbosity, &colors, wrap_width)
+                }))
+                .chain([self.unlogged_error_lines()]);
+        let mut matches = Vec::new();
+        let mut first_row = 0;
+        for lines in blocks {
+            let rows = row_texts(lines, wrap_width, self.glyphs);
+            matches.extend(matches_in_rows(&rows, first_row, query));
+            first_row += rows.len();
         }
+        (matches, first_row)
     }
-}
 
-impl Default for MessageLog {
-    fn default() -> Self {
-        Self::new()
+    /// Stop typing and jump to the first match from the top of the view
+    /// down, or the first of all if none is below it
+    pub fn jump_to_first_match(&mut self) {
+        let top = self.vertical_scroll;
+        let Some(find) = self.find.as_mut() else {
+            return;
+        };
+        find.typing = false;
+        find.current = match find.matches.iter().position(|found| found.row() >= top) {
+            Some(index) => Some(index),
+            None if find.matches.is_empty() => None,
+            None => Some(0),
+        };
+        self.scroll_to_current_match();
+    }
+
+    /// Step to the next match, or the previous one, wrapping around the ends
+    pub fn step_find(&mut self, forward: bool) {
+        let Some(find) = self.find.as_mut() else {
+            return;
+        };
+        let count = find.matches.len();
+        if count == 0 {
+            return;
+        }
+        find.current = Some(match (find.current, forward) {
+            (None, true) => 0,
+            (None, false) => count - 1,
+            (Some(current), true) => (current + 1) % count,
+            (Some(current), false) => (current + count - 1) % count,
+        });
+        self.scroll_to_current_match();
+    }
+
+    /// Scroll just far enough to bring the current match into view
+    fn scroll_to_current_match(&mut self) {
+        let Some(find) = self.find.as_ref() else {
+            return;
+        };
+        let Some(found) = find.current.and_then(|current| find.matches.get(current)) else {
+            return;
+        };
+        let (first, last) = (found.row(), found.rows.last().map_or(0, |(row, _)| *row));
+        if first < self.vertical_scroll {
+            self.vertical_scroll = first;
+        } else if last >= self.vertical_scroll + find.view_height {
+            self.vertical_scroll = last + 1 - find.view_height;
+        }
+        self.refresh_scrollbar_states();
+    }
+
+    /// Stop finding, clearing the highlights
+    pub fn end_find(&mut self) {
+        if self.find.take().is_some() {
+            self.following = self.is_at_bottom();
+        }
+    }
+
+    /// Matches of the find query, and the one jumped to
+    pub fn find_matches(&self) -> (&[LogMatch], Option<usize>) {
+        match &self.find {
+            Some(find) => (&find.matches, find.current),
+            None => (&[], None),
+        }
+    }
+
+    /// Show the tool call `part_id` of `message_id` with all of its input
+    /// and output, whatever its policy
+    pub fn expand_tool(&mut self, message_id: &str, part_id: &str) {
+        if self.expanded.insert(part_id.to_string()) {
+            self.row_cache.0.borrow_mut().by_message.remove(message_id);
+            self.mark_content_dirty();
+        }
+    }
+
+    /// Show the prompt `message_id` whole, however long it is
+    pub fn expand_prompt(&mut self, message_id: &str) {
+        if self.expanded.insert(message_id.to_string()) {
+            self.row_cache.0.borrow_mut().by_message.remove(message_id);
+            self.mark_content_dirty();
+        }
+    }
+
+    /// Scroll so the header of the tool call `part_id` of `message_id` is
+    /// the top row in view, returning whether it's in the log. Updates
+    /// don't scroll the log away from it until it's back at the bottom.
+    pub fn jump_to_part(&mut self, message_id: &str, part_id: &str) -> bool {
+        let Some(index) = self
+            .message_containers
+            .iter()
+            .position(|container| container_id(container) == message_id)
+        else {
+            return false;
+        };
+        let (layout, _) = self.last_layout();
+        let (wrap_width, verbosity) = match layout {
+            Some((width, verbosity)) => (Some(width), verbosity),
+            None => (None, VerbosityLevel::Summary),
+        };
+        let colors = GutterColors::default();
+        let rows_of = |container: &MessageContainer| {
+            lay_out(
+                self.container_lines(container, verbosity, &colors, wrap_width),
+                wrap_width,
+                self.glyphs,
+            )
+        };
+
+        // The header is the first row that changes when the tool is renamed,
+        // wherever its step put it among the message's other parts
+        let container = &self.message_containers[index];
+        let Some(Part::Tool(tool_part)) = container.parts.get(part_id) else {
+            return false;
+        };
+        let mut renamed = (**container).clone();
+        let mut renamed_part = tool_part.clone();
+        renamed_part.tool = format!("\u{1}{}", tool_part.tool);
+        renamed
+            .parts
+            .insert(part_id.to_string(), Part::Tool(renamed_part));
+        let rows = rows_of(container);
+        let renamed_rows = rows_of(&renamed);
+        let Some(header) = rows
+            .iter()
+            .zip(&renamed_rows)
+            .position(|(row, renamed_row)| row != renamed_row)
+        else {
+            return false;
+        };
+
+        let above = lay_out(self.earlier_lines(), wrap_width, self.glyphs).len()
+            + self.message_containers[..index]
+                .iter()
+                .map(|container| rows_of(container).len())
+                .sum::<usize>();
+        self.vertical_scroll = above + header;
+        self.following = false;
+        self.auto_scroll_pending = false;
+        self.refresh_scrollbar_states();
+        true
+    }
+
+    /// Scroll a code block or expanded tool output `columns` sideways: the
+    /// one under the selection cursor, or else the lowest one in view.
+    /// Returns whether there was one to scroll.
+    pub fn scroll_block(&mut self, columns: i16) -> bool {
+        let (layout, view_height) = self.last_layout();
+        let Some((wrap_width, verbosity)) = layout else {
+            return false;
+        };
+        let colors = GutterColors::default();
+        let rows_of = |container: &MessageContainer, block_scroll: &HashMap<String, usize>| {
+            lay_out(
+                self.container_lines_scrolled(
+                    container,
+                    verbosity,
+                    &colors,
+                    Some(wrap_width),
+                    block_scroll,
+                ),
+                Some(wrap_width),
+                self.glyphs,
+            )
+        };
+
+        // Each block's rows are the ones that change when it's scrolled
+        let mut blocks = Vec::new();
+        let mut top = lay_out(self.earlier_lines(), Some(wrap_width), self.glyphs).len();
+        for container in &self.message_containers {
+            let rows = rows_of(container, &self.block_scroll);
+            if let Message::Assistant(_) = &container.info {
+                let renderer = self.assistant_renderer(
+                    container,
+                    verbosity,
+                    Some(wrap_width),
+                    &self.block_scroll,
+                );
+                for block in renderer.scroll_blocks() {
+                    let mut nudged = self.block_scroll.clone();
+                    let offset = nudged.entry(block.key.clone()).or_default();
+                    *offset = match *offset {
+                        0 => 1,
+                        _ => 0,
+                    };
+                    let nudged_rows = rows_of(container, &nudged);
+                    let mut changed = rows
+                        .iter()
+                        .zip(&nudged_rows)
+                        .enumerate()
+                        .filter(|(_, (row, nudged_row))| row != nudged_row)
+                        .map(|(index, _)| top + index);
+                    if let Some(first) = changed.next() {
+                        let last = changed.last().unwrap_or(first);
+                        blocks.push((first..last + 1, block));
+                    }
+                }
+            }
+            top += rows.len();
+        }
+
+        let view_top = self.vertical_scroll.min(top.saturating_sub(view_height));
+        let target = match &self.selection {
+            Some(selection) => selection.cursor.row,
+            None => (view_top + view_height).min(top).saturating_sub(1),
+        };
+        let Some((_, block)) = blocks
+            .iter()
+            .find(|(rows, _)| rows.contains(&target))
+            .or_else(|| {
+                blocks
+                    .iter()
+                    .rev()
+                    .find(|(rows, _)| rows.start <= target && rows.end > view_top)
+            })
+        else {
+            return false;
+        };
+
+        let offset = self
+            .block_scroll
+            .get(&block.key)
+            .copied()
+            .unwrap_or(0)
+            .min(block.max_offset())
+            .saturating_add_signed(columns as isize)
+            .min(block.max_offset());
+        match offset {
+            0 => self.block_scroll.remove(&block.key),
+            _ => self.block_scroll.insert(block.key.clone(), offset),
+        };
+        // The selection copies from the block where it's scrolled to now
+        if self.selection.is_some() {
+            let rows = row_texts(
+                self.log_lines(verbosity, &colors, Some(wrap_width)),
+                Some(wrap_width),
+                self.glyphs,
+            );
+            if let Some(selection) = self.selection.as_mut() {
+                selection.rows = rows;
+            }
+        }
+        true
+    }
+
+    /// Fold tool output by these policies, laying every message out again
+    pub fn set_tool_render(&mut self, policies: ToolRenderPolicies) {
+        self.tool_render = policies;
+        *self.row_cache.0.borrow_mut() = RowCounts::default();
+    }
+
+    /// Draw messages with `glyphs`, laying every message out again
+    pub fn set_glyphs(&mut self, glyphs: &'static Glyphs) {
+        self.glyphs = glyphs;
+        *self.row_cache.0.borrow_mut() = RowCounts::default();
+    }
+
+    /// Fold prompts longer than `lines` lines, laying every message out again
+    pub fn set_prompt_collapse_lines(&mut self, lines: usize) {
+        self.prompt_collapse_lines = lines;
+        *self.row_cache.0.borrow_mut() = RowCounts::default();
+    }
+
+    /// Expand tabs to stops every `tab_width` columns, laying every message
+    /// out again
+    pub fn set_tab_width(&mut self, tab_width: usize) {
+        self.tab_width = tab_width;
+        *self.row_cache.0.borrow_mut() = RowCounts::default();
+    }
+
+    /// Annotate replies not from `current_model`, laying every message out
+    /// again when it changed
+    pub fn set_current_model(&mut self, current_model: String) {
+        if self.current_model.as_ref() != Some(&current_model) {
+            self.current_model = Some(current_model);
+            *self.row_cache.0.borrow_mut() = RowCounts::default();
+            self.mark_content_dirty();
+        }
+    }
+
+    /// Note where commands ran relative to `root`, laying every message out
+    /// again when it changed
+    pub fn set_workspace_root(&mut self, root: Option<String>) {
+        if self.workspace_root != root {
+            self.workspace_root = root;
+            *self.row_cache.0.borrow_mut() = RowCounts::default();
+            self.mark_content_dirty();
+        }
+    }
+
+    pub fn link_paths(&self) -> &LinkPaths {
+        &self.link_paths
+    }
+
+    /// Paths of `links` to look up, see `LinkPaths::start_checking`
+    pub fn start_checking_link_paths(&mut self, links: &[LinkTarget]) -> Vec<String> {
+        self.link_paths.start_checking(links)
+    }
+
+    pub fn record_link_paths(&mut self, results: Vec<(String, Option<bool>)>) {
+        self.link_paths.record(results);
+    }
+
+    fn max_vertical_scroll(&mut self) -> usize {
+        self.get_total_line_count()
+            .saturating_sub(MIN_VIEWPORT_HEIGHT)
+    }
+
+    /// Scroll to the bottom on the next flush, unless the user is reading
+    /// further up
+    fn request_auto_scroll(&mut self) {
+        if self.following {
+            self.auto_scroll_pending = true;
+        }
+    }
+
+    /// Replace or insert a single message, keeping the log ordered by message
+    /// ID like MessageState. Only the changed container is cloned, and the
+    /// auto-scroll waits for `flush_auto_scroll`.
+    pub fn apply_update(&mut self, message_id: &str, container: &MessageContainer) {
+        let container = Arc::new(container.clone());
+        match self
+            .message_containers
+            .binary_search_by(|existing| container_id(existing).cmp(message_id))
+        {
+            Ok(index) => self.message_containers[index] = container,
+            Err(index) => {
+                self.message_containers.insert(index, container);
+                if !self.following {
+                    self.unseen_messages += 1;
+                }
+            }
+        }
+        self.mark_content_dirty();
+        self.request_auto_scroll();
+    }
+
+    pub fn remove_message(&mut self, message_id: &str) {
+        self.message_containers
+            .retain(|existing| container_id(existing) != message_id);
+        self.mark_content_dirty();
+        self.request_auto_scroll();
+    }
+
+    /// ID of the newest assistant message, which a session error belongs to
+    pub fn last_assistant_message_id(&self) -> Option<String> {
+        self.message_containers
+            .iter()
+            .rev()
+            .find(|container| matches!(container.info, Message::Assistant(_)))
+            .map(|container| container_id(container).to_string())
+    }
+
+    pub fn session_error(&self) -> Option<&SessionErrorBlock> {
+        self.session_error.as_ref()
+    }
+
+    pub fn set_session_error(&mut self, error: Option<SessionErrorBlock>) {
+        if self.session_error != error {
+            self.session_error = error;
+            self.mark_content_dirty();
+            self.request_auto_scroll();
+        }
+    }
+
+    /// Reveal the next few characters of each streaming text part. Parts
+    /// drop out once they or their message complete, showing them whole.
+    pub fn advance_reveal(&mut self) {
+        let revealed: HashMap<String, usize> = streaming_text_parts(&self.message_containers)
+            .map(|(id, len)| {
+                let shown = self.revealed.get(id).copied().unwrap_or(0);
+                (id.to_string(), reveal_step(shown, len))
+            })
+            .collect();
+        if revealed != self.revealed {
+            self.revealed = revealed;
+            self.mark_content_dirty();
+            self.request_auto_scroll();
+        }
+    }
+
+    /// Whether some streamed text is still waiting to be revealed
+    pub fn is_revealing(&self) -> bool {
+        streaming_text_parts(&self.message_containers)
+            .any(|(id, len)| self.revealed.get(id).is_some_and(|shown| *shown < len))
+    }
+
+    /// Apply any auto-scroll requested since the last flush
+    pub fn flush_auto_scroll(&mut self) {
+        if self.auto_scroll_pending {
+            self.auto_scroll_pending = false;
+            self.touch_scroll();
+        }
+    }
+
+    fn render_message_content(&self, verbosity: VerbosityLevel) -> Text<'static> {
+        Text::from(lay_out(
+            self.log_lines(verbosity, &GutterColors::default(), None),
+            None,
+            self.glyphs,
+        ))
+    }
+
+    fn log_lines(
+        &self,
+        verbosity: VerbosityLevel,
+        colors: &GutterColors,
+        wrap_width: Option<usize>,
+    ) -> Vec<LogLine> {
+        let mut lines = self.earlier_lines();
+        for container in &self.message_containers {
+            lines.extend(self.container_lines(container, verbosity, colors, wrap_width));
+        }
+        lines.extend(self.unlogged_error_lines());
+        lines
+    }
+
+    /// The marker for older history above the first message
+    fn earlier_lines(&self) -> Vec<LogLine> {
+        let mut lines = Vec::new();
+        let earlier = match self.earlier_messages {
+            EarlierMessages::None => None,
+            EarlierMessages::Available if self.dropped_messages > 0 => Some(format!(
+                "{} {} earlier messages {} ctrl+x L to load",
+                self.glyphs.ellipsis, self.dropped_messages, self.glyphs.dash
+            )),
+            EarlierMessages::Available => Some(format!(
+                "{rule} load earlier messages {rule}",
+                rule = self.glyphs.rule(2)
+            )),
+            EarlierMessages::Loading => Some(format!(
+                "{rule} loading earlier messages... {rule}",
+                rule = self.glyphs.rule(2)
+            )),
+        };
+        if let Some(earlier) = earlier {
+            lines.push(LogLine::plain(Line::from(Span::styled(
+                earlier,
+                Style::default().fg(Color::DarkGray),
+            ))));
+            lines.push(LogLine::plain(Line::from("")));
+        }
+        lines
+    }
+
+    /// The session error reported for `container`'s message
+    fn error_for(&self, container: &MessageContainer) -> Option<&SessionErrorBlock> {
+        self.session_error
+            .as_ref()
+            .filter(|error| error.message_id.as_deref() == Some(container_id(container)))
+    }
+
+    /// A message in its gutter, the blank line after it, and its error if
+    /// it ended in one, laid out for rows `wrap_width` wide if given
+    fn container_lines(
+        &self,
+        container: &MessageContainer,
+        verbosity: VerbosityLevel,
+        colors: &GutterColors,
+        wrap_width: Option<usize>,
+    ) -> Vec<LogLine> {
+        self.container_lines_scrolled(container, verbosity, colors, wrap_width, &self.block_scroll)
+    }
+
+    /// Renders an assistant message for rows `wrap_width` wide if given,
+    /// its code blocks scrolled sideways by `block_scroll`
+    fn assistant_renderer(
+        &self,
+        container: &MessageContainer,
+        verbosity: VerbosityLevel,
+        wrap_width: Option<usize>,
+        block_scroll: &HashMap<String, usize>,
+    ) -> MessageRenderer {
+        let mut renderer = MessageRenderer::from_message_container(
+            container,
+            MessageContext::Fullscreen,
+            verbosity,
+        )
+        .with_activity_indent()
+        .with_links(&self.link_paths)
+        .with_tool_render(&self.tool_render)
+        .with_glyphs(self.glyphs)
+        .with_tab_width(self.tab_width)
+        .with_expanded(&self.expanded)
+        .with_workspace_root(self.workspace_root.as_deref())
+        .with_block_scroll(self.message_width(wrap_width), block_scroll);
+        if let Some(current_model) = &self.current_model {
+            renderer = renderer.with_model_annotation(&container.info, current_model);
+        }
+        if container.is_streaming {
+            renderer = renderer.with_streaming(&self.revealed);
+        }
+        renderer
+    }
+
+    /// Columns for a message's text in rows `wrap_width` wide, past its gutter
+    fn message_width(&self, wrap_width: Option<usize>) -> Option<usize> {
+        wrap_width.map(|width| width.saturating_sub(display_width(&gutter_for(self.glyphs))))
+    }
+
+    /// `container_lines`, with code blocks scrolled by `block_scroll`
+    fn container_lines_scrolled(
+        &self,
+        container: &MessageContainer,
+        verbosity: VerbosityLevel,
+        colors: &GutterColors,
+        wrap_width: Option<usize>,
+        block_scroll: &HashMap<String, usize>,
+    ) -> Vec<LogLine> {
+        let mut lines = Vec::new();
+        let error = self.error_for(container);
+        let gutter = match &container.info {
+            _ if container.send_failed || error.is_some() => colors.error,
+            Message::User(_) => colors.user,
+            Message::Assistant(_) => colors.assistant,
+        };
+
+        let mut message_lines = Vec::new();
+        if let Message::User(_) = &container.info {
+            // A failed send is red until it's retried
+            let text_color = match container.send_failed {
+                true => Color::Red,
+                false => Color::White,
+            };
+            message_lines.push(Line::from(""));
+            let renderer = MessageRenderer::from_message_container(
+                container,
+                MessageContext::Fullscreen,
+                verbosity,
+            )
+            .with_glyphs(self.glyphs)
+            .with_expanded(&self.expanded)
+            .with_prompt_collapse(self.prompt_collapse_lines);
+            message_lines.extend(renderer.render_user_parts(text_color));
+            if container.provisional {
+                message_lines.push(provisional_marker(container.send_failed, self.glyphs));
+            }
+        } else {
+            let renderer = self.assistant_renderer(container, verbosity, wrap_width, block_scroll);
+            message_lines.extend(
+                renderer
+                    .render_to_width(self.message_width(wrap_width))
+                    .lines,
+            );
+        }
+        lines.extend(
+            message_lines
+                .into_iter()
+                .map(|line| LogLine::in_gutter(gutter, line)),
+        );
+
+        // Add empty line between messages
+        lines.push(LogLine::plain(Line::from("")));
+
+        if let Some(error) = error {
+            lines.extend(error.lines(self.glyphs).into_iter().map(LogLine::plain));
+            lines.push(LogLine::plain(Line::from("")));
+        }
+        lines
+    }
+
+    /// An error without a logged message, which goes at the end
+    fn unlogged_error_lines(&self) -> Vec<LogLine> {
+        let mut lines = Vec::new();
+        if let Some(error) = &self.session_error {
+            let logged = self
+                .message_containers
+                .iter()
+                .any(|container| error.message_id.as_deref() == Some(container_id(container)));
+            if !logged {
+                lines.extend(error.lines(self.glyphs).into_iter().map(LogLine::plain));
+                lines.push(LogLine::plain(Line::from("")));
+            }
+        }
+
+        lines
+    }
+
+    fn mark_content_dirty(&mut self) {
+        self.content_dirty = true;
+        self.cached_content_lines = None;
+    }
+
+    fn get_total_line_count(&mut self) -> usize {
+        if let (false, Some(line_count)) = (self.content_dirty, self.cached_content_lines) {
+            return line_count;
+        }
+
+        let content = self.render_message_content(VerbosityLevel::Summary);
+        let line_count = content.lines.len();
+
+        // Cache the result
+        self.cached_content_lines = Some(line_count);
+        self.content_dirty = false;
+
+        line_count
+    }
+}
+
+/// Lines as drawn, with their gutters, each wrapped to `wrap_width` columns
+/// if given so the gutter runs down every row
+fn lay_out(lines: Vec<LogLine>, wrap_width: Option<usize>, glyphs: &Glyphs) -> Vec<Line<'static>> {
+    lines
+        .into_iter()
+        .flat_map(|line| wrap_in_gutter(line, wrap_width, glyphs))
+        .collect()
+}
+
+/// The rows `line` is drawn as
+fn wrap_in_gutter(
+    LogLine { gutter, line }: LogLine,
+    wrap_width: Option<usize>,
+    glyphs: &Glyphs,
+) -> Vec<Line<'static>> {
+    let gutter_text = gutter_for(glyphs);
+    let gutter_width = gutter.map_or(0, |_| display_width(&gutter_text));
+    let wrapped = match wrap_width {
+        Some(width) => wrap_line(line, width.saturating_sub(gutter_width)),
+        None => vec![line],
+    };
+    wrapped
+        .into_iter()
+        .map(|row| match gutter {
+            Some(color) => {
+                let mut spans = vec![Span::styled(
+                    gutter_text.clone(),
+                    Style::default().fg(color),
+                )];
+                spans.extend(row.spans);
+                Line::from(spans).style(row.style)
+            }
+            None => row,
+        })
+        .collect()
+}
+
+/// Text of the rows `lay_out` draws
+fn row_texts(lines: Vec<LogLine>, wrap_width: Option<usize>, glyphs: &Glyphs) -> Vec<RowText> {
+    let mut rows = Vec::new();
+    for line in lines {
+        let gutter = line
+            .gutter
+            .map_or(0, |_| gutter_for(glyphs).chars().count());
+        for (index, row) in wrap_in_gutter(line, wrap_width, glyphs).iter().enumerate() {
+            let text = row.to_string();
+            let indent = match index {
+                0 => 0,
+                _ => text.chars().skip(gutter).take_while(|c| *c == ' ').count(),
+            };
+            rows.push(RowText {
+                start: gutter + indent,
+                wrapped: index > 0,
+                text,
+            });
+        }
+    }
+    rows
+}
+
+/// Characters shown after one more frame of revealing `len` characters
+fn reveal_step(shown: usize, len: usize) -> usize {
+    shown.saturating_add(REVEAL_CHARS_PER_FRAME).min(len)
+}
+
+/// ID and length in characters of each text part still streaming in
+fn streaming_text_parts(
+    containers: &[Arc<MessageContainer>],
+) -> impl Iterator<Item = (&str, usize)> {
+    containers
+        .iter()
+        .filter(|container| container.is_streaming)
+        .flat_map(|container| container.parts.values())
+        .filter_map(|part| match part {
+            Part::Text(text_part)
+                if text_part.time.as_ref().and_then(|time| time.end).is_none() =>
+            {
+                Some((text_part.id.as_str(), text_part.text.chars().count()))
+            }
+            _ => None,
+        })
+}
+
+impl Widget for &MessageLog {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        self.render_with(&RenderCtx::default(), area, buf);
+    }
+}
+
+impl MessageLog {
+    /// Rows of every message at this wrap width, from the row cache where
+    /// the message hasn't changed, laying out the rest
+    fn message_rows(&self, ctx: &RenderCtx, content_width: usize) -> Vec<usize> {
+        let mut cache = self.row_cache.0.borrow_mut();
+        let layout = Some((content_width, ctx.verbosity));
+        if cache.layout != layout {
+            *cache = RowCounts {
+                layout,
+                ..RowCounts::default()
+            };
+        }
+        cache.laid_out.clear();
+
+        let counts = self
+            .message_containers
+            .iter()
+            .map(|container| {
+                let id = container_id(container);
+                let has_error = self.error_for(container).is_some();
+                if let Some(counted) = cache.by_message.get(id) {
+                    if Arc::ptr_eq(&counted.container, container) && counted.has_error == has_error
+                    {
+                        return counted.rows;
+                    }
+                }
+                let rows = lay_out(
+                    self.container_lines(
+                        container,
+                        ctx.verbosity,
+                        &ctx.gutter_colors,
+                        Some(content_width),
+                    ),
+                    Some(content_width),
+                    self.glyphs,
+                );
+                cache.laid_out.push(id.to_string());
+                // Streaming text is revealed a little more every frame
+                if !container.is_streaming {
+                    let counted = CountedRows {
+                        container: container.clone(),
+                        has_error,
+                        rows: rows.len(),
+                    };
+                    cache.by_message.insert(id.to_string(), counted);
+                }
+                rows.len()
+            })
+            .collect();
+
+        if cache.by_message.len() > self.message_containers.len() {
+            let ids: HashSet<&str> = self
+                .message_containers
+                .iter()
+                .map(|c| container_id(c))
+                .collect();
+            cache.by_message.retain(|id, _| ids.contains(id.as_str()));
+        }
+        counts
+    }
+
+    /// The rows of the blocks that `view` touches, and how many of them are
+    /// above it. Messages wholly out of view aren't laid out.
+    fn visible_rows(
+        &self,
+        ctx: &RenderCtx,
+        content_width: usize,
+        head: Vec<Line<'static>>,
+        message_rows: &[usize],
+        tail: Vec<Line<'static>>,
+        view: Range<usize>,
+    ) -> (Vec<Line<'static>>, usize) {
+        // Blocks in order: the earlier marker, each message, then the error
+        let block_rows: Vec<usize> = std::iter::once(head.len())
+            .chain(message_rows.iter().copied())
+            .chain([tail.len()])
+            .collect();
+        let mut start = 0;
+        let mut in_view = Vec::new();
+        for (index, rows) in block_rows.iter().enumerate() {
+            if start + rows > view.start && start < view.end {
+                in_view.push((index, start));
+            }
+            start += rows;
+        }
+        let skipped = in_view
+            .first()
+            .map_or(0, |(_, first_start)| view.start - first_start);
+
+        let mut rows = Vec::new();
+        let (mut head, mut tail) = (Some(head), Some(tail));
+        for (index, _) in in_view {
+            match index
+                .checked_sub(1)
+                .and_then(|i| self.message_containers.get(i))
+            {
+                Some(container) => {
+                    self.row_cache
+                        .0
+                        .borrow_mut()
+                        .laid_out
+                        .push(container_id(container).to_string());
+                    rows.extend(lay_out(
+                        self.container_lines(
+                            container,
+                            ctx.verbosity,
+                            &ctx.gutter_colors,
+                            Some(content_width),
+                        ),
+                        Some(content_width),
+                        self.glyphs,
+                    ));
+                }
+                None if index == 0 => rows.extend(head.take().unwrap_or_default()),
+                None => rows.extend(tail.take().unwrap_or_default()),
+            }
+        }
+        (rows, skipped)
+    }
+
+    /// Messages the latest frame laid out
+    #[cfg(test)]
+    fn laid_out(&self) -> Vec<String> {
+        self.row_cache.0.borrow().laid_out.clone()
+    }
+
+    pub fn render_with(&self, ctx: &RenderCtx, area: Rect, buf: &mut Buffer) {
+        // Wrapped here rather than by the paragraph, so wrapped rows keep
+        // their message's gutter
+        let content_width = area.width.saturating_sub(2) as usize;
+        let head = lay_out(self.earlier_lines(), Some(content_width), self.glyphs);
+        let tail = lay_out(
+            self.unlogged_error_lines(),
+            Some(content_width),
+            self.glyphs,
+        );
+        let message_rows = self.message_rows(ctx, content_width);
+        let available_height = area.height.saturating_sub(2) as usize;
+        self.row_cache.0.borrow_mut().view_height = Some(available_height);
+
+        // The scrollbar measures the whole log, though only what's in view
+        // gets laid out. Nothing scrolls the whole log sideways: prose
+        // wraps, and code blocks scroll on their own.
+        let content_lines = head.len() + message_rows.iter().sum::<usize>() + tail.len();
+
+        let vertical_scrollbar_area = area.inner(Margin {
+            vertical: 1,
+            horizontal: 0,
+        });
+
+        // Use current scroll positions directly from the model (no mutation)
+        let constrained_vertical_scroll = {
+            let max_vertical_scroll = if content_lines > available_height {
+                content_lines - available_height
+            } else {
+                0
+            };
+            self.vertical_scroll.min(max_vertical_scroll)
+        };
+
+        // Create scrollbar states for rendering using fresh content dimensions
+        // This ensures scrollbar state matches the actual content being rendered
+        let mut vertical_scrollbar_state = self
+            .vertical_scroll_state
+            .content_length(content_lines)
+            .position(constrained_vertical_scroll);
+
+        let (content, skipped) = self.visible_rows(
+            ctx,
+            content_width,
+            head,
+            &message_rows,
+            tail,
+            constrained_vertical_scroll..constrained_vertical_scroll + available_height,
+        );
+        let title = match (&self.find, &self.selection) {
+            (Some(find), _) => find.title(),
+            (None, Some(_)) => LOG_SELECTING_TITLE.to_string(),
+            (None, None) => LOG_TITLE.to_string(),
+        };
+        let paragraph = Paragraph::new(content)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_set(ctx.border_set())
+                    .title(ctx.glyphs.text(&title).into_owned().bold())
+                    .gray(),
+            )
+            .wrap(Wrap { trim: false })
+            .scroll((skipped as u16, 0));
+
+        paragraph.render(area, buf);
+
+        // Drawn over the rows rather than into them, so the cached rows stay
+        // valid while the cursor moves
+        if let Some(selection) = &self.selection {
+            if selection.layout == Some((content_width, ctx.verbosity)) {
+                selection.render_highlight(
+                    area.inner(Margin {
+                        vertical: 1,
+                        horizontal: 1,
+                    }),
+                    constrained_vertical_scroll,
+                    buf,
+                );
+            }
+        }
+        if let Some(find) = &self.find {
+            if find.layout == Some((content_width, ctx.verbosity)) {
+                find.render_highlight(
+                    area.inner(Margin {
+                        vertical: 1,
+                        horizontal: 1,
+                    }),
+                    constrained_vertical_scroll,
+                    buf,
+                );
+            }
+        }
+
+        // Only render vertical scrollbar if content is taller than the available area
+        if content_lines > (area.height.saturating_sub(2)) as usize {
+            let vertical_scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
+                .symbols(ctx.glyphs.scrollbar_log.clone())
+                .begin_symbol(None)
+                .end_symbol(None);
+
+            vertical_scrollbar.render(vertical_scrollbar_area, buf, &mut vertical_scrollbar_state);
+        }
+
+        if self.unseen_messages > 0 {
+            render_unseen_pill(self.unseen_messages, ctx.glyphs, area, buf);
+        }
+    }
+}
+
+/// "▼ 3 new messages · End" over the bottom right of the log, inside the
+/// border and clear of the scrollbar
+fn render_unseen_pill(count: usize, glyphs: &Glyphs, area: Rect, buf: &mut Buffer) {
+    let noun = match count {
+        1 => UNSEEN_MESSAGE,
+        _ => UNSEEN_MESSAGES,
+    };
+    let text = format!(
+        " {} {} {} {} {} ",
+        glyphs.triangle_down, count, noun, glyphs.separator, JUMP_TO_BOTTOM_KEY
+    );
+    let width = display_width(&text) as u16;
+    if area.height < 3 || area.width < width + 3 {
+        return;
+    }
+    let pill_area = Rect {
+        x: area.right() - width - 2,
+        y: area.bottom() - 2,
+        width,
+        height: 1,
+    };
+    Line::styled(text, Style::default().fg(Color::Black).bg(Color::Yellow)).render(pill_area, buf);
+}
+
+impl Default for MessageLog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::ui_components::glyphs::ASCII;
+    use opencode_sdk::models::{AssistantMessage, UserMessage};
+    use opencode_sdk::models::{
+        TextPart, ToolPart, ToolState, ToolStateCompleted, ToolStateCompletedTime, UnknownError,
+        UnknownErrorData,
+    };
+    use ratatui::style::Modifier;
+    use ratatui::widgets::BorderType;
+    use std::time::SystemTime;
+
+    fn user_container(id: &str) -> MessageContainer {
+        MessageContainer {
+            info: Message::User(Box::new(UserMessage {
+                id: id.to_string(),
+                ..Default::default()
+            })),
+            parts: HashMap::new(),
+            part_order: vec![],
+            is_streaming: false,
+            last_updated: SystemTime::UNIX_EPOCH,
+            printed_to_stdout: false,
+            provisional: false,
+            send_failed: false,
+        }
+    }
+
+    fn assistant_container_with_tool(output: &str) -> MessageContainer {
+        let tool_part = Part::Tool(Box::new(ToolPart {
+            id: "prt_1".to_string(),
+            session_id: "ses_1".to_string(),
+            message_id: "msg_1".to_string(),
+            call_id: "call_1".to_string(),
+            tool: "bash".to_string(),
+            state: Box::new(ToolState::Completed(Box::new(ToolStateCompleted {
+                input: HashMap::new(),
+                output: output.to_string(),
+                title: "ls".to_string(),
+                metadata: HashMap::new(),
+                time: Box::new(ToolStateCompletedTime {
+                    start: 0.0,
+                    end: 1.0,
+                }),
+            }))),
+        }));
+        MessageContainer {
+            info: Message::Assistant(Box::new(AssistantMessage {
+                id: "msg_1".to_string(),
+                ..Default::default()
+            })),
+            parts: HashMap::from([("prt_1".to_string(), tool_part)]),
+            part_order: vec!["prt_1".to_string()],
+            is_streaming: false,
+            last_updated: SystemTime::UNIX_EPOCH,
+            printed_to_stdout: false,
+            provisional: false,
+            send_failed: false,
+        }
+    }
+
+    fn streaming_text_container(text: &str) -> MessageContainer {
+        let text_part = Part::Text(Box::new(TextPart {
+            id: "prt_1".to_string(),
+            session_id: "ses_1".to_string(),
+            message_id: "msg_1".to_string(),
+            text: text.to_string(),
+            synthetic: None,
+            time: None,
+        }));
+        MessageContainer {
+            info: Message::Assistant(Box::new(AssistantMessage {
+                id: "msg_1".to_string(),
+                ..Default::default()
+            })),
+            parts: HashMap::from([("prt_1".to_string(), text_part)]),
+            part_order: vec!["prt_1".to_string()],
+            is_streaming: true,
+            last_updated: SystemTime::UNIX_EPOCH,
+            printed_to_stdout: false,
+            provisional: false,
+            send_failed: false,
+        }
+    }
+
+    fn provisional_container(text: &str, send_failed: bool) -> MessageContainer {
+        let text_part = Part::Text(Box::new(TextPart {
+            id: "prt_1".to_string(),
+            message_id: "msg_1".to_string(),
+            text: text.to_string(),
+            ..Default::default()
+        }));
+        MessageContainer {
+            parts: HashMap::from([("prt_1".to_string(), text_part)]),
+            part_order: vec!["prt_1".to_string()],
+            provisional: true,
+            send_failed,
+            ..user_container("msg_1")
+        }
+    }
+
+    fn rendered_with(log: &MessageLog, ctx: &RenderCtx) -> String {
+        let area = Rect::new(0, 0, 40, 10);
+        let mut buf = Buffer::empty(area);
+        log.render_with(ctx, area, &mut buf);
+        (area.top()..area.bottom())
+            .map(|y| {
+                (area.left()..area.right())
+                    .map(|x| buf[(x, y)].symbol())
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn rendered(log: &MessageLog) -> Vec<String> {
+        log.render_message_content(VerbosityLevel::Summary)
+            .lines
+            .iter()
+            .map(|line| line.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_session_error_renders_after_its_message() {
+        let mut log = MessageLog::new();
+        log.set_message_containers(vec![user_container("msg_1"), user_container("msg_2")]);
+        let error = AssistantMessageError::UnknownError(Box::new(UnknownError {
+            data: Box::new(UnknownErrorData {
+                message: "Rate limit exceeded".to_string(),
+            }),
+        }));
+        log.set_session_error(Some(SessionErrorBlock::new(
+            Some("msg_1".to_string()),
+            Some(&error),
+        )));
+
+        let lines = rendered(&log);
+        assert_eq!(
+            lines[2..6],
+            [
+                "  ┌─ Error",
+                "  │ Rate limit exceeded",
+                "  │ ^x r retry last message",
+                "  └─",
+            ]
+        );
+        // The later message still follows the block
+        assert_eq!(lines[7], "┃ ");
+
+        // Without a known message the block goes last
+        log.set_session_error(Some(SessionErrorBlock::new(None, None)));
+        let lines = rendered(&log);
+        assert_eq!(lines[lines.len() - 4], "  │ Unknown session error");
+
+        // Reloading the log drops the block
+        log.set_message_containers(vec![user_container("msg_1")]);
+        assert!(log.session_error().is_none());
+    }
+
+    #[test]
+    fn test_provisional_message_markers() {
+        let mut log = MessageLog::new();
+        log.set_message_containers(vec![provisional_container("hello", false)]);
+        assert_eq!(rendered(&log)[..3], ["┃ ", "┃  hello", "┃   sending…"]);
+
+        log.set_message_containers(vec![provisional_container("hello", true)]);
+        let text = log.render_message_content(VerbosityLevel::Summary);
+        assert_eq!(
+            text.lines[2].to_string(),
+            "┃   not sent · ^x r retry last message"
+        );
+        assert_eq!(text.lines[1].spans[0].style.fg, Some(Color::Red));
+        assert_eq!(text.lines[1].spans[2].style.fg, Some(Color::Red));
+    }
+
+    fn gutter_color(line: &Line) -> Option<Color> {
+        line.spans
+            .first()
+            .filter(|span| span.content == gutter_for(&UNICODE))
+            .and_then(|span| span.style.fg)
+    }
+
+    #[test]
+    fn test_gutter_on_every_line_of_a_message() {
+        let mut log = MessageLog::new();
+        let user = MessageContainer {
+            provisional: false,
+            ..provisional_container("one\ntwo\nthree", false)
+        };
+        let assistant = MessageContainer {
+            info: Message::Assistant(Box::new(AssistantMessage {
+                id: "msg_2".to_string(),
+                ..Default::default()
+            })),
+            ..assistant_container_with_tool("file")
+        };
+        log.set_message_containers(vec![user, assistant]);
+
+        let text = log.render_message_content(VerbosityLevel::Summary);
+        let colors: Vec<_> = text.lines.iter().map(gutter_color).collect();
+        let cyan = Some(Color::Cyan);
+        let dim = Some(Color::DarkGray);
+        assert_eq!(
+            colors,
+            [cyan, cyan, cyan, cyan, None, dim, dim, dim, None],
+            "{:?}",
+            rendered(&log)
+        );
+        // Blank separators stay empty, tool calls sit a level in
+        assert_eq!(text.lines[4].to_string(), "");
+        assert_eq!(text.lines[6].to_string(), "┃   ● bash");
+    }
+
+    #[test]
+    fn test_gutter_red_for_a_message_that_ended_in_an_error() {
+        let mut log = MessageLog::new();
+        log.set_message_containers(vec![assistant_container_with_tool("file")]);
+        log.set_session_error(Some(SessionErrorBlock::new(
+            Some("msg_1".to_string()),
+            None,
+        )));
+
+        let text = log.render_message_content(VerbosityLevel::Summary);
+        assert_eq!(gutter_color(&text.lines[1]), Some(Color::Red));
+        // The error block itself has no gutter
+        assert_eq!(text.lines[4].to_string(), "  ┌─ Error");
+    }
+
+    #[test]
+    fn test_wrapped_rows_keep_the_gutter() {
+        let mut log = MessageLog::new();
+        log.set_message_containers(vec![MessageContainer {
+            provisional: false,
+            ..provisional_container("a message long enough to wrap over several rows", false)
+        }]);
+
+        let area = Rect::new(0, 0, 20, 10);
+        let mut buf = Buffer::empty(area);
+        log.render_with(&RenderCtx::default(), area, &mut buf);
+        let rows: Vec<String> = (1..6)
+            .map(|y| (0..area.width).map(|x| buf[(x, y)].symbol()).collect())
+            .collect();
+        assert_eq!(
+            rows,
+            [
+                "│┃                 │",
+                "│┃  a message long │",
+                "│┃  enough to wrap │",
+                "│┃  over several   │",
+                "│┃  rows           │",
+            ]
+        );
+    }
+
+    fn log_with(count: usize) -> MessageLog {
+        let mut log = MessageLog::new();
+        log.set_message_containers(
+            (0..count)
+                .map(|i| user_container(&format!("msg_{:02}", i)))
+                .collect(),
+        );
+        log
+    }
+
+    #[test]
+    fn test_auto_scroll_only_when_at_bottom() {
+        let mut log = log_with(20);
+        assert!(log.is_at_bottom());
+
+        // At the bottom, new messages are followed
+        log.apply_update("msg_20", &user_container("msg_20"));
+        log.flush_auto_scroll();
+        assert!(log.is_at_bottom());
+        assert_eq!(log.unseen_messages(), 0);
+
+        // Reading further up keeps the position and counts what arrives
+        log.scroll_vertical(&-20);
+        let reading_at = log.vertical_scroll;
+        log.apply_update("msg_21", &user_container("msg_21"));
+        log.apply_update("msg_21", &user_container("msg_21"));
+        log.apply_update("msg_22", &user_container("msg_22"));
+        log.flush_auto_scroll();
+        assert_eq!(log.vertical_scroll, reading_at);
+        assert_eq!(log.unseen_messages(), 2);
+
+        // A line or two short of the bottom still counts as there
+        log.scroll_vertical(&100);
+        log.scroll_vertical(&-2);
+        assert!(log.is_at_bottom());
+        assert_eq!(log.unseen_messages(), 0);
+    }
+
+    #[test]
+    fn test_unseen_pill_until_jumping_to_bottom() {
+        let mut log = log_with(20);
+        log.scroll_vertical(&-20);
+        log.apply_update("msg_20", &user_container("msg_20"));
+        log.apply_update("msg_21", &user_container("msg_21"));
+        assert!(rendered_with(&log, &RenderCtx::default()).contains(" ▼ 2 new messages · End "));
+
+        log.touch_scroll();
+        assert_eq!(log.unseen_messages(), 0);
+        assert!(!rendered_with(&log, &RenderCtx::default()).contains("new messages"));
+    }
+
+    #[test]
+    fn test_prepend_keeps_scroll_position() {
+        let mut log = MessageLog::new();
+        log.set_message_containers(vec![user_container("msg_3"), user_container("msg_4")]);
+        log.set_earlier_messages(EarlierMessages::Available);
+        assert_eq!(rendered(&log)[0], "── load earlier messages ──");
+        log.scroll_vertical(&-100);
+        assert!(log.is_scrolled_to_top());
+
+        log.set_earlier_messages(EarlierMessages::Loading);
+        log.prepend_message_containers(
+            vec![user_container("msg_1"), user_container("msg_2")],
+            EarlierMessages::None,
+        );
+
+        // The sentinel is gone and msg_3 stays two lines down the view
+        let lines = rendered(&log);
+        assert_eq!(lines.len(), 8);
+        assert_eq!(log.vertical_scroll, 2);
+        let order: Vec<_> = log
+            .message_containers()
+            .iter()
+            .map(|container| container_id(container))
+            .collect();
+        assert_eq!(order, ["msg_1", "msg_2", "msg_3", "msg_4"]);
+    }
+
+    #[test]
+    fn test_dropped_messages_leave_a_stub() {
+        let mut log = log_with(5);
+        log.drop_earliest(&["msg_00".to_string(), "msg_01".to_string()], 2);
+        assert_eq!(log.message_containers().len(), 3);
+        assert_eq!(log.earlier_messages(), EarlierMessages::Available);
+        assert_eq!(rendered(&log)[0], "… 2 earlier messages — ctrl+x L to load");
+
+        // Loading them back leaves the plain marker
+        log.set_dropped_messages(0);
+        assert_eq!(rendered(&log)[0], "── load earlier messages ──");
+    }
+
+    #[test]
+    fn test_render_follows_the_context() {
+        let mut log = MessageLog::new();
+        log.set_message_containers(vec![assistant_container_with_tool("Cargo.toml")]);
+
+        let summary = rendered_with(&log, &RenderCtx::default());
+        let verbose = rendered_with(
+            &log,
+            &RenderCtx {
+                verbosity: VerbosityLevel::Verbose,
+                border_type: BorderType::Plain,
+                ..RenderCtx::default()
+            },
+        );
+        assert_ne!(summary, verbose);
+
+        // The border comes from the context
+        assert!(summary.starts_with('╭'));
+        assert!(verbose.starts_with('┌'));
+        // and so does whether the tool output is expanded
+        assert!(summary.contains("(ctrl+r to expand)"));
+        assert!(!summary.contains("Full Output"));
+        assert!(verbose.contains("Full Output"));
+    }
+
+    #[test]
+    fn test_ascii_glyphs_keep_the_layout() {
+        let ascii_log = |glyphs: &'static Glyphs| {
+            let mut log = log_with(12);
+            log.set_glyphs(glyphs);
+            log.drop_earliest(&["msg_00".to_string()], 1);
+            log.apply_update("msg_1", &assistant_container_with_tool("Cargo.toml\nsrc"));
+            let provisional = MessageContainer {
+                info: user_container("msg_p").info,
+                ..provisional_container("hello", false)
+            };
+            log.apply_update("msg_p", &provisional);
+            log.set_session_error(Some(SessionErrorBlock::new(None, None)));
+            log
+        };
+        let unicode = ascii_log(&UNICODE);
+        let ascii = ascii_log(&ASCII);
+
+        // Every line stands in character for character, so columns line up
+        for verbosity in [VerbosityLevel::Summary, VerbosityLevel::Verbose] {
+            let unicode = unicode.render_message_content(verbosity).lines;
+            let ascii = ascii.render_message_content(verbosity).lines;
+            assert_eq!(ascii.len(), unicode.len());
+            for (ascii, unicode) in ascii.iter().zip(&unicode) {
+                assert!(ascii.to_string().is_ascii(), "{}", ascii);
+                assert_eq!(ascii.width(), unicode.width(), "{}", ascii);
+            }
+        }
+
+        // Borders, scrollbar and the unseen pill included
+        let mut ascii = ascii;
+        ascii.scroll_vertical(&-100);
+        ascii.apply_update("msg_new", &user_container("msg_new"));
+        let screen = rendered_with(
+            &ascii,
+            &RenderCtx {
+                verbosity: VerbosityLevel::Verbose,
+                glyphs: &ASCII,
+                ..RenderCtx::default()
+            },
+        );
+        assert!(screen.is_ascii(), "{}", screen);
+        assert!(screen.starts_with('+'));
+        assert!(screen.contains("~ 1 earlier messages - ctrl+x L"));
+        assert!(screen.contains(" v 1 new message - End "));
+    }
+
+    #[test]
+    fn test_reveal_step() {
+        assert_eq!(reveal_step(0, 200), REVEAL_CHARS_PER_FRAME);
+        assert_eq!(reveal_step(160, 200), 200);
+        assert_eq!(reveal_step(200, 200), 200);
+        // Text that shrank is cut to its new length
+        assert_eq!(reveal_step(120, 50), 50);
+    }
+
+    #[test]
+    fn test_reveal_catches_up_then_drops_completed_parts() {
+        let text = "a".repeat(200);
+        let mut log = MessageLog::new();
+        log.set_message_containers(vec![streaming_text_container(&text)]);
+        let shown = |log: &MessageLog| {
+            let lines = rendered(log);
+            let last = lines.iter().rev().find(|line| line.contains('▌'));
+            last.map(|line| line.matches('a').count())
+        };
+
+        // Without the reveal the whole text shows at once
+        assert_eq!(shown(&log), Some(200));
+        assert!(!log.is_revealing());
+
+        log.advance_reveal();
+        assert_eq!(shown(&log), Some(REVEAL_CHARS_PER_FRAME));
+        assert!(log.is_revealing());
+        log.advance_reveal();
+        log.advance_reveal();
+        assert_eq!(shown(&log), Some(200));
+        assert!(!log.is_revealing());
+
+        // A completed message never shows the cursor, and its parts drop out
+        let mut completed = streaming_text_container(&format!("{}b", text));
+        completed.is_streaming = false;
+        log.apply_update("msg_1", &completed);
+        log.advance_reveal();
+        assert!(log.revealed.is_empty());
+        assert_eq!(shown(&log), None);
+        assert!(rendered(&log).iter().any(|line| line.ends_with("ab")));
+    }
+
+    fn user_text_container(id: &str, text: &str) -> MessageContainer {
+        let text_part = Part::Text(Box::new(TextPart {
+            id: format!("prt_{}", id),
+            message_id: id.to_string(),
+            text: text.to_string(),
+            ..Default::default()
+        }));
+        MessageContainer {
+            parts: HashMap::from([(format!("prt_{}", id), text_part)]),
+            part_order: vec![format!("prt_{}", id)],
+            ..user_container(id)
+        }
+    }
+
+    #[test]
+    fn test_long_prompt_folds_until_expanded() {
+        let text = (1..=65)
+            .map(|n| format!("line {}", n))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let mut log = MessageLog::new();
+        log.set_message_containers(vec![user_text_container("msg_01", &text)]);
+        let folded = rendered(&log);
+        assert!(folded.contains(&"┃  … 57 lines hidden (ctrl+r to expand)".to_string()));
+        assert!(!folded.contains(&"┃  line 30".to_string()));
+        assert!(folded.contains(&"┃  line 65".to_string()));
+
+        log.expand_prompt("msg_01");
+        let expanded = rendered(&log);
+        assert!(expanded.contains(&"┃  line 30".to_string()));
+        assert!(!expanded.iter().any(|row| row.contains("lines hidden")));
+
+        // Short prompts aren't folded at all
+        let mut log = MessageLog::new();
+        log.set_prompt_collapse_lines(100);
+        log.set_message_containers(vec![user_text_container("msg_01", &text)]);
+        assert!(rendered(&log).contains(&"┃  line 30".to_string()));
+    }
+
+    #[test]
+    fn test_only_messages_in_view_are_laid_out() {
+        let mut log = MessageLog::new();
+        log.set_message_containers(
+            (0..5000)
+                .map(|n| user_text_container(&format!("msg_{:04}", n), &format!("message {}", n)))
+                .collect(),
+        );
+        let ctx = RenderCtx::default();
+        let ids = |range: std::ops::Range<usize>| -> Vec<String> {
+            range.map(|n| format!("msg_{:04}", n)).collect()
+        };
+
+        // Each message is a header row, its text and a blank row, so the
+        // 8 rows inside the border reach into the third message from the end
+        let first_frame = rendered_with(&log, &ctx);
+        assert!(first_frame.contains("message 4999"));
+        assert_eq!(log.laid_out().len(), 5000 + 3);
+
+        rendered_with(&log, &ctx);
+        assert_eq!(log.laid_out(), ids(4997..5000));
+
+        log.scroll_vertical(&-20000);
+        let top = rendered_with(&log, &ctx);
+        assert!(top.contains("message 0"));
+        assert_eq!(log.laid_out(), ids(0..3));
+
+        // A changed message is counted again, the others come from the cache
+        log.apply_update("msg_2500", &user_text_container("msg_2500", "edited"));
+        rendered_with(&log, &ctx);
+        assert_eq!(log.laid_out(), [ids(2500..2501), ids(0..3)].concat());
+    }
+
+    /// A log of one message, drawn 20 columns wide so its first line wraps
+    fn selecting(text: &str) -> MessageLog {
+        let mut log = MessageLog::new();
+        log.set_message_containers(vec![user_text_container("msg_1", text)]);
+        rendered_with_area(&log, Rect::new(0, 0, 20, 10));
+        log.start_selection();
+        log
+    }
+
+    fn rendered_with_area(log: &MessageLog, area: Rect) -> Vec<String> {
+        let mut buf = Buffer::empty(area);
+        log.render_with(&RenderCtx::default(), area, &mut buf);
+        (area.top()..area.bottom())
+            .map(|y| {
+                (area.left()..area.right())
+                    .map(|x| buf[(x, y)].symbol())
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn cursor(log: &MessageLog) -> (usize, usize) {
+        let cursor = log.selection.as_ref().unwrap().cursor;
+        (cursor.row, cursor.column)
+    }
+
+    #[test]
+    fn test_selection_cursor_stays_on_the_rows() {
+        // Rows: blank, " alpha beta ", " gamma delta", " end", then the
+        // blank after the message
+        let mut log = selecting("alpha beta gamma delta\nend");
+        assert_eq!(cursor(&log), (4, 0));
+
+        log.move_selection(-100, 0);
+        assert_eq!(cursor(&log), (0, 0));
+        log.move_selection(1, -3);
+        assert_eq!(cursor(&log), (1, 0));
+        log.move_selection(0, 100);
+        assert_eq!(cursor(&log), (1, "┃  alpha beta ".chars().count() - 1));
+
+        // A shorter row pulls the column back onto it
+        log.move_selection(2, 0);
+        assert_eq!(cursor(&log), (3, "┃  end".chars().count() - 1));
+        log.move_selection(100, 100);
+        assert_eq!(cursor(&log), (4, 0));
+    }
+
+    #[test]
+    fn test_selected_text_joins_wrapped_rows() {
+        let mut log = selecting("alpha beta gamma delta\nend");
+        log.move_selection(-3, 3);
+        log.toggle_selection_mark();
+        log.move_selection(2, 4);
+
+        // The region is reversed over the rows as drawn, gutter included
+        let rows = rendered_with_area(&log, Rect::new(0, 0, 20, 10));
+        assert_eq!(rows[4], "│┃  end            │");
+        let mut buf = Buffer::empty(Rect::new(0, 0, 20, 10));
+        log.render_with(&RenderCtx::default(), buf.area, &mut buf);
+        let reversed = |x: u16, y: u16| buf[(x, y)].modifier.contains(Modifier::REVERSED);
+        assert!(!reversed(3, 2) && reversed(4, 2) && reversed(14, 2));
+        assert!(reversed(1, 3) && reversed(14, 3) && !reversed(15, 3));
+        assert!(reversed(1, 4) && reversed(6, 4) && !reversed(7, 4));
+
+        // The wrapped rows are one line again, without gutters or indents
+        assert_eq!(
+            log.end_selection().as_deref(),
+            Some("alpha beta gamma delta\n end")
+        );
+        assert!(!log.is_selecting());
+    }
+
+    /// A log of these messages drawn 20 columns wide, finding `query`
+    fn finding(texts: &[&str], query: &str) -> MessageLog {
+        let mut log = MessageLog::new();
+        log.set_message_containers(
+            texts
+                .iter()
+                .enumerate()
+                .map(|(index, text)| user_text_container(&format!("msg_{}", index), text))
+                .collect(),
+        );
+        rendered_with_area(&log, Rect::new(0, 0, 20, 10));
+        log.start_find();
+        log.edit_find_query(|typed| typed.push_str(query));
+        log
+    }
+
+    fn match_rows(log: &MessageLog) -> Vec<Vec<(usize, Range<usize>)>> {
+        let (matches, _) = log.find_matches();
+        matches.iter().map(|found| found.rows.clone()).collect()
+    }
+
+    #[test]
+    fn test_find_maps_matches_onto_the_rows_as_drawn() {
+        // Rows: blank, "┃  alpha beta ", "┃  gamma delta", "┃  end", then the
+        // blank after the message
+        let log = finding(&["alpha beta gamma delta\nend"], "BETA gamma");
+        // Found in the line before wrapping, so it runs across both rows
+        assert_eq!(match_rows(&log), [vec![(1, 9..14), (2, 3..8)]]);
+
+        // Lines of a part are searched one by one
+        let log = finding(&["alpha beta gamma delta\nend"], "d");
+        assert_eq!(match_rows(&log), [vec![(2, 9..10)], vec![(3, 5..6)]]);
+        let log = finding(&["alpha beta gamma delta\nend"], "delta end");
+        assert!(match_rows(&log).is_empty());
+
+        // A later message's rows start after the earlier one's
+        let log = finding(&["one\ntwo", "three two"], "two");
+        assert_eq!(match_rows(&log), [vec![(2, 3..6)], vec![(5, 9..12)]]);
+    }
+
+    #[test]
+    fn test_find_highlights_the_matches_and_the_title_counts_them() {
+        let mut log = finding(&["one\ntwo", "three two"], "two");
+        assert_eq!(
+            log.find.as_ref().unwrap().title(),
+            "Message Log · /two · 2 matches · enter jump · esc clear"
+        );
+        let mut buf = Buffer::empty(Rect::new(0, 0, 20, 10));
+        log.render_with(&RenderCtx::default(), buf.area, &mut buf);
+        // Drawn inside the border, the rows one down and one across
+        assert_eq!(buf[(4, 3)].bg, Color::Yellow);
+        assert_eq!(buf[(10, 6)].bg, Color::Yellow);
+        assert_ne!(buf[(3, 3)].bg, Color::Yellow);
+
+        log.jump_to_first_match();
+        assert!(log
+            .find
+            .as_ref()
+            .unwrap()
+            .title()
+            .contains("/two · 1/2 · n next"));
+        log.render_with(&RenderCtx::default(), buf.area, &mut buf);
+        assert_eq!(buf[(4, 3)].bg, Color::LightCyan);
+        assert_eq!(buf[(10, 6)].bg, Color::Yellow);
+
+        log.end_find();
+        let mut buf = Buffer::empty(Rect::new(0, 0, 20, 10));
+        log.render_with(&RenderCtx::default(), buf.area, &mut buf);
+        assert_ne!(buf[(4, 3)].bg, Color::Yellow);
+    }
+
+    #[test]
+    fn test_find_steps_through_matches_wrapping_around() {
+        let texts: Vec<String> = (0..12).map(|n| format!("note {}", n)).collect();
+        let texts: Vec<&str> = texts.iter().map(String::as_str).collect();
+        let mut log = finding(&texts, "NOTE");
+        // Three rows a message, 8 rows in view, so the log ends scrolled
+        // down to the last messages
+        assert_eq!(log.find_matches().0.len(), 12);
+        assert_eq!(log.vertical_scroll, 36 - 8);
+
+        // The first match from the top of the view
+        log.jump_to_first_match();
+        assert_eq!(log.find_matches().1, Some(9));
+        log.step_find(true);
+        log.step_find(true);
+        assert_eq!(log.find_matches().1, Some(11));
+        log.step_find(true);
+        assert_eq!(log.find_matches().1, Some(0));
+        assert_eq!(log.vertical_scroll, 1);
+        log.step_find(false);
+        assert_eq!(log.find_matches().1, Some(11));
+        assert_eq!(log.vertical_scroll, 34 + 1 - 8);
+
+        // Editing the query starts over, finding nothing steps nowhere
+        log.start_find();
+        log.edit_find_query(|query| query.push('x'));
+        assert_eq!(log.find_matches(), (&[][..], None));
+        log.jump_to_first_match();
+        log.step_find(true);
+        assert_eq!(log.find_matches(), (&[][..], None));
+        assert!(!log.is_typing_find());
+    }
+
+    #[test]
+    fn test_copying_without_a_region_takes_the_cursor_row() {
+        let mut log = selecting("alpha beta gamma delta\nend");
+        log.move_selection(-1, 0);
+        assert_eq!(log.end_selection().as_deref(), Some(" end"));
+    }
+
+    #[test]
+    fn test_jump_to_a_tool_call_and_expand_it() {
+        let tool = |id: &str, output: &str| {
+            let Part::Tool(mut tool_part) =
+                assistant_container_with_tool(output).parts["prt_1"].clone()
+            else {
+                unreachable!();
+            };
+            tool_part.id = id.to_string();
+            tool_part.message_id = "msg_30".to_string();
+            (id.to_string(), Part::Tool(tool_part))
+        };
+        let text = Part::Text(Box::new(TextPart {
+            id: "prt_text".to_string(),
+            message_id: "msg_30".to_string(),
+            text: "running the tests twice".to_string(),
+            ..Default::default()
+        }));
+        let output: Vec<String> = (1..=30).map(|n| format!("line {}", n)).collect();
+        let reply = MessageContainer {
+            info: Message::Assistant(Box::new(AssistantMessage {
+                id: "msg_30".to_string(),
+                ..Default::default()
+            })),
+            parts: HashMap::from([
+                ("prt_text".to_string(), text),
+                tool("prt_a", "first run"),
+                tool("prt_b", &output.join("\n")),
+            ]),
+            part_order: vec![
+                "prt_text".to_string(),
+                "prt_a".to_string(),
+                "prt_b".to_string(),
+            ],
+            ..assistant_container_with_tool("")
+        };
+        let mut containers: Vec<MessageContainer> = (10..30)
+            .map(|n| user_text_container(&format!("msg_{}", n), &format!("message {}", n)))
+            .collect();
+        containers.push(reply);
+        containers.extend(
+            (40..50).map(|n| user_text_container(&format!("msg_{}", n), &format!("message {}", n))),
+        );
+        let mut log = MessageLog::new();
+        log.set_message_containers(containers);
+        let area = Rect::new(0, 0, 40, 10);
+        rendered_with_area(&log, area);
+        assert!(log.is_following());
+
+        // Same tool, same title: the second call is the one at the top
+        assert!(log.jump_to_part("msg_30", "prt_b"));
+        assert!(!log.is_following());
+        let view = rendered_with_area(&log, area);
+        assert!(view[1].contains("bash"), "{view:#?}");
+        assert!(view[2..].join("\n").contains("line 1"), "{view:#?}");
+        assert!(!view[2..].join("\n").contains("first run"), "{view:#?}");
+
+        assert!(log.jump_to_part("msg_30", "prt_a"));
+        let view = rendered_with_area(&log, area);
+        assert!(view[1].contains("bash"), "{view:#?}");
+        assert!(view[2..].join("\n").contains("first run"), "{view:#?}");
+
+        // Expanded, all of the output shows and the header stays put
+        assert!(!rendered(&log).iter().any(|line| line.contains("line 30")));
+        log.expand_tool("msg_30", "prt_b");
+        assert!(log.jump_to_part("msg_30", "prt_b"));
+        let view = rendered_with_area(&log, area);
+        assert!(view[1].contains("bash"), "{view:#?}");
+        assert!(rendered(&log).iter().any(|line| line.contains("line 30")));
+
+        // Parts that aren't tool calls, or aren't loaded, aren't jumped to
+        assert!(!log.jump_to_part("msg_30", "prt_text"));
+        assert!(!log.jump_to_part("msg_01", "prt_a"));
+    }
+
+    /// A reply of prose too long for a row, then two code blocks with
+    /// lines too long for one
+    fn reply_with_code() -> MessageContainer {
+        let text = [
+            "The prose before the code wraps onto the rows below it, however long.",
+            "```rust",
+            "let first = \"0123456789abcdefghijklmnopqrstuvwxyz0123456789\";",
+            "```",
+            "```",
+            "second: 0123456789abcdefghijklmnopqrstuvwxyz0123456789",
+            "```",
+        ]
+        .join("\n");
+        MessageContainer {
+            is_streaming: false,
+            ..streaming_text_container(&text)
+        }
+    }
+
+    #[test]
+    fn test_code_blocks_scroll_sideways_on_their_own() {
+        let mut log = MessageLog::new();
+        log.set_message_containers(vec![reply_with_code()]);
+        let area = Rect::new(0, 0, 44, 16);
+        let before = rendered_with_area(&log, area);
+        let row = |rows: &[String], text: &str| {
+            rows.iter()
+                .find(|row| row.contains(text))
+                .cloned()
+                .unwrap_or_else(|| panic!("no row with {:?} in {:#?}", text, rows))
+        };
+        // Code keeps to a row, cut at the border, and says which columns show
+        assert!(row(&before, "```rust").contains("cols 1–38"));
+        assert!(row(&before, "let first").ends_with("\"0123456789abcdefghijklmno│"));
+
+        // Without a selection, the lowest block in view scrolls
+        assert!(log.scroll_block(8));
+        let after = rendered_with_area(&log, area);
+        assert!(row(&after, "``` cols").contains("cols 9–46"));
+        assert!(row(&after, "│┃   0123").contains("0123456789abcdef"));
+        assert_eq!(row(&after, "let first"), row(&before, "let first"));
+        assert_eq!(row(&after, "```rust"), row(&before, "```rust"));
+
+        // It stops at its last column, and back at its first
+        assert!(log.scroll_block(100));
+        assert!(row(&rendered_with_area(&log, area), "``` cols").contains("cols 17–54"));
+        assert!(log.scroll_block(-100));
+        assert_eq!(rendered_with_area(&log, area), before);
+
+        // Selecting, the block under the cursor scrolls
+        log.start_selection();
+        let first_row = before
+            .iter()
+            .position(|row| row.contains("let first"))
+            .unwrap();
+        let cursor_row = log.selection.as_ref().unwrap().cursor.row;
+        log.move_selection(first_row as i16 - 1 - cursor_row as i16, 0);
+        assert!(log.scroll_block(8));
+        let selected = rendered_with_area(&log, area);
+        assert!(row(&selected, "```rust").contains("cols 9–46"));
+        assert!(row(&selected, "│┃   t = \"").contains("0123456789"));
+        assert!(row(&selected, "second: ").contains("second: 0123456789"));
+    }
+
+    #[test]
+    fn test_prose_never_scrolls_sideways() {
+        let mut log = MessageLog::new();
+        log.set_message_containers(vec![reply_with_code()]);
+        let area = Rect::new(0, 0, 44, 16);
+        let prose_rows = |rows: Vec<String>| -> Vec<String> {
+            rows.into_iter()
+                .take_while(|row| !row.contains("```"))
+                .collect()
+        };
+        let before = prose_rows(rendered_with_area(&log, area));
+        assert!(before.iter().any(|row| row.contains("The prose before")));
+        assert!(before.iter().any(|row| row.contains("however long.")));
+
+        for columns in [8, 8, -8, 100] {
+            log.scroll_block(columns);
+            assert_eq!(prose_rows(rendered_with_area(&log, area)), before);
+        }
+
+        // With no code in view there's nothing to scroll
+        let mut prose_only = MessageLog::new();
+        prose_only.set_message_containers(vec![MessageContainer {
+            is_streaming: false,
+            ..streaming_text_container(&"word ".repeat(30))
+        }]);
+        let rows = rendered_with_area(&prose_only, area);
+        assert!(!prose_only.scroll_block(8));
+        assert_eq!(rendered_with_area(&prose_only, area), rows);
     }
 }
@@ -1,14 +1,143 @@
+use crate::app::{
+    links::{find_links, find_links_in_line, LinkPaths, LinkTarget},
+    message_state::UNKNOWN_MODEL,
+    token_estimate::format_tokens,
+    ui_components::{
+        glyphs::{Glyphs, UNICODE},
+        text_width::{
+            clip_columns, display_width, printable_line, truncate_to_width, DEFAULT_TAB_WIDTH,
+        },
+        tool_render::{ToolRenderPolicies, ToolRenderPolicy},
+    },
+    working_time::format_working_time,
+};
 use opencode_sdk::models::{
-    FilePart, Part, SessionMessages200ResponseInner, TextPart, ToolPart, ToolState,
+    AgentPart, FilePart, Message, Part, SessionMessages200ResponseInner, StepFinishPart, TextPart,
+    ToolPart, ToolState,
 };
 use ratatui::{
     buffer::Buffer,
     layout::Rect,
-    style::{Color, Style},
+    style::{Color, Modifier, Style},
     text::{Line, Span, Text},
     widgets::{Paragraph, Widget},
 };
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use std::path::Path;
+use std::time::Duration;
+
+/// Extra indent of tool and step activity under a message's text
+const ACTIVITY_INDENT: &str = "  ";
+/// Columns of the indent or bullet before each line of text
+const TEXT_PREFIX_WIDTH: usize = 2;
+
+/// Longest tool input value shown in full, in characters
+const TOOL_INPUT_MAX_CHARS: usize = 2000;
+
+/// Prompts longer than this many lines are folded in the log by default
+pub const DEFAULT_PROMPT_COLLAPSE_LINES: usize = 12;
+/// Lines of a folded prompt shown above the fold
+const PROMPT_HEAD_LINES: usize = 6;
+/// Lines of a folded prompt shown below the fold
+const PROMPT_TAIL_LINES: usize = 2;
+
+/// How a long prompt is folded: the lines shown from its start and end,
+/// and how many are hidden between them
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PromptFold {
+    pub head: usize,
+    pub hidden: usize,
+    pub tail: usize,
+}
+
+/// The fold of a prompt of `line_count` lines, None when it's no longer
+/// than `collapse_lines` or folding wouldn't hide at least two lines
+pub fn prompt_fold(line_count: usize, collapse_lines: usize) -> Option<PromptFold> {
+    let shown = PROMPT_HEAD_LINES + PROMPT_TAIL_LINES;
+    (line_count > collapse_lines.max(shown + 1)).then(|| PromptFold {
+        head: PROMPT_HEAD_LINES,
+        hidden: line_count - shown,
+        tail: PROMPT_TAIL_LINES,
+    })
+}
+
+/// Input keys listed first in a tool's Input block, most important first.
+/// Any other keys follow in alphabetical order.
+fn tool_input_key_order(tool: &str) -> &'static [&'static str] {
+    match tool {
+        "bash" => &["command", "description", "timeout"],
+        "edit" => &["filePath", "oldString", "newString", "replaceAll"],
+        "write" => &["filePath", "content"],
+        "read" => &["filePath", "offset", "limit"],
+        "glob" | "grep" => &["pattern", "path", "include"],
+        "list" => &["path", "ignore"],
+        "webfetch" => &["url", "format"],
+        _ => &[],
+    }
+}
+
+/// Value as shown in the Input block: strings as they are, anything else as
+/// indented JSON, cut at `TOOL_INPUT_MAX_CHARS`
+fn format_tool_input_value(value: &serde_json::Value, glyphs: &Glyphs) -> String {
+    let text = match value {
+        serde_json::Value::String(text) => text.clone(),
+        other => serde_json::to_string_pretty(other).unwrap_or_else(|_| other.to_string()),
+    };
+    let total = text.chars().count();
+    if total <= TOOL_INPUT_MAX_CHARS {
+        return text;
+    }
+    let kept: String = text.chars().take(TOOL_INPUT_MAX_CHARS).collect();
+    format!(
+        "{}{} [{} more chars]",
+        kept,
+        glyphs.ellipsis,
+        total - TOOL_INPUT_MAX_CHARS
+    )
+}
+
+/// Tool that runs a sub-agent, and the input key naming the agent
+const TASK_TOOL: &str = "task";
+const TASK_AGENT_KEY: &str = "subagent_type";
+
+/// Progress of a sub-agent run, shown in its section header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AgentStatus {
+    Pending,
+    Running,
+    Completed,
+    Failed,
+}
+
+impl AgentStatus {
+    fn from_task(task: Option<&ToolPart>) -> Self {
+        match task.map(|task| &*task.state) {
+            None | Some(ToolState::Pending(_)) => AgentStatus::Pending,
+            Some(ToolState::Running(_)) => AgentStatus::Running,
+            Some(ToolState::Completed(_)) => AgentStatus::Completed,
+            Some(ToolState::Error(_)) => AgentStatus::Failed,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            AgentStatus::Pending => "pending",
+            AgentStatus::Running => "running",
+            AgentStatus::Completed => "completed",
+            AgentStatus::Failed => "failed",
+        }
+    }
+
+    fn color(self) -> Color {
+        match self {
+            AgentStatus::Pending => Color::Yellow,
+            AgentStatus::Running => Color::Blue,
+            AgentStatus::Completed => Color::Green,
+            AgentStatus::Failed => Color::Red,
+        }
+    }
+}
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum MessageContext {
@@ -35,15 +164,131 @@ pub struct MessageRenderer {
     context: MessageContext,
     verbosity: VerbosityLevel,
     step_rendering_mode: StepRenderingMode,
-    expanded_tools: HashSet<String>, // Track which tools are expanded (fullscreen only)
+    expanded: HashSet<String>, // Tools, steps and prompts shown in full, by ID (fullscreen only)
+    streaming: bool,
+    revealed: HashMap<String, usize>, // Characters shown of text parts still being revealed
+    indent_activity: bool,            // Tool, agent and step lines one level under the text
+    linked_paths: HashSet<String>,    // Paths in the text known to name a file
+    tool_render: ToolRenderPolicies,  // How much output each tool shows when folded
+    glyphs: &'static Glyphs,          // Box, bullet and marker characters
+    model_annotation: Option<String>, // Model that wrote it, when not the current one
+    tab_width: usize,                 // Columns between tab stops
+    prompt_fold_after: Option<usize>, // Lines past which prompts fold until expanded
+    workspace_root: Option<String>,   // Commands run elsewhere say where
+    block_scroll: Option<(usize, HashMap<String, usize>)>, // Room for blocks that don't wrap, and their offsets
 }
 
-#[derive(Debug, Clone)]
+/// A code block or expanded tool output too wide for its room, which
+/// scrolls sideways on its own rather than wrapping
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScrollBlock {
+    /// The tool's part ID for its output, or the part ID and the block's
+    /// number in it for a code block, see `block_key`
+    pub key: String,
+    /// Columns of its widest line
+    pub width: usize,
+    /// Columns of it in view
+    pub view: usize,
+}
+
+impl ScrollBlock {
+    /// Furthest it scrolls
+    pub fn max_offset(&self) -> usize {
+        self.width.saturating_sub(self.view)
+    }
+}
+
+/// Key of the `index`th code block of a text part
+fn block_key(part_id: &str, index: usize) -> String {
+    format!("{}:{}", part_id, index)
+}
+
+/// The lines inside each fenced code block of `lines`, by index. A block
+/// still streaming in runs to the end.
+fn code_blocks(lines: &[String]) -> Vec<Range<usize>> {
+    let mut blocks = Vec::new();
+    let mut open = None;
+    for (index, line) in lines.iter().enumerate() {
+        if line.trim_start().starts_with("```") {
+            match open.take() {
+                Some(start) => blocks.push(start..index),
+                None => open = Some(index + 1),
+            }
+        }
+    }
+    if let Some(start) = open {
+        blocks.push(start..lines.len());
+    }
+    blocks
+}
+
+/// Columns of the widest of `lines`
+fn widest_line(lines: &[String]) -> usize {
+    lines
+        .iter()
+        .map(|line| display_width(line))
+        .max()
+        .unwrap_or(0)
+}
+
+#[derive(Debug, Clone, Default)]
 struct StepGroup {
     text_parts: Vec<TextPart>,
     tool_parts: Vec<ToolPart>,
     file_parts: Vec<FilePart>,
-    is_completed: bool, // Track if this step has received a StepFinish
+    agent_parts: Vec<AgentPart>,
+    is_completed: bool,             // Track if this step has received a StepFinish
+    finish: Option<StepFinishPart>, // The StepFinish itself, for the step's footer
+}
+
+impl StepGroup {
+    fn has_parts(&self) -> bool {
+        !self.text_parts.is_empty()
+            || !self.tool_parts.is_empty()
+            || !self.file_parts.is_empty()
+            || !self.agent_parts.is_empty()
+    }
+
+    /// Parts a collapsed step hides
+    fn part_count(&self) -> usize {
+        self.text_parts.len()
+            + self.tool_parts.len()
+            + self.file_parts.len()
+            + self.agent_parts.len()
+    }
+
+    /// Milliseconds from the first part starting to the last one ending, when
+    /// the parts have times
+    fn duration_ms(&self) -> Option<f64> {
+        let text_times = self
+            .text_parts
+            .iter()
+            .filter_map(|text_part| text_part.time.as_ref())
+            .map(|time| (time.start, time.end));
+        let tool_times = self
+            .tool_parts
+            .iter()
+            .filter_map(|tool_part| match &*tool_part.state {
+                ToolState::Completed(completed) => {
+                    Some((completed.time.start, Some(completed.time.end)))
+                }
+                ToolState::Error(error) => Some((error.time.start, Some(error.time.end))),
+                _ => None,
+            });
+        let (start, end) = text_times.chain(tool_times).fold(
+            (None::<f64>, None::<f64>),
+            |(start, end), (part_start, part_end)| {
+                (
+                    Some(start.map_or(part_start, |start| start.min(part_start))),
+                    part_end.map_or(end, |part_end| {
+                        Some(end.map_or(part_end, |end| end.max(part_end)))
+                    }),
+                )
+            },
+        );
+        let duration = end? - start?;
+        (duration >= 0.0).then_some(duration)
+    }
 }
 
 impl MessageRenderer {
@@ -53,7 +298,18 @@ impl MessageRenderer {
             context,
             verbosity,
             step_rendering_mode: StepRenderingMode::Immediate,
-            expanded_tools: HashSet::new(),
+            expanded: HashSet::new(),
+            prompt_fold_after: None,
+            workspace_root: None,
+            streaming: false,
+            revealed: HashMap::new(),
+            indent_activity: false,
+            linked_paths: HashSet::new(),
+            tool_render: ToolRenderPolicies::default(),
+            glyphs: &UNICODE,
+            model_annotation: None,
+            tab_width: DEFAULT_TAB_WIDTH,
+            block_scroll: None,
         }
     }
 
@@ -115,6 +371,271 @@ impl MessageRenderer {
         self
     }
 
+    /// Render as a message that is still streaming: the cursor follows the
+    /// newest text, and text parts in `revealed` are cut to that many
+    /// characters
+    pub fn with_streaming(mut self, revealed: &HashMap<String, usize>) -> Self {
+        self.streaming = true;
+        self.revealed = self
+            .parts
+            .iter()
+            .filter_map(|part| match part {
+                Part::Text(text_part) => revealed
+                    .get(&text_part.id)
+                    .map(|shown| (text_part.id.clone(), *shown)),
+                _ => None,
+            })
+            .collect();
+        self
+    }
+
+    /// Indent tool calls, agent runs, attachments and steps in progress one
+    /// level under the message's text
+    pub fn with_activity_indent(mut self) -> Self {
+        self.indent_activity = true;
+        self
+    }
+
+    /// Fold each tool's output by its policy until expanded
+    pub fn with_tool_render(mut self, policies: &ToolRenderPolicies) -> Self {
+        self.tool_render = policies.clone();
+        self
+    }
+
+    /// Draw tool boxes, bullets and markers with `glyphs`
+    pub fn with_glyphs(mut self, glyphs: &'static Glyphs) -> Self {
+        self.glyphs = glyphs;
+        self
+    }
+
+    /// Show the tools and agent runs with these part IDs expanded, and the
+    /// prompts with these message IDs
+    pub fn with_expanded(mut self, ids: &HashSet<String>) -> Self {
+        self.expanded = ids.clone();
+        self
+    }
+
+    /// Fold prompts longer than `lines` lines to their first and last few,
+    /// in the summary view until expanded
+    pub fn with_prompt_collapse(mut self, lines: usize) -> Self {
+        self.prompt_fold_after = Some(lines);
+        self
+    }
+
+    /// Expand tabs to stops every `tab_width` columns
+    pub fn with_tab_width(mut self, tab_width: usize) -> Self {
+        self.tab_width = tab_width;
+        self
+    }
+
+    /// Note where bash commands ran when it wasn't `root`
+    pub fn with_workspace_root(mut self, root: Option<&str>) -> Self {
+        self.workspace_root = root.map(str::to_string);
+        self
+    }
+
+    /// Keep code blocks and expanded tool output to `width` columns rather
+    /// than wrapping them, each scrolled sideways by its offset in `offsets`
+    /// and labelled with the columns in view when it doesn't fit. Prose
+    /// still wraps.
+    pub fn with_block_scroll(
+        mut self,
+        width: Option<usize>,
+        offsets: &HashMap<String, usize>,
+    ) -> Self {
+        self.block_scroll = width.map(|width| (width, offsets.clone()));
+        self
+    }
+
+    /// Note which model wrote the reply `info` on its first line, when it
+    /// isn't `current_model`, so answers from before a switch can be told apart
+    pub fn with_model_annotation(mut self, info: &Message, current_model: &str) -> Self {
+        self.model_annotation = reply_model_label(info).filter(|label| label != current_model);
+        self
+    }
+
+    /// Show paths in the text as links once they're known to name a file.
+    /// URLs are shown as links either way.
+    pub fn with_links(mut self, paths: &LinkPaths) -> Self {
+        self.linked_paths = self
+            .parts
+            .iter()
+            .filter_map(|part| match part {
+                Part::Text(text_part) => Some(find_links(&text_part.text)),
+                _ => None,
+            })
+            .flatten()
+            .filter_map(|link| match link {
+                LinkTarget::File { path, .. } if paths.exists(&path) => Some(path),
+                _ => None,
+            })
+            .collect();
+        self
+    }
+
+    /// `line` in `style`, with its links underlined
+    fn linkified(&self, line: &str, in_code_block: bool, style: Style) -> Vec<Span<'static>> {
+        let mut spans = Vec::new();
+        let mut end = 0;
+        for link in find_links_in_line(line, in_code_block) {
+            let link_style = match &link.target {
+                LinkTarget::Url(_) => style.fg(Color::Blue),
+                LinkTarget::File { path, .. } if self.linked_paths.contains(path) => {
+                    style.fg(Color::Cyan)
+                }
+                LinkTarget::File { .. } => continue,
+            };
+            if link.range.start > end {
+                spans.push(Span::styled(line[end..link.range.start].to_string(), style));
+            }
+            spans.push(Span::styled(
+                line[link.range.clone()].to_string(),
+                link_style.add_modifier(Modifier::UNDERLINED),
+            ));
+            end = link.range.end;
+        }
+        if end < line.len() || spans.is_empty() {
+            spans.push(Span::styled(line[end..].to_string(), style));
+        }
+        spans
+    }
+
+    /// `lines` of tool or step activity, indented if asked to
+    fn activity(&self, lines: Vec<Line<'static>>) -> Vec<Line<'static>> {
+        if !self.indent_activity {
+            return lines;
+        }
+        lines
+            .into_iter()
+            .map(|line| {
+                let mut spans = vec![Span::raw(ACTIVITY_INDENT)];
+                spans.extend(line.spans);
+                Line::from(spans).style(line.style)
+            })
+            .collect()
+    }
+
+    /// The offset and the columns in view of the block `key`, `width`
+    /// columns wide with `margin` columns left of it, when blocks scroll and
+    /// it's too wide for its room
+    fn block_view(&self, key: &str, width: usize, margin: usize) -> Option<(usize, usize)> {
+        let (room, offsets) = self.block_scroll.as_ref()?;
+        let view = room.saturating_sub(margin);
+        if view == 0 || width <= view {
+            return None;
+        }
+        let offset = offsets.get(key).copied().unwrap_or(0).min(width - view);
+        Some((offset, view))
+    }
+
+    /// " cols 81–160", for the border of a block scrolled `offset` columns
+    fn block_ruler(&self, offset: usize, view: usize) -> Span<'static> {
+        let ruler = format!(" cols {}–{}", offset + 1, offset + view);
+        Span::styled(
+            self.glyphs.text(&ruler).into_owned(),
+            Style::default().fg(Color::DarkGray),
+        )
+    }
+
+    /// The text part as far as it's revealed
+    fn shown_text(&self, text_part: &TextPart) -> String {
+        match self.revealed.get(&text_part.id) {
+            Some(shown) => text_part.text.chars().take(*shown).collect(),
+            None => text_part.text.clone(),
+        }
+    }
+
+    /// Lines of the text part as far as it's revealed, made safe to draw
+    fn text_lines(&self, text_part: &TextPart) -> Vec<String> {
+        self.shown_text(text_part)
+            .lines()
+            .map(|line| printable_line(line, self.tab_width, self.glyphs.ascii))
+            .collect()
+    }
+
+    /// Lines of a tool's output made safe to draw
+    fn output_lines(&self, output: &str) -> Vec<String> {
+        output
+            .lines()
+            .map(|line| printable_line(line, self.tab_width, self.glyphs.ascii))
+            .collect()
+    }
+
+    /// Whether the tool shows all of its input and output
+    fn is_expanded(&self, tool_part: &ToolPart) -> bool {
+        self.verbosity == VerbosityLevel::Verbose || self.expanded.contains(&tool_part.id)
+    }
+
+    /// The output of a completed tool, as it's shown
+    fn tool_output(&self, tool_part: &ToolPart) -> Option<String> {
+        let ToolState::Completed(completed) = &*tool_part.state else {
+            return None;
+        };
+        Some(match tool_part.tool.as_str() {
+            "webfetch" => fetched_content(&completed.output, self.glyphs),
+            _ => completed.output.clone(),
+        })
+    }
+
+    /// Columns left of a line of tool output: the activity indent and the
+    /// box's side
+    fn tool_output_margin(&self) -> usize {
+        let indent = match self.indent_activity {
+            true => ACTIVITY_INDENT.len(),
+            false => 0,
+        };
+        indent + display_width(&self.tool_box().1) + 1
+    }
+
+    /// The code blocks and expanded tool outputs too wide for the width
+    /// given to `with_block_scroll`
+    pub fn scroll_blocks(&self) -> Vec<ScrollBlock> {
+        let mut blocks = Vec::new();
+        for part in &self.parts {
+            match part {
+                Part::Text(text_part) => {
+                    let lines = self.text_lines(text_part);
+                    for (index, range) in code_blocks(&lines).into_iter().enumerate() {
+                        let key = block_key(&text_part.id, index);
+                        let width = widest_line(&lines[range]);
+                        if let Some((_, view)) = self.block_view(&key, width, TEXT_PREFIX_WIDTH) {
+                            blocks.push(ScrollBlock { key, width, view });
+                        }
+                    }
+                }
+                Part::Tool(tool_part) if self.is_expanded(tool_part) => {
+                    let Some(output) = self.tool_output(tool_part) else {
+                        continue;
+                    };
+                    let width = widest_line(&self.output_lines(&output));
+                    let margin = self.tool_output_margin();
+                    if let Some((_, view)) = self.block_view(&tool_part.id, width, margin) {
+                        blocks.push(ScrollBlock {
+                            key: tool_part.id.clone(),
+                            width,
+                            view,
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+        blocks
+    }
+
+    /// The text part the streaming cursor follows
+    fn cursor_part_id(&self) -> Option<&str> {
+        if !self.streaming {
+            return None;
+        }
+        self.parts.iter().rev().find_map(|part| match part {
+            Part::Text(text_part) if !text_part.synthetic.unwrap_or(false) => {
+                Some(text_part.id.as_str())
+            }
+            _ => None,
+        })
+    }
+
     /// Create a renderer that automatically defers incomplete step rendering
     /// Uses OnStepFinish mode if container has incomplete steps, otherwise Immediate mode
     pub fn step_safe(
@@ -141,17 +662,18 @@ impl MessageRenderer {
         }
     }
 
-    fn format_tool_args(&self, tool_part: &ToolPart) -> String {
+    /// The tool's main argument on one line, like a bash command or a file name
+    pub fn format_tool_args(tool_part: &ToolPart) -> String {
         // Parse tool arguments from state.input
         match &*tool_part.state {
             ToolState::Completed(completed) => {
-                self.parse_tool_input(&tool_part.tool, &completed.input)
+                Self::parse_tool_input(&tool_part.tool, &completed.input)
             }
             ToolState::Running(running) => {
                 // Running state has Option<Option<Value>> input, flatten it
                 if let Some(Some(input_value)) = &running.input {
                     if let Some(input_obj) = input_value.as_object() {
-                        self.parse_tool_input_from_value(&tool_part.tool, input_obj)
+                        Self::parse_tool_input_from_value(&tool_part.tool, input_obj)
                     } else {
                         "".to_string()
                     }
@@ -159,7 +681,7 @@ impl MessageRenderer {
                     "".to_string()
                 }
             }
-            ToolState::Error(error) => self.parse_tool_input(&tool_part.tool, &error.input),
+            ToolState::Error(error) => Self::parse_tool_input(&tool_part.tool, &error.input),
             ToolState::Pending(_) => {
                 // Pending state has no input field
                 "".to_string()
@@ -168,7 +690,6 @@ impl MessageRenderer {
     }
 
     fn parse_tool_input(
-        &self,
         tool_name: &str,
         input: &std::collections::HashMap<String, serde_json::Value>,
     ) -> String {
@@ -277,7 +798,6 @@ impl MessageRenderer {
     }
 
     fn parse_tool_input_from_value(
-        &self,
         tool_name: &str,
         input: &serde_json::Map<String, serde_json::Value>,
     ) -> String {
@@ -511,60 +1031,69 @@ impl MessageRenderer {
                         format!("Found {} items", lines)
                     }
                     "bash" => {
-                        // Check metadata for exit code first
-                        if let Some(exit_code) = completed.metadata.get("exit") {
-                            if let Some(code) = exit_code.as_u64() {
-                                if code == 0 {
-                                    if output.contains("warning") || output.contains("Warning") {
-                                        "Command completed with warnings".to_string()
-                                    } else if output.trim().is_empty() {
-                                        "Command completed successfully".to_string()
-                                    } else {
-                                        // Show first meaningful line for successful commands
-                                        if let Some(first_line) = output.lines().find(|line| {
-                                            !line.trim().is_empty() && !line.trim().starts_with(' ')
-                                        }) {
-                                            self.truncate_output(first_line.trim(), 40)
-                                        } else {
+                        let summary = {
+                            // Check metadata for exit code first
+                            if let Some(exit_code) = completed.metadata.get("exit") {
+                                if let Some(code) = exit_code.as_u64() {
+                                    if code == 0 {
+                                        if output.contains("warning") || output.contains("Warning")
+                                        {
+                                            "Command completed with warnings".to_string()
+                                        } else if output.trim().is_empty() {
                                             "Command completed successfully".to_string()
+                                        } else {
+                                            // Show first meaningful line for successful commands
+                                            if let Some(first_line) = output.lines().find(|line| {
+                                                !line.trim().is_empty()
+                                                    && !line.trim().starts_with(' ')
+                                            }) {
+                                                self.truncate_output(first_line.trim(), 40)
+                                            } else {
+                                                "Command completed successfully".to_string()
+                                            }
                                         }
+                                    } else {
+                                        format!("Command failed (exit {})", code)
                                     }
                                 } else {
-                                    format!("Command failed (exit {})", code)
+                                    "Command completed".to_string()
                                 }
                             } else {
-                                "Command completed".to_string()
-                            }
-                        } else {
-                            // Fallback to output parsing
-                            if output.contains("error")
-                                || output.contains("Error")
-                                || output.contains("ERROR")
-                            {
-                                "Command failed".to_string()
-                            } else if output.contains("warning") || output.contains("Warning") {
-                                "Command completed with warnings".to_string()
-                            } else if output.trim().is_empty() {
-                                "Command completed".to_string()
-                            } else {
-                                // Show first meaningful line
-                                if let Some(first_line) =
-                                    output.lines().find(|line| !line.trim().is_empty())
+                                // Fallback to output parsing
+                                if output.contains("error")
+                                    || output.contains("Error")
+                                    || output.contains("ERROR")
                                 {
-                                    self.truncate_output(first_line, 40)
-                                } else {
+                                    "Command failed".to_string()
+                                } else if output.contains("warning") || output.contains("Warning") {
+                                    "Command completed with warnings".to_string()
+                                } else if output.trim().is_empty() {
                                     "Command completed".to_string()
+                                } else {
+                                    // Show first meaningful line
+                                    if let Some(first_line) =
+                                        output.lines().find(|line| !line.trim().is_empty())
+                                    {
+                                        self.truncate_output(first_line, 40)
+                                    } else {
+                                        "Command completed".to_string()
+                                    }
                                 }
                             }
+                        };
+                        match command_directory(&completed.metadata, self.workspace_root.as_deref())
+                        {
+                            Some(directory) => {
+                                format!("{} {} in {}", summary, self.glyphs.separator, directory)
+                            }
+                            None => summary,
                         }
                     }
-                    "webfetch" => {
-                        if output.len() > 100 {
-                            format!("Fetched {} chars", output.len())
-                        } else {
-                            "Content fetched".to_string()
-                        }
-                    }
+                    "webfetch" => webfetch_summary(
+                        output,
+                        completed.input.get("url").and_then(|v| v.as_str()),
+                        &completed.metadata,
+                    ),
                     _ => {
                         // Generic truncated output
                         self.truncate_output(output, 50)
@@ -577,9 +1106,10 @@ impl MessageRenderer {
         }
     }
 
-    fn truncate_output(&self, text: &str, max_len: usize) -> String {
-        if text.len() > max_len {
-            format!("{}...", &text[..max_len])
+    fn truncate_output(&self, text: &str, max_width: usize) -> String {
+        let kept = truncate_to_width(text, max_width);
+        if kept.len() < text.len() {
+            format!("{}...", kept)
         } else {
             text.to_string()
         }
@@ -609,10 +1139,10 @@ impl MessageRenderer {
                             todo.get("status").and_then(|v| v.as_str()),
                         ) {
                             let checkbox = match status {
-                                "completed" => "☒",
-                                "in_progress" => "◐",
-                                "cancelled" => "☒",
-                                _ => "☐",
+                                "completed" => self.glyphs.todo_done,
+                                "in_progress" => self.glyphs.todo_in_progress,
+                                "cancelled" => self.glyphs.todo_done,
+                                _ => self.glyphs.todo_pending,
                             };
 
                             let checkbox_color = match status {
@@ -640,7 +1170,10 @@ impl MessageRenderer {
                     // Fallback: show that todos were updated but couldn't parse
                     lines.push(Line::from(vec![
                         Span::styled("     ".to_string(), Style::default()),
-                        Span::styled("⎿ ".to_string(), Style::default().fg(Color::Gray)),
+                        Span::styled(
+                            format!("{} ", self.glyphs.tool_result),
+                            Style::default().fg(Color::Gray),
+                        ),
                         Span::styled(
                             "Todo list updated".to_string(),
                             Style::default().fg(Color::Gray),
@@ -651,7 +1184,10 @@ impl MessageRenderer {
                 // Fallback for non-JSON output
                 lines.push(Line::from(vec![
                     Span::styled("     ".to_string(), Style::default()),
-                    Span::styled("⎿ ".to_string(), Style::default().fg(Color::Gray)),
+                    Span::styled(
+                        format!("{} ", self.glyphs.tool_result),
+                        Style::default().fg(Color::Gray),
+                    ),
                     Span::styled(
                         "Todo list updated".to_string(),
                         Style::default().fg(Color::Gray),
@@ -669,13 +1205,13 @@ impl MessageRenderer {
 
         // Status-based bullet point color
         let bullet_color = self.get_tool_status_color(&*tool_part.state);
-        let tool_args = self.format_tool_args(tool_part);
+        let tool_args = Self::format_tool_args(tool_part);
 
         // Tool call header
         let tool_header = if tool_args.is_empty() {
-            format!("● {}", tool_part.tool)
+            format!("{} {}", self.glyphs.tool, tool_part.tool)
         } else {
-            format!("● {}({})", tool_part.tool, tool_args)
+            format!("{} {}({})", self.glyphs.tool, tool_part.tool, tool_args)
         };
 
         lines.push(Line::from(vec![Span::styled(
@@ -684,13 +1220,20 @@ impl MessageRenderer {
         )]));
 
         // Result summary with tree connector
-        let result_summary = self.format_tool_result_summary(tool_part);
+        let result_summary = printable_line(
+            &self.format_tool_result_summary(tool_part),
+            self.tab_width,
+            self.glyphs.ascii,
+        );
         let summary_line = match (&self.context, &self.verbosity) {
             (MessageContext::Fullscreen, VerbosityLevel::Summary) => {
-                format!("  ⎿  {} (ctrl+r to expand)", result_summary)
+                format!(
+                    "  {}  {} (ctrl+r to expand)",
+                    self.glyphs.tool_result, result_summary
+                )
             }
             (MessageContext::Inline, _) | (_, VerbosityLevel::Verbose) => {
-                format!("  ⎿  {}", result_summary)
+                format!("  {}  {}", self.glyphs.tool_result, result_summary)
             }
         };
 
@@ -704,16 +1247,207 @@ impl MessageRenderer {
             lines.extend(self.render_todo_list_content(tool_part));
         }
 
-        // In verbose mode, show full tool output inline
-        if self.verbosity == VerbosityLevel::Verbose {
-            if let ToolState::Completed(completed) = &*tool_part.state {
-                lines.extend(self.render_full_tool_output(&completed.output));
+        // Expanded, the tool's input and all of its output show inline;
+        // otherwise the tool's policy decides how much output shows
+        let expanded = self.is_expanded(tool_part);
+        let policy = match expanded {
+            true => ToolRenderPolicy::Full,
+            false => self.tool_render.resolve(&tool_part.tool),
+        };
+        if expanded {
+            lines.extend(self.render_tool_input(tool_part));
+        }
+        if let Some(output) = self.tool_output(tool_part) {
+            // Expanded output keeps its lines whole, scrolling sideways instead
+            let block = expanded.then_some(tool_part.id.as_str());
+            match policy {
+                ToolRenderPolicy::SummaryOnly => {}
+                ToolRenderPolicy::Lines(max_lines) => {
+                    lines.extend(self.render_tool_output(&output, Some(max_lines), block))
+                }
+                ToolRenderPolicy::Full => {
+                    lines.extend(self.render_tool_output(&output, None, block))
+                }
+            }
+        }
+
+        lines
+    }
+
+    fn has_agent_part(&self, name: &str) -> bool {
+        self.parts
+            .iter()
+            .any(|part| matches!(part, Part::Agent(agent_part) if agent_part.name == name))
+    }
+
+    /// Latest task run of the named agent in this message
+    fn find_agent_task(&self, name: &str) -> Option<&ToolPart> {
+        self.parts.iter().rev().find_map(|part| match part {
+            Part::Tool(tool_part) if task_agent_name(tool_part).as_deref() == Some(name) => {
+                Some(tool_part.as_ref())
+            }
+            _ => None,
+        })
+    }
+
+    /// Collapsible section for a sub-agent run: a status header, the steps it
+    /// took when expanded, and its final summary once it completes
+    fn render_agent_section(
+        &self,
+        name: &str,
+        id: &str,
+        task: Option<&ToolPart>,
+    ) -> Vec<Line<'static>> {
+        let mut lines = Vec::new();
+        lines.push(Line::from(" "));
+
+        let status = AgentStatus::from_task(task);
+        let steps = task.map(agent_steps).unwrap_or_default();
+        let expanded = self.verbosity == VerbosityLevel::Verbose || self.expanded.contains(id);
+
+        let mut header = vec![Span::styled(
+            format!(
+                "{} agent: {} {} {}",
+                self.glyphs.agent,
+                name,
+                self.glyphs.dash,
+                status.label()
+            ),
+            Style::default().fg(status.color()),
+        )];
+        if !expanded && !steps.is_empty() && self.context == MessageContext::Fullscreen {
+            header.push(Span::styled(
+                " (ctrl+r to expand)",
+                Style::default().fg(Color::Gray),
+            ));
+        }
+        lines.push(Line::from(header));
+
+        if expanded {
+            if let Some(task) = task {
+                lines.extend(self.render_tool_input(task));
+            }
+            // The sub-agent's own steps, one indent level deeper
+            let nested = MessageRenderer::new(steps, self.context.clone(), self.verbosity)
+                .with_glyphs(self.glyphs)
+                .with_tab_width(self.tab_width);
+            for line in nested.render().lines {
+                let mut spans = vec![Span::raw("    ")];
+                spans.extend(line.spans);
+                lines.push(Line::from(spans));
+            }
+        }
+
+        let outcome = match task.map(|task| &*task.state) {
+            Some(ToolState::Completed(completed)) => Some((completed.output.trim(), Color::Gray)),
+            Some(ToolState::Error(error)) => Some((error.error.trim(), Color::Red)),
+            _ => None,
+        };
+        if let Some((text, color)) = outcome {
+            for (index, line) in text.lines().enumerate() {
+                let prefix = if index == 0 {
+                    self.glyphs.tool_result
+                } else {
+                    " "
+                };
+                lines.push(Line::from(Span::styled(
+                    format!("  {}  {}", prefix, line),
+                    Style::default().fg(color),
+                )));
             }
         }
 
         lines
     }
 
+    /// Boxed placeholder for a file attachment, since its content can't be
+    /// shown in the terminal
+    pub fn render_file_part(file_part: &FilePart, glyphs: &Glyphs) -> Vec<Line<'static>> {
+        let label = format!(" {} ", format_file_part(file_part, glyphs));
+        let inner_width = Line::from(label.as_str()).width();
+        let border_style = Style::default().fg(Color::DarkGray);
+
+        vec![
+            Line::from(" "),
+            Line::from(Span::styled(
+                format!(
+                    "  {}{}{}",
+                    glyphs.box_top_left,
+                    glyphs.rule(inner_width),
+                    glyphs.box_top_right
+                ),
+                border_style,
+            )),
+            Line::from(vec![
+                Span::styled(format!("  {}", glyphs.box_vertical), border_style),
+                Span::styled(label, Style::default().fg(Color::Cyan)),
+                Span::styled(glyphs.box_vertical, border_style),
+            ]),
+            Line::from(Span::styled(
+                format!(
+                    "  {}{}{}",
+                    glyphs.box_bottom_left,
+                    glyphs.rule(inner_width),
+                    glyphs.box_bottom_right
+                ),
+                border_style,
+            )),
+        ]
+    }
+
+    /// A user message: its text as typed, long prompts folded unless
+    /// expanded, and its attachments
+    pub fn render_user_parts(&self, text_color: Color) -> Vec<Line<'static>> {
+        let mut lines = Vec::new();
+        for part in &self.parts {
+            match part {
+                Part::Text(text_part) => {
+                    let text_lines: Vec<&str> = text_part.text.lines().collect();
+                    let prompt_line = |line: &str| {
+                        Line::from(vec![
+                            Span::raw(" "),
+                            Span::styled(line.to_string(), Style::default().fg(text_color)),
+                        ])
+                    };
+                    let fold = self
+                        .prompt_fold_after
+                        .filter(|_| {
+                            self.verbosity == VerbosityLevel::Summary
+                                && !self.expanded.contains(&text_part.message_id)
+                        })
+                        .and_then(|collapse_lines| prompt_fold(text_lines.len(), collapse_lines));
+                    let Some(fold) = fold else {
+                        lines.extend(text_lines.into_iter().map(prompt_line));
+                        continue;
+                    };
+                    lines.extend(text_lines[..fold.head].iter().copied().map(prompt_line));
+                    let hint = match self.context {
+                        MessageContext::Fullscreen => " (ctrl+r to expand)",
+                        MessageContext::Inline => "",
+                    };
+                    lines.push(Line::from(Span::styled(
+                        format!(
+                            " {} {} lines hidden{}",
+                            self.glyphs.ellipsis, fold.hidden, hint
+                        ),
+                        Style::default().fg(Color::DarkGray),
+                    )));
+                    lines.extend(
+                        text_lines[text_lines.len() - fold.tail..]
+                            .iter()
+                            .copied()
+                            .map(prompt_line),
+                    );
+                }
+                Part::File(file_part) => {
+                    lines.extend(Self::render_file_part(file_part, self.glyphs));
+                }
+                _ => {}
+            }
+        }
+        lines
+    }
+
     fn render_text_part(&self, text_part: &TextPart, is_grouped: bool) -> Vec<Line<'static>> {
         let mut lines = Vec::new();
         lines.push(Line::from(" "));
@@ -723,7 +1457,8 @@ impl MessageRenderer {
             return lines;
         }
 
-        let content = text_part.text.clone();
+        // Escapes in the text would be acted on by the terminal
+        let text_lines = self.text_lines(text_part);
 
         // Determine prefix based on context
         let prefix = if is_grouped {
@@ -732,15 +1467,62 @@ impl MessageRenderer {
             "> " // Bullet for standalone text
         };
 
+        // Code blocks too wide to show whole, with where each is scrolled to
+        let scrolled: Vec<(Range<usize>, (usize, usize))> = code_blocks(&text_lines)
+            .into_iter()
+            .enumerate()
+            .filter_map(|(index, range)| {
+                let width = widest_line(&text_lines[range.clone()]);
+                let key = block_key(&text_part.id, index);
+                Some((range, self.block_view(&key, width, TEXT_PREFIX_WIDTH)?))
+            })
+            .collect();
+
         // Split content into lines and apply prefix
-        for line in content.lines() {
+        let mut in_code_block = false;
+        for (index, line) in text_lines.iter().enumerate() {
+            if line.trim_start().starts_with("```") {
+                in_code_block = !in_code_block;
+            }
             if line.trim().is_empty() {
                 lines.push(Line::from(" "));
-            } else {
-                lines.push(Line::from(vec![
+                continue;
+            }
+            let mut shown = line.clone();
+            let mut ruler = None;
+            for (range, (offset, view)) in &scrolled {
+                if index + 1 == range.start {
+                    ruler = Some(self.block_ruler(*offset, *view));
+                } else if range.contains(&index) {
+                    shown = clip_columns(line, *offset, *view);
+                }
+            }
+            let mut spans = vec![Span::styled(
+                prefix.to_string(),
+                Style::default().fg(Color::White),
+            )];
+            spans.extend(self.linkified(&shown, in_code_block, Style::default().fg(Color::White)));
+            spans.extend(ruler);
+            lines.push(Line::from(spans));
+        }
+
+        if self.cursor_part_id() == Some(text_part.id.as_str()) {
+            let cursor = Span::styled(
+                self.glyphs.streaming_cursor,
+                Style::default()
+                    .fg(Color::White)
+                    .add_modifier(Modifier::SLOW_BLINK),
+            );
+            // After the last character, or on a new line once one was started
+            let content = self.shown_text(text_part);
+            match lines.last_mut() {
+                Some(last) if !content.is_empty() && !content.ends_with('\n') => {
+                    last.spans.push(cursor)
+                }
+                _ => lines.push(Line::from(vec![
                     Span::styled(prefix.to_string(), Style::default().fg(Color::White)),
-                    Span::styled(line.to_string(), Style::default().fg(Color::White)),
-                ]));
+                    cursor,
+                ])),
             }
         }
 
@@ -749,12 +1531,7 @@ impl MessageRenderer {
 
     fn group_parts_into_steps(&self) -> Vec<StepGroup> {
         let mut groups = Vec::new();
-        let mut current_group = StepGroup {
-            text_parts: Vec::new(),
-            tool_parts: Vec::new(),
-            file_parts: Vec::new(),
-            is_completed: false,
-        };
+        let mut current_group = StepGroup::default();
         let mut in_step = false;
 
         for part in &self.parts {
@@ -763,37 +1540,22 @@ impl MessageRenderer {
                     // Start a new step group
                     if in_step {
                         // Finish previous group (not completed if no StepFinish was seen)
-                        if !current_group.text_parts.is_empty()
-                            || !current_group.tool_parts.is_empty()
-                            || !current_group.file_parts.is_empty()
-                        {
+                        if current_group.has_parts() {
                             groups.push(current_group);
                         }
                     }
-                    current_group = StepGroup {
-                        text_parts: Vec::new(),
-                        tool_parts: Vec::new(),
-                        file_parts: Vec::new(),
-                        is_completed: false,
-                    };
+                    current_group = StepGroup::default();
                     in_step = true;
                 }
-                Part::StepFinish(_) => {
+                Part::StepFinish(finish) => {
                     // Finish current step group and mark as completed
                     if in_step {
                         current_group.is_completed = true;
-                        if !current_group.text_parts.is_empty()
-                            || !current_group.tool_parts.is_empty()
-                            || !current_group.file_parts.is_empty()
-                        {
+                        current_group.finish = Some((**finish).clone());
+                        if current_group.has_parts() {
                             groups.push(current_group);
                         }
-                        current_group = StepGroup {
-                            text_parts: Vec::new(),
-                            tool_parts: Vec::new(),
-                            file_parts: Vec::new(),
-                            is_completed: false,
-                        };
+                        current_group = StepGroup::default();
                     }
                     in_step = false;
                 }
@@ -834,27 +1596,14 @@ impl MessageRenderer {
                     synthetic: None,
                     time: None,
                 }),
-                Part::Agent(agent_part) => current_group.text_parts.push(TextPart {
-                    id: agent_part.id.clone(),
-                    session_id: agent_part.session_id.clone(),
-                    message_id: agent_part.message_id.clone(),
-                    text: format!(
-                        "TODO(agent) name={} source={}",
-                        agent_part.name,
-                        serde_json::to_string(&agent_part.source).unwrap_or("-".to_string())
-                    ),
-                    synthetic: None,
-                    time: None,
-                }),
+                Part::Agent(agent_part) => {
+                    current_group.agent_parts.push((**agent_part).clone());
+                }
             }
         }
 
         // Don't forget the last group if we're still in a step
-        if in_step
-            && (!current_group.text_parts.is_empty()
-                || !current_group.tool_parts.is_empty()
-                || !current_group.file_parts.is_empty())
-        {
+        if in_step && current_group.has_parts() {
             groups.push(current_group);
         }
 
@@ -873,13 +1622,16 @@ impl MessageRenderer {
         if !should_render_step {
             // For incomplete steps in OnStepFinish mode, show a placeholder
             lines.push(Line::from(" "));
-            lines.push(Line::from(vec![
-                Span::styled("⏳ ".to_string(), Style::default().fg(Color::Yellow)),
+            lines.extend(self.activity(vec![Line::from(vec![
+                Span::styled(
+                    format!("{} ", self.glyphs.waiting),
+                    Style::default().fg(Color::Yellow),
+                ),
                 Span::styled(
                     "Step in progress...".to_string(),
                     Style::default().fg(Color::Gray),
                 ),
-            ]));
+            ])]));
             return lines;
         }
 
@@ -900,38 +1652,256 @@ impl MessageRenderer {
             }
         }
 
+        // Sub-agent runs, each with the task that runs it
+        for agent_part in &group.agent_parts {
+            let task = self.find_agent_task(&agent_part.name);
+            lines.extend(self.activity(self.render_agent_section(
+                &agent_part.name,
+                &agent_part.id,
+                task,
+            )));
+        }
+
         // Tool parts rendering
         for tool_part in &group.tool_parts {
-            lines.extend(self.render_tool_part(tool_part));
+            match task_agent_name(tool_part) {
+                // Shown in its agent's section
+                Some(name) if self.has_agent_part(&name) => {}
+                Some(name) => lines.extend(self.activity(self.render_agent_section(
+                    &name,
+                    &tool_part.id,
+                    Some(tool_part),
+                ))),
+                None => lines.extend(self.activity(self.render_tool_part(tool_part))),
+            }
+        }
+
+        // File parts rendering
+        for file_part in &group.file_parts {
+            lines.extend(self.activity(Self::render_file_part(file_part, self.glyphs)));
         }
 
         lines
     }
 
-    fn render_full_tool_output(&self, output: &str) -> Vec<Line<'static>> {
-        let mut lines = Vec::new();
+    /// Whether a finished step before the latest shows only its footer: in
+    /// the fullscreen summary, until it or one of its tools is expanded
+    fn collapses_step(&self, group: &StepGroup, finish: &StepFinishPart) -> bool {
+        self.context == MessageContext::Fullscreen
+            && self.verbosity == VerbosityLevel::Summary
+            && !self.expanded.contains(&finish.id)
+            && !group
+                .tool_parts
+                .iter()
+                .any(|tool_part| self.expanded.contains(&tool_part.id))
+    }
+
+    /// "— step 2 finished · 8.1s · 3 tools —", and how much is hidden when
+    /// the step is collapsed
+    fn render_step_footer(
+        &self,
+        number: usize,
+        group: &StepGroup,
+        collapsed: bool,
+    ) -> Vec<Line<'static>> {
+        let glyphs = self.glyphs;
+        let mut details = vec![format!("step {} finished", number)];
+        details.extend(group.duration_ms().map(format_step_duration));
+        match group.tool_parts.len() {
+            0 => {}
+            1 => details.push("1 tool".to_string()),
+            tools => details.push(format!("{} tools", tools)),
+        }
+        if let Some(finish) = &group.finish {
+            let tokens = finish.tokens.input + finish.tokens.output + finish.tokens.reasoning;
+            if tokens > 0.0 {
+                details.push(format!("{} tokens", format_tokens(tokens as usize)));
+            }
+            if finish.cost > 0.0 {
+                details.push(format!("${:.2}", finish.cost));
+            }
+        }
+        if collapsed {
+            details.push(format!("{} hidden", group.part_count()));
+        }
+        let mut footer = vec![Span::styled(
+            format!(
+                "{} {} {}",
+                glyphs.dash,
+                details.join(&format!(" {} ", glyphs.separator)),
+                glyphs.dash
+            ),
+            Style::default().fg(Color::DarkGray),
+        )];
+        if collapsed {
+            footer.push(Span::styled(
+                " (ctrl+r to expand)",
+                Style::default().fg(Color::Gray),
+            ));
+        }
+        let mut lines = vec![Line::from(" ")];
+        lines.extend(self.activity(vec![Line::from(footer)]));
+        lines
+    }
+
+    /// The tool's input, with the most important keys first
+    fn ordered_tool_input(&self, tool_part: &ToolPart) -> Vec<(String, serde_json::Value)> {
+        let mut input: Vec<(String, serde_json::Value)> = match &*tool_part.state {
+            ToolState::Completed(completed) => completed.input.clone().into_iter().collect(),
+            ToolState::Error(error) => error.input.clone().into_iter().collect(),
+            ToolState::Running(running) => match &running.input {
+                Some(Some(serde_json::Value::Object(input))) => input.clone().into_iter().collect(),
+                _ => Vec::new(),
+            },
+            ToolState::Pending(_) => Vec::new(),
+        };
+
+        // The description the model gave a command may only be in its metadata
+        if tool_part.tool == "bash" && !input.iter().any(|(key, _)| key == "description") {
+            let description = tool_metadata(&tool_part.state)
+                .and_then(|metadata| metadata.get("description"))
+                .filter(|description| description.is_string());
+            if let Some(description) = description {
+                input.push(("description".to_string(), description.clone()));
+            }
+        }
+
+        let key_order = tool_input_key_order(&tool_part.tool);
+        input.sort_by(|(a, _), (b, _)| {
+            let rank = |key: &str| {
+                key_order
+                    .iter()
+                    .position(|ordered| *ordered == key)
+                    .unwrap_or(key_order.len())
+            };
+            rank(a).cmp(&rank(b)).then_with(|| a.cmp(b))
+        });
+        input
+    }
+
+    fn render_tool_input(&self, tool_part: &ToolPart) -> Vec<Line<'static>> {
+        let mut lines = Vec::new();
+        let input = self.ordered_tool_input(tool_part);
+        if input.is_empty() {
+            return lines;
+        }
+
+        let border_style = Style::default().fg(Color::DarkGray);
+        let (open, side, close) = self.tool_box();
+        lines.push(Line::from(vec![Span::styled(
+            format!("{} Input:", open),
+            border_style,
+        )]));
+
+        for (key, value) in input {
+            let value = format_tool_input_value(&value, self.glyphs);
+            let mut value_lines = value.lines();
+            match (value_lines.next(), value_lines.next()) {
+                // Single line values sit next to their key
+                (first, None) => {
+                    lines.push(Line::from(vec![
+                        Span::styled(format!("{} ", side), border_style),
+                        Span::styled(format!("{}: ", key), Style::default().fg(Color::Cyan)),
+                        Span::styled(
+                            first.unwrap_or_default().to_string(),
+                            Style::default().fg(Color::Gray),
+                        ),
+                    ]));
+                }
+                // Multi-line values go underneath, indented
+                _ => {
+                    lines.push(Line::from(vec![
+                        Span::styled(format!("{} ", side), border_style),
+                        Span::styled(format!("{}:", key), Style::default().fg(Color::Cyan)),
+                    ]));
+                    for line in value.lines() {
+                        lines.push(Line::from(vec![
+                            Span::styled(format!("{}   ", side), border_style),
+                            Span::styled(
+                                printable_line(line, self.tab_width, self.glyphs.ascii),
+                                Style::default().fg(Color::Gray),
+                            ),
+                        ]));
+                    }
+                }
+            }
+        }
+
+        lines.push(Line::from(vec![Span::styled(close, border_style)]));
+        lines
+    }
+
+    /// The opening, side and closing of the box around a tool's input and
+    /// output, indented under the tool's header
+    fn tool_box(&self) -> (String, String, String) {
+        let glyphs = self.glyphs;
+        (
+            format!("    {}{}", glyphs.box_top_left, glyphs.box_horizontal),
+            format!("    {}", glyphs.box_vertical),
+            format!("    {}{}", glyphs.box_bottom_left, glyphs.box_horizontal),
+        )
+    }
+
+    /// The output in a box, cut after `max_lines` lines with a count of
+    /// the rest when given. Output that's the scrolling block `block` shows
+    /// the columns it's scrolled to.
+    fn render_tool_output(
+        &self,
+        output: &str,
+        max_lines: Option<usize>,
+        block: Option<&str>,
+    ) -> Vec<Line<'static>> {
+        let mut lines = Vec::new();
 
         if output.trim().is_empty() {
             return lines;
         }
 
+        let output_lines = self.output_lines(output);
+        let total = output_lines.len();
+        let shown = max_lines.unwrap_or(total).min(total);
+        let scrolled = block.and_then(|key| {
+            self.block_view(key, widest_line(&output_lines), self.tool_output_margin())
+        });
+
         // Add separator line
-        lines.push(Line::from(vec![Span::styled(
-            "    ┌─ Full Output:",
+        let (open, side, close) = self.tool_box();
+        let mut separator = vec![Span::styled(
+            match shown < total {
+                true => format!("{} Output:", open),
+                false => format!("{} Full Output:", open),
+            },
             Style::default().fg(Color::DarkGray),
-        )]));
+        )];
+        separator.extend(scrolled.map(|(offset, view)| self.block_ruler(offset, view)));
+        lines.push(Line::from(separator));
 
         // Render each line of output with proper indentation
-        for line in output.lines() {
+        for line in output_lines.into_iter().take(shown) {
+            let line = match scrolled {
+                Some((offset, view)) => clip_columns(&line, offset, view),
+                None => line,
+            };
+            lines.push(Line::from(vec![
+                Span::styled(format!("{} ", side), Style::default().fg(Color::DarkGray)),
+                Span::styled(line, Style::default().fg(Color::Gray)),
+            ]));
+        }
+        if shown < total {
             lines.push(Line::from(vec![
-                Span::styled("    │ ".to_string(), Style::default().fg(Color::DarkGray)),
-                Span::styled(line.to_string(), Style::default().fg(Color::Gray)),
+                Span::styled(format!("{} ", side), Style::default().fg(Color::DarkGray)),
+                Span::styled(
+                    format!("{} {} more lines", self.glyphs.ellipsis, total - shown),
+                    Style::default()
+                        .fg(Color::DarkGray)
+                        .add_modifier(Modifier::ITALIC),
+                ),
             ]));
         }
 
         // Add closing line
         lines.push(Line::from(vec![Span::styled(
-            "    └─",
+            close,
             Style::default().fg(Color::DarkGray),
         )]));
 
@@ -946,10 +1916,8 @@ impl MessageRenderer {
         if step_groups.is_empty() {
             // Create a single group from all parts to apply mixed grouping logic
             let mut ungrouped_group = StepGroup {
-                text_parts: Vec::new(),
-                tool_parts: Vec::new(),
-                file_parts: Vec::new(),
                 is_completed: true, // Ungrouped parts are always considered "completed"
+                ..StepGroup::default()
             };
 
             for part in &self.parts {
@@ -963,27 +1931,325 @@ impl MessageRenderer {
                     Part::File(file_part) => {
                         ungrouped_group.file_parts.push((**file_part).clone());
                     }
+                    Part::Agent(agent_part) => {
+                        ungrouped_group.agent_parts.push((**agent_part).clone());
+                    }
                     _ => {} // Skip other part types when ungrouped
                 }
             }
 
             lines.extend(self.render_step_group(&ungrouped_group));
         } else {
-            // Render grouped parts
-            for group in step_groups {
-                lines.extend(self.render_step_group(&group));
+            // Render grouped parts, each finished one with its footer
+            let latest = step_groups.len() - 1;
+            for (index, group) in step_groups.iter().enumerate() {
+                let Some(finish) = &group.finish else {
+                    lines.extend(self.render_step_group(group));
+                    continue;
+                };
+                match index < latest && self.collapses_step(group, finish) {
+                    true => lines.extend(self.render_step_footer(index + 1, group, true)),
+                    false => {
+                        lines.extend(self.render_step_group(group));
+                        lines.extend(self.render_step_footer(index + 1, group, false));
+                    }
+                }
             }
         }
 
         Text::from(lines)
     }
 
+    /// `render`, with the model annotation right-aligned in `width` columns
+    /// when there is one, or just after the first line without a width
+    pub fn render_to_width(&self, width: Option<usize>) -> Text<'static> {
+        let mut text = self.render();
+        if let Some(label) = &self.model_annotation {
+            let aside = Span::styled(label.clone(), Style::default().fg(Color::DarkGray));
+            text.lines = annotate_first_line(text.lines, aside, width);
+        }
+        text
+    }
+
     pub fn height(&self) -> u16 {
         let text = self.render();
         text.lines.len() as u16
     }
 }
 
+/// `provider/model` of a reply, once its info has arrived
+pub fn reply_model_label(info: &Message) -> Option<String> {
+    match info {
+        Message::Assistant(message) if message.model_id != UNKNOWN_MODEL => {
+            Some(format!("{}/{}", message.provider_id, message.model_id))
+        }
+        _ => None,
+    }
+}
+
+/// `lines` with `aside` at the right end of the first, or on a line of its
+/// own above when the first leaves no room for it
+fn annotate_first_line(
+    mut lines: Vec<Line<'static>>,
+    aside: Span<'static>,
+    width: Option<usize>,
+) -> Vec<Line<'static>> {
+    let first_width = lines.first().map_or(0, Line::width);
+    let gap = match width {
+        Some(width) => width.checked_sub(first_width + aside.width()),
+        None => Some(2),
+    };
+    match (gap, lines.first_mut()) {
+        (Some(gap), Some(first)) if gap >= 2 || first_width == 0 => {
+            first.spans.push(Span::raw(" ".repeat(gap)));
+            first.spans.push(aside);
+        }
+        _ => {
+            let pad = width.map_or(0, |width| width.saturating_sub(aside.width()));
+            lines.insert(0, Line::from(vec![Span::raw(" ".repeat(pad)), aside]));
+        }
+    }
+    lines
+}
+
+/// Agent run by a task tool call, if it is one
+fn task_agent_name(tool_part: &ToolPart) -> Option<String> {
+    if tool_part.tool != TASK_TOOL {
+        return None;
+    }
+    let input = match &*tool_part.state {
+        ToolState::Completed(completed) => completed.input.get(TASK_AGENT_KEY),
+        ToolState::Error(error) => error.input.get(TASK_AGENT_KEY),
+        ToolState::Running(running) => match &running.input {
+            Some(Some(serde_json::Value::Object(input))) => input.get(TASK_AGENT_KEY),
+            _ => None,
+        },
+        ToolState::Pending(_) => None,
+    };
+    Some(
+        input
+            .and_then(|value| value.as_str())
+            .unwrap_or(TASK_TOOL)
+            .to_string(),
+    )
+}
+
+/// What a tool reported about its run, once it's started
+fn tool_metadata(state: &ToolState) -> Option<&HashMap<String, serde_json::Value>> {
+    match state {
+        ToolState::Running(running) => running.metadata.as_ref(),
+        ToolState::Completed(completed) => Some(&completed.metadata),
+        ToolState::Error(error) => error.metadata.as_ref(),
+        ToolState::Pending(_) => None,
+    }
+}
+
+/// Where a command ran, relative to the workspace root, when that's not the
+/// root itself. Outside the workspace it's the whole path; without a known
+/// root or a `cwd` in the metadata there's nothing to tell.
+fn command_directory(
+    metadata: &HashMap<String, serde_json::Value>,
+    workspace_root: Option<&str>,
+) -> Option<String> {
+    let cwd = Path::new(metadata.get("cwd")?.as_str()?);
+    match cwd.strip_prefix(workspace_root?) {
+        Ok(relative) if relative.as_os_str().is_empty() => None,
+        Ok(relative) => Some(relative.display().to_string()),
+        Err(_) => Some(cwd.display().to_string()),
+    }
+}
+
+/// Parts of the sub-agent's own run, which the task reports in its metadata
+/// as they stream in
+fn agent_steps(task: &ToolPart) -> Vec<Part> {
+    tool_metadata(&task.state)
+        .and_then(|metadata| metadata.get("summary"))
+        .and_then(|summary| summary.as_array())
+        .map(|steps| {
+            steps
+                .iter()
+                .filter_map(|step| serde_json::from_value(step.clone()).ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Longest fetched page shown when a webfetch is expanded, in characters
+const WEBFETCH_MAX_RENDERED_CHARS: usize = 4000;
+
+/// Summary of a webfetch, like "Fetched 12345 chars from https://example.com/docs
+/// (HTTP 200)". The URL is the one the fetch ended at, after any redirects,
+/// when the metadata has it.
+fn webfetch_summary(
+    output: &str,
+    requested_url: Option<&str>,
+    metadata: &HashMap<String, serde_json::Value>,
+) -> String {
+    let mut summary = format!("Fetched {} chars", output.chars().count());
+    let url = metadata
+        .get("url")
+        .and_then(|url| url.as_str())
+        .or(requested_url);
+    if let Some(url) = url {
+        summary.push_str(&format!(" from {}", url));
+    }
+    if let Some(status) = metadata.get("status").and_then(|status| status.as_u64()) {
+        summary.push_str(&format!(" (HTTP {})", status));
+    }
+    summary
+}
+
+/// A fetched page for reading in the terminal: markdown links become
+/// "text (url)" and long pages are cut at `WEBFETCH_MAX_RENDERED_CHARS`
+fn fetched_content(output: &str, glyphs: &Glyphs) -> String {
+    let text = render_links(output);
+    let total = text.chars().count();
+    if total <= WEBFETCH_MAX_RENDERED_CHARS {
+        return text;
+    }
+    let kept: String = text.chars().take(WEBFETCH_MAX_RENDERED_CHARS).collect();
+    format!(
+        "{}\n{} [{} more chars]",
+        kept.trim_end(),
+        glyphs.ellipsis,
+        total - WEBFETCH_MAX_RENDERED_CHARS
+    )
+}
+
+/// Rewrite markdown links and images as "text (url)", and autolinks as the
+/// bare URL, since the terminal can't follow them
+fn render_links(text: &str) -> String {
+    let mut rendered = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(['[', '<']) {
+        let (before, from) = rest.split_at(start);
+        rendered.push_str(before);
+        match parse_link(from) {
+            Some((link, consumed)) => {
+                // An image's `!` was already copied
+                if from.starts_with('[') && rendered.ends_with('!') {
+                    rendered.pop();
+                }
+                rendered.push_str(&link);
+                rest = &from[consumed..];
+            }
+            None => {
+                rendered.push_str(&from[..1]);
+                rest = &from[1..];
+            }
+        }
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+/// The link at the start of `text` as it should be shown, and how many bytes
+/// of `text` it spans
+fn parse_link(text: &str) -> Option<(String, usize)> {
+    if let Some(autolink) = text.strip_prefix('<') {
+        let end = autolink.find('>')?;
+        let url = &autolink[..end];
+        let is_url = url.starts_with("http://") || url.starts_with("https://");
+        return (is_url && !url.contains(char::is_whitespace)).then(|| (url.to_string(), end + 2));
+    }
+
+    let label_end = text.find("](")?;
+    let label = &text[1..label_end];
+    if label.contains(['[', ']', '\n']) {
+        return None;
+    }
+    // URLs may hold balanced parentheses, as Wikipedia's do
+    let target = &text[label_end + 2..];
+    let mut depth = 0;
+    let target_end = target.char_indices().find_map(|(i, c)| match c {
+        '(' => {
+            depth += 1;
+            None
+        }
+        ')' if depth == 0 => Some(i),
+        ')' => {
+            depth -= 1;
+            None
+        }
+        _ => None,
+    })?;
+    // Drop a link title, as in [text](url "title")
+    let url = target[..target_end].split_whitespace().next()?;
+    let consumed = label_end + 2 + target_end + 1;
+    let shown = if label.trim().is_empty() || label == url {
+        url.to_string()
+    } else {
+        format!("{} ({})", label, url)
+    };
+    Some((shown, consumed))
+}
+
+pub fn is_image_mime(mime: &str) -> bool {
+    mime.starts_with("image/")
+}
+
+/// One-line description of a file part, e.g. "🖼 screenshot.png · image/png · 1.2 MB".
+/// The size is only known for inline `data:` URLs.
+pub fn format_file_part(file_part: &FilePart, glyphs: &Glyphs) -> String {
+    let icon = if is_image_mime(&file_part.mime) {
+        glyphs.image
+    } else {
+        glyphs.attachment
+    };
+    let name = file_part
+        .filename
+        .clone()
+        .or_else(|| {
+            file_part
+                .url
+                .rsplit('/')
+                .next()
+                .filter(|name| !name.is_empty() && !file_part.url.starts_with("data:"))
+                .map(str::to_string)
+        })
+        .unwrap_or_else(|| "attachment".to_string());
+
+    let mut label = format!("{} {} {} {}", icon, name, glyphs.separator, file_part.mime);
+    if let Some(size) = data_url_size(&file_part.url) {
+        label.push_str(&format!(" {} {}", glyphs.separator, humanize_bytes(size)));
+    }
+    label
+}
+
+/// Decoded size of a base64 `data:` URL's payload
+pub fn data_url_size(url: &str) -> Option<u64> {
+    let (header, payload) = url.strip_prefix("data:")?.split_once(',')?;
+    if !header.ends_with(";base64") {
+        return Some(payload.len() as u64);
+    }
+    let payload = payload.trim_end();
+    let padding = payload.chars().rev().take_while(|&c| c == '=').count();
+    Some((payload.len() / 4 * 3).saturating_sub(padding) as u64)
+}
+
+/// Byte count in the largest unit that keeps it at or above 1, e.g. "1.2 MB"
+pub fn humanize_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+/// "8.1s" under a minute, then as the working time is
+fn format_step_duration(ms: f64) -> String {
+    match ms < 60_000.0 {
+        true => format!("{:.1}s", ms / 1000.0),
+        false => format_working_time(Duration::from_millis(ms as u64)),
+    }
+}
+
 // Legacy MessagePart for backward compatibility
 #[derive(Debug, Clone)]
 pub struct MessagePart<'a> {
@@ -1022,6 +2288,7 @@ impl<'a> Widget for MessagePart<'a> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::app::ui_components::glyphs::ASCII;
     use opencode_sdk::models::{
         StepFinishPart, StepStartPart, ToolStateCompleted, ToolStateCompletedTime,
     };
@@ -1058,6 +2325,32 @@ mod tests {
         }))
     }
 
+    fn create_tool_part_with_input(
+        tool: &str,
+        input: &[(&str, serde_json::Value)],
+        output: &str,
+    ) -> Part {
+        let Part::Tool(mut tool_part) = create_tool_part(tool, output) else {
+            unreachable!();
+        };
+        if let ToolState::Completed(completed) = &mut *tool_part.state {
+            completed.input = input
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.clone()))
+                .collect();
+        }
+        Part::Tool(tool_part)
+    }
+
+    fn rendered_lines(part: Part, verbosity: VerbosityLevel) -> Vec<String> {
+        MessageRenderer::new(vec![part], MessageContext::Fullscreen, verbosity)
+            .render()
+            .lines
+            .iter()
+            .map(|line| line.to_string())
+            .collect()
+    }
+
     fn create_step_start_part(id: &str) -> Part {
         Part::StepStart(Box::new(StepStartPart {
             id: id.to_string(),
@@ -1197,6 +2490,70 @@ mod tests {
         assert!(!content.contains("(ctrl+r to expand)"));
     }
 
+    #[test]
+    fn test_prompt_fold_keeps_the_first_and_last_lines() {
+        assert_eq!(
+            prompt_fold(65, DEFAULT_PROMPT_COLLAPSE_LINES),
+            Some(PromptFold {
+                head: 6,
+                hidden: 57,
+                tail: 2
+            })
+        );
+        assert_eq!(prompt_fold(12, 12), None);
+        assert_eq!(prompt_fold(13, 12).map(|fold| fold.hidden), Some(5));
+        // Never a fold that hides less than it takes
+        assert_eq!(prompt_fold(9, 3), None);
+        assert_eq!(prompt_fold(10, 3).map(|fold| fold.hidden), Some(2));
+
+        let text = (1..=65)
+            .map(|n| format!("line {}", n))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let parts = vec![create_text_part(&text)];
+        let rows = |renderer: MessageRenderer| -> Vec<String> {
+            renderer
+                .render_user_parts(Color::White)
+                .iter()
+                .map(|line| line.to_string())
+                .collect()
+        };
+        let folded = rows(
+            MessageRenderer::new(
+                parts.clone(),
+                MessageContext::Fullscreen,
+                VerbosityLevel::Summary,
+            )
+            .with_prompt_collapse(DEFAULT_PROMPT_COLLAPSE_LINES),
+        );
+        assert_eq!(folded.len(), 9);
+        assert_eq!(folded[0], " line 1");
+        assert_eq!(folded[5], " line 6");
+        assert_eq!(folded[6], " … 57 lines hidden (ctrl+r to expand)");
+        assert_eq!(folded[7..], [" line 64", " line 65"]);
+
+        // Expanded by its message ID, or in the verbose view, it's whole
+        let expanded = rows(
+            MessageRenderer::new(
+                parts.clone(),
+                MessageContext::Fullscreen,
+                VerbosityLevel::Summary,
+            )
+            .with_prompt_collapse(DEFAULT_PROMPT_COLLAPSE_LINES)
+            .with_expanded(&HashSet::from(["msg1".to_string()])),
+        );
+        assert_eq!(expanded.len(), 65);
+        let verbose = rows(
+            MessageRenderer::new(
+                parts.clone(),
+                MessageContext::Fullscreen,
+                VerbosityLevel::Verbose,
+            )
+            .with_prompt_collapse(DEFAULT_PROMPT_COLLAPSE_LINES),
+        );
+        assert_eq!(verbose, expanded);
+    }
+
     #[test]
     fn test_text_only_grouping_always_shows_text() {
         let parts = vec![create_text_part("This is standalone text")];
@@ -1252,6 +2609,137 @@ mod tests {
         assert!(!content.contains("Step in progress"));
     }
 
+    fn timed_tool_part(id: &str, start: f64, end: f64) -> Part {
+        let Part::Tool(mut tool_part) = create_tool_part("bash", "ok") else {
+            unreachable!();
+        };
+        tool_part.id = id.to_string();
+        if let ToolState::Completed(completed) = &mut *tool_part.state {
+            completed.time = Box::new(ToolStateCompletedTime { start, end });
+        }
+        Part::Tool(tool_part)
+    }
+
+    fn step_lines(renderer: MessageRenderer) -> Vec<String> {
+        renderer
+            .render()
+            .lines
+            .iter()
+            .map(|line| line.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_step_footer_formatting() {
+        let parts = vec![
+            create_step_start_part("start_1"),
+            timed_tool_part("tool_a", 1_000.0, 3_000.0),
+            timed_tool_part("tool_b", 2_000.0, 4_000.0),
+            timed_tool_part("tool_c", 5_000.0, 9_100.0),
+            create_step_finish_part("finish_1"),
+        ];
+        let renderer = MessageRenderer::new(
+            parts.clone(),
+            MessageContext::Fullscreen,
+            VerbosityLevel::Summary,
+        );
+        assert_eq!(
+            step_lines(renderer).last().unwrap(),
+            "— step 1 finished · 8.1s · 3 tools —"
+        );
+
+        // Tokens and cost once the step reports them
+        let mut parts = parts;
+        if let Some(Part::StepFinish(finish)) = parts.last_mut() {
+            finish.tokens.input = 11_500.0;
+            finish.tokens.output = 500.0;
+            finish.cost = 0.042;
+        }
+        let renderer = MessageRenderer::new(parts, MessageContext::Inline, VerbosityLevel::Summary)
+            .with_glyphs(&ASCII);
+        assert_eq!(
+            step_lines(renderer).last().unwrap(),
+            "- step 1 finished - 8.1s - 3 tools - 12k tokens - $0.04 -"
+        );
+
+        assert_eq!(format_step_duration(450.0), "0.5s");
+        assert_eq!(format_step_duration(125_000.0), "2m");
+    }
+
+    fn two_step_parts() -> Vec<Part> {
+        vec![
+            create_step_start_part("start_1"),
+            timed_tool_part("tool_a", 0.0, 1_000.0),
+            create_step_finish_part("finish_1"),
+            create_step_start_part("start_2"),
+            create_text_part("All done"),
+            create_step_finish_part("finish_2"),
+        ]
+    }
+
+    #[test]
+    fn test_earlier_steps_collapse_to_their_footer() {
+        let lines = step_lines(MessageRenderer::new(
+            two_step_parts(),
+            MessageContext::Fullscreen,
+            VerbosityLevel::Summary,
+        ));
+        assert!(
+            lines.contains(
+                &"— step 1 finished · 1.0s · 1 tool · 1 hidden — (ctrl+r to expand)".to_string()
+            ),
+            "{lines:?}"
+        );
+        assert!(!lines.iter().any(|line| line.contains("bash")), "{lines:?}");
+        // The latest step shows in full
+        assert!(
+            lines.iter().any(|line| line.contains("All done")),
+            "{lines:?}"
+        );
+        assert_eq!(lines.last().unwrap(), "— step 2 finished —");
+
+        // Verbose, and in the inline scrollback where it couldn't be
+        // expanded again, nothing collapses
+        for renderer in [
+            MessageRenderer::new(
+                two_step_parts(),
+                MessageContext::Fullscreen,
+                VerbosityLevel::Verbose,
+            ),
+            MessageRenderer::new(
+                two_step_parts(),
+                MessageContext::Inline,
+                VerbosityLevel::Summary,
+            ),
+        ] {
+            let lines = step_lines(renderer);
+            assert!(lines.iter().any(|line| line.contains("bash")), "{lines:?}");
+            assert!(
+                lines.contains(&"— step 1 finished · 1.0s · 1 tool —".to_string()),
+                "{lines:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_collapsed_steps_expand_like_tools() {
+        for expanded in ["finish_1", "tool_a"] {
+            let lines = step_lines(
+                MessageRenderer::new(
+                    two_step_parts(),
+                    MessageContext::Fullscreen,
+                    VerbosityLevel::Summary,
+                )
+                .with_expanded(&HashSet::from([expanded.to_string()])),
+            );
+            assert!(lines.iter().any(|line| line.contains("bash")), "{expanded}");
+            assert!(
+                lines.contains(&"— step 1 finished · 1.0s · 1 tool —".to_string()),
+                "{expanded}: {lines:?}"
+            );
+        }
+    }
+
     #[test]
     fn test_step_rendering_mode_on_step_finish() {
         // Test incomplete step (no StepFinish)
@@ -1318,4 +2806,666 @@ mod tests {
         assert!(content.contains("bash"));
         assert!(!content.contains("Step in progress"));
     }
+
+    fn create_file_part(filename: Option<&str>, mime: &str, url: &str) -> FilePart {
+        FilePart {
+            id: "file1".to_string(),
+            session_id: "session1".to_string(),
+            message_id: "msg1".to_string(),
+            mime: mime.to_string(),
+            filename: filename.map(str::to_string),
+            url: url.to_string(),
+            source: None,
+        }
+    }
+
+    #[test]
+    fn test_verbose_bash_shows_full_command_input() {
+        let part = create_tool_part_with_input(
+            "bash",
+            &[
+                ("timeout", serde_json::json!(60000)),
+                ("description", serde_json::json!("Build and test")),
+                ("command", serde_json::json!("cargo build &&\n  cargo test")),
+            ],
+            "ok",
+        );
+
+        let lines = rendered_lines(part.clone(), VerbosityLevel::Verbose);
+        let input_start = lines.iter().position(|l| l == "    ┌─ Input:").unwrap();
+        assert_eq!(
+            lines[input_start..input_start + 7],
+            [
+                "    ┌─ Input:",
+                "    │ command:",
+                "    │   cargo build &&",
+                "    │     cargo test",
+                "    │ description: Build and test",
+                "    │ timeout: 60000",
+                "    └─",
+            ]
+        );
+        // The input comes before the output
+        let output_start = lines
+            .iter()
+            .position(|l| l == "    ┌─ Full Output:")
+            .unwrap();
+        assert!(input_start < output_start);
+
+        // Summary mode keeps the one-line header only
+        let lines = rendered_lines(part, VerbosityLevel::Summary);
+        assert!(!lines.iter().any(|l| l == "    ┌─ Input:"));
+    }
+
+    fn bash_part_with_metadata(metadata: &[(&str, serde_json::Value)]) -> Part {
+        let Part::Tool(mut tool_part) = create_tool_part_with_input(
+            "bash",
+            &[("command", serde_json::json!("make test"))],
+            "ok",
+        ) else {
+            unreachable!();
+        };
+        if let ToolState::Completed(completed) = &mut *tool_part.state {
+            completed.metadata = metadata
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.clone()))
+                .collect();
+        }
+        Part::Tool(tool_part)
+    }
+
+    #[test]
+    fn test_bash_summary_says_where_the_command_ran() {
+        let summary = |part: Part, root: Option<&str>| -> String {
+            MessageRenderer::new(vec![part], MessageContext::Inline, VerbosityLevel::Summary)
+                .with_workspace_root(root)
+                .render()
+                .lines
+                .iter()
+                .map(|line| line.to_string())
+                .find(|line| line.contains("⎿"))
+                .unwrap()
+        };
+        let in_dir = |cwd: &str| bash_part_with_metadata(&[("cwd", serde_json::json!(cwd))]);
+
+        assert_eq!(
+            summary(in_dir("/work/proj/crates/sdk"), Some("/work/proj")),
+            "  ⎿  ok · in crates/sdk"
+        );
+        assert_eq!(
+            summary(in_dir("/tmp/build"), Some("/work/proj")),
+            "  ⎿  ok · in /tmp/build"
+        );
+        // Nothing to add at the root, or without a root or cwd to go on
+        assert_eq!(summary(in_dir("/work/proj"), Some("/work/proj")), "  ⎿  ok");
+        assert_eq!(summary(in_dir("/work/proj/crates/sdk"), None), "  ⎿  ok");
+        assert_eq!(
+            summary(bash_part_with_metadata(&[]), Some("/work/proj")),
+            "  ⎿  ok"
+        );
+    }
+
+    #[test]
+    fn test_bash_description_from_metadata_in_the_input() {
+        let input_lines = |part: Part| -> Vec<String> {
+            rendered_lines(part, VerbosityLevel::Verbose)
+                .into_iter()
+                .skip_while(|line| line != "    ┌─ Input:")
+                .take_while(|line| line != "    └─")
+                .collect()
+        };
+        let part =
+            bash_part_with_metadata(&[("description", serde_json::json!("Run the SDK tests"))]);
+        assert_eq!(
+            input_lines(part),
+            [
+                "    ┌─ Input:",
+                "    │ command: make test",
+                "    │ description: Run the SDK tests",
+            ]
+        );
+        // Without one there's only the command
+        assert_eq!(
+            input_lines(bash_part_with_metadata(&[("exit", serde_json::json!(0))])),
+            ["    ┌─ Input:", "    │ command: make test"]
+        );
+    }
+
+    #[test]
+    fn test_verbose_edit_input_order_and_truncation() {
+        let long = "x".repeat(TOOL_INPUT_MAX_CHARS + 5);
+        let part = create_tool_part_with_input(
+            "edit",
+            &[
+                ("newString", serde_json::json!(long)),
+                ("extra", serde_json::json!({"a": [1, 2]})),
+                ("oldString", serde_json::json!("old")),
+                ("filePath", serde_json::json!("/src/main.rs")),
+            ],
+            "",
+        );
+
+        let lines = rendered_lines(part, VerbosityLevel::Verbose);
+        let input_start = lines.iter().position(|l| l == "    ┌─ Input:").unwrap();
+        let block = &lines[input_start..];
+        assert_eq!(block[1], "    │ filePath: /src/main.rs");
+        assert_eq!(block[2], "    │ oldString: old");
+        assert_eq!(
+            block[3],
+            format!(
+                "    │ newString: {}… [5 more chars]",
+                "x".repeat(TOOL_INPUT_MAX_CHARS)
+            )
+        );
+        // Other values are pretty-printed JSON, after the known keys
+        assert_eq!(
+            block[4..12],
+            [
+                "    │ extra:",
+                "    │   {",
+                "    │     \"a\": [",
+                "    │       1,",
+                "    │       2",
+                "    │     ]",
+                "    │   }",
+                "    └─",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tool_summary_cut_by_width() {
+        let renderer =
+            MessageRenderer::new(vec![], MessageContext::Fullscreen, VerbosityLevel::Summary);
+        let Part::Tool(tool_part) = create_tool_part("custom", &"出力".repeat(40)) else {
+            unreachable!();
+        };
+        // 50 columns of two-column characters, without splitting one
+        assert_eq!(
+            renderer.format_tool_result_summary(&tool_part),
+            format!("{}出...", "出力".repeat(12))
+        );
+    }
+
+    #[test]
+    fn test_tool_render_policy_folds_output() {
+        let output: String = (1..=50).map(|n| format!("src/file_{}.rs\n", n)).collect();
+        let policies = ToolRenderPolicies::new([
+            ("glob".to_string(), ToolRenderPolicy::Lines(5)),
+            ("edit".to_string(), ToolRenderPolicy::Full),
+        ]);
+        let render = |tool: &str, verbosity: VerbosityLevel| -> Vec<String> {
+            MessageRenderer::new(
+                vec![create_tool_part(tool, &output)],
+                MessageContext::Fullscreen,
+                verbosity,
+            )
+            .with_tool_render(&policies)
+            .render()
+            .lines
+            .iter()
+            .map(|line| line.to_string())
+            .collect()
+        };
+
+        let folded = render("glob", VerbosityLevel::Summary);
+        let start = folded
+            .iter()
+            .position(|line| line == "    ┌─ Output:")
+            .unwrap();
+        assert_eq!(
+            folded[start + 1..start + 7],
+            [
+                "    │ src/file_1.rs",
+                "    │ src/file_2.rs",
+                "    │ src/file_3.rs",
+                "    │ src/file_4.rs",
+                "    │ src/file_5.rs",
+                "    │ … 45 more lines",
+            ]
+        );
+        assert!(!folded.iter().any(|line| line.contains("file_6.rs")));
+
+        // Expanding shows it all, whatever the policy
+        let expanded = render("glob", VerbosityLevel::Verbose);
+        assert!(expanded.contains(&"    │ src/file_50.rs".to_string()));
+        assert!(!expanded.iter().any(|line| line.contains("more lines")));
+
+        let full = render("edit", VerbosityLevel::Summary);
+        assert!(full.contains(&"    │ src/file_50.rs".to_string()));
+        // Tools without a policy keep to the summary line
+        let summary = render("bash", VerbosityLevel::Summary);
+        assert!(!summary.iter().any(|line| line.contains("Output:")));
+    }
+
+    #[test]
+    fn test_expanded_output_scrolls_instead_of_wrapping() {
+        let output = "short\n0123456789abcdefghijklmnopqrstuvwxyz";
+        let policies = ToolRenderPolicies::new([("glob".to_string(), ToolRenderPolicy::Lines(5))]);
+        let render = |verbosity: VerbosityLevel, offset: usize| {
+            let renderer = MessageRenderer::new(
+                vec![create_tool_part("glob", output)],
+                MessageContext::Fullscreen,
+                verbosity,
+            )
+            .with_tool_render(&policies)
+            .with_block_scroll(Some(26), &HashMap::from([("tool1".to_string(), offset)]));
+            let lines: Vec<String> = renderer
+                .render()
+                .lines
+                .iter()
+                .map(|line| line.to_string())
+                .collect();
+            (lines, renderer.scroll_blocks())
+        };
+
+        // 20 of the output's 36 columns fit beside the box
+        let (expanded, blocks) = render(VerbosityLevel::Verbose, 0);
+        assert_eq!(
+            blocks,
+            vec![ScrollBlock {
+                key: "tool1".to_string(),
+                width: 36,
+                view: 20,
+            }]
+        );
+        assert!(expanded.contains(&"    ┌─ Full Output: cols 1–20".to_string()));
+        assert!(expanded.contains(&"    │ 0123456789abcdefghij".to_string()));
+
+        let (scrolled, _) = render(VerbosityLevel::Verbose, 30);
+        assert!(scrolled.contains(&"    ┌─ Full Output: cols 17–36".to_string()));
+        assert!(scrolled.contains(&"    │ ghijklmnopqrstuvwxyz".to_string()));
+        assert!(scrolled.contains(&"    │ ".to_string()));
+
+        // Folded output isn't a block, and wraps with the prose
+        let (folded, blocks) = render(VerbosityLevel::Summary, 30);
+        assert!(blocks.is_empty());
+        assert!(folded.contains(&"    │ 0123456789abcdefghijklmnopqrstuvwxyz".to_string()));
+    }
+
+    #[test]
+    fn test_humanize_bytes() {
+        assert_eq!(humanize_bytes(0), "0 B");
+        assert_eq!(humanize_bytes(1023), "1023 B");
+        assert_eq!(humanize_bytes(1024), "1.0 KB");
+        assert_eq!(humanize_bytes(1_258_291), "1.2 MB");
+        assert_eq!(humanize_bytes(5 * 1024 * 1024 * 1024), "5.0 GB");
+    }
+
+    #[test]
+    fn test_webfetch_summary() {
+        let output = "# Docs\n\nHello";
+        let requested = Some("http://example.com/docs");
+        assert_eq!(
+            webfetch_summary(output, requested, &HashMap::new()),
+            "Fetched 13 chars from http://example.com/docs"
+        );
+        assert_eq!(
+            webfetch_summary(output, None, &HashMap::new()),
+            "Fetched 13 chars"
+        );
+
+        // The metadata has the URL the redirects ended at
+        let metadata = HashMap::from([
+            (
+                "url".to_string(),
+                serde_json::json!("https://example.com/docs/"),
+            ),
+            ("status".to_string(), serde_json::json!(200)),
+        ]);
+        assert_eq!(
+            webfetch_summary(output, requested, &metadata),
+            "Fetched 13 chars from https://example.com/docs/ (HTTP 200)"
+        );
+    }
+
+    #[test]
+    fn test_control_characters_drawn_visibly() {
+        let text = create_text_part("\u{1b}[31mred\u{1b}[0m\nid\tsize\nsrc\t12\n50%\r100%");
+        let lines = rendered_lines(text, VerbosityLevel::Verbose);
+        assert_eq!(
+            lines[1..],
+            ["  ␛[31mred␛[0m", "  id  size", "  src 12", "  50%␍100%"]
+        );
+
+        let bash = create_tool_part("bash", "\u{1b}[32mok\u{1b}[0m\ta\u{7}");
+        let lines = rendered_lines(bash, VerbosityLevel::Verbose);
+        assert!(
+            lines.iter().any(|line| line.ends_with("␛[32mok␛[0m a␇")),
+            "{:?}",
+            lines
+        );
+        for line in lines {
+            assert!(line.chars().all(|ch| !ch.is_control()), "{:?}", line);
+        }
+    }
+
+    #[test]
+    fn test_render_links() {
+        assert_eq!(
+            render_links("See [the guide](https://example.com/guide) first."),
+            "See the guide (https://example.com/guide) first."
+        );
+        assert_eq!(
+            render_links("![Logo](/logo.png \"Our logo\") and <https://example.com>"),
+            "Logo (/logo.png) and https://example.com"
+        );
+        assert_eq!(
+            render_links("[Rust](https://en.wikipedia.org/wiki/Rust_(programming_language))."),
+            "Rust (https://en.wikipedia.org/wiki/Rust_(programming_language))."
+        );
+        assert_eq!(
+            render_links("[https://example.com](https://example.com)"),
+            "https://example.com"
+        );
+        // Brackets and angles that aren't links are left alone
+        assert_eq!(
+            render_links("a[0] < b and [x] (y) <br>"),
+            "a[0] < b and [x] (y) <br>"
+        );
+    }
+
+    #[test]
+    fn test_expanded_webfetch_renders_links_and_caps_length() {
+        let page = format!(
+            "Read [the docs](https://example.com/docs).\n{}",
+            "x".repeat(WEBFETCH_MAX_RENDERED_CHARS)
+        );
+        let part = create_tool_part_with_input(
+            "webfetch",
+            &[("url", serde_json::json!("https://example.com"))],
+            &page,
+        );
+        let lines = rendered_lines(part, VerbosityLevel::Verbose);
+
+        assert!(lines.contains(&format!(
+            "  ⎿  Fetched {} chars from https://example.com",
+            page.chars().count()
+        )));
+        assert!(lines.contains(&"    │ Read the docs (https://example.com/docs).".to_string()));
+        assert!(lines.contains(&"    │ … [42 more chars]".to_string()));
+    }
+
+    #[test]
+    fn test_data_url_size() {
+        // "hello" is 5 bytes, "aGVsbG8=" once encoded
+        assert_eq!(data_url_size("data:image/png;base64,aGVsbG8="), Some(5));
+        assert_eq!(data_url_size("data:image/png;base64,aGVsbG8h"), Some(6));
+        assert_eq!(data_url_size("data:text/plain,hello"), Some(5));
+        assert_eq!(data_url_size("file:///tmp/screenshot.png"), None);
+    }
+
+    #[test]
+    fn test_format_file_part_placeholder() {
+        let image = create_file_part(
+            Some("screenshot.png"),
+            "image/png",
+            "file:///tmp/screenshot.png",
+        );
+        assert_eq!(
+            format_file_part(&image, &UNICODE),
+            "🖼 screenshot.png · image/png"
+        );
+
+        // Name falls back to the URL, size comes from inline data
+        let inline = create_file_part(None, "image/jpeg", "data:image/jpeg;base64,aGVsbG8=");
+        assert_eq!(
+            format_file_part(&inline, &UNICODE),
+            "🖼 attachment · image/jpeg · 5 B"
+        );
+        let unnamed = create_file_part(None, "text/plain", "file:///src/main.rs");
+        assert_eq!(
+            format_file_part(&unnamed, &UNICODE),
+            "📎 main.rs · text/plain"
+        );
+    }
+
+    #[test]
+    fn test_file_part_renders_bordered_placeholder() {
+        let image = create_file_part(Some("shot.png"), "image/png", "file:///shot.png");
+        let renderer = MessageRenderer::new(
+            vec![Part::File(Box::new(image))],
+            MessageContext::Fullscreen,
+            VerbosityLevel::Summary,
+        );
+        let text = renderer.render();
+        let lines: Vec<String> = text.lines.iter().map(|line| line.to_string()).collect();
+
+        assert_eq!(lines[2], "  │ 🖼 shot.png · image/png │");
+        assert!(lines[3].starts_with("  └─"));
+        // Borders line up with the label's display width, not its byte length
+        assert_eq!(text.lines[1].width(), text.lines[2].width());
+        assert_eq!(text.lines[3].width(), text.lines[2].width());
+    }
+
+    fn create_agent_part(name: &str) -> Part {
+        Part::Agent(Box::new(AgentPart {
+            id: "agent1".to_string(),
+            session_id: "session1".to_string(),
+            message_id: "msg1".to_string(),
+            name: name.to_string(),
+            source: None,
+        }))
+    }
+
+    /// Task tool running the researcher, with the sub-agent's steps so far
+    /// and its output once done
+    fn create_task_part(output: Option<&str>) -> Part {
+        let input = serde_json::json!({
+            "description": "Find call sites",
+            "prompt": "Find every caller of generate_id",
+            "subagent_type": "researcher",
+        });
+        let summary = serde_json::json!([{
+            "type": "tool",
+            "id": "nested1",
+            "sessionID": "session2",
+            "messageID": "msg2",
+            "callID": "nested1",
+            "tool": "grep",
+            "state": {
+                "status": "completed",
+                "input": { "pattern": "generate_id" },
+                "output": "src/app/tea_update.rs",
+                "title": "generate_id",
+                "metadata": {},
+                "time": { "start": 0.0, "end": 1.0 },
+            },
+        }]);
+        let state = match output {
+            Some(output) => serde_json::json!({
+                "status": "completed",
+                "input": input,
+                "output": output,
+                "title": "Find call sites",
+                "metadata": { "summary": summary },
+                "time": { "start": 0.0, "end": 1.0 },
+            }),
+            None => serde_json::json!({
+                "status": "running",
+                "input": input,
+                "metadata": { "summary": summary },
+                "time": { "start": 0.0 },
+            }),
+        };
+        serde_json::from_value(serde_json::json!({
+            "type": "tool",
+            "id": "task1",
+            "sessionID": "session1",
+            "messageID": "msg1",
+            "callID": "task1",
+            "tool": "task",
+            "state": state,
+        }))
+        .unwrap()
+    }
+
+    fn render_parts(parts: Vec<Part>, verbosity: VerbosityLevel) -> Vec<String> {
+        MessageRenderer::new(parts, MessageContext::Fullscreen, verbosity)
+            .render()
+            .lines
+            .iter()
+            .map(|line| line.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_agent_section_collapsed_and_expanded() {
+        let parts = vec![
+            create_agent_part("researcher"),
+            create_task_part(Some("Found 2 call sites\nBoth in src/app")),
+        ];
+
+        // Collapsed: the header and the final summary, with the task folded in
+        let collapsed = render_parts(parts.clone(), VerbosityLevel::Summary);
+        assert_eq!(
+            collapsed,
+            vec![
+                " ",
+                "⚒ agent: researcher — completed (ctrl+r to expand)",
+                "  ⎿  Found 2 call sites",
+                "     Both in src/app",
+            ]
+        );
+
+        // Expanded: the task's input, then the sub-agent's steps indented
+        let expanded = render_parts(parts, VerbosityLevel::Verbose);
+        assert_eq!(expanded[1], "⚒ agent: researcher — completed");
+        assert!(expanded.contains(&"    │ subagent_type: researcher".to_string()));
+        let nested = expanded
+            .iter()
+            .position(|line| line == "    ● grep(pattern: \"generate_id\")")
+            .expect("nested grep step");
+        assert_eq!(expanded[nested + 1], "      ⎿  Found 1 matches");
+        assert!(expanded.contains(&"        │ src/app/tea_update.rs".to_string()));
+        assert_eq!(expanded.last().unwrap(), "     Both in src/app");
+        assert_eq!(
+            expanded
+                .iter()
+                .filter(|line| line.contains("● task"))
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_agent_header_follows_task_status() {
+        let agent = create_agent_part("researcher");
+        assert_eq!(
+            render_parts(vec![agent.clone()], VerbosityLevel::Summary)[1],
+            "⚒ agent: researcher — pending"
+        );
+
+        let running = render_parts(
+            vec![agent.clone(), create_task_part(None)],
+            VerbosityLevel::Summary,
+        );
+        assert_eq!(
+            running[1],
+            "⚒ agent: researcher — running (ctrl+r to expand)"
+        );
+        assert_eq!(running.len(), 2);
+
+        let completed = render_parts(
+            vec![agent, create_task_part(Some("Done"))],
+            VerbosityLevel::Summary,
+        );
+        assert_eq!(
+            completed[1],
+            "⚒ agent: researcher — completed (ctrl+r to expand)"
+        );
+        assert_eq!(completed[2], "  ⎿  Done");
+
+        // A task run without an agent part gets a section of its own
+        let task_only = render_parts(vec![create_task_part(None)], VerbosityLevel::Summary);
+        assert_eq!(
+            task_only[1],
+            "⚒ agent: researcher — running (ctrl+r to expand)"
+        );
+    }
+
+    #[test]
+    fn test_streaming_cursor_follows_the_last_text() {
+        let render = |text: &str, revealed: Option<HashMap<String, usize>>| {
+            let mut renderer = MessageRenderer::new(
+                vec![create_text_part(text)],
+                MessageContext::Fullscreen,
+                VerbosityLevel::Summary,
+            );
+            if let Some(revealed) = revealed {
+                renderer = renderer.with_streaming(&revealed);
+            }
+            let lines: Vec<String> = renderer
+                .render()
+                .lines
+                .iter()
+                .map(|line| line.to_string())
+                .collect();
+            lines.last().cloned().unwrap_or_default()
+        };
+
+        assert_eq!(render("Hello\nworld", Some(HashMap::new())), "  world▌");
+        assert_eq!(render("Hello\n", Some(HashMap::new())), "  ▌");
+        // Only the revealed characters are shown
+        let revealed = HashMap::from([("text1".to_string(), 3)]);
+        assert_eq!(render("Hello\nworld", Some(revealed)), "  Hel▌");
+        // A message that isn't streaming has no cursor
+        assert_eq!(render("Hello\nworld", None), "  world");
+    }
+
+    fn reply_from(provider_id: &str, model_id: &str) -> Message {
+        Message::Assistant(Box::new(opencode_sdk::models::AssistantMessage {
+            provider_id: provider_id.to_string(),
+            model_id: model_id.to_string(),
+            ..Default::default()
+        }))
+    }
+
+    #[test]
+    fn test_model_annotation_only_for_other_models() {
+        let render = |info: &Message, width: Option<usize>| -> Vec<String> {
+            MessageRenderer::new(
+                vec![create_text_part("Hello")],
+                MessageContext::Inline,
+                VerbosityLevel::Summary,
+            )
+            .with_model_annotation(info, "anthropic/claude-sonnet-4")
+            .render_to_width(width)
+            .lines
+            .iter()
+            .map(|line| line.to_string())
+            .collect()
+        };
+
+        // Right-aligned on the blank line leading the text
+        let other = reply_from("openai", "gpt-4o");
+        let lines = render(&other, Some(30));
+        assert_eq!(lines[0], format!(" {:>29}", "openai/gpt-4o"));
+        assert_eq!(lines[1], "  Hello");
+        assert_eq!(render(&other, None)[0], "   openai/gpt-4o");
+
+        // Not for the current model, user messages, or placeholder info
+        let plain = vec![" ".to_string(), "  Hello".to_string()];
+        assert_eq!(
+            render(&reply_from("anthropic", "claude-sonnet-4"), Some(30)),
+            plain
+        );
+        let user = Message::User(Box::new(opencode_sdk::models::UserMessage::default()));
+        assert_eq!(render(&user, Some(30)), plain);
+        assert_eq!(
+            render(&reply_from(UNKNOWN_MODEL, UNKNOWN_MODEL), Some(30)),
+            plain
+        );
+    }
+
+    #[test]
+    fn test_model_annotation_on_its_own_line_without_room() {
+        let lines = annotate_first_line(
+            vec![Line::from("a first line filling the row")],
+            Span::raw("openai/gpt-4o"),
+            Some(32),
+        );
+        assert_eq!(lines[0].to_string(), format!("{:>32}", "openai/gpt-4o"));
+        assert_eq!(lines[1].to_string(), "a first line filling the row");
+    }
 }
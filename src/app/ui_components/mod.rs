@@ -1,22 +1,58 @@
 pub mod attachment_display;
 pub mod banner;
+pub mod diff_review;
+pub mod glyphs;
+pub mod inline_height;
+pub mod layout_class;
+pub mod live_step;
 pub mod message_log;
 pub mod message_part;
+pub mod modal_activity_log;
+pub mod modal_changelog;
 pub mod modal_file_selector;
+pub mod modal_metrics_view;
+pub mod modal_new_session;
+pub mod modal_project_selector;
+pub mod modal_save_output;
 pub mod modal_selector;
+pub mod modal_server_selector;
 pub mod modal_session_selector;
+pub mod modal_snippet_selector;
+pub mod modal_symbol_selector;
+pub mod pinned_pane;
+pub mod render_ctx;
+pub mod session_header;
 pub mod status_bar;
 pub mod text_input;
+pub mod text_width;
+pub mod tool_render;
 
 pub use attachment_display::AttachmentDisplay;
 pub use banner::create_welcome_text;
-pub use message_log::MessageLog;
+pub use diff_review::{DiffReview, FileChange};
+pub use layout_class::LayoutClass;
+pub use message_log::{
+    EarlierMessages, LogMatch, MessageLog, MsgLogFind, MsgLogSelection, ScrollPosition,
+    BLOCK_SCROLL_COLUMNS,
+};
 pub use message_part::{MessageContext, MessagePart, MessageRenderer};
+pub use modal_activity_log::{ActivityLog, MsgModalActivityLog};
+pub use modal_changelog::ChangelogView;
 pub use modal_file_selector::{FileSelector, MsgModalFileSelector};
+pub use modal_metrics_view::{MetricsView, MsgModalMetrics};
+pub use modal_new_session::{MsgNewSessionPrompt, NewSessionPrompt};
+pub use modal_project_selector::{MsgModalProjectSelector, ProjectSelector};
+pub use modal_save_output::{MsgSaveOutputPrompt, SaveOutputPrompt};
 pub use modal_selector::{
     ModalSelector, ModalSelectorEvent, SelectableData, SelectorConfig, SelectorMode, TableColumn,
 };
+pub use modal_server_selector::{MsgModalServerSelector, ServerSelector};
 pub use modal_session_selector::{MsgModalSessionSelector, SessionSelector};
+pub use modal_snippet_selector::{MsgModalSnippetSelector, SnippetSelector};
+pub use modal_symbol_selector::{MsgModalSymbolSelector, SymbolSelector};
+pub use pinned_pane::PinnedPane;
+pub use render_ctx::{GutterColors, RenderCtx};
+pub use session_header::SessionHeader;
 pub use status_bar::StatusBar;
 pub use text_input::{InputResult, MsgTextArea, TextInputArea};
 
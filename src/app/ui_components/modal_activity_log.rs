@@ -0,0 +1,240 @@
+use crate::app::{
+    event_msg::{Cmd, CmdOrBatch},
+    message_state::{ToolActivity, ToolStatus},
+    tea_model::Model,
+    ui_components::{
+        glyphs::Glyphs, modal_selector::ModalSelectorUpdate, Component, ModalSelector,
+        ModalSelectorEvent, RenderCtx, SelectableData, SelectorConfig, SelectorMode, TableColumn,
+    },
+};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Alignment, Constraint, Rect},
+    style::{Color, Modifier, Style},
+    widgets::{Borders, Cell, Widget},
+};
+use serde::{Deserialize, Serialize};
+
+/// Data wrapper for one tool call's row in the activity log
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ActivityData {
+    pub activity: ToolActivity,
+    /// Bullet drawn in the status column, as in the tool's header
+    pub bullet: String,
+}
+
+/// Time of day a tool started, "14:03:27" in UTC
+fn format_started(started_ms: Option<f64>) -> String {
+    match started_ms {
+        Some(ms) => {
+            let secs = (ms / 1000.0) as u64 % 86_400;
+            format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+        }
+        None => "--:--:--".to_string(),
+    }
+}
+
+/// "850ms", "2.5s" or "3m 05s"
+fn format_duration(duration_ms: f64) -> String {
+    match duration_ms {
+        ms if ms < 1000.0 => format!("{}ms", ms as u64),
+        ms if ms < 60_000.0 => format!("{:.1}s", ms / 1000.0),
+        ms => {
+            let secs = (ms / 1000.0) as u64;
+            format!("{}m {:02}s", secs / 60, secs % 60)
+        }
+    }
+}
+
+pub fn status_color(status: ToolStatus) -> Color {
+    match status {
+        ToolStatus::Pending => Color::Yellow,
+        ToolStatus::Running => Color::Blue,
+        ToolStatus::Completed => Color::Green,
+        ToolStatus::Failed => Color::Red,
+    }
+}
+
+impl ActivityData {
+    fn duration(&self) -> String {
+        match (self.activity.status, self.activity.duration_ms) {
+            (_, Some(duration_ms)) => format_duration(duration_ms),
+            (ToolStatus::Running, None) => "running".to_string(),
+            (_, None) => "pending".to_string(),
+        }
+    }
+}
+
+impl SelectableData for ActivityData {
+    fn to_cells(&self) -> Vec<Cell<'_>> {
+        vec![
+            Cell::from(format_started(self.activity.started_ms))
+                .style(Style::default().fg(Color::Gray)),
+            Cell::from(self.bullet.clone())
+                .style(Style::default().fg(status_color(self.activity.status))),
+            Cell::from(self.activity.tool.clone()),
+            Cell::from(self.activity.args.clone()).style(Style::default().fg(Color::Gray)),
+            Cell::from(self.duration()),
+        ]
+    }
+
+    fn to_string(&self) -> String {
+        format!(
+            "{} {} {} {} {}",
+            format_started(self.activity.started_ms),
+            self.bullet,
+            self.activity.tool,
+            self.activity.args,
+            self.duration()
+        )
+    }
+}
+
+/// Submessage enum for the activity log that wraps generic events
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum MsgModalActivityLog {
+    Event(ModalSelectorEvent<ActivityData>),
+}
+
+/// Every tool call of the session, oldest first. Choosing one shows it
+/// expanded at the top of the message log.
+#[derive(Debug, Clone)]
+pub struct ActivityLog {
+    pub modal: ModalSelector<ActivityData>,
+}
+
+impl ActivityLog {
+    pub fn new() -> Self {
+        let config = SelectorConfig {
+            title: Some("Tool Activity".to_string()),
+            footer: Some("↑↓ navigate, Enter show in log, Esc close".to_string()),
+            max_width: Some(100),
+            max_height: Some(20),
+            padding: 1,
+            show_scrollbar: true,
+            alternating_rows: true,
+            borders: Borders::ALL,
+            border_color: Color::Blue,
+            selected_style: Style::default()
+                .add_modifier(Modifier::REVERSED)
+                .fg(Color::Blue),
+            header_style: Style::default().fg(Color::Yellow),
+            row_style: Style::default().fg(Color::White),
+            alt_row_style: None,
+            multi_select: false,
+        };
+
+        let columns = vec![
+            TableColumn::new("UTC", Constraint::Length(9)).optional(),
+            TableColumn::new("", Constraint::Length(2)),
+            TableColumn::new("Tool", Constraint::Length(10)),
+            TableColumn::new("Args", Constraint::Min(20)),
+            TableColumn::new("Took", Constraint::Length(8)).with_alignment(Alignment::Right),
+        ];
+
+        Self {
+            modal: ModalSelector::new(config, SelectorMode::Table { columns }),
+        }
+    }
+
+    /// List `activity`, keeping the same call selected as it updates
+    pub fn set_activity<'a>(
+        &mut self,
+        activity: impl Iterator<Item = &'a ToolActivity>,
+        glyphs: &Glyphs,
+    ) {
+        let selected = self
+            .modal
+            .selected_item()
+            .map(|data| data.activity.part_id.clone());
+        let data: Vec<ActivityData> = activity
+            .map(|activity| ActivityData {
+                activity: activity.clone(),
+                bullet: glyphs.tool.to_string(),
+            })
+            .collect();
+        let index = selected.and_then(|part_id| {
+            data.iter()
+                .position(|data| data.activity.part_id == part_id)
+        });
+        self.modal.set_items(data);
+        if let Some(index) = index {
+            self.modal.select(index);
+        }
+    }
+
+    /// Open on the latest call
+    pub fn show(&mut self) {
+        self.modal
+            .select(self.modal.items().len().saturating_sub(1));
+        self.modal.show();
+    }
+
+    pub fn items(&self) -> &[ActivityData] {
+        self.modal.items()
+    }
+}
+
+impl Default for ActivityLog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Component<Model, MsgModalActivityLog, Cmd> for ActivityLog {
+    fn update(msg: MsgModalActivityLog, state: &mut Model) -> CmdOrBatch<Cmd> {
+        let model = state;
+        match msg {
+            MsgModalActivityLog::Event(event) => {
+                match model.modal_activity_log.modal.handle_event(event) {
+                    ModalSelectorUpdate::Hide => {
+                        model.pop_modal();
+                    }
+                    ModalSelectorUpdate::ItemSelected(data) => {
+                        model.modal_activity_log.modal.hide();
+                        model.pop_modal();
+                        // Inline, the log went to the terminal's scrollback
+                        if model.init.inline_mode() {
+                            return CmdOrBatch::Single(Cmd::None);
+                        }
+                        let ToolActivity {
+                            message_id,
+                            part_id,
+                            ..
+                        } = &data.activity;
+                        model.message_log.expand_tool(message_id, part_id);
+                        model.message_log.jump_to_part(message_id, part_id);
+                    }
+                    ModalSelectorUpdate::ItemsSelected(_) | ModalSelectorUpdate::None => {}
+                }
+            }
+        }
+        CmdOrBatch::Single(Cmd::None)
+    }
+}
+
+impl Widget for &ActivityLog {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        self.modal.render(area, buf);
+    }
+}
+
+impl ActivityLog {
+    pub fn render_with(&self, ctx: &RenderCtx, area: Rect, buf: &mut Buffer) {
+        self.modal.render_with(ctx, area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_activity_columns() {
+        assert_eq!(format_started(Some(1_700_000_123_456.0)), "22:15:23");
+        assert_eq!(format_started(None), "--:--:--");
+        assert_eq!(format_duration(850.0), "850ms");
+        assert_eq!(format_duration(2_540.0), "2.5s");
+        assert_eq!(format_duration(185_000.0), "3m 05s");
+    }
+}
@@ -0,0 +1,169 @@
+//! The changelog modal
+//!
+//! After an upgrade it shows what's new since the version last run, and
+//! `/changelog` shows every release in it. The releases can run longer than
+//! the terminal is tall, so it scrolls with ↑/↓ and the page keys.
+
+use crate::app::{
+    changelog::{changelog_lines, Release},
+    strings::CHANGELOG_HINT,
+    ui_components::{text_width::wrap_line, RenderCtx},
+};
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::Stylize,
+    text::Line,
+    widgets::{Block, Borders, Paragraph, Widget},
+};
+use std::cell::Cell;
+
+/// Widest the modal gets, past which entries wrap
+pub const CHANGELOG_MAX_WIDTH: u16 = 72;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChangelogView {
+    pub title: String,
+    pub lines: Vec<String>,
+    /// First row in view
+    pub scroll: usize,
+    /// Wrapped rows and the rows in view at the last render, which the
+    /// scroll stops short of running past
+    rows: Cell<usize>,
+    view_height: Cell<usize>,
+}
+
+impl ChangelogView {
+    pub fn new(title: impl Into<String>, releases: &[Release]) -> Self {
+        Self {
+            title: title.into(),
+            lines: changelog_lines(releases),
+            scroll: 0,
+            rows: Cell::new(0),
+            view_height: Cell::new(0),
+        }
+    }
+
+    /// Scroll by `rows`, stopping at the first row and with the last one at
+    /// the bottom
+    pub fn scroll_by(&mut self, rows: i16) {
+        let scroll = self.scroll.saturating_add_signed(rows as isize);
+        self.scroll = scroll.min(self.max_scroll());
+    }
+
+    /// Rows a page key scrolls by
+    pub fn page(&self) -> i16 {
+        self.view_height.get().saturating_sub(1).max(1) as i16
+    }
+
+    fn max_scroll(&self) -> usize {
+        self.rows.get().saturating_sub(self.view_height.get())
+    }
+
+    /// The lines wrapped to `width` columns, after a blank row
+    fn wrapped(&self, width: usize) -> Vec<Line<'static>> {
+        std::iter::once(String::new())
+            .chain(self.lines.iter().map(|line| format!(" {}", line)))
+            .flat_map(|line| wrap_line(Line::from(line), width))
+            .collect()
+    }
+
+    /// Where the modal goes in `frame_area`: centered, and as tall as its
+    /// rows while they fit
+    pub fn area(&self, frame_area: Rect) -> Rect {
+        let width = CHANGELOG_MAX_WIDTH.min(frame_area.width);
+        let rows = self.wrapped(width.saturating_sub(2) as usize).len() as u16;
+        let height = (rows + 3).min(frame_area.height);
+        Rect {
+            x: frame_area.x + (frame_area.width - width) / 2,
+            y: frame_area.y + (frame_area.height - height) / 2,
+            width,
+            height,
+        }
+    }
+
+    pub fn render_with(&self, ctx: &RenderCtx, area: Rect, buf: &mut Buffer) {
+        let rows = self.wrapped(area.width.saturating_sub(2) as usize);
+        let view_height = area.height.saturating_sub(2) as usize;
+        self.rows.set(rows.len());
+        self.view_height.set(view_height);
+        // Taller than when last scrolled, so rows that fit are no longer
+        // scrolled out of view
+        let top = self.scroll.min(self.max_scroll());
+        let content: Vec<Line> = rows
+            .into_iter()
+            .skip(top)
+            .take(view_height)
+            .map(|line| {
+                let text: String = line.spans.iter().map(|span| &*span.content).collect();
+                Line::from(ctx.glyphs.text(&text).into_owned())
+            })
+            .collect();
+        let title = format!(" {} ", self.title);
+        let hint = format!(" {} ", CHANGELOG_HINT);
+        Paragraph::new(content)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_set(ctx.border_set())
+                    .title(title.bold())
+                    .title_bottom(ctx.glyphs.text(&hint).into_owned().dark_gray()),
+            )
+            .render(area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::tea_model::Model;
+
+    fn releases(count: usize) -> Vec<Release> {
+        (1..=count)
+            .rev()
+            .map(|minor| Release {
+                version: format!("0.{}.0", minor),
+                entries: vec![format!("Change {}", minor)],
+            })
+            .collect()
+    }
+
+    /// The rows inside the border
+    fn render(view: &ChangelogView, height: u16) -> Vec<String> {
+        let model = Model::new();
+        let frame_area = Rect::new(0, 0, 40, height);
+        let ctx = RenderCtx::from_model(&model, frame_area);
+        let area = view.area(frame_area);
+        let mut buf = Buffer::empty(area);
+        view.render_with(&ctx, area, &mut buf);
+        (area.top() + 1..area.bottom() - 1)
+            .map(|y| {
+                (area.left() + 1..area.right() - 1)
+                    .map(|x| buf[(x, y)].symbol())
+                    .collect::<String>()
+                    .trim_end()
+                    .to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_sized_to_fit_and_scrolls_past_the_frame() {
+        let mut view = ChangelogView::new("What's new", &releases(1));
+        assert_eq!(render(&view, 20), vec!["", " 0.1.0", "   - Change 1", ""]);
+
+        view = ChangelogView::new("What's new", &releases(5));
+        let rows = render(&view, 8);
+        assert_eq!(
+            rows,
+            vec!["", " 0.5.0", "   - Change 5", "", " 0.4.0", "   - Change 4"]
+        );
+        view.scroll_by(view.page());
+        assert_eq!(render(&view, 8)[0], "   - Change 4");
+        // Stops with the last row at the bottom
+        view.scroll_by(100);
+        assert_eq!(render(&view, 8).last().unwrap(), "   - Change 1");
+        view.scroll_by(-100);
+        assert_eq!(view.scroll, 0);
+    }
+}
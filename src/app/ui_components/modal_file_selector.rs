@@ -1,15 +1,16 @@
 use std::{collections::HashSet, u16};
 
 use crate::app::{
-    event_msg::{Cmd, CmdOrBatch},
-    tea_model::{AppModalState, AttachedFile, Model, TimeoutType},
+    event_msg::CmdOrBatch,
+    mentions::{workspace_relative_path, MENTION_PREFIX},
+    strings::FILES_CHECKED_HINT,
+    tea_model::{Model, TimeoutType},
     tea_view::MAX_UI_WIDTH,
     ui_components::{
         modal_selector::ModalSelectorUpdate, Component, ModalSelector, ModalSelectorEvent,
-        MsgModalSessionSelector, SelectableData, SelectorConfig, SelectorMode, TableColumn,
+        RenderCtx, SelectableData, SelectorConfig, SelectorMode, TableColumn,
     },
 };
-use crate::sdk::client::{generate_id, IdPrefix};
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use opencode_sdk::models::File;
 use ratatui::{
@@ -19,16 +20,30 @@ use ratatui::{
     text::Span,
     widgets::{Borders, Cell, Widget},
 };
+use serde::{Deserialize, Serialize};
 
 /// Data wrapper for file selection
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct FileData {
     pub file: File,
+    /// Checked to be attached with the others
+    #[serde(default)]
+    pub selected: bool,
 }
 
 impl FileData {
     pub fn from_file(file: File) -> Self {
-        Self { file }
+        Self {
+            file,
+            selected: false,
+        }
+    }
+
+    fn checkbox(&self) -> &'static str {
+        match self.selected {
+            true => "[x] ",
+            false => "",
+        }
     }
 
     fn format_changes(&self) -> Vec<Span<'static>> {
@@ -63,7 +78,7 @@ impl SelectableData for FileData {
     fn to_cells(&self) -> Vec<Cell> {
         vec![
             Cell::from(ratatui::text::Line::from(self.format_changes())),
-            Cell::from(self.file.path.clone()),
+            Cell::from(format!("{}{}", self.checkbox(), self.file.path)),
         ]
     }
 
@@ -74,13 +89,18 @@ impl SelectableData for FileData {
     fn to_spans(&self) -> Option<Vec<Span>> {
         let mut spans = self.format_changes();
         spans.push(Span::raw(" "));
+        spans.push(Span::raw(self.checkbox()));
         spans.push(Span::raw(&self.file.path));
         Some(spans)
     }
+
+    fn set_toggled(&mut self, toggled: bool) {
+        self.selected = toggled;
+    }
 }
 
 /// Submessage enum for the file selector that wraps generic events
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum MsgModalFileSelector {
     Event(ModalSelectorEvent<FileData>),
     KeyInput(KeyEvent),
@@ -96,7 +116,8 @@ pub struct FileSelector {
     // Store both data sources separately
     file_status: Vec<File>,
     find_files_results: Vec<File>,
-    // attachments
+    /// Files checked so far, kept while the search changes
+    checked: Vec<File>,
 }
 
 impl FileSelector {
@@ -119,6 +140,7 @@ impl FileSelector {
             header_style: Style::default().fg(Color::Gray),
             row_style: Style::default().fg(Color::White),
             alt_row_style: None, // Some(Style::default().bg(Color::DarkGray)),
+            multi_select: true,
         };
 
         let columns = vec![
@@ -132,6 +154,7 @@ impl FileSelector {
             depth: 0,
             file_status: Vec::new(),
             find_files_results: Vec::new(),
+            checked: Vec::new(),
         }
     }
 
@@ -174,6 +197,49 @@ impl FileSelector {
             .map(FileData::from_file)
             .collect();
         self.modal.set_items(file_data);
+        self.recheck_items();
+    }
+
+    /// Check the highlighted file, or uncheck it if it was
+    pub fn toggle_highlighted(&mut self) {
+        self.modal.toggle_selected();
+        let Some(index) = self.modal.selected_index() else {
+            return;
+        };
+        let file = self.modal.items()[index].file.clone();
+        self.checked.retain(|checked| checked.path != file.path);
+        if self.modal.is_toggled(index) {
+            self.checked.push(file);
+        }
+        self.update_footer();
+    }
+
+    /// Files checked so far, in the order they were checked
+    pub fn checked(&self) -> &[File] {
+        &self.checked
+    }
+
+    /// Check the listed files that were checked under an earlier search
+    fn recheck_items(&mut self) {
+        let checked: Vec<usize> = (0..self.modal.items().len())
+            .filter(|&index| {
+                let path = &self.modal.items()[index].file.path;
+                self.checked.iter().any(|checked| &checked.path == path)
+            })
+            .collect();
+        for index in checked {
+            self.modal.set_toggled(index, true);
+        }
+    }
+
+    fn update_footer(&mut self) {
+        self.modal.config.footer = (!self.checked.is_empty())
+            .then(|| format!("{} {}", self.checked.len(), FILES_CHECKED_HINT));
+    }
+
+    /// The mention being searched for, without its prefix
+    pub fn query(&self) -> &str {
+        &self.query
     }
 
     pub fn is_file_selector_input(key: KeyEvent) -> bool {
@@ -187,13 +253,38 @@ impl FileSelector {
         self.query = "".to_string();
         self.file_status.clear();
         self.find_files_results.clear();
+        self.checked.clear();
+        self.update_footer();
         self.modal.set_items(Vec::new());
     }
 }
 
-fn model_select_file(file: File, model: &mut Model) {
+fn model_select_files(files: Vec<File>, model: &mut Model) {
+    let mut selected: Vec<File> = Vec::new();
+    for mut file in files {
+        file.path = workspace_relative_path(&file.path, model.workspace_root.as_deref());
+        if !selected.iter().any(|other| other.path == file.path) {
+            selected.push(file);
+        }
+    }
+
+    // Swap the mention being typed, the last one in the input, for the paths
     let current_text = model.text_input_area.content();
-    let new_text = current_text.replace(&model.modal_file_selector.query, &file.path);
+    let typed = format!("{}{}", MENTION_PREFIX, model.modal_file_selector.query);
+    let mentions = selected
+        .iter()
+        .map(|file| format!("{}{}", MENTION_PREFIX, file.path))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let new_text = match current_text.rfind(&typed) {
+        Some(start) => format!(
+            "{}{}{}",
+            &current_text[..start],
+            mentions,
+            &current_text[start + typed.len()..]
+        ),
+        None => current_text,
+    };
     model.text_input_area.set_content(&new_text);
     for _ in new_text.chars() {
         model
@@ -201,31 +292,17 @@ fn model_select_file(file: File, model: &mut Model) {
             .handle_input(KeyEvent::new(KeyCode::Right, KeyModifiers::NONE));
     }
 
-    // Add file attachment
-    let attached_file = AttachedFile {
-        display_name: file
-            .path
-            .split('/')
-            .last()
-            .unwrap_or(&file.path)
-            .to_string(),
-        part_id: generate_id(IdPrefix::Part),
-        file: file.clone(),
-    };
-
-    // Check if file already attached to avoid duplicates
-    if !model
-        .attached_files
-        .iter()
-        .any(|af| af.file.path == file.path)
-    {
-        model.attached_files.push(attached_file);
+    for file in selected {
+        model.attach_file(file);
     }
 }
 
 fn model_clear(model: &mut Model) {
+    // A search still waiting on its debounce would otherwise run after closing
+    let query = model.modal_file_selector.query.clone();
+    model.clear_timeout(&TimeoutType::DebounceFindFiles(query));
     model.modal_file_selector.clear();
-    model.state = AppModalState::None;
+    model.pop_modal();
 }
 
 fn model_search_files(model: &mut Model) {
@@ -250,8 +327,19 @@ impl Component<Model, MsgModalFileSelector, ()> for FileSelector {
                     ModalSelectorUpdate::Hide => {
                         model_clear(model);
                     }
+                    // Checked files are attached rather than the highlighted
+                    // one, even those checked under an earlier search
                     ModalSelectorUpdate::ItemSelected(file_data) => {
-                        model_select_file(file_data.file, model);
+                        let files = match model.modal_file_selector.checked() {
+                            [] => vec![file_data.file],
+                            checked => checked.to_vec(),
+                        };
+                        model_select_files(files, model);
+                        model_clear(model);
+                    }
+                    ModalSelectorUpdate::ItemsSelected(_) => {
+                        let files = model.modal_file_selector.checked().to_vec();
+                        model_select_files(files, model);
                         model_clear(model);
                     }
                     _ => {}
@@ -273,14 +361,14 @@ impl Component<Model, MsgModalFileSelector, ()> for FileSelector {
                             }
                             model.text_input_area.handle_input(key);
                         }
+                        // Space checks files rather than ending the mention
+                        KeyCode::Char(' ') => {
+                            model.modal_file_selector.toggle_highlighted();
+                        }
                         KeyCode::Char(c) => {
-                            if c == ' ' {
-                                model_clear(model);
-                            } else {
-                                model.modal_file_selector.depth += 1;
-                                model.modal_file_selector.query.push(c);
-                                model_search_files(model);
-                            }
+                            model.modal_file_selector.depth += 1;
+                            model.modal_file_selector.query.push(c);
+                            model_search_files(model);
                             model.text_input_area.handle_input(key);
                         }
                         _ => {}
@@ -301,3 +389,9 @@ impl Widget for &FileSelector {
         self.modal.render(area, buf);
     }
 }
+
+impl FileSelector {
+    pub fn render_with(&self, ctx: &RenderCtx, area: Rect, buf: &mut Buffer) {
+        self.modal.render_with(ctx, area, buf);
+    }
+}
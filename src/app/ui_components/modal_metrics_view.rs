@@ -0,0 +1,241 @@
+use crate::app::{
+    event_msg::{Cmd, CmdOrBatch},
+    message_state::ModelUsage,
+    tea_model::Model,
+    ui_components::{
+        modal_selector::ModalSelectorUpdate, Component, ModalSelector, ModalSelectorEvent,
+        RenderCtx, SelectableData, SelectorConfig, SelectorMode, TableColumn,
+    },
+};
+use crate::sdk::extensions::metrics::EndpointStats;
+use crossterm::event::KeyCode;
+use ratatui::{
+    buffer::Buffer,
+    layout::{Alignment, Constraint, Rect},
+    style::{Color, Modifier, Style},
+    widgets::{Borders, Cell, Widget},
+};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Data wrapper for one endpoint's row in the stats table
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EndpointStatsData {
+    pub stats: EndpointStats,
+}
+
+fn format_latency(duration: Duration) -> String {
+    format!("{}ms", duration.as_millis())
+}
+
+impl SelectableData for EndpointStatsData {
+    fn to_cells(&self) -> Vec<Cell<'_>> {
+        let errors_style = if self.stats.errors > 0 {
+            Style::default().fg(Color::Red)
+        } else {
+            Style::default()
+        };
+        vec![
+            Cell::from(self.stats.endpoint.clone()),
+            Cell::from(self.stats.count.to_string()),
+            Cell::from(self.stats.errors.to_string()).style(errors_style),
+            Cell::from(format_latency(self.stats.p50)),
+            Cell::from(format_latency(self.stats.p95)),
+        ]
+    }
+
+    fn to_string(&self) -> String {
+        format!(
+            "{} {} {} {} {}",
+            self.stats.endpoint,
+            self.stats.count,
+            self.stats.errors,
+            format_latency(self.stats.p50),
+            format_latency(self.stats.p95)
+        )
+    }
+}
+
+/// Data wrapper for one model's row in the tokens table
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelUsageData {
+    pub usage: ModelUsage,
+}
+
+impl ModelUsageData {
+    fn label(&self) -> String {
+        format!("{}/{}", self.usage.provider_id, self.usage.model_id)
+    }
+}
+
+fn format_tokens(tokens: f64) -> String {
+    format!("{:.1}k", tokens / 1000.0)
+}
+
+impl SelectableData for ModelUsageData {
+    fn to_cells(&self) -> Vec<Cell<'_>> {
+        vec![
+            Cell::from(self.label()),
+            Cell::from(self.usage.replies.to_string()),
+            Cell::from(format_tokens(self.usage.tokens)),
+        ]
+    }
+
+    fn to_string(&self) -> String {
+        format!(
+            "{} {} {}",
+            self.label(),
+            self.usage.replies,
+            format_tokens(self.usage.tokens)
+        )
+    }
+}
+
+/// Submessage enum for the request stats view that wraps generic events
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum MsgModalMetrics {
+    Event(ModalSelectorEvent<EndpointStatsData>),
+}
+
+/// Read-only tables of per-endpoint request latencies and, a Tab away, the
+/// session's tokens per model
+#[derive(Debug, Clone)]
+pub struct MetricsView {
+    pub modal: ModalSelector<EndpointStatsData>,
+    pub usage: ModalSelector<ModelUsageData>,
+    pub showing_usage: bool,
+}
+
+impl MetricsView {
+    pub fn new() -> Self {
+        let config = SelectorConfig {
+            title: Some("Request Stats".to_string()),
+            footer: Some("Recent requests per endpoint, Tab tokens, Esc close".to_string()),
+            max_width: Some(70),
+            max_height: Some(20),
+            padding: 1,
+            show_scrollbar: false,
+            alternating_rows: true,
+            borders: Borders::ALL,
+            border_color: Color::Blue,
+            selected_style: Style::default()
+                .add_modifier(Modifier::REVERSED)
+                .fg(Color::Blue),
+            header_style: Style::default().fg(Color::Yellow),
+            row_style: Style::default().fg(Color::White),
+            alt_row_style: None,
+            multi_select: false,
+        };
+
+        let columns = vec![
+            TableColumn::new("Endpoint", Constraint::Min(20)),
+            TableColumn::new("Count", Constraint::Length(7)).with_alignment(Alignment::Right),
+            TableColumn::new("Errors", Constraint::Length(7)).with_alignment(Alignment::Right),
+            TableColumn::new("p50", Constraint::Length(9))
+                .with_alignment(Alignment::Right)
+                .optional(),
+            TableColumn::new("p95", Constraint::Length(9)).with_alignment(Alignment::Right),
+        ];
+
+        let usage_config = SelectorConfig {
+            title: Some("Tokens per Model".to_string()),
+            footer: Some("This session's replies, Tab requests, Esc close".to_string()),
+            ..config.clone()
+        };
+        let usage_columns = vec![
+            TableColumn::new("Model", Constraint::Min(20)),
+            TableColumn::new("Replies", Constraint::Length(8)).with_alignment(Alignment::Right),
+            TableColumn::new("Tokens", Constraint::Length(10)).with_alignment(Alignment::Right),
+        ];
+
+        Self {
+            modal: ModalSelector::new(config, SelectorMode::Table { columns }),
+            usage: ModalSelector::new(
+                usage_config,
+                SelectorMode::Table {
+                    columns: usage_columns,
+                },
+            ),
+            showing_usage: false,
+        }
+    }
+
+    pub fn set_stats(&mut self, stats: Vec<EndpointStats>) {
+        let data = stats
+            .into_iter()
+            .map(|stats| EndpointStatsData { stats })
+            .collect();
+        self.modal.set_items(data);
+    }
+
+    pub fn items(&self) -> &[EndpointStatsData] {
+        self.modal.items()
+    }
+
+    pub fn set_usage(&mut self, usage: Vec<ModelUsage>) {
+        let data = usage
+            .into_iter()
+            .map(|usage| ModelUsageData { usage })
+            .collect();
+        self.usage.set_items(data);
+    }
+
+    pub fn usage_items(&self) -> &[ModelUsageData] {
+        self.usage.items()
+    }
+
+    /// Open on the requests table
+    pub fn show(&mut self) {
+        self.showing_usage = false;
+        self.modal.show();
+        self.usage.show();
+    }
+}
+
+impl Default for MetricsView {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Component<Model, MsgModalMetrics, Cmd> for MetricsView {
+    fn update(msg: MsgModalMetrics, state: &mut Model) -> CmdOrBatch<Cmd> {
+        let model = state;
+        match msg {
+            MsgModalMetrics::Event(ModalSelectorEvent::KeyInput(key))
+                if key.code == KeyCode::Tab =>
+            {
+                model.modal_metrics.showing_usage = !model.modal_metrics.showing_usage;
+            }
+            MsgModalMetrics::Event(ModalSelectorEvent::KeyInput(key))
+                if model.modal_metrics.showing_usage =>
+            {
+                let event = ModalSelectorEvent::KeyInput(key);
+                if let ModalSelectorUpdate::Hide = model.modal_metrics.usage.handle_event(event) {
+                    model.pop_modal();
+                }
+            }
+            MsgModalMetrics::Event(event) => {
+                if let ModalSelectorUpdate::Hide = model.modal_metrics.modal.handle_event(event) {
+                    model.pop_modal();
+                }
+            }
+        }
+        CmdOrBatch::Single(Cmd::None)
+    }
+}
+
+impl Widget for &MetricsView {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        self.render_with(&RenderCtx::default(), area, buf);
+    }
+}
+
+impl MetricsView {
+    pub fn render_with(&self, ctx: &RenderCtx, area: Rect, buf: &mut Buffer) {
+        match self.showing_usage {
+            true => self.usage.render_with(ctx, area, buf),
+            false => self.modal.render_with(ctx, area, buf),
+        }
+    }
+}
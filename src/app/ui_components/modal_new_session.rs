@@ -0,0 +1,329 @@
+//! Directory prompt for a new session
+//!
+//! "Create New Session" in the session selector asks where the session is
+//! rooted, starting from the workspace root so enter alone creates it there
+//! as before. Tab completes the directory from the files the server finds
+//! under what was typed, and another directory is checked to exist before the
+//! session is created in it.
+
+use crate::app::{
+    event_msg::{Cmd, CmdOrBatch},
+    strings::NO_MATCHING_DIRECTORY,
+    tea_model::{Model, SessionState, TimeoutType, SESSION_CREATION_TIMEOUT_MS},
+    ui_components::Component,
+};
+use crate::sdk::OpenCodeClient;
+use crossterm::event::{KeyCode, KeyEvent};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+/// Completions listed below the input, the rest being cut off
+pub const MAX_COMPLETIONS: usize = 6;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NewSessionPrompt {
+    /// The directory as typed
+    pub input: String,
+    /// Directories the last completion left to pick from, relative to the
+    /// workspace
+    pub completions: Vec<String>,
+    /// Why the directory was refused, or completion found nothing
+    pub error: Option<String>,
+    /// Directory being checked before the session is created in it
+    pub checking: Option<String>,
+}
+
+impl NewSessionPrompt {
+    /// A prompt starting at the workspace root
+    pub fn new(workspace_root: Option<&str>) -> Self {
+        Self {
+            input: workspace_root
+                .map(|root| format!("{}/", root.trim_end_matches('/')))
+                .unwrap_or_default(),
+            ..Self::default()
+        }
+    }
+
+    /// Complete the input from the directories found for `query`
+    pub fn apply_completions(&mut self, query: &str, completions: Vec<String>, root: &str) {
+        self.error = None;
+        match completions.as_slice() {
+            [] => {
+                self.error = Some(NO_MATCHING_DIRECTORY.to_string());
+                self.completions.clear();
+            }
+            [only] => {
+                self.input = format!("{}/", join_root(root, only));
+                self.completions.clear();
+            }
+            _ => {
+                let prefix = common_prefix(&completions);
+                if prefix.len() > query.len() {
+                    self.input = join_root(root, prefix);
+                }
+                self.completions = completions;
+            }
+        }
+    }
+}
+
+/// Submessage enum for the new session prompt
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum MsgNewSessionPrompt {
+    KeyInput(KeyEvent),
+    Complete,
+    Submit,
+    Cancel,
+}
+
+impl Component<Model, MsgNewSessionPrompt, Cmd> for NewSessionPrompt {
+    fn update(msg: MsgNewSessionPrompt, state: &mut Model) -> CmdOrBatch<Cmd> {
+        let model = state;
+        match msg {
+            MsgNewSessionPrompt::KeyInput(key) => {
+                let prompt = &mut model.new_session_prompt;
+                match key.code {
+                    KeyCode::Char(c) => prompt.input.push(c),
+                    KeyCode::Backspace => {
+                        prompt.input.pop();
+                    }
+                    _ => return CmdOrBatch::Single(Cmd::None),
+                }
+                prompt.completions.clear();
+                prompt.error = None;
+            }
+            MsgNewSessionPrompt::Complete => {
+                let root = model.workspace_root.as_deref();
+                let query = completion_query(&model.new_session_prompt.input, root);
+                if let (Some(client), Some(query)) = (model.client.clone(), query) {
+                    return CmdOrBatch::Single(Cmd::AsyncFindDirectories(client, query));
+                }
+            }
+            MsgNewSessionPrompt::Submit => {
+                let directory = resolve_directory(
+                    &model.new_session_prompt.input,
+                    model.workspace_root.as_deref(),
+                    dirs::home_dir().as_deref(),
+                );
+                match directory {
+                    Some(directory) => {
+                        model.new_session_prompt.error = None;
+                        model.new_session_prompt.checking = Some(directory.clone());
+                        return CmdOrBatch::Single(Cmd::AsyncCheckSessionDirectory(directory));
+                    }
+                    None => start_new_session(model, None),
+                }
+            }
+            MsgNewSessionPrompt::Cancel => {
+                model.new_session_prompt = NewSessionPrompt::default();
+                model.pop_modal();
+            }
+        }
+        CmdOrBatch::Single(Cmd::None)
+    }
+}
+
+/// Start a pending session, created in `directory` or else the workspace
+/// once its first message is sent
+pub fn start_new_session(model: &mut Model, directory: Option<String>) {
+    model.new_session_prompt = NewSessionPrompt::default();
+    model.change_session(Some(0));
+    if let SessionState::Pending(pending_info) = &mut model.session_state {
+        pending_info.directory = directory;
+    }
+}
+
+/// Create the pending session with `text` as its first message
+pub fn create_pending_session(model: &mut Model, client: OpenCodeClient, text: String) -> Cmd {
+    let SessionState::Pending(pending_info) = &model.session_state else {
+        return Cmd::None;
+    };
+    let directory = pending_info.directory.clone();
+    model.session_state = SessionState::Creating(pending_info.clone());
+    model.pending_first_message = Some(text.clone());
+    model.start_response();
+    model.set_timeout(TimeoutType::SessionCreation, SESSION_CREATION_TIMEOUT_MS);
+    Cmd::AsyncCreateSessionWithMessage(client, text, directory)
+}
+
+/// The directory `input` names, relative ones and `~` resolved, or none
+/// for the workspace root itself
+pub fn resolve_directory(
+    input: &str,
+    workspace_root: Option<&str>,
+    home: Option<&Path>,
+) -> Option<String> {
+    let input = input.trim();
+    if input.is_empty() {
+        return None;
+    }
+    let expanded = match (input.strip_prefix('~'), home) {
+        (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with('/') => {
+            home.join(rest.trim_start_matches('/'))
+        }
+        _ => PathBuf::from(input),
+    };
+    let path = match workspace_root {
+        Some(root) if expanded.is_relative() => Path::new(root).join(expanded),
+        _ => expanded,
+    };
+    // Without trailing separators or `.`, so the root compares equal however
+    // it was typed
+    let path: PathBuf = path.components().collect();
+    match workspace_root {
+        Some(root) if path == Path::new(root) => None,
+        _ => Some(path.to_string_lossy().into_owned()),
+    }
+}
+
+/// What to search the workspace's files for to complete `input`, relative to
+/// the workspace. Nothing outside it can be completed.
+pub fn completion_query(input: &str, workspace_root: Option<&str>) -> Option<String> {
+    let input = input.trim_start();
+    if input.starts_with('~') {
+        return None;
+    }
+    if !input.starts_with('/') {
+        return Some(input.trim_start_matches("./").to_string());
+    }
+    let rest = input.strip_prefix(workspace_root?.trim_end_matches('/'))?;
+    if !rest.is_empty() && !rest.starts_with('/') {
+        // A sibling sharing the root's name as a prefix
+        return None;
+    }
+    Some(rest.trim_start_matches('/').to_string())
+}
+
+/// Directories one level below where `query` ends that it's a prefix of,
+/// from the workspace-relative `files` a search found
+pub fn directory_completions(query: &str, files: &[String]) -> Vec<String> {
+    let base = &query[..query.rfind('/').map_or(0, |slash| slash + 1)];
+    let mut found = BTreeSet::new();
+    for file in files {
+        let mut parent = Path::new(file.trim_start_matches("./")).parent();
+        while let Some(directory) = parent.filter(|dir| !dir.as_os_str().is_empty()) {
+            let name = directory.to_string_lossy();
+            let below_base = name.strip_prefix(base).filter(|rest| !rest.contains('/'));
+            if below_base.is_some() && name.starts_with(query) {
+                found.insert(name.into_owned());
+            }
+            parent = directory.parent();
+        }
+    }
+    found.into_iter().collect()
+}
+
+fn join_root(root: &str, relative: &str) -> String {
+    Path::new(root)
+        .join(relative)
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Longest prefix all `items` share, on character boundaries
+fn common_prefix(items: &[String]) -> &str {
+    let Some((first, rest)) = items.split_first() else {
+        return "";
+    };
+    let mut end = first.len();
+    for item in rest {
+        end = first
+            .char_indices()
+            .zip(item.chars())
+            .take_while(|((_, a), b)| a == b)
+            .map(|((index, a), _)| index + a.len_utf8())
+            .last()
+            .unwrap_or(0)
+            .min(end);
+    }
+    &first[..end]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROOT: &str = "/home/me/project";
+
+    fn files(paths: &[&str]) -> Vec<String> {
+        paths.iter().map(|path| path.to_string()).collect()
+    }
+
+    #[test]
+    fn test_resolve_directory() {
+        let home = Some(Path::new("/home/me"));
+        let resolve = |input| resolve_directory(input, Some(ROOT), home);
+        // The workspace itself, however it's typed
+        assert_eq!(resolve(""), None);
+        assert_eq!(resolve("/home/me/project/"), None);
+        assert_eq!(resolve("."), None);
+        assert_eq!(
+            resolve("/home/me/project/api/").as_deref(),
+            Some("/home/me/project/api")
+        );
+        assert_eq!(resolve("api").as_deref(), Some("/home/me/project/api"));
+        assert_eq!(resolve("~/other").as_deref(), Some("/home/me/other"));
+        assert_eq!(resolve("/srv/app").as_deref(), Some("/srv/app"));
+        // A relative path stays relative without a workspace to anchor it
+        assert_eq!(resolve_directory("api", None, home).as_deref(), Some("api"));
+    }
+
+    #[test]
+    fn test_completion_query() {
+        assert_eq!(
+            completion_query("/home/me/project/", Some(ROOT)).as_deref(),
+            Some("")
+        );
+        assert_eq!(
+            completion_query("/home/me/project/src/ap", Some(ROOT)).as_deref(),
+            Some("src/ap")
+        );
+        assert_eq!(
+            completion_query("./src", Some(ROOT)).as_deref(),
+            Some("src")
+        );
+        assert_eq!(completion_query("/home/me/project-old", Some(ROOT)), None);
+        assert_eq!(completion_query("/srv", Some(ROOT)), None);
+        assert_eq!(completion_query("~/project", Some(ROOT)), None);
+    }
+
+    #[test]
+    fn test_directory_completions_one_level_down() {
+        let found = files(&[
+            "src/app/mod.rs",
+            "src/app/ui_components/mod.rs",
+            "./src/sdk/client.rs",
+            "src/lib.rs",
+            "scripts/build.sh",
+            "README.md",
+        ]);
+        assert_eq!(directory_completions("", &found), ["scripts", "src"]);
+        assert_eq!(directory_completions("s", &found), ["scripts", "src"]);
+        assert_eq!(
+            directory_completions("src/", &found),
+            ["src/app", "src/sdk"]
+        );
+        assert_eq!(directory_completions("src/a", &found), ["src/app"]);
+        assert!(directory_completions("docs", &found).is_empty());
+    }
+
+    #[test]
+    fn test_completions_extend_the_input() {
+        let mut prompt = NewSessionPrompt::new(Some(ROOT));
+        assert_eq!(prompt.input, "/home/me/project/");
+
+        prompt.apply_completions("s", vec!["src".to_string()], ROOT);
+        assert_eq!(prompt.input, "/home/me/project/src/");
+        assert!(prompt.completions.is_empty());
+
+        let candidates = vec!["src/sdk".to_string(), "src/storybook".to_string()];
+        prompt.apply_completions("src/", candidates.clone(), ROOT);
+        assert_eq!(prompt.input, "/home/me/project/src/s");
+        assert_eq!(prompt.completions, candidates);
+
+        prompt.apply_completions("src/x", Vec::new(), ROOT);
+        assert_eq!(prompt.input, "/home/me/project/src/s");
+        assert_eq!(prompt.error.as_deref(), Some(NO_MATCHING_DIRECTORY));
+    }
+}
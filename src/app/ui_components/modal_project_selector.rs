@@ -0,0 +1,215 @@
+use crate::app::{
+    event_msg::{Cmd, CmdOrBatch},
+    projects::{connect_project, resume_project, ProjectActivity, CONNECT_COMMAND},
+    tea_model::Model,
+    ui_components::{
+        modal_selector::ModalSelectorUpdate, Component, ModalSelector, ModalSelectorEvent,
+        RenderCtx, SelectableData, SelectorConfig, SelectorMode, TableColumn,
+    },
+};
+use crate::sdk::discovery::ServerCandidate;
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Rect},
+    style::{Color, Modifier, Style},
+    widgets::{Borders, Cell, Widget},
+};
+use serde::{Deserialize, Serialize};
+
+/// One row of the project picker
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ProjectData {
+    /// The project being shown
+    Active { root: String, url: String },
+    /// A connected project in the background
+    Parked {
+        root: String,
+        url: String,
+        activity: ProjectActivity,
+    },
+    /// A running server that isn't connected yet
+    Server(ServerCandidate),
+    /// Fills the input with the connect command, to type a URL
+    AddByUrl,
+}
+
+impl SelectableData for ProjectData {
+    fn to_cells(&self) -> Vec<Cell<'_>> {
+        match self {
+            ProjectData::Active { root, url } => vec![
+                Cell::from(root.clone()),
+                Cell::from("active").style(Style::default().fg(Color::Green)),
+                Cell::from(url.clone()),
+            ],
+            ProjectData::Parked {
+                root,
+                url,
+                activity,
+            } => vec![
+                Cell::from(root.clone()),
+                Cell::from(activity.label()).style(Style::default().fg(Color::Yellow)),
+                Cell::from(url.clone()),
+            ],
+            ProjectData::Server(candidate) => vec![
+                Cell::from(candidate.project_path.clone()),
+                Cell::from(""),
+                Cell::from(candidate.url.clone()),
+            ],
+            ProjectData::AddByUrl => vec![
+                Cell::from("Add by URL...").style(Style::default().fg(Color::Gray)),
+                Cell::from(""),
+                Cell::from(""),
+            ],
+        }
+    }
+
+    fn to_string(&self) -> String {
+        match self {
+            ProjectData::Active { root, .. } | ProjectData::Parked { root, .. } => root.clone(),
+            ProjectData::Server(candidate) => candidate.project_path.clone(),
+            ProjectData::AddByUrl => "Add by URL...".to_string(),
+        }
+    }
+}
+
+/// Submessage enum for the project picker that wraps generic events
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum MsgModalProjectSelector {
+    Event(ModalSelectorEvent<ProjectData>),
+}
+
+/// Picker of the connected projects and the other running servers
+#[derive(Debug, Clone)]
+pub struct ProjectSelector {
+    pub modal: ModalSelector<ProjectData>,
+    /// Servers found by the last discovery, listed unless already connected
+    candidates: Vec<ServerCandidate>,
+}
+
+/// Rows of the picker: the active project, the parked ones, the servers
+/// that aren't connected yet, then the entry for adding one by URL
+pub fn project_rows(model: &Model, candidates: &[ServerCandidate]) -> Vec<ProjectData> {
+    let mut rows = Vec::new();
+    let mut connected = Vec::new();
+    if let Some(client) = model.client() {
+        let url = client.base_url().to_string();
+        rows.push(ProjectData::Active {
+            root: model.project_key().unwrap_or_else(|| url.clone()),
+            url: url.clone(),
+        });
+        connected.push(url);
+    }
+    for (root, project) in model.projects.iter() {
+        let url = project.client.base_url().to_string();
+        rows.push(ProjectData::Parked {
+            root: root.clone(),
+            url: url.clone(),
+            activity: project.activity(),
+        });
+        connected.push(url);
+    }
+    rows.extend(
+        candidates
+            .iter()
+            .filter(|candidate| !connected.contains(&candidate.url))
+            .cloned()
+            .map(ProjectData::Server),
+    );
+    rows.push(ProjectData::AddByUrl);
+    rows
+}
+
+impl ProjectSelector {
+    pub fn new() -> Self {
+        let config = SelectorConfig {
+            title: Some("Switch Project".to_string()),
+            footer: Some("↑↓/Tab navigate, Enter switch, Esc cancel".to_string()),
+            max_width: Some(100),
+            max_height: Some(14),
+            padding: 1,
+            show_scrollbar: false,
+            alternating_rows: true,
+            borders: Borders::ALL,
+            border_color: Color::Blue,
+            selected_style: Style::default()
+                .add_modifier(Modifier::REVERSED)
+                .fg(Color::Blue),
+            header_style: Style::default().fg(Color::Yellow),
+            row_style: Style::default().fg(Color::White),
+            alt_row_style: None,
+            multi_select: false,
+        };
+
+        let columns = vec![
+            TableColumn::new("Project", Constraint::Min(30)),
+            TableColumn::new("Status", Constraint::Length(8)),
+            TableColumn::new("URL", Constraint::Length(24)).optional(),
+        ];
+
+        Self {
+            modal: ModalSelector::new(config, SelectorMode::Table { columns }),
+            candidates: Vec::new(),
+        }
+    }
+
+    pub fn set_candidates(&mut self, candidates: Vec<ServerCandidate>) {
+        self.candidates = candidates;
+    }
+
+    pub fn candidates(&self) -> &[ServerCandidate] {
+        &self.candidates
+    }
+}
+
+impl Default for ProjectSelector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Component<Model, MsgModalProjectSelector, Cmd> for ProjectSelector {
+    fn update(msg: MsgModalProjectSelector, state: &mut Model) -> CmdOrBatch<Cmd> {
+        let model = state;
+        match msg {
+            MsgModalProjectSelector::Event(event) => {
+                match model.modal_project_selector.modal.handle_event(event) {
+                    ModalSelectorUpdate::Hide => {
+                        model.pop_modal();
+                    }
+                    ModalSelectorUpdate::ItemSelected(data) => {
+                        model.modal_project_selector.modal.hide();
+                        model.pop_modal();
+                        return match data {
+                            ProjectData::Active { .. } => CmdOrBatch::Single(Cmd::None),
+                            ProjectData::Parked { root, .. } => resume_project(model, &root),
+                            ProjectData::Server(candidate) => {
+                                connect_project(model, &candidate.url)
+                            }
+                            ProjectData::AddByUrl => {
+                                model
+                                    .text_input_area
+                                    .set_content(&format!("{} ", CONNECT_COMMAND));
+                                model.text_input_area.move_cursor_to_end();
+                                CmdOrBatch::Single(Cmd::None)
+                            }
+                        };
+                    }
+                    ModalSelectorUpdate::ItemsSelected(_) | ModalSelectorUpdate::None => {}
+                }
+            }
+        }
+        CmdOrBatch::Single(Cmd::None)
+    }
+}
+
+impl Widget for &ProjectSelector {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        self.modal.render(area, buf);
+    }
+}
+
+impl ProjectSelector {
+    pub fn render_with(&self, ctx: &RenderCtx, area: Rect, buf: &mut Buffer) {
+        self.modal.render_with(ctx, area, buf);
+    }
+}
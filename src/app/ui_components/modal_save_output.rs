@@ -0,0 +1,365 @@
+//! Saving a tool's output to a file
+//!
+//! Some results are worth keeping verbatim, like a long bash run or a fetched
+//! page. The latest tool output is offered to be saved under a name made from
+//! the tool and the time, in the workspace, and the file is written exactly
+//! as the tool returned it. Tab toggles saving the tool's metadata as JSON
+//! beside it. An existing file is never replaced, nor a missing directory
+//! created, until enter is pressed again to confirm.
+
+use crate::app::{
+    event_msg::{Cmd, CmdOrBatch},
+    session_directory::display_directory,
+    strings::{SAVE_OUTPUT_CREATE_DIRECTORY, SAVE_OUTPUT_NO_PATH, SAVE_OUTPUT_OVERWRITE},
+    tea_model::Model,
+    ui_components::{modal_new_session::resolve_directory, Component},
+};
+use crossterm::event::{KeyCode, KeyEvent};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::{fs, io::AsyncWriteExt};
+
+/// Added to the output's path for the metadata saved beside it
+pub const METADATA_SUFFIX: &str = ".metadata.json";
+
+/// The output of a finished tool call
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ToolOutput {
+    pub tool: String,
+    pub output: String,
+    pub metadata: HashMap<String, serde_json::Value>,
+}
+
+/// What enter goes ahead with after the last attempt stopped short
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SaveConfirm {
+    Overwrite,
+    CreateDirectories,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SaveOutputPrompt {
+    /// The path as typed
+    pub input: String,
+    pub output: ToolOutput,
+    /// Whether the metadata is saved beside the output
+    pub with_metadata: bool,
+    pub confirm: Option<SaveConfirm>,
+    /// Why the last attempt stopped, or what enter would confirm
+    pub error: Option<String>,
+    /// Path being written
+    pub saving: Option<String>,
+}
+
+impl SaveOutputPrompt {
+    /// A prompt to save `output`, named for it at `now`
+    pub fn new(output: ToolOutput, now: SystemTime) -> Self {
+        Self {
+            input: default_file_name(&output.tool, now),
+            output,
+            ..Self::default()
+        }
+    }
+
+    /// Ask to confirm what stopped the save, or show why it failed
+    pub fn refused(&mut self, refusal: SaveRefusal, home: Option<&Path>) {
+        let display = |path: &str| display_directory(path, home);
+        let (confirm, error) = match refusal {
+            SaveRefusal::Exists(path) => (
+                Some(SaveConfirm::Overwrite),
+                SAVE_OUTPUT_OVERWRITE.replace("{path}", &display(&path)),
+            ),
+            SaveRefusal::NoDirectory(directory) => (
+                Some(SaveConfirm::CreateDirectories),
+                SAVE_OUTPUT_CREATE_DIRECTORY.replace("{path}", &display(&directory)),
+            ),
+            SaveRefusal::Failed(error) => (None, error),
+        };
+        self.confirm = confirm;
+        self.error = Some(error);
+    }
+}
+
+/// `./tool-<name>-<timestamp>.txt`, the time in UTC
+pub fn default_file_name(tool: &str, now: SystemTime) -> String {
+    let name: String = tool
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '_' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    let name = name.trim_matches('-');
+    let name = if name.is_empty() { "output" } else { name };
+    format!("./tool-{}-{}.txt", name, timestamp(now))
+}
+
+/// `YYYYMMDD-HHMMSS` in UTC
+fn timestamp(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default();
+    let (days, secs_of_day) = (secs / 86_400, secs % 86_400);
+    // Civil date from days since the epoch, after Howard Hinnant's algorithm
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}{:02}{:02}-{:02}{:02}{:02}",
+        year,
+        month,
+        day,
+        secs_of_day / 3600,
+        secs_of_day / 60 % 60,
+        secs_of_day % 60
+    )
+}
+
+/// A write of a tool's output, and its metadata if asked for
+#[derive(Debug, Clone, PartialEq)]
+pub struct SaveRequest {
+    pub path: String,
+    pub output: String,
+    pub metadata: Option<String>,
+    pub overwrite: bool,
+    pub create_directories: bool,
+}
+
+/// Why nothing was written
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum SaveRefusal {
+    Exists(String),      // path that would be overwritten
+    NoDirectory(String), // directory that would be created
+    Failed(String),      // error
+}
+
+/// Write the output, and the metadata beside it, returning the bytes of
+/// output written. Nothing is written if either file exists and may not be
+/// overwritten.
+pub async fn save_output(request: SaveRequest) -> Result<usize, SaveRefusal> {
+    let failed = |error: std::io::Error| SaveRefusal::Failed(error.to_string());
+    let path = Path::new(&request.path);
+    if let Some(directory) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        if fs::metadata(directory).await.is_err() {
+            if !request.create_directories {
+                return Err(SaveRefusal::NoDirectory(
+                    directory.to_string_lossy().into_owned(),
+                ));
+            }
+            fs::create_dir_all(directory).await.map_err(failed)?;
+        }
+    }
+
+    let metadata_path = format!("{}{}", request.path, METADATA_SUFFIX);
+    let mut files = vec![(request.path.as_str(), request.output.as_bytes())];
+    if let Some(metadata) = &request.metadata {
+        files.push((metadata_path.as_str(), metadata.as_bytes()));
+    }
+    if !request.overwrite {
+        for (path, _) in &files {
+            if fs::try_exists(path).await.unwrap_or(true) {
+                return Err(SaveRefusal::Exists(path.to_string()));
+            }
+        }
+    }
+    for (path, content) in files {
+        let mut options = fs::OpenOptions::new();
+        options.write(true);
+        if request.overwrite {
+            options.create(true).truncate(true);
+        } else {
+            // Still refused if it appeared since it was checked
+            options.create_new(true);
+        }
+        let mut file = options
+            .open(path)
+            .await
+            .map_err(|error| match error.kind() {
+                ErrorKind::AlreadyExists => SaveRefusal::Exists(path.to_string()),
+                _ => failed(error),
+            })?;
+        file.write_all(content).await.map_err(failed)?;
+        // Tokio writes in the background, so a dropped file may not be done
+        file.flush().await.map_err(failed)?;
+    }
+    Ok(request.output.len())
+}
+
+/// Submessage enum for the save output prompt
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum MsgSaveOutputPrompt {
+    KeyInput(KeyEvent),
+    ToggleMetadata,
+    Submit,
+    Cancel,
+}
+
+impl Component<Model, MsgSaveOutputPrompt, Cmd> for SaveOutputPrompt {
+    fn update(msg: MsgSaveOutputPrompt, state: &mut Model) -> CmdOrBatch<Cmd> {
+        let model = state;
+        let prompt = &mut model.save_output_prompt;
+        match msg {
+            MsgSaveOutputPrompt::KeyInput(key) => {
+                match key.code {
+                    KeyCode::Char(c) => prompt.input.push(c),
+                    KeyCode::Backspace => {
+                        prompt.input.pop();
+                    }
+                    _ => return CmdOrBatch::Single(Cmd::None),
+                }
+                prompt.confirm = None;
+                prompt.error = None;
+            }
+            MsgSaveOutputPrompt::ToggleMetadata => {
+                prompt.with_metadata = !prompt.with_metadata;
+                prompt.confirm = None;
+                prompt.error = None;
+            }
+            MsgSaveOutputPrompt::Submit => {
+                if prompt.saving.is_some() {
+                    return CmdOrBatch::Single(Cmd::None);
+                }
+                // Relative to the workspace, like a new session's directory
+                let path = resolve_directory(
+                    &prompt.input,
+                    model.workspace_root.as_deref(),
+                    dirs::home_dir().as_deref(),
+                );
+                let Some(path) = path else {
+                    prompt.error = Some(SAVE_OUTPUT_NO_PATH.to_string());
+                    return CmdOrBatch::Single(Cmd::None);
+                };
+                let metadata = prompt.with_metadata.then(|| {
+                    serde_json::to_string_pretty(&prompt.output.metadata).unwrap_or_default()
+                });
+                prompt.error = None;
+                prompt.saving = Some(path.clone());
+                return CmdOrBatch::Single(Cmd::AsyncSaveToolOutput(SaveRequest {
+                    path,
+                    output: prompt.output.output.clone(),
+                    metadata,
+                    overwrite: prompt.confirm == Some(SaveConfirm::Overwrite),
+                    create_directories: prompt.confirm == Some(SaveConfirm::CreateDirectories),
+                }));
+            }
+            MsgSaveOutputPrompt::Cancel => {
+                model.save_output_prompt = SaveOutputPrompt::default();
+                model.pop_modal();
+            }
+        }
+        CmdOrBatch::Single(Cmd::None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn request(path: &Path, overwrite: bool, create_directories: bool) -> SaveRequest {
+        SaveRequest {
+            path: path.to_string_lossy().into_owned(),
+            output: "line one\nline two\n".to_string(),
+            metadata: Some("{\"exit\": 1}".to_string()),
+            overwrite,
+            create_directories,
+        }
+    }
+
+    #[test]
+    fn test_default_file_name() {
+        let now = UNIX_EPOCH + Duration::from_secs(1_760_612_100);
+        assert_eq!(
+            default_file_name("bash", now),
+            "./tool-bash-20251016-105500.txt"
+        );
+        assert_eq!(
+            default_file_name("github/get_issue", UNIX_EPOCH),
+            "./tool-github-get_issue-19700101-000000.txt"
+        );
+        // A leap day, and nothing left of the name
+        let leap_day = UNIX_EPOCH + Duration::from_secs(951_782_400 + 86_399);
+        assert_eq!(
+            default_file_name("/", leap_day),
+            "./tool-output-20000229-235959.txt"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_existing_files_kept_until_overwrite_is_confirmed() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("tool-bash.txt");
+        let metadata_path = format!("{}{}", path.display(), METADATA_SUFFIX);
+        std::fs::write(&metadata_path, "kept").unwrap();
+
+        // The metadata file alone stops both from being written
+        assert_eq!(
+            save_output(request(&path, false, false)).await,
+            Err(SaveRefusal::Exists(metadata_path.clone()))
+        );
+        assert!(!path.exists());
+        assert_eq!(std::fs::read_to_string(&metadata_path).unwrap(), "kept");
+
+        assert_eq!(save_output(request(&path, true, false)).await, Ok(18));
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "line one\nline two\n"
+        );
+        assert_eq!(
+            std::fs::read_to_string(&metadata_path).unwrap(),
+            "{\"exit\": 1}"
+        );
+        assert_eq!(
+            save_output(request(&path, false, false)).await,
+            Err(SaveRefusal::Exists(path.to_string_lossy().into_owned()))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_directories_created_only_when_asked() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let directory = temp_dir.path().join("logs").join("ci");
+        let path = directory.join("tool-bash.txt");
+        assert_eq!(
+            save_output(request(&path, false, false)).await,
+            Err(SaveRefusal::NoDirectory(
+                directory.to_string_lossy().into_owned()
+            ))
+        );
+        assert!(!directory.exists());
+
+        assert_eq!(save_output(request(&path, false, true)).await, Ok(18));
+        assert!(path.exists());
+    }
+
+    #[test]
+    fn test_refusals_ask_to_confirm() {
+        let mut prompt = SaveOutputPrompt::new(ToolOutput::default(), UNIX_EPOCH);
+        let home = Some(Path::new("/home/me"));
+        prompt.refused(SaveRefusal::Exists("/home/me/out.txt".to_string()), home);
+        assert_eq!(prompt.confirm, Some(SaveConfirm::Overwrite));
+        assert!(prompt.error.as_deref().unwrap().contains("~/out.txt"));
+
+        prompt.refused(SaveRefusal::Failed("permission denied".to_string()), home);
+        assert_eq!(prompt.confirm, None);
+        assert_eq!(prompt.error.as_deref(), Some("permission denied"));
+    }
+}
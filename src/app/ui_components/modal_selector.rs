@@ -1,21 +1,19 @@
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, ModifierKeyCode};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use ratatui::{
     buffer::Buffer,
     layout::{Constraint, Rect},
-    style::{Color, Modifier, Style},
+    style::{Color, Style},
     text::{Line, Span, Text},
     widgets::{
-        Block, Borders, Cell, List, ListItem, Padding, Paragraph, Row, Scrollbar,
-        ScrollbarOrientation, ScrollbarState, Table, TableState, Widget,
+        Block, Borders, Cell, List, ListItem, Padding, Row, Scrollbar, ScrollbarOrientation,
+        ScrollbarState, Table, TableState, Widget,
     },
 };
-use std::marker::PhantomData;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeSet, marker::PhantomData};
 
-use crate::app::ui_components::Component;
-use crate::app::{
-    tea_view::{clear_area_for_rect, MAX_UI_WIDTH},
-    view_model_context::ViewModelContext,
-};
+use crate::app::tea_view::{clear_area_for_rect, MAX_UI_WIDTH};
+use crate::app::ui_components::{LayoutClass, RenderCtx};
 
 /// Configuration for table columns
 #[derive(Debug, Clone, PartialEq)]
@@ -23,6 +21,8 @@ pub struct TableColumn {
     pub header: String,
     pub constraint: Constraint,
     pub alignment: Option<ratatui::layout::Alignment>,
+    /// Dropped on a narrow frame, along with its cells
+    pub optional: bool,
 }
 
 impl TableColumn {
@@ -31,6 +31,7 @@ impl TableColumn {
             header: header.into(),
             constraint,
             alignment: None,
+            optional: false,
         }
     }
 
@@ -38,6 +39,11 @@ impl TableColumn {
         self.alignment = Some(alignment);
         self
     }
+
+    pub fn optional(mut self) -> Self {
+        self.optional = true;
+        self
+    }
 }
 
 /// Configuration for the modal selector appearance
@@ -56,6 +62,8 @@ pub struct SelectorConfig {
     pub header_style: Style,
     pub row_style: Style,
     pub alt_row_style: Option<Style>,
+    /// Space checks items, and Enter picks every checked one
+    pub multi_select: bool,
 }
 
 impl Default for SelectorConfig {
@@ -76,6 +84,7 @@ impl Default for SelectorConfig {
             header_style: Style::default().fg(Color::Gray),
             row_style: Style::default().fg(Color::White),
             alt_row_style: Some(Style::default().bg(Color::DarkGray)),
+            multi_select: false,
         }
     }
 }
@@ -92,6 +101,14 @@ pub trait SelectableData: Clone {
     fn to_spans(&self) -> Option<Vec<Span>> {
         None
     }
+
+    /// Separator rows, like group headers, are shown but never selected
+    fn is_separator(&self) -> bool {
+        false
+    }
+
+    /// Told when the item is checked or unchecked, to show it
+    fn set_toggled(&mut self, _toggled: bool) {}
 }
 
 /// Display mode for the selector
@@ -102,7 +119,7 @@ pub enum SelectorMode {
 }
 
 /// Generic events that can be handled by any modal selector
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum ModalSelectorEvent<T>
 where
     T: SelectableData + Clone,
@@ -122,6 +139,8 @@ where
 {
     Hide,
     ItemSelected(T),
+    /// The checked items, in list order, from a multi-select selector
+    ItemsSelected(Vec<T>),
     None,
 }
 
@@ -136,9 +155,13 @@ where
     pub items: Vec<T>,
     pub state: TableState, // Used for both table and list selection
     pub scroll_state: ScrollbarState,
+    /// Indices of the checked items
+    pub toggled: BTreeSet<usize>,
     pub is_visible: bool,
     pub loading: bool,
     pub error: Option<String>,
+    /// Shown in place of the items while there are none
+    pub empty_text: Option<String>,
     _phantom: PhantomData<T>,
 }
 
@@ -156,9 +179,11 @@ where
             items: Vec::new(),
             state,
             scroll_state: ScrollbarState::new(0),
+            toggled: BTreeSet::new(),
             is_visible: false,
             loading: false,
             error: None,
+            empty_text: None,
             _phantom: PhantomData,
         }
     }
@@ -211,39 +236,44 @@ where
     pub fn set_items(&mut self, items: Vec<T>) {
         self.items = items;
         self.scroll_state = ScrollbarState::new(self.items.len());
-        self.state
-            .select(if self.items.is_empty() { None } else { Some(0) });
+        self.toggled.clear();
+        self.select(0);
         self.loading = false;
         self.error = None;
     }
 
+    /// Select the item at `index`, or the next selectable one after it
+    pub fn select(&mut self, index: usize) {
+        let selectable = (index..self.items.len()).find(|&i| !self.items[i].is_separator());
+        self.state
+            .select(selectable.or_else(|| self.step_from(index, false)));
+    }
+
     // Navigation methods
     pub fn navigate_up(&mut self) {
-        if self.items.is_empty() {
-            return;
-        }
-
         let current = self.state.selected().unwrap_or(0);
-        let new_index = if current == 0 {
-            self.items.len() - 1
-        } else {
-            current - 1
-        };
-        self.state.select(Some(new_index));
+        if let Some(new_index) = self.step_from(current, false) {
+            self.state.select(Some(new_index));
+        }
     }
 
     pub fn navigate_down(&mut self) {
-        if self.items.is_empty() {
-            return;
+        let current = self.state.selected().unwrap_or(0);
+        if let Some(new_index) = self.step_from(current, true) {
+            self.state.select(Some(new_index));
         }
+    }
 
-        let current = self.state.selected().unwrap_or(0);
-        let new_index = if current >= self.items.len() - 1 {
-            0
-        } else {
-            current + 1
-        };
-        self.state.select(Some(new_index));
+    /// The nearest selectable item before or after `index`, wrapping around
+    /// the ends and skipping separators
+    fn step_from(&self, index: usize, forward: bool) -> Option<usize> {
+        let len = self.items.len();
+        (1..=len)
+            .map(|step| match forward {
+                true => (index + step) % len,
+                false => (index + len - step % len) % len,
+            })
+            .find(|&i| !self.items[i].is_separator())
     }
 
     pub fn selected_index(&self) -> Option<usize> {
@@ -258,6 +288,39 @@ where
         &self.items
     }
 
+    /// Check the highlighted item, or uncheck it if it was
+    pub fn toggle_selected(&mut self) {
+        if let Some(index) = self.selected_index() {
+            let toggled = !self.is_toggled(index);
+            self.set_toggled(index, toggled);
+        }
+    }
+
+    pub fn set_toggled(&mut self, index: usize, toggled: bool) {
+        let Some(item) = self.items.get_mut(index) else {
+            return;
+        };
+        if item.is_separator() {
+            return;
+        }
+        item.set_toggled(toggled);
+        match toggled {
+            true => self.toggled.insert(index),
+            false => self.toggled.remove(&index),
+        };
+    }
+
+    pub fn is_toggled(&self, index: usize) -> bool {
+        self.toggled.contains(&index)
+    }
+
+    pub fn toggled_items(&self) -> Vec<T> {
+        self.toggled
+            .iter()
+            .filter_map(|&index| self.items.get(index).cloned())
+            .collect()
+    }
+
     // Generic event handling
     pub fn handle_event(&mut self, event: ModalSelectorEvent<T>) -> ModalSelectorUpdate<T> {
         match event {
@@ -307,6 +370,14 @@ where
                 };
                 ModalSelectorUpdate::None
             }
+            KeyCode::Char(' ') if self.config.multi_select => {
+                self.toggle_selected();
+                ModalSelectorUpdate::None
+            }
+            // With nothing checked, Enter picks the highlighted item as usual
+            KeyCode::Enter if self.config.multi_select && !self.toggled.is_empty() => {
+                ModalSelectorUpdate::ItemsSelected(self.toggled_items())
+            }
             KeyCode::Enter => {
                 if let Some(item) = self.selected_item() {
                     ModalSelectorUpdate::ItemSelected(item.clone())
@@ -319,16 +390,14 @@ where
     }
 
     // Rendering methods
-    fn render_loading(&self, area: Rect, buf: &mut Buffer) {
-        let model = ViewModelContext::current();
-
+    fn render_loading(&self, ctx: &RenderCtx, area: Rect, buf: &mut Buffer) {
         let mut block = Block::default()
             .padding(Padding::uniform(self.config.padding))
             .borders(self.config.borders)
-            .border_type(model.border_type())
+            .border_set(ctx.border_set())
             .border_style(Style::default().fg(self.config.border_color));
         if let Some(title) = &self.config.title {
-            block = block.title_top(title.clone())
+            block = block.title_top(ctx.glyphs.text(title).into_owned())
         }
 
         let loading_text = Text::from("Loading...");
@@ -339,16 +408,14 @@ where
         paragraph.render(area, buf);
     }
 
-    fn render_error(&self, area: Rect, buf: &mut Buffer, error: &str) {
-        let model = ViewModelContext::current();
-
+    fn render_error(&self, ctx: &RenderCtx, area: Rect, buf: &mut Buffer, error: &str) {
         let mut block = Block::default()
             .padding(Padding::uniform(self.config.padding))
             .borders(self.config.borders)
-            .border_type(model.border_type())
+            .border_set(ctx.border_set())
             .border_style(Style::default().fg(Color::Red));
         if let Some(title) = &self.config.title {
-            block = block.title_top(title.clone())
+            block = block.title_top(ctx.glyphs.text(title).into_owned())
         }
 
         let error_text = Text::from(format!("Error: {}", error));
@@ -359,23 +426,21 @@ where
         paragraph.render(area, buf);
     }
 
-    fn render_list(&self, area: Rect, buf: &mut Buffer) {
-        let model = ViewModelContext::current();
-
+    fn render_list(&self, ctx: &RenderCtx, area: Rect, buf: &mut Buffer) {
         let mut block = Block::default()
             .padding(Padding::uniform(self.config.padding))
             .borders(self.config.borders)
-            .border_type(model.border_type())
+            .border_set(ctx.border_set())
             .border_style(Style::default().fg(self.config.border_color));
         if let Some(title) = &self.config.title {
-            block = block.title_top(title.clone())
+            block = block.title_top(ctx.glyphs.text(title).into_owned())
         }
         if let Some(footer) = &self.config.footer {
-            block = block.title_bottom(footer.clone())
+            block = block.title_bottom(ctx.glyphs.text(footer).into_owned())
         }
 
         if self.items.is_empty() {
-            let empty_text = Text::from("No items found");
+            let empty_text = Text::from(self.empty_text.as_deref().unwrap_or("No items found"));
             let paragraph = ratatui::widgets::Paragraph::new(empty_text)
                 .style(self.config.row_style)
                 .block(block);
@@ -388,14 +453,26 @@ where
             .iter()
             .enumerate()
             .map(|(i, item)| {
-                let style = if Some(i) == self.selected_index() {
+                let style = if item.is_separator() {
+                    self.config.header_style
+                } else if Some(i) == self.selected_index() {
                     self.config.selected_style
                 } else {
                     self.config.row_style
                 };
 
                 let content = if let Some(spans) = item.to_spans() {
-                    Line::from(spans)
+                    Line::from(
+                        spans
+                            .into_iter()
+                            .map(|span| {
+                                Span::styled(
+                                    ctx.glyphs.text(&span.content).into_owned(),
+                                    span.style,
+                                )
+                            })
+                            .collect::<Vec<_>>(),
+                    )
                 } else {
                     Line::from(item.to_string())
                 };
@@ -408,24 +485,31 @@ where
         list.render(area, buf);
     }
 
-    fn render_table(&self, area: Rect, buf: &mut Buffer, columns: &[TableColumn]) {
-        let model = ViewModelContext::current();
-
+    fn render_table(
+        &self,
+        ctx: &RenderCtx,
+        area: Rect,
+        buf: &mut Buffer,
+        columns: &[TableColumn],
+        layout: LayoutClass,
+    ) {
         let mut block = Block::default()
             .padding(Padding::uniform(self.config.padding))
             .borders(self.config.borders)
-            .border_type(model.border_type())
+            .border_set(ctx.border_set())
             .border_style(Style::default().fg(self.config.border_color));
         if let Some(title) = &self.config.title {
-            block = block.title_top(title.clone())
+            block = block.title_top(ctx.glyphs.text(title).into_owned())
         }
         if let Some(footer) = &self.config.footer {
-            block = block.title_bottom(footer.clone())
+            block = block.title_bottom(ctx.glyphs.text(footer).into_owned())
         }
 
         if self.items.is_empty() {
             let empty_table = Table::new(
-                [Row::new([Cell::from("No matching items")])],
+                [Row::new([Cell::from(
+                    self.empty_text.as_deref().unwrap_or("No matching items"),
+                )])],
                 [Constraint::Percentage(100)],
             )
             .block(block);
@@ -433,11 +517,16 @@ where
             return;
         }
 
+        // Keep only the columns that fit the frame
+        let shown: Vec<usize> = (0..columns.len())
+            .filter(|&i| !(layout.narrow && columns[i].optional))
+            .collect();
+
         // Create header
         let header = Row::new(
-            columns
+            shown
                 .iter()
-                .map(|col| Cell::from(col.header.clone()))
+                .map(|&i| Cell::from(columns[i].header.clone()))
                 .collect::<Vec<_>>(),
         )
         .style(self.config.header_style)
@@ -445,7 +534,9 @@ where
 
         // Create rows
         let rows = self.items.iter().enumerate().map(|(i, item)| {
-            let style = if Some(i) == self.selected_index() {
+            let style = if item.is_separator() {
+                self.config.header_style
+            } else if Some(i) == self.selected_index() {
                 self.config.selected_style
             } else if self.config.alternating_rows && i % 2 == 1 {
                 self.config.alt_row_style.unwrap_or(self.config.row_style)
@@ -453,11 +544,17 @@ where
                 self.config.row_style
             };
 
-            Row::new(item.to_cells()).style(style).height(1)
+            let cells = item
+                .to_cells()
+                .into_iter()
+                .enumerate()
+                .filter(|(i, _)| shown.contains(i))
+                .map(|(_, cell)| cell);
+            Row::new(cells).style(style).height(1)
         });
 
         // Extract constraints from columns
-        let constraints: Vec<Constraint> = columns.iter().map(|col| col.constraint).collect();
+        let constraints: Vec<Constraint> = shown.iter().map(|&i| columns[i].constraint).collect();
 
         let table = Table::new(rows, constraints)
             .header(header)
@@ -486,6 +583,7 @@ where
             let mut scroll_state = self.scroll_state.clone();
             let scrollbar = Scrollbar::default()
                 .orientation(ScrollbarOrientation::VerticalRight)
+                .symbols(ctx.glyphs.scrollbar_modal.clone())
                 .begin_symbol(None)
                 .end_symbol(None);
             ratatui::widgets::StatefulWidget::render(
@@ -499,11 +597,18 @@ where
 
     fn calculate_popup_area(&self, area: Rect) -> Rect {
         let popup_width = self.config.max_width.unwrap_or(area.width).min(area.width);
+        // Room for the borders and padding around the items, and a header
+        let borders = self.config.borders.contains(Borders::TOP) as u16
+            + self.config.borders.contains(Borders::BOTTOM) as u16;
+        // Without a border on its side, a title or footer takes a row
+        let titles = (self.config.title.is_some() && !self.config.borders.contains(Borders::TOP))
+            as u16
+            + (self.config.footer.is_some() && !self.config.borders.contains(Borders::BOTTOM))
+                as u16;
+        let chrome = borders + titles + self.config.padding * 2;
         let popup_height = match &self.mode {
-            SelectorMode::List => (self.items.len() as u16).saturating_add(self.config.padding * 2),
-            SelectorMode::Table { .. } => {
-                (self.items.len() as u16).saturating_add(self.config.padding * 2)
-            }
+            SelectorMode::List => (self.items.len() as u16).saturating_add(chrome),
+            SelectorMode::Table { .. } => (self.items.len() as u16).saturating_add(chrome + 1),
         }
         .min(
             area.height
@@ -528,23 +633,157 @@ where
     T: SelectableData + Clone,
 {
     fn render(self, area: Rect, buf: &mut Buffer) {
+        self.render_with(&RenderCtx::default(), area, buf);
+    }
+}
+
+impl<T> ModalSelector<T>
+where
+    T: SelectableData + Clone,
+{
+    pub fn render_with(&self, ctx: &RenderCtx, area: Rect, buf: &mut Buffer) {
         if !self.is_visible {
             return;
         }
 
+        let layout = LayoutClass::from_area(area);
         let popup_area = self.calculate_popup_area(area);
         clear_area_for_rect(buf, popup_area);
 
         // Render content based on state
         if self.loading {
-            self.render_loading(popup_area, buf);
+            self.render_loading(ctx, popup_area, buf);
         } else if let Some(error) = &self.error {
-            self.render_error(popup_area, buf, error);
+            self.render_error(ctx, popup_area, buf, error);
         } else {
             match &self.mode {
-                SelectorMode::List => self.render_list(popup_area, buf),
-                SelectorMode::Table { columns } => self.render_table(popup_area, buf, columns),
+                SelectorMode::List => self.render_list(ctx, popup_area, buf),
+                SelectorMode::Table { columns } => {
+                    self.render_table(ctx, popup_area, buf, columns, layout)
+                }
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Item(&'static str);
+
+    impl SelectableData for Item {
+        fn to_cells(&self) -> Vec<Cell> {
+            vec![Cell::from(self.0)]
+        }
+
+        fn to_string(&self) -> String {
+            self.0.to_string()
+        }
+
+        fn is_separator(&self) -> bool {
+            self.0.starts_with('-')
+        }
+    }
+
+    fn selector_of(rows: &[&'static str]) -> ModalSelector<Item> {
+        let mut selector = ModalSelector::list("Rows");
+        selector.set_items(rows.iter().map(|row| Item(row)).collect());
+        selector
+    }
+
+    fn selected(selector: &ModalSelector<Item>) -> Option<&'static str> {
+        selector.selected_item().map(|row| row.0)
+    }
+
+    #[test]
+    fn test_navigation_skips_separators() {
+        let mut selector = selector_of(&["-today", "a", "b", "-earlier", "c"]);
+        assert_eq!(selected(&selector), Some("a"));
+
+        selector.navigate_down();
+        selector.navigate_down();
+        assert_eq!(selected(&selector), Some("c"));
+        // Wraps past the leading separator
+        selector.navigate_down();
+        assert_eq!(selected(&selector), Some("a"));
+        selector.navigate_up();
+        assert_eq!(selected(&selector), Some("c"));
+        selector.navigate_up();
+        assert_eq!(selected(&selector), Some("b"));
+
+        selector.select(3);
+        assert_eq!(selected(&selector), Some("c"));
+    }
+
+    #[test]
+    fn test_only_separators_select_nothing() {
+        let mut selector = selector_of(&["-today", "-earlier"]);
+        assert_eq!(selected(&selector), None);
+        selector.navigate_down();
+        assert_eq!(selected(&selector), None);
+        assert_eq!(
+            selector.handle_event(ModalSelectorEvent::KeyInput(KeyEvent::from(KeyCode::Enter))),
+            ModalSelectorUpdate::None
+        );
+
+        let mut empty = selector_of(&[]);
+        empty.navigate_up();
+        assert_eq!(selected(&empty), None);
+    }
+
+    #[test]
+    fn test_checked_items_kept_across_navigation() {
+        let mut selector = selector_of(&["-today", "a", "b", "c"]);
+        selector.config.multi_select = true;
+        let press = |selector: &mut ModalSelector<Item>, code| {
+            selector.handle_event(ModalSelectorEvent::KeyInput(KeyEvent::from(code)))
+        };
+
+        press(&mut selector, KeyCode::Char(' '));
+        press(&mut selector, KeyCode::Down);
+        press(&mut selector, KeyCode::Down);
+        press(&mut selector, KeyCode::Char(' '));
+        // Checked twice is unchecked again
+        press(&mut selector, KeyCode::Up);
+        press(&mut selector, KeyCode::Char(' '));
+        press(&mut selector, KeyCode::Char(' '));
+        assert_eq!(selected(&selector), Some("b"));
+        assert_eq!(selector.toggled_items(), vec![Item("a"), Item("c")]);
+        assert_eq!(
+            press(&mut selector, KeyCode::Enter),
+            ModalSelectorUpdate::ItemsSelected(vec![Item("a"), Item("c")])
+        );
+
+        // Separators can't be checked
+        selector.set_toggled(0, true);
+        assert!(!selector.is_toggled(0));
+
+        // With everything unchecked Enter picks the highlighted item
+        selector.set_toggled(1, false);
+        selector.set_toggled(3, false);
+        assert_eq!(
+            press(&mut selector, KeyCode::Enter),
+            ModalSelectorUpdate::ItemSelected(Item("b"))
+        );
+
+        // New items start unchecked
+        selector.toggle_selected();
+        selector.set_items(vec![Item("a"), Item("b")]);
+        assert!(selector.toggled_items().is_empty());
+    }
+
+    #[test]
+    fn test_space_does_nothing_without_multi_select() {
+        let mut selector = selector_of(&["a", "b"]);
+        selector.handle_event(ModalSelectorEvent::KeyInput(KeyEvent::from(KeyCode::Char(
+            ' ',
+        ))));
+        selector.toggle_selected();
+        assert_eq!(
+            selector.handle_event(ModalSelectorEvent::KeyInput(KeyEvent::from(KeyCode::Enter))),
+            ModalSelectorUpdate::ItemSelected(Item("a"))
+        );
+    }
+}
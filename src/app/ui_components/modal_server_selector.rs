@@ -0,0 +1,130 @@
+use crate::app::{
+    event_msg::{Cmd, CmdOrBatch},
+    tea_model::{AppModalState, ConnectionStatus, Model},
+    ui_components::{
+        modal_selector::ModalSelectorUpdate, Component, ModalSelector, ModalSelectorEvent,
+        RenderCtx, SelectableData, SelectorConfig, SelectorMode, TableColumn,
+    },
+};
+use crate::sdk::discovery::ServerCandidate;
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Rect},
+    style::{Color, Modifier, Style},
+    widgets::{Borders, Cell, Widget},
+};
+use serde::{Deserialize, Serialize};
+
+/// Data wrapper for server selection
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ServerData {
+    pub candidate: ServerCandidate,
+}
+
+impl SelectableData for ServerData {
+    fn to_cells(&self) -> Vec<Cell<'_>> {
+        vec![
+            Cell::from(self.candidate.project_path.clone()),
+            Cell::from(self.candidate.url.clone()),
+            Cell::from(self.candidate.version.clone().unwrap_or_default()),
+        ]
+    }
+
+    fn to_string(&self) -> String {
+        format!("{} ({})", self.candidate.project_path, self.candidate.url)
+    }
+}
+
+/// Submessage enum for the server selector that wraps generic events
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum MsgModalServerSelector {
+    Event(ModalSelectorEvent<ServerData>),
+}
+
+/// Picker shown when several servers are running and none is for this project
+#[derive(Debug, Clone)]
+pub struct ServerSelector {
+    pub modal: ModalSelector<ServerData>,
+}
+
+impl ServerSelector {
+    pub fn new() -> Self {
+        let config = SelectorConfig {
+            title: Some("Choose Server".to_string()),
+            footer: Some("↑↓/Tab navigate, Enter connect, Esc cancel".to_string()),
+            max_width: Some(90),
+            max_height: Some(12),
+            padding: 1,
+            show_scrollbar: false,
+            alternating_rows: true,
+            borders: Borders::ALL,
+            border_color: Color::Blue,
+            selected_style: Style::default()
+                .add_modifier(Modifier::REVERSED)
+                .fg(Color::Blue),
+            header_style: Style::default().fg(Color::Yellow),
+            row_style: Style::default().fg(Color::White),
+            alt_row_style: None,
+            multi_select: false,
+        };
+
+        let columns = vec![
+            TableColumn::new("Project", Constraint::Min(30)),
+            TableColumn::new("URL", Constraint::Length(24)),
+            TableColumn::new("Version", Constraint::Length(10)).optional(),
+        ];
+
+        Self {
+            modal: ModalSelector::new(config, SelectorMode::Table { columns }),
+        }
+    }
+
+    pub fn set_candidates(&mut self, candidates: Vec<ServerCandidate>) {
+        let data = candidates
+            .into_iter()
+            .map(|candidate| ServerData { candidate })
+            .collect();
+        self.modal.set_items(data);
+    }
+}
+
+impl Default for ServerSelector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Component<Model, MsgModalServerSelector, Cmd> for ServerSelector {
+    fn update(msg: MsgModalServerSelector, state: &mut Model) -> CmdOrBatch<Cmd> {
+        let model = state;
+        match msg {
+            MsgModalServerSelector::Event(event) => {
+                match model.modal_server_selector.modal.handle_event(event) {
+                    ModalSelectorUpdate::Hide => {
+                        model.modal_server_selector.modal.hide();
+                        model.set_state(AppModalState::Connecting(ConnectionStatus::Disconnected));
+                    }
+                    ModalSelectorUpdate::ItemSelected(data) => {
+                        model.modal_server_selector.modal.hide();
+                        model.set_state(AppModalState::Connecting(ConnectionStatus::Connecting));
+                        return CmdOrBatch::Single(Cmd::AsyncConnectServer(data.candidate.url));
+                    }
+                    ModalSelectorUpdate::ItemsSelected(_) | ModalSelectorUpdate::None => {}
+                }
+            }
+        }
+        CmdOrBatch::Single(Cmd::None)
+    }
+}
+
+impl Widget for &ServerSelector {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        self.modal.render(area, buf);
+    }
+}
+
+impl ServerSelector {
+    pub fn render_with(&self, ctx: &RenderCtx, area: Rect, buf: &mut Buffer) {
+        self.modal.render_with(ctx, area, buf);
+    }
+}
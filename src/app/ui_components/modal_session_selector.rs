@@ -1,10 +1,12 @@
 use crate::app::{
     event_msg::{Cmd, CmdOrBatch},
+    session_templates::{start_template_session, BLANK_TEMPLATE},
     tea_model::{AppModalState, Model},
     ui_components::{
         modal_selector::ModalSelectorUpdate, Component, ModalSelector, ModalSelectorEvent,
-        SelectableData, SelectorConfig, SelectorMode,
+        NewSessionPrompt, RenderCtx, SelectableData, SelectorConfig, SelectorMode,
     },
+    working_time::format_working_time,
 };
 use opencode_sdk::models::Session;
 use ratatui::{
@@ -14,13 +16,131 @@ use ratatui::{
     text::Span,
     widgets::{Borders, Cell, Widget},
 };
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::time::Duration;
+
+const DAY_MS: f64 = 24.0 * 60.0 * 60.0 * 1000.0;
+
+/// Order of the sessions in the selector, cycled with `s`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SessionSort {
+    #[default]
+    Updated,
+    Created,
+    Title,
+}
+
+impl SessionSort {
+    pub fn next(self) -> Self {
+        match self {
+            SessionSort::Updated => SessionSort::Created,
+            SessionSort::Created => SessionSort::Title,
+            SessionSort::Title => SessionSort::Updated,
+        }
+    }
+
+    /// Name in the config file
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SessionSort::Updated => "updated",
+            SessionSort::Created => "created",
+            SessionSort::Title => "title",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            SessionSort::Updated => "recently updated",
+            SessionSort::Created => "created",
+            SessionSort::Title => "title A–Z",
+        }
+    }
+
+    /// Newest first for the recency sorts, falling back to recency for
+    /// sessions with the same title
+    pub fn compare(self, a: &Session, b: &Session) -> Ordering {
+        match self {
+            SessionSort::Updated => b.time.updated.total_cmp(&a.time.updated),
+            SessionSort::Created => b.time.created.total_cmp(&a.time.created),
+            SessionSort::Title => a
+                .title
+                .to_lowercase()
+                .cmp(&b.title.to_lowercase())
+                .then_with(|| SessionSort::Updated.compare(a, b)),
+        }
+    }
+
+    /// The time sessions are grouped by days of, for the recency sorts
+    fn group_time(self, session: &Session) -> Option<f64> {
+        match self {
+            SessionSort::Updated => Some(session.time.updated),
+            SessionSort::Created => Some(session.time.created),
+            SessionSort::Title => None,
+        }
+    }
+}
+
+/// Day header for a session at `time_ms`. Days are counted back from now
+/// rather than from local midnight, as the timestamps carry no time zone.
+pub fn day_group(time_ms: f64, now_ms: f64) -> &'static str {
+    let age_ms = now_ms - time_ms;
+    if age_ms < DAY_MS {
+        "Today"
+    } else if age_ms < 2.0 * DAY_MS {
+        "Yesterday"
+    } else {
+        "Earlier"
+    }
+}
+
+/// Selector rows for `sessions` in `sort` order after "Create New Session",
+/// with a header above each day when sorting by recency and the directory
+/// labelled beside sessions rooted away from the workspace
+pub fn session_rows(
+    sessions: &[Session],
+    current_session_id: Option<&str>,
+    directory_labels: &HashMap<String, String>,
+    sort: SessionSort,
+    now_ms: f64,
+) -> Vec<SessionData> {
+    let mut sorted: Vec<&Session> = sessions.iter().collect();
+    sorted.sort_by(|a, b| sort.compare(a, b));
+
+    let mut rows = vec![SessionData::new_session()];
+    let mut group = None;
+    for session in sorted {
+        if let Some(time) = sort.group_time(session) {
+            let session_group = day_group(time, now_ms);
+            if group != Some(session_group) {
+                group = Some(session_group);
+                rows.push(SessionData::header(session_group));
+            }
+        }
+        let is_current = current_session_id == Some(session.id.as_str());
+        let mut row = SessionData::from_session(session, is_current);
+        row.directory = directory_labels.get(&session.id).cloned();
+        rows.push(row);
+    }
+    rows
+}
 
 /// Data wrapper for session selection
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct SessionData {
     pub session: Option<Session>,
     pub display_text: String,
+    /// Where the session is rooted, when it isn't the workspace root
+    pub directory: Option<String>,
+    /// Time the agent spent working in the session, when it has any
+    pub working_time: Option<String>,
     pub is_current: bool,
+    pub is_header: bool,
+    /// Template to start the new session from, for the rows under
+    /// "Create New Session"
+    pub template: Option<String>,
 }
 
 impl SessionData {
@@ -28,15 +148,55 @@ impl SessionData {
         Self {
             session: None,
             display_text: "Create New Session".to_string(),
+            directory: None,
+            working_time: None,
             is_current: false,
+            is_header: false,
+            template: None,
         }
     }
 
     pub fn from_session(session: &Session, is_current: bool) -> Self {
         Self {
             display_text: session.title.clone(),
+            directory: None,
+            working_time: None,
             session: Some(session.clone()),
             is_current,
+            is_header: false,
+            template: None,
+        }
+    }
+
+    /// ID of the session, or none for "Create New Session"
+    pub fn session_id(&self) -> Option<&str> {
+        self.session.as_ref().map(|session| session.id.as_str())
+    }
+
+    /// A group header, which can't be selected
+    pub fn header(label: &str) -> Self {
+        Self {
+            session: None,
+            display_text: label.to_string(),
+            directory: None,
+            working_time: None,
+            is_current: false,
+            is_header: true,
+            template: None,
+        }
+    }
+
+    /// A template to start the new session from, indented under
+    /// "Create New Session"
+    pub fn template(name: &str) -> Self {
+        Self {
+            session: None,
+            display_text: format!("  {}", name),
+            directory: None,
+            working_time: None,
+            is_current: false,
+            is_header: false,
+            template: Some(name.to_string()),
         }
     }
 }
@@ -51,9 +211,12 @@ impl SelectableData for SessionData {
     }
 
     fn to_spans(&self) -> Option<Vec<Span>> {
+        if self.is_header {
+            return Some(vec![Span::raw(&self.display_text)]);
+        }
         let prefix = if self.is_current { "* " } else { "  " };
 
-        Some(vec![
+        let mut spans = vec![
             Span::styled(
                 prefix,
                 if self.is_current {
@@ -63,15 +226,33 @@ impl SelectableData for SessionData {
                 },
             ),
             Span::raw(&self.display_text),
-        ])
+        ];
+        if let Some(working_time) = &self.working_time {
+            spans.push(Span::styled(
+                format!("  ⏱ {}", working_time),
+                Style::default().fg(Color::DarkGray),
+            ));
+        }
+        if let Some(directory) = &self.directory {
+            spans.push(Span::styled(
+                format!("  {}", directory),
+                Style::default().fg(Color::DarkGray),
+            ));
+        }
+        Some(spans)
+    }
+
+    fn is_separator(&self) -> bool {
+        self.is_header
     }
 }
 
 /// Submessage enum for the session selector that wraps generic events
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum MsgModalSessionSelector {
     Event(ModalSelectorEvent<SessionData>),
     SessionSelected(usize),
+    CycleSort,
     CreateNew,
     Cancel,
 }
@@ -82,13 +263,22 @@ pub struct SessionSelector {
     pub modal: ModalSelector<SessionData>,
     sessions: Vec<Session>,
     current_session_index: Option<usize>,
+    current_session_id: Option<String>,
+    directory_labels: HashMap<String, String>,
+    working_times: HashMap<String, Duration>,
+    /// Names of the session templates, listed under "Create New Session"
+    /// while it's expanded
+    templates: Vec<String>,
+    templates_open: bool,
+    sort: SessionSort,
+    now_ms: f64,
 }
 
 impl SessionSelector {
     pub fn new() -> Self {
         let config = SelectorConfig {
             title: Some("Switch Session".to_string()),
-            footer: Some("↑↓/Tab navigate, Enter select, Esc cancel".to_string()),
+            footer: Some(footer(SessionSort::default())),
             max_width: Some(60),
             max_height: Some(15),
             padding: 1,
@@ -102,15 +292,122 @@ impl SessionSelector {
             header_style: Style::default().fg(Color::Yellow),
             row_style: Style::default().fg(Color::White),
             alt_row_style: None, // Some(Style::default().bg(Color::DarkGray)),
+            multi_select: false,
         };
 
         Self {
             modal: ModalSelector::new(config, SelectorMode::List),
             sessions: Vec::new(),
             current_session_index: None,
+            current_session_id: None,
+            directory_labels: HashMap::new(),
+            working_times: HashMap::new(),
+            templates: Vec::new(),
+            templates_open: false,
+            sort: SessionSort::default(),
+            now_ms: 0.0,
         }
     }
 
+    /// Show `sessions`, in server order, sorted by `sort` and with the
+    /// selection back at the top
+    pub fn set_sessions(
+        &mut self,
+        sessions: Vec<Session>,
+        current_session_id: Option<String>,
+        sort: SessionSort,
+        now_ms: f64,
+    ) {
+        self.sessions = sessions;
+        self.current_session_id = current_session_id;
+        self.now_ms = now_ms;
+        self.set_sort(sort);
+        self.modal.set_items(self.rows());
+    }
+
+    /// Label the sessions' directories, by session id, keeping the selection
+    pub fn set_directory_labels(&mut self, labels: HashMap<String, String>) {
+        self.directory_labels = labels;
+        self.resort(self.sort);
+    }
+
+    /// Show the agent's working time in each session, by session id,
+    /// keeping the selection
+    pub fn set_working_times(&mut self, times: HashMap<String, Duration>) {
+        self.working_times = times;
+        self.resort(self.sort);
+    }
+
+    /// List `names` under "Create New Session", collapsed until it's picked
+    pub fn set_templates(&mut self, names: Vec<String>) {
+        self.templates = names;
+        self.templates_open = false;
+        self.modal.set_items(self.rows());
+    }
+
+    pub fn has_templates(&self) -> bool {
+        !self.templates.is_empty()
+    }
+
+    /// Expand or collapse the templates, selecting the first one when expanded
+    pub fn toggle_templates(&mut self) {
+        self.templates_open = !self.templates_open;
+        self.modal.set_items(self.rows());
+        self.modal.select(usize::from(self.templates_open));
+    }
+
+    pub fn sort(&self) -> SessionSort {
+        self.sort
+    }
+
+    /// Re-sort the rows in place, keeping the selection on the same session
+    pub fn resort(&mut self, sort: SessionSort) {
+        let selected = self
+            .modal
+            .selected_item()
+            .map(|item| (item.session_id().map(str::to_string), item.template.clone()));
+        self.set_sort(sort);
+        self.modal.set_items(self.rows());
+        if let Some((session_id, template)) = selected {
+            let index = self.modal.items().iter().position(|item| {
+                !item.is_header
+                    && item.session_id() == session_id.as_deref()
+                    && item.template == template
+            });
+            if let Some(index) = index {
+                self.modal.select(index);
+            }
+        }
+    }
+
+    fn set_sort(&mut self, sort: SessionSort) {
+        self.sort = sort;
+        self.modal.config.footer = Some(footer(sort));
+    }
+
+    fn rows(&self) -> Vec<SessionData> {
+        let mut rows = session_rows(
+            &self.sessions,
+            self.current_session_id.as_deref(),
+            &self.directory_labels,
+            self.sort,
+            self.now_ms,
+        );
+        for row in &mut rows {
+            let working_time = row.session_id().and_then(|id| self.working_times.get(id));
+            row.working_time = working_time
+                .filter(|elapsed| !elapsed.is_zero())
+                .map(|elapsed| format_working_time(*elapsed));
+        }
+        if self.templates_open {
+            let templates = std::iter::once(BLANK_TEMPLATE)
+                .chain(self.templates.iter().map(String::as_str))
+                .map(SessionData::template);
+            rows.splice(1..1, templates);
+        }
+        rows
+    }
+
     pub fn is_visible(&self) -> bool {
         self.modal.is_visible()
     }
@@ -142,12 +439,12 @@ impl SessionSelector {
 
     pub fn set_current_session_index(&mut self, index: Option<usize>) {
         self.current_session_index = index;
-        let mut session_data = Vec::<SessionData>::new();
-        for (i, session) in self.sessions.iter().enumerate() {
-            let is_current = self.current_session_index == Some(i + 1); // +1 because of "Create New"
-            session_data.push(SessionData::from_session(session, is_current));
-        }
-        self.modal.set_items(session_data);
+        // -1 because of "Create New"
+        self.current_session_id = index
+            .and_then(|index| index.checked_sub(1))
+            .and_then(|index| self.sessions.get(index))
+            .map(|session| session.id.clone());
+        self.modal.set_items(self.rows());
     }
 
     pub fn current_session_index(&self) -> Option<usize> {
@@ -169,17 +466,34 @@ impl Component<Model, MsgModalSessionSelector, Cmd> for SessionSelector {
                 // and handle any responses it emits back
                 match model.modal_session_selector.modal.handle_event(event) {
                     ModalSelectorUpdate::Hide => {
-                        model.state = AppModalState::None;
+                        model.pop_modal();
                     }
                     ModalSelectorUpdate::ItemSelected(session_data) => {
                         // Convert session data back to index
-                        if session_data.session.is_none() {
-                            // "Create New" selected - index 0
-                            if let Some(client) = model.client.clone() {
-                                if model.change_session(Some(0)) {
-                                    return CmdOrBatch::Single(Cmd::AsyncSpawnSessionInit(client));
-                                }
+                        if let Some(name) = &session_data.template {
+                            if name == BLANK_TEMPLATE {
+                                open_new_session_prompt(model);
+                                return CmdOrBatch::Single(Cmd::None);
+                            }
+                            let template = model
+                                .config
+                                .session_templates
+                                .iter()
+                                .find(|template| &template.name == name)
+                                .cloned();
+                            if let Some(template) = template {
+                                return start_template_session(model, &template);
                             }
+                            model.pop_modal();
+                        } else if session_data.session.is_none() {
+                            // "Create New" selected - index 0, opening the
+                            // templates under it when there are any
+                            if model.modal_session_selector.has_templates() {
+                                model.modal_session_selector.toggle_templates();
+                            } else {
+                                open_new_session_prompt(model);
+                            }
+                            return CmdOrBatch::Single(Cmd::None);
                         } else {
                             // Find the session index
                             if let Some(session) = &session_data.session {
@@ -194,7 +508,7 @@ impl Component<Model, MsgModalSessionSelector, Cmd> for SessionSelector {
                                 }
                             }
                         }
-                        model.state = AppModalState::None;
+                        close_unless_confirming(model);
                     }
                     _ => {}
                 }
@@ -205,26 +519,217 @@ impl Component<Model, MsgModalSessionSelector, Cmd> for SessionSelector {
                         return CmdOrBatch::Single(Cmd::AsyncSpawnSessionInit(client));
                     }
                 }
-                model.state = AppModalState::None;
+                close_unless_confirming(model);
             }
-            MsgModalSessionSelector::CreateNew => {
-                if let Some(client) = model.client.clone() {
-                    if model.change_session(Some(0)) {
-                        return CmdOrBatch::Single(Cmd::AsyncSpawnSessionInit(client));
-                    }
-                }
-                model.state = AppModalState::None;
+            MsgModalSessionSelector::CycleSort => {
+                let sort = model.config.session_sort.next();
+                model.config.session_sort = sort;
+                model.modal_session_selector.resort(sort);
+                return CmdOrBatch::Single(Cmd::AsyncSaveSessionSort(sort));
             }
+            MsgModalSessionSelector::CreateNew => open_new_session_prompt(model),
             MsgModalSessionSelector::Cancel => {
-                model.state = AppModalState::None;
+                model.pop_modal();
             }
         };
         CmdOrBatch::Single(Cmd::None)
     }
 }
 
+fn footer(sort: SessionSort) -> String {
+    format!(
+        "↑↓ navigate, Enter select, s sort: {}, Esc cancel",
+        sort.label()
+    )
+}
+
+/// Ask where to root the new session, over the selector so esc goes back to
+/// it
+fn open_new_session_prompt(model: &mut Model) {
+    if model.client.is_none() {
+        model.pop_modal();
+        return;
+    }
+    model.new_session_prompt = NewSessionPrompt::new(model.workspace_root.as_deref());
+    model.push_modal(AppModalState::ModalNewSession);
+}
+
+/// Close the selector, unless the picked session is waiting on confirmation
+/// because it belongs to another project
+fn close_unless_confirming(model: &mut Model) {
+    if model.state != AppModalState::ModalSessionDirectoryConfirm {
+        model.pop_modal();
+    }
+}
+
 impl Widget for &SessionSelector {
     fn render(self, area: Rect, buf: &mut Buffer) {
         self.modal.render(area, buf);
     }
 }
+
+impl SessionSelector {
+    pub fn render_with(&self, ctx: &RenderCtx, area: Rect, buf: &mut Buffer) {
+        self.modal.render_with(ctx, area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opencode_sdk::models::SessionTime;
+
+    const NOW_MS: f64 = 10.0 * DAY_MS;
+
+    fn session(id: &str, title: &str, created_days_ago: f64, updated_days_ago: f64) -> Session {
+        Session::new(
+            id.to_string(),
+            title.to_string(),
+            "0.3.1".to_string(),
+            SessionTime::new(
+                NOW_MS - created_days_ago * DAY_MS,
+                NOW_MS - updated_days_ago * DAY_MS,
+            ),
+        )
+    }
+
+    fn sessions() -> Vec<Session> {
+        vec![
+            session("ses_a", "beta", 5.0, 0.5),
+            session("ses_b", "Alpha", 0.2, 0.1),
+            session("ses_c", "gamma", 9.0, 1.5),
+            session("ses_d", "alpha", 3.0, 4.0),
+        ]
+    }
+
+    fn row_texts(rows: &[SessionData]) -> Vec<String> {
+        rows.iter()
+            .map(|row| match row.is_header {
+                true => format!("-- {}", row.display_text),
+                false => row.display_text.clone(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_sort_comparators() {
+        let order = |sort: SessionSort| {
+            let mut sessions = sessions();
+            sessions.sort_by(|a, b| sort.compare(a, b));
+            sessions
+                .into_iter()
+                .map(|session| session.id)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            order(SessionSort::Updated),
+            ["ses_b", "ses_a", "ses_c", "ses_d"]
+        );
+        assert_eq!(
+            order(SessionSort::Created),
+            ["ses_b", "ses_d", "ses_a", "ses_c"]
+        );
+        // Case-insensitive, with the same title broken by recency
+        assert_eq!(
+            order(SessionSort::Title),
+            ["ses_b", "ses_d", "ses_a", "ses_c"]
+        );
+        assert_eq!(SessionSort::Title.next(), SessionSort::Updated);
+    }
+
+    #[test]
+    fn test_headers_only_when_sorting_by_recency() {
+        let rows = session_rows(
+            &sessions(),
+            Some("ses_a"),
+            &HashMap::new(),
+            SessionSort::Updated,
+            NOW_MS,
+        );
+        assert_eq!(
+            row_texts(&rows),
+            [
+                "Create New Session",
+                "-- Today",
+                "Alpha",
+                "beta",
+                "-- Yesterday",
+                "gamma",
+                "-- Earlier",
+                "alpha",
+            ]
+        );
+        assert!(rows[3].is_current);
+
+        let labels = HashMap::from([("ses_d".to_string(), "services/api/".to_string())]);
+        let rows = session_rows(&sessions(), None, &labels, SessionSort::Title, NOW_MS);
+        assert_eq!(
+            row_texts(&rows),
+            ["Create New Session", "Alpha", "alpha", "beta", "gamma"]
+        );
+        // Only the session rooted away from the workspace names its directory
+        assert_eq!(rows[2].directory.as_deref(), Some("services/api/"));
+        assert!(rows.iter().filter(|row| row.directory.is_some()).count() == 1);
+    }
+
+    #[test]
+    fn test_resort_keeps_the_selected_session() {
+        let mut selector = SessionSelector::new();
+        selector.set_sessions(sessions(), None, SessionSort::Updated, NOW_MS);
+        assert_eq!(selector.modal.selected_item().unwrap().session_id(), None);
+
+        // Past the "Today" header onto the first session
+        selector.modal.navigate_down();
+        selector.modal.navigate_down();
+        selector.modal.navigate_down();
+        assert_eq!(
+            selector.modal.selected_item().unwrap().session_id(),
+            Some("ses_c")
+        );
+
+        selector.resort(SessionSort::Title);
+        assert_eq!(selector.sort(), SessionSort::Title);
+        assert_eq!(selector.modal.selected_index(), Some(4));
+        assert_eq!(
+            selector.modal.selected_item().unwrap().session_id(),
+            Some("ses_c")
+        );
+        assert!(selector
+            .modal
+            .config
+            .footer
+            .as_deref()
+            .unwrap()
+            .contains("sort: title A–Z"));
+    }
+
+    #[test]
+    fn test_templates_open_under_create_new() {
+        let mut selector = SessionSelector::new();
+        selector.set_sessions(sessions(), None, SessionSort::Title, NOW_MS);
+        selector.set_templates(vec!["review".to_string()]);
+        assert_eq!(selector.items()[..2], ["Create New Session", "Alpha"]);
+
+        selector.toggle_templates();
+        assert_eq!(
+            selector.items()[..4],
+            ["Create New Session", "  blank", "  review", "Alpha"]
+        );
+        assert_eq!(
+            selector.modal.selected_item().unwrap().template.as_deref(),
+            Some(BLANK_TEMPLATE)
+        );
+
+        // Re-sorting keeps the selection on the template
+        selector.modal.navigate_down();
+        selector.resort(SessionSort::Updated);
+        assert_eq!(
+            selector.modal.selected_item().unwrap().template.as_deref(),
+            Some("review")
+        );
+
+        selector.toggle_templates();
+        assert_eq!(selector.selected_index(), 0);
+        assert_eq!(selector.items()[1], "Today");
+    }
+}
@@ -0,0 +1,130 @@
+use crate::app::{
+    event_msg::{Cmd, CmdOrBatch},
+    snippets::Snippet,
+    tea_model::Model,
+    ui_components::{
+        modal_selector::ModalSelectorUpdate, Component, ModalSelector, ModalSelectorEvent,
+        RenderCtx, SelectableData, SelectorConfig, SelectorMode, TableColumn,
+    },
+};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Rect},
+    style::{Color, Modifier, Style},
+    widgets::{Borders, Cell, Widget},
+};
+use serde::{Deserialize, Serialize};
+
+/// Data wrapper for snippet selection
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SnippetData {
+    pub snippet: Snippet,
+}
+
+impl SelectableData for SnippetData {
+    fn to_cells(&self) -> Vec<Cell<'_>> {
+        vec![
+            Cell::from(self.snippet.name.clone()),
+            Cell::from(self.snippet.preview().trim().to_string())
+                .style(Style::default().fg(Color::Gray)),
+        ]
+    }
+
+    fn to_string(&self) -> String {
+        self.snippet.name.clone()
+    }
+}
+
+/// Submessage enum for the snippet picker that wraps generic events
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum MsgModalSnippetSelector {
+    Event(ModalSelectorEvent<SnippetData>),
+}
+
+/// Snippet picker that wraps the generic ModalSelector
+#[derive(Debug, Clone)]
+pub struct SnippetSelector {
+    pub modal: ModalSelector<SnippetData>,
+}
+
+impl SnippetSelector {
+    pub fn new() -> Self {
+        let config = SelectorConfig {
+            title: Some("Insert Snippet".to_string()),
+            footer: Some("↑↓/Tab navigate, Enter insert, Esc cancel".to_string()),
+            max_width: Some(70),
+            max_height: Some(15),
+            padding: 1,
+            show_scrollbar: false,
+            alternating_rows: true,
+            borders: Borders::ALL,
+            border_color: Color::Blue,
+            selected_style: Style::default()
+                .add_modifier(Modifier::REVERSED)
+                .fg(Color::Blue),
+            header_style: Style::default().fg(Color::Yellow),
+            row_style: Style::default().fg(Color::White),
+            alt_row_style: None,
+            multi_select: false,
+        };
+
+        let columns = vec![
+            TableColumn::new("Name", Constraint::Length(16)),
+            TableColumn::new("Template", Constraint::Min(20)).optional(),
+        ];
+
+        Self {
+            modal: ModalSelector::new(config, SelectorMode::Table { columns }),
+        }
+    }
+
+    pub fn set_snippets(&mut self, snippets: &[Snippet]) {
+        let data = snippets
+            .iter()
+            .map(|snippet| SnippetData {
+                snippet: snippet.clone(),
+            })
+            .collect();
+        self.modal.set_items(data);
+    }
+}
+
+impl Default for SnippetSelector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Component<Model, MsgModalSnippetSelector, Cmd> for SnippetSelector {
+    fn update(msg: MsgModalSnippetSelector, state: &mut Model) -> CmdOrBatch<Cmd> {
+        let model = state;
+        match msg {
+            MsgModalSnippetSelector::Event(event) => {
+                match model.modal_snippet_selector.modal.handle_event(event) {
+                    ModalSelectorUpdate::Hide => {
+                        model.pop_modal();
+                    }
+                    ModalSelectorUpdate::ItemSelected(data) => {
+                        model.modal_snippet_selector.modal.hide();
+                        model.pop_modal();
+                        model.insert_snippet(&data.snippet);
+                    }
+                    ModalSelectorUpdate::ItemsSelected(_) | ModalSelectorUpdate::None => {}
+                }
+            }
+        }
+        CmdOrBatch::Single(Cmd::None)
+    }
+}
+
+impl Widget for &SnippetSelector {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        self.modal.render(area, buf);
+    }
+}
+
+impl SnippetSelector {
+    pub fn render_with(&self, ctx: &RenderCtx, area: Rect, buf: &mut Buffer) {
+        self.modal.render_with(ctx, area, buf);
+    }
+}
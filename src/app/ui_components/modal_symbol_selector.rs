@@ -0,0 +1,391 @@
+//! Jumping to a symbol anywhere in the project
+//!
+//! ^x O searches the server's workspace symbols as the name is typed. Enter
+//! inserts a `@path#line` reference to the symbol into the input, ctrl+y
+//! copies its `path:line` instead. Symbols come from the server's language
+//! servers, so until one has indexed the project nothing is found.
+
+use crate::app::{
+    event_async_task_manager::TaskKind,
+    event_msg::{Cmd, CmdOrBatch},
+    mentions::{workspace_relative_path, LINE_SEPARATOR, MENTION_PREFIX},
+    strings::{NO_SYMBOLS_FOUND, SYMBOL_SEARCH_FOOTER, SYMBOL_SEARCH_PROMPT, SYMBOL_SEARCH_TITLE},
+    tea_model::{Model, TimeoutType},
+    ui_components::{
+        modal_selector::ModalSelectorUpdate, Component, ModalSelector, ModalSelectorEvent,
+        RenderCtx, SelectableData, SelectorConfig, SelectorMode, TableColumn,
+    },
+};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use opencode_sdk::models::{Symbol, SymbolLocation};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Rect},
+    style::{Color, Modifier, Style},
+    widgets::{Borders, Cell, Widget},
+};
+use serde::{Deserialize, Serialize};
+
+/// Typing pause after which the symbols are searched
+pub const SYMBOL_SEARCH_DEBOUNCE_MS: u64 = 250;
+
+/// A found symbol, with its location relative to the workspace
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SymbolData {
+    pub symbol: Symbol,
+    pub path: String,
+    /// 1-based, like editors count them
+    pub line: usize,
+    pub end_line: usize,
+}
+
+impl SymbolData {
+    pub fn new(symbol: Symbol, workspace_root: Option<&str>) -> Self {
+        let (path, line, end_line) = symbol_position(&symbol.location, workspace_root);
+        Self {
+            symbol,
+            path,
+            line,
+            end_line,
+        }
+    }
+
+    /// "src/app/mod.rs:42", or "src/app/mod.rs:42-58" for a symbol spanning
+    /// several lines
+    pub fn location(&self) -> String {
+        match self.end_line > self.line {
+            true => format!("{}:{}-{}", self.path, self.line, self.end_line),
+            false => format!("{}:{}", self.path, self.line),
+        }
+    }
+
+    /// The mention inserted into the input, "@src/app/mod.rs#42"
+    pub fn reference(&self) -> String {
+        format!(
+            "{}{}{}{}",
+            MENTION_PREFIX, self.path, LINE_SEPARATOR, self.line
+        )
+    }
+}
+
+impl SelectableData for SymbolData {
+    fn to_cells(&self) -> Vec<Cell<'_>> {
+        vec![
+            Cell::from(kind_label(self.symbol.kind)).style(Style::default().fg(Color::Cyan)),
+            Cell::from(self.symbol.name.clone()),
+            Cell::from(self.location()).style(Style::default().fg(Color::Gray)),
+        ]
+    }
+
+    fn to_string(&self) -> String {
+        self.symbol.name.clone()
+    }
+}
+
+/// Path and 1-based first and last lines of an LSP location, whose `uri` is
+/// usually a `file://` URI and whose lines count from 0
+pub fn symbol_position(
+    location: &SymbolLocation,
+    workspace_root: Option<&str>,
+) -> (String, usize, usize) {
+    let path = location
+        .uri
+        .strip_prefix("file://")
+        .map(decode_uri_path)
+        .unwrap_or_else(|| location.uri.clone());
+    let line = location.range.start.line.max(0.0) as usize + 1;
+    let end_line = (location.range.end.line.max(0.0) as usize + 1).max(line);
+    (
+        workspace_relative_path(&path, workspace_root),
+        line,
+        end_line,
+    )
+}
+
+/// Undo the percent-encoding of a URI path, leaving malformed escapes as
+/// they are
+fn decode_uri_path(path: &str) -> String {
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| path.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Short name of an LSP `SymbolKind`
+pub fn kind_label(kind: f64) -> &'static str {
+    match kind as u32 {
+        1 => "file",
+        2..=4 => "mod",
+        5 => "class",
+        6 => "method",
+        7 | 8 => "field",
+        9 => "new",
+        10 => "enum",
+        11 => "trait",
+        12 => "fn",
+        13 => "var",
+        14 => "const",
+        22 => "variant",
+        23 => "struct",
+        26 => "type",
+        _ => "symbol",
+    }
+}
+
+/// Submessage enum for the symbol search that wraps generic events
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum MsgModalSymbolSelector {
+    Event(ModalSelectorEvent<SymbolData>),
+    KeyInput(KeyEvent),
+}
+
+/// Symbol search that wraps the generic ModalSelector
+#[derive(Debug, Clone)]
+pub struct SymbolSelector {
+    pub modal: ModalSelector<SymbolData>,
+    query: String,
+}
+
+impl SymbolSelector {
+    pub fn new() -> Self {
+        let config = SelectorConfig {
+            title: Some(SYMBOL_SEARCH_TITLE.to_string()),
+            footer: Some(SYMBOL_SEARCH_FOOTER.to_string()),
+            max_width: Some(100),
+            max_height: Some(20),
+            padding: 1,
+            show_scrollbar: true,
+            alternating_rows: true,
+            borders: Borders::ALL,
+            border_color: Color::Blue,
+            selected_style: Style::default()
+                .add_modifier(Modifier::REVERSED)
+                .fg(Color::Blue),
+            header_style: Style::default().fg(Color::Yellow),
+            row_style: Style::default().fg(Color::White),
+            alt_row_style: None,
+            multi_select: false,
+        };
+
+        let columns = vec![
+            TableColumn::new("Kind", Constraint::Length(8)),
+            TableColumn::new("Symbol", Constraint::Min(20)),
+            TableColumn::new("Location", Constraint::Min(20)).optional(),
+        ];
+
+        let mut selector = Self {
+            modal: ModalSelector::new(config, SelectorMode::Table { columns }),
+            query: String::new(),
+        };
+        selector.clear();
+        selector
+    }
+
+    /// The name being searched for
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    /// Show the symbols found for the query, or why there are none
+    pub fn set_symbols(&mut self, symbols: Vec<Symbol>, workspace_root: Option<&str>) {
+        let symbols = symbols
+            .into_iter()
+            .map(|symbol| SymbolData::new(symbol, workspace_root))
+            .collect();
+        self.modal.set_items(symbols);
+        self.modal.empty_text = Some(NO_SYMBOLS_FOUND.to_string());
+    }
+
+    pub fn clear(&mut self) {
+        self.query.clear();
+        self.update_title();
+        self.modal.set_items(Vec::new());
+        self.modal.empty_text = Some(SYMBOL_SEARCH_PROMPT.to_string());
+    }
+
+    fn update_title(&mut self) {
+        self.modal.config.title = Some(match self.query.is_empty() {
+            true => SYMBOL_SEARCH_TITLE.to_string(),
+            false => format!("{}: {}", SYMBOL_SEARCH_TITLE, self.query),
+        });
+    }
+
+    fn edit_query(&mut self, key: KeyEvent) -> bool {
+        match key.code {
+            KeyCode::Backspace => self.query.pop().is_some(),
+            KeyCode::Char(c)
+                if !key
+                    .modifiers
+                    .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) =>
+            {
+                self.query.push(c);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Default for SymbolSelector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Close the search, returning the command cancelling one still running
+fn model_close(model: &mut Model) -> Cmd {
+    let query = model.modal_symbol_selector.query.clone();
+    model.clear_timeout(&TimeoutType::DebounceFindSymbols(query.clone()));
+    model.modal_symbol_selector.modal.hide();
+    model.modal_symbol_selector.clear();
+    model.pop_modal();
+    Cmd::AsyncCancelKeyed(TaskKind::FindSymbols { query })
+}
+
+impl Component<Model, MsgModalSymbolSelector, Cmd> for SymbolSelector {
+    fn update(msg: MsgModalSymbolSelector, state: &mut Model) -> CmdOrBatch<Cmd> {
+        let model = state;
+        match msg {
+            MsgModalSymbolSelector::Event(event) => {
+                match model.modal_symbol_selector.modal.handle_event(event) {
+                    ModalSelectorUpdate::Hide => return CmdOrBatch::Single(model_close(model)),
+                    ModalSelectorUpdate::ItemSelected(data) => {
+                        let cancel = model_close(model);
+                        model
+                            .text_input_area
+                            .insert_text(&format!("{} ", data.reference()));
+                        return CmdOrBatch::Single(cancel);
+                    }
+                    ModalSelectorUpdate::ItemsSelected(_) | ModalSelectorUpdate::None => {}
+                }
+            }
+            MsgModalSymbolSelector::KeyInput(key)
+                if key.code == KeyCode::Char('y')
+                    && key.modifiers.contains(KeyModifiers::CONTROL) =>
+            {
+                if let Some(data) = model.modal_symbol_selector.modal.selected_item() {
+                    let location = data.location();
+                    return CmdOrBatch::Batch(vec![
+                        model_close(model),
+                        Cmd::TerminalCopyToClipboard(location),
+                    ]);
+                }
+            }
+            MsgModalSymbolSelector::KeyInput(key) => {
+                let selector = &mut model.modal_symbol_selector;
+                if selector.edit_query(key) {
+                    selector.update_title();
+                    let query = selector.query.clone();
+                    if query.is_empty() {
+                        model.clear_timeout(&TimeoutType::DebounceFindSymbols(query));
+                        model.modal_symbol_selector.clear();
+                    } else {
+                        model.set_timeout(
+                            TimeoutType::DebounceFindSymbols(query),
+                            SYMBOL_SEARCH_DEBOUNCE_MS,
+                        );
+                    }
+                }
+            }
+        }
+        CmdOrBatch::Single(Cmd::None)
+    }
+}
+
+impl Widget for &SymbolSelector {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        self.modal.render(area, buf);
+    }
+}
+
+impl SymbolSelector {
+    pub fn render_with(&self, ctx: &RenderCtx, area: Rect, buf: &mut Buffer) {
+        self.modal.render_with(ctx, area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opencode_sdk::models::{Range, RangeStart};
+
+    fn location(uri: &str, start: f64, end: f64) -> SymbolLocation {
+        let position = |line| RangeStart {
+            line,
+            character: 4.0,
+        };
+        SymbolLocation::new(uri.to_string(), Range::new(position(start), position(end)))
+    }
+
+    fn symbol_at(uri: &str, start: f64, end: f64) -> SymbolData {
+        SymbolData::new(
+            Symbol::new("MessageLog".to_string(), 23.0, location(uri, start, end)),
+            Some("/home/me/project"),
+        )
+    }
+
+    #[test]
+    fn test_locations_are_relative_and_one_based() {
+        let log = symbol_at("file:///home/me/project/src/app/log.rs", 41.0, 41.0);
+        assert_eq!(log.location(), "src/app/log.rs:42");
+        assert_eq!(log.reference(), "@src/app/log.rs#42");
+        assert_eq!(kind_label(log.symbol.kind), "struct");
+
+        // A range over the whole definition
+        let spanning = symbol_at("file:///home/me/project/src/main.rs", 9.0, 30.0);
+        assert_eq!(spanning.location(), "src/main.rs:10-31");
+        assert_eq!(spanning.reference(), "@src/main.rs#10");
+
+        // Escaped characters, and paths outside the workspace
+        let escaped = symbol_at("file:///home/me/project/my%20docs/100%.rs", 0.0, 0.0);
+        assert_eq!(escaped.location(), "my docs/100%.rs:1");
+        let outside = symbol_at("file:///usr/lib/rust/core.rs", 2.0, 1.0);
+        assert_eq!(outside.location(), "/usr/lib/rust/core.rs:3");
+        // Not a file URI
+        let other = symbol_at("src/lib.rs", 4.0, 4.0);
+        assert_eq!(other.location(), "src/lib.rs:5");
+    }
+
+    #[test]
+    fn test_empty_states() {
+        let mut selector = SymbolSelector::new();
+        assert_eq!(
+            selector.modal.empty_text.as_deref(),
+            Some(SYMBOL_SEARCH_PROMPT)
+        );
+
+        selector.set_symbols(Vec::new(), None);
+        assert_eq!(selector.modal.empty_text.as_deref(), Some(NO_SYMBOLS_FOUND));
+        assert_eq!(selector.modal.items().len(), 0);
+
+        selector
+            .modal
+            .set_error(Some("language server crashed".to_string()));
+        assert_eq!(
+            selector.modal.error.as_deref(),
+            Some("language server crashed")
+        );
+        selector.clear();
+        assert_eq!(
+            selector.modal.empty_text.as_deref(),
+            Some(SYMBOL_SEARCH_PROMPT)
+        );
+        assert_eq!(selector.modal.error, None);
+    }
+}
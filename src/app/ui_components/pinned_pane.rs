@@ -0,0 +1,210 @@
+//! Output pinned beside the message log
+//!
+//! In fullscreen on a wide terminal, the full output of a tool or a file read
+//! from the server can be kept in view to the right of the conversation, say
+//! a failing test's output while iterating on the fix. There is one pin at a
+//! time, so pinning something else replaces it, and it scrolls on its own
+//! with alt+↑/↓ while the log keeps its keys.
+
+use crate::app::{strings::PIN_HINT, ui_components::RenderCtx};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::Stylize,
+    widgets::{Block, Borders, Paragraph, Widget},
+};
+use std::cell::Cell;
+
+/// Narrowest area the log shares with a pin, inside the fullscreen padding;
+/// below it the pin is kept but hidden until there is room again
+pub const PIN_MIN_WIDTH: u16 = 120;
+
+/// Share of the width the log keeps, the pin taking the rest
+const LOG_SHARE_PERCENT: u16 = 60;
+
+/// `/pin <path>` pins a file instead of sending
+pub const PIN_COMMAND: &str = "/pin";
+
+/// The path of a `/pin <path>` command, empty if none was given
+pub fn parse_pin_command(input: &str) -> Option<&str> {
+    let rest = input.trim().strip_prefix(PIN_COMMAND)?;
+    if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+    Some(rest.trim())
+}
+
+/// Split `area` into the log and the pin beside it, None if it's too narrow
+/// to share
+pub fn split_for_pin(area: Rect) -> Option<(Rect, Rect)> {
+    if area.width < PIN_MIN_WIDTH {
+        return None;
+    }
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage(LOG_SHARE_PERCENT),
+            Constraint::Percentage(100 - LOG_SHARE_PERCENT),
+        ])
+        .split(area);
+    Some((chunks[0], chunks[1]))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PinnedPane {
+    pub title: String,
+    pub lines: Vec<String>,
+    /// First line in view
+    pub scroll: usize,
+    /// Lines in view at the last render, which the scroll stops short of
+    /// running past
+    view_height: Cell<usize>,
+}
+
+impl PinnedPane {
+    pub fn new(title: impl Into<String>, text: &str) -> Self {
+        Self {
+            title: title.into(),
+            // Tabs would throw the columns off, as a cell is drawn per character
+            lines: text
+                .lines()
+                .map(|line| line.replace('\t', "    "))
+                .collect(),
+            scroll: 0,
+            view_height: Cell::new(0),
+        }
+    }
+
+    /// Scroll by `rows`, stopping at the first line and with the last one at
+    /// the bottom
+    pub fn scroll_by(&mut self, rows: i16) {
+        let scroll = self.scroll.saturating_add_signed(rows as isize);
+        self.scroll = scroll.min(self.max_scroll());
+    }
+
+    fn max_scroll(&self) -> usize {
+        self.lines.len().saturating_sub(self.view_height.get())
+    }
+
+    pub fn render_with(&self, ctx: &RenderCtx, area: Rect, buf: &mut Buffer) {
+        let view_height = area.height.saturating_sub(2) as usize;
+        self.view_height.set(view_height);
+        // Taller than when last scrolled, so lines that fit are no longer
+        // scrolled out of view
+        let top = self.scroll.min(self.max_scroll());
+        let content: Vec<&str> = self
+            .lines
+            .iter()
+            .skip(top)
+            .take(view_height)
+            .map(String::as_str)
+            .collect();
+        let title = format!(" {} · {} ", self.title, PIN_HINT);
+        Paragraph::new(content.join("\n"))
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_set(ctx.border_set())
+                    .title(ctx.glyphs.text(&title).into_owned().bold())
+                    .gray(),
+            )
+            .render(area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::{
+        tea_model::{AppModalState, ConnectionStatus, Model, ModelInit},
+        tea_view::view,
+    };
+    use ratatui::{backend::TestBackend, Terminal};
+
+    fn numbered(count: usize) -> String {
+        (1..=count)
+            .map(|n| format!("line {}", n))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn render(model: &Model, width: u16, height: u16) -> Vec<String> {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        let frame = terminal.draw(|f| view(model, f)).unwrap();
+        let area = frame.area;
+        (area.top()..area.bottom())
+            .map(|y| {
+                (area.left()..area.right())
+                    .map(|x| frame.buffer[(x, y)].symbol())
+                    .collect::<String>()
+            })
+            .collect()
+    }
+
+    fn pinned_model() -> Model {
+        let mut model = Model::new();
+        model.init = ModelInit::new(false);
+        model.state = AppModalState::None;
+        model.connection_status = ConnectionStatus::Connected;
+        model.pinned_pane = Some(PinnedPane::new("bash · cargo test", &numbered(50)));
+        model
+    }
+
+    #[test]
+    fn test_pin_shown_beside_the_log_only_when_wide_enough() {
+        let model = pinned_model();
+        // Fullscreen pads a column either side
+        let width = PIN_MIN_WIDTH + 2;
+
+        let wide = render(&model, width, 30);
+        let title_row = wide
+            .iter()
+            .position(|row| row.contains("bash · cargo test"))
+            .expect("pin is shown");
+        // On the right, with the log keeping the larger share
+        let title_column = wide[title_row].find("bash").unwrap();
+        assert!(title_column > width as usize / 2, "{:?}", wide);
+        assert!(wide[title_row + 1].contains("line 1"));
+
+        let narrow = render(&model, width - 1, 30);
+        assert!(!narrow.iter().any(|row| row.contains("cargo test")));
+
+        let mut inline = pinned_model();
+        inline.init = ModelInit::new(true);
+        let inline = render(&inline, width, 30);
+        assert!(!inline.iter().any(|row| row.contains("cargo test")));
+    }
+
+    #[test]
+    fn test_scroll_clamped_to_the_lines_in_view() {
+        let mut pane = PinnedPane::new("output", &numbered(50));
+        let ctx = RenderCtx::default();
+        let area = Rect::new(0, 0, 40, 12);
+        pane.render_with(&ctx, area, &mut Buffer::empty(area));
+
+        pane.scroll_by(-3);
+        assert_eq!(pane.scroll, 0);
+        pane.scroll_by(15);
+        assert_eq!(pane.scroll, 15);
+        // 10 lines in view, so line 50 ends up at the bottom
+        pane.scroll_by(100);
+        assert_eq!(pane.scroll, 40);
+
+        // A taller pane shows the lines the scroll had run past
+        let taller = Rect::new(0, 0, 40, 22);
+        let mut buf = Buffer::empty(taller);
+        pane.render_with(&ctx, taller, &mut buf);
+        let first_row: String = (1..39).map(|x| buf[(x, 1)].symbol()).collect();
+        assert_eq!(first_row.trim(), "line 31");
+        pane.scroll_by(1);
+        assert_eq!(pane.scroll, 30);
+    }
+
+    #[test]
+    fn test_parse_pin_command() {
+        assert_eq!(parse_pin_command("/pin src/lib.rs"), Some("src/lib.rs"));
+        assert_eq!(parse_pin_command("/pin"), Some(""));
+        assert_eq!(parse_pin_command("/pinned"), None);
+        assert_eq!(parse_pin_command("pin src/lib.rs"), None);
+    }
+}
@@ -0,0 +1,100 @@
+//! What a frame is rendered with
+//!
+//! `view` builds a `RenderCtx` from the model once per frame and hands it to
+//! each component's `render_with`, so the components render from their
+//! arguments alone and can be drawn in tests without a model in scope.
+
+use crate::app::tea_model::Model;
+use crate::app::ui_components::{
+    glyphs::{Glyphs, UNICODE},
+    message_part::VerbosityLevel,
+    LayoutClass,
+};
+use ratatui::{layout::Rect, style::Color, symbols::border, widgets::BorderType};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RenderCtx {
+    pub verbosity: VerbosityLevel,
+    pub border_type: BorderType,
+    pub layout_class: LayoutClass,
+    pub inline_mode: bool,
+    pub gutter_colors: GutterColors,
+    pub glyphs: &'static Glyphs,
+}
+
+/// Colors of the bar down the left of each message in the log
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GutterColors {
+    pub user: Color,
+    pub assistant: Color,
+    /// Failed sends and messages that ended in a session error
+    pub error: Color,
+}
+
+impl Default for GutterColors {
+    fn default() -> Self {
+        Self {
+            user: Color::Cyan,
+            assistant: Color::DarkGray,
+            error: Color::Red,
+        }
+    }
+}
+
+impl RenderCtx {
+    /// The context for a frame of `area` drawn from `model`
+    pub fn from_model(model: &Model, area: Rect) -> Self {
+        Self {
+            verbosity: model.verbosity_level,
+            border_type: match model.config.ui_block_is_rounded {
+                true => BorderType::Rounded,
+                false => BorderType::Plain,
+            },
+            layout_class: LayoutClass::from_area(area),
+            inline_mode: model.init.inline_mode(),
+            gutter_colors: GutterColors::default(),
+            glyphs: model.config.ui_glyphs.glyphs(),
+        }
+    }
+
+    /// The characters to draw block borders with
+    pub fn border_set(&self) -> border::Set {
+        self.glyphs.border_set(self.border_type)
+    }
+}
+
+/// Used by the plain `Widget` impls, for callers without a model
+impl Default for RenderCtx {
+    fn default() -> Self {
+        Self {
+            verbosity: VerbosityLevel::Summary,
+            border_type: BorderType::Rounded,
+            layout_class: LayoutClass::default(),
+            inline_mode: true,
+            gutter_colors: GutterColors::default(),
+            glyphs: &UNICODE,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::{tea_model::ModelInit, ui_components::glyphs::GlyphMode};
+
+    #[test]
+    fn test_from_model() {
+        let mut model = Model::new();
+        model.init = ModelInit::new(false);
+        model.config.ui_block_is_rounded = false;
+        model.verbosity_level = VerbosityLevel::Verbose;
+        model.config.ui_glyphs = GlyphMode::Ascii;
+
+        let ctx = RenderCtx::from_model(&model, Rect::new(0, 0, 60, 40));
+        assert_eq!(ctx.verbosity, VerbosityLevel::Verbose);
+        assert_eq!(ctx.border_type, BorderType::Plain);
+        assert!(ctx.layout_class.narrow);
+        assert!(!ctx.inline_mode);
+        assert_eq!(ctx.border_set().top_left, "+");
+    }
+}
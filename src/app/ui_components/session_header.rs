@@ -0,0 +1,128 @@
+//! Header line of the inline viewport
+//!
+//! Inline mode only shows the input and the status line, so this line above
+//! them names the session being typed into, how many messages it holds and
+//! whether a response is streaming. It's built from the model on each frame,
+//! so renames and session switches show up on the next draw.
+
+use crate::app::strings::UNTITLED_SESSION;
+use crate::app::tea_model::Model;
+use crate::app::ui_components::glyphs::{Glyphs, UNICODE};
+use crate::app::ui_components::text_width::{display_width, truncate_with_ellipsis};
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Paragraph, Widget},
+};
+
+pub const SESSION_HEADER_HEIGHT: u16 = 1;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionHeader {
+    title: String,
+    message_count: usize,
+    streaming: bool,
+    glyphs: &'static Glyphs,
+}
+
+impl SessionHeader {
+    pub fn new(title: impl Into<String>, message_count: usize, streaming: bool) -> Self {
+        Self {
+            title: title.into(),
+            message_count,
+            streaming,
+            glyphs: &UNICODE,
+        }
+    }
+
+    pub fn from_model(model: &Model) -> Self {
+        let title = model.session().map(|session| session.title.as_str());
+        Self {
+            glyphs: model.config.ui_glyphs.glyphs(),
+            ..Self::new(
+                title.unwrap_or(UNTITLED_SESSION),
+                model.message_state.message_count(),
+                model.has_work_in_flight(),
+            )
+        }
+    }
+
+    fn count_text(&self) -> String {
+        match self.message_count {
+            1 => format!(" {} 1 message", self.glyphs.separator),
+            count => format!(" {} {} messages", self.glyphs.separator, count),
+        }
+    }
+}
+
+impl Widget for &SessionHeader {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let dot_color = match self.streaming {
+            true => Color::Green,
+            false => Color::DarkGray,
+        };
+        let streaming_dot = format!("{} ", self.glyphs.tool);
+        let count_text = self.count_text();
+        let title_width = (area.width as usize)
+            .saturating_sub(display_width(&streaming_dot))
+            .saturating_sub(display_width(&count_text));
+        let title = truncate_with_ellipsis(&self.title, title_width);
+        let title = match title.strip_suffix('…') {
+            Some(kept) => format!("{}{}", kept, self.glyphs.ellipsis),
+            None => title,
+        };
+
+        Paragraph::new(Line::from(vec![
+            Span::styled(streaming_dot, Style::default().fg(dot_color)),
+            Span::styled(title, Style::default().add_modifier(Modifier::BOLD)),
+            Span::styled(count_text, Style::default().fg(Color::DarkGray)),
+        ]))
+        .render(area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(header: &SessionHeader, width: u16) -> String {
+        let area = Rect::new(0, 0, width, 1);
+        let mut buf = Buffer::empty(area);
+        header.render(area, &mut buf);
+        (0..width).map(|x| buf[(x, 0)].symbol()).collect()
+    }
+
+    #[test]
+    fn test_title_truncated_to_width() {
+        let header = SessionHeader::new("Refactor the session loader", 12, false);
+        assert_eq!(
+            render(&header, 50).trim_end(),
+            "● Refactor the session loader · 12 messages"
+        );
+        assert_eq!(render(&header, 29), "● Refactor the… · 12 messages");
+
+        let single = SessionHeader::new("Fix", 1, true);
+        assert_eq!(render(&single, 20).trim_end(), "● Fix · 1 message");
+    }
+
+    #[test]
+    fn test_wide_title_stays_in_its_line() {
+        let header = SessionHeader::new("セッションの読み込みを修正する", 3, false);
+        // Each wide character covers its cell and the blank one after it,
+        // and the count still fits after the cut title
+        assert_eq!(render(&header, 24), "● セ ッ シ ョ … · 3 messages");
+    }
+
+    #[test]
+    fn test_streaming_dot_color() {
+        let area = Rect::new(0, 0, 20, 1);
+        let mut buf = Buffer::empty(area);
+        SessionHeader::new("Fix", 1, true).render(area, &mut buf);
+        assert_eq!(buf[(0, 0)].fg, Color::Green);
+
+        SessionHeader::new("Fix", 1, false).render(area, &mut buf);
+        assert_eq!(buf[(0, 0)].fg, Color::DarkGray);
+    }
+}
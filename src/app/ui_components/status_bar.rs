@@ -1,113 +1,807 @@
-use crate::app::tea_model::{Model, RepeatShortcutKey};
-use crate::app::view_model_context::ViewModelContext;
+//! Status line under the text input
+//!
+//! Everything on the line is composed by `StatusBarContent::from_model` on
+//! each frame, so it follows the model as modes, sessions and the connection
+//! change rather than holding on to what was true at startup. On a narrow
+//! frame the line is compacted to the activity, model and mode.
+
+use crate::app::event_async_task_manager::TaskKind;
+use crate::app::keybindings::shortcut_hints;
+use crate::app::session_directory::directory_name;
+use crate::app::strings::{
+    MODEL_NOT_OFFERED, MODE_UNKNOWN, POLLING_FOR_MESSAGES, SESSION_TOGGLE_HINT, UPDATE_MARKER,
+};
+use crate::app::tea_model::{
+    AppModalState, ConnectionStatus, EventStreamState, Model, RepeatShortcutKey, TimeoutType,
+};
+use crate::app::token_estimate::format_tokens;
+use crate::app::ui_components::glyphs::Glyphs;
+use crate::app::ui_components::text_width::{display_width, truncate_with_ellipsis};
+use crate::app::ui_components::{LayoutClass, RenderCtx};
+use crate::app::working_time::format_working_time;
 use ratatui::{
     buffer::Buffer,
-    layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Style},
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
     text::{Line, Span},
     widgets::{Paragraph, Widget},
 };
 use throbber_widgets_tui::Throbber;
 
+/// Background of the build, plan and general modes
 const MODE_COLORS: [Color; 3] = [Color::Black, Color::Magenta, Color::Green];
 const MODE_DEFAULT_COLOR: Color = Color::Gray;
+/// Mode names are padded to this width so the line doesn't shift on cycling
+const MODE_WIDTH: usize = 8;
 
-#[derive(Debug, Clone, Default)]
-pub struct StatusBar;
+#[derive(Debug, Clone)]
+pub struct StatusBar {
+    content: StatusBarContent,
+    layout: LayoutClass,
+    glyphs: &'static Glyphs,
+}
 
 impl StatusBar {
-    pub fn new() -> Self {
-        Self
+    pub fn new(model: &Model, ctx: &RenderCtx) -> Self {
+        let content = StatusBarContent::from_model(model);
+        let content = if ctx.layout_class.narrow {
+            content.compact()
+        } else {
+            content
+        };
+        Self {
+            content,
+            layout: ctx.layout_class,
+            glyphs: ctx.glyphs,
+        }
     }
 }
 
-impl Widget for &StatusBar {
-    fn render(self, area: Rect, buf: &mut Buffer) {
-        let model = ViewModelContext::current();
-
-        // Get mode info
-        let (mode_text, mode_color) = if let Some(mode_index) = model.get().mode_state {
-            let bg_color = MODE_COLORS
-                .get(mode_index as usize)
-                .copied()
-                .unwrap_or(MODE_DEFAULT_COLOR);
-            (
+/// Working directory, branch and dirty file count, before fitting to a width
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocationSegment {
+    pub directory: String,
+    pub branch: Option<String>,
+    pub dirty_count: usize,
+}
+
+/// The segments of the status line, in the order they are drawn
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatusBarContent {
+    pub location: Option<LocationSegment>,
+    /// Connection state, a pending shortcut hint, or what is running
+    pub activity: String,
+    /// The shortcut waiting for its second key, when the activity is its hint
+    pub shortcut: Option<RepeatShortcutKey>,
+    /// Whether the activity gets a spinner
+    pub busy: bool,
+    pub session_id: Option<String>,
+    /// Keys that apply right now, as many as fit after the session
+    pub hints: Vec<String>,
+    /// Name of the session's directory, while it isn't the workspace root
+    pub session_directory: Option<String>,
+    /// Draft size against the text part limit, once it gets close
+    pub draft: Option<(String, Color)>,
+    /// Estimated tokens of sending the draft, against the context limit if known
+    pub estimate: Option<(String, Color)>,
+    /// System prompt and disabled tools of the session, from `/system` and `/tools`
+    pub badges: Vec<String>,
+    /// Provider and model the next message is sent with
+    pub provider_model: String,
+    /// Whether the provider is known not to serve that model
+    pub model_not_offered: bool,
+    /// Context size of the latest response
+    pub tokens: Option<String>,
+    /// Time the agent worked in the session, counting the running reply
+    pub working_time: Option<String>,
+    pub update_available: bool,
+    pub mode: String,
+    pub mode_color: Color,
+    /// Colors inverted for a moment by an alert, see `alerts`
+    pub flash: bool,
+}
+
+impl StatusBarContent {
+    pub fn from_model(model: &Model) -> Self {
+        let shortcut = pending_shortcut(model);
+        let (activity, busy) = activity(model, shortcut.as_ref());
+
+        let (mode, mode_color) = match (&model.modes, model.mode_state) {
+            (Some(_), Some(index)) => (
                 model
-                    .get()
                     .get_current_mode_name()
-                    .unwrap_or("UNKNOWN".to_string()),
-                bg_color,
+                    .unwrap_or_else(|| MODE_UNKNOWN.to_string()),
+                MODE_COLORS
+                    .get(index as usize)
+                    .copied()
+                    .unwrap_or(MODE_DEFAULT_COLOR),
+            ),
+            _ => (MODE_UNKNOWN.to_string(), MODE_DEFAULT_COLOR),
+        };
+
+        // The mode's own model wins over the default, as when sending
+        let model_name = model
+            .get_current_mode()
+            .and_then(|mode| mode.model.clone())
+            .unwrap_or_else(|| model.sdk_model.clone());
+
+        let draft = model.draft_size_warning().map(|(draft_bytes, limit)| {
+            (
+                format!(
+                    " draft {:.1}k/{}k",
+                    draft_bytes as f64 / 1024.0,
+                    limit / 1024
+                ),
+                if draft_bytes > limit {
+                    Color::Red
+                } else {
+                    Color::Yellow
+                },
             )
-        } else {
-            ("UNKNOWN".to_string(), MODE_DEFAULT_COLOR)
+        });
+
+        let estimate = model.token_estimate.map(|estimate| {
+            let text = match estimate.limit {
+                Some(limit) => format!(
+                    " ~{}/{}",
+                    format_tokens(estimate.tokens),
+                    format_tokens(limit)
+                ),
+                None => format!(" ~{} tokens", format_tokens(estimate.tokens)),
+            };
+            let color = match estimate.exceeds_limit() {
+                true => Color::Red,
+                false => Color::DarkGray,
+            };
+            (text, color)
+        });
+
+        Self {
+            location: model.git_info.as_ref().map(|git_info| LocationSegment {
+                directory: git_info.directory.clone(),
+                branch: git_info.branch.clone(),
+                dirty_count: model.dirty_file_count(),
+            }),
+            activity,
+            shortcut,
+            busy,
+            session_id: model.current_session_id(),
+            hints: shortcut_hints(model)
+                .iter()
+                .map(|binding| binding.hint_text())
+                .collect(),
+            session_directory: model
+                .session()
+                .and_then(|session| model.session_directory_off_root(session))
+                .map(directory_name),
+            draft,
+            estimate,
+            badges: model.session_options().badges(),
+            provider_model: format!(" {} {}", model.sdk_provider, model_name),
+            model_not_offered: model.model_mismatch_hint().is_some(),
+            tokens: model
+                .message_state
+                .latest_token_count()
+                .map(|count| format!(" ({:.1}k tokens)", count / 1000.0)),
+            working_time: model
+                .session_working_time()
+                .map(|elapsed| format!(" ⏱ {}", format_working_time(elapsed))),
+            update_available: model.is_update_available(),
+            mode,
+            mode_color,
+            flash: model.is_timeout_active(&TimeoutType::AlertFlash),
+        }
+    }
+
+    /// The mode label, padded to a fixed width
+    pub fn mode_label(&self) -> String {
+        format!(" {:<width$} ", self.mode, width = MODE_WIDTH)
+    }
+
+    /// Drop the location, session and hints, and shorten a shortcut hint, to
+    /// fit a narrow frame
+    pub fn compact(mut self) -> Self {
+        self.location = None;
+        self.session_id = None;
+        self.hints.clear();
+        self.session_directory = None;
+        if let Some(key) = &self.shortcut {
+            self.activity = compact_hint(key).to_string();
+        }
+        self
+    }
+}
+
+fn pending_shortcut(model: &Model) -> Option<RepeatShortcutKey> {
+    if !model.has_active_timeout() {
+        return None;
+    }
+    model
+        .repeat_shortcut_timeout
+        .as_ref()
+        .map(|timeout| timeout.key.clone())
+}
+
+fn hint(key: &RepeatShortcutKey) -> &'static str {
+    match key {
+        RepeatShortcutKey::Leader => "Shortcut waiting...",
+        RepeatShortcutKey::CtrlC => "Ctrl+C again to confirm",
+        RepeatShortcutKey::CtrlD => "Ctrl+D again to confirm",
+        RepeatShortcutKey::Esc => "Esc again to confirm",
+    }
+}
+
+fn compact_hint(key: &RepeatShortcutKey) -> &'static str {
+    match key {
+        RepeatShortcutKey::Leader => "? help",
+        RepeatShortcutKey::CtrlC => "^C again",
+        RepeatShortcutKey::CtrlD => "^D again",
+        RepeatShortcutKey::Esc => "Esc again",
+    }
+}
+
+/// The activity label and whether something is in progress. A pending
+/// shortcut hint comes first, then a connection that isn't up, then tasks.
+fn activity(model: &Model, shortcut: Option<&RepeatShortcutKey>) -> (String, bool) {
+    let working = !model.session_is_idle || model.active_task_count > 0;
+    if let Some(key) = shortcut {
+        return (hint(key).to_string(), working);
+    }
+
+    let connection = match &model.state {
+        AppModalState::Connecting(status) => status,
+        _ => &model.connection_status,
+    };
+    match connection {
+        ConnectionStatus::Disconnected => return ("Disconnected".to_string(), false),
+        ConnectionStatus::Error(_) => return ("Connection failed".to_string(), false),
+        ConnectionStatus::Connecting => return ("Connecting...".to_string(), true),
+        ConnectionStatus::InitializingSession => return ("Starting session...".to_string(), true),
+        ConnectionStatus::Connected | ConnectionStatus::SessionReady => {}
+    }
+    match &model.event_stream_state {
+        EventStreamState::Reconnecting { attempt, .. } => {
+            return (format!("Reconnecting ({})...", attempt), true)
+        }
+        EventStreamState::Failed(_) if model.message_poll_ms.is_some() => {
+            return (POLLING_FOR_MESSAGES.to_string(), working)
+        }
+        EventStreamState::Failed(_) => return ("Event stream lost".to_string(), working),
+        _ => {}
+    }
+
+    // Shown for a moment after switching sessions
+    if model.previous_session_id.is_some()
+        && model.is_timeout_active(&TimeoutType::SessionToggleHint)
+    {
+        return (SESSION_TOGGLE_HINT.to_string(), working);
+    }
+
+    if model.active_task_count == 0 {
+        return ("Ready".to_string(), working);
+    }
+    let label = model
+        .active_task_kinds
+        .first()
+        .map(TaskKind::label)
+        .unwrap_or("Working...");
+    (label.to_string(), working)
+}
+
+/// Format the location segment ("opencoders main ±4"), fitting it into
+/// `max_width` columns. Pieces are dropped in order of least importance: the
+/// directory first, then the dirty count, and finally the branch is cut short.
+pub fn format_location(
+    directory: &str,
+    branch: Option<&str>,
+    dirty_count: usize,
+    max_width: usize,
+) -> String {
+    let dirty = (dirty_count > 0).then(|| format!("±{}", dirty_count));
+    let mut candidates: Vec<Vec<&str>> = Vec::new();
+    match branch {
+        Some(branch) => {
+            let with_dirty: Vec<&str> = std::iter::once(branch).chain(dirty.as_deref()).collect();
+            let mut full = vec![directory];
+            full.extend(&with_dirty);
+            candidates.push(full);
+            candidates.push(with_dirty);
+            candidates.push(vec![branch]);
+        }
+        None => candidates.push(vec![directory]),
+    }
+
+    for pieces in &candidates {
+        let text = pieces
+            .iter()
+            .filter(|piece| !piece.is_empty())
+            .copied()
+            .collect::<Vec<_>>()
+            .join(" ");
+        if display_width(&text) <= max_width {
+            return text;
+        }
+    }
+
+    // Nothing fits whole, so cut the most important piece short
+    let last = candidates.last().and_then(|pieces| pieces.first()).copied();
+    truncate_with_ellipsis(last.unwrap_or_default(), max_width)
+}
+
+/// The hints joined, leaving off those that would go past `max_width`
+fn fit_hints(hints: &[String], glyphs: &Glyphs, max_width: usize) -> String {
+    let mut text = String::new();
+    for hint in hints {
+        let hint = glyphs.text(hint);
+        let joined = match text.is_empty() {
+            true => hint.into_owned(),
+            false => format!("{}  {}", text, hint),
         };
+        if display_width(&joined) > max_width {
+            break;
+        }
+        text = joined;
+    }
+    text
+}
 
-        // Calculate layout sections
-        let mut mode_len = mode_text.len();
-        let mode_padding = " ".repeat(8 - mode_len);
-        mode_len += mode_padding.len();
+impl Widget for &StatusBar {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let content = &self.content;
 
-        let status_text = format!(
-            " {} {}", // TODO: (20.4k tokens / 9% context)
-            model.get().sdk_provider,
-            model.get().sdk_model,
-        );
-        let status_len = status_text.len();
+        let mode_label = content.mode_label();
+        let mode_len = display_width(&mode_label);
+        let draft_text = content
+            .draft
+            .as_ref()
+            .map(|(text, _)| text.as_str())
+            .unwrap_or_default();
+        let estimate_text = content
+            .estimate
+            .as_ref()
+            .map(|(text, _)| text.as_str())
+            .unwrap_or_default();
+        let badges_text: String = content
+            .badges
+            .iter()
+            .map(|badge| format!(" [{}]", badge))
+            .collect();
+        let tokens_text = content.tokens.as_deref().unwrap_or_default();
+        let glyphs = self.glyphs;
+        let working_time_text = glyphs.text(content.working_time.as_deref().unwrap_or_default());
+        let update_marker = if content.update_available {
+            glyphs.text(UPDATE_MARKER)
+        } else {
+            "".into()
+        };
+        let model_warning = if content.model_not_offered {
+            MODEL_NOT_OFFERED
+        } else {
+            ""
+        };
+        let status_len = display_width(draft_text)
+            + display_width(estimate_text)
+            + display_width(&badges_text)
+            + display_width(&content.provider_model)
+            + display_width(model_warning)
+            + display_width(tokens_text)
+            + display_width(&working_time_text)
+            + display_width(&update_marker);
 
         // Layout the status bar horizontally
         let start_width = (area.width / 4).min(10);
+
+        // The location gets what's left once the fixed sections are placed,
+        // capped so it never crowds out the loading indicator
+        let location_text = match &content.location {
+            Some(location) => {
+                let max_width = (area.width as usize)
+                    .saturating_sub(status_len + mode_len + start_width as usize * 2)
+                    .min(area.width as usize / 3)
+                    .saturating_sub(1);
+                let text = format_location(
+                    &location.directory,
+                    location.branch.as_deref(),
+                    location.dirty_count,
+                    max_width,
+                );
+                let text = glyphs.text(&text).into_owned();
+                if text.is_empty() {
+                    text
+                } else {
+                    format!("{} ", text)
+                }
+            }
+            None => String::new(),
+        };
+        let location_len = display_width(&location_text);
+        // A compacted line gives the session's room to the activity
+        let session_constraint = match self.layout.narrow {
+            true => Constraint::Length(0),
+            false => Constraint::Min(start_width),
+        };
+
         let chunks = Layout::default()
             .direction(Direction::Horizontal)
             .constraints([
-                Constraint::Min(start_width / 2),      // Loading section
-                Constraint::Min(start_width),          // Session ID section
-                Constraint::Length(status_len as u16), // Provider/model section
-                Constraint::Length(mode_len as u16),   // Mode section
+                Constraint::Length(location_len as u16), // Directory/branch section
+                Constraint::Min(start_width / 2),        // Loading section
+                session_constraint,                      // Session ID section
+                Constraint::Length(status_len as u16),   // Provider/model section
+                Constraint::Length(mode_len as u16),     // Mode section
             ])
             .split(area);
 
-        // Render loading indicator
-        let loading_label = match (
-            &model.get().has_active_timeout(),
-            &model.get().repeat_shortcut_timeout,
-            &model.get().active_task_count,
-        ) {
-            (true, Some(timeout), _) => match timeout.key {
-                RepeatShortcutKey::Leader => "Shortcut waiting...",
-                RepeatShortcutKey::CtrlC => "Ctrl+C again to confirm",
-                RepeatShortcutKey::CtrlD => "Ctrl+D again to confirm",
-                RepeatShortcutKey::Esc => "Esc again to confirm",
-            },
-            (_, _, 0) => "Ready",
-            _ => "Working...",
-        };
+        // Render directory, branch and dirty count
+        Paragraph::new(Line::from(Span::styled(
+            location_text,
+            Style::default().fg(Color::Cyan),
+        )))
+        .render(chunks[0], buf);
 
-        if !model.get().session_is_idle || model.get().active_task_count > 0 {
-            Throbber::default()
-                .label(loading_label)
-                .render(chunks[0], buf);
+        // Render loading indicator
+        let activity = glyphs.text(&content.activity);
+        if content.busy {
+            let throbber = Throbber::default().label(activity.as_ref());
+            match glyphs.ascii {
+                true => throbber.throbber_set(throbber_widgets_tui::ASCII),
+                false => throbber,
+            }
+            .render(chunks[1], buf);
         } else {
-            Paragraph::new(loading_label).render(chunks[0], buf);
+            Paragraph::new(activity.as_ref()).render(chunks[1], buf);
         }
 
-        // Render session ID if present (from model instead of local state)
-        if let Some(session_id) = model.get().current_session_id() {
-            let session_paragraph = Paragraph::new(Line::from(Span::styled(
-                &session_id,
+        let mut session_len = 0;
+        if let Some(session_id) = &content.session_id {
+            let directory_text = content
+                .session_directory
+                .as_ref()
+                .map(|directory| format!("{} ", directory))
+                .unwrap_or_default();
+            session_len = display_width(&directory_text) + display_width(session_id);
+            Paragraph::new(Line::from(vec![
+                Span::styled(directory_text, Style::default().fg(Color::Yellow)),
+                Span::styled(session_id.as_str(), Style::default().fg(Color::DarkGray)),
+            ]))
+            .render(chunks[2], buf);
+        }
+
+        // Hints take the room after the session, right aligned, dropping the
+        // lowest ranked that don't fit
+        let hints_room = (chunks[2].width as usize).saturating_sub(session_len + 1);
+        let hints_text = fit_hints(&content.hints, glyphs, hints_room);
+        if !hints_text.is_empty() {
+            Paragraph::new(Span::styled(
+                hints_text,
                 Style::default().fg(Color::DarkGray),
-            )));
-            session_paragraph.render(chunks[1], buf);
+            ))
+            .alignment(Alignment::Right)
+            .render(chunks[2], buf);
         }
 
         // Render provider/model info
-        let status_paragraph = Paragraph::new(Line::from(status_text));
-        status_paragraph.render(chunks[2], buf);
+        let draft_color = content
+            .draft
+            .as_ref()
+            .map(|(_, color)| *color)
+            .unwrap_or(Color::Reset);
+        let estimate_color = content
+            .estimate
+            .as_ref()
+            .map(|(_, color)| *color)
+            .unwrap_or(Color::Reset);
+        Paragraph::new(Line::from(vec![
+            Span::styled(estimate_text, Style::default().fg(estimate_color)),
+            Span::styled(draft_text, Style::default().fg(draft_color)),
+            Span::styled(badges_text, Style::default().fg(Color::Magenta)),
+            Span::raw(content.provider_model.as_str()),
+            Span::styled(model_warning, Style::default().fg(Color::Yellow)),
+            Span::styled(tokens_text, Style::default().fg(Color::DarkGray)),
+            Span::styled(working_time_text, Style::default().fg(Color::DarkGray)),
+            Span::styled(update_marker, Style::default().fg(Color::Yellow)),
+        ]))
+        .render(chunks[3], buf);
 
         // Render mode indicator
-        let mode_paragraph = Paragraph::new(Line::from(Span::styled(
-            format!(" {}{} ", mode_text, mode_padding),
-            Style::default().bg(mode_color).fg(Color::White),
-        )));
-        mode_paragraph.render(chunks[3], buf);
+        Paragraph::new(Line::from(Span::styled(
+            mode_label,
+            Style::default().bg(content.mode_color).fg(Color::White),
+        )))
+        .render(chunks[4], buf);
+
+        if content.flash {
+            buf.set_style(area, Style::default().add_modifier(Modifier::REVERSED));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::tea_model::SessionState;
+    use crate::app::token_estimate::TokenEstimate;
+    use crate::app::ui_components::glyphs;
+    use opencode_sdk::models::{AgentConfig, ConfigAgent, Session, SessionTime};
+
+    fn connected() -> Model {
+        let mut model = Model::new();
+        model.state = AppModalState::None;
+        model.connection_status = ConnectionStatus::Connected;
+        model
+    }
+
+    fn with_modes(mut model: Model, mode_index: u16) -> Model {
+        model.set_modes(ConfigAgent {
+            build: Some(AgentConfig::new()),
+            plan: Some(AgentConfig {
+                model: Some("claude-opus-4".to_string()),
+                ..AgentConfig::new()
+            }),
+            general: Some(AgentConfig::new()),
+        });
+        model.set_mode(mode_index);
+        model
+    }
+
+    #[test]
+    fn test_content_from_model_states() {
+        let mut connecting = Model::new();
+        connecting.state = AppModalState::Connecting(ConnectionStatus::Connecting);
+
+        let mut failed = Model::new();
+        failed.state = AppModalState::Connecting(ConnectionStatus::Error("refused".to_string()));
+
+        let mut working = with_modes(connected(), 0);
+        working.session_is_idle = false;
+
+        let mut polling = connected();
+        polling.event_stream_state = EventStreamState::Failed("proxy".to_string());
+        polling.start_message_polling();
+
+        // (name, model, activity, busy, mode, mode color, provider/model)
+        let cases = [
+            (
+                "connecting",
+                connecting,
+                "Connecting...",
+                true,
+                "UNKNOWN",
+                MODE_DEFAULT_COLOR,
+                " anthropic claude-sonnet-4-20250514",
+            ),
+            (
+                "error",
+                failed,
+                "Connection failed",
+                false,
+                "UNKNOWN",
+                MODE_DEFAULT_COLOR,
+                " anthropic claude-sonnet-4-20250514",
+            ),
+            (
+                "ready with plan mode",
+                with_modes(connected(), 1),
+                "Ready",
+                false,
+                "plan",
+                Color::Magenta,
+                " anthropic claude-opus-4",
+            ),
+            (
+                "working in build mode",
+                working,
+                "Ready",
+                true,
+                "build",
+                Color::Black,
+                " anthropic claude-sonnet-4-20250514",
+            ),
+            (
+                "polling without the event stream",
+                polling,
+                "Live updates unavailable — polling",
+                false,
+                "UNKNOWN",
+                MODE_DEFAULT_COLOR,
+                " anthropic claude-sonnet-4-20250514",
+            ),
+            (
+                "ready before modes load",
+                connected(),
+                "Ready",
+                false,
+                "UNKNOWN",
+                MODE_DEFAULT_COLOR,
+                " anthropic claude-sonnet-4-20250514",
+            ),
+        ];
+
+        for (name, model, activity, busy, mode, mode_color, provider_model) in cases {
+            let content = StatusBarContent::from_model(&model);
+            assert_eq!(content.activity, activity, "{}", name);
+            assert_eq!(content.busy, busy, "{}", name);
+            assert_eq!(content.mode, mode, "{}", name);
+            assert_eq!(content.mode_color, mode_color, "{}", name);
+            assert_eq!(content.provider_model, provider_model, "{}", name);
+            assert_eq!(content.tokens, None, "{}", name);
+            assert_eq!(content.working_time, None, "{}", name);
+            assert!(content.badges.is_empty(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_badges_follow_the_session_options() {
+        let mut model = connected();
+        model.session_options_mut().toggle_tool("bash");
+        model.session_options_mut().system = Some("Be careful".to_string());
+        assert_eq!(
+            StatusBarContent::from_model(&model).badges,
+            vec!["system", "-bash"]
+        );
+        // Narrow frames keep them, since they change what gets sent
+        assert_eq!(
+            StatusBarContent::from_model(&model).compact().badges,
+            vec!["system", "-bash"]
+        );
+    }
+
+    #[test]
+    fn test_token_estimate_red_over_the_limit() {
+        let mut model = connected();
+        assert_eq!(StatusBarContent::from_model(&model).estimate, None);
+
+        model.token_estimate = Some(TokenEstimate {
+            tokens: 1_200,
+            limit: None,
+        });
+        assert_eq!(
+            StatusBarContent::from_model(&model).estimate,
+            Some((" ~1k tokens".to_string(), Color::DarkGray))
+        );
+        model.token_estimate = Some(TokenEstimate {
+            tokens: 215_000,
+            limit: Some(200_000),
+        });
+        assert_eq!(
+            StatusBarContent::from_model(&model).estimate,
+            Some((" ~215k/200k".to_string(), Color::Red))
+        );
+    }
+
+    #[test]
+    fn test_session_directory_shown_off_the_workspace_root() {
+        let mut model = connected();
+        model.workspace_root = Some("/home/me/project".to_string());
+        model.session_state = SessionState::Ready(Session::new(
+            "ses_a".to_string(),
+            "Elsewhere".to_string(),
+            "0.3.1".to_string(),
+            SessionTime::new(0.0, 0.0),
+        ));
+        assert_eq!(StatusBarContent::from_model(&model).session_directory, None);
+
+        model
+            .session_directories
+            .insert("ses_a".to_string(), "/home/me/project".to_string());
+        assert_eq!(StatusBarContent::from_model(&model).session_directory, None);
+
+        // Rooted below the workspace, as a new session can be
+        model
+            .session_directories
+            .insert("ses_a".to_string(), "/home/me/project/src".to_string());
+        assert_eq!(
+            StatusBarContent::from_model(&model).session_directory,
+            Some("src".to_string())
+        );
+
+        model
+            .session_directories
+            .insert("ses_a".to_string(), "/home/me/other".to_string());
+        assert_eq!(
+            StatusBarContent::from_model(&model).session_directory,
+            Some("other".to_string())
+        );
+    }
+
+    #[test]
+    fn test_content_follows_modes_loading() {
+        let mut model = connected();
+        assert_eq!(
+            StatusBarContent::from_model(&model).mode_label(),
+            " UNKNOWN  "
+        );
+
+        // Modes arrive after the first render, and the next render shows them
+        model = with_modes(model, 0);
+        model.increment_mode_index();
+        let content = StatusBarContent::from_model(&model);
+        assert_eq!(content.mode_label(), " plan     ");
+        assert_eq!(content.mode_color, Color::Magenta);
+    }
+
+    #[test]
+    fn test_shortcut_hint_comes_before_connection_state() {
+        let mut model = Model::new();
+        model.clock = crate::app::tea_model::Clock::Manual(std::time::SystemTime::UNIX_EPOCH);
+        model.state = AppModalState::Connecting(ConnectionStatus::Disconnected);
+        assert_eq!(
+            StatusBarContent::from_model(&model).activity,
+            "Disconnected"
+        );
+
+        model.set_repeat_shortcut_timeout(RepeatShortcutKey::CtrlC);
+        assert_eq!(
+            StatusBarContent::from_model(&model).activity,
+            "Ctrl+C again to confirm"
+        );
+        assert_eq!(
+            StatusBarContent::from_model(&model).compact().activity,
+            "^C again"
+        );
+
+        model.set_repeat_shortcut_timeout(RepeatShortcutKey::Leader);
+        assert_eq!(
+            StatusBarContent::from_model(&model).compact().activity,
+            "? help"
+        );
+    }
+
+    #[test]
+    fn test_hints_fit_what_room_there_is() {
+        let mut model = connected();
+        model.state = AppModalState::ModalFileSelect;
+        let content = StatusBarContent::from_model(&model);
+        assert_eq!(
+            content.hints,
+            vec!["space check", "enter choose", "↑↓ move", "esc close"]
+        );
+        assert!(content.compact().hints.is_empty());
+
+        let hints = StatusBarContent::from_model(&model).hints;
+        assert_eq!(
+            fit_hints(&hints, &glyphs::ASCII, 80),
+            "space check  enter choose  ^v move  esc close"
+        );
+        // Those that don't fit whole are left off
+        assert_eq!(
+            fit_hints(&hints, &glyphs::UNICODE, 30),
+            "space check  enter choose"
+        );
+        assert_eq!(fit_hints(&hints, &glyphs::UNICODE, 5), "");
+    }
+
+    #[test]
+    fn test_format_location() {
+        assert_eq!(
+            format_location("opencoders", Some("main"), 4, 80),
+            "opencoders main ±4"
+        );
+        // A clean tree doesn't show a count
+        assert_eq!(
+            format_location("opencoders", Some("main"), 0, 80),
+            "opencoders main"
+        );
+        // Outside of git there's only the directory
+        assert_eq!(format_location("scratch", None, 0, 80), "scratch");
+    }
+
+    #[test]
+    fn test_format_location_truncation_order() {
+        let fit = |width| format_location("opencoders", Some("feature/status"), 12, width);
+
+        assert_eq!(fit(29), "opencoders feature/status ±12");
+        // The directory goes first
+        assert_eq!(fit(28), "feature/status ±12");
+        assert_eq!(fit(18), "feature/status ±12");
+        // Then the dirty count
+        assert_eq!(fit(17), "feature/status");
+        assert_eq!(fit(14), "feature/status");
+        // Then the branch is cut short
+        assert_eq!(fit(8), "feature…");
+        assert_eq!(fit(1), "…");
+        assert_eq!(fit(0), "");
+
+        assert_eq!(format_location("opencoders", None, 3, 6), "openc…");
     }
 }
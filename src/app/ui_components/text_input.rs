@@ -1,28 +1,25 @@
-use crate::app::event_msg::{Cmd, CmdOrBatch, Msg};
-use crate::app::tea_model::{Model, RepeatShortcutKey, SessionState, INLINE_HEIGHT};
-use crate::app::ui_components::Component;
-use crate::app::view_model_context::ViewModelContext;
-use crate::sdk::client::{generate_id, IdPrefix};
+use crate::app::event_msg::CmdOrBatch;
+use crate::app::mentions::MENTION_PATTERN;
+use crate::app::strings::INPUT_PLACEHOLDER;
+use crate::app::tea_model::{Model, INLINE_HEIGHT};
+use crate::app::ui_components::{Component, RenderCtx};
 use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
-use ratatui::widgets::BorderType;
 use ratatui::{
     buffer::Buffer,
-    layout::{Constraint, Direction, Layout, Rect},
+    layout::Rect,
     style::{Color, Style},
-    text::{Line, Span},
-    widgets::{Block, Borders, Paragraph, Widget},
+    widgets::{Block, Borders, Widget},
 };
-use throbber_widgets_tui::{Throbber, ThrobberState};
-use tui_textarea::{Input, Key, TextArea};
+use serde::{Deserialize, Serialize};
+use tui_textarea::{CursorMove, Input, TextArea};
 
-const MODE_COLORS: [Color; 3] = [Color::Black, Color::Magenta, Color::Green];
-const MODE_DEFAULT_COLOR: Color = Color::Gray;
-
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum MsgTextArea {
     KeyInput(KeyEvent),
     Newline,
     Clear,
+    Undo,
+    Redo,
 }
 
 #[derive(Debug, Clone)]
@@ -44,25 +41,28 @@ pub struct InputResult {
 
 pub const TEXT_INPUT_HEIGHT: u16 = 4;
 pub const TEXT_INPUT_AREA_MIN_HEIGHT: u16 = 3; // minimum: border + content + border
-pub const TEXT_INPUT_AREA_MAX_HEIGHT: u16 = INLINE_HEIGHT - 2; // configurable maximum
+pub const TEXT_INPUT_AREA_MAX_HEIGHT: u16 = INLINE_HEIGHT - 2; // until the terminal's size is known
 
-// E.g.:
-// ╭─────────────────────────────────────────────────────────────────────────────────────────────╮
-// │ >                                                                                           │
-// ╰─────────────────────────────────────────────────────────────────────────────────────────────╯
-// ⠧ Working                             Anthropic Claude Opus (21.4k tokens / 9% context) > build
+/// Show `@` file mentions in a distinct style
+fn highlight_mentions(textarea: &mut TextArea) {
+    textarea
+        .set_search_pattern(MENTION_PATTERN)
+        .expect("mention pattern is a valid regex");
+    textarea.set_search_style(Style::default().fg(Color::Cyan));
+}
 
 impl TextInputArea {
     pub fn new() -> Self {
         let mut textarea = TextArea::default();
         textarea.set_cursor_line_style(Style::default()); // No cursor line highlighting by default
+        highlight_mentions(&mut textarea);
 
         Self {
             textarea,
             min_height: TEXT_INPUT_AREA_MIN_HEIGHT,
             max_height: TEXT_INPUT_AREA_MAX_HEIGHT,
             current_height: TEXT_INPUT_AREA_MIN_HEIGHT,
-            placeholder: "Type your message...".to_string(),
+            placeholder: INPUT_PLACEHOLDER.to_string(),
             is_focused: false,
         }
     }
@@ -74,13 +74,30 @@ impl TextInputArea {
         instance
     }
 
+    /// Remove all text as a single edit, so it can be restored with undo.
+    /// What was last killed stays ready to yank.
     pub fn clear(&mut self) {
-        self.textarea = TextArea::default();
-        self.textarea.set_cursor_line_style(Style::default());
-        self.textarea.set_placeholder_text(&self.placeholder);
+        let yank = self.textarea.yank_text();
+        self.textarea.select_all();
+        self.textarea.cut();
+        self.textarea.set_yank_text(yank);
         self.current_height = self.min_height;
     }
 
+    /// Revert the last edit, returning whether anything changed
+    pub fn undo(&mut self) -> bool {
+        let changed = self.textarea.undo();
+        self.current_height = self.calculate_required_height();
+        changed
+    }
+
+    /// Reapply the last undone edit, returning whether anything changed
+    pub fn redo(&mut self) -> bool {
+        let changed = self.textarea.redo();
+        self.current_height = self.calculate_required_height();
+        changed
+    }
+
     pub fn content(&self) -> String {
         self.textarea.lines().join("\n")
     }
@@ -97,10 +114,37 @@ impl TextInputArea {
         self.textarea = TextArea::from(content.lines());
         self.textarea.set_cursor_line_style(Style::default());
         self.textarea.set_placeholder_text(&self.placeholder);
+        highlight_mentions(&mut self.textarea);
         let new_height = self.calculate_required_height();
         self.current_height = new_height;
     }
 
+    pub fn move_cursor_to_end(&mut self) {
+        self.textarea.move_cursor(CursorMove::Bottom);
+        self.textarea.move_cursor(CursorMove::End);
+    }
+
+    /// Insert text at the cursor, newlines included
+    pub fn insert_text(&mut self, text: &str) {
+        self.textarea.insert_str(text);
+        self.current_height = self.calculate_required_height();
+    }
+
+    /// Insert text at the cursor, leaving the cursor between `before` and `after`
+    pub fn insert_around_cursor(&mut self, before: &str, after: &str) {
+        self.textarea.insert_str(before);
+        let (row, col) = self.textarea.cursor();
+        self.textarea.insert_str(after);
+        self.textarea
+            .move_cursor(CursorMove::Jump(row as u16, col as u16));
+        self.current_height = self.calculate_required_height();
+    }
+
+    /// Cursor position as (row, column)
+    pub fn cursor(&self) -> (usize, usize) {
+        self.textarea.cursor()
+    }
+
     pub fn is_empty(&self) -> bool {
         self.textarea.lines().len() == 1 && self.textarea.lines()[0].is_empty()
     }
@@ -115,6 +159,12 @@ impl TextInputArea {
         self.current_height
     }
 
+    /// Let the input grow to `max_height` rows before it scrolls
+    pub fn set_max_height(&mut self, max_height: u16) {
+        self.max_height = max_height.max(self.min_height);
+        self.current_height = self.calculate_required_height();
+    }
+
     pub fn handle_input(&mut self, key_event: KeyEvent) -> InputResult {
         let old_height = self.current_height;
 
@@ -125,10 +175,7 @@ impl TextInputArea {
         ) {
             (KeyCode::Enter, true) => {
                 self.textarea.insert_newline();
-                let new_height = self
-                    .current_height
-                    .saturating_add(1)
-                    .min(TEXT_INPUT_AREA_MAX_HEIGHT);
+                let new_height = self.current_height.saturating_add(1).min(self.max_height);
                 self.current_height = new_height;
                 return InputResult {
                     submitted_text: None,
@@ -562,7 +609,7 @@ impl Component<Model, MsgTextArea, ()> for TextInputArea {
             MsgTextArea::Newline => {
                 model.text_input_area.textarea.insert_newline();
                 model.text_input_area.current_height =
-                    model.text_input_area.current_height.saturating_add(1);
+                    model.text_input_area.calculate_required_height();
             }
             MsgTextArea::KeyInput(key_event) => {
                 model.text_input_area.handle_input(key_event);
@@ -570,6 +617,12 @@ impl Component<Model, MsgTextArea, ()> for TextInputArea {
             MsgTextArea::Clear => {
                 model.text_input_area.clear();
             }
+            MsgTextArea::Undo => {
+                model.text_input_area.undo();
+            }
+            MsgTextArea::Redo => {
+                model.text_input_area.redo();
+            }
         };
         CmdOrBatch::Single(())
     }
@@ -584,15 +637,19 @@ impl Default for TextInputArea {
 // Widget implementation for TextInputArea
 impl Widget for &TextInputArea {
     fn render(self, area: Rect, buf: &mut Buffer) {
-        let model = ViewModelContext::current();
+        self.render_with(&RenderCtx::default(), area, buf);
+    }
+}
 
+impl TextInputArea {
+    pub fn render_with(&self, ctx: &RenderCtx, area: Rect, buf: &mut Buffer) {
         // Create a mutable textarea for rendering with proper styling
         let mut textarea = self.textarea.clone();
 
         // Set up the block with focus-dependent styling
         let block = Block::default()
             .borders(Borders::ALL)
-            .border_type(model.border_type())
+            .border_set(ctx.border_set())
             .border_style(if self.is_focused {
                 Style::default().fg(Color::Blue)
             } else {
@@ -605,3 +662,55 @@ impl Widget for &TextInputArea {
         textarea.render(area, buf);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_height_counts_rows_not_characters() {
+        let mut input = TextInputArea::new();
+        input.set_content("x");
+        let one_row = input.current_height();
+
+        // Wide characters still take a single row each line
+        input.set_content(&"日本語の入力🦀".repeat(20));
+        assert_eq!(input.current_height(), one_row);
+
+        input.set_content("一行目\n二行目");
+        assert_eq!(input.current_height(), one_row + 1);
+    }
+
+    #[test]
+    fn test_clear_keeps_the_killed_text() {
+        let mut input = TextInputArea::new();
+        input.set_content("keep this");
+        let ctrl = |c| KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL);
+        input.handle_input(ctrl('a'));
+        input.handle_input(ctrl('k'));
+        input.insert_text("sent prompt");
+        input.clear();
+        assert!(input.is_empty());
+
+        input.handle_input(ctrl('y'));
+        assert_eq!(input.content(), "keep this");
+
+        // Clearing is still undone as one edit
+        input.clear();
+        input.undo();
+        assert_eq!(input.content(), "keep this");
+    }
+
+    #[test]
+    fn test_height_grows_to_the_max_set() {
+        let mut input = TextInputArea::new();
+        input.set_content(&"line\n".repeat(30));
+        assert_eq!(input.current_height(), TEXT_INPUT_AREA_MAX_HEIGHT);
+
+        input.set_max_height(20);
+        assert_eq!(input.current_height(), 20);
+
+        input.set_max_height(1);
+        assert_eq!(input.current_height(), TEXT_INPUT_AREA_MIN_HEIGHT);
+    }
+}
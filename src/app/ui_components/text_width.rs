@@ -0,0 +1,256 @@
+//! Widths of text in terminal columns
+//!
+//! CJK characters and most emoji take two columns and combining marks take
+//! none, so counting chars misplaces anything laid out next to them. Layout,
+//! truncation and wrapping measure with these helpers instead.
+
+use ratatui::{
+    style::Style,
+    text::{Line, Span},
+};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+const ELLIPSIS: char = '…';
+
+/// Columns `text` takes in the terminal
+pub fn display_width(text: &str) -> usize {
+    text.width()
+}
+
+/// The longest prefix of `text` that fits in `max_width` columns
+pub fn truncate_to_width(text: &str, max_width: usize) -> &str {
+    let mut width = 0;
+    for (index, ch) in text.char_indices() {
+        width += ch.width().unwrap_or(0);
+        if width > max_width {
+            return &text[..index];
+        }
+    }
+    text
+}
+
+/// `text` cut to `max_width` columns, ending in an ellipsis if anything was cut
+pub fn truncate_with_ellipsis(text: &str, max_width: usize) -> String {
+    if display_width(text) <= max_width {
+        return text.to_string();
+    }
+    match max_width {
+        0 => String::new(),
+        _ => format!("{}{}", truncate_to_width(text, max_width - 1), ELLIPSIS),
+    }
+}
+
+/// Columns `skip..skip + width` of `text`, for a line scrolled sideways. A
+/// wide character cut by the left edge leaves a space for its right half,
+/// and one cut by the right edge is left out.
+pub fn clip_columns(text: &str, skip: usize, width: usize) -> String {
+    let mut clipped = String::new();
+    let mut column = 0;
+    for ch in text.chars() {
+        let end = column + ch.width().unwrap_or(0);
+        if end > skip + width {
+            break;
+        }
+        if column >= skip {
+            clipped.push(ch);
+        } else if end > skip {
+            clipped.push_str(&" ".repeat(end - skip));
+        }
+        column = end;
+    }
+    clipped
+}
+
+/// `line` split into rows of at most `width` columns, keeping the styles of
+/// its spans. Rows break after the last space that fits, or mid-word when a
+/// word is longer than a row, and continue at the line's own indent.
+/// Wrapping here rather than in the `Paragraph`
+/// lets callers put something before every row, like a gutter.
+pub fn wrap_line(line: Line<'static>, width: usize) -> Vec<Line<'static>> {
+    if width == 0 || line.width() <= width {
+        return vec![line];
+    }
+
+    let indent = line
+        .spans
+        .iter()
+        .flat_map(|span| span.content.chars())
+        .take_while(|ch| *ch == ' ')
+        .count();
+    // A deep indent would leave the continuation rows no room
+    let indent = if indent * 2 > width { 0 } else { indent };
+
+    let mut rows: Vec<Vec<(char, Style)>> = Vec::new();
+    let mut row: Vec<(char, Style)> = Vec::new();
+    let mut row_width = 0;
+    for span in &line.spans {
+        for ch in span.content.chars() {
+            let ch_width = ch.width().unwrap_or(0);
+            let row_limit = if rows.is_empty() {
+                width
+            } else {
+                width - indent
+            };
+            if row_width + ch_width > row_limit && !row.is_empty() {
+                let rest = match row.iter().rposition(|(c, _)| *c == ' ') {
+                    Some(space) if space > 0 && space + 1 < row.len() => row.split_off(space + 1),
+                    _ => Vec::new(),
+                };
+                rows.push(std::mem::replace(&mut row, rest));
+                row_width = row.iter().map(|(c, _)| c.width().unwrap_or(0)).sum();
+            }
+            row.push((ch, span.style));
+            row_width += ch_width;
+        }
+    }
+    rows.push(row);
+
+    rows.into_iter()
+        .enumerate()
+        .map(|(index, row)| {
+            let mut spans: Vec<Span<'static>> = Vec::new();
+            if index > 0 && indent > 0 {
+                spans.push(Span::raw(" ".repeat(indent)));
+            }
+            for (ch, style) in row {
+                match spans.last_mut() {
+                    Some(last) if last.style == style => last.content.to_mut().push(ch),
+                    _ => spans.push(Span::styled(ch.to_string(), style)),
+                }
+            }
+            Line::from(spans).style(line.style)
+        })
+        .collect()
+}
+
+/// Columns a tab advances to the next multiple of, unless configured
+pub const DEFAULT_TAB_WIDTH: usize = 4;
+
+/// One line of `text` made safe to draw: tabs expanded to the next multiple
+/// of `tab_width` columns, and control characters, which the terminal would
+/// act on, shown as their picture, like ␛ for an escape. ASCII terminals get
+/// `?` instead.
+pub fn printable_line(line: &str, tab_width: usize, ascii: bool) -> String {
+    let tab_width = tab_width.max(1);
+    let mut printable = String::with_capacity(line.len());
+    let mut column = 0;
+    for ch in line.chars() {
+        let shown = match ch {
+            '\t' => {
+                let spaces = tab_width - column % tab_width;
+                printable.extend(std::iter::repeat_n(' ', spaces));
+                column += spaces;
+                continue;
+            }
+            _ if !ch.is_control() => ch,
+            _ if ascii => '?',
+            // Control Pictures, U+2400 on, has one for each C0 control
+            '\0'..='\u{1f}' => char::from_u32(0x2400 + ch as u32).unwrap_or('?'),
+            '\u{7f}' => '␡',
+            _ => '\u{fffd}',
+        };
+        printable.push(shown);
+        column += shown.width().unwrap_or(0);
+    }
+    printable
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wide_characters_take_two_columns() {
+        assert_eq!(display_width("abc"), 3);
+        assert_eq!(display_width("日本語"), 6);
+        assert_eq!(display_width("🦀 ok"), 5);
+        // A combining accent adds no column
+        assert_eq!(display_width("e\u{301}"), 1);
+    }
+
+    #[test]
+    fn test_truncate_never_splits_a_wide_character() {
+        assert_eq!(truncate_to_width("日本語", 4), "日本");
+        assert_eq!(truncate_to_width("日本語", 5), "日本");
+        assert_eq!(truncate_to_width("a🦀b", 2), "a");
+        assert_eq!(truncate_to_width("short", 10), "short");
+    }
+
+    #[test]
+    fn test_ellipsis_fits_the_width() {
+        let cut = truncate_with_ellipsis("会話の要約を作成する", 9);
+        assert_eq!(cut, "会話の要…");
+        assert!(display_width(&cut) <= 9);
+
+        assert_eq!(truncate_with_ellipsis("🦀🦀🦀", 4), "🦀…");
+        assert_eq!(truncate_with_ellipsis("fits", 4), "fits");
+        assert_eq!(truncate_with_ellipsis("cut", 0), "");
+    }
+
+    #[test]
+    fn test_clip_columns_keeps_wide_characters_in_their_columns() {
+        assert_eq!(clip_columns("abcdefgh", 2, 3), "cde");
+        assert_eq!(clip_columns("abc", 2, 10), "c");
+        assert_eq!(clip_columns("abc", 5, 10), "");
+        // "日本語" takes columns 0-1, 2-3 and 4-5
+        assert_eq!(clip_columns("日本語", 1, 4), " 本");
+        assert_eq!(clip_columns("日本語", 2, 3), "本");
+    }
+
+    #[test]
+    fn test_wrap_line_keeps_styles_and_breaks_at_spaces() {
+        use ratatui::style::Color;
+
+        let bold = Style::default().fg(Color::Cyan);
+        let line = Line::from(vec![
+            Span::styled("one two ", bold),
+            Span::raw("three four"),
+        ]);
+        let rows = wrap_line(line, 9);
+        let text: Vec<String> = rows.iter().map(|row| row.to_string()).collect();
+        assert_eq!(text, ["one two ", "three ", "four"]);
+        assert_eq!(rows[0].spans[0].style, bold);
+        assert_eq!(rows[1].spans[0].style, Style::default());
+
+        // Words longer than a row are cut, wide characters kept whole
+        let rows = wrap_line(Line::from("abcdefgh 日本語"), 3);
+        let text: Vec<String> = rows.iter().map(|row| row.to_string()).collect();
+        assert_eq!(text, ["abc", "def", "gh ", "日", "本", "語"]);
+
+        // Continuation rows keep the indent
+        let rows = wrap_line(Line::from("  ⎿  a tool summary"), 12);
+        let text: Vec<String> = rows.iter().map(|row| row.to_string()).collect();
+        assert_eq!(text, ["  ⎿  a tool ", "  summary"]);
+
+        assert_eq!(wrap_line(Line::from("fits"), 4).len(), 1);
+    }
+
+    #[test]
+    fn test_printable_line_shows_controls_and_aligns_tabs() {
+        assert_eq!(
+            printable_line("\u{1b}[31mred\u{1b}[0m", 4, false),
+            "␛[31mred␛[0m"
+        );
+        assert_eq!(printable_line("50%\rdone", 4, false), "50%␍done");
+        assert_eq!(
+            printable_line("bell\u{7}\u{9b}\u{7f}", 4, false),
+            "bell␇\u{fffd}␡"
+        );
+        assert_eq!(printable_line("\u{1b}[1m\u{7}", 4, true), "?[1m?");
+
+        // Tabs stop at the same columns whatever came before them
+        let rows = ["a\tb", "abc\tb", "日\tb", "\tb", "abcd\tb"]
+            .map(|line| printable_line(line, 4, false));
+        assert_eq!(rows[0], "a   b");
+        for row in &rows[..4] {
+            assert_eq!(display_width(row), 5, "{:?}", row);
+        }
+        assert_eq!(display_width(&rows[4]), 9);
+        assert_eq!(printable_line("a\tb", 8, false), "a       b");
+        assert_eq!(printable_line("a\tb", 0, false), "a b");
+
+        for row in rows {
+            assert!(row.chars().all(|ch| !ch.is_control()), "{:?}", row);
+        }
+    }
+}
@@ -0,0 +1,139 @@
+//! How much of each tool's output a reply shows
+//!
+//! Some outputs are worth reading every time, like an edit's diff, while
+//! others are noise, like a glob listing hundreds of files. The `[tools.render]`
+//! table of the config file sets a policy per tool name, with `*` for every
+//! tool not named:
+//!
+//! ```toml
+//! [tools.render]
+//! edit = "full"
+//! bash = "lines(20)"
+//! glob = "summary_only"
+//! "*" = "lines(5)"
+//! ```
+//!
+//! Policies fold the output in the summary view. Expanding with ctrl+r
+//! shows every output in full, whatever its policy.
+
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// The key of the policy for tools without one of their own
+pub const WILDCARD_TOOL: &str = "*";
+
+/// How much of a tool's output shows under its summary line
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum ToolRenderPolicy {
+    /// Only the summary line
+    #[default]
+    SummaryOnly,
+    /// The first lines of the output, then how many more there are
+    Lines(usize),
+    /// All of the output
+    Full,
+}
+
+impl FromStr for ToolRenderPolicy {
+    type Err = String;
+
+    fn from_str(policy: &str) -> Result<Self, Self::Err> {
+        match policy {
+            "summary_only" => Ok(ToolRenderPolicy::SummaryOnly),
+            "full" => Ok(ToolRenderPolicy::Full),
+            _ => policy
+                .strip_prefix("lines(")
+                .and_then(|rest| rest.strip_suffix(')'))
+                .and_then(|count| count.trim().parse().ok())
+                .map(ToolRenderPolicy::Lines)
+                .ok_or_else(|| {
+                    format!(
+                        "unknown tool render policy {:?}, expected summary_only, lines(N) or full",
+                        policy
+                    )
+                }),
+        }
+    }
+}
+
+impl TryFrom<String> for ToolRenderPolicy {
+    type Error = String;
+
+    fn try_from(policy: String) -> Result<Self, Self::Error> {
+        policy.parse()
+    }
+}
+
+/// The configured policies, by tool name
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ToolRenderPolicies {
+    by_tool: HashMap<String, ToolRenderPolicy>,
+}
+
+impl ToolRenderPolicies {
+    pub fn new(by_tool: impl IntoIterator<Item = (String, ToolRenderPolicy)>) -> Self {
+        Self {
+            by_tool: by_tool.into_iter().collect(),
+        }
+    }
+
+    /// The tool's own policy, else the wildcard's, else summary only
+    pub fn resolve(&self, tool: &str) -> ToolRenderPolicy {
+        self.by_tool
+            .get(tool)
+            .or_else(|| self.by_tool.get(WILDCARD_TOOL))
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_policies() {
+        assert_eq!("summary_only".parse(), Ok(ToolRenderPolicy::SummaryOnly));
+        assert_eq!("full".parse(), Ok(ToolRenderPolicy::Full));
+        assert_eq!("lines(20)".parse(), Ok(ToolRenderPolicy::Lines(20)));
+        assert_eq!("lines( 3 )".parse(), Ok(ToolRenderPolicy::Lines(3)));
+        for invalid in ["lines", "lines()", "lines(-1)", "lines(5", "Full", ""] {
+            assert!(
+                invalid.parse::<ToolRenderPolicy>().is_err(),
+                "{:?} parsed",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn test_resolve_prefers_the_tool_then_the_wildcard() {
+        let policy = |pairs: &[(&str, ToolRenderPolicy)]| {
+            ToolRenderPolicies::new(
+                pairs
+                    .iter()
+                    .map(|(tool, policy)| (tool.to_string(), *policy)),
+            )
+        };
+
+        // Nothing configured keeps the summary line only
+        assert_eq!(
+            ToolRenderPolicies::default().resolve("glob"),
+            ToolRenderPolicy::SummaryOnly
+        );
+
+        let policies = policy(&[
+            ("edit", ToolRenderPolicy::Full),
+            (WILDCARD_TOOL, ToolRenderPolicy::Lines(5)),
+        ]);
+        assert_eq!(policies.resolve("edit"), ToolRenderPolicy::Full);
+        assert_eq!(policies.resolve("glob"), ToolRenderPolicy::Lines(5));
+        assert_eq!(policies.resolve("my_mcp_tool"), ToolRenderPolicy::Lines(5));
+
+        let policies = policy(&[("glob", ToolRenderPolicy::SummaryOnly)]);
+        assert_eq!(policies.resolve("glob"), ToolRenderPolicy::SummaryOnly);
+        assert_eq!(policies.resolve("bash"), ToolRenderPolicy::SummaryOnly);
+    }
+}
@@ -0,0 +1,190 @@
+//! Time the agent spent working in each session
+//!
+//! A session's age says little about how long the agent worked in it, so
+//! each span from sending a message to the session going idle is added up
+//! per session instead. A session opened from the server starts from its
+//! history, the time each reply took to complete, so the total holds across
+//! restarts. Spans a failed send started are dropped, as nothing ran.
+
+use opencode_sdk::models::{Message, SessionMessages200ResponseInner};
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WorkingTime {
+    /// Finished spans added up, by session id
+    totals: HashMap<String, Duration>,
+    /// When the running span started, from a send until the session is idle.
+    /// A new session doesn't have an id yet when its first message is sent,
+    /// so the span is given one when it ends.
+    pub working_since: Option<SystemTime>,
+}
+
+impl WorkingTime {
+    /// The agent started working, unless it already is
+    pub fn start(&mut self, now: SystemTime) {
+        self.working_since.get_or_insert(now);
+    }
+
+    /// The agent stopped working in `session_id`, adding the span to it
+    pub fn stop(&mut self, session_id: &str, now: SystemTime) {
+        if let Some(since) = self.working_since.take() {
+            self.add(session_id, now.duration_since(since).unwrap_or_default());
+        }
+    }
+
+    /// Nothing ran after all
+    pub fn cancel(&mut self) {
+        self.working_since = None;
+    }
+
+    pub fn add(&mut self, session_id: &str, elapsed: Duration) {
+        *self.totals.entry(session_id.to_string()).or_default() += elapsed;
+    }
+
+    /// Start `session_id` from what its history shows, keeping what was
+    /// counted here if that's more, as with replies that never completed
+    pub fn seed(&mut self, session_id: &str, history: Duration) {
+        let total = self.totals.entry(session_id.to_string()).or_default();
+        *total = (*total).max(history);
+    }
+
+    /// Time finished in `session_id`
+    pub fn total(&self, session_id: &str) -> Duration {
+        self.totals.get(session_id).copied().unwrap_or_default()
+    }
+
+    /// Time so far in the running span
+    pub fn running(&self, now: SystemTime) -> Duration {
+        self.working_since
+            .and_then(|since| now.duration_since(since).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn totals(&self) -> &HashMap<String, Duration> {
+        &self.totals
+    }
+}
+
+/// Time the replies among `messages` took to complete
+pub fn history_working_time(messages: &[SessionMessages200ResponseInner]) -> Duration {
+    let millis: f64 = messages
+        .iter()
+        .filter_map(|message| match &*message.info {
+            Message::Assistant(reply) => reply
+                .time
+                .completed
+                .map(|completed| (completed - reply.time.created).max(0.0)),
+            _ => None,
+        })
+        .sum();
+    Duration::from_millis(millis as u64)
+}
+
+/// "45s", "14m" or "2h 05m"
+pub fn format_working_time(elapsed: Duration) -> String {
+    let secs = elapsed.as_secs();
+    match secs {
+        0..60 => format!("{}s", secs),
+        60..3600 => format!("{}m", secs / 60),
+        _ => format!("{}h {:02}m", secs / 3600, secs / 60 % 60),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opencode_sdk::models::{
+        AssistantMessage, AssistantMessageTime, UserMessage, UserMessageTime,
+    };
+    use std::time::UNIX_EPOCH;
+
+    fn at(secs: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    fn reply(created_ms: f64, completed_ms: Option<f64>) -> SessionMessages200ResponseInner {
+        let info = Message::Assistant(Box::new(AssistantMessage {
+            time: Box::new(AssistantMessageTime {
+                created: created_ms,
+                completed: completed_ms,
+            }),
+            ..Default::default()
+        }));
+        SessionMessages200ResponseInner::new(info, Vec::new())
+    }
+
+    fn question(created_ms: f64) -> SessionMessages200ResponseInner {
+        let info = Message::User(Box::new(UserMessage {
+            time: Box::new(UserMessageTime::new(created_ms)),
+            ..Default::default()
+        }));
+        SessionMessages200ResponseInner::new(info, Vec::new())
+    }
+
+    #[test]
+    fn test_spans_add_up_across_sends() {
+        let mut time = WorkingTime::default();
+        time.start(at(100));
+        // Sending again while working keeps the span going
+        time.start(at(130));
+        assert_eq!(time.running(at(160)), Duration::from_secs(60));
+        time.stop("ses_a", at(190));
+        assert_eq!(time.total("ses_a"), Duration::from_secs(90));
+        assert_eq!(time.running(at(200)), Duration::ZERO);
+
+        // Idle between sends isn't counted
+        time.start(at(500));
+        time.stop("ses_a", at(530));
+        assert_eq!(time.total("ses_a"), Duration::from_secs(120));
+
+        // Aborted mid-generation, which ends the span like going idle
+        time.start(at(600));
+        time.stop("ses_a", at(615));
+        assert_eq!(time.total("ses_a"), Duration::from_secs(135));
+        // A second stop, as from the idle event after the abort, adds nothing
+        time.stop("ses_a", at(640));
+        assert_eq!(time.total("ses_a"), Duration::from_secs(135));
+
+        // A send that failed never started anything
+        time.start(at(700));
+        time.cancel();
+        time.stop("ses_a", at(760));
+        assert_eq!(time.total("ses_a"), Duration::from_secs(135));
+        assert_eq!(time.total("ses_b"), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_seeded_from_history() {
+        let messages = vec![
+            question(1_000.0),
+            reply(2_000.0, Some(62_000.0)),
+            question(100_000.0),
+            reply(100_500.0, Some(130_500.0)),
+            // Still running, or never completed
+            reply(200_000.0, None),
+        ];
+        assert_eq!(history_working_time(&messages), Duration::from_secs(90));
+
+        let mut time = WorkingTime::default();
+        time.seed("ses_a", history_working_time(&messages));
+        assert_eq!(time.total("ses_a"), Duration::from_secs(90));
+        // Loaded again later, history doesn't take away what was counted
+        time.add("ses_a", Duration::from_secs(30));
+        time.seed("ses_a", Duration::from_secs(100));
+        assert_eq!(time.total("ses_a"), Duration::from_secs(120));
+    }
+
+    #[test]
+    fn test_format_working_time() {
+        assert_eq!(format_working_time(Duration::from_secs(45)), "45s");
+        assert_eq!(
+            format_working_time(Duration::from_secs(14 * 60 + 59)),
+            "14m"
+        );
+        assert_eq!(
+            format_working_time(Duration::from_secs(2 * 3600 + 5 * 60)),
+            "2h 05m"
+        );
+    }
+}
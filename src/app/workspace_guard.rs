@@ -0,0 +1,98 @@
+//! Guard against driving a server that works on another project
+//!
+//! The assistant's tools change the server's workspace, not the directory
+//! the TUI was started in. Started in one repository and connected to a
+//! server from another, it would edit the wrong files, so once the server
+//! says where its workspace is, a mismatch is confirmed before going on.
+
+use crate::app::session_directory::display_directory;
+use crate::app::strings::WORKSPACE_MISMATCH_TEXT;
+use std::path::{Path, PathBuf};
+
+/// Whether `directory` is `root` or below it. Paths that exist are resolved
+/// first, so symlinks compare by where they point, and components are
+/// compared whole, so `/a/bc` isn't below `/a/b`.
+pub fn is_within(directory: &Path, root: &Path) -> bool {
+    let resolve = |path: &Path| path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    resolve(directory).starts_with(resolve(root))
+}
+
+/// The directory the TUI was started in, outside the server's workspace
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorkspaceMismatch {
+    pub directory: PathBuf,
+    pub root: String,
+}
+
+impl WorkspaceMismatch {
+    /// A mismatch unless `directory` is within the workspace at `root`
+    pub fn check(directory: &Path, root: &str) -> Option<Self> {
+        (!is_within(directory, Path::new(root))).then(|| WorkspaceMismatch {
+            directory: directory.to_path_buf(),
+            root: root.to_string(),
+        })
+    }
+
+    /// Body of the confirmation modal
+    pub fn confirm_text(&self, home: Option<&Path>) -> String {
+        WORKSPACE_MISMATCH_TEXT
+            .replace("{root}", &display_directory(&self.root, home))
+            .replace(
+                "{directory}",
+                &display_directory(&self.directory.to_string_lossy(), home),
+            )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_within_compares_whole_components() {
+        let root = Path::new("/no/such/project");
+        assert!(is_within(Path::new("/no/such/project"), root));
+        assert!(is_within(Path::new("/no/such/project/"), root));
+        assert!(is_within(Path::new("/no/such/project/src/app"), root));
+        assert!(!is_within(Path::new("/no/such"), root));
+        assert!(!is_within(Path::new("/no/such/other"), root));
+        assert!(!is_within(Path::new("/no/such/project-old"), root));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_is_within_follows_symlinks() {
+        use std::os::unix::fs::symlink;
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let project = temp_dir.path().join("project");
+        let other = temp_dir.path().join("other");
+        std::fs::create_dir_all(project.join("src")).unwrap();
+        std::fs::create_dir(&other).unwrap();
+        let linked_project = temp_dir.path().join("linked");
+        let linked_src = other.join("src");
+        symlink(&project, &linked_project).unwrap();
+        symlink(project.join("src"), &linked_src).unwrap();
+
+        // Either side may be reached through a link
+        assert!(is_within(&linked_project.join("src"), &project));
+        assert!(is_within(&project, &linked_project));
+        // Where a link points decides, not where it sits
+        assert!(is_within(&linked_src, &project));
+        assert!(!is_within(&linked_src, &other));
+    }
+
+    #[test]
+    fn test_check_names_both_directories() {
+        let home = Some(Path::new("/home/me"));
+        assert_eq!(
+            WorkspaceMismatch::check(Path::new("/home/me/project/src"), "/home/me/project"),
+            None
+        );
+        let mismatch =
+            WorkspaceMismatch::check(Path::new("/home/me/project"), "/srv/other").unwrap();
+        let text = mismatch.confirm_text(home);
+        assert!(text.contains("/srv/other"));
+        assert!(text.contains("~/project"));
+    }
+}
@@ -0,0 +1,105 @@
+//! Session maintenance against a running server, using the SDK's bulk helpers
+//!
+//! ```text
+//! maintenance [--url <url>] list [<title filter>]
+//! maintenance [--url <url>] prune <days>
+//! maintenance [--url <url>] export <dir> [json|md]
+//! ```
+//!
+//! Without `--url` the server is discovered like the TUI does.
+use color_eyre::Result;
+use opencoders::sdk::{
+    bulk::{self, ExportFormat, Report},
+    OpenCodeClient,
+};
+use std::{path::PathBuf, time::Duration};
+
+const USAGE: &str = "usage: maintenance [--url <url>] (list [<title filter>] | prune <days> | export <dir> [json|md])";
+
+#[derive(Debug)]
+enum Command {
+    List(Option<String>),
+    Prune(u64),
+    Export(PathBuf, ExportFormat),
+}
+
+fn parse_args(args: Vec<String>) -> Result<(Option<String>, Command)> {
+    let mut args = args.into_iter().peekable();
+    let mut url = None;
+    if args.peek().map(String::as_str) == Some("--url") {
+        args.next();
+        url = Some(
+            args.next()
+                .ok_or_else(|| eyre::eyre!("--url needs a URL"))?,
+        );
+    }
+    let command = match (args.next().as_deref(), args.next(), args.next()) {
+        (Some("list"), filter, None) => Command::List(filter),
+        (Some("prune"), Some(days), None) => Command::Prune(days.parse()?),
+        (Some("export"), Some(dir), format) => {
+            let format = match format.as_deref() {
+                None | Some("json") => ExportFormat::Json,
+                Some("md") => ExportFormat::Markdown,
+                Some(other) => eyre::bail!("Unknown export format: {}", other),
+            };
+            Command::Export(PathBuf::from(dir), format)
+        }
+        _ => eyre::bail!(USAGE),
+    };
+    Ok((url, command))
+}
+
+fn print_report<T: std::fmt::Display>(verb: &str, report: &Report<T>) {
+    for done in &report.succeeded {
+        println!("{} {}", verb, done);
+    }
+    for failure in &report.failed {
+        eprintln!("failed {}: {}", failure.session_id, failure.error);
+    }
+    println!(
+        "{} succeeded, {} failed",
+        report.succeeded.len(),
+        report.failed.len()
+    );
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    color_eyre::install()?;
+    let (url, command) = parse_args(std::env::args().skip(1).collect())?;
+    let client = match url {
+        Some(url) => OpenCodeClient::new(&url),
+        None => OpenCodeClient::discover().await?,
+    };
+
+    match command {
+        Command::List(filter) => {
+            let sessions = bulk::find_sessions(&client, |session| {
+                filter
+                    .as_deref()
+                    .is_none_or(|filter| session.title.contains(filter))
+            })
+            .await?;
+            for session in sessions {
+                println!("{}  {}", session.id, session.title);
+            }
+        }
+        Command::Prune(days) => {
+            let age = Duration::from_secs(days * 24 * 60 * 60);
+            let report = bulk::delete_sessions_older_than(&client, age).await?;
+            print_report("deleted", &report);
+        }
+        Command::Export(dir, format) => {
+            let report = bulk::export_all_sessions(&client, &dir, format).await?;
+            let paths: Vec<_> = report.succeeded.iter().map(|p| p.display()).collect();
+            print_report(
+                "wrote",
+                &Report {
+                    succeeded: paths,
+                    failed: report.failed,
+                },
+            );
+        }
+    }
+    Ok(())
+}
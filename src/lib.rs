@@ -1,10 +1,13 @@
 //! OpenCode Rust TUI
 //!
 //! This crate provides a Terminal User Interface (TUI) for the OpenCode project.
-//! It includes an SDK for communicating with the OpenCode server.
+//! It includes an SDK for communicating with the OpenCode server, which builds
+//! on its own with `default-features = false`.
 
+#[cfg(feature = "tui")]
 pub mod app;
 pub mod sdk;
+#[cfg(feature = "tui")]
 pub mod storybook;
 
 // Re-export commonly used types for convenience
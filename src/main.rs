@@ -1,13 +1,18 @@
-mod app;
-mod sdk;
+use opencoders::app::{self, profile::Profile};
+use std::process::ExitCode;
 
-fn main() -> app::Result<()> {
+fn main() -> app::Result<ExitCode> {
     // Install color-eyre for enhanced error reporting
     // This must be the very first operation to ensure proper error handling
     color_eyre::install().expect("Failed to install color-eyre");
 
+    // Parsed before the logger, which feeds the profile when one is asked for
+    let args = app::cli::CliArgs::from_env();
+    let profile = matches!(&args, Ok(args) if args.profile).then(Profile::default);
+
     // Initialize logger - keep guard alive for the duration of the program
-    let _logger_guard = app::logger::init().expect("Failed to initialize logger");
+    let (_logger_guard, log_control) =
+        app::logger::init(profile.as_ref()).expect("Failed to initialize logger");
     // Log diagnostics in debug mode
     #[cfg(debug_assertions)]
     {
@@ -16,12 +21,16 @@ fn main() -> app::Result<()> {
 
     tracing::info!("TUI application starting");
 
-    let result = app::run();
+    let result = args.and_then(|args| app::run(args, log_control));
 
     if let Err(ref e) = result {
         tracing::error!("Application error: {}", e);
     }
 
     tracing::info!("TUI application shutting down");
+    // Printed once the terminal is restored
+    if let Some(profile) = profile {
+        eprint!("{}", profile.report());
+    }
     result
 }
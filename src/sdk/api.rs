@@ -0,0 +1,228 @@
+//! The server API the client is built on
+//!
+//! `OpenCodeClient` talks to the server through [`OpenCodeApi`] rather than
+//! calling the generated `default_api` functions itself. [`HttpApi`] is the
+//! real implementation over the generated crate; with the `mock` feature,
+//! `MockApi` answers from memory so code built on the client can be tested
+//! without a server.
+//!
+//! Event streams and the raw requests of the extensions still go over HTTP
+//! to the client's base URL.
+
+use crate::sdk::error::Result;
+use async_trait::async_trait;
+use opencode_sdk::{
+    apis::{configuration::Configuration, default_api},
+    models::{
+        App, AppLogRequest, AssistantMessage, Config, ConfigProviders200Response, File,
+        FileRead200Response, FindText200ResponseInner, Session, SessionChatRequest,
+        SessionCreateRequest, SessionInitRequest, SessionMessages200ResponseInner,
+        SessionSummarizeRequest, Symbol,
+    },
+};
+
+/// The requests `OpenCodeClient` makes, one method per endpoint
+#[async_trait]
+pub trait OpenCodeApi: std::fmt::Debug + Send + Sync {
+    // App and configuration
+
+    async fn app_get(&self) -> Result<App>;
+    async fn app_init(&self) -> Result<bool>;
+    async fn app_log(&self, request: AppLogRequest) -> Result<bool>;
+    async fn config_get(&self) -> Result<Config>;
+    async fn config_providers(&self) -> Result<ConfigProviders200Response>;
+
+    // Sessions
+
+    async fn session_create(&self, request: SessionCreateRequest) -> Result<Session>;
+    async fn session_get(&self, session_id: &str) -> Result<Session>;
+    async fn session_list(&self) -> Result<Vec<Session>>;
+    async fn session_delete(&self, session_id: &str) -> Result<bool>;
+    async fn session_init(&self, session_id: &str, request: SessionInitRequest) -> Result<bool>;
+    async fn session_abort(&self, session_id: &str) -> Result<bool>;
+    async fn session_share(&self, session_id: &str) -> Result<Session>;
+    async fn session_unshare(&self, session_id: &str) -> Result<Session>;
+    async fn session_summarize(
+        &self,
+        session_id: &str,
+        request: SessionSummarizeRequest,
+    ) -> Result<bool>;
+
+    // Messages
+
+    async fn session_messages(
+        &self,
+        session_id: &str,
+    ) -> Result<Vec<SessionMessages200ResponseInner>>;
+    async fn session_chat(
+        &self,
+        session_id: &str,
+        request: SessionChatRequest,
+    ) -> Result<AssistantMessage>;
+
+    // Files and search
+
+    async fn file_read(&self, path: &str) -> Result<FileRead200Response>;
+    async fn file_status(&self) -> Result<Vec<File>>;
+    async fn find_text(&self, pattern: &str) -> Result<Vec<FindText200ResponseInner>>;
+    async fn find_files(&self, query: &str) -> Result<Vec<String>>;
+    async fn find_symbols(&self, query: &str) -> Result<Vec<Symbol>>;
+}
+
+/// The API of a running server, through the generated client
+#[derive(Debug, Clone)]
+pub struct HttpApi {
+    config: Configuration,
+}
+
+impl HttpApi {
+    pub fn new(config: Configuration) -> Self {
+        Self { config }
+    }
+}
+
+#[async_trait]
+impl OpenCodeApi for HttpApi {
+    async fn app_get(&self) -> Result<App> {
+        Ok(default_api::app_period_get(&self.config).await?)
+    }
+
+    async fn app_init(&self) -> Result<bool> {
+        Ok(default_api::app_period_init(&self.config).await?)
+    }
+
+    async fn app_log(&self, request: AppLogRequest) -> Result<bool> {
+        let params = default_api::AppPeriodLogParams {
+            app_log_request: Some(request),
+        };
+        Ok(default_api::app_period_log(&self.config, params).await?)
+    }
+
+    async fn config_get(&self) -> Result<Config> {
+        Ok(default_api::config_period_get(&self.config).await?)
+    }
+
+    async fn config_providers(&self) -> Result<ConfigProviders200Response> {
+        Ok(default_api::config_period_providers(&self.config).await?)
+    }
+
+    async fn session_create(&self, request: SessionCreateRequest) -> Result<Session> {
+        let params = default_api::SessionPeriodCreateParams {
+            session_create_request: Some(request),
+        };
+        Ok(default_api::session_period_create(&self.config, params).await?)
+    }
+
+    async fn session_get(&self, session_id: &str) -> Result<Session> {
+        let params = default_api::SessionPeriodGetParams {
+            id: session_id.to_string(),
+        };
+        Ok(default_api::session_period_get(&self.config, params).await?)
+    }
+
+    async fn session_list(&self) -> Result<Vec<Session>> {
+        Ok(default_api::session_period_list(&self.config).await?)
+    }
+
+    async fn session_delete(&self, session_id: &str) -> Result<bool> {
+        let params = default_api::SessionPeriodDeleteParams {
+            id: session_id.to_string(),
+        };
+        Ok(default_api::session_period_delete(&self.config, params).await?)
+    }
+
+    async fn session_init(&self, session_id: &str, request: SessionInitRequest) -> Result<bool> {
+        let params = default_api::SessionPeriodInitParams {
+            id: session_id.to_string(),
+            session_init_request: Some(request),
+        };
+        Ok(default_api::session_period_init(&self.config, params).await?)
+    }
+
+    async fn session_abort(&self, session_id: &str) -> Result<bool> {
+        let params = default_api::SessionPeriodAbortParams {
+            id: session_id.to_string(),
+        };
+        Ok(default_api::session_period_abort(&self.config, params).await?)
+    }
+
+    async fn session_share(&self, session_id: &str) -> Result<Session> {
+        let params = default_api::SessionPeriodShareParams {
+            id: session_id.to_string(),
+        };
+        Ok(default_api::session_period_share(&self.config, params).await?)
+    }
+
+    async fn session_unshare(&self, session_id: &str) -> Result<Session> {
+        let params = default_api::SessionPeriodUnshareParams {
+            id: session_id.to_string(),
+        };
+        Ok(default_api::session_period_unshare(&self.config, params).await?)
+    }
+
+    async fn session_summarize(
+        &self,
+        session_id: &str,
+        request: SessionSummarizeRequest,
+    ) -> Result<bool> {
+        let params = default_api::SessionPeriodSummarizeParams {
+            id: session_id.to_string(),
+            session_summarize_request: Some(request),
+        };
+        Ok(default_api::session_period_summarize(&self.config, params).await?)
+    }
+
+    async fn session_messages(
+        &self,
+        session_id: &str,
+    ) -> Result<Vec<SessionMessages200ResponseInner>> {
+        let params = default_api::SessionPeriodMessagesParams {
+            id: session_id.to_string(),
+        };
+        Ok(default_api::session_period_messages(&self.config, params).await?)
+    }
+
+    async fn session_chat(
+        &self,
+        session_id: &str,
+        request: SessionChatRequest,
+    ) -> Result<AssistantMessage> {
+        let params = default_api::SessionPeriodChatParams {
+            id: session_id.to_string(),
+            session_chat_request: Some(request),
+        };
+        Ok(default_api::session_period_chat(&self.config, params).await?)
+    }
+
+    async fn file_read(&self, path: &str) -> Result<FileRead200Response> {
+        let params = default_api::FilePeriodReadParams {
+            path: path.to_string(),
+        };
+        Ok(default_api::file_period_read(&self.config, params).await?)
+    }
+
+    async fn file_status(&self) -> Result<Vec<File>> {
+        Ok(default_api::file_period_status(&self.config).await?)
+    }
+
+    async fn find_text(&self, pattern: &str) -> Result<Vec<FindText200ResponseInner>> {
+        let params = default_api::FindPeriodTextParams {
+            pattern: pattern.to_string(),
+        };
+        Ok(default_api::find_period_text(&self.config, params).await?)
+    }
+
+    async fn find_files(&self, query: &str) -> Result<Vec<String>> {
+        let params = default_api::FindPeriodFilesParams {
+            query: query.to_string(),
+        };
+        Ok(default_api::find_period_files(&self.config, params).await?)
+    }
+
+    async fn find_symbols(&self, query: &str) -> Result<Vec<Symbol>> {
+        let params = default_api::FindPeriodSymbolsParams {
+            query: query.to_string(),
+        };
+        Ok(default_api::find_period_symbols(&self.config, params).await?)
+    }
+}
@@ -0,0 +1,253 @@
+//! Bulk operations over a server's sessions, for maintenance scripts
+//!
+//! Each helper lists the sessions once and then makes one request per
+//! session, a few at a time. Every outcome is collected into a `Report`
+//! instead of stopping at the first failure, so one bad session doesn't
+//! leave the rest of a cleanup undone.
+
+use crate::sdk::{
+    error::{OpenCodeError, Result},
+    OpenCodeClient, Session,
+};
+use futures_util::stream::{self, StreamExt};
+use opencode_sdk::models::{Message, Part, SessionMessages200ResponseInner};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Requests in flight at once, to keep a big cleanup from flooding the server
+const MAX_IN_FLIGHT: usize = 4;
+
+/// What a bulk operation did to each session
+#[derive(Debug)]
+pub struct Report<T> {
+    pub succeeded: Vec<T>,
+    pub failed: Vec<Failure>,
+}
+
+/// A session the operation failed on
+#[derive(Debug)]
+pub struct Failure {
+    pub session_id: String,
+    pub error: OpenCodeError,
+}
+
+impl<T> Default for Report<T> {
+    fn default() -> Self {
+        Self {
+            succeeded: Vec::new(),
+            failed: Vec::new(),
+        }
+    }
+}
+
+impl<T> Report<T> {
+    /// Whether every session succeeded
+    pub fn is_complete(&self) -> bool {
+        self.failed.is_empty()
+    }
+
+    fn record(&mut self, session_id: String, outcome: Result<T>) {
+        match outcome {
+            Ok(value) => self.succeeded.push(value),
+            Err(error) => self.failed.push(Failure { session_id, error }),
+        }
+    }
+}
+
+/// How `export_all_sessions` writes each session
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// The session and its messages as the server returns them
+    Json,
+    /// The text of each message under a heading for its role
+    Markdown,
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Json => "json",
+            ExportFormat::Markdown => "md",
+        }
+    }
+}
+
+/// Run `op` on each session with a bounded number in flight
+async fn for_each_session<T, F, Fut>(sessions: Vec<Session>, op: F) -> Report<T>
+where
+    F: Fn(Session) -> Fut,
+    Fut: Future<Output = (String, Result<T>)>,
+{
+    stream::iter(sessions)
+        .map(op)
+        .buffer_unordered(MAX_IN_FLIGHT)
+        .fold(
+            Report::default(),
+            |mut report, (session_id, outcome)| async {
+                report.record(session_id, outcome);
+                report
+            },
+        )
+        .await
+}
+
+/// The sessions `predicate` holds for
+pub async fn find_sessions(
+    client: &OpenCodeClient,
+    predicate: impl Fn(&Session) -> bool,
+) -> Result<Vec<Session>> {
+    let sessions = client.list_sessions().await?;
+    Ok(sessions.into_iter().filter(|s| predicate(s)).collect())
+}
+
+/// Delete `sessions`, reporting the ids of the ones deleted
+pub async fn delete_sessions(client: &OpenCodeClient, sessions: Vec<Session>) -> Report<String> {
+    for_each_session(sessions, |session| async move {
+        let outcome = match client.delete_session(&session.id).await {
+            Ok(true) => Ok(session.id.clone()),
+            Ok(false) => Err(OpenCodeError::Unexpected(
+                "server declined to delete the session".to_string(),
+            )),
+            Err(e) => Err(e),
+        };
+        (session.id, outcome)
+    })
+    .await
+}
+
+/// Delete every session not updated within `age`
+pub async fn delete_sessions_older_than(
+    client: &OpenCodeClient,
+    age: Duration,
+) -> Result<Report<String>> {
+    let cutoff = SystemTime::now()
+        .checked_sub(age)
+        .unwrap_or(UNIX_EPOCH)
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let sessions = find_sessions(client, |session| updated_before(session, cutoff)).await?;
+    Ok(delete_sessions(client, sessions).await)
+}
+
+/// Whether the session was last updated before `cutoff`, since the epoch.
+/// Session times are in milliseconds.
+fn updated_before(session: &Session, cutoff: Duration) -> bool {
+    session.time.updated < cutoff.as_millis() as f64
+}
+
+/// Write each session to `dir` as `<session id>.<extension>`, reporting the
+/// paths written
+pub async fn export_all_sessions(
+    client: &OpenCodeClient,
+    dir: &Path,
+    format: ExportFormat,
+) -> Result<Report<PathBuf>> {
+    tokio::fs::create_dir_all(dir)
+        .await
+        .map_err(|e| OpenCodeError::Unexpected(format!("Creating {}: {}", dir.display(), e)))?;
+    let sessions = client.list_sessions().await?;
+    Ok(for_each_session(sessions, |session| async move {
+        let outcome = export_session(client, &session, dir, format).await;
+        (session.id, outcome)
+    })
+    .await)
+}
+
+async fn export_session(
+    client: &OpenCodeClient,
+    session: &Session,
+    dir: &Path,
+    format: ExportFormat,
+) -> Result<PathBuf> {
+    let messages = client.get_messages(&session.id).await?;
+    let contents = match format {
+        ExportFormat::Json => serde_json::to_string_pretty(&serde_json::json!({
+            "session": session,
+            "messages": messages,
+        }))?,
+        ExportFormat::Markdown => session_markdown(session, &messages),
+    };
+    let path = dir.join(format!("{}.{}", session.id, format.extension()));
+    tokio::fs::write(&path, contents)
+        .await
+        .map_err(|e| OpenCodeError::Unexpected(format!("Writing {}: {}", path.display(), e)))?;
+    Ok(path)
+}
+
+/// The session's title, then each message's text under its role
+fn session_markdown(session: &Session, messages: &[SessionMessages200ResponseInner]) -> String {
+    let mut markdown = format!("# {}\n", session.title);
+    for message in messages {
+        let role = match &*message.info {
+            Message::User(_) => "User",
+            Message::Assistant(_) => "Assistant",
+        };
+        markdown.push_str(&format!("\n## {}\n", role));
+        for part in &message.parts {
+            if let Part::Text(text) = part {
+                markdown.push_str(&format!("\n{}\n", text.text));
+            }
+        }
+    }
+    markdown
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opencode_sdk::models::{SessionTime, TextPart};
+
+    fn session(id: &str, updated_ms: f64) -> Session {
+        Session::new(
+            id.to_string(),
+            "Fix the parser".to_string(),
+            "0.3.1".to_string(),
+            SessionTime::new(0.0, updated_ms),
+        )
+    }
+
+    fn text(text: &str) -> Part {
+        Part::Text(Box::new(TextPart {
+            text: text.to_string(),
+            ..Default::default()
+        }))
+    }
+
+    #[test]
+    fn test_updated_before() {
+        let cutoff = Duration::from_secs(100);
+        assert!(updated_before(&session("ses_a", 99_000.0), cutoff));
+        assert!(!updated_before(&session("ses_b", 100_000.0), cutoff));
+    }
+
+    #[test]
+    fn test_session_markdown() {
+        let user = Message::User(Box::default());
+        let assistant = Message::Assistant(Box::default());
+        let messages = vec![
+            SessionMessages200ResponseInner::new(user, vec![text("Why does it fail?")]),
+            SessionMessages200ResponseInner::new(assistant, vec![text("A missing brace.")]),
+        ];
+        assert_eq!(
+            session_markdown(&session("ses_a", 0.0), &messages),
+            "# Fix the parser\n\n## User\n\nWhy does it fail?\n\n## Assistant\n\nA missing brace.\n"
+        );
+    }
+
+    #[test]
+    fn test_report_keeps_every_outcome() {
+        let mut report = Report::default();
+        report.record("ses_a".to_string(), Ok("ses_a".to_string()));
+        report.record(
+            "ses_b".to_string(),
+            Err(OpenCodeError::SessionNotFound {
+                session_id: "ses_b".to_string(),
+            }),
+        );
+        assert_eq!(report.succeeded, vec!["ses_a".to_string()]);
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].session_id, "ses_b");
+        assert!(!report.is_complete());
+    }
+}
@@ -1,12 +1,14 @@
 //! High-level client wrapper for the OpenCode API
 
 use crate::sdk::{
+    api::{HttpApi, OpenCodeApi},
     discovery::{discover_opencode_server, DiscoveryConfig},
     error::{OpenCodeError, Result},
     extensions::events::{EventStream, EventStreamHandle},
+    extensions::metrics::{EndpointStats, RequestMetrics, RequestSample},
     LogLevel,
 };
-use crate::app::tea_model::AttachedFile;
+pub use crate::sdk::id::{generate_descending_id, generate_id, IdPrefix};
 use opencode_sdk::{
     apis::{configuration::Configuration, default_api},
     models::{
@@ -14,123 +16,152 @@ use opencode_sdk::{
         SessionChatRequest, SessionChatRequestPartsInner, SessionMessages200ResponseInner, *,
     },
 };
-use rand::{thread_rng, Rng};
 use reqwest::Client;
-use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::Arc;
-use std::time::{SystemTime, UNIX_EPOCH};
-use tokio::sync::RwLock;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/// Default maximum size in bytes of a single outbound text part
+pub const DEFAULT_MAX_TEXT_PART_BYTES: usize = 32 * 1024;
+
+/// Client-side options that shape outgoing requests
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClientOptions {
+    /// Text longer than this is split on line boundaries into multiple parts
+    pub max_text_part_bytes: usize,
+}
+
+impl Default for ClientOptions {
+    fn default() -> Self {
+        Self {
+            max_text_part_bytes: DEFAULT_MAX_TEXT_PART_BYTES,
+        }
+    }
+}
+
+/// Optional settings sent along with a chat message
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChatOptions {
+    /// Mode (agent) to respond with
+    pub mode: Option<String>,
+    /// System prompt for this message
+    pub system: Option<String>,
+    /// Tools to enable or disable by name, the rest keep the server default
+    pub tools: Option<HashMap<String, bool>>,
+}
+
+/// A file attached to an outgoing message
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AttachedFile {
+    pub file: File,           // From opencode_sdk::models::File
+    pub part_id: String,      // Generated ID for the file part
+    pub display_name: String, // For UI display (filename only)
+}
 
-static COUNTER: AtomicU64 = AtomicU64::new(0);
-static LAST_TIMESTAMP: AtomicU64 = AtomicU64::new(0);
+/// One page of a session's messages, oldest first
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MessagePage {
+    pub messages: Vec<SessionMessages200ResponseInner>,
+    /// Whether the session has messages before this page
+    pub has_earlier: bool,
+}
+
+/// The `limit` messages before `before_message_id`, or the latest ones. An
+/// empty page when the session no longer has that message, since there is
+/// nothing known to come before it.
+pub fn page_messages(
+    mut messages: Vec<SessionMessages200ResponseInner>,
+    before_message_id: Option<&str>,
+    limit: usize,
+) -> MessagePage {
+    if let Some(before) = before_message_id {
+        let Some(end) = messages
+            .iter()
+            .position(|message| message_id(&message.info) == before)
+        else {
+            return MessagePage::default();
+        };
+        messages.truncate(end);
+    }
+    let start = messages.len().saturating_sub(limit);
+    MessagePage {
+        has_earlier: start > 0,
+        messages: messages.split_off(start),
+    }
+}
+
+fn message_id(message: &Message) -> &str {
+    match message {
+        Message::User(user) => &user.id,
+        Message::Assistant(assistant) => &assistant.id,
+    }
+}
 
 /// High-level client for the OpenCode API
 ///
 /// This client provides an ergonomic interface to the OpenCode API,
-/// wrapping the generated client with additional functionality.
+/// wrapping the generated client with additional functionality. Requests go
+/// through an [`OpenCodeApi`], so tests can swap the server for a mock.
 #[derive(Debug, Clone)]
 pub struct OpenCodeClient {
     config: Configuration,
-    #[allow(dead_code)]
-    event_stream: Option<Arc<RwLock<EventStream>>>,
+    options: ClientOptions,
+    /// Recent request timings, shared by every clone of this client
+    metrics: Arc<Mutex<RequestMetrics>>,
+    /// Where requests go, the server itself unless built `with_api`
+    api: Arc<dyn OpenCodeApi>,
+    /// The live event stream, shared by every clone so they subscribe to
+    /// one connection
+    event_stream: Arc<Mutex<Option<EventStream>>>,
 }
 
-#[derive(Debug, Clone, Copy)] // Add traits for convenience
-pub enum IdPrefix {
-    Message,
-    Session,
-    User,
-    Part,
-    Permission,
-}
-impl IdPrefix {
-    pub fn as_str(&self) -> &'static str {
-        match self {
-            IdPrefix::Message => "msg",
-            IdPrefix::Session => "ses",
-            IdPrefix::User => "usr",
-            IdPrefix::Part => "prt",
-            IdPrefix::Permission => "per",
+/// Split text into chunks of at most `limit` bytes, breaking only on line boundaries
+///
+/// Line endings stay attached to their line so joining the chunks gives back
+/// the original text. Fails if a single line is longer than the limit.
+pub fn split_text_into_chunks(text: &str, limit: usize) -> Result<Vec<String>> {
+    if text.len() <= limit {
+        return Ok(vec![text.to_string()]);
+    }
+
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    for line in text.split_inclusive('\n') {
+        if line.len() > limit {
+            return Err(OpenCodeError::text_part_too_large(line.len(), limit));
+        }
+        if current.len() + line.len() > limit {
+            chunks.push(std::mem::take(&mut current));
         }
+        current.push_str(line);
+    }
+    if !current.is_empty() {
+        chunks.push(current);
     }
-}
-
-pub fn generate_id(prefix: IdPrefix) -> String {
-    generate_id_with_direction(prefix, false)
-}
 
-pub fn generate_descending_id(prefix: IdPrefix) -> String {
-    generate_id_with_direction(prefix, true)
+    Ok(chunks)
 }
 
-fn generate_id_with_direction(prefix: IdPrefix, descending: bool) -> String {
-    let current_timestamp = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap()
-        .as_millis() as u64;
-
-    // Handle counter increment with atomic operations to match Go/TypeScript logic
-    let (timestamp_to_use, counter) = loop {
-        let last_ts = LAST_TIMESTAMP.load(Ordering::SeqCst);
-
-        if current_timestamp != last_ts {
-            // Try to update the timestamp and reset counter
-            if LAST_TIMESTAMP
-                .compare_exchange(
-                    last_ts,
-                    current_timestamp,
-                    Ordering::SeqCst,
-                    Ordering::SeqCst,
-                )
-                .is_ok()
-            {
-                COUNTER.store(1, Ordering::SeqCst);
-                break (current_timestamp, 1);
-            }
-            // If we failed to update, loop again
-        } else {
-            // Same timestamp, increment counter
-            let counter = COUNTER.fetch_add(1, Ordering::SeqCst) + 1;
-            break (current_timestamp, counter);
-        }
-    };
-
-    // Match TypeScript/Go: (timestamp_ms << 12) + counter
-    let mut now = timestamp_to_use * 0x1000 + counter;
-
-    // Apply descending bit flip if requested
-    if descending {
-        now = !now;
-    }
-
-    // Extract 6 bytes like TypeScript/Go (48 bits total)
-    let time_bytes: [u8; 6] = [
-        ((now >> 40) & 0xff) as u8,
-        ((now >> 32) & 0xff) as u8,
-        ((now >> 24) & 0xff) as u8,
-        ((now >> 16) & 0xff) as u8,
-        ((now >> 8) & 0xff) as u8,
-        (now & 0xff) as u8,
-    ];
-
-    // Convert to hex string (12 hex chars)
-    let time_hex = time_bytes
-        .iter()
-        .map(|b| format!("{:02x}", b))
-        .collect::<String>();
-
-    // Generate crypto-secure random base62 string (14 chars)
-    let mut rng = thread_rng();
-    let chars = "0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
-    let random_part: String = (0..14)
-        .map(|_| {
-            let idx = rng.gen_range(0..62);
-            chars.chars().nth(idx).unwrap()
+fn text_parts(text: &str, limit: usize) -> Result<Vec<SessionChatRequestPartsInner>> {
+    let chunks = split_text_into_chunks(text, limit)?;
+    if chunks.len() > 1 {
+        tracing::debug!("Split {} bytes of text into {} parts", text.len(), chunks.len());
+    }
+
+    Ok(chunks
+        .into_iter()
+        .map(|chunk| {
+            let text_part = TextPartInput {
+                id: Some(generate_id(IdPrefix::Part)),
+                text: chunk,
+                synthetic: None,
+                time: None,
+            };
+            SessionChatRequestPartsInner::Text(Box::new(text_part))
         })
-        .collect();
-
-    // Format: {prefix}_{12_hex_chars}{14_base62_chars}
-    format!("{}_{}{}", prefix.as_str(), time_hex, random_part)
+        .collect())
 }
 
 impl OpenCodeClient {
@@ -140,10 +171,7 @@ impl OpenCodeClient {
         config.base_path = base_url.to_string();
         config.client = Client::new();
 
-        Self {
-            config,
-            event_stream: None,
-        }
+        Self::with_configuration(config)
     }
 
     /// Create a new client with custom HTTP client
@@ -152,9 +180,34 @@ impl OpenCodeClient {
         config.base_path = base_url.to_string();
         config.client = client;
 
+        Self::with_configuration(config)
+    }
+
+    /// Create a client whose requests go to `api` instead of the server at
+    /// `base_url`, e.g. a `MockApi` in tests. Event streams still connect to
+    /// `base_url`.
+    pub fn with_api(base_url: &str, api: impl OpenCodeApi + 'static) -> Self {
+        Self {
+            api: Arc::new(api),
+            ..Self::new(base_url)
+        }
+    }
+
+    fn with_configuration(config: Configuration) -> Self {
         Self {
+            api: Arc::new(HttpApi::new(config.clone())),
             config,
-            event_stream: None,
+            options: ClientOptions::default(),
+            metrics: Arc::default(),
+            event_stream: Arc::default(),
+        }
+    }
+
+    /// Create a new client with custom client options
+    pub fn with_options(base_url: &str, options: ClientOptions) -> Self {
+        Self {
+            options,
+            ..Self::new(base_url)
         }
     }
 
@@ -183,6 +236,11 @@ impl OpenCodeClient {
         &self.config
     }
 
+    /// Get the client-side options for this client
+    pub fn options(&self) -> &ClientOptions {
+        &self.options
+    }
+
     /// Test connection to the server
     pub async fn test_connection(&self) -> Result<()> {
         match self.get_app_info().await {
@@ -201,45 +259,74 @@ impl OpenCodeClient {
     pub fn clone_client(&self) -> Self {
         Self {
             config: self.config.clone(),
-            event_stream: None, // Don't clone event stream
+            options: self.options.clone(),
+            metrics: self.metrics.clone(),
+            api: self.api.clone(),
+            event_stream: Arc::default(), // Don't share the event stream
+        }
+    }
+
+    /// Per-endpoint request counts and latencies over the recent requests
+    pub fn metrics_snapshot(&self) -> Vec<EndpointStats> {
+        match self.metrics.lock() {
+            Ok(metrics) => metrics.snapshot(),
+            Err(_) => Vec::new(),
         }
     }
 
+    /// Await an API request, recording how long it took and whether it failed
+    pub(crate) async fn timed<T, E>(
+        &self,
+        endpoint: &'static str,
+        request: impl Future<Output = std::result::Result<T, E>>,
+    ) -> Result<T>
+    where
+        OpenCodeError: From<E>,
+    {
+        let start = Instant::now();
+        let result = request.await.map_err(OpenCodeError::from);
+        let sample = RequestSample {
+            endpoint,
+            duration: start.elapsed(),
+            success: result.is_ok(),
+        };
+        if let Ok(mut metrics) = self.metrics.lock() {
+            metrics.record(sample);
+        }
+        result
+    }
+
     // App operations
 
     /// Get application information
     pub async fn get_app_info(&self) -> Result<App> {
-        default_api::app_period_get(&self.config)
+        self.timed("app.get", self.api.app_get())
             .await
-            .map_err(OpenCodeError::from)
     }
 
     /// Initialize the application
     pub async fn initialize_app(&self) -> Result<bool> {
-        default_api::app_period_init(&self.config)
+        self.timed("app.init", self.api.app_init())
             .await
-            .map_err(OpenCodeError::from)
     }
 
     // Configuration operations
 
     /// Get configuration information
     pub async fn get_config(&self) -> Result<Config> {
-        default_api::config_period_get(&self.config)
+        self.timed("config.get", self.api.config_get())
             .await
-            .map_err(OpenCodeError::from)
     }
 
     /// Get available providers
     pub async fn get_providers(&self) -> Result<ConfigProviders200Response> {
-        default_api::config_period_providers(&self.config)
+        self.timed("config.providers", self.api.config_providers())
             .await
-            .map_err(OpenCodeError::from)
     }
 
     /// Get available agent configurations (formerly modes)
     pub async fn get_agent_configs(&self) -> Result<ConfigAgent> {
-        let config = self.get_config().await?;
+        let config = self.timed("config.agent", self.api.config_get()).await?;
         Ok(config.agent.unwrap_or_default())
     }
 
@@ -247,29 +334,26 @@ impl OpenCodeClient {
 
     /// Create a new session
     pub async fn create_session(&self) -> Result<Session> {
-        let params = default_api::SessionPeriodCreateParams {
-            session_create_request: Some(SessionCreateRequest::new()),
-        };
-        default_api::session_period_create(&self.config, params)
+        self.timed("session.create", self.api.session_create(SessionCreateRequest::new()))
+            .await
+    }
+
+    /// Get a single session by ID
+    pub async fn get_session(&self, session_id: &str) -> Result<Session> {
+        self.timed("session.get", self.api.session_get(session_id))
             .await
-            .map_err(OpenCodeError::from)
     }
 
     /// List all sessions
     pub async fn list_sessions(&self) -> Result<Vec<Session>> {
-        default_api::session_period_list(&self.config)
+        self.timed("session.list", self.api.session_list())
             .await
-            .map_err(OpenCodeError::from)
     }
 
     /// Delete a session
     pub async fn delete_session(&self, session_id: &str) -> Result<bool> {
-        let params = default_api::SessionPeriodDeleteParams {
-            id: session_id.to_string(),
-        };
-        default_api::session_period_delete(&self.config, params)
+        self.timed("session.delete", self.api.session_delete(session_id))
             .await
-            .map_err(OpenCodeError::from)
     }
 
     /// Initialize a session (analyze app and create AGENTS.md)
@@ -286,44 +370,26 @@ impl OpenCodeClient {
             model_id: model_id.to_string(),
         };
 
-        let params = default_api::SessionPeriodInitParams {
-            id: session_id.to_string(),
-            session_init_request: Some(request),
-        };
-
-        default_api::session_period_init(&self.config, params)
+        self.timed("session.init", self.api.session_init(session_id, request))
             .await
-            .map_err(OpenCodeError::from)
     }
 
     /// Abort a session
     pub async fn abort_session(&self, session_id: &str) -> Result<bool> {
-        let params = default_api::SessionPeriodAbortParams {
-            id: session_id.to_string(),
-        };
-        default_api::session_period_abort(&self.config, params)
+        self.timed("session.abort", self.api.session_abort(session_id))
             .await
-            .map_err(OpenCodeError::from)
     }
 
     /// Share a session
     pub async fn share_session(&self, session_id: &str) -> Result<Session> {
-        let params = default_api::SessionPeriodShareParams {
-            id: session_id.to_string(),
-        };
-        default_api::session_period_share(&self.config, params)
+        self.timed("session.share", self.api.session_share(session_id))
             .await
-            .map_err(OpenCodeError::from)
     }
 
     /// Unshare a session
     pub async fn unshare_session(&self, session_id: &str) -> Result<Session> {
-        let params = default_api::SessionPeriodUnshareParams {
-            id: session_id.to_string(),
-        };
-        default_api::session_period_unshare(&self.config, params)
+        self.timed("session.unshare", self.api.session_unshare(session_id))
             .await
-            .map_err(OpenCodeError::from)
     }
 
     /// Summarize a session
@@ -338,14 +404,8 @@ impl OpenCodeClient {
             model_id: model_id.to_string(),
         };
 
-        let params = default_api::SessionPeriodSummarizeParams {
-            id: session_id.to_string(),
-            session_summarize_request: Some(request),
-        };
-
-        default_api::session_period_summarize(&self.config, params)
+        self.timed("session.summarize", self.api.session_summarize(session_id, request))
             .await
-            .map_err(OpenCodeError::from)
     }
 
     // Message operations
@@ -355,11 +415,10 @@ impl OpenCodeClient {
         &self,
         session_id: &str,
     ) -> Result<Vec<SessionMessages200ResponseInner>> {
-        let params = default_api::SessionPeriodMessagesParams {
-            id: session_id.to_string(),
-        };
-
-        match default_api::session_period_messages(&self.config, params).await {
+        match self
+            .timed("session.messages", self.api.session_messages(session_id))
+            .await
+        {
             Ok(messages) => {
                 tracing::info!(
                     "Retrieved {} messages for session {}",
@@ -370,11 +429,24 @@ impl OpenCodeClient {
             }
             Err(e) => {
                 tracing::error!("Failed to get messages for session {}: {}", session_id, e);
-                Err(OpenCodeError::from(e))
+                Err(e)
             }
         }
     }
 
+    /// Get the `limit` messages before `before_message_id`, or the latest
+    /// ones without it. The server has no paging, so the whole session is
+    /// fetched, but only the page is kept and handed to the caller.
+    pub async fn get_messages_page(
+        &self,
+        session_id: &str,
+        before_message_id: Option<&str>,
+        limit: usize,
+    ) -> Result<MessagePage> {
+        let messages = self.get_messages(session_id).await?;
+        Ok(page_messages(messages, before_message_id, limit))
+    }
+
     /// Send a user message to a session
     pub async fn send_user_message(
         &self,
@@ -383,41 +455,32 @@ impl OpenCodeClient {
         text: &str,
         provider_id: &str,
         model_id: &str,
-        mode: Option<&str>,
+        options: &ChatOptions,
     ) -> Result<AssistantMessage> {
         tracing::info!("Sending message to session {}", session_id);
 
-        let text_part = TextPartInput {
-            id: Some(generate_id(IdPrefix::Part)),
-            text: text.to_string(),
-            synthetic: None,
-            time: None,
-        };
-
-        let part = SessionChatRequestPartsInner::Text(Box::new(text_part));
+        let parts = text_parts(text, self.options.max_text_part_bytes)?;
         let request = SessionChatRequest {
             message_id: Some(message_id.to_string()),
             provider_id: provider_id.to_string(),
             model_id: model_id.to_string(),
-            agent: mode.map(|m| m.to_string()),
-            system: None,
-            tools: None,
-            parts: vec![part],
-        };
-
-        let params = default_api::SessionPeriodChatParams {
-            id: session_id.to_string(),
-            session_chat_request: Some(request),
+            agent: options.mode.clone(),
+            system: options.system.clone(),
+            tools: options.tools.clone(),
+            parts,
         };
 
-        match default_api::session_period_chat(&self.config, params).await {
+        match self
+            .timed("session.chat", self.api.session_chat(session_id, request))
+            .await
+        {
             Ok(message) => {
                 tracing::info!("Message sent successfully");
                 Ok(message)
             }
             Err(e) => {
                 tracing::error!("Failed to send message: {}", e);
-                Err(OpenCodeError::from(e))
+                Err(e)
             }
         }
     }
@@ -431,7 +494,7 @@ impl OpenCodeClient {
         attached_files: &[AttachedFile],
         provider_id: &str,
         model_id: &str,
-        mode: Option<&str>,
+        options: &ChatOptions,
     ) -> Result<AssistantMessage> {
         tracing::info!("Sending message with {} attachments to session {}", attached_files.len(), session_id);
 
@@ -441,9 +504,15 @@ impl OpenCodeClient {
             .model(model_id)
             .add_text_part(text);
         
-        if let Some(m) = mode {
+        if let Some(m) = &options.mode {
             builder = builder.mode(m);
         }
+        if let Some(system) = &options.system {
+            builder = builder.system(system);
+        }
+        if let Some(tools) = &options.tools {
+            builder = builder.tools(tools.clone());
+        }
         
         // Add file parts for each attachment
         for attached_file in attached_files {
@@ -454,63 +523,48 @@ impl OpenCodeClient {
             );
         }
         
-        builder.send(&self.config).await
+        let (session_id, request) = builder.into_request()?;
+        self.timed("session.chat", self.api.session_chat(&session_id, request))
+            .await
     }
 
     /// Create a message builder for complex message construction
     pub fn message_builder(&self, session_id: &str) -> MessageBuilder {
-        MessageBuilder::new(session_id)
+        MessageBuilder::new(session_id, self.options.max_text_part_bytes)
     }
 
     // File operations
 
     /// Read a file
     pub async fn read_file(&self, path: &str) -> Result<FileRead200Response> {
-        let params = default_api::FilePeriodReadParams {
-            path: path.to_string(),
-        };
-        default_api::file_period_read(&self.config, params)
+        self.timed("file.read", self.api.file_read(path))
             .await
-            .map_err(OpenCodeError::from)
     }
 
     /// Get file status
     pub async fn get_file_status(&self) -> Result<Vec<File>> {
-        default_api::file_period_status(&self.config)
+        self.timed("file.status", self.api.file_status())
             .await
-            .map_err(OpenCodeError::from)
     }
 
     // Search operations
 
     /// Find text in files
     pub async fn find_text(&self, pattern: &str) -> Result<Vec<FindText200ResponseInner>> {
-        let params = default_api::FindPeriodTextParams {
-            pattern: pattern.to_string(),
-        };
-        default_api::find_period_text(&self.config, params)
+        self.timed("find.text", self.api.find_text(pattern))
             .await
-            .map_err(OpenCodeError::from)
     }
 
     /// Find files
     pub async fn find_files(&self, query: &str) -> Result<Vec<String>> {
-        let params = default_api::FindPeriodFilesParams {
-            query: query.to_string(),
-        };
-        default_api::find_period_files(&self.config, params)
+        self.timed("find.files", self.api.find_files(query))
             .await
-            .map_err(OpenCodeError::from)
     }
 
     /// Find symbols
     pub async fn find_symbols(&self, query: &str) -> Result<Vec<Symbol>> {
-        let params = default_api::FindPeriodSymbolsParams {
-            query: query.to_string(),
-        };
-        default_api::find_period_symbols(&self.config, params)
+        self.timed("find.symbols", self.api.find_symbols(query))
             .await
-            .map_err(OpenCodeError::from)
     }
 
     // Logging
@@ -538,22 +592,30 @@ impl OpenCodeClient {
             extra,
         };
 
-        let params = default_api::AppPeriodLogParams {
-            app_log_request: Some(request),
-        };
-
-        default_api::app_period_log(&self.config, params)
+        self.timed("app.log", self.api.app_log(request))
             .await
-            .map_err(OpenCodeError::from)
     }
 
     // Event streaming
 
     /// Subscribe to real-time events
-    pub async fn subscribe_to_events(&mut self) -> Result<EventStreamHandle> {
-        let stream = EventStream::new(self.config.clone()).await?;
-        let handle = stream.handle();
-        self.event_stream = Some(Arc::new(RwLock::new(stream)));
+    ///
+    /// Every subscriber of this client and its clones reads from the same
+    /// connection, which is opened on first use and closed a while after the
+    /// last handle is dropped.
+    pub async fn subscribe_to_events(&self) -> Result<EventStreamHandle> {
+        let mut event_stream = self
+            .event_stream
+            .lock()
+            .map_err(|_| OpenCodeError::event_stream_error("event stream lock poisoned"))?;
+        if let Some(handle) = event_stream.as_ref().and_then(EventStream::handle) {
+            return Ok(handle);
+        }
+        let stream = EventStream::new(self.config.clone());
+        let handle = stream
+            .handle()
+            .ok_or_else(|| OpenCodeError::event_stream_error("event stream ended at start"))?;
+        *event_stream = Some(stream);
         Ok(handle)
     }
 }
@@ -572,18 +634,27 @@ pub struct MessageBuilder {
     provider_id: Option<String>,
     model_id: Option<String>,
     mode: Option<String>,
+    system: Option<String>,
+    tools: Option<HashMap<String, bool>>,
     parts: Vec<SessionChatRequestPartsInner>,
+    max_text_part_bytes: usize,
+    // Deferred so the builder chain stays infallible, reported by send()
+    error: Option<OpenCodeError>,
 }
 
 impl MessageBuilder {
-    fn new(session_id: &str) -> Self {
+    fn new(session_id: &str, max_text_part_bytes: usize) -> Self {
         Self {
             session_id: session_id.to_string(),
             message_id: None,
             provider_id: None,
             model_id: None,
             mode: None,
+            system: None,
+            tools: None,
             parts: Vec::new(),
+            max_text_part_bytes,
+            error: None,
         }
     }
 
@@ -611,16 +682,26 @@ impl MessageBuilder {
         self
     }
 
-    /// Add a text part to the message
+    /// Set the system prompt for this message
+    pub fn system(mut self, text: &str) -> Self {
+        self.system = Some(text.to_string());
+        self
+    }
+
+    /// Enable or disable tools by name for this message
+    pub fn tools(mut self, tools: HashMap<String, bool>) -> Self {
+        self.tools = Some(tools);
+        self
+    }
+
+    /// Add a text part to the message, split into several parts if it exceeds the limit
     pub fn add_text_part(mut self, text: &str) -> Self {
-        let text_part = TextPartInput {
-            id: Some(generate_id(IdPrefix::Part)),
-            text: text.to_string(),
-            synthetic: None,
-            time: None,
-        };
-        let part = SessionChatRequestPartsInner::Text(Box::new(text_part));
-        self.parts.push(part);
+        match text_parts(text, self.max_text_part_bytes) {
+            Ok(parts) => self.parts.extend(parts),
+            Err(e) => {
+                self.error.get_or_insert(e);
+            }
+        }
         self
     }
 
@@ -640,6 +721,23 @@ impl MessageBuilder {
 
     /// Send the message
     pub async fn send(self, config: &Configuration) -> Result<AssistantMessage> {
+        let (session_id, request) = self.into_request()?;
+        let params = default_api::SessionPeriodChatParams {
+            id: session_id,
+            session_chat_request: Some(request),
+        };
+
+        default_api::session_period_chat(config, params)
+            .await
+            .map_err(OpenCodeError::from)
+    }
+
+    /// The session ID and the request to send to it
+    fn into_request(self) -> Result<(String, SessionChatRequest)> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+
         let request = SessionChatRequest {
             message_id: Some(
                 self.message_id
@@ -652,18 +750,149 @@ impl MessageBuilder {
                 .model_id
                 .ok_or_else(|| OpenCodeError::invalid_request("model_id is required"))?,
             agent: self.mode,
-            system: None,
-            tools: None,
+            system: self.system,
+            tools: self.tools,
             parts: self.parts,
         };
+        Ok((self.session_id, request))
+    }
+}
 
-        let params = default_api::SessionPeriodChatParams {
-            id: self.session_id,
-            session_chat_request: Some(request),
-        };
-
-        default_api::session_period_chat(config, params)
-            .await
-            .map_err(OpenCodeError::from)
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_text_under_and_at_limit() {
+        let text = "a".repeat(16);
+        assert_eq!(split_text_into_chunks(&text, 16).unwrap(), vec![text.clone()]);
+        assert_eq!(split_text_into_chunks("", 16).unwrap(), vec!["".to_string()]);
+
+        // Lines that exactly fill the limit stay together
+        let text = "1234567\n1234567\n";
+        assert_eq!(
+            split_text_into_chunks(text, 8).unwrap(),
+            vec!["1234567\n", "1234567\n"]
+        );
+    }
+
+    #[test]
+    fn test_split_text_on_line_boundaries() {
+        let text = "one\ntwo\nthree\nfour";
+        let chunks = split_text_into_chunks(text, 9).unwrap();
+        assert_eq!(chunks, vec!["one\ntwo\n", "three\n", "four"]);
+        assert!(chunks.iter().all(|c| c.len() <= 9));
+        assert_eq!(chunks.concat(), text);
+    }
+
+    #[test]
+    fn test_split_text_multibyte_at_edge() {
+        // "é" is two bytes, so this line is 8 bytes despite being 5 chars
+        let text = "ab\néééé\n";
+        assert_eq!(split_text_into_chunks(text, 9).unwrap(), vec!["ab\n", "éééé\n"]);
+
+        let error = split_text_into_chunks(text, 8).unwrap_err();
+        assert_eq!(error, OpenCodeError::text_part_too_large(9, 8));
+    }
+
+    #[test]
+    fn test_split_text_single_oversized_line() {
+        let text = format!("short\n{}\nshort", "x".repeat(20));
+        let error = split_text_into_chunks(&text, 10).unwrap_err();
+        assert_eq!(error, OpenCodeError::text_part_too_large(21, 10));
+        assert!(error.is_client_error());
+    }
+
+    #[test]
+    fn test_text_parts_have_unique_ids() {
+        let text = "line\n".repeat(10);
+        let parts = text_parts(&text, 10).unwrap();
+        assert_eq!(parts.len(), 5);
+
+        let ids: std::collections::HashSet<_> = parts
+            .iter()
+            .map(|part| match part {
+                SessionChatRequestPartsInner::Text(text_part) => text_part.id.clone().unwrap(),
+                _ => panic!("expected text part"),
+            })
+            .collect();
+        assert_eq!(ids.len(), 5);
+    }
+
+    fn chat_builder() -> MessageBuilder {
+        MessageBuilder::new("ses_1", DEFAULT_MAX_TEXT_PART_BYTES)
+            .message_id("msg_1")
+            .provider("anthropic")
+            .model("claude-sonnet-4")
+            .add_text_part("hello")
+    }
+
+    #[test]
+    fn test_message_builder_serializes_system_and_tools() {
+        let tools = HashMap::from([("bash".to_string(), false)]);
+        let (session_id, request) = chat_builder()
+            .system("Answer in French")
+            .tools(tools)
+            .into_request()
+            .unwrap();
+        assert_eq!(session_id, "ses_1");
+
+        let body = serde_json::to_value(&request).unwrap();
+        assert_eq!(body["system"], "Answer in French");
+        assert_eq!(body["tools"], serde_json::json!({ "bash": false }));
+    }
+
+    #[test]
+    fn test_message_builder_omits_unset_overrides() {
+        let (_, request) = chat_builder().into_request().unwrap();
+        let body = serde_json::to_value(&request).unwrap();
+        assert!(body.get("system").is_none());
+        assert!(body.get("tools").is_none());
+    }
+
+    fn message(id: &str) -> SessionMessages200ResponseInner {
+        let info = UserMessage::new(
+            id.to_string(),
+            "ses_1".to_string(),
+            user_message::Role::User,
+            UserMessageTime::new(0.0),
+        );
+        SessionMessages200ResponseInner::new(Message::User(Box::new(info)), vec![])
+    }
+
+    fn page_ids(page: &MessagePage) -> Vec<&str> {
+        page.messages
+            .iter()
+            .map(|message| message_id(&message.info))
+            .collect()
+    }
+
+    #[test]
+    fn test_page_messages() {
+        let messages: Vec<_> = ["msg_1", "msg_2", "msg_3", "msg_4", "msg_5"]
+            .into_iter()
+            .map(message)
+            .collect();
+
+        let latest = page_messages(messages.clone(), None, 2);
+        assert_eq!(page_ids(&latest), vec!["msg_4", "msg_5"]);
+        assert!(latest.has_earlier);
+
+        let earlier = page_messages(messages.clone(), Some("msg_4"), 2);
+        assert_eq!(page_ids(&earlier), vec!["msg_2", "msg_3"]);
+        assert!(earlier.has_earlier);
+
+        let first = page_messages(messages.clone(), Some("msg_2"), 2);
+        assert_eq!(page_ids(&first), vec!["msg_1"]);
+        assert!(!first.has_earlier);
+
+        let all = page_messages(messages.clone(), None, 10);
+        assert_eq!(all.messages.len(), 5);
+        assert!(!all.has_earlier);
+
+        // Not the latest page again when the anchor is gone
+        let missing = page_messages(messages, Some("msg_9"), 2);
+        assert!(missing.messages.is_empty());
+        assert!(!missing.has_earlier);
     }
 }
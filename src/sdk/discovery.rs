@@ -4,8 +4,22 @@
 //! OpenCode server instances through various methods.
 
 use crate::sdk::{error::{OpenCodeError, Result}, OpenCodeClient};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 use tokio::process::Command;
+use tokio::task::JoinSet;
+
+/// Ports `opencode serve` picks from when none is given
+pub const DEFAULT_PORT_RANGE: RangeInclusive<u16> = 4096..=4105;
+
+/// Port the development server is started on
+const DEV_SERVER_PORT: u16 = 8080;
+
+/// Environment variable naming the server to use, when `server_url` isn't set
+pub const SERVER_URL_ENV: &str = "OPENCODE_SERVER_URL";
 
 /// Configuration for server discovery
 #[derive(Debug, Clone)]
@@ -16,6 +30,14 @@ pub struct DiscoveryConfig {
     pub max_retries: u32,
     /// Base delay between retries (exponential backoff)
     pub retry_delay: Duration,
+    /// Local ports probed for servers, on top of those found in the process list
+    pub port_range: RangeInclusive<u16>,
+    /// Timeout for each candidate probe, which is tried only once
+    pub probe_timeout: Duration,
+    /// Project to prefer when several servers are running
+    pub preferred_project_path: Option<PathBuf>,
+    /// Server tried before any other, instead of `$OPENCODE_SERVER_URL`
+    pub server_url: Option<String>,
 }
 
 impl Default for DiscoveryConfig {
@@ -24,41 +46,152 @@ impl Default for DiscoveryConfig {
             validation_timeout: Duration::from_secs(5),
             max_retries: 3,
             retry_delay: Duration::from_millis(500),
+            port_range: DEFAULT_PORT_RANGE,
+            probe_timeout: Duration::from_millis(500),
+            preferred_project_path: None,
+            server_url: None,
         }
     }
 }
 
+/// A running server and the project it was started in
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ServerCandidate {
+    pub url: String,
+    /// Working directory of the server
+    pub project_path: String,
+    /// Version of the server, as recorded on its most recent session
+    pub version: Option<String>,
+}
+
+/// Result of discovery when the caller can let the user choose
+#[derive(Debug, Clone, PartialEq)]
+pub enum DiscoveryOutcome {
+    /// The configured server, or the only server for the preferred project
+    Server(String),
+    /// Servers are running but none is for the preferred project, or several
+    /// are, so the user chooses even when there's only one
+    Ambiguous(Vec<ServerCandidate>),
+}
+
 /// Discover a running OpenCode server instance
 pub async fn discover_opencode_server() -> Result<String> {
     discover_opencode_server_with_config(DiscoveryConfig::default()).await
 }
 
-/// Discover a running OpenCode server instance with custom configuration
+/// Discover a running OpenCode server instance with custom configuration.
+/// When several servers are running and none matches the preferred project,
+/// the first one is used; see `discover_or_list_servers` to choose instead.
 pub async fn discover_opencode_server_with_config(config: DiscoveryConfig) -> Result<String> {
-    // 1. Check environment variable
-    if let Ok(url) = std::env::var("OPENCODE_SERVER_URL") {
-        if validate_server_with_config(&url, &config).await.is_ok() {
-            return Ok(url);
-        }
+    match discover_or_list_servers(&config).await? {
+        DiscoveryOutcome::Server(url) => Ok(url),
+        DiscoveryOutcome::Ambiguous(candidates) => candidates
+            .into_iter()
+            .next()
+            .map(|candidate| candidate.url)
+            .ok_or(OpenCodeError::ServerNotFound),
     }
+}
 
-    // 2. Process detection (platform-specific)
-    if let Ok(url) = detect_running_process().await {
-        if validate_server_with_config(&url, &config).await.is_ok() {
-            return Ok(url);
+/// Discover a server, or every running server when there's no clear choice
+pub async fn discover_or_list_servers(config: &DiscoveryConfig) -> Result<DiscoveryOutcome> {
+    // 1. The configured server, or else the environment variable
+    let configured = config
+        .server_url
+        .clone()
+        .or_else(|| std::env::var(SERVER_URL_ENV).ok());
+    if let Some(url) = configured {
+        match validate_server_with_config(&url, config).await {
+            Ok(()) => return Ok(DiscoveryOutcome::Server(url)),
+            Err(error) => tracing::warn!("Configured server {} not reachable: {}", url, error),
         }
     }
 
+    // 2. Running servers, from the process list and the port range
+    let candidates = discover_opencode_servers(config).await;
+    if let Some(candidate) = choose_candidate(&candidates, config.preferred_project_path.as_deref()) {
+        return Ok(DiscoveryOutcome::Server(candidate.url.clone()));
+    }
+    if !candidates.is_empty() {
+        return Ok(DiscoveryOutcome::Ambiguous(candidates));
+    }
+
     // 3. In development mode, try to start the server automatically
     if is_development_mode() {
-        if let Ok(url) = start_server_and_discover(&config).await {
-            return Ok(url);
+        if let Ok(url) = start_server_and_discover(config).await {
+            return Ok(DiscoveryOutcome::Server(url));
         }
     }
 
     Err(OpenCodeError::ServerNotFound)
 }
 
+/// Find every reachable server, ordered by URL. Servers are found through
+/// the process list and the port range; lock files aren't probed.
+pub async fn discover_opencode_servers(config: &DiscoveryConfig) -> Vec<ServerCandidate> {
+    let mut urls: BTreeSet<String> = detect_running_processes().await.into_iter().collect();
+    for port in config.port_range.clone().chain([DEV_SERVER_PORT]) {
+        urls.insert(format!("http://127.0.0.1:{}", port));
+    }
+
+    let mut probes = JoinSet::new();
+    for url in urls {
+        let timeout = config.probe_timeout;
+        probes.spawn(async move { probe_server(url, timeout).await });
+    }
+
+    let mut candidates = Vec::new();
+    while let Some(result) = probes.join_next().await {
+        if let Ok(Some(candidate)) = result {
+            candidates.push(candidate);
+        }
+    }
+    candidates.sort_by(|a, b| a.url.cmp(&b.url));
+    candidates
+}
+
+/// Ask a possible server which project it's serving
+async fn probe_server(url: String, timeout: Duration) -> Option<ServerCandidate> {
+    let client = OpenCodeClient::new(&url);
+    let app = tokio::time::timeout(timeout, client.get_app_info()).await.ok()?.ok()?;
+    // The app endpoint doesn't report a version, but sessions record the
+    // version of the server that last updated them
+    let version = match tokio::time::timeout(timeout, client.list_sessions()).await {
+        Ok(Ok(sessions)) => sessions
+            .into_iter()
+            .max_by(|a, b| a.time.updated.total_cmp(&b.time.updated))
+            .map(|session| session.version),
+        _ => None,
+    };
+    Some(ServerCandidate {
+        url,
+        project_path: app.path.cwd.clone(),
+        version,
+    })
+}
+
+/// The candidate serving `project_path`, if exactly one does. A server started
+/// in a parent directory also serves it, and the closest such server wins.
+pub fn choose_candidate<'a>(
+    candidates: &'a [ServerCandidate],
+    project_path: Option<&Path>,
+) -> Option<&'a ServerCandidate> {
+    let project_path = project_path?;
+    let depth = |candidate: &ServerCandidate| Path::new(&candidate.project_path).components().count();
+    let mut matches: Vec<&ServerCandidate> = candidates
+        .iter()
+        .filter(|candidate| project_path.starts_with(&candidate.project_path))
+        .collect();
+    matches.sort_by_key(|candidate| std::cmp::Reverse(depth(candidate)));
+
+    match matches.as_slice() {
+        [] => None,
+        [only] => Some(only),
+        // Two servers for the same directory leave the choice to the user
+        [closest, next, ..] => (depth(closest) > depth(next)).then_some(*closest),
+    }
+}
+
 /// Validate that a server is running and accessible at the given URL
 pub async fn validate_server(url: &str) -> Result<()> {
     validate_server_with_config(url, &DiscoveryConfig::default()).await
@@ -91,28 +224,22 @@ pub async fn validate_server_with_config(url: &str, config: &DiscoveryConfig) ->
     Err(OpenCodeError::ConnectionTimeout)
 }
 
-/// Detect running OpenCode processes and extract server URLs
-async fn detect_running_process() -> Result<String> {
+/// Detect running OpenCode processes and extract their server URLs
+async fn detect_running_processes() -> Vec<String> {
     // Try to find opencode serve processes
-    let output = Command::new("ps")
-        .args(&["aux"])
-        .output()
-        .await
-        .map_err(|_| OpenCodeError::ProcessDetectionFailed)?;
+    let output = match Command::new("ps").args(["aux"]).output().await {
+        Ok(output) => output,
+        Err(_) => return Vec::new(),
+    };
 
     let stdout = String::from_utf8_lossy(&output.stdout);
-    
-    // Look for opencode serve processes
-    for line in stdout.lines() {
-        if line.contains("opencode") && line.contains("serve") {
-            // Extract port from command line arguments
-            if let Some(url) = extract_server_url_from_process_line(line) {
-                return Ok(url);
-            }
-        }
-    }
 
-    Err(OpenCodeError::ProcessDetectionFailed)
+    // Look for opencode serve processes, extracting the port from their arguments
+    stdout
+        .lines()
+        .filter(|line| line.contains("opencode") && line.contains("serve"))
+        .filter_map(extract_server_url_from_process_line)
+        .collect()
 }
 
 /// Extract server URL from a process command line
@@ -155,7 +282,7 @@ fn is_development_mode() -> bool {
 async fn start_server_and_discover(_config: &DiscoveryConfig) -> Result<String> {
     // Default server configuration
     let hostname = "127.0.0.1";
-    let port = 8080u16;
+    let port = DEV_SERVER_PORT;
     let server_url = format!("http://{}:{}", hostname, port);
     
     // Try local repo command first
@@ -187,6 +314,7 @@ async fn start_server_and_discover(_config: &DiscoveryConfig) -> Result<String>
         validation_timeout: Duration::from_secs(10),
         max_retries: 10,
         retry_delay: Duration::from_millis(1000),
+        ..DiscoveryConfig::default()
     };
     
     // Try to validate the server is running
@@ -238,4 +366,45 @@ mod tests {
             assert!(!is_development_mode());
         }
     }
-}
\ No newline at end of file
+
+    fn candidate(port: u16, project_path: &str) -> ServerCandidate {
+        ServerCandidate {
+            url: format!("http://127.0.0.1:{}", port),
+            project_path: project_path.to_string(),
+            version: None,
+        }
+    }
+
+    #[test]
+    fn test_choose_candidate_for_project() {
+        let candidates = vec![
+            candidate(4096, "/home/me/api"),
+            candidate(4097, "/home/me/web"),
+        ];
+        let chosen = choose_candidate(&candidates, Some(Path::new("/home/me/web/src")));
+        assert_eq!(chosen, Some(&candidates[1]));
+
+        // A sibling whose name shares a prefix isn't a match
+        assert_eq!(choose_candidate(&candidates, Some(Path::new("/home/me/webapp"))), None);
+        assert_eq!(choose_candidate(&candidates, Some(Path::new("/tmp"))), None);
+        assert_eq!(choose_candidate(&candidates, None), None);
+    }
+
+    #[test]
+    fn test_choose_closest_candidate() {
+        let candidates = vec![
+            candidate(4096, "/repo"),
+            candidate(4097, "/repo/packages/app"),
+        ];
+        let chosen = choose_candidate(&candidates, Some(Path::new("/repo/packages/app")));
+        assert_eq!(chosen, Some(&candidates[1]));
+        let chosen = choose_candidate(&candidates, Some(Path::new("/repo/docs")));
+        assert_eq!(chosen, Some(&candidates[0]));
+    }
+
+    #[test]
+    fn test_choose_candidate_ambiguous() {
+        let candidates = vec![candidate(4096, "/repo"), candidate(4097, "/repo")];
+        assert_eq!(choose_candidate(&candidates, Some(Path::new("/repo"))), None);
+    }
+}
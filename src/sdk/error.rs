@@ -33,6 +33,9 @@ pub enum OpenCodeError {
     /// Event stream error
     EventStream(String),
 
+    /// An event stream handle fell behind and missed this many events
+    EventStreamLagged { missed: u64 },
+
     /// Configuration error
     Configuration(String),
 
@@ -57,6 +60,9 @@ pub enum OpenCodeError {
     /// Server start failed
     ServerStartFailed(String),
 
+    /// A single line of outbound text exceeds the text part limit and can't be split
+    TextPartTooLarge { line_bytes: usize, limit: usize },
+
     /// Generic error for unexpected situations
     Unexpected(String),
 }
@@ -73,6 +79,9 @@ impl fmt::Display for OpenCodeError {
                 write!(f, "Message not found: {} in session {}", message_id, session_id)
             }
             Self::EventStream(msg) => write!(f, "Event stream error: {}", msg),
+            Self::EventStreamLagged { missed } => {
+                write!(f, "Event stream fell behind, {} events missed", missed)
+            }
             Self::Configuration(msg) => write!(f, "Configuration error: {}", msg),
             Self::InvalidRequest(msg) => write!(f, "Invalid request: {}", msg),
             Self::Timeout(msg) => write!(f, "Request timeout: {}", msg),
@@ -81,6 +90,11 @@ impl fmt::Display for OpenCodeError {
             Self::ProcessDetectionFailed => write!(f, "Failed to detect running OpenCode processes"),
             Self::SessionPersistence(msg) => write!(f, "Session persistence error: {}", msg),
             Self::ServerStartFailed(msg) => write!(f, "Failed to start OpenCode server: {}", msg),
+            Self::TextPartTooLarge { line_bytes, limit } => write!(
+                f,
+                "Text part too large: a single line is {} bytes, limit is {} bytes",
+                line_bytes, limit
+            ),
             Self::Unexpected(msg) => write!(f, "Unexpected error: {}", msg),
         }
     }
@@ -123,6 +137,7 @@ impl Clone for OpenCodeError {
                 message_id: message_id.clone() 
             },
             Self::EventStream(msg) => Self::EventStream(msg.clone()),
+            Self::EventStreamLagged { missed } => Self::EventStreamLagged { missed: *missed },
             Self::Configuration(msg) => Self::Configuration(msg.clone()),
             Self::InvalidRequest(msg) => Self::InvalidRequest(msg.clone()),
             Self::Timeout(msg) => Self::Timeout(msg.clone()),
@@ -131,6 +146,10 @@ impl Clone for OpenCodeError {
             Self::ProcessDetectionFailed => Self::ProcessDetectionFailed,
             Self::SessionPersistence(msg) => Self::SessionPersistence(msg.clone()),
             Self::ServerStartFailed(msg) => Self::ServerStartFailed(msg.clone()),
+            Self::TextPartTooLarge { line_bytes, limit } => Self::TextPartTooLarge {
+                line_bytes: *line_bytes,
+                limit: *limit,
+            },
             Self::Unexpected(msg) => Self::Unexpected(msg.clone()),
         }
     }
@@ -148,6 +167,7 @@ impl PartialEq for OpenCodeError {
             (Self::SessionNotFound { session_id: a }, Self::SessionNotFound { session_id: b }) => a == b,
             (Self::MessageNotFound { session_id: s1, message_id: m1 }, Self::MessageNotFound { session_id: s2, message_id: m2 }) => s1 == s2 && m1 == m2,
             (Self::EventStream(a), Self::EventStream(b)) => a == b,
+            (Self::EventStreamLagged { missed: a }, Self::EventStreamLagged { missed: b }) => a == b,
             (Self::Configuration(a), Self::Configuration(b)) => a == b,
             (Self::InvalidRequest(a), Self::InvalidRequest(b)) => a == b,
             (Self::Timeout(a), Self::Timeout(b)) => a == b,
@@ -156,6 +176,10 @@ impl PartialEq for OpenCodeError {
             (Self::ProcessDetectionFailed, Self::ProcessDetectionFailed) => true,
             (Self::SessionPersistence(a), Self::SessionPersistence(b)) => a == b,
             (Self::ServerStartFailed(a), Self::ServerStartFailed(b)) => a == b,
+            (
+                Self::TextPartTooLarge { line_bytes: l1, limit: m1 },
+                Self::TextPartTooLarge { line_bytes: l2, limit: m2 },
+            ) => l1 == l2 && m1 == m2,
             (Self::Unexpected(a), Self::Unexpected(b)) => a == b,
             _ => false,
         }
@@ -221,6 +245,11 @@ impl OpenCodeError {
         Self::ServerStartFailed(message.into())
     }
 
+    /// Create a text part too large error
+    pub fn text_part_too_large(line_bytes: usize, limit: usize) -> Self {
+        Self::TextPartTooLarge { line_bytes, limit }
+    }
+
     /// Check if this error is retryable
     pub fn is_retryable(&self) -> bool {
         match self {
@@ -243,6 +272,17 @@ impl OpenCodeError {
             Self::SessionNotFound { .. } => true,
             Self::MessageNotFound { .. } => true,
             Self::InvalidRequest(_) => true,
+            Self::TextPartTooLarge { .. } => true,
+            _ => false,
+        }
+    }
+
+    /// Check if this error means the requested resource no longer exists
+    pub fn is_not_found(&self) -> bool {
+        match self {
+            Self::Api { status, .. } => *status == 404,
+            Self::SessionNotFound { .. } => true,
+            Self::MessageNotFound { .. } => true,
             _ => false,
         }
     }
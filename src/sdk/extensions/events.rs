@@ -1,15 +1,54 @@
-//! Event stream handling for real-time updates
+//! Event stream for real-time updates
+//!
+//! One SSE connection to the server's `/event` endpoint is fanned out to any
+//! number of [`EventStreamHandle`]s through a broadcast channel. Each handle
+//! reads at its own pace from a shared buffer of the latest events. A handle
+//! that falls more than the buffer behind misses the oldest events it hadn't
+//! read; its next read is an [`OpenCodeError::EventStreamLagged`] giving how
+//! many, after which it carries on with the oldest event still held. Once the
+//! last handle is dropped, the connection is kept for a grace period in case
+//! someone subscribes again, then closed.
+//!
+//! An event these models can't read, whether of a type added to a newer
+//! server or cut short on the way, is logged and skipped rather than ending
+//! the connection; only a failing connection is reconnected. Skipped events of
+//! an unknown type are counted in [`EventStreamHandle::unknown_event_count`].
 
 use crate::sdk::error::{OpenCodeError, Result};
 use opencode_sdk::{apis::configuration::Configuration, models::Event};
-use std::time::Duration;
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+use std::time::{Duration, Instant};
 use tokio::sync::broadcast;
 
+/// Events held for the handles, so the most a handle can fall behind
+const EVENT_BUFFER_SIZE: usize = 1000;
+
+/// How long the connection outlives its last handle
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(5);
+
 /// Event stream for receiving real-time updates from the OpenCode server
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct EventStream {
-    sender: broadcast::Sender<Event>,
-    _handle: tokio::task::JoinHandle<()>,
+    // Weak, so handles see the stream end once the connection task stops
+    sender: broadcast::WeakSender<Event>,
+    // Events of a type these models don't know, skipped since connecting
+    unknown_events: Arc<AtomicUsize>,
+}
+
+/// What the `data:` line of an SSE event held
+#[derive(Debug, PartialEq)]
+enum SseData {
+    Event(Event),
+    /// JSON not matching any event these models know, most likely of a type
+    /// added to a newer server
+    Unknown {
+        event_type: String,
+    },
+    /// Not an event at all, as when cut short by a buffering proxy
+    Malformed,
 }
 
 fn get_event_name(event: &Event) -> &'static str {
@@ -35,33 +74,62 @@ fn get_event_name(event: &Event) -> &'static str {
 }
 
 impl EventStream {
-    /// Create a new event stream
-    pub async fn new(config: Configuration) -> Result<Self> {
-        let (sender, _) = broadcast::channel(1000);
-
-        let sender_clone = sender.clone();
-        let config_clone = config.clone();
+    /// Create a new event stream, connecting in the background
+    pub fn new(config: Configuration) -> Self {
+        Self::start(config, EVENT_BUFFER_SIZE, SHUTDOWN_GRACE)
+    }
 
-        // Start the polling task
-        let handle = tokio::spawn(async move {
-            Self::poll_events(config_clone, sender_clone).await;
+    /// Connect in the background, holding `capacity` events for the handles
+    /// and closing `grace` after the last handle is dropped
+    pub(crate) fn start(config: Configuration, capacity: usize, grace: Duration) -> Self {
+        let (sender, _) = broadcast::channel(capacity);
+        let weak_sender = sender.downgrade();
+        let unknown_events = Arc::new(AtomicUsize::new(0));
+        let counter = unknown_events.clone();
+        tokio::spawn(async move {
+            tokio::select! {
+                _ = Self::poll_events(config, &sender, &counter) => {}
+                _ = Self::until_unused(&sender, grace) => {
+                    tracing::debug!("No event stream handles left, closing the connection");
+                }
+            }
         });
+        Self {
+            sender: weak_sender,
+            unknown_events,
+        }
+    }
 
-        Ok(Self {
-            sender,
-            _handle: handle,
+    /// Get a handle to subscribe to events, None once the connection has
+    /// been closed
+    pub fn handle(&self) -> Option<EventStreamHandle> {
+        let sender = self.sender.upgrade()?;
+        Some(EventStreamHandle {
+            receiver: sender.subscribe(),
+            unknown_events: self.unknown_events.clone(),
         })
     }
 
-    /// Get a handle to subscribe to events
-    pub fn handle(&self) -> EventStreamHandle {
-        EventStreamHandle {
-            receiver: self.sender.subscribe(),
+    /// Resolves once no handle has been left for `grace`
+    async fn until_unused(sender: &broadcast::Sender<Event>, grace: Duration) {
+        let check_interval = (grace / 4).max(Duration::from_millis(10));
+        let mut unused_since: Option<Instant> = None;
+        loop {
+            tokio::time::sleep(check_interval).await;
+            if sender.receiver_count() > 0 {
+                unused_since = None;
+            } else if unused_since.get_or_insert_with(Instant::now).elapsed() >= grace {
+                return;
+            }
         }
     }
 
     /// Internal SSE stream processing for events
-    async fn poll_events(config: Configuration, sender: broadcast::Sender<Event>) {
+    async fn poll_events(
+        config: Configuration,
+        sender: &broadcast::Sender<Event>,
+        unknown_events: &AtomicUsize,
+    ) {
         let mut consecutive_errors = 0;
         const MAX_CONSECUTIVE_ERRORS: u32 = 10;
 
@@ -69,12 +137,13 @@ impl EventStream {
             tracing::debug!("Starting SSE stream connection to /event");
 
             match Self::connect_sse_stream(&config).await {
-                Ok(()) => {
+                Ok(response) => {
                     consecutive_errors = 0;
                     tracing::info!("SSE stream connected successfully");
 
                     // Process the SSE stream
-                    if let Err(e) = Self::process_sse_stream(&config, &sender).await {
+                    if let Err(e) = Self::process_sse_stream(response, sender, unknown_events).await
+                    {
                         tracing::warn!("SSE stream processing error: {}", e);
                         consecutive_errors += 1;
                     }
@@ -100,12 +169,11 @@ impl EventStream {
         }
     }
 
-    /// Connect to SSE stream and verify connection
-    async fn connect_sse_stream(config: &Configuration) -> Result<()> {
+    /// Connect to SSE stream and verify it is one
+    async fn connect_sse_stream(config: &Configuration) -> Result<reqwest::Response> {
         let event_url = format!("{}/event", config.base_path);
         let client = &config.client;
 
-        // Test connection first
         let response = client.get(&event_url).send().await.map_err(|e| {
             OpenCodeError::event_stream_error(format!("Failed to connect to SSE stream: {}", e))
         })?;
@@ -131,40 +199,48 @@ impl EventStream {
             )));
         }
 
-        Ok(())
+        Ok(response)
     }
 
-    /// Process the SSE stream and parse events
+    /// Process the SSE stream and parse events, failing only when the
+    /// connection does
     async fn process_sse_stream(
-        config: &Configuration,
+        mut response: reqwest::Response,
         sender: &broadcast::Sender<Event>,
+        unknown_events: &AtomicUsize,
     ) -> Result<()> {
-        let event_url = format!("{}/event", config.base_path);
-        let client = &config.client;
-
-        let mut response = client.get(&event_url).send().await.map_err(|e| {
-            OpenCodeError::event_stream_error(format!("Failed to get SSE stream: {}", e))
-        })?;
-
-        // Process the streaming response
+        // A chunk can end partway through a line, even a character, so the
+        // rest of it waits for the next chunk
+        let mut pending = Vec::new();
         while let Some(chunk) = response.chunk().await.map_err(|e| {
             OpenCodeError::event_stream_error(format!("Failed to read SSE chunk: {}", e))
         })? {
+            pending.extend_from_slice(&chunk);
+            let Some(end) = pending.iter().rposition(|&byte| byte == b'\n') else {
+                continue;
+            };
+            let lines: Vec<u8> = pending.drain(..=end).collect();
+
             // Parse SSE format: "data: {JSON}\n"
-            let chunk_str = std::str::from_utf8(&chunk).map_err(|e| {
-                OpenCodeError::event_stream_error(format!("Invalid UTF-8 in SSE stream: {}", e))
-            })?;
-
-            for line in chunk_str.lines() {
-                if let Some(event) = Self::parse_sse_line(line)? {
-                    tracing::info!("Parsed SSE event: {:?}", get_event_name(&event));
-                    tracing::debug!("Parsed SSE event: {:?}", event);
-
-                    // Send event to all subscribers
-                    if sender.send(event).is_err() {
-                        tracing::debug!("No more receivers, stopping SSE stream");
-                        return Ok(());
+            for line in String::from_utf8_lossy(&lines).lines() {
+                match Self::parse_sse_line(line) {
+                    Some(SseData::Event(event)) => {
+                        tracing::info!("Parsed SSE event: {:?}", get_event_name(&event));
+                        tracing::debug!("Parsed SSE event: {:?}", event);
+
+                        // Send event to all subscribers. With none right now it's
+                        // dropped, and the connection waits out the grace period.
+                        let _ = sender.send(event);
+                    }
+                    Some(SseData::Unknown { event_type }) => {
+                        tracing::warn!("Skipped SSE event of unknown type {:?}", event_type);
+                        tracing::debug!("Skipped SSE event: {}", line);
+                        unknown_events.fetch_add(1, Ordering::Relaxed);
                     }
+                    Some(SseData::Malformed) => {
+                        tracing::warn!("Skipped malformed SSE event: {}", line);
+                    }
+                    None => {}
                 }
             }
         }
@@ -173,22 +249,30 @@ impl EventStream {
         Ok(())
     }
 
-    /// Parse a single SSE line and extract JSON event if present
-    fn parse_sse_line(line: &str) -> Result<Option<Event>> {
+    /// Parse a single SSE line, None unless it holds data
+    fn parse_sse_line(line: &str) -> Option<SseData> {
         let trimmed = line.trim();
 
-        // SSE format: "data: {JSON}"
-        if let Some(data) = trimmed.strip_prefix("data: ") {
-            if !data.trim().is_empty() {
-                let event: Event = serde_json::from_str(data).map_err(|e| {
-                    OpenCodeError::event_stream_error(format!("Failed to parse SSE JSON: {}", e))
-                })?;
-                return Ok(Some(event));
-            }
+        // SSE format: "data: {JSON}", ignoring other SSE lines (comments,
+        // event types, etc.)
+        let data = trimmed.strip_prefix("data: ")?.trim();
+        if data.is_empty() {
+            return None;
         }
 
-        // Ignore other SSE lines (comments, event types, etc.)
-        Ok(None)
+        // Read as any JSON first, so an event that isn't one of ours can be
+        // told apart from one that got cut short
+        let Ok(value) = serde_json::from_str::<serde_json::Value>(data) else {
+            return Some(SseData::Malformed);
+        };
+        let event_type = match value.get("type").and_then(|t| t.as_str()) {
+            Some(event_type) => event_type.to_string(),
+            None => return Some(SseData::Malformed),
+        };
+        Some(match serde_json::from_value::<Event>(value) {
+            Ok(event) => SseData::Event(event),
+            Err(_) => SseData::Unknown { event_type },
+        })
     }
 }
 
@@ -196,6 +280,7 @@ impl EventStream {
 #[derive(Debug)]
 pub struct EventStreamHandle {
     receiver: broadcast::Receiver<Event>,
+    unknown_events: Arc<AtomicUsize>,
 }
 
 impl PartialEq for EventStreamHandle {
@@ -208,31 +293,37 @@ impl PartialEq for EventStreamHandle {
 }
 
 impl EventStreamHandle {
-    /// Receive the next event (blocking)
-    pub async fn next_event(&mut self) -> Option<Event> {
-        loop {
-            match self.receiver.recv().await {
-                Ok(event) => return Some(event),
-                Err(broadcast::error::RecvError::Closed) => return None,
-                Err(broadcast::error::RecvError::Lagged(_)) => {
-                    // We lagged behind, continue to try to get the next event
-                    continue;
-                }
+    /// A handle whose stream has already ended, for messages replayed without
+    /// a server
+    pub(crate) fn ended() -> Self {
+        let (_, receiver) = broadcast::channel(1);
+        Self {
+            receiver,
+            unknown_events: Arc::default(),
+        }
+    }
+
+    /// Receive the next event (blocking), or how many were missed by falling
+    /// behind. None once the stream has ended.
+    pub async fn next_event(&mut self) -> Option<Result<Event>> {
+        match self.receiver.recv().await {
+            Ok(event) => Some(Ok(event)),
+            Err(broadcast::error::RecvError::Closed) => None,
+            Err(broadcast::error::RecvError::Lagged(missed)) => {
+                Some(Err(OpenCodeError::EventStreamLagged { missed }))
             }
         }
     }
 
-    /// Try to receive an event without blocking
-    pub fn try_next_event(&mut self) -> Option<Event> {
-        loop {
-            match self.receiver.try_recv() {
-                Ok(event) => return Some(event),
-                Err(broadcast::error::TryRecvError::Empty) => return None,
-                Err(broadcast::error::TryRecvError::Closed) => return None,
-                Err(broadcast::error::TryRecvError::Lagged(_)) => {
-                    // We lagged behind, try again
-                    continue;
-                }
+    /// Try to receive an event without blocking, like `next_event`. None
+    /// when there is nothing to read yet, as well as once the stream ended.
+    pub fn try_next_event(&mut self) -> Option<Result<Event>> {
+        match self.receiver.try_recv() {
+            Ok(event) => Some(Ok(event)),
+            Err(broadcast::error::TryRecvError::Empty) => None,
+            Err(broadcast::error::TryRecvError::Closed) => None,
+            Err(broadcast::error::TryRecvError::Lagged(missed)) => {
+                Some(Err(OpenCodeError::EventStreamLagged { missed }))
             }
         }
     }
@@ -241,12 +332,219 @@ impl EventStreamHandle {
     pub fn is_active(&self) -> bool {
         !self.receiver.is_closed()
     }
+
+    /// Events skipped for being of a type these models don't know, across
+    /// every handle of the stream
+    pub fn unknown_event_count(&self) -> usize {
+        self.unknown_events.load(Ordering::Relaxed)
+    }
 }
 
 impl Clone for EventStreamHandle {
     fn clone(&self) -> Self {
         Self {
             receiver: self.receiver.resubscribe(),
+            unknown_events: self.unknown_events.clone(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sdk::OpenCodeClient;
+    use opencode_sdk::models::{
+        event_period_session_period_idle, EventPeriodSessionPeriodIdle, EventSessionIdleProperties,
+    };
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+        sync::{mpsc, Mutex},
+    };
+
+    fn idle_event(session_id: &str) -> Event {
+        Event::SessionPeriodIdle(Box::new(EventPeriodSessionPeriodIdle::new(
+            event_period_session_period_idle::Type::SessionPeriodIdle,
+            EventSessionIdleProperties::new(session_id.to_string()),
+        )))
+    }
+
+    fn sse_data(event: &Event) -> String {
+        format!("data: {}\n\n", serde_json::to_string(event).unwrap())
+    }
+
+    /// An `/event` endpoint that counts its connections and sends what's
+    /// pushed to it as is
+    async fn sse_server() -> (String, Arc<AtomicUsize>, mpsc::UnboundedSender<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let connections = Arc::new(AtomicUsize::new(0));
+        let (push, events) = mpsc::unbounded_channel::<String>();
+        let events = Arc::new(Mutex::new(events));
+
+        let counter = connections.clone();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                counter.fetch_add(1, Ordering::SeqCst);
+                let events = events.clone();
+                tokio::spawn(async move {
+                    let mut request = Vec::new();
+                    let mut buf = [0; 1024];
+                    while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+                        let read = socket.read(&mut buf).await.unwrap();
+                        request.extend_from_slice(&buf[..read]);
+                    }
+                    socket
+                        .write_all(b"HTTP/1.1 200 OK\r\ncontent-type: text/event-stream\r\n\r\n")
+                        .await
+                        .unwrap();
+                    let mut events = events.lock().await;
+                    while let Some(data) = events.recv().await {
+                        if socket.write_all(data.as_bytes()).await.is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+        });
+        (url, connections, push)
+    }
+
+    fn config(url: &str) -> Configuration {
+        let mut config = Configuration::new();
+        config.base_path = url.to_string();
+        config
+    }
+
+    async fn wait_for_connections(connections: &AtomicUsize, count: usize) {
+        for _ in 0..200 {
+            if connections.load(Ordering::SeqCst) >= count {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("expected {} connections", count);
+    }
+
+    #[tokio::test]
+    async fn test_slow_handle_is_told_what_it_missed() {
+        let (url, connections, push) = sse_server().await;
+        let stream = EventStream::start(config(&url), 4, SHUTDOWN_GRACE);
+        let mut fast = stream.handle().unwrap();
+        let mut slow = stream.handle().unwrap();
+        wait_for_connections(&connections, 1).await;
+
+        for index in 0..8 {
+            let event = idle_event(&format!("ses_{}", index));
+            push.send(sse_data(&event)).unwrap();
+            assert_eq!(fast.next_event().await, Some(Ok(event)));
+        }
+
+        // The buffer holds 4 events, so the first 4 are gone for the slow one
+        assert_eq!(
+            slow.next_event().await,
+            Some(Err(OpenCodeError::EventStreamLagged { missed: 4 }))
+        );
+        for index in 4..8 {
+            assert_eq!(
+                slow.next_event().await,
+                Some(Ok(idle_event(&format!("ses_{}", index))))
+            );
+        }
+        assert_eq!(slow.try_next_event(), None);
+        assert_eq!(connections.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_subscribers_share_one_connection() {
+        let (url, connections, push) = sse_server().await;
+        let client = OpenCodeClient::new(&url);
+        let mut first = client.subscribe_to_events().await.unwrap();
+        let mut second = client.clone().subscribe_to_events().await.unwrap();
+        wait_for_connections(&connections, 1).await;
+
+        push.send(sse_data(&idle_event("ses_1"))).unwrap();
+        assert_eq!(first.next_event().await, Some(Ok(idle_event("ses_1"))));
+        assert_eq!(second.next_event().await, Some(Ok(idle_event("ses_1"))));
+        assert_eq!(connections.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_connection_closes_after_the_last_handle_is_dropped() {
+        let (url, connections, _push) = sse_server().await;
+        let grace = Duration::from_millis(50);
+        let stream = EventStream::start(config(&url), 4, grace);
+        let handle = stream.handle().unwrap();
+        wait_for_connections(&connections, 1).await;
+
+        // A new handle within the grace period keeps the connection
+        drop(handle);
+        let handle = stream.handle().unwrap();
+        tokio::time::sleep(grace * 3).await;
+        assert!(handle.is_active());
+
+        drop(handle);
+        tokio::time::sleep(grace * 3).await;
+        assert!(stream.handle().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_unreadable_events_are_skipped_without_reconnecting() {
+        let (url, connections, push) = sse_server().await;
+        let stream = EventStream::start(config(&url), 8, SHUTDOWN_GRACE);
+        let mut handle = stream.handle().unwrap();
+        wait_for_connections(&connections, 1).await;
+
+        push.send(sse_data(&idle_event("ses_1"))).unwrap();
+        // From a newer server
+        push.send("data: {\"type\":\"session.compacted\",\"properties\":{}}\n\n".into())
+            .unwrap();
+        // Cut short
+        push.send("data: {\"type\":\"session.idle\",\"prop\n\n".into())
+            .unwrap();
+        // Split across writes, partway through the JSON
+        let split = sse_data(&idle_event("ses_2"));
+        let (start, end) = split.split_at(20);
+        push.send(start.to_string()).unwrap();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        push.send(end.to_string()).unwrap();
+
+        assert_eq!(handle.next_event().await, Some(Ok(idle_event("ses_1"))));
+        assert_eq!(handle.next_event().await, Some(Ok(idle_event("ses_2"))));
+        assert_eq!(handle.unknown_event_count(), 1);
+        assert_eq!(handle.clone().unknown_event_count(), 1);
+        assert_eq!(connections.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_parse_sse_line() {
+        let data = sse_data(&idle_event("ses_1"));
+        assert_eq!(
+            EventStream::parse_sse_line(data.trim()),
+            Some(SseData::Event(idle_event("ses_1")))
+        );
+        assert_eq!(
+            EventStream::parse_sse_line(r#"data: {"type":"session.compacted"}"#),
+            Some(SseData::Unknown {
+                event_type: "session.compacted".to_string()
+            })
+        );
+        // A known type with properties it doesn't have counts as unknown too
+        assert_eq!(
+            EventStream::parse_sse_line(r#"data: {"type":"session.idle","properties":{}}"#),
+            Some(SseData::Unknown {
+                event_type: "session.idle".to_string()
+            })
+        );
+        assert_eq!(
+            EventStream::parse_sse_line(r#"data: {"type":"sess"#),
+            Some(SseData::Malformed)
+        );
+        assert_eq!(
+            EventStream::parse_sse_line("data: [1, 2]"),
+            Some(SseData::Malformed)
+        );
+        assert_eq!(EventStream::parse_sse_line(": keep-alive"), None);
+        assert_eq!(EventStream::parse_sse_line("data: "), None);
+    }
+}
@@ -0,0 +1,229 @@
+//! Typed file reads with binary detection
+//!
+//! The file endpoint returns every file as a JSON string, so binary files come
+//! back lossily decoded. These helpers classify the content before anything
+//! tries to render it, and cap how much text is kept around.
+
+use crate::sdk::{error::Result, OpenCodeClient};
+use std::path::Path;
+
+/// Largest amount of text kept from a smart read, in bytes
+pub const READ_FILE_MAX_BYTES: usize = 1024 * 1024;
+
+/// How much of the content is sampled by the binary heuristics
+const BINARY_SNIFF_BYTES: usize = 8 * 1024;
+
+/// Share of undecodable characters above which content is treated as binary
+const BINARY_REPLACEMENT_RATIO: f64 = 0.1;
+
+/// A file's content, classified for display
+#[derive(Debug, Clone, PartialEq)]
+pub enum FileContent {
+    Text(String),
+    /// Text cut at the size cap; `size` is the full length in bytes
+    Truncated {
+        text: String,
+        size: usize,
+    },
+    Binary {
+        size: usize,
+        mime_guess: &'static str,
+    },
+}
+
+impl OpenCodeClient {
+    /// Read the lines `start_line..end_line` (0-based, end exclusive) of a file.
+    /// The endpoint has no range parameters, so the file is sliced after reading.
+    pub async fn read_file_range(
+        &self,
+        path: &str,
+        start_line: usize,
+        end_line: usize,
+    ) -> Result<String> {
+        let file = self.read_file(path).await?;
+        Ok(slice_lines(&file.content, start_line, end_line))
+    }
+
+    /// Whether a workspace-relative path still exists, by exact match against
+    /// a file search for it. The search may answer with absolute paths, which
+    /// are taken relative to `workspace_root` before comparing.
+    pub async fn file_exists(&self, path: &str, workspace_root: Option<&str>) -> Result<bool> {
+        let path = path.trim_start_matches("./");
+        let found = self.find_files(path).await?;
+        Ok(found
+            .iter()
+            .any(|candidate| workspace_relative_path(candidate, workspace_root) == path))
+    }
+
+    /// Read a file, detecting binary content and capping the size of text
+    pub async fn read_file_smart(&self, path: &str) -> Result<FileContent> {
+        let file = self.read_file(path).await?;
+        Ok(classify_content(path, file.content, READ_FILE_MAX_BYTES))
+    }
+}
+
+/// `path` relative to the workspace `root`. Paths outside the root are kept as
+/// they are, since the server can still resolve them.
+pub fn workspace_relative_path(path: &str, root: Option<&str>) -> String {
+    let relative = root
+        .map(|root| root.trim_end_matches('/'))
+        .filter(|root| !root.is_empty())
+        .and_then(|root| path.strip_prefix(root))
+        .and_then(|rest| rest.strip_prefix('/'))
+        .unwrap_or(path);
+    let relative = relative.trim_start_matches("./");
+    if relative.is_empty() {
+        path.to_string()
+    } else {
+        relative.to_string()
+    }
+}
+
+/// Classify content read from `path`, truncating text past `max_bytes`
+pub fn classify_content(path: &str, content: String, max_bytes: usize) -> FileContent {
+    let size = content.len();
+    if looks_binary(&content) {
+        return FileContent::Binary {
+            size,
+            mime_guess: guess_mime(path),
+        };
+    }
+    if size <= max_bytes {
+        return FileContent::Text(content);
+    }
+
+    let mut end = max_bytes;
+    while !content.is_char_boundary(end) {
+        end -= 1;
+    }
+    FileContent::Truncated {
+        text: content[..end].to_string(),
+        size,
+    }
+}
+
+/// Whether content looks binary: it contains NUL characters, or too many
+/// characters that couldn't be decoded as UTF-8
+pub fn looks_binary(content: &str) -> bool {
+    let mut end = content.len().min(BINARY_SNIFF_BYTES);
+    while !content.is_char_boundary(end) {
+        end -= 1;
+    }
+    let sample = &content[..end];
+
+    let mut total = 0usize;
+    let mut replacements = 0usize;
+    for c in sample.chars() {
+        if c == '\0' {
+            return true;
+        }
+        if c == char::REPLACEMENT_CHARACTER {
+            replacements += 1;
+        }
+        total += 1;
+    }
+    total > 0 && replacements as f64 / total as f64 > BINARY_REPLACEMENT_RATIO
+}
+
+/// Mime type guessed from a path's extension
+pub fn guess_mime(path: &str) -> &'static str {
+    let extension = Path::new(path)
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_ascii_lowercase());
+    match extension.as_deref() {
+        Some("png") => "image/png",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        Some("svg") => "image/svg+xml",
+        Some("ico") => "image/x-icon",
+        Some("pdf") => "application/pdf",
+        Some("zip") => "application/zip",
+        Some("gz" | "tgz") => "application/gzip",
+        Some("tar") => "application/x-tar",
+        Some("wasm") => "application/wasm",
+        Some("json") => "application/json",
+        Some("html" | "htm") => "text/html",
+        Some("css") => "text/css",
+        Some("js" | "mjs") => "text/javascript",
+        Some("md") => "text/markdown",
+        Some("txt" | "rs" | "toml" | "ts" | "py" | "go" | "yaml" | "yml") => "text/plain",
+        _ => "application/octet-stream",
+    }
+}
+
+fn slice_lines(content: &str, start_line: usize, end_line: usize) -> String {
+    content
+        .split_inclusive('\n')
+        .skip(start_line)
+        .take(end_line.saturating_sub(start_line))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_is_not_binary() {
+        assert!(!looks_binary(""));
+        assert!(!looks_binary(
+            "fn main() {\n    println!(\"héllo 👋\");\n}\n"
+        ));
+        // A stray undecodable byte in otherwise normal text
+        let mostly_text = format!("{}\u{FFFD}", "a".repeat(100));
+        assert!(!looks_binary(&mostly_text));
+    }
+
+    #[test]
+    fn test_nul_bytes_are_binary() {
+        assert!(looks_binary("text\0more text"));
+    }
+
+    #[test]
+    fn test_lossy_decoded_bytes_are_binary() {
+        // PNG header and some pixel data, decoded the way the server would
+        let png = [
+            0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n', 0xff, 0xd8, 0xe0, 0xfe, 0x90, 0xa1,
+        ];
+        assert!(looks_binary(&String::from_utf8_lossy(&png)));
+    }
+
+    #[test]
+    fn test_classify_content() {
+        assert_eq!(
+            classify_content("notes.txt", "short".to_string(), 16),
+            FileContent::Text("short".to_string())
+        );
+        assert_eq!(
+            classify_content("image.png", "\u{FFFD}\u{FFFD}PNG".to_string(), 16),
+            FileContent::Binary {
+                size: 9,
+                mime_guess: "image/png"
+            }
+        );
+        // Truncation backs off to a character boundary
+        assert_eq!(
+            classify_content("notes.txt", "ab€cd".to_string(), 3),
+            FileContent::Truncated {
+                text: "ab".to_string(),
+                size: 7
+            }
+        );
+    }
+
+    #[test]
+    fn test_guess_mime() {
+        assert_eq!(guess_mime("shots/Screen.PNG"), "image/png");
+        assert_eq!(guess_mime("src/main.rs"), "text/plain");
+        assert_eq!(guess_mime("Makefile"), "application/octet-stream");
+    }
+
+    #[test]
+    fn test_slice_lines() {
+        let content = "one\ntwo\nthree\nfour";
+        assert_eq!(slice_lines(content, 1, 3), "two\nthree\n");
+        assert_eq!(slice_lines(content, 3, 10), "four");
+        assert_eq!(slice_lines(content, 2, 1), "");
+    }
+}
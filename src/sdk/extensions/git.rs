@@ -0,0 +1,129 @@
+//! Git details for the project the server is running in
+//!
+//! The API reports whether the app root is a git repository but not which
+//! branch is checked out, so the branch is read from `.git/HEAD` under the
+//! root the server reports.
+
+use crate::sdk::{error::Result, OpenCodeClient};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+/// Length of the abbreviated commit shown for a detached HEAD
+const SHORT_SHA_LEN: usize = 7;
+
+/// Where the server is working, as shown in the status bar
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GitInfo {
+    /// Basename of the server's working directory
+    pub directory: String,
+    /// Checked out branch, or a short commit for a detached HEAD
+    pub branch: Option<String>,
+}
+
+impl OpenCodeClient {
+    /// Get the working directory and current git branch of the server's project
+    pub async fn get_git_info(&self) -> Result<GitInfo> {
+        let app = self.get_app_info().await?;
+        let directory = Path::new(&app.path.cwd)
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| app.path.cwd.clone());
+        let branch = if app.git {
+            read_git_branch(Path::new(&app.path.root)).await
+        } else {
+            None
+        };
+        Ok(GitInfo { directory, branch })
+    }
+}
+
+/// Read the checked out branch of the repository at `root`
+pub async fn read_git_branch(root: &Path) -> Option<String> {
+    let git_dir = resolve_git_dir(root).await?;
+    let head = fs::read_to_string(git_dir.join("HEAD")).await.ok()?;
+    parse_head(&head)
+}
+
+/// Find the git directory, following the `gitdir:` pointer that worktrees
+/// and submodules use in place of a `.git` directory
+async fn resolve_git_dir(root: &Path) -> Option<PathBuf> {
+    let dot_git = root.join(".git");
+    let metadata = fs::metadata(&dot_git).await.ok()?;
+    if metadata.is_dir() {
+        return Some(dot_git);
+    }
+
+    let content = fs::read_to_string(&dot_git).await.ok()?;
+    let git_dir = content.trim().strip_prefix("gitdir:")?.trim();
+    Some(root.join(git_dir))
+}
+
+/// Parse the contents of a HEAD file into a branch name or short commit
+pub fn parse_head(content: &str) -> Option<String> {
+    let content = content.trim();
+    if let Some(reference) = content.strip_prefix("ref:") {
+        let reference = reference.trim();
+        let branch = reference.strip_prefix("refs/heads/").unwrap_or(reference);
+        return (!branch.is_empty()).then(|| branch.to_string());
+    }
+
+    let is_sha = content.len() >= SHORT_SHA_LEN && content.chars().all(|c| c.is_ascii_hexdigit());
+    is_sha.then(|| content[..SHORT_SHA_LEN].to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_head() {
+        assert_eq!(
+            parse_head("ref: refs/heads/main\n"),
+            Some("main".to_string())
+        );
+        assert_eq!(
+            parse_head("ref: refs/heads/feature/status-bar"),
+            Some("feature/status-bar".to_string())
+        );
+        assert_eq!(
+            parse_head("3f2a9c1d5e6b7a8f9e0d1c2b3a4f5e6d7c8b9a0f\n"),
+            Some("3f2a9c1".to_string())
+        );
+        assert_eq!(parse_head(""), None);
+        assert_eq!(parse_head("not a head"), None);
+    }
+
+    #[tokio::test]
+    async fn test_read_git_branch() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        assert_eq!(read_git_branch(root).await, None);
+
+        std::fs::create_dir(root.join(".git")).unwrap();
+        std::fs::write(root.join(".git").join("HEAD"), "ref: refs/heads/main\n").unwrap();
+        assert_eq!(read_git_branch(root).await, Some("main".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_read_git_branch_from_worktree() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let worktree_git_dir = temp_dir
+            .path()
+            .join("repo.git")
+            .join("worktrees")
+            .join("wt");
+        std::fs::create_dir_all(&worktree_git_dir).unwrap();
+        std::fs::write(worktree_git_dir.join("HEAD"), "ref: refs/heads/wip\n").unwrap();
+
+        let root = temp_dir.path().join("wt");
+        std::fs::create_dir(&root).unwrap();
+        std::fs::write(
+            root.join(".git"),
+            format!("gitdir: {}\n", worktree_git_dir.display()),
+        )
+        .unwrap();
+
+        assert_eq!(read_git_branch(&root).await, Some("wip".to_string()));
+    }
+}
@@ -0,0 +1,187 @@
+//! Request latency metrics for the client
+//!
+//! Every API call made through `OpenCodeClient` is timed and kept in a small
+//! ring buffer shared by the client's clones. Snapshots summarize the recent
+//! samples per endpoint, so a slow server can be told apart from a slow UI.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::time::Duration;
+
+/// Number of recent requests kept for the stats view
+pub const METRICS_CAPACITY: usize = 512;
+
+/// One timed API call
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RequestSample {
+    pub endpoint: &'static str,
+    pub duration: Duration,
+    pub success: bool,
+}
+
+/// Bounded buffer of the most recent request samples
+#[derive(Debug, Clone)]
+pub struct RequestMetrics {
+    samples: VecDeque<RequestSample>,
+    capacity: usize,
+}
+
+/// Summary of the recent calls to one endpoint
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EndpointStats {
+    pub endpoint: String,
+    pub count: usize,
+    pub errors: usize,
+    pub p50: Duration,
+    pub p95: Duration,
+}
+
+impl RequestMetrics {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            samples: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Record a sample, dropping the oldest one when the buffer is full
+    pub fn record(&mut self, sample: RequestSample) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// Per-endpoint stats over the buffered samples, ordered by endpoint name
+    pub fn snapshot(&self) -> Vec<EndpointStats> {
+        let mut by_endpoint: BTreeMap<&'static str, (Vec<Duration>, usize)> = BTreeMap::new();
+        for sample in &self.samples {
+            let (durations, errors) = by_endpoint.entry(sample.endpoint).or_default();
+            durations.push(sample.duration);
+            if !sample.success {
+                *errors += 1;
+            }
+        }
+
+        by_endpoint
+            .into_iter()
+            .map(|(endpoint, (mut durations, errors))| {
+                durations.sort_unstable();
+                EndpointStats {
+                    endpoint: endpoint.to_string(),
+                    count: durations.len(),
+                    errors,
+                    p50: percentile(&durations, 50.0),
+                    p95: percentile(&durations, 95.0),
+                }
+            })
+            .collect()
+    }
+}
+
+impl Default for RequestMetrics {
+    fn default() -> Self {
+        Self::new(METRICS_CAPACITY)
+    }
+}
+
+/// Nearest-rank percentile of sorted durations, zero when there are none
+pub fn percentile(sorted: &[Duration], p: f64) -> Duration {
+    if sorted.is_empty() {
+        return Duration::ZERO;
+    }
+    let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(endpoint: &'static str, millis: u64, success: bool) -> RequestSample {
+        RequestSample {
+            endpoint,
+            duration: Duration::from_millis(millis),
+            success,
+        }
+    }
+
+    #[test]
+    fn test_ring_buffer_drops_oldest() {
+        let mut metrics = RequestMetrics::new(3);
+        assert!(metrics.is_empty());
+        for millis in 1..=5 {
+            metrics.record(sample("app.get", millis, true));
+        }
+        assert_eq!(metrics.len(), 3);
+
+        let stats = metrics.snapshot();
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].count, 3);
+        // Samples 1 and 2 were evicted, leaving 3, 4 and 5
+        assert_eq!(stats[0].p50, Duration::from_millis(4));
+        assert_eq!(stats[0].p95, Duration::from_millis(5));
+    }
+
+    #[test]
+    fn test_zero_capacity_records_nothing() {
+        let mut metrics = RequestMetrics::new(0);
+        metrics.record(sample("app.get", 1, true));
+        assert!(metrics.snapshot().is_empty());
+    }
+
+    #[test]
+    fn test_percentile() {
+        let durations: Vec<Duration> = (1..=100).map(Duration::from_millis).collect();
+        assert_eq!(percentile(&durations, 50.0), Duration::from_millis(50));
+        assert_eq!(percentile(&durations, 95.0), Duration::from_millis(95));
+        assert_eq!(percentile(&durations, 100.0), Duration::from_millis(100));
+        assert_eq!(percentile(&durations, 0.0), Duration::from_millis(1));
+
+        let single = [Duration::from_millis(7)];
+        assert_eq!(percentile(&single, 50.0), Duration::from_millis(7));
+        assert_eq!(percentile(&single, 95.0), Duration::from_millis(7));
+        assert_eq!(percentile(&[], 50.0), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_snapshot_groups_by_endpoint() {
+        let mut metrics = RequestMetrics::default();
+        metrics.record(sample("session.list", 30, true));
+        metrics.record(sample("app.get", 10, true));
+        metrics.record(sample("session.list", 10, false));
+        metrics.record(sample("session.list", 20, true));
+
+        let stats = metrics.snapshot();
+        assert_eq!(
+            stats,
+            vec![
+                EndpointStats {
+                    endpoint: "app.get".to_string(),
+                    count: 1,
+                    errors: 0,
+                    p50: Duration::from_millis(10),
+                    p95: Duration::from_millis(10),
+                },
+                EndpointStats {
+                    endpoint: "session.list".to_string(),
+                    count: 3,
+                    errors: 1,
+                    p50: Duration::from_millis(20),
+                    p95: Duration::from_millis(30),
+                },
+            ]
+        );
+    }
+}
@@ -1,3 +1,8 @@
 //! Extensions and utilities for the generated SDK
 
 pub mod events;
+pub mod files;
+pub mod git;
+pub mod metrics;
+pub mod sessions;
+pub mod version;
@@ -0,0 +1,166 @@
+//! Working directories of sessions
+//!
+//! Servers record the directory a session was created in, but the generated
+//! `Session` model predates the field and drops it. The session list is read
+//! again here for just the ids and directories, so a session from another
+//! project can be told apart before it is opened, and sessions are created
+//! with a directory of their own the same way.
+
+use crate::sdk::{error::Result, OpenCodeClient};
+use opencode_sdk::models::{Session, SessionCreateRequest};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// The part of a listed session this module reads
+#[derive(Debug, Deserialize)]
+struct SessionDirectory {
+    id: String,
+    #[serde(default)]
+    directory: Option<String>,
+}
+
+/// A create request rooted in a directory, which the generated request lacks
+#[derive(Debug, Serialize)]
+struct SessionCreateInRequest<'a> {
+    #[serde(flatten)]
+    request: SessionCreateRequest,
+    directory: &'a str,
+}
+
+impl OpenCodeClient {
+    /// Directory of each session by id. Sessions from servers that don't
+    /// record one are left out.
+    pub async fn list_session_directories(&self) -> Result<HashMap<String, String>> {
+        let config = self.configuration();
+        let url = format!("{}/session", config.base_path);
+        let request = async {
+            let response = config.client.get(&url).send().await?.error_for_status()?;
+            response.json::<Vec<serde_json::Value>>().await
+        };
+        let sessions = self.timed("session.list", request).await?;
+        Ok(parse_session_directories(sessions))
+    }
+
+    /// Create a session rooted in `directory` rather than the server's
+    /// working directory
+    pub async fn create_session_in(&self, directory: &str) -> Result<Session> {
+        let config = self.configuration();
+        let url = format!("{}/session", config.base_path);
+        let body = SessionCreateInRequest {
+            request: SessionCreateRequest::new(),
+            directory,
+        };
+        let request = async {
+            let response = config
+                .client
+                .post(&url)
+                .json(&body)
+                .send()
+                .await?
+                .error_for_status()?;
+            response.json::<Session>().await
+        };
+        self.timed("session.create", request).await
+    }
+}
+
+/// Pick the directories out of the listed sessions, skipping entries without one
+fn parse_session_directories(sessions: Vec<serde_json::Value>) -> HashMap<String, String> {
+    sessions
+        .into_iter()
+        .filter_map(|session| serde_json::from_value::<SessionDirectory>(session).ok())
+        .filter_map(|session| Some((session.id, session.directory?)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// Answer one request with `body`, handing back the request as received
+    async fn serve_once(body: String) -> (String, tokio::sync::oneshot::Receiver<String>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (sender, receiver) = tokio::sync::oneshot::channel();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 1024];
+            // Read the headers, then as much body as they announce
+            while !request_complete(&request) {
+                let read = socket.read(&mut buf).await.unwrap();
+                if read == 0 {
+                    break;
+                }
+                request.extend_from_slice(&buf[..read]);
+            }
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+            socket.shutdown().await.unwrap();
+            let _ = sender.send(String::from_utf8_lossy(&request).into_owned());
+        });
+        (format!("http://{}", addr), receiver)
+    }
+
+    fn request_complete(request: &[u8]) -> bool {
+        let text = String::from_utf8_lossy(request);
+        let Some((headers, body)) = text.split_once("\r\n\r\n") else {
+            return false;
+        };
+        let length = headers
+            .lines()
+            .find_map(|line| {
+                let (name, value) = line.split_once(':')?;
+                name.eq_ignore_ascii_case("content-length")
+                    .then(|| value.trim().parse::<usize>().ok())?
+            })
+            .unwrap_or(0);
+        body.len() >= length
+    }
+
+    #[tokio::test]
+    async fn test_create_session_in_sends_the_directory() {
+        let session = json!({
+            "id": "ses_api",
+            "title": "New session",
+            "version": "0.3.1",
+            "time": {"created": 1.0, "updated": 1.0},
+            "directory": "/home/me/project/api"
+        });
+        let (base_url, request) = serve_once(session.to_string()).await;
+        let client = OpenCodeClient::new(&base_url);
+
+        let created = client
+            .create_session_in("/home/me/project/api")
+            .await
+            .unwrap();
+        assert_eq!(created.id, "ses_api");
+
+        let request = request.await.unwrap();
+        assert!(request.starts_with("POST /session "), "{}", request);
+        let (_, body) = request.split_once("\r\n\r\n").unwrap();
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(body).unwrap(),
+            json!({"directory": "/home/me/project/api"})
+        );
+    }
+
+    #[test]
+    fn test_parse_session_directories() {
+        let sessions = vec![
+            json!({"id": "ses_a", "title": "Here", "directory": "/home/me/project"}),
+            json!({"id": "ses_b", "title": "Older server"}),
+            json!({"title": "No id"}),
+        ];
+        assert_eq!(
+            parse_session_directories(sessions),
+            HashMap::from([("ses_a".to_string(), "/home/me/project".to_string())])
+        );
+    }
+}
@@ -0,0 +1,46 @@
+//! Version of the running server
+//!
+//! The generated `App` model has no version, though newer servers report one
+//! with the app info. It's read from `/app` again here so the client can tell
+//! what the server supports as soon as it connects, rather than once the
+//! first session is created.
+
+use crate::sdk::{error::Result, OpenCodeClient};
+
+impl OpenCodeClient {
+    /// Version the server reports with its app info, if it reports one
+    pub async fn get_server_version(&self) -> Result<Option<String>> {
+        let config = self.configuration();
+        let url = format!("{}/app", config.base_path);
+        let request = async {
+            let response = config.client.get(&url).send().await?.error_for_status()?;
+            response.json::<serde_json::Value>().await
+        };
+        let app = self.timed("app.get", request).await?;
+        Ok(parse_server_version(&app))
+    }
+}
+
+/// The non-empty `version` string of an app info response
+fn parse_server_version(app: &serde_json::Value) -> Option<String> {
+    app.get("version")
+        .and_then(serde_json::Value::as_str)
+        .map(str::trim)
+        .filter(|version| !version.is_empty())
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_server_version() {
+        let app = json!({"hostname": "dev", "git": true, "version": "0.4.1"});
+        assert_eq!(parse_server_version(&app).as_deref(), Some("0.4.1"));
+        assert_eq!(parse_server_version(&json!({"git": true})), None);
+        assert_eq!(parse_server_version(&json!({"version": " "})), None);
+        assert_eq!(parse_server_version(&json!({"version": 4})), None);
+    }
+}
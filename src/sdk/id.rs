@@ -0,0 +1,201 @@
+//! Sortable identifiers in the server's format
+//!
+//! An id is `{prefix}_{12 hex chars}{14 base62 chars}`. The hex part holds
+//! the low 48 bits of `(timestamp_ms << 12) + counter`, so ids made within
+//! the same millisecond still sort in creation order, and descending ids flip
+//! those bits to sort newest first.
+//!
+//! The server orders messages and parts by id, so the time component never
+//! goes backwards within a process. When the wall clock steps back (an NTP
+//! correction, resuming from suspend) ids keep the last timestamp and count
+//! on from it, and a millisecond that runs out of counter moves on to the
+//! next one.
+
+use rand::{thread_rng, Rng};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Ids per millisecond, the 12 bits below the timestamp
+const COUNTER_LIMIT: u64 = 0x1000;
+
+const BASE62: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+static ID_CLOCK: Mutex<IdClock> = Mutex::new(IdClock::new());
+
+#[derive(Debug, Clone, Copy)] // Add traits for convenience
+pub enum IdPrefix {
+    Message,
+    Session,
+    User,
+    Part,
+    Permission,
+}
+impl IdPrefix {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IdPrefix::Message => "msg",
+            IdPrefix::Session => "ses",
+            IdPrefix::User => "usr",
+            IdPrefix::Part => "prt",
+            IdPrefix::Permission => "per",
+        }
+    }
+}
+
+/// Timestamp and counter of the last id, which the next one sorts after
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct IdClock {
+    last_timestamp: u64,
+    counter: u64,
+}
+
+impl IdClock {
+    const fn new() -> Self {
+        Self {
+            last_timestamp: 0,
+            counter: 0,
+        }
+    }
+
+    /// Time component of an id made at `now_ms`, always greater than the
+    /// previous one
+    fn next(&mut self, now_ms: u64) -> u64 {
+        if now_ms > self.last_timestamp {
+            self.last_timestamp = now_ms;
+            self.counter = 0;
+        }
+        self.counter += 1;
+        if self.counter == COUNTER_LIMIT {
+            // Out of ids for this millisecond, so borrow the next one
+            self.last_timestamp += 1;
+            self.counter = 1;
+        }
+        self.last_timestamp * COUNTER_LIMIT + self.counter
+    }
+}
+
+pub fn generate_id(prefix: IdPrefix) -> String {
+    generate_id_with_direction(prefix, false)
+}
+
+pub fn generate_descending_id(prefix: IdPrefix) -> String {
+    generate_id_with_direction(prefix, true)
+}
+
+fn generate_id_with_direction(prefix: IdPrefix, descending: bool) -> String {
+    let now_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64;
+    // The clock holds no invariant a panic elsewhere could have broken
+    let time = ID_CLOCK
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .next(now_ms);
+    format_id(prefix, time, descending)
+}
+
+fn format_id(prefix: IdPrefix, time: u64, descending: bool) -> String {
+    let time = if descending { !time } else { time };
+    // Low 48 bits as 12 hex chars, like the TypeScript and Go implementations
+    let time_hex = format!("{:012x}", time & 0xffff_ffff_ffff);
+
+    let mut rng = thread_rng();
+    let random_part: String = (0..14)
+        .map(|_| BASE62[rng.gen_range(0..BASE62.len())] as char)
+        .collect();
+
+    // Format: {prefix}_{12_hex_chars}{14_base62_chars}
+    format!("{}_{}{}", prefix.as_str(), time_hex, random_part)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    const START_MS: u64 = 1_700_000_000_000;
+
+    /// Wall clock readings that mostly move forward, but stall and step back
+    /// by up to a few seconds along the way
+    fn wandering_clock(seed: u64, len: usize) -> Vec<u64> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut now = START_MS;
+        (0..len)
+            .map(|_| {
+                match rng.gen_range(0..10) {
+                    0 => now -= rng.gen_range(1..5_000),
+                    1..=4 => {}
+                    _ => now += rng.gen_range(1..50),
+                }
+                now
+            })
+            .collect()
+    }
+
+    fn ids_for(readings: &[u64], descending: bool) -> Vec<String> {
+        let mut clock = IdClock::new();
+        readings
+            .iter()
+            .map(|&now_ms| format_id(IdPrefix::Message, clock.next(now_ms), descending))
+            .collect()
+    }
+
+    #[test]
+    fn test_ids_sort_in_order_when_the_clock_steps_back() {
+        for seed in 0..50 {
+            let ids = ids_for(&wandering_clock(seed, 500), false);
+            for pair in ids.windows(2) {
+                assert!(pair[0] < pair[1], "seed {}: {:?}", seed, pair);
+            }
+        }
+    }
+
+    #[test]
+    fn test_descending_ids_sort_in_reverse() {
+        for seed in 0..50 {
+            let ids = ids_for(&wandering_clock(seed, 500), true);
+            for pair in ids.windows(2) {
+                assert!(pair[0] > pair[1], "seed {}: {:?}", seed, pair);
+            }
+        }
+    }
+
+    #[test]
+    fn test_clock_regression_reuses_last_timestamp() {
+        let mut clock = IdClock::new();
+        assert_eq!(clock.next(START_MS), START_MS * COUNTER_LIMIT + 1);
+        assert_eq!(clock.next(START_MS - 1_000), START_MS * COUNTER_LIMIT + 2);
+        assert_eq!(clock.next(START_MS + 1), (START_MS + 1) * COUNTER_LIMIT + 1);
+    }
+
+    #[test]
+    fn test_counter_overflow_moves_to_next_millisecond() {
+        let mut clock = IdClock::new();
+        let times: Vec<u64> = (0..COUNTER_LIMIT + 1)
+            .map(|_| clock.next(START_MS))
+            .collect();
+        assert_eq!(
+            times[COUNTER_LIMIT as usize - 2],
+            START_MS * COUNTER_LIMIT + 0xfff
+        );
+        assert_eq!(
+            times[COUNTER_LIMIT as usize - 1],
+            (START_MS + 1) * COUNTER_LIMIT + 1
+        );
+        assert!(times.windows(2).all(|pair| pair[0] < pair[1]));
+
+        // The real clock catching up to the borrowed millisecond counts on
+        assert_eq!(clock.next(START_MS + 1), (START_MS + 1) * COUNTER_LIMIT + 3);
+    }
+
+    #[test]
+    fn test_format_id_layout() {
+        let id = format_id(IdPrefix::Part, 0xabc_0000_0001, false);
+        assert!(id.starts_with("prt_0abc00000001"), "{}", id);
+        assert_eq!(id.len(), 4 + 12 + 14);
+
+        let id = format_id(IdPrefix::Part, 0xabc_0000_0001, true);
+        assert!(id.starts_with("prt_f543fffffffe"), "{}", id);
+    }
+}
@@ -0,0 +1,541 @@
+//! In-memory stand-in for the server
+//!
+//! [`MockApi`] keeps sessions, messages and files in memory and answers the
+//! client's requests from them, so code built on `OpenCodeClient` can be
+//! tested without starting opencode. Each endpoint can be given a latency
+//! and queued failures, named like the request metrics (`"session.chat"`).
+//!
+//! ```ignore
+//! let api = MockApi::new().with_latency("session.chat", Duration::from_millis(50));
+//! api.fail_next("session.list", OpenCodeError::api_error(500, "down"));
+//! let client = OpenCodeClient::with_api("http://mock", api);
+//! ```
+
+use crate::sdk::{
+    api::OpenCodeApi,
+    error::{OpenCodeError, Result},
+    id::{generate_descending_id, generate_id, IdPrefix},
+};
+use async_trait::async_trait;
+use opencode_sdk::models::{
+    App, AppLogRequest, AppPath, AppTime, AssistantMessage, AssistantMessageTime, Config,
+    ConfigProviders200Response, File, FilePart, FileRead200Response, FindText200ResponseInner,
+    FindText200ResponseInnerPath, Message, Part, Session, SessionChatRequest,
+    SessionChatRequestPartsInner, SessionCreateRequest, SessionInitRequest,
+    SessionMessages200ResponseInner, SessionShare, SessionSummarizeRequest, SessionTime, Symbol,
+    TextPart, UserMessage, UserMessageTime,
+};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// What the mock server holds
+#[derive(Debug, Default)]
+struct MockState {
+    sessions: Vec<Session>,
+    messages: HashMap<String, Vec<SessionMessages200ResponseInner>>,
+    files: BTreeMap<String, String>,
+    file_status: Vec<File>,
+    symbols: Vec<Symbol>,
+    reply: Option<String>,
+    logs: Vec<AppLogRequest>,
+    latencies: HashMap<String, Duration>,
+    failures: HashMap<String, VecDeque<OpenCodeError>>,
+}
+
+/// An `OpenCodeApi` answered from memory
+#[derive(Debug, Default)]
+pub struct MockApi {
+    state: Mutex<MockState>,
+}
+
+fn now_millis() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as f64
+}
+
+impl MockApi {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Delay every request to `endpoint` by `latency`
+    pub fn with_latency(self, endpoint: &str, latency: Duration) -> Self {
+        self.set_latency(endpoint, latency);
+        self
+    }
+
+    pub fn set_latency(&self, endpoint: &str, latency: Duration) {
+        self.state().latencies.insert(endpoint.to_string(), latency);
+    }
+
+    /// Fail the next request to `endpoint` with `error`. Queued failures are
+    /// used up in order, then requests succeed again.
+    pub fn fail_next(&self, endpoint: &str, error: OpenCodeError) {
+        self.state()
+            .failures
+            .entry(endpoint.to_string())
+            .or_default()
+            .push_back(error);
+    }
+
+    /// Add a file for the file and find requests to see
+    pub fn with_file(self, path: &str, content: &str) -> Self {
+        self.state()
+            .files
+            .insert(path.to_string(), content.to_string());
+        self
+    }
+
+    pub fn with_file_status(self, files: Vec<File>) -> Self {
+        self.state().file_status = files;
+        self
+    }
+
+    pub fn with_symbols(self, symbols: Vec<Symbol>) -> Self {
+        self.state().symbols = symbols;
+        self
+    }
+
+    /// Answer chat messages with `text`, instead of an empty reply
+    pub fn with_reply(self, text: &str) -> Self {
+        self.state().reply = Some(text.to_string());
+        self
+    }
+
+    /// Log entries written so far
+    pub fn logs(&self) -> Vec<AppLogRequest> {
+        self.state().logs.clone()
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, MockState> {
+        // A test that panicked mid-request leaves nothing half-written worth refusing
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Wait out the endpoint's latency, then fail if a failure is queued
+    async fn request(&self, endpoint: &str) -> Result<()> {
+        let (latency, failure) = {
+            let mut state = self.state();
+            let latency = state.latencies.get(endpoint).copied();
+            let failure = state
+                .failures
+                .get_mut(endpoint)
+                .and_then(VecDeque::pop_front);
+            (latency, failure)
+        };
+        if let Some(latency) = latency {
+            tokio::time::sleep(latency).await;
+        }
+        match failure {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+
+    /// Apply `change` to a session, or fail if there is none by that ID
+    fn with_session<T>(
+        &self,
+        session_id: &str,
+        change: impl FnOnce(&mut Session) -> T,
+    ) -> Result<T> {
+        let mut state = self.state();
+        state
+            .sessions
+            .iter_mut()
+            .find(|session| session.id == session_id)
+            .map(change)
+            .ok_or_else(|| OpenCodeError::session_not_found(session_id))
+    }
+}
+
+/// Parts of a chat request as the server stores them
+fn request_parts(session_id: &str, message_id: &str, request: &SessionChatRequest) -> Vec<Part> {
+    request
+        .parts
+        .iter()
+        .filter_map(|part| match part {
+            SessionChatRequestPartsInner::Text(text) => Some(Part::Text(Box::new(TextPart {
+                id: text
+                    .id
+                    .clone()
+                    .unwrap_or_else(|| generate_id(IdPrefix::Part)),
+                session_id: session_id.to_string(),
+                message_id: message_id.to_string(),
+                text: text.text.clone(),
+                ..Default::default()
+            }))),
+            SessionChatRequestPartsInner::File(file) => Some(Part::File(Box::new(FilePart {
+                id: file
+                    .id
+                    .clone()
+                    .unwrap_or_else(|| generate_id(IdPrefix::Part)),
+                session_id: session_id.to_string(),
+                message_id: message_id.to_string(),
+                mime: file.mime.clone(),
+                filename: file.filename.clone(),
+                url: file.url.clone(),
+                source: None,
+            }))),
+            SessionChatRequestPartsInner::Agent(_) => None,
+        })
+        .collect()
+}
+
+#[async_trait]
+impl OpenCodeApi for MockApi {
+    async fn app_get(&self) -> Result<App> {
+        self.request("app.get").await?;
+        Ok(App::new(
+            "mock".to_string(),
+            false,
+            AppPath::new(
+                "/mock/config".to_string(),
+                "/mock/data".to_string(),
+                "/mock".to_string(),
+                "/mock".to_string(),
+                "/mock/state".to_string(),
+            ),
+            AppTime::new(),
+        ))
+    }
+
+    async fn app_init(&self) -> Result<bool> {
+        self.request("app.init").await?;
+        Ok(true)
+    }
+
+    async fn app_log(&self, request: AppLogRequest) -> Result<bool> {
+        self.request("app.log").await?;
+        self.state().logs.push(request);
+        Ok(true)
+    }
+
+    async fn config_get(&self) -> Result<Config> {
+        self.request("config.get").await?;
+        Ok(Config::default())
+    }
+
+    async fn config_providers(&self) -> Result<ConfigProviders200Response> {
+        self.request("config.providers").await?;
+        Ok(ConfigProviders200Response::default())
+    }
+
+    async fn session_create(&self, request: SessionCreateRequest) -> Result<Session> {
+        self.request("session.create").await?;
+        let now = now_millis();
+        let session = Session {
+            id: generate_descending_id(IdPrefix::Session),
+            parent_id: request.parent_id,
+            title: request.title.unwrap_or_else(|| "New session".to_string()),
+            version: "mock".to_string(),
+            time: Box::new(SessionTime::new(now, now)),
+            ..Default::default()
+        };
+        let mut state = self.state();
+        state.messages.insert(session.id.clone(), Vec::new());
+        state.sessions.push(session.clone());
+        Ok(session)
+    }
+
+    async fn session_get(&self, session_id: &str) -> Result<Session> {
+        self.request("session.get").await?;
+        self.with_session(session_id, |session| session.clone())
+    }
+
+    async fn session_list(&self) -> Result<Vec<Session>> {
+        self.request("session.list").await?;
+        Ok(self.state().sessions.clone())
+    }
+
+    async fn session_delete(&self, session_id: &str) -> Result<bool> {
+        self.request("session.delete").await?;
+        let mut state = self.state();
+        let before = state.sessions.len();
+        state.sessions.retain(|session| session.id != session_id);
+        state.messages.remove(session_id);
+        if state.sessions.len() == before {
+            return Err(OpenCodeError::session_not_found(session_id));
+        }
+        Ok(true)
+    }
+
+    async fn session_init(&self, session_id: &str, _request: SessionInitRequest) -> Result<bool> {
+        self.request("session.init").await?;
+        self.with_session(session_id, |_| true)
+    }
+
+    async fn session_abort(&self, session_id: &str) -> Result<bool> {
+        self.request("session.abort").await?;
+        self.with_session(session_id, |_| true)
+    }
+
+    async fn session_share(&self, session_id: &str) -> Result<Session> {
+        self.request("session.share").await?;
+        self.with_session(session_id, |session| {
+            session.share = Some(Box::new(SessionShare {
+                url: format!("https://opencode.ai/s/{}", session.id),
+            }));
+            session.clone()
+        })
+    }
+
+    async fn session_unshare(&self, session_id: &str) -> Result<Session> {
+        self.request("session.unshare").await?;
+        self.with_session(session_id, |session| {
+            session.share = None;
+            session.clone()
+        })
+    }
+
+    async fn session_summarize(
+        &self,
+        session_id: &str,
+        _request: SessionSummarizeRequest,
+    ) -> Result<bool> {
+        self.request("session.summarize").await?;
+        self.with_session(session_id, |_| true)
+    }
+
+    async fn session_messages(
+        &self,
+        session_id: &str,
+    ) -> Result<Vec<SessionMessages200ResponseInner>> {
+        self.request("session.messages").await?;
+        self.state()
+            .messages
+            .get(session_id)
+            .cloned()
+            .ok_or_else(|| OpenCodeError::session_not_found(session_id))
+    }
+
+    async fn session_chat(
+        &self,
+        session_id: &str,
+        request: SessionChatRequest,
+    ) -> Result<AssistantMessage> {
+        self.request("session.chat").await?;
+        let now = now_millis();
+        self.with_session(session_id, |session| session.time.updated = now)?;
+
+        let user_id = request
+            .message_id
+            .clone()
+            .unwrap_or_else(|| generate_id(IdPrefix::Message));
+        let user = UserMessage {
+            id: user_id.clone(),
+            session_id: session_id.to_string(),
+            time: Box::new(UserMessageTime::new(now)),
+        };
+        let user_parts = request_parts(session_id, &user_id, &request);
+
+        let mut assistant = AssistantMessage {
+            id: generate_id(IdPrefix::Message),
+            session_id: session_id.to_string(),
+            time: Box::new(AssistantMessageTime::new(now)),
+            provider_id: request.provider_id,
+            model_id: request.model_id,
+            mode: request.agent.unwrap_or_else(|| "build".to_string()),
+            system: request.system.into_iter().collect(),
+            ..Default::default()
+        };
+        assistant.time.completed = Some(now);
+
+        let mut state = self.state();
+        let reply_parts = match &state.reply {
+            Some(reply) => vec![Part::Text(Box::new(TextPart {
+                id: generate_id(IdPrefix::Part),
+                session_id: session_id.to_string(),
+                message_id: assistant.id.clone(),
+                text: reply.clone(),
+                ..Default::default()
+            }))],
+            None => Vec::new(),
+        };
+        let messages = state.messages.entry(session_id.to_string()).or_default();
+        messages.push(SessionMessages200ResponseInner::new(
+            Message::User(Box::new(user)),
+            user_parts,
+        ));
+        messages.push(SessionMessages200ResponseInner::new(
+            Message::Assistant(Box::new(assistant.clone())),
+            reply_parts,
+        ));
+        Ok(assistant)
+    }
+
+    async fn file_read(&self, path: &str) -> Result<FileRead200Response> {
+        self.request("file.read").await?;
+        match self.state().files.get(path) {
+            Some(content) => Ok(FileRead200Response {
+                content: content.clone(),
+                ..Default::default()
+            }),
+            None => Err(OpenCodeError::api_error(
+                404,
+                format!("No such file: {}", path),
+            )),
+        }
+    }
+
+    async fn file_status(&self) -> Result<Vec<File>> {
+        self.request("file.status").await?;
+        Ok(self.state().file_status.clone())
+    }
+
+    async fn find_text(&self, pattern: &str) -> Result<Vec<FindText200ResponseInner>> {
+        self.request("find.text").await?;
+        let state = self.state();
+        let mut matches = Vec::new();
+        for (path, content) in &state.files {
+            let mut offset = 0;
+            for (index, line) in content.split_inclusive('\n').enumerate() {
+                if line.contains(pattern) {
+                    matches.push(FindText200ResponseInner::new(
+                        FindText200ResponseInnerPath { text: path.clone() },
+                        FindText200ResponseInnerPath {
+                            text: line.to_string(),
+                        },
+                        (index + 1) as f64,
+                        offset as f64,
+                        Vec::new(),
+                    ));
+                }
+                offset += line.len();
+            }
+        }
+        Ok(matches)
+    }
+
+    async fn find_files(&self, query: &str) -> Result<Vec<String>> {
+        self.request("find.files").await?;
+        Ok(self
+            .state()
+            .files
+            .keys()
+            .filter(|path| path.contains(query))
+            .cloned()
+            .collect())
+    }
+
+    async fn find_symbols(&self, query: &str) -> Result<Vec<Symbol>> {
+        self.request("find.symbols").await?;
+        Ok(self
+            .state()
+            .symbols
+            .iter()
+            .filter(|symbol| symbol.name.contains(query))
+            .cloned()
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sdk::{client::ChatOptions, OpenCodeClient};
+
+    fn client(api: MockApi) -> OpenCodeClient {
+        OpenCodeClient::with_api("http://mock", api)
+    }
+
+    #[tokio::test]
+    async fn test_chat_is_stored_with_the_session() {
+        let client = client(MockApi::new().with_reply("hi there"));
+        let session = client.create_session().await.unwrap();
+
+        let reply = client
+            .send_user_message(
+                &session.id,
+                "msg_user",
+                "hello",
+                "anthropic",
+                "claude-sonnet-4",
+                &ChatOptions::default(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(reply.session_id, session.id);
+
+        let messages = client.get_messages(&session.id).await.unwrap();
+        assert_eq!(messages.len(), 2);
+        assert!(matches!(&*messages[0].info, Message::User(user) if user.id == "msg_user"));
+        assert!(matches!(&messages[0].parts[..], [Part::Text(text)] if text.text == "hello"));
+        assert!(matches!(&messages[1].parts[..], [Part::Text(text)] if text.text == "hi there"));
+
+        assert!(client
+            .send_user_message(
+                "ses_missing",
+                "msg_2",
+                "hello",
+                "a",
+                "b",
+                &ChatOptions::default()
+            )
+            .await
+            .unwrap_err()
+            .is_not_found());
+    }
+
+    #[tokio::test]
+    async fn test_queued_failures_are_used_up() {
+        let api = MockApi::new();
+        api.fail_next("session.list", OpenCodeError::api_error(500, "down"));
+        let client = client(api);
+
+        let error = client.list_sessions().await.unwrap_err();
+        assert!(error.is_server_error());
+        assert!(client.list_sessions().await.unwrap().is_empty());
+
+        let stats = client.metrics_snapshot();
+        let list = stats
+            .iter()
+            .find(|stats| stats.endpoint == "session.list")
+            .unwrap();
+        assert_eq!((list.count, list.errors), (2, 1));
+    }
+
+    #[tokio::test]
+    async fn test_file_exists_with_absolute_search_results() {
+        let client =
+            client(MockApi::new().with_file("/home/me/project/docs/report.md", "# Report"));
+        let root = Some("/home/me/project");
+        assert!(client.file_exists("docs/report.md", root).await.unwrap());
+        assert!(client.file_exists("./docs/report.md", root).await.unwrap());
+        assert!(!client.file_exists("docs/report.md", None).await.unwrap());
+        assert!(!client.file_exists("report.md", root).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_agent_configs_are_timed() {
+        let client = client(MockApi::new());
+        client.get_agent_configs().await.unwrap();
+        let stats = client.metrics_snapshot();
+        assert!(stats.iter().any(|stats| stats.endpoint == "config.agent"));
+    }
+
+    #[tokio::test]
+    async fn test_latency_delays_the_endpoint() {
+        let client = client(
+            MockApi::new()
+                .with_latency("app.get", Duration::from_millis(50))
+                .with_file("src/main.rs", "fn main() {\n    run();\n}\n"),
+        );
+
+        let start = std::time::Instant::now();
+        client.get_app_info().await.unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(50));
+
+        let matches = client.find_text("run").await.unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].line_number, 2.0);
+        assert_eq!(
+            client.find_files("main").await.unwrap(),
+            vec!["src/main.rs"]
+        );
+    }
+}
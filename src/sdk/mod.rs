@@ -6,17 +6,25 @@
 
 #![allow(unused)]
 
+pub mod api;
+pub mod bulk;
 pub mod client;
 pub mod discovery;
 pub mod error;
 pub mod extensions;
+pub mod id;
+#[cfg(feature = "mock")]
+pub mod mock;
 pub mod session_manager;
 // pub mod streams;
 
 // High-level exports for easy use
-pub use client::OpenCodeClient;
-pub use discovery::{discover_opencode_server, DiscoveryConfig};
+pub use api::{HttpApi, OpenCodeApi};
+pub use client::{AttachedFile, ChatOptions, MessagePage, OpenCodeClient};
+pub use discovery::{discover_opencode_server, DiscoveryConfig, ServerCandidate};
 pub use error::{OpenCodeError, Result};
+#[cfg(feature = "mock")]
+pub use mock::MockApi;
 pub use session_manager::SessionManager;
 
 // Re-export commonly used generated types for convenience
@@ -51,6 +51,14 @@ impl SessionManager {
         Ok(session)
     }
 
+    /// Create a new session rooted in `directory` and save it as the current
+    /// session
+    pub async fn create_new_session_in(&self, directory: &str) -> Result<Session> {
+        let session = self.client.create_session_in(directory).await?;
+        self.save_last_session_id(&session.id).await?;
+        Ok(session)
+    }
+
     /// Get the current session ID if one exists and is valid
     pub async fn get_current_session_id(&self) -> Option<String> {
         if let Ok(session_id) = self.load_last_session_id().await {
@@ -64,6 +72,25 @@ impl SessionManager {
         None
     }
 
+    /// Get the remembered session, if it still exists on the server
+    ///
+    /// A remembered session that the server no longer knows about is
+    /// forgotten locally so the next start doesn't retry it.
+    pub async fn get_current_session(&self) -> Result<Option<Session>> {
+        let Ok(session_id) = self.load_last_session_id().await else {
+            return Ok(None);
+        };
+
+        match self.client.get_session(&session_id).await {
+            Ok(session) => Ok(Some(session)),
+            Err(e) if e.is_not_found() => {
+                self.clear_current_session().await?;
+                Ok(None)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
     /// Switch to a specific session
     pub async fn switch_to_session(&self, session_id: &str) -> Result<Session> {
         // Validate session exists
@@ -150,12 +177,24 @@ impl OpenCodeClient {
         manager.create_new_session().await
     }
 
+    /// Create a new session rooted in `directory` and set it as current
+    pub async fn create_new_session_in(&self, directory: &str) -> Result<Session> {
+        let manager = SessionManager::new(self.clone());
+        manager.create_new_session_in(directory).await
+    }
+
     /// Get the current session ID if one exists and is valid
     pub async fn get_current_session_id(&self) -> Option<String> {
         let manager = SessionManager::new(self.clone());
         manager.get_current_session_id().await
     }
 
+    /// Get the remembered session if it still exists on the server
+    pub async fn get_current_session(&self) -> Result<Option<Session>> {
+        let manager = SessionManager::new(self.clone());
+        manager.get_current_session().await
+    }
+
     /// Switch to a specific session
     pub async fn switch_to_session(&self, session_id: &str) -> Result<Session> {
         let manager = SessionManager::new(self.clone());
@@ -210,6 +249,44 @@ mod tests {
         assert!(manager.load_last_session_id().await.is_err());
     }
 
+    /// Serve a single canned HTTP response and return the base URL
+    async fn serve_once(response: &'static str) -> String {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 1024];
+            let _ = socket.read(&mut buf).await;
+            socket.write_all(response.as_bytes()).await.unwrap();
+            socket.shutdown().await.unwrap();
+        });
+        format!("http://{}", addr)
+    }
+
+    #[tokio::test]
+    async fn test_get_current_session_without_saved_id() {
+        let (manager, _temp_dir) = create_test_session_manager();
+        assert!(manager.get_current_session().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_get_current_session_deleted_server_side() {
+        let base_url = serve_once(
+            "HTTP/1.1 404 Not Found\r\nContent-Type: application/json\r\nContent-Length: 2\r\nConnection: close\r\n\r\n{}",
+        )
+        .await;
+        let (mut manager, _temp_dir) = create_test_session_manager();
+        manager.client = OpenCodeClient::new(&base_url);
+        manager.save_last_session_id("ses_gone").await.unwrap();
+
+        let session = manager.get_current_session().await.unwrap();
+        assert!(session.is_none());
+        // The stale id is forgotten
+        assert!(manager.load_last_session_id().await.is_err());
+    }
+
     #[test]
     fn test_get_opencode_state_dir() {
         let state_dir = get_opencode_state_dir();
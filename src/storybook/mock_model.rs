@@ -1,6 +1,20 @@
 use crate::app::{
-    tea_model::{UserConfig, INLINE_HEIGHT},
-    ui_components::{message_part::VerbosityLevel, MessageLog, SessionSelector, TextInputArea},
+    alerts::AlertPolicy,
+    completion_notice::DEFAULT_NOTIFY_AFTER_SECS,
+    input_history::DEFAULT_INPUT_HISTORY_SIZE,
+    links::UrlAction,
+    log_control::LogLevels,
+    project_config::ResolvedSettings,
+    redaction::Redactor,
+    tea_model::{UserConfig, INLINE_HEIGHT, SESSION_RESUME_MAX_AGE_SECS},
+    ui_components::{
+        glyphs::GlyphMode,
+        message_part::{VerbosityLevel, DEFAULT_PROMPT_COLLAPSE_LINES},
+        modal_session_selector::SessionSort,
+        text_width::DEFAULT_TAB_WIDTH,
+        tool_render::ToolRenderPolicies,
+        MessageLog, SessionSelector, TextInputArea,
+    },
 };
 
 #[derive(Debug, Clone)]
@@ -23,8 +37,29 @@ impl MockModel {
                 ui_block_is_rounded: false,
                 ui_status_is_bottom: true,
                 ui_status_use_labels: true,
+                ui_show_banner: true,
                 height: INLINE_HEIGHT,
                 keys_shortcut_timeout_ms: 1000,
+                session_resume: false,
+                session_resume_max_age_secs: SESSION_RESUME_MAX_AGE_SECS,
+                session_sort: SessionSort::default(),
+                snippets: Vec::new(),
+                notify_on_complete: false,
+                notify_after_secs: DEFAULT_NOTIFY_AFTER_SECS,
+                ui_stream_reveal: false,
+                ui_glyphs: GlyphMode::Unicode,
+                ui_max_height: None,
+                ui_tab_width: DEFAULT_TAB_WIDTH,
+                ui_prompt_collapse_lines: DEFAULT_PROMPT_COLLAPSE_LINES,
+                link_url_action: UrlAction::default(),
+                tool_render: ToolRenderPolicies::default(),
+                alerts: AlertPolicy::default(),
+                input_history_size: DEFAULT_INPUT_HISTORY_SIZE,
+                retained_messages: None,
+                redactor: Redactor::defaults(),
+                log_levels: LogLevels::default(),
+                session_templates: Vec::new(),
+                settings: ResolvedSettings::default(),
             },
             verbosity_level: VerbosityLevel::Summary,
             message_log: MessageLog::new(),
@@ -64,14 +99,4 @@ impl MockModel {
     pub fn can_accept_input(&self) -> bool {
         true
     }
-
-    // Method to execute mock model within ViewModelContext safely
-    pub fn with_context<F, R>(&self, f: F) -> R
-    where
-        F: FnOnce() -> R,
-    {
-        // For now, we'll skip the ViewModelContext since it requires a full Model
-        // In a real implementation, we'd need to adapt ViewModelContext for MockModel
-        f()
-    }
 }
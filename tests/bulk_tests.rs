@@ -0,0 +1,91 @@
+//! Bulk session operation tests for the OpenCode SDK
+//!
+//! These tests run the bulk helpers against a real opencode server instance.
+//! They are ignored by default: `cargo test --test bulk_tests -- --ignored`.
+
+mod common;
+
+use common::TestServer;
+use opencode_sdk::{
+    apis::default_api,
+    models::{Session, SessionCreateRequest},
+};
+use opencoders::sdk::{
+    bulk::{self, ExportFormat},
+    OpenCodeClient,
+};
+
+/// Create a session with a title, which the client's `create_session` leaves
+/// to the server
+async fn create_titled_session(client: &OpenCodeClient, title: &str) -> Session {
+    let params = default_api::SessionPeriodCreateParams {
+        session_create_request: Some(SessionCreateRequest {
+            title: Some(title.to_string()),
+            ..SessionCreateRequest::new()
+        }),
+    };
+    default_api::session_period_create(client.configuration(), params)
+        .await
+        .expect("Failed to create session")
+}
+
+#[tokio::test]
+#[ignore = "needs an opencode server, run with --ignored"]
+async fn test_delete_sessions_matching_a_title() {
+    let server = TestServer::start()
+        .await
+        .expect("Failed to start test server");
+
+    let client = OpenCodeClient::new(server.base_url());
+
+    for title in ["scratch 1", "keep 1", "scratch 2", "keep 2", "scratch 3"] {
+        create_titled_session(&client, title).await;
+    }
+
+    let scratch = bulk::find_sessions(&client, |session| session.title.starts_with("scratch"))
+        .await
+        .expect("find_sessions");
+    assert_eq!(scratch.len(), 3);
+
+    let report = bulk::delete_sessions(&client, scratch).await;
+    assert_eq!(report.succeeded.len(), 3);
+    assert!(report.is_complete(), "{:?}", report.failed);
+    println!("✓ Deleted {} scratch sessions", report.succeeded.len());
+
+    let remaining = client.list_sessions().await.expect("list_sessions");
+    assert_eq!(remaining.len(), 2);
+    assert!(remaining
+        .iter()
+        .all(|session| session.title.starts_with("keep")));
+    println!("✓ Other sessions kept");
+
+    server.shutdown().await.expect("Failed to shutdown server");
+}
+
+#[tokio::test]
+#[ignore = "needs an opencode server, run with --ignored"]
+async fn test_export_all_sessions() {
+    let server = TestServer::start()
+        .await
+        .expect("Failed to start test server");
+
+    let client = OpenCodeClient::new(server.base_url());
+    let dir = tempfile::tempdir().expect("Failed to create temp dir");
+
+    for title in ["first", "second"] {
+        create_titled_session(&client, title).await;
+    }
+
+    let report = bulk::export_all_sessions(&client, dir.path(), ExportFormat::Markdown)
+        .await
+        .expect("export_all_sessions");
+    assert_eq!(report.succeeded.len(), 2);
+    assert!(report.is_complete(), "{:?}", report.failed);
+    for path in &report.succeeded {
+        let contents = std::fs::read_to_string(path).expect("Failed to read export");
+        assert!(contents.starts_with("# "), "{}", contents);
+    }
+    println!("✓ Exported {} sessions", report.succeeded.len());
+
+    server.shutdown().await.expect("Failed to shutdown server");
+}
@@ -3,7 +3,7 @@
 #![allow(dead_code)]
 
 /// Assert that an API call succeeds, providing detailed error information on failure
-#[macro_export()]
+#[macro_export]
 macro_rules! assert_api_success {
     ($result:expr, $context:expr) => {
         match $result {
@@ -27,7 +27,7 @@ macro_rules! assert_api_success {
 }
 
 /// Assert that an API call fails with a specific error type
-#[macro_export()]
+#[macro_export]
 macro_rules! assert_api_error {
     ($result:expr, $expected_error:pat, $context:expr) => {
         match $result {
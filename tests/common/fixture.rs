@@ -0,0 +1,442 @@
+//! A stand-in opencode server with canned assistant replies
+//!
+//! Without a model provider the real server can't reply to anything, which
+//! leaves streaming, tool parts and idle events untested. `FixtureServer`
+//! answers just enough of the HTTP API from memory: `/app`, `/session`, a
+//! session's messages and the `/event` stream. A message sent to a session is
+//! answered with the next reply of its `MockProvider`, streamed as the real
+//! server does: the user's message and parts, the assistant's message, each
+//! of its parts (a tool call running, then completed), the finished message,
+//! and `session.idle`. All of it is sent before the send request returns, so
+//! a test subscribed beforehand sees the same events in the same order on
+//! every run.
+
+use eyre::{eyre, Result, WrapErr};
+use opencode_sdk::models::{
+    App, AppPath, AppTime, AssistantMessage, AssistantMessageTime, Event,
+    EventMessagePartUpdatedProperties, EventMessageUpdatedProperties,
+    EventPeriodMessagePeriodPartPeriodUpdated, EventPeriodMessagePeriodUpdated,
+    EventPeriodServerPeriodConnected, EventPeriodSessionPeriodIdle, EventSessionIdleProperties,
+    Message, Part, Session, SessionChatRequest, SessionChatRequestPartsInner,
+    SessionMessages200ResponseInner, SessionTime, TextPart, ToolPart, ToolState,
+    ToolStateCompleted, ToolStateCompletedTime, ToolStateRunning, ToolStateRunningTime,
+    UserMessage, UserMessageTime,
+};
+use opencoders::sdk::client::{generate_descending_id, generate_id, IdPrefix};
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+
+/// Events kept for a subscriber that's slow to read them
+const EVENT_BUFFER_SIZE: usize = 256;
+
+/// Timestamps of everything the fixture makes, so replies are the same on
+/// every run
+const FIXED_TIME: f64 = 1_700_000_000_000.0;
+
+/// One part of a canned reply
+#[derive(Debug, Clone, PartialEq)]
+pub enum CannedPart {
+    Text(String),
+    /// A tool call, streamed running and then completed with `output`
+    Tool {
+        tool: String,
+        input: serde_json::Value,
+        output: String,
+    },
+}
+
+impl CannedPart {
+    pub fn text(text: &str) -> Self {
+        Self::Text(text.to_string())
+    }
+
+    pub fn tool(tool: &str, input: serde_json::Value, output: &str) -> Self {
+        Self::Tool {
+            tool: tool.to_string(),
+            input,
+            output: output.to_string(),
+        }
+    }
+}
+
+/// Replies to the messages sent, in order. Once they run out, a message is
+/// echoed back as text.
+#[derive(Debug, Clone, Default)]
+pub struct MockProvider {
+    replies: VecDeque<Vec<CannedPart>>,
+}
+
+impl MockProvider {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue the reply to the next message not replied to yet
+    pub fn reply(mut self, parts: Vec<CannedPart>) -> Self {
+        self.replies.push_back(parts);
+        self
+    }
+
+    fn next_reply(&mut self, prompt: &str) -> Vec<CannedPart> {
+        self.replies
+            .pop_front()
+            .unwrap_or_else(|| vec![CannedPart::Text(format!("echo: {}", prompt))])
+    }
+}
+
+/// What the fixture holds
+#[derive(Debug, Default)]
+struct FixtureState {
+    sessions: Vec<Session>,
+    messages: HashMap<String, Vec<SessionMessages200ResponseInner>>,
+    provider: MockProvider,
+}
+
+/// An HTTP server on a local port answering like opencode
+pub struct FixtureServer {
+    base_url: String,
+    port: u16,
+    accept_task: JoinHandle<()>,
+}
+
+impl FixtureServer {
+    /// Listen on a free local port, replying with `provider`
+    pub async fn start(provider: MockProvider) -> Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .wrap_err("Failed to bind the fixture server")?;
+        let port = listener.local_addr()?.port();
+        let state = Arc::new(Mutex::new(FixtureState {
+            provider,
+            ..Default::default()
+        }));
+        let (events, _) = broadcast::channel(EVENT_BUFFER_SIZE);
+
+        let accept_task = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let state = state.clone();
+                let events = events.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = handle_connection(stream, state, events).await {
+                        eprintln!("Fixture server connection failed: {}", e);
+                    }
+                });
+            }
+        });
+
+        Ok(Self {
+            base_url: format!("http://127.0.0.1:{}", port),
+            port,
+            accept_task,
+        })
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+}
+
+impl Drop for FixtureServer {
+    fn drop(&mut self) {
+        self.accept_task.abort();
+    }
+}
+
+/// A request as far as the fixture reads it
+struct Request {
+    method: String,
+    path: String,
+    body: Vec<u8>,
+}
+
+async fn read_request(reader: &mut BufReader<TcpStream>) -> Result<Request> {
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).await?;
+    let mut words = request_line.split_whitespace();
+    let (Some(method), Some(target)) = (words.next(), words.next()) else {
+        return Err(eyre!("Malformed request line {:?}", request_line));
+    };
+    let path = target.split('?').next().unwrap_or_default().to_string();
+    let method = method.to_string();
+
+    let mut content_length = 0;
+    loop {
+        let mut header = String::new();
+        reader.read_line(&mut header).await?;
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse()?;
+            }
+        }
+    }
+
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).await?;
+    Ok(Request { method, path, body })
+}
+
+async fn handle_connection(
+    stream: TcpStream,
+    state: Arc<Mutex<FixtureState>>,
+    events: broadcast::Sender<String>,
+) -> Result<()> {
+    let mut reader = BufReader::new(stream);
+    let request = read_request(&mut reader).await?;
+    let mut stream = reader.into_inner();
+
+    let segments: Vec<&str> = request.path.trim_matches('/').split('/').collect();
+    let response = match (request.method.as_str(), segments.as_slice()) {
+        ("GET", ["event"]) => return stream_events(stream, events.subscribe()).await,
+        ("GET", ["app"]) => json(&app()),
+        ("GET", ["session"]) => json(&state.lock().unwrap().sessions),
+        ("POST", ["session"]) => json(&create_session(&state)),
+        ("GET", ["session", session_id, "message"]) => {
+            match state.lock().unwrap().messages.get(*session_id) {
+                Some(messages) => json(messages),
+                None => not_found(),
+            }
+        }
+        ("POST", ["session", session_id, "message"]) => {
+            let chat: SessionChatRequest = serde_json::from_slice(&request.body)
+                .wrap_err("Failed to read the chat request")?;
+            match chat_reply(&state, &events, session_id, chat) {
+                Some(assistant) => json(&assistant),
+                None => not_found(),
+            }
+        }
+        _ => not_found(),
+    };
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+fn json<T: Serialize>(body: &T) -> String {
+    let body = serde_json::to_string(body).expect("fixture responses serialize");
+    format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        body.len(),
+        body
+    )
+}
+
+fn not_found() -> String {
+    "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string()
+}
+
+/// Keep the connection open, writing each event as it's sent
+async fn stream_events(
+    mut stream: TcpStream,
+    mut events: broadcast::Receiver<String>,
+) -> Result<()> {
+    let head = "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nConnection: close\r\n\r\n";
+    stream.write_all(head.as_bytes()).await?;
+    let connected = Event::ServerPeriodConnected(Box::new(EventPeriodServerPeriodConnected::new(
+        Default::default(),
+        serde_json::json!({}),
+    )));
+    stream.write_all(sse_line(&connected).as_bytes()).await?;
+    while let Ok(line) = events.recv().await {
+        stream.write_all(line.as_bytes()).await?;
+    }
+    Ok(())
+}
+
+/// An event as the `data:` line of the stream
+fn sse_line(event: &Event) -> String {
+    let data = serde_json::to_string(event).expect("events serialize");
+    format!("data: {}\n\n", data)
+}
+
+fn app() -> App {
+    App::new(
+        "fixture".to_string(),
+        false,
+        AppPath::new(
+            "/fixture/config".to_string(),
+            "/fixture/data".to_string(),
+            "/fixture".to_string(),
+            "/fixture".to_string(),
+            "/fixture/state".to_string(),
+        ),
+        AppTime::new(),
+    )
+}
+
+fn create_session(state: &Mutex<FixtureState>) -> Session {
+    let session = Session::new(
+        generate_descending_id(IdPrefix::Session),
+        "New session".to_string(),
+        "fixture".to_string(),
+        SessionTime::new(FIXED_TIME, FIXED_TIME),
+    );
+    let mut state = state.lock().unwrap();
+    state.messages.insert(session.id.clone(), Vec::new());
+    state.sessions.push(session.clone());
+    session
+}
+
+/// Store the message and the provider's reply to it, sending the events
+/// along the way. None if there's no such session.
+fn chat_reply(
+    state: &Mutex<FixtureState>,
+    events: &broadcast::Sender<String>,
+    session_id: &str,
+    chat: SessionChatRequest,
+) -> Option<AssistantMessage> {
+    let mut state = state.lock().unwrap();
+    if !state.messages.contains_key(session_id) {
+        return None;
+    }
+    let send = |event: Event| {
+        // Nobody may be subscribed, which is fine
+        let _ = events.send(sse_line(&event));
+    };
+
+    let user_id = chat
+        .message_id
+        .clone()
+        .unwrap_or_else(|| generate_id(IdPrefix::Message));
+    let user = Message::User(Box::new(UserMessage::new(
+        user_id.clone(),
+        session_id.to_string(),
+        Default::default(),
+        UserMessageTime::new(FIXED_TIME),
+    )));
+    let prompt: Vec<String> = chat
+        .parts
+        .iter()
+        .filter_map(|part| match part {
+            SessionChatRequestPartsInner::Text(text) => Some(text.text.clone()),
+            _ => None,
+        })
+        .collect();
+    let user_parts: Vec<Part> = prompt
+        .iter()
+        .map(|text| text_part(session_id, &user_id, text))
+        .collect();
+    send(message_updated(user.clone()));
+    user_parts.iter().cloned().map(part_updated).for_each(send);
+
+    let mut assistant = AssistantMessage {
+        id: generate_id(IdPrefix::Message),
+        session_id: session_id.to_string(),
+        time: Box::new(AssistantMessageTime::new(FIXED_TIME)),
+        provider_id: chat.provider_id,
+        model_id: chat.model_id,
+        mode: chat.agent.unwrap_or_else(|| "build".to_string()),
+        ..Default::default()
+    };
+    send(message_updated(Message::Assistant(Box::new(
+        assistant.clone(),
+    ))));
+
+    let mut reply_parts = Vec::new();
+    for canned in state.provider.next_reply(&prompt.join("\n")) {
+        let part = match canned {
+            CannedPart::Text(text) => text_part(session_id, &assistant.id, &text),
+            CannedPart::Tool {
+                tool,
+                input,
+                output,
+            } => {
+                let running = tool_part(session_id, &assistant.id, &tool, &input);
+                send(part_updated(running.clone()));
+                let Part::Tool(running) = running else {
+                    unreachable!("tool_part makes tool parts")
+                };
+                completed_tool(*running, &input, &output)
+            }
+        };
+        send(part_updated(part.clone()));
+        reply_parts.push(part);
+    }
+
+    assistant.time.completed = Some(FIXED_TIME);
+    send(message_updated(Message::Assistant(Box::new(
+        assistant.clone(),
+    ))));
+    send(Event::SessionPeriodIdle(Box::new(
+        EventPeriodSessionPeriodIdle::new(
+            Default::default(),
+            EventSessionIdleProperties::new(session_id.to_string()),
+        ),
+    )));
+
+    let messages = state.messages.get_mut(session_id)?;
+    messages.push(SessionMessages200ResponseInner::new(user, user_parts));
+    messages.push(SessionMessages200ResponseInner::new(
+        Message::Assistant(Box::new(assistant.clone())),
+        reply_parts,
+    ));
+    Some(assistant)
+}
+
+fn message_updated(info: Message) -> Event {
+    Event::MessagePeriodUpdated(Box::new(EventPeriodMessagePeriodUpdated::new(
+        Default::default(),
+        EventMessageUpdatedProperties::new(info),
+    )))
+}
+
+fn part_updated(part: Part) -> Event {
+    Event::MessagePeriodPartPeriodUpdated(Box::new(EventPeriodMessagePeriodPartPeriodUpdated::new(
+        Default::default(),
+        EventMessagePartUpdatedProperties::new(part),
+    )))
+}
+
+fn text_part(session_id: &str, message_id: &str, text: &str) -> Part {
+    Part::Text(Box::new(TextPart {
+        id: generate_id(IdPrefix::Part),
+        session_id: session_id.to_string(),
+        message_id: message_id.to_string(),
+        text: text.to_string(),
+        ..Default::default()
+    }))
+}
+
+/// A tool call that's running
+fn tool_part(session_id: &str, message_id: &str, tool: &str, input: &serde_json::Value) -> Part {
+    let mut running =
+        ToolStateRunning::new(Default::default(), ToolStateRunningTime::new(FIXED_TIME));
+    running.input = Some(Some(input.clone()));
+    Part::Tool(Box::new(ToolPart {
+        id: generate_id(IdPrefix::Part),
+        session_id: session_id.to_string(),
+        message_id: message_id.to_string(),
+        call_id: generate_id(IdPrefix::Part),
+        tool: tool.to_string(),
+        state: Box::new(ToolState::Running(Box::new(running))),
+    }))
+}
+
+/// The same call, finished with `output`
+fn completed_tool(mut part: ToolPart, input: &serde_json::Value, output: &str) -> Part {
+    let input = input
+        .as_object()
+        .map(|input| input.clone().into_iter().collect())
+        .unwrap_or_default();
+    part.state = Box::new(ToolState::Completed(Box::new(ToolStateCompleted::new(
+        Default::default(),
+        input,
+        output.to_string(),
+        part.tool.clone(),
+        HashMap::new(),
+        ToolStateCompletedTime::new(FIXED_TIME, FIXED_TIME),
+    ))));
+    Part::Tool(Box::new(part))
+}
@@ -1,12 +1,15 @@
 //! Common test utilities for smoke tests
 
-#![allow(unused_imports)]
+// Each test binary uses only some of these
+#![allow(dead_code, unused_imports)]
 
 mod assertions;
+mod fixture;
 mod server;
 pub use assertions::{
     assert_error_not_empty, assert_string_not_empty, validate_basic_response_structure,
 };
+pub use fixture::{CannedPart, MockProvider};
 pub use server::TestServer;
 use std::time::Duration;
 
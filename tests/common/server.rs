@@ -1,17 +1,34 @@
 //! Test server management for smoke tests
 
+use crate::common::fixture::{FixtureServer, MockProvider};
 use crate::common::{find_available_port, wait_for_server_ready, TestConfig};
 use eyre::{Result, WrapErr};
+use opencode_sdk::models::Session;
+use opencoders::sdk::client::{generate_id, ChatOptions, IdPrefix, OpenCodeClient};
 use std::process::Stdio;
 use tempfile::TempDir;
 use tokio::process::{Child, Command};
 
+/// Provider and model that seeded messages are sent with
+const SEED_PROVIDER: &str = "fixture";
+const SEED_MODEL: &str = "canned";
+
+/// What answers the test's requests
+enum Backend {
+    /// `opencode serve`, which can't reply to messages without a provider
+    Opencode {
+        process: Child,
+        _temp_dir: TempDir, // Keep temp dir alive for the duration of the test
+    },
+    /// The in-process stand-in, replying from a `MockProvider`
+    Fixture(FixtureServer),
+}
+
 /// Manages a test instance of the opencode server
 pub struct TestServer {
-    process: Child,
+    backend: Backend,
     base_url: String,
     port: u16,
-    _temp_dir: TempDir, // Keep temp dir alive for the duration of the test
 }
 
 impl TestServer {
@@ -76,10 +93,12 @@ impl TestServer {
             Ok(()) => {
                 println!("Test server ready at {}", base_url);
                 Ok(Self {
-                    process,
+                    backend: Backend::Opencode {
+                        process,
+                        _temp_dir: temp_dir,
+                    },
                     base_url,
                     port,
-                    _temp_dir: temp_dir,
                 })
             }
             Err(e) => {
@@ -90,6 +109,41 @@ impl TestServer {
         }
     }
 
+    /// Start the in-process fixture in place of opencode, replying to
+    /// messages from `provider`. Needs no `opencode` binary.
+    pub async fn start_with_provider(provider: MockProvider) -> Result<Self> {
+        let fixture = FixtureServer::start(provider).await?;
+        Ok(Self {
+            base_url: fixture.base_url().to_string(),
+            port: fixture.port(),
+            backend: Backend::Fixture(fixture),
+        })
+    }
+
+    /// Create a session holding `prompts` and the replies to them, sent
+    /// through the same endpoints as the app uses
+    pub async fn seed_session(&self, prompts: &[&str]) -> Result<Session> {
+        let client = OpenCodeClient::new(self.base_url());
+        let session = client
+            .create_session()
+            .await
+            .wrap_err("Failed to create the seeded session")?;
+        for prompt in prompts {
+            client
+                .send_user_message(
+                    &session.id,
+                    &generate_id(IdPrefix::Message),
+                    prompt,
+                    SEED_PROVIDER,
+                    SEED_MODEL,
+                    &ChatOptions::default(),
+                )
+                .await
+                .wrap_err_with(|| format!("Failed to seed the message {:?}", prompt))?;
+        }
+        Ok(session)
+    }
+
     /// Get the base URL of the test server
     pub fn base_url(&self) -> &str {
         &self.base_url
@@ -97,7 +151,10 @@ impl TestServer {
 
     /// Check if the server process is still running
     pub fn is_running(&mut self) -> bool {
-        match self.process.try_wait() {
+        let Backend::Opencode { process, .. } = &mut self.backend else {
+            return true;
+        };
+        match process.try_wait() {
             Ok(Some(_)) => false, // Process has exited
             Ok(None) => true,     // Process is still running
             Err(_) => false,      // Error checking status, assume not running
@@ -107,14 +164,17 @@ impl TestServer {
     /// Gracefully shutdown the server
     pub async fn shutdown(mut self) -> Result<()> {
         println!("Shutting down test server on port {}", self.port);
+        let Backend::Opencode { process, .. } = &mut self.backend else {
+            return Ok(());
+        };
 
         // Try to terminate gracefully first
-        if let Err(e) = self.process.kill().await {
+        if let Err(e) = process.kill().await {
             eprintln!("Warning: Failed to kill server process: {}", e);
         }
 
         // Wait for the process to exit
-        match self.process.wait().await {
+        match process.wait().await {
             Ok(status) => {
                 if status.success() {
                     println!("Server shut down successfully");
@@ -134,12 +194,15 @@ impl TestServer {
 impl Drop for TestServer {
     fn drop(&mut self) {
         // Ensure the process is killed when the TestServer is dropped
-        if self.is_running() {
+        if !self.is_running() {
+            return;
+        }
+        if let Backend::Opencode { process, .. } = &mut self.backend {
             println!(
                 "Force killing server process on port {} during cleanup",
                 self.port
             );
-            let _ = self.process.start_kill();
+            let _ = process.start_kill();
         }
     }
 }
@@ -166,4 +229,21 @@ mod tests {
         // Shutdown server
         server.shutdown().await.expect("Failed to shutdown server");
     }
+
+    #[tokio::test]
+    async fn test_fixture_lifecycle() {
+        let mut server = TestServer::start_with_provider(MockProvider::new())
+            .await
+            .expect("Failed to start the fixture server");
+        assert!(server.is_running());
+
+        let client = OpenCodeClient::new(server.base_url());
+        client
+            .test_connection()
+            .await
+            .expect("Fixture should answer /app");
+        assert!(client.list_sessions().await.unwrap().is_empty());
+
+        server.shutdown().await.expect("Failed to shutdown server");
+    }
 }
@@ -5,8 +5,8 @@
 
 mod common;
 
-use common::{assert_error_not_empty, TestServer};
-use opencoders::sdk::OpenCodeClient;
+use common::{assert_error_not_empty, TestConfig, TestServer};
+use opencoders::sdk::{extensions::files::FileContent, OpenCodeClient};
 
 use crate::common::assert_string_not_empty;
 
@@ -226,3 +226,29 @@ async fn smoke_test_concurrent_file_operations() {
 
     server.shutdown().await.expect("Failed to shutdown server");
 }
+
+#[tokio::test]
+async fn smoke_test_read_file_smart_and_range() {
+    let program = "fn main() {\n    println!(\"hello\");\n}\n";
+    let server = TestServer::start_with_config(TestConfig {
+        program_path: Some("main.rs".to_string()),
+        program_contents: Some(program.to_string()),
+        ..Default::default()
+    })
+    .await
+    .expect("Failed to start test server");
+
+    let client = OpenCodeClient::new(server.base_url());
+
+    let smart_result = client.read_file_smart("main.rs").await;
+    let content = assert_api_success!(smart_result, "read_file_smart");
+    assert_eq!(content, FileContent::Text(program.to_string()));
+    println!("✓ Source file read as text");
+
+    let range_result = client.read_file_range("main.rs", 1, 2).await;
+    let line = assert_api_success!(range_result, "read_file_range");
+    assert_eq!(line, "    println!(\"hello\");\n");
+    println!("✓ Line range read from source file");
+
+    server.shutdown().await.expect("Failed to shutdown server");
+}
@@ -0,0 +1,53 @@
+//! Smoke tests against the in-memory MockApi
+//!
+//! The same checks as `smoke_tests.rs`, run without a server to show the
+//! mock answers like one. Needs `--features mock`.
+
+use opencoders::sdk::{MockApi, OpenCodeClient};
+use std::time::Duration;
+
+fn mock_client(api: MockApi) -> OpenCodeClient {
+    OpenCodeClient::with_api("http://mock", api)
+}
+
+#[tokio::test]
+async fn smoke_test_app_info() {
+    let client = mock_client(MockApi::new());
+
+    let app = client.get_app_info().await.expect("get_app_info");
+
+    // Verify basic app info structure
+    assert!(!app.hostname.is_empty(), "app hostname should not be empty");
+    println!(
+        "✓ App info retrieved successfully: hostname {}",
+        app.hostname
+    );
+}
+
+#[tokio::test]
+async fn smoke_test_concurrent_requests() {
+    // Slow enough that the two requests overlap
+    let api = MockApi::new()
+        .with_latency("app.get", Duration::from_millis(20))
+        .with_latency("config.get", Duration::from_millis(20));
+    let client = mock_client(api);
+
+    // Test concurrent requests to ensure thread safety
+    let task1 = tokio::spawn({
+        let client = client.clone();
+        async move { client.get_app_info().await }
+    });
+    let task2 = tokio::spawn({
+        let client = client.clone();
+        async move { client.get_config().await }
+    });
+
+    // Wait for all tasks to complete
+    let result1 = task1.await.expect("Task should complete");
+    result1.expect("concurrent request 1");
+    println!("✓ Concurrent request 1 completed successfully");
+
+    let result2 = task2.await.expect("Task should complete");
+    result2.expect("concurrent request 2");
+    println!("✓ Concurrent request 2 completed successfully");
+}
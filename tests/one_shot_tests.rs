@@ -0,0 +1,93 @@
+//! One-shot `--prompt` runs of the binary against a real opencode server
+//!
+//! These run the built `opencoders` binary with its output piped, the way a
+//! script would, and check what lands on each stream and the exit code.
+
+mod common;
+
+use common::TestServer;
+use std::process::{Output, Stdio};
+use std::time::Duration;
+use tokio::process::{Child, Command};
+
+/// Longest a prompt may take before the test gives up on it
+const RUN_TIMEOUT: Duration = Duration::from_secs(120);
+
+fn one_shot(server: &TestServer, prompt: &str) -> Child {
+    Command::new(env!("CARGO_BIN_EXE_opencoders"))
+        .args(["--prompt", prompt])
+        .env("OPENCODE_SERVER_URL", server.base_url())
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .expect("Failed to start opencoders")
+}
+
+async fn wait(child: Child) -> Output {
+    tokio::time::timeout(RUN_TIMEOUT, child.wait_with_output())
+        .await
+        .expect("One-shot run did not finish in time")
+        .expect("Failed to wait for opencoders")
+}
+
+#[tokio::test]
+async fn test_prompt_prints_plain_reply_and_exits() {
+    let server = TestServer::start()
+        .await
+        .expect("Failed to start test server");
+
+    let output = wait(one_shot(&server, "Reply with the single word: pong")).await;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    println!("stdout: {:?}\nstderr: {:?}", stdout, stderr);
+
+    // Nothing meant for a terminal reaches the pipes
+    assert!(!stdout.contains('\x1b'), "escape codes on stdout");
+    assert!(!stderr.contains('\x1b'), "escape codes on stderr");
+
+    // Without a configured provider the session reports an error instead
+    match output.status.code() {
+        Some(0) => assert!(!stdout.trim().is_empty(), "no reply on stdout"),
+        Some(2) => assert!(!stderr.trim().is_empty(), "no error on stderr"),
+        other => panic!("unexpected exit code {:?}, stderr: {}", other, stderr),
+    }
+
+    server.shutdown().await.expect("Failed to shutdown server");
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_interrupted_prompt_exits_130() {
+    let server = TestServer::start()
+        .await
+        .expect("Failed to start test server");
+
+    let mut child = one_shot(
+        &server,
+        "Count slowly from one to five hundred, one number per line",
+    );
+    let pid = child
+        .id()
+        .expect("opencoders exited before it was interrupted");
+    tokio::time::sleep(Duration::from_secs(3)).await;
+    // Without a configured provider the run has already ended in an error
+    if let Some(status) = child.try_wait().expect("Failed to check on opencoders") {
+        println!("Finished before the interrupt with {:?}", status.code());
+        server.shutdown().await.expect("Failed to shutdown server");
+        return;
+    }
+    let killed = Command::new("kill")
+        .args(["-INT", &pid.to_string()])
+        .status()
+        .await
+        .expect("Failed to run kill");
+    assert!(killed.success());
+
+    let output = wait(child).await;
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(130), "stderr: {}", stderr);
+
+    server.shutdown().await.expect("Failed to shutdown server");
+}
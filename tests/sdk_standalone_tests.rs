@@ -0,0 +1,49 @@
+//! The SDK on its own, as built with `--no-default-features`
+//!
+//! Projects using `opencoders::sdk` as a library shouldn't have to build the
+//! terminal UI, so none of its crates may be reachable from the library once
+//! the `tui` feature is off.
+
+use std::process::Command;
+
+/// Crates only the terminal UI needs
+const TUI_CRATES: &[&str] = &[
+    "crossterm",
+    "ratatui",
+    "throbber-widgets-tui",
+    "tui-textarea",
+];
+
+#[test]
+fn test_sdk_builds_without_tui_crates() {
+    // Offline, since building this test already fetched every crate
+    let output = Command::new(env!("CARGO"))
+        .args([
+            "tree",
+            "--offline",
+            "--no-default-features",
+            "--edges",
+            "normal",
+            "--prefix",
+            "none",
+        ])
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .expect("Failed to run cargo tree");
+    assert!(
+        output.status.success(),
+        "cargo tree failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let tree = String::from_utf8_lossy(&output.stdout);
+    assert!(tree.starts_with("opencoders v"));
+    for line in tree.lines() {
+        let name = line.split(' ').next().unwrap_or_default();
+        assert!(
+            !TUI_CRATES.contains(&name),
+            "{} is built without the tui feature",
+            name
+        );
+    }
+}
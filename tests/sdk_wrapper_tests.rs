@@ -8,9 +8,13 @@ mod common;
 
 use common::TestServer;
 use eyre::{Result, WrapErr};
-use opencoders::sdk::client::{generate_descending_id, generate_id, IdPrefix, OpenCodeClient};
+use opencoders::sdk::client::{
+    generate_descending_id, generate_id, ChatOptions, IdPrefix, OpenCodeClient,
+};
+use opencoders::sdk::discovery::{choose_candidate, discover_opencode_servers, DiscoveryConfig};
 use opencoders::sdk::LogLevel;
 use std::collections::HashSet;
+use std::path::Path;
 use std::time::Duration;
 
 use crate::common::TestConfig;
@@ -60,6 +64,36 @@ async fn test_client_discovery() -> Result<()> {
     Ok(())
 }
 
+/// Test that discovery lists every running server and picks by project
+#[tokio::test]
+async fn test_discovery_lists_multiple_servers() -> Result<()> {
+    let first = TestServer::start().await?;
+    let second = TestServer::start().await?;
+
+    // Test servers use random ports, so they're found through the process list
+    let candidates = discover_opencode_servers(&DiscoveryConfig::default()).await;
+    for server in [&first, &second] {
+        assert!(
+            candidates.iter().any(|c| c.url == server.base_url()),
+            "{} should be discovered",
+            server.base_url()
+        );
+    }
+
+    // Each server runs in its own temp dir, so its project picks it
+    for server in [&first, &second] {
+        let candidate = candidates
+            .iter()
+            .find(|c| c.url == server.base_url())
+            .unwrap();
+        let project_path = Path::new(&candidate.project_path).join("src");
+        let chosen = choose_candidate(&candidates, Some(&project_path));
+        assert_eq!(chosen.map(|c| c.url.as_str()), Some(server.base_url()));
+    }
+
+    Ok(())
+}
+
 /// Test client cloning
 #[tokio::test]
 async fn test_client_cloning() -> Result<()> {
@@ -98,11 +132,8 @@ async fn test_get_app_info() -> Result<()> {
         !app_info.hostname.is_empty(),
         "App hostname should not be empty"
     );
-    // Git field should be boolean
-    assert!(
-        app_info.git || !app_info.git,
-        "Git field should be valid boolean"
-    );
+    // The server runs in the test's temporary directory
+    assert!(!app_info.path.cwd.is_empty(), "App cwd should not be empty");
     Ok(())
 }
 
@@ -345,7 +376,7 @@ async fn test_send_user_message() -> Result<()> {
             "Hello, this is a test message",
             provider_id,
             model_id,
-            None, // no mode
+            &ChatOptions::default(), // no mode
         )
         .await
     {
@@ -492,16 +523,20 @@ mod tests {
         "File search results should be valid"
     );
 
-    // TODO resolve this case
-    // // Test symbol search
-    // let symbol_results = client
-    //     .find_symbols("Message")
-    //     .await
-    //     .wrap_err("Should be able to search symbols")?;
-    // assert!(
-    //     symbol_results.len() >= 1,
-    //     "Symbol search results should be valid"
-    // );
+    // Test symbol search. Symbols come from the server's language servers,
+    // which only index the project once a file of their language is opened,
+    // so a fresh server may well find none.
+    let symbol_results = client
+        .find_symbols("Message")
+        .await
+        .wrap_err("Should be able to search symbols")?;
+    for symbol in &symbol_results {
+        assert!(
+            !symbol.name.is_empty() && !symbol.location.uri.is_empty(),
+            "Symbol search results should be valid: {:?}",
+            symbol
+        );
+    }
 
     Ok(())
 }
@@ -561,6 +596,53 @@ async fn test_write_log() -> Result<()> {
     Ok(())
 }
 
+// ============================================================================
+// Event Stream Tests
+// ============================================================================
+
+/// Test that every subscriber reads from one `/event` connection
+#[tokio::test]
+async fn test_event_subscribers_share_one_connection() -> Result<()> {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use tokio::net::{TcpListener, TcpStream};
+
+    let server = TestServer::start().await?;
+
+    // Count the connections made to the server by going through a proxy
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let proxy_url = format!("http://{}", listener.local_addr()?);
+    let server_addr = server.base_url().trim_start_matches("http://").to_string();
+    let connections = Arc::new(AtomicUsize::new(0));
+    let counter = connections.clone();
+    tokio::spawn(async move {
+        while let Ok((mut inbound, _)) = listener.accept().await {
+            counter.fetch_add(1, Ordering::SeqCst);
+            let server_addr = server_addr.clone();
+            tokio::spawn(async move {
+                if let Ok(mut outbound) = TcpStream::connect(server_addr).await {
+                    let _ = tokio::io::copy_bidirectional(&mut inbound, &mut outbound).await;
+                }
+            });
+        }
+    });
+
+    let client = OpenCodeClient::new(&proxy_url);
+    let mut first = client.subscribe_to_events().await?;
+    let mut second = client.clone().subscribe_to_events().await?;
+
+    // The server greets each connection with an event
+    for handle in [&mut first, &mut second] {
+        let event = tokio::time::timeout(Duration::from_secs(10), handle.next_event())
+            .await
+            .wrap_err("Should receive an event from the server")?;
+        assert!(matches!(event, Some(Ok(_))), "Got {:?}", event);
+    }
+    assert_eq!(connections.load(Ordering::SeqCst), 1);
+
+    Ok(())
+}
+
 // ============================================================================
 // ID Generation Tests
 // ============================================================================
@@ -0,0 +1,269 @@
+//! Send, stream and idle, against the fixture server
+//!
+//! These run without `opencode`: `TestServer::start_with_provider` stands in
+//! for it, replying to each message with canned parts over the event stream.
+//! Needs the `tui` feature for `MessageState`.
+
+mod common;
+
+use common::{CannedPart, MockProvider, TestServer};
+use eyre::{eyre, Result};
+use opencode_sdk::models::{Event, Message, Part, ToolState};
+use opencoders::app::message_state::MessageState;
+use opencoders::sdk::client::{generate_id, ChatOptions, IdPrefix, OpenCodeClient};
+use opencoders::sdk::{AssistantMessage, EventStreamHandle};
+use serde_json::json;
+use std::time::Duration;
+
+/// Longest wait for the next event before the test fails
+const EVENT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A reply with a tool call between two texts
+fn read_main() -> Vec<CannedPart> {
+    vec![
+        CannedPart::text("Reading the file first."),
+        CannedPart::tool("read", json!({"filePath": "src/main.rs"}), "fn main() {}"),
+        CannedPart::text("It only has an empty main."),
+    ]
+}
+
+/// `read_main`'s parts as `summarize` gives them
+fn read_main_summary() -> Vec<String> {
+    vec![
+        "Reading the file first.".to_string(),
+        "read: fn main() {}".to_string(),
+        "It only has an empty main.".to_string(),
+    ]
+}
+
+async fn next_event(events: &mut EventStreamHandle) -> Result<Event> {
+    match tokio::time::timeout(EVENT_TIMEOUT, events.next_event()).await {
+        Ok(Some(event)) => Ok(event?),
+        Ok(None) => Err(eyre!("the event stream ended")),
+        Err(_) => Err(eyre!("no event within {:?}", EVENT_TIMEOUT)),
+    }
+}
+
+/// Subscribe, returning once the server says the stream is connected so
+/// nothing sent afterwards is missed
+async fn subscribe(client: &OpenCodeClient) -> Result<EventStreamHandle> {
+    let mut events = client.subscribe_to_events().await?;
+    while !matches!(
+        next_event(&mut events).await?,
+        Event::ServerPeriodConnected(_)
+    ) {}
+    Ok(events)
+}
+
+/// Send `text` to the session, with the events streamed for it up to and
+/// including the session going idle
+async fn send_and_stream(
+    client: &OpenCodeClient,
+    events: &mut EventStreamHandle,
+    session_id: &str,
+    text: &str,
+) -> Result<(AssistantMessage, Vec<Event>)> {
+    let assistant = client
+        .send_user_message(
+            session_id,
+            &generate_id(IdPrefix::Message),
+            text,
+            "fixture",
+            "canned",
+            &ChatOptions::default(),
+        )
+        .await?;
+    let mut streamed = Vec::new();
+    loop {
+        let event = next_event(events).await?;
+        let idle = matches!(&event, Event::SessionPeriodIdle(idle) if idle.properties.session_id == session_id);
+        streamed.push(event);
+        if idle {
+            return Ok((assistant, streamed));
+        }
+    }
+}
+
+/// Event types in order, with the part or message kind they carry
+fn describe(events: &[Event]) -> Vec<String> {
+    events
+        .iter()
+        .map(|event| match event {
+            Event::MessagePeriodUpdated(updated) => match updated.properties.info.as_ref() {
+                Message::User(_) => "message user".to_string(),
+                Message::Assistant(assistant) if assistant.time.completed.is_some() => {
+                    "message assistant completed".to_string()
+                }
+                Message::Assistant(_) => "message assistant".to_string(),
+            },
+            Event::MessagePeriodPartPeriodUpdated(updated) => {
+                match updated.properties.part.as_ref() {
+                    Part::Text(text) => format!("part text {:?}", text.text),
+                    Part::Tool(tool) => match tool.state.as_ref() {
+                        ToolState::Running(_) => format!("part tool {} running", tool.tool),
+                        ToolState::Completed(done) => {
+                            format!("part tool {} completed {:?}", tool.tool, done.output)
+                        }
+                        _ => format!("part tool {}", tool.tool),
+                    },
+                    _ => "part other".to_string(),
+                }
+            }
+            Event::SessionPeriodIdle(_) => "idle".to_string(),
+            _ => "other".to_string(),
+        })
+        .collect()
+}
+
+/// Apply streamed events as the app does
+fn apply(state: &mut MessageState, events: Vec<Event>) {
+    for event in events {
+        match event {
+            Event::MessagePeriodUpdated(updated) => {
+                state.update_message(*updated.properties.info);
+            }
+            Event::MessagePeriodPartPeriodUpdated(updated) => {
+                state.update_message_part(*updated.properties.part);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Each message's role and parts, to compare states by
+fn summarize(state: &MessageState) -> Vec<(String, Vec<String>)> {
+    state
+        .get_all_message_containers()
+        .iter()
+        .map(|container| {
+            let role = match &container.info {
+                Message::User(_) => "user",
+                Message::Assistant(_) => "assistant",
+            };
+            let parts = container
+                .part_order
+                .iter()
+                .map(|part_id| match &container.parts[part_id] {
+                    Part::Text(text) => text.text.clone(),
+                    Part::Tool(tool) => match tool.state.as_ref() {
+                        ToolState::Completed(done) => format!("{}: {}", tool.tool, done.output),
+                        _ => format!("{}: unfinished", tool.tool),
+                    },
+                    _ => "other".to_string(),
+                })
+                .collect();
+            (role.to_string(), parts)
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn test_send_streams_the_reply_until_idle() -> Result<()> {
+    let server = TestServer::start_with_provider(MockProvider::new().reply(read_main())).await?;
+    let client = OpenCodeClient::new(server.base_url());
+    let session = client.create_session().await?;
+    let mut events = subscribe(&client).await?;
+
+    let (assistant, streamed) =
+        send_and_stream(&client, &mut events, &session.id, "What's in main.rs?").await?;
+    assert_eq!(
+        describe(&streamed),
+        vec![
+            "message user",
+            "part text \"What's in main.rs?\"",
+            "message assistant",
+            "part text \"Reading the file first.\"",
+            "part tool read running",
+            "part tool read completed \"fn main() {}\"",
+            "part text \"It only has an empty main.\"",
+            "message assistant completed",
+            "idle",
+        ]
+    );
+    // The send returns the same message the stream finished
+    assert!(streamed.iter().any(|event| matches!(
+        event,
+        Event::MessagePeriodUpdated(updated)
+            if matches!(updated.properties.info.as_ref(), Message::Assistant(streamed) if streamed.id == assistant.id)
+    )));
+    assert!(assistant.time.completed.is_some());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_streamed_reply_matches_the_stored_messages() -> Result<()> {
+    let provider = MockProvider::new()
+        .reply(vec![CannedPart::text("Hi.")])
+        .reply(read_main());
+    let server = TestServer::start_with_provider(provider).await?;
+    let client = OpenCodeClient::new(server.base_url());
+    let session = server.seed_session(&["hello"]).await?;
+    let mut events = subscribe(&client).await?;
+
+    // The app's view of the session, loaded before the reply and then
+    // following the stream
+    let mut streamed_state = MessageState::new();
+    streamed_state.set_session_id(Some(session.id.clone()));
+    streamed_state.load_messages(client.get_messages(&session.id).await?);
+    let (_, streamed) = send_and_stream(&client, &mut events, &session.id, "And main.rs?").await?;
+    apply(&mut streamed_state, streamed);
+
+    let mut loaded_state = MessageState::new();
+    loaded_state.set_session_id(Some(session.id.clone()));
+    loaded_state.load_messages(client.get_messages(&session.id).await?);
+
+    let summary = summarize(&streamed_state);
+    assert_eq!(summary, summarize(&loaded_state));
+    assert_eq!(summary.len(), 4);
+    assert_eq!(summary[1].1, vec!["Hi."]);
+    assert_eq!(summary[3].1, read_main_summary());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_load_during_the_stream_keeps_the_reply() -> Result<()> {
+    let server = TestServer::start_with_provider(MockProvider::new().reply(read_main())).await?;
+    let client = OpenCodeClient::new(server.base_url());
+    let session = client.create_session().await?;
+    let mut events = subscribe(&client).await?;
+
+    // A load whose response predates the reply lands after it streamed in
+    let mut state = MessageState::new();
+    state.set_session_id(Some(session.id.clone()));
+    state.begin_load();
+    let stale = client.get_messages(&session.id).await?;
+    let (_, streamed) =
+        send_and_stream(&client, &mut events, &session.id, "What's in main.rs?").await?;
+    apply(&mut state, streamed);
+    state.load_messages(stale);
+
+    let summary = summarize(&state);
+    assert_eq!(summary.len(), 2);
+    assert_eq!(
+        summary[0],
+        ("user".to_string(), vec!["What's in main.rs?".to_string()])
+    );
+    assert_eq!(summary[1].1, read_main_summary());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_seeded_session_holds_prompts_and_replies() -> Result<()> {
+    let server = TestServer::start_with_provider(MockProvider::new().reply(read_main())).await?;
+    let session = server
+        .seed_session(&["What's in main.rs?", "thanks"])
+        .await?;
+
+    let client = OpenCodeClient::new(server.base_url());
+    let mut state = MessageState::new();
+    state.set_session_id(Some(session.id.clone()));
+    state.load_messages(client.get_messages(&session.id).await?);
+
+    let summary = summarize(&state);
+    let roles: Vec<&str> = summary.iter().map(|(role, _)| role.as_str()).collect();
+    assert_eq!(roles, vec!["user", "assistant", "user", "assistant"]);
+    assert_eq!(summary[1].1, read_main_summary());
+    // Past the canned replies, messages are echoed
+    assert_eq!(summary[3].1, vec!["echo: thanks"]);
+    Ok(())
+}